@@ -0,0 +1,129 @@
+"""
+DOCX Format Bridge - Entry point for the Tauri formatting commands
+
+Wraps the docx_formatter package so the app gets one JSON object on stdout
+for every call, success or failure.
+
+Usage:
+    python docx_format_tauri.py input.docx output.docx --spec-json '{...}'
+    python docx_format_tauri.py input.docx output.docx --request "Kopfzeile 10pt" [--dry-run]
+    python docx_format_tauri.py --detect-only --request "Mache die Schrift größer"
+
+Requests the app could not turn into a FormatSpec itself arrive here via
+--request. The LLM runs inside the app, not in this script, so those are
+answered with an error asking for a more concrete wording.
+"""
+
+# Checked by the app (script_locator.rs); bump together with EXPECTED_SCRIPT_VERSION
+SCRIPT_VERSION = 1
+
+import sys
+import os
+import json
+import argparse
+
+# Force UTF-8 for Windows
+if sys.platform == 'win32':
+    import io
+    sys.stdout = io.TextIOWrapper(sys.stdout.buffer, encoding='utf-8')
+    sys.stderr = io.TextIOWrapper(sys.stderr.buffer, encoding='utf-8')
+
+# The package modules import each other by bare name
+sys.path.insert(0, os.path.join(os.path.dirname(os.path.abspath(__file__)), 'docx_formatter'))
+
+
+def emit(result: dict) -> int:
+    print(json.dumps(result, ensure_ascii=False))
+    return 0 if result.get('success', True) else 1
+
+
+def failure(input_file: str, output_file: str, errors: list) -> dict:
+    return {
+        'success': False,
+        'input_file': input_file,
+        'output_file': output_file,
+        'applied_changes': {},
+        'warnings': [],
+        'errors': errors,
+    }
+
+
+def detect(request: str) -> int:
+    from llm_spec_generator import is_formatting_request
+    return emit({'is_formatting_request': is_formatting_request(request)})
+
+
+def apply_spec(input_file: str, output_file: str, spec_json: str, dry_run: bool) -> int:
+    from format_spec import FormatSpec, validate_spec
+
+    try:
+        spec_dict = json.loads(spec_json)
+    except json.JSONDecodeError as e:
+        return emit(failure(input_file, output_file, [f"Invalid spec JSON: {e}"]))
+
+    errors = validate_spec(spec_dict)
+    if errors:
+        return emit(failure(input_file, output_file, errors))
+
+    try:
+        spec = FormatSpec.from_dict(spec_dict)
+    except Exception as e:
+        return emit(failure(input_file, output_file, [f"Could not parse spec: {e}"]))
+
+    if dry_run:
+        return emit({
+            'success': True,
+            'input_file': input_file,
+            'output_file': output_file,
+            'applied_changes': {'planned_spec': spec.to_dict()},
+            'warnings': [],
+            'errors': [],
+        })
+
+    try:
+        from docx_formatter import format_docx
+    except ImportError:
+        return emit(failure(input_file, output_file,
+                            ["python-docx not installed. Run: pip install python-docx"]))
+
+    return emit(format_docx(input_file, output_file, spec).to_dict())
+
+
+def main() -> int:
+    parser = argparse.ArgumentParser(description='Format a DOCX file for the Gutachten Assistant')
+    parser.add_argument('input_file', nargs='?')
+    parser.add_argument('output_file', nargs='?')
+    parser.add_argument('--request', help='Formatting request in natural language')
+    parser.add_argument('--spec-json', dest='spec_json', help='FormatSpec as a JSON string')
+    parser.add_argument('--detect-only', action='store_true',
+                        help='Only report whether --request is a formatting request')
+    parser.add_argument('--dry-run', action='store_true',
+                        help='Plan the changes without writing the output file')
+    args = parser.parse_args()
+
+    if args.detect_only:
+        if not args.request:
+            parser.error('--detect-only requires --request')
+        return detect(args.request)
+
+    if not args.input_file or not args.output_file:
+        parser.error('input and output files are required')
+
+    if not os.path.exists(args.input_file):
+        return emit(failure(args.input_file, args.output_file,
+                            [f"Input file not found: {args.input_file}"]))
+
+    if args.spec_json:
+        return apply_spec(args.input_file, args.output_file, args.spec_json, args.dry_run)
+
+    if args.request:
+        return emit(failure(args.input_file, args.output_file, [
+            "Die Formatierungsanfrage konnte nicht in konkrete Regeln übersetzt werden. "
+            "Bitte genauer formulieren, z. B. \"Schrift Arial 12pt\" oder \"Ränder 25 mm\"."
+        ]))
+
+    parser.error('either --spec-json or --request is required')
+
+
+if __name__ == '__main__':
+    sys.exit(main())
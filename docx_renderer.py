@@ -12,6 +12,9 @@ Features:
 - Inserts Kopfzeile/Fußzeile
 """
 
+# Checked by the app (script_locator.rs); bump together with EXPECTED_SCRIPT_VERSION
+SCRIPT_VERSION = 1
+
 import sys
 import os
 import json
@@ -28,7 +31,8 @@ if sys.platform == 'win32':
 try:
     from docx import Document
     from docx.shared import Pt, Inches, RGBColor
-    from docx.enum.text import WD_ALIGN_PARAGRAPH
+    from docx.enum.text import WD_ALIGN_PARAGRAPH, WD_BREAK
+    from docx.enum.section import WD_SECTION
     from docx.oxml.ns import qn, nsmap
     from docx.oxml import OxmlElement
 except ImportError:
@@ -190,6 +194,16 @@ class DocxRenderer:
         unclear_texts = {u.get("text") for u in slot_unclear}
 
         for para_text in paragraphs:
+            # Typed items: manual breaks kept from an imported report, other blocks by their text
+            if isinstance(para_text, dict):
+                item_type = para_text.get("type")
+                if item_type == "page_break":
+                    doc.add_paragraph().add_run().add_break(WD_BREAK.PAGE)
+                    continue
+                if item_type == "section_break":
+                    doc.add_section(WD_SECTION.NEW_PAGE)
+                    continue
+                para_text = para_text.get("text", "")
             if not para_text or not para_text.strip():
                 continue
 
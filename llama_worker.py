@@ -8,10 +8,13 @@ ARCHITECTURE:
 - Word/DOCX formatting is done separately in code (not here)
 
 PROTOCOL:
-  Input:  {"text": "...", "input_type": "short"|"long"} or {"command": "ping"|"shutdown"|"metrics"}
+  Input:  {"text": "...", "input_type": "short"|"long", "debug": false, "generation": {...}} or {"command": "ping"|"shutdown"|"metrics"}
   Output: {"clean_text": "...", "notes": [], "metrics": {...}}
 """
 
+# Checked by the app (script_locator.rs); bump together with EXPECTED_SCRIPT_VERSION
+SCRIPT_VERSION = 1
+
 import sys
 import json
 import os
@@ -56,6 +59,16 @@ CONFIG = {
     "stop_sequences": ["\n}", "}\n", "\n\n"],
 }
 
+# Announced in the ping response, so the app only uses what this script understands
+PROTOCOL_VERSION = 2
+CAPABILITIES = {
+    "protocol_version": PROTOCOL_VERSION,
+    "commands": ["ping", "shutdown", "metrics", "info", "text"],
+    "streaming": False,
+    "n_ctx": CONFIG["n_ctx"],
+    "features": ["debug", "generation_params"],
+}
+
 
 # =============================================================================
 # SYSTEM PROMPTS - Short vs Full
@@ -273,8 +286,24 @@ class LlamaWorker:
             return SYSTEM_PROMPT_SHORT
         return SYSTEM_PROMPT_FULL
 
-    def correct_text(self, text: str, input_type: str = "auto") -> dict:
-        """Main correction pipeline."""
+    def get_generation_params(self, text_length: int, overrides: dict = None) -> dict:
+        """Sampling parameters for a request; overrides from the app replace the defaults,
+        max_tokens only lowers the dynamic limit. Unknown keys are ignored."""
+        overrides = overrides or {}
+        params = {
+            "temperature": float(overrides.get("temperature", CONFIG["temperature"])),
+            "top_p": float(overrides.get("top_p", CONFIG["top_p"])),
+            "repeat_penalty": float(overrides.get("repeat_penalty", CONFIG["repeat_penalty"])),
+            "max_tokens": self.get_max_tokens(text_length),
+        }
+        if "max_tokens" in overrides:
+            params["max_tokens"] = min(params["max_tokens"], int(overrides["max_tokens"]))
+        return params
+
+    def correct_text(self, text: str, input_type: str = "auto", debug: bool = False,
+                     generation: dict = None) -> dict:
+        """Main correction pipeline. With debug=True the composed prompt is echoed as prompt_debug.
+        The effective sampling parameters are echoed as generation."""
         self.request_count += 1
         request_start = datetime.now()
 
@@ -292,9 +321,15 @@ class LlamaWorker:
 
         # Step 2: LLM correction
         system_prompt = self.get_system_prompt(text_length)
-        max_tokens = self.get_max_tokens(text_length)
+        params = self.get_generation_params(text_length, generation)
+        max_tokens = params["max_tokens"]
 
         user_prompt = f"Text:\n{cleaned}"
+        prompt_debug = {
+            "system": system_prompt,
+            "user": user_prompt,
+            "max_tokens": max_tokens,
+        } if debug else None
 
         try:
             infer_start = datetime.now()
@@ -305,9 +340,9 @@ class LlamaWorker:
                     {"role": "user", "content": user_prompt}
                 ],
                 max_tokens=max_tokens,
-                temperature=CONFIG["temperature"],
-                top_p=CONFIG["top_p"],
-                repeat_penalty=CONFIG["repeat_penalty"],
+                temperature=params["temperature"],
+                top_p=params["top_p"],
+                repeat_penalty=params["repeat_penalty"],
                 stop=CONFIG["stop_sequences"],
             )
 
@@ -340,23 +375,31 @@ class LlamaWorker:
             print(f"[WORKER] Request #{self.request_count}: {prompt_tokens}+{completion_tokens} tokens, "
                   f"{tokens_per_sec:.2f} tok/s, {infer_time_ms}ms", file=sys.stderr)
 
-            return {
+            result = {
                 "clean_text": clean_text,
                 "notes": notes,
                 "removed_tokens": removed_tokens,
                 "guardrail_status": guardrail_status,
-                "metrics": metrics
+                "metrics": metrics,
+                "generation": params,
             }
+            if prompt_debug:
+                result["prompt_debug"] = prompt_debug
+            return result
 
         except Exception as e:
             print(f"[WORKER] Inference error: {e}", file=sys.stderr)
-            return {
+            result = {
                 "clean_text": cleaned,
                 "notes": [f"LLM error: {str(e)}"],
                 "removed_tokens": removed_tokens,
                 "guardrail_status": "error",
-                "metrics": self._build_metrics(request_start, 0, 0, 0, "error")
+                "metrics": self._build_metrics(request_start, 0, 0, 0, "error"),
+                "generation": params,
             }
+            if prompt_debug:
+                result["prompt_debug"] = prompt_debug
+            return result
 
     def _build_metrics(self, start_time, prompt_tokens, completion_tokens,
                        tokens_per_sec, status) -> dict:
@@ -389,12 +432,23 @@ class LlamaWorker:
             "python_version": platform.python_version(),
         }
 
+    def get_model_info(self) -> dict:
+        """Return details of the loaded model."""
+        return {
+            "model_loaded": self.model_loaded,
+            "model_name": os.path.basename(self.model_path) if self.model_path else None,
+            "model_path": self.model_path,
+            "n_ctx": CONFIG["n_ctx"],
+            "device": "gpu" if CONFIG["n_gpu_layers"] > 0 else "cpu",
+            "n_gpu_layers": CONFIG["n_gpu_layers"],
+        }
+
     def handle_request(self, request: dict) -> dict:
         """Handle incoming request."""
         cmd = request.get("command")
 
         if cmd == "ping":
-            return {"status": "ready", "model_loaded": self.model_loaded}
+            return {"status": "ready", "model_loaded": self.model_loaded, "capabilities": CAPABILITIES}
 
         if cmd == "shutdown":
             return {"status": "shutting_down"}
@@ -402,9 +456,13 @@ class LlamaWorker:
         if cmd == "metrics":
             return self.get_full_metrics()
 
+        if cmd == "info":
+            return self.get_model_info()
+
         if "text" in request:
             input_type = request.get("input_type", "auto")
-            return self.correct_text(request["text"], input_type)
+            return self.correct_text(request["text"], input_type, bool(request.get("debug", False)),
+                                     request.get("generation"))
 
         return {"error": "Unknown request format"}
 
@@ -11,6 +11,9 @@ Input: Raw Whisper transcript
 Output: content.json with slots, unclear_spans, missing_slots
 """
 
+# Checked by the app (script_locator.rs); bump together with EXPECTED_SCRIPT_VERSION
+SCRIPT_VERSION = 1
+
 import sys
 import os
 import json
@@ -18,6 +21,7 @@ import re
 import urllib.request
 import urllib.error
 import subprocess
+import threading
 import time
 from datetime import datetime
 
@@ -41,6 +45,18 @@ CONFIG = {
     "server_path": os.path.join(os.path.dirname(__file__), "llama-cpp-bin", "llama-server.exe"),
     "temperature": 0.1,   # Low but not zero for slight flexibility
     "max_tokens": 2000,
+    "top_p": 0.95,        # llama-server defaults
+    "repeat_penalty": 1.1,
+}
+
+# Announced in the ping response, so the app only uses what this script understands
+PROTOCOL_VERSION = 2
+CAPABILITIES = {
+    "protocol_version": PROTOCOL_VERSION,
+    "commands": ["ping", "shutdown", "metrics", "info", "text"],
+    "streaming": False,
+    "n_ctx": CONFIG["n_ctx"],
+    "features": ["debug", "generation_params", "grammar", "section_prompts"],
 }
 
 # =============================================================================
@@ -76,6 +92,16 @@ AUSGABE NUR als gültiges JSON:
   "missing_slots": ["slot_id_3"]
 }}"""
 
+# Grammar correction only (request "task": "grammar"), for installs without the Llama model;
+# same output format as llama_worker.py
+GRAMMAR_PROMPT = """Du bist ein Korrekturleser für deutsche medizinische Diktate.
+Korrigiere nur Rechtschreibung, Grammatik und Zeichensetzung.
+Minimale Änderungen, keine neuen Inhalte, Absätze beibehalten.
+Unklare Stellen: [UNKLAR: originaltext] markieren, nicht raten.
+
+AUSGABE NUR gültiges JSON:
+{"clean_text": "korrigierter text", "notes": []}"""
+
 # Path to template spec (loaded automatically)
 TEMPLATE_SPEC_PATH = os.path.join(os.path.dirname(__file__), "template_output", "template_spec.json")
 
@@ -170,16 +196,17 @@ class QwenStructurer:
             "--port", str(CONFIG["server_port"]),
             "-c", str(CONFIG["n_ctx"]),
             "-t", str(CONFIG["n_threads"]),
-            "--log-disable",
         ]
 
         try:
             self.server_process = subprocess.Popen(
                 cmd,
-                stdout=subprocess.PIPE,
+                stdout=subprocess.DEVNULL,
                 stderr=subprocess.PIPE,
                 creationflags=subprocess.CREATE_NO_WINDOW if sys.platform == 'win32' else 0
             )
+            # Forward the server log (model load, VRAM errors) to our stderr, which the app buffers
+            threading.Thread(target=self._forward_server_log, daemon=True).start()
 
             # Wait for server ready
             for i in range(90):  # 90 second timeout
@@ -200,6 +227,14 @@ class QwenStructurer:
             print(f"[STRUCTURER] Failed to start server: {e}", file=sys.stderr)
             return False
 
+    def _forward_server_log(self):
+        """Copy llama-server's stderr line by line, prefixed, until the server exits."""
+        process = self.server_process
+        for raw in iter(process.stderr.readline, b""):
+            line = raw.decode("utf-8", errors="replace").rstrip()
+            if line:
+                print(f"[llama-server] {line}", file=sys.stderr, flush=True)
+
     def _check_health(self) -> bool:
         try:
             req = urllib.request.Request(f"{self.base_url}/health")
@@ -215,13 +250,17 @@ class QwenStructurer:
         with urllib.request.urlopen(req, timeout=300) as resp:
             return json.loads(resp.read().decode('utf-8'))
 
-    def structure_transcript(self, transcript: str, template_spec: dict = None) -> dict:
+    def structure_transcript(self, transcript: str, template_spec: dict = None, debug: bool = False,
+                             generation: dict = None, section_prompts: dict = None) -> dict:
         """
         Main function: Convert transcript to structured JSON.
 
         Args:
             transcript: Raw Whisper transcript
             template_spec: Optional template spec for slot names (uses loaded spec if not provided)
+            debug: Echo the composed prompt as prompt_debug (off by default, contains the transcript)
+            generation: Sampling overrides (temperature, top_p, repeat_penalty, max_tokens); unknown keys are ignored
+            section_prompts: User instructions per section ({section: text}), appended to the system prompt
 
         Returns:
             content.json structure
@@ -235,8 +274,12 @@ class QwenStructurer:
             return self._fallback_structure(cleaned)
 
         # Step 2: Build prompt using loaded template spec
+        system_prompt = self.system_prompt
+        if section_prompts:
+            instructions = "\n".join(f"- {section}: {text}" for section, text in section_prompts.items())
+            system_prompt += f"\n\nZUSÄTZLICHE ANWEISUNGEN JE ABSCHNITT:\n{instructions}"
         prompt = f"""<|im_start|>system
-{self.system_prompt}
+{system_prompt}
 <|im_end|>
 <|im_start|>user
 Strukturiere diesen Gutachten-Text:
@@ -246,11 +289,21 @@ Strukturiere diesen Gutachten-Text:
 <|im_start|>assistant
 """
 
+        overrides = generation or {}
+        params = {
+            "temperature": float(overrides.get("temperature", CONFIG["temperature"])),
+            "top_p": float(overrides.get("top_p", CONFIG["top_p"])),
+            "repeat_penalty": float(overrides.get("repeat_penalty", CONFIG["repeat_penalty"])),
+            "max_tokens": int(overrides.get("max_tokens", CONFIG["max_tokens"])),
+        }
+
         try:
             response = self._api_call({
                 "prompt": prompt,
-                "n_predict": CONFIG["max_tokens"],
-                "temperature": CONFIG["temperature"],
+                "n_predict": params["max_tokens"],
+                "temperature": params["temperature"],
+                "top_p": params["top_p"],
+                "repeat_penalty": params["repeat_penalty"],
                 "stop": ["<|im_end|>", "<|im_start|>"],
                 "cache_prompt": True,
             })
@@ -267,6 +320,9 @@ Strukturiere diesen Gutachten-Text:
                 "tokens_predicted": tokens,
                 "processing_time_s": elapsed,
             }
+            result["generation"] = params
+            if debug:
+                result["prompt_debug"] = {"prompt": prompt, "n_predict": params["max_tokens"]}
 
             return result
 
@@ -274,6 +330,60 @@ Strukturiere diesen Gutachten-Text:
             print(f"[STRUCTURER] API error: {e}", file=sys.stderr)
             return self._fallback_structure(cleaned)
 
+    def correct_grammar(self, text: str, debug: bool = False, generation: dict = None) -> dict:
+        """Grammar correction with the Qwen model (when the Llama model is not installed)."""
+        cleaned = preprocess_dictation(text)
+        if not self.server_ready:
+            return {"clean_text": cleaned, "notes": ["LLM not loaded - regex cleanup only"],
+                    "guardrail_status": "unknown"}
+
+        prompt = f"""<|im_start|>system
+{GRAMMAR_PROMPT}
+<|im_end|>
+<|im_start|>user
+Text:
+{cleaned}
+<|im_end|>
+<|im_start|>assistant
+"""
+        overrides = generation or {}
+        params = {
+            "temperature": float(overrides.get("temperature", 0.0)),
+            "top_p": float(overrides.get("top_p", CONFIG["top_p"])),
+            "repeat_penalty": float(overrides.get("repeat_penalty", 1.0)),
+            "max_tokens": int(overrides.get("max_tokens", CONFIG["max_tokens"])),
+        }
+        try:
+            start = datetime.now()
+            response = self._api_call({
+                "prompt": prompt,
+                "n_predict": params["max_tokens"],
+                "temperature": params["temperature"],
+                "top_p": params["top_p"],
+                "repeat_penalty": params["repeat_penalty"],
+                "stop": ["<|im_end|>", "<|im_start|>"],
+                "cache_prompt": True,
+            })
+            elapsed = (datetime.now() - start).total_seconds()
+            content = response.get("content", "").strip()
+            json_match = re.search(r'\{[\s\S]*\}', content)
+            parsed = json.loads(json_match.group()) if json_match else {}
+            tokens = response.get("tokens_predicted", 0)
+            result = {
+                "clean_text": parsed.get("clean_text", cleaned),
+                "notes": parsed.get("notes", []),
+                "guardrail_status": "passed" if "clean_text" in parsed else "unknown",
+                "tokens_per_sec": round(tokens / elapsed, 2) if elapsed > 0 else None,
+                "generation": params,
+            }
+            if debug:
+                result["prompt_debug"] = {"prompt": prompt, "n_predict": params["max_tokens"]}
+            return result
+        except Exception as e:
+            print(f"[STRUCTURER] Grammar error: {e}", file=sys.stderr)
+            return {"clean_text": cleaned, "notes": [f"LLM error: {str(e)}"],
+                    "guardrail_status": "error", "generation": params}
+
     def _get_slot_names(self, template_spec: dict = None) -> list:
         """Extract slot names from template spec or use defaults."""
         spec = template_spec or self.template_spec if hasattr(self, 'template_spec') else None
@@ -385,14 +495,27 @@ Strukturiere diesen Gutachten-Text:
         cmd = request.get("command")
 
         if cmd == "ping":
-            return {"status": "ready", "server_ready": self.server_ready}
+            return {"status": "ready", "server_ready": self.server_ready, "capabilities": CAPABILITIES}
         if cmd == "shutdown":
             self.stop_server()
             return {"status": "shutting_down"}
         if cmd == "metrics":
             return {"server_ready": self.server_ready}
+        if cmd == "info":
+            return {
+                "model_loaded": self.server_ready,
+                "model_name": os.path.basename(CONFIG["model_path"]),
+                "model_path": CONFIG["model_path"],
+                "n_ctx": CONFIG["n_ctx"],
+                "device": "cpu",  # llama-server is started without -ngl
+            }
+        if "text" in request and request.get("task") == "grammar":
+            return self.correct_grammar(request["text"], debug=bool(request.get("debug", False)),
+                                        generation=request.get("generation"))
         if "text" in request:
-            return self.structure_transcript(request["text"])
+            return self.structure_transcript(request["text"], debug=bool(request.get("debug", False)),
+                                             generation=request.get("generation"),
+                                             section_prompts=request.get("section_prompts"))
         return {"error": "Unknown request"}
 
     def run(self):
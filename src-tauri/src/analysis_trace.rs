@@ -0,0 +1,114 @@
+// Per-analysis record of the decisions the DOCX analyzer made: which pattern matched, which value
+// was chosen and what was rejected. Saved with the cached analysis so "why Arial 11 when the
+// document is Times 12" can be answered later; release builds have no console to read.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Entries kept per analysis; a document with thousands of paragraphs must not grow the cache entry
+pub const MAX_TRACE_ENTRIES: usize = 300;
+/// Entries kept per step, so a flood of one step (e.g. header candidates) can't crowd out the others
+pub const MAX_ENTRIES_PER_STEP: usize = 40;
+/// Details longer than this are cut off
+pub const MAX_DETAIL_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TraceOutcome {
+    Matched,   // A pattern found a candidate value
+    Chosen,    // The value taken for the result
+    Rejected,  // A candidate that lost, with the reason
+    Default,   // Nothing found, the fallback was used
+    Info,      // Context such as part sizes
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TraceEntry {
+    pub step: String,  // "font_family", "heading_styles", ...
+    pub outcome: TraceOutcome,
+    pub detail: String,
+}
+
+/// Decisions of one analysis in order; `dropped_entries` counts what the bounds cut off
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AnalysisTrace {
+    pub entries: Vec<TraceEntry>,
+    #[serde(default)]
+    pub dropped_entries: usize,
+    #[serde(skip)]
+    per_step: HashMap<String, usize>,
+}
+
+impl AnalysisTrace {
+    pub fn record(&mut self, step: &str, outcome: TraceOutcome, detail: impl Into<String>) {
+        let step_count = self.per_step.entry(step.to_string()).or_insert(0);
+        if self.entries.len() >= MAX_TRACE_ENTRIES || *step_count >= MAX_ENTRIES_PER_STEP {
+            self.dropped_entries += 1;
+            return;
+        }
+        *step_count += 1;
+
+        let detail: String = detail.into();
+        let detail = match detail.char_indices().nth(MAX_DETAIL_CHARS) {
+            Some((cut, _)) => format!("{}…", &detail[..cut]),
+            None => detail,
+        };
+        self.entries.push(TraceEntry { step: step.to_string(), outcome, detail });
+    }
+
+    pub fn matched(&mut self, step: &str, detail: impl Into<String>) {
+        self.record(step, TraceOutcome::Matched, detail);
+    }
+
+    pub fn chosen(&mut self, step: &str, detail: impl Into<String>) {
+        self.record(step, TraceOutcome::Chosen, detail);
+    }
+
+    pub fn rejected(&mut self, step: &str, detail: impl Into<String>) {
+        self.record(step, TraceOutcome::Rejected, detail);
+    }
+
+    pub fn defaulted(&mut self, step: &str, detail: impl Into<String>) {
+        self.record(step, TraceOutcome::Default, detail);
+    }
+
+    pub fn info(&mut self, step: &str, detail: impl Into<String>) {
+        self.record(step, TraceOutcome::Info, detail);
+    }
+
+    /// One line per entry, for the console when GUTACHTEN_TRACE is set
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self.entries.iter()
+            .map(|entry| format!("[{}] {:?}: {}", entry.step, entry.outcome, entry.detail))
+            .collect();
+        if self.dropped_entries > 0 {
+            lines.push(format!("... {} further entries dropped", self.dropped_entries));
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trace_is_bounded_per_step_and_in_total() {
+        let mut trace = AnalysisTrace::default();
+        for i in 0..100 {
+            trace.matched("headers", format!("header {}", i));
+        }
+        trace.chosen("font_family", "x".repeat(1000));
+        assert_eq!(trace.entries.len(), MAX_ENTRIES_PER_STEP + 1);
+        assert_eq!(trace.dropped_entries, 100 - MAX_ENTRIES_PER_STEP);
+        // The later step still got its entry, with the detail cut off
+        let font = trace.entries.last().unwrap();
+        assert_eq!(font.outcome, TraceOutcome::Chosen);
+        assert_eq!(font.detail.chars().count(), MAX_DETAIL_CHARS + 1);
+
+        for step in 0..400 {
+            trace.info(&format!("step{}", step), "detail");
+        }
+        assert_eq!(trace.entries.len(), MAX_TRACE_ENTRIES);
+    }
+}
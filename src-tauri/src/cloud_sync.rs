@@ -0,0 +1,162 @@
+// user-data on a synced cloud folder (OneDrive, Dropbox, ...): cloud-only placeholder files are
+// downloaded before they are opened, and writes/renames the sync client briefly holds open are
+// retried instead of failing with a sharing violation.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+/// Placeholder whose content is downloaded on first read (OneDrive "Files On-Demand")
+const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+
+/// Windows error codes of a file another process (the sync client) holds open
+const ERROR_ACCESS_DENIED: i32 = 5;
+const ERROR_SHARING_VIOLATION: i32 = 32;
+const ERROR_LOCK_VIOLATION: i32 = 33;
+
+const RETRY_ATTEMPTS: u32 = 5;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(100);
+
+/// Folder names of sync clients, lowercase; "OneDrive - Praxis" is OneDrive for Business
+const SYNC_FOLDERS: &[(&str, &str)] = &[
+    ("onedrive", "OneDrive"),
+    ("dropbox", "Dropbox"),
+    ("google drive", "Google Drive"),
+    ("googledrive", "Google Drive"),
+    ("my drive", "Google Drive"),
+    ("meine ablage", "Google Drive"),
+    ("icloud drive", "iCloud Drive"),
+    ("iclouddrive", "iCloud Drive"),
+    ("nextcloud", "Nextcloud"),
+    ("owncloud", "ownCloud"),
+    ("pcloud drive", "pCloud"),
+];
+
+fn is_placeholder_attributes(attributes: u32) -> bool {
+    attributes & (FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_OFFLINE) != 0
+}
+
+#[cfg(target_os = "windows")]
+fn file_attributes(path: &Path) -> Option<u32> {
+    use std::os::windows::fs::MetadataExt;
+    fs::metadata(path).ok().map(|metadata| metadata.file_attributes())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn file_attributes(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// The file exists only in the cloud; reading its metadata does not download it
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    file_attributes(path).is_some_and(is_placeholder_attributes)
+}
+
+/// Make sure a file is on disk before it is opened: a placeholder is downloaded by reading it
+/// once; if that fails (offline, sync client not running) the error says so
+pub fn ensure_local(path: &Path) -> Result<(), String> {
+    if !is_cloud_placeholder(path) {
+        return Ok(());
+    }
+    println!("Downloading cloud-only file {}", path.display());
+    fs::File::open(path)
+        .and_then(|mut file| io::copy(&mut file, &mut io::sink()))
+        .map(|_| ())
+        .map_err(|e| format!(
+            "Datei ist noch nicht heruntergeladen (OneDrive): {} – bitte die Internetverbindung prüfen oder die Datei in OneDrive auf \"Immer auf diesem Gerät behalten\" stellen ({})",
+            path.display(), e
+        ))
+}
+
+/// The sync client (or a virus scanner) has the file open; this passes within a second or two
+pub fn is_sharing_violation(error: &io::Error) -> bool {
+    cfg!(target_os = "windows")
+        && matches!(error.raw_os_error(), Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION | ERROR_ACCESS_DENIED))
+}
+
+/// Run `operation` until it succeeds, fails with an error `is_transient` rejects, or
+/// `attempts` are used up; the delay doubles after each try
+fn retry_with_backoff<T>(
+    attempts: u32,
+    base_delay: Duration,
+    is_transient: impl Fn(&io::Error) -> bool,
+    mut operation: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    let mut delay = base_delay;
+    let mut attempt = 1;
+    loop {
+        match operation() {
+            Err(e) if attempt < attempts && is_transient(&e) => {
+                std::thread::sleep(delay);
+                delay *= 2;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// `fs::rename`, retried while the target or source is held open by a sync client
+pub fn rename_with_retry(from: &Path, to: &Path) -> io::Result<()> {
+    retry_with_backoff(RETRY_ATTEMPTS, RETRY_BASE_DELAY, is_sharing_violation, || fs::rename(from, to))
+}
+
+/// `fs::write`, retried while the file is held open by a sync client
+pub fn write_with_retry(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    retry_with_backoff(RETRY_ATTEMPTS, RETRY_BASE_DELAY, is_sharing_violation, || fs::write(path, contents.as_ref()))
+}
+
+/// Name of the sync client whose folder contains `path`, if any: the OneDrive folders Windows
+/// reports in the environment, then well-known folder names along the path
+pub fn known_sync_folder(path: &Path) -> Option<&'static str> {
+    let in_onedrive = ["OneDrive", "OneDriveCommercial", "OneDriveConsumer"].iter()
+        .filter_map(std::env::var_os)
+        .any(|root| !root.is_empty() && path.starts_with(&root));
+    if in_onedrive {
+        return Some("OneDrive");
+    }
+
+    path.components()
+        .filter_map(|component| component.as_os_str().to_str())
+        .map(str::to_lowercase)
+        .find_map(|name| SYNC_FOLDERS.iter().find(|(folder, _)| {
+            name == *folder || (*folder == "onedrive" && name.starts_with("onedrive - "))
+        }))
+        .map(|(_, client)| *client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn test_sync_folders_placeholders_and_retries() {
+        assert_eq!(known_sync_folder(Path::new("C:/Users/anna/OneDrive - Praxis Dr. Weber/Gutachten/user-data")), Some("OneDrive"));
+        assert_eq!(known_sync_folder(Path::new("/Users/anna/Dropbox/gutachten/user-data")), Some("Dropbox"));
+        assert_eq!(known_sync_folder(Path::new("C:/Program Files/Gutachten Assistant/user-data")), None);
+        assert_eq!(known_sync_folder(Path::new("D:/OneDriveBackup/user-data")), None);
+
+        assert!(is_placeholder_attributes(FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS | 0x20));
+        assert!(!is_placeholder_attributes(0x20));  // FILE_ATTRIBUTE_ARCHIVE: an ordinary local file
+
+        // Transient errors are retried until the operation succeeds, others fail right away
+        let calls = Cell::new(0);
+        let result = retry_with_backoff(5, Duration::from_millis(1), |e| e.kind() == io::ErrorKind::WouldBlock, || {
+            calls.set(calls.get() + 1);
+            if calls.get() < 3 { Err(io::Error::from(io::ErrorKind::WouldBlock)) } else { Ok(calls.get()) }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        calls.set(0);
+        let result: io::Result<()> = retry_with_backoff(5, Duration::from_millis(1), |e| e.kind() == io::ErrorKind::WouldBlock, || {
+            calls.set(calls.get() + 1);
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls.get(), 1);
+    }
+}
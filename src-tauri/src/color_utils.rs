@@ -0,0 +1,135 @@
+// Colors of DOCX runs and styles, shared by the analyzer and the export: hex parsing, theme color
+// references (w:themeColor="accent1" resolved against word/theme/theme1.xml) and the contrast
+// check that catches headings too light to read on paper.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+
+/// Below this contrast against white (WCAG's minimum for large text) a heading is hard to read
+pub const MIN_CONTRAST_ON_WHITE: f32 = 3.0;
+
+static ATTRIBUTE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"w:(val|themeColor|themeShade|themeTint)="([^"]*)""#).unwrap());
+/// <a:accent1><a:srgbClr val="4472C4"/></a:accent1>; system colors carry the value in lastClr
+static THEME_ENTRY: Lazy<Regex> = Lazy::new(|| Regex::new(
+    r#"(?s)<a:(dk1|lt1|dk2|lt2|accent[1-6]|hlink|folHlink)>\s*<a:(?:srgbClr val|sysClr [^>]*?lastClr)="([0-9A-Fa-f]{6})""#
+).unwrap());
+
+pub type Rgb = [u8; 3];
+
+/// "#1F3864" or "1F3864"
+pub fn parse_hex_color(value: &str) -> Option<Rgb> {
+    let hex = value.trim().trim_start_matches('#');
+    if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+pub fn to_hex(rgb: Rgb) -> String {
+    format!("#{:02X}{:02X}{:02X}", rgb[0], rgb[1], rgb[2])
+}
+
+/// The color scheme of a theme part, by scheme name (dk1, lt1, accent1, ...)
+#[derive(Debug, Clone, Default)]
+pub struct ThemeColors {
+    colors: HashMap<String, Rgb>,
+}
+
+impl ThemeColors {
+    pub fn parse(theme_xml: &str) -> Self {
+        let colors = THEME_ENTRY.captures_iter(theme_xml)
+            .filter_map(|c| Some((c[1].to_string(), parse_hex_color(&c[2])?)))
+            .collect();
+        ThemeColors { colors }
+    }
+
+    /// A w:themeColor value; text/background names map onto the dark/light scheme colors
+    pub fn resolve(&self, theme_color: &str) -> Option<Rgb> {
+        let scheme_name = match theme_color {
+            "text1" | "dark1" => "dk1",
+            "background1" | "light1" => "lt1",
+            "text2" | "dark2" => "dk2",
+            "background2" | "light2" => "lt2",
+            "hyperlink" => "hlink",
+            "followedHyperlink" => "folHlink",
+            other => other,
+        };
+        self.colors.get(scheme_name).copied()
+    }
+}
+
+/// themeShade darkens, themeTint lightens (hex byte, FF = unchanged); applied per RGB channel,
+/// which is close to Word's HSL adjustment for the saturated accents templates use
+fn shade_and_tint(rgb: Rgb, shade: Option<u8>, tint: Option<u8>) -> Rgb {
+    rgb.map(|channel| {
+        let mut value = channel as f32;
+        if let Some(shade) = shade {
+            value *= shade as f32 / 255.0;
+        }
+        if let Some(tint) = tint {
+            value += (255.0 - value) * (1.0 - tint as f32 / 255.0);
+        }
+        value.round().clamp(0.0, 255.0) as u8
+    })
+}
+
+/// Color of a <w:color .../> element as "#RRGGBB"; a theme reference wins over w:val (Word keeps
+/// w:val only as a fallback). None for "auto" and unresolvable references.
+pub fn resolve_color_element(element: &str, theme: &ThemeColors) -> Option<String> {
+    let attributes: HashMap<&str, &str> = ATTRIBUTE.captures_iter(element)
+        .map(|c| (c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()))
+        .collect();
+    let byte = |name: &str| attributes.get(name).and_then(|v| u8::from_str_radix(v, 16).ok());
+
+    let themed = attributes.get("themeColor")
+        .and_then(|name| theme.resolve(name))
+        .map(|rgb| shade_and_tint(rgb, byte("themeShade"), byte("themeTint")));
+    themed.or_else(|| attributes.get("val").and_then(|v| parse_hex_color(v)))
+        .map(to_hex)
+}
+
+/// WCAG relative luminance (0 = black, 1 = white)
+pub fn relative_luminance(rgb: Rgb) -> f32 {
+    let linear = |channel: u8| {
+        let c = channel as f32 / 255.0;
+        if c <= 0.03928 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    0.2126 * linear(rgb[0]) + 0.7152 * linear(rgb[1]) + 0.0722 * linear(rgb[2])
+}
+
+/// Contrast ratio against white paper, 1 (white) to 21 (black)
+pub fn contrast_on_white(rgb: Rgb) -> f32 {
+    1.05 / (relative_luminance(rgb) + 0.05)
+}
+
+/// Warning for a color too light for white paper; it is still used as specified
+pub fn low_contrast_warning(role: &str, color: &str) -> Option<String> {
+    let contrast = contrast_on_white(parse_hex_color(color)?);
+    (contrast < MIN_CONTRAST_ON_WHITE).then(|| format!(
+        "{} hat die Farbe {} und ist auf weißem Papier kaum lesbar (Kontrast {:.1}:1, mindestens {:.0}:1 empfohlen)",
+        role, color, contrast, MIN_CONTRAST_ON_WHITE
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_theme_references_resolve_and_light_colors_are_flagged() {
+        let theme = ThemeColors::parse(r#"<a:clrScheme name="Office"><a:dk1><a:sysClr val="windowText" lastClr="000000"/></a:dk1><a:lt1><a:sysClr val="window" lastClr="FFFFFF"/></a:lt1><a:accent1><a:srgbClr val="4472C4"/></a:accent1></a:clrScheme>"#);
+
+        assert_eq!(resolve_color_element(r#"<w:color w:val="4472C4" w:themeColor="accent1"/>"#, &theme).as_deref(), Some("#4472C4"));
+        assert_eq!(resolve_color_element(r#"<w:color w:val="000000" w:themeColor="accent1" w:themeShade="BF"/>"#, &theme).as_deref(), Some("#335593"));
+        assert_eq!(resolve_color_element(r#"<w:color w:val="FFFFFF" w:themeColor="background1"/>"#, &theme).as_deref(), Some("#FFFFFF"));
+        assert_eq!(resolve_color_element(r#"<w:color w:val="1F3864"/>"#, &theme).as_deref(), Some("#1F3864"));
+        assert_eq!(resolve_color_element(r#"<w:color w:val="auto"/>"#, &theme), None);
+
+        assert!(low_contrast_warning("Überschrift 1", "#000000").is_none());
+        assert!(low_contrast_warning("Überschrift 1", "#4472C4").is_none());
+        assert!(low_contrast_warning("Überschrift 1", "#F2F2F2").unwrap().contains("kaum lesbar"));
+        assert!((contrast_on_white([0, 0, 0]) - 21.0).abs() < 0.01);
+    }
+}
@@ -0,0 +1,285 @@
+// Anchor commands - Rust-native anchor extraction (recurring section headings and their order)
+// across example DOCX files; builds a minimal TemplateSpec when the Python extractor is unavailable
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::fs;
+use std::io::BufReader;
+
+use crate::commands::document_commands::{extract_document_xml, extract_paragraphs_with_levels};
+use crate::commands::docx_commands::classify_line;
+use crate::heading_normalizer::{canonical_heading, heading_similarity};
+
+/// Same threshold as the fuzzy matching of template_extractor.py
+const ANCHOR_SIMILARITY_THRESHOLD: f32 = 0.88;
+/// Headings found in fewer documents are not anchors
+const MIN_ANCHOR_FREQUENCY: f32 = 0.5;
+/// Anchors found in fewer documents become optional slots
+const REQUIRED_ANCHOR_FREQUENCY: f32 = 0.8;
+const MAX_HEADING_CHARS: usize = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Anchor {
+    pub text: String,          // Most common spelling, without trailing colon
+    pub normalized: String,    // Canonical heading ("BEFUND")
+    pub frequency: f32,        // Share of documents containing the heading
+    pub median_position: f32,  // Typical place in the document (0 = start, 1 = end)
+    pub variants: Vec<String>,
+    pub styles: Vec<String>,   // "Heading N" for styled headings
+}
+
+/// A cluster of near-identical headings across documents
+struct HeadingCluster {
+    normalized: String,
+    variants: Vec<(String, usize)>,  // (spelling, count)
+    styles: Vec<String>,
+    documents: usize,
+    positions: Vec<f32>,
+}
+
+/// Paragraphs that may be section headings: styled headings or short lines the DOCX
+/// heading rules recognize (all caps, numbered, known section, "Label:")
+fn heading_candidates(paragraphs: &[(Option<u8>, String)]) -> Vec<(String, Option<u8>, f32)> {
+    let total = paragraphs.len().max(1) as f32;
+    paragraphs.iter().enumerate()
+        .filter(|(_, (_, text))| text.chars().count() <= MAX_HEADING_CHARS)
+        .filter(|(_, (level, text))| level.is_some() || classify_line(text).kind == "heading")
+        .map(|(i, (level, text))| (text.trim().to_string(), *level, i as f32 / total))
+        .collect()
+}
+
+fn median(values: &mut [f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f32::total_cmp);
+    let mid = values.len() / 2;
+    if values.len() % 2 == 1 {
+        values[mid]
+    } else {
+        (values[mid - 1] + values[mid]) / 2.0
+    }
+}
+
+/// Cluster the headings of all documents and keep those found in at least half of them,
+/// in their typical order. Each document counts once per cluster (at its first occurrence).
+pub fn extract_anchors_from_paragraphs(documents: &[Vec<(Option<u8>, String)>]) -> Vec<Anchor> {
+    let mut clusters: Vec<HeadingCluster> = Vec::new();
+
+    for paragraphs in documents {
+        let mut seen_in_document: Vec<usize> = Vec::new();
+        for (text, level, position) in heading_candidates(paragraphs) {
+            let normalized = canonical_heading(&text);
+            if normalized.chars().filter(|c| c.is_alphabetic()).count() < 3 {
+                continue;
+            }
+
+            let index = match clusters.iter().position(|c| heading_similarity(&c.normalized, &normalized) >= ANCHOR_SIMILARITY_THRESHOLD) {
+                Some(index) => index,
+                None => {
+                    clusters.push(HeadingCluster {
+                        normalized: normalized.clone(),
+                        variants: Vec::new(),
+                        styles: Vec::new(),
+                        documents: 0,
+                        positions: Vec::new(),
+                    });
+                    clusters.len() - 1
+                }
+            };
+            let cluster = &mut clusters[index];
+
+            let spelling = text.trim_end_matches(':').trim().to_string();
+            match cluster.variants.iter_mut().find(|(v, _)| *v == spelling) {
+                Some((_, count)) => *count += 1,
+                None => cluster.variants.push((spelling, 1)),
+            }
+            if let Some(level) = level {
+                let style = format!("Heading {}", level);
+                if !cluster.styles.contains(&style) {
+                    cluster.styles.push(style);
+                }
+            }
+            if !seen_in_document.contains(&index) {
+                seen_in_document.push(index);
+                cluster.documents += 1;
+                cluster.positions.push(position);
+            }
+        }
+    }
+
+    let total_documents = documents.len().max(1) as f32;
+    let mut anchors: Vec<Anchor> = clusters.into_iter()
+        .filter(|c| c.documents as f32 / total_documents >= MIN_ANCHOR_FREQUENCY)
+        .map(|mut cluster| {
+            // Most common spelling first; ties keep the first seen
+            cluster.variants.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            let variants: Vec<String> = cluster.variants.into_iter().map(|(v, _)| v).collect();
+            Anchor {
+                text: variants[0].clone(),
+                normalized: canonical_heading(&variants[0]),
+                frequency: cluster.documents as f32 / total_documents,
+                median_position: median(&mut cluster.positions),
+                variants,
+                styles: cluster.styles,
+            }
+        })
+        .collect();
+    anchors.sort_by(|a, b| a.median_position.total_cmp(&b.median_position));
+    anchors
+}
+
+fn read_docx_paragraphs(path: &Path) -> Result<Vec<(Option<u8>, String)>, String> {
+    let file = fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read DOCX {}: {}", path.display(), e))?;
+    Ok(extract_paragraphs_with_levels(&extract_document_xml(&mut archive)?))
+}
+
+/// Read every document; unreadable files are skipped with a message
+fn extract_anchors_from_files(paths: &[String]) -> Result<(Vec<Anchor>, usize), String> {
+    let documents: Vec<Vec<(Option<u8>, String)>> = paths.iter()
+        .filter_map(|path| match read_docx_paragraphs(Path::new(path)) {
+            Ok(paragraphs) => Some(paragraphs),
+            Err(e) => {
+                println!("[RUST] Skipping example document: {}", e);
+                None
+            }
+        })
+        .collect();
+    if documents.is_empty() {
+        return Err("Keine lesbaren DOCX-Beispieldokumente gefunden".to_string());
+    }
+    Ok((extract_anchors_from_paragraphs(&documents), documents.len()))
+}
+
+/// "2. Befund" -> "2._befund" like template_extractor.py; duplicates get a suffix
+fn anchor_ids(anchors: &[Anchor]) -> Vec<String> {
+    let mut ids: Vec<String> = Vec::new();
+    for anchor in anchors {
+        let base = anchor.text.to_lowercase().replace(' ', "_");
+        let mut id = base.clone();
+        let mut suffix = 2;
+        while ids.contains(&id) {
+            id = format!("{}_{}", base, suffix);
+            suffix += 1;
+        }
+        ids.push(id);
+    }
+    ids
+}
+
+/// Minimal TemplateSpec in the extractor's layout: one fixed heading and one slot per anchor
+pub fn build_minimal_template_spec(anchors: &[Anchor], documents_analyzed: usize) -> Value {
+    let ids = anchor_ids(anchors);
+    let spec_anchors: Vec<Value> = anchors.iter().zip(&ids)
+        .map(|(anchor, id)| serde_json::json!({
+            "id": id,
+            "canonical_text": anchor.text,
+            "match_mode": "fuzzy",
+            "min_similarity": ANCHOR_SIMILARITY_THRESHOLD,
+            "variants_seen": anchor.variants.iter().take(5).collect::<Vec<_>>(),
+            "occurrence_rate": anchor.frequency,
+            "styles": anchor.styles,
+        }))
+        .collect();
+
+    let skeleton: Vec<Value> = anchors.iter().zip(&ids)
+        .flat_map(|(anchor, id)| [
+            serde_json::json!({
+                "type": "fixed",
+                "id": format!("{}_heading", id),
+                "paragraphs": [{
+                    "text": anchor.text,
+                    "style": anchor.styles.first().map(String::as_str).unwrap_or("Heading 1"),
+                }],
+            }),
+            serde_json::json!({
+                "type": "slot",
+                "slot_id": format!("{}_body", id),
+                "section_name": anchor.text,
+                "allowed_styles": ["BODY", "BULLET"],
+                "list_behavior": "bullets_allowed",
+                "optional": anchor.frequency < REQUIRED_ANCHOR_FREQUENCY,
+            }),
+        ])
+        .collect();
+
+    serde_json::json!({
+        "version": "1.0",
+        "created_at": chrono::Utc::now().to_rfc3339(),
+        "family_id": "default",
+        "family_name": "Standard",
+        "anchors": spec_anchors,
+        "skeleton": skeleton,
+        "style_roles": {"H1": "Heading 1", "H2": "Heading 2", "BODY": "Normal", "BULLET": "List Bullet"},
+        "quality_metrics": {
+            "documents_analyzed": documents_analyzed,
+            "anchors_detected": anchors.len(),
+            "engine": "rust",
+        },
+    })
+}
+
+/// Build a minimal spec from the DOCX files of a folder (fallback of extract_template)
+pub(crate) fn build_template_spec_from_folder(input_folder: &str) -> Result<Value, String> {
+    let mut paths: Vec<String> = fs::read_dir(input_folder)
+        .map_err(|e| format!("Failed to read input folder: {}", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("docx")))
+        .filter(|path| !path.file_name().is_some_and(|n| n.to_string_lossy().starts_with("~$")))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    paths.sort();
+
+    let (anchors, documents_analyzed) = extract_anchors_from_files(&paths)?;
+    Ok(build_minimal_template_spec(&anchors, documents_analyzed))
+}
+
+/// Find recurring section headings across example documents without the Python extractor
+#[command]
+pub async fn extract_anchors_rust(paths: Vec<String>) -> Result<Vec<Anchor>, String> {
+    let (anchors, documents) = tokio::task::spawn_blocking(move || extract_anchors_from_files(&paths))
+        .await
+        .map_err(|e| format!("Anchor extraction task failed: {}", e))??;
+    println!("[RUST] Found {} anchors in {} documents", anchors.len(), documents);
+    Ok(anchors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn doc(paragraphs: &[(Option<u8>, &str)]) -> Vec<(Option<u8>, String)> {
+        paragraphs.iter().map(|(level, text)| (*level, text.to_string())).collect()
+    }
+
+    #[test]
+    fn test_anchors_clustered_with_frequency_and_order() {
+        let documents = vec![
+            doc(&[(Some(1), "Gutachterliche Fragestellung"), (None, "Text."), (None, "1. Anamnese"), (None, "Text."), (None, "BEFUND:"), (None, "Text.")]),
+            doc(&[(Some(1), "Gutachterliche Fragestellung"), (None, "Text."), (None, "1. Anamnese:"), (None, "Text."), (None, "Befund"), (None, "Text.")]),
+            doc(&[(Some(1), "Gutachterliche Fragestelung"), (None, "Anamnese"), (None, "Text."), (None, "DIAGNOSEN"), (None, "Text."), (None, "Befund")]),
+            doc(&[(None, "Text ohne Überschriften.")]),
+        ];
+
+        let anchors = extract_anchors_from_paragraphs(&documents);
+        let texts: Vec<&str> = anchors.iter().map(|a| a.text.as_str()).collect();
+        assert_eq!(texts, vec!["Gutachterliche Fragestellung", "1. Anamnese", "Befund"]);
+        assert_eq!(anchors[0].frequency, 0.75);
+        assert_eq!(anchors[0].variants.len(), 2);
+        assert_eq!(anchors[0].styles, vec!["Heading 1"]);
+        assert_eq!(anchors[1].normalized, "ANAMNESE");
+        assert!(anchors[1].median_position < anchors[2].median_position);
+
+        let spec = build_minimal_template_spec(&anchors, documents.len());
+        assert_eq!(spec["quality_metrics"]["engine"], "rust");
+        assert_eq!(spec["anchors"][1]["id"], "1._anamnese");
+        assert_eq!(spec["skeleton"].as_array().unwrap().len(), 6);
+        assert_eq!(spec["skeleton"][3]["optional"], true);
+        let parsed: crate::commands::template_commands::TemplateSpec = serde_json::from_value(spec).unwrap();
+        assert!(parsed.validate().is_ok());
+    }
+}
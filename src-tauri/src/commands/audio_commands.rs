@@ -6,21 +6,86 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::fs;
 
-#[derive(Debug, Serialize, Deserialize)]
+use crate::formats::{self, AUDIO_FORMATS};
+use crate::text_segmentation::is_abbreviation;
+use crate::script_locator::{locate_script, EXPECTED_SCRIPT_VERSION, WHISPER_SCRIPT};
+use crate::commands::session_commands::lock_session_audio;
+use crate::commands::temp_file_commands::processing_temp_dir;
+use crate::error_classifier::explain_error;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TranscriptionResult {
     pub text: String,
     pub confidence: f32,
     pub processing_time_ms: u32,
     pub language: String,
     pub segments: Vec<TranscriptionSegment>,
+    #[serde(default)]
+    pub filtered_segments: Vec<FilteredSegment>,  // Segments flagged by the hallucination filter
+    #[serde(default)]
+    pub partial: bool,  // Whisper crashed; only the segments decoded before the crash
+    #[serde(default)]
+    pub detected_language: Option<String>,  // Whisper's most likely language; `language` is the requested one
+    #[serde(default)]
+    pub language_probabilities: Vec<LanguageProbability>,  // Most likely first
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LanguageProbability {
+    pub language: String,  // ISO 639-1 code
+    pub probability: f32,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Payload of the transcription_language_mismatch event
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LanguageMismatch {
+    pub expected_language: String,
+    pub detected_language: String,
+    pub probability: f32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct TranscriptionSegment {
     pub start_time: f32,
     pub end_time: f32,
     pub text: String,
     pub confidence: f32,
+    #[serde(default)]
+    pub no_speech_prob: Option<f32>,
+    #[serde(default)]
+    pub compression_ratio: Option<f32>,
+}
+
+/// A segment flagged as a likely Whisper hallucination
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FilteredSegment {
+    pub segment: TranscriptionSegment,
+    pub reasons: Vec<String>,
+    pub removed: bool,
+}
+
+/// Thresholds for the hallucination post-filter
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HallucinationFilterOptions {
+    pub max_repetitions: usize,
+    pub similarity_threshold: f32,
+    pub no_speech_threshold: f32,
+    pub max_compression_ratio: f32,
+    pub drop_flagged: bool,
+}
+
+impl Default for HallucinationFilterOptions {
+    fn default() -> Self {
+        Self {
+            max_repetitions: 2,            // "Vielen Dank." more than twice is suspicious
+            similarity_threshold: 0.9,
+            no_speech_threshold: 0.6,      // Same cut-off Whisper uses internally
+            max_compression_ratio: 2.4,    // Whisper's default compression_ratio_threshold
+            drop_flagged: true,
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,6 +99,8 @@ pub struct AudioProcessingProgress {
 #[command]
 pub async fn process_audio_file(
     file_path: String,
+    filter_hallucinations: Option<bool>,
+    session_id: Option<String>,
     window: Window,
 ) -> Result<TranscriptionResult, String> {
     // Validate input
@@ -52,14 +119,20 @@ pub async fn process_audio_file(
         .unwrap_or("")
         .to_lowercase();
     
-    let supported_formats = ["wav", "mp3", "m4a", "flac", "ogg", "webm"];
-    if !supported_formats.contains(&extension.as_str()) {
+    if !formats::is_audio_format(&extension) {
         return Err(format!(
             "Unsupported audio format: {}. Supported formats: {:?}",
-            extension, supported_formats
+            extension, AUDIO_FORMATS
         ));
     }
     
+    // Work on the session's copy so the original can be moved or deleted meanwhile
+    let path = match session_id {
+        Some(session_id) => tokio::task::spawn_blocking(move || lock_session_audio(&session_id, &path))
+            .await.map_err(|e| format!("Session audio task failed: {}", e))??,
+        None => path,
+    };
+
     // Emit processing started
     window.emit("audio_processing_progress", AudioProcessingProgress {
         progress: 0.0,
@@ -115,13 +188,26 @@ pub async fn process_audio_file(
     }).map_err(|e| format!("Failed to emit event: {}", e))?;
 
     // Return real transcription result
-    Ok(TranscriptionResult {
+    let mut transcription = TranscriptionResult {
         text: result.text,
         confidence: result.confidence,
         processing_time_ms: processing_time,
-        language: "de".to_string(),
+        language: result.language,
         segments: result.segments,
-    })
+        filtered_segments: Vec::new(),
+        partial: result.partial,
+        detected_language: result.detected_language,
+        language_probabilities: result.language_probabilities,
+        warnings: Vec::new(),
+    };
+
+    // Hallucination filter is opt-in for interactive transcription
+    if filter_hallucinations.unwrap_or(false) {
+        filter_hallucinated_segments(&mut transcription, &HallucinationFilterOptions::default());
+    }
+
+    warn_on_language_mismatch(&mut transcription, &load_language_settings(), &window);
+    Ok(transcription)
 }
 
 /// Save audio blob data to file for processing (Enhanced for new architecture)
@@ -130,8 +216,8 @@ pub async fn save_audio_file(
     audio_data: Vec<u8>,
     filename: Option<String>,
 ) -> Result<String, String> {
-    // Use system temp directory for audio storage
-    let temp_dir = std::env::temp_dir();
+    // Configured temp directory, or the system temp
+    let temp_dir = processing_temp_dir();
 
     // Generate unique filename with timestamp
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
@@ -152,6 +238,13 @@ pub async fn save_audio_file(
         .ok_or_else(|| "Failed to convert path to string".to_string())
 }
 
+/// Output path of a conversion: "<name>_<timestamp>.wav" in the temp directory
+fn converted_wav_path(temp_dir: &std::path::Path, output_filename: Option<String>) -> PathBuf {
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
+    let base_name = output_filename.unwrap_or_else(|| "converted".to_string());
+    temp_dir.join(format!("{}_{}.wav", base_name, timestamp))
+}
+
 /// Convert audio file to WAV format using FFmpeg (New architecture)
 #[command]
 pub async fn convert_audio_to_wav(
@@ -164,12 +257,7 @@ pub async fn convert_audio_to_wav(
         return Err(format!("Input file does not exist: {}", input_path));
     }
 
-    // Generate output filename
-    let temp_dir = std::env::temp_dir();
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S").to_string();
-    let base_name = output_filename.unwrap_or_else(|| "converted".to_string());
-    let wav_filename = format!("{}_{}.wav", base_name, timestamp);
-    let output_path = temp_dir.join(&wav_filename);
+    let output_path = converted_wav_path(&processing_temp_dir(), output_filename);
 
     // Clone paths for the closure
     let output_path_clone = output_path.clone();
@@ -189,11 +277,64 @@ pub async fn convert_audio_to_wav(
         .ok_or_else(|| "Failed to convert path to string".to_string())
 }
 
+/// Options for splitting a recording into takes at long pauses
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SilenceSplitOptions {
+    pub noise_threshold_db: f32,  // Everything quieter counts as silence
+    pub min_silence_s: f32,       // Only pauses at least this long separate takes
+}
+
+impl Default for SilenceSplitOptions {
+    fn default() -> Self {
+        SilenceSplitOptions {
+            noise_threshold_db: -35.0,
+            min_silence_s: 2.0,
+        }
+    }
+}
+
+/// Split a recording with several dictations into numbered WAV takes (take_001.wav, ...)
+/// cut in the middle of each long pause. Without pauses the whole file becomes one take.
+#[command]
+pub async fn split_audio_by_silence(
+    input_path: String,
+    output_dir: String,
+    options: Option<SilenceSplitOptions>,
+) -> Result<Vec<String>, String> {
+    let input_path_buf = PathBuf::from(&input_path);
+    if !input_path_buf.exists() {
+        return Err(format!("Input file does not exist: {}", input_path));
+    }
+
+    let output_dir_buf = PathBuf::from(&output_dir);
+    fs::create_dir_all(&output_dir_buf)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let options = options.unwrap_or_default();
+    if options.min_silence_s <= 0.0 {
+        return Err(format!("Invalid minimum silence duration: {}", options.min_silence_s));
+    }
+
+    let takes = tokio::task::spawn_blocking(move || {
+        split_audio_by_silence_with_ffmpeg(&input_path_buf, &output_dir_buf, &options)
+    }).await.map_err(|e| format!("Split task failed: {}", e))??;
+
+    println!("Audio split into {} take(s) in {}", takes.len(), output_dir);
+    Ok(takes)
+}
+
 /// Transcribe audio file using simplified pipeline (New architecture)
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn transcribe_audio_simple(
     audio_path: String,
     convert_to_wav: Option<bool>,
+    filter_hallucinations: Option<bool>,
+    dedupe_repeats: Option<bool>,
+    strip_edge_artifacts: Option<bool>,
+    session_id: Option<String>,
+    language: Option<String>,  // Re-run in the detected language after a mismatch; default the expected one
+    window: Window,
 ) -> Result<TranscriptionResult, String> {
     let input_path = PathBuf::from(&audio_path);
 
@@ -201,12 +342,19 @@ pub async fn transcribe_audio_simple(
         return Err(format!("Audio file does not exist: {}", audio_path));
     }
 
+    // Work on the session's copy so the original can be moved or deleted meanwhile
+    let input_path = match session_id {
+        Some(session_id) => tokio::task::spawn_blocking(move || lock_session_audio(&session_id, &input_path))
+            .await.map_err(|e| format!("Session audio task failed: {}", e))??,
+        None => input_path,
+    };
+
     // Step 1: Convert to WAV if requested
     let wav_path = if convert_to_wav.unwrap_or(true) {
         println!("Converting audio to WAV format...");
         let input_path_clone = input_path.clone();
         let wav_result = tokio::task::spawn_blocking(move || {
-            let temp_dir = std::env::temp_dir();
+            let temp_dir = processing_temp_dir();
             let wav_filename = format!("whisper_input_{}.wav", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
             let wav_path = temp_dir.join(&wav_filename);
 
@@ -223,10 +371,21 @@ pub async fn transcribe_audio_simple(
     println!("Starting Whisper transcription...");
     let transcription_start = std::time::Instant::now();
 
-    // Clone wav_path for the transcription closure
+    // Clone wav_path for the transcription closure; long recordings are transcribed in chunks
     let wav_path_clone = wav_path.clone();
+    let chunking = load_chunking_settings();
+    let language_settings = load_language_settings();
+    let language = language.unwrap_or_else(|| language_settings.expected_language.clone());
+    let progress_window = window.clone();
     let result = tokio::task::spawn_blocking(move || {
-        perform_whisper_transcription(&wav_path_clone)
+        let duration = if chunking.enabled { probe_audio_duration(&wav_path_clone).ok() } else { None };
+        match duration {
+            Some(duration) if duration > chunking.min_duration_minutes * 60.0 => {
+                println!("Recording is {:.0} min long, transcribing in chunks", duration / 60.0);
+                transcribe_in_chunks(&wav_path_clone, duration, &chunking, &language, &progress_window)
+            }
+            _ => perform_whisper_transcription_in(&wav_path_clone, DEFAULT_WHISPER_MODEL, &language),
+        }
     }).await.map_err(|e| format!("Transcription task failed: {}", e))??;
 
     let processing_time = transcription_start.elapsed().as_millis() as u32;
@@ -238,15 +397,958 @@ pub async fn transcribe_audio_simple(
         }
     }
 
-    Ok(TranscriptionResult {
+    let mut transcription = TranscriptionResult {
         text: result.text,
         confidence: result.confidence,
         processing_time_ms: processing_time,
-        language: "de".to_string(),
+        language: result.language,
         segments: result.segments,
+        filtered_segments: Vec::new(),
+        partial: result.partial,
+        detected_language: result.detected_language,
+        language_probabilities: result.language_probabilities,
+        warnings: Vec::new(),
+    };
+
+    // Merge stutters first so they don't count as hallucinated repetitions
+    if dedupe_repeats.unwrap_or(false) {
+        transcription = dedupe_consecutive_segments(transcription, DEFAULT_DEDUPE_SIMILARITY);
+    }
+
+    // Hallucination filter is opt-in for interactive transcription
+    if filter_hallucinations.unwrap_or(false) {
+        filter_hallucinated_segments(&mut transcription, &HallucinationFilterOptions::default());
+    }
+
+    if strip_edge_artifacts.unwrap_or(false) {
+        strip_transcription_edges(&mut transcription, &load_artifact_settings().phrases);
+    }
+
+    warn_on_language_mismatch(&mut transcription, &language_settings, &window);
+    Ok(transcription)
+}
+
+/// Transcribe only [start_sec, end_sec] of a recording, e.g. to redo a garbled passage;
+/// segment timestamps refer to the whole recording
+#[command]
+pub async fn transcribe_range(audio_path: String, start_sec: f32, end_sec: f32) -> Result<TranscriptionResult, String> {
+    let input_path = PathBuf::from(&audio_path);
+    if !input_path.exists() {
+        return Err(format!("Audio file does not exist: {}", audio_path));
+    }
+
+    let probe_path = input_path.clone();
+    let duration = tokio::task::spawn_blocking(move || probe_audio_duration(&probe_path))
+        .await.map_err(|e| format!("Duration probe failed: {}", e))??;
+    check_time_range(start_sec, end_sec, duration)?;
+
+    let clip_path = processing_temp_dir().join(format!("range_{}.wav", uuid::Uuid::new_v4()));
+    let clip_path_clone = clip_path.clone();
+    let transcription_start = std::time::Instant::now();
+    let result = tokio::task::spawn_blocking(move || {
+        cut_audio_range_with_ffmpeg(&input_path, &clip_path_clone, start_sec, end_sec)?;
+        perform_whisper_transcription(&clip_path_clone)
+    }).await.map_err(|e| format!("Transcription task failed: {}", e))?;
+    let _ = fs::remove_file(&clip_path);
+    let result = result?;
+
+    println!("Transcribed range {:.2}s-{:.2}s of {}", start_sec, end_sec, audio_path);
+    Ok(TranscriptionResult {
+        text: result.text,
+        confidence: result.confidence,
+        processing_time_ms: transcription_start.elapsed().as_millis() as u32,
+        language: result.language,
+        segments: offset_segments(result.segments, start_sec),
+        filtered_segments: Vec::new(),
+        partial: result.partial,
+        detected_language: result.detected_language,
+        language_probabilities: result.language_probabilities,
+        warnings: Vec::new(),
+    })
+}
+
+/// The range must lie within the recording (small tolerance for rounding of the duration)
+fn check_time_range(start_sec: f32, end_sec: f32, duration: f32) -> Result<(), String> {
+    const TOLERANCE_S: f32 = 0.05;
+    if start_sec < 0.0 || end_sec <= start_sec {
+        return Err(format!("Ungültiger Zeitbereich: {:.2}s - {:.2}s", start_sec, end_sec));
+    }
+    if end_sec > duration + TOLERANCE_S {
+        return Err(format!("Zeitbereich endet nach dem Ende der Aufnahme ({:.2}s > {:.2}s)", end_sec, duration));
+    }
+    Ok(())
+}
+
+/// Shift clip-relative segment times onto the timeline of the whole recording
+fn offset_segments(segments: Vec<TranscriptionSegment>, offset_s: f32) -> Vec<TranscriptionSegment> {
+    segments.into_iter()
+        .map(|mut segment| {
+            segment.start_time += offset_s;
+            segment.end_time += offset_s;
+            segment
+        })
+        .collect()
+}
+
+/// Whisper model sizes the transcription script accepts
+pub const WHISPER_MODEL_SIZES: [&str; 5] = ["tiny", "base", "small", "medium", "large"];
+pub const DEFAULT_WHISPER_MODEL: &str = "base";
+
+/// Only one Whisper process at a time: each one loads its own model (up to ~10 GB for large)
+static WHISPER_RUN: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+/// Transcription of one recording with one model size
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ModelComparisonEntry {
+    pub model: String,
+    pub result: TranscriptionResult,
+    pub processing_time_ms: u32,
+    pub cached: bool,  // Taken from an earlier run on the same file contents
+}
+
+fn get_transcription_cache_dir() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("cache").join("transcription"))
+}
+
+/// Transcribe with each model, reusing cached results for the same audio hash, model, language
+/// and script version (a result for another expected language is not the same transcript)
+fn compare_models_with(
+    audio_hash: &str,
+    models: &[String],
+    language: &str,
+    cache_dir: &std::path::Path,
+    transcribe: &mut dyn FnMut(&str) -> Result<WhisperTranscriptionResult, String>,
+) -> Result<Vec<ModelComparisonEntry>, String> {
+    let mut entries = Vec::with_capacity(models.len());
+    for model in models {
+        let cache_path = cache_dir.join(format!("{}_{}_{}_v{}.json", audio_hash, model, language, EXPECTED_SCRIPT_VERSION));
+        let cached = fs::read_to_string(&cache_path).ok()
+            .and_then(|content| serde_json::from_str::<TranscriptionResult>(&content).ok());
+        if let Some(result) = cached {
+            println!("📦 Using cached {} transcription ({})", model, &audio_hash[..audio_hash.len().min(12)]);
+            entries.push(ModelComparisonEntry { model: model.clone(), processing_time_ms: result.processing_time_ms, result, cached: true });
+            continue;
+        }
+
+        let start = std::time::Instant::now();
+        let whisper = transcribe(model)?;
+        let result = TranscriptionResult {
+            text: whisper.text,
+            confidence: whisper.confidence,
+            processing_time_ms: start.elapsed().as_millis() as u32,
+            language: whisper.language,
+            segments: whisper.segments,
+            filtered_segments: Vec::new(),
+            partial: whisper.partial,
+            detected_language: whisper.detected_language,
+            language_probabilities: whisper.language_probabilities,
+            warnings: Vec::new(),
+        };
+        // Cache failures never fail the comparison
+        let written = fs::create_dir_all(cache_dir).map_err(|e| e.to_string())
+            .and_then(|_| serde_json::to_string_pretty(&result).map_err(|e| e.to_string()))
+            .and_then(|json| fs::write(&cache_path, json).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            println!("Warning: Failed to cache {} transcription: {}", model, e);
+        }
+        entries.push(ModelComparisonEntry { model: model.clone(), processing_time_ms: result.processing_time_ms, result, cached: false });
+    }
+    Ok(entries)
+}
+
+/// Transcribe the same recording with several Whisper model sizes (e.g. medium vs large)
+/// to compare their output; models run one after another
+#[command]
+pub async fn compare_whisper_models(audio_path: String, models: Vec<String>) -> Result<Vec<ModelComparisonEntry>, String> {
+    let input_path = PathBuf::from(&audio_path);
+    if !input_path.exists() {
+        return Err(format!("Audio file does not exist: {}", audio_path));
+    }
+    if models.is_empty() {
+        return Err("Keine Modelle zum Vergleichen angegeben".to_string());
+    }
+    if let Some(unknown) = models.iter().find(|m| !WHISPER_MODEL_SIZES.contains(&m.as_str())) {
+        return Err(format!("Unbekanntes Whisper-Modell: {} (verfügbar: {})", unknown, WHISPER_MODEL_SIZES.join(", ")));
+    }
+
+    let cache_dir = get_transcription_cache_dir()?;
+    let language = load_language_settings().expected_language;
+    let entries = tokio::task::spawn_blocking(move || {
+        let audio_hash = crate::commands::format_commands::sha256_file(&input_path)?;
+        compare_models_with(&audio_hash, &models, &language, &cache_dir, &mut |model| {
+            println!("Transcribing {} with Whisper {}...", input_path.display(), model);
+            perform_whisper_transcription_in(&input_path, model, &language)
+        })
+    }).await.map_err(|e| format!("Comparison task failed: {}", e))??;
+
+    println!("Compared {} Whisper models on {}", entries.len(), audio_path);
+    Ok(entries)
+}
+
+/// Chunked transcription of long recordings (one Whisper run on a 40+ minute file can exhaust
+/// RAM); stored in user-data/settings/chunking.json
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ChunkingSettings {
+    #[serde(default = "default_chunking_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_min_duration_minutes")]
+    pub min_duration_minutes: f32,  // Shorter recordings are transcribed in one piece
+    #[serde(default = "default_chunk_minutes")]
+    pub chunk_minutes: f32,
+    #[serde(default = "default_overlap_seconds")]
+    pub overlap_seconds: f32,       // Audio shared by neighbouring chunks, for stitching
+}
+
+impl Default for ChunkingSettings {
+    fn default() -> Self {
+        ChunkingSettings {
+            enabled: default_chunking_enabled(),
+            min_duration_minutes: default_min_duration_minutes(),
+            chunk_minutes: default_chunk_minutes(),
+            overlap_seconds: default_overlap_seconds(),
+        }
+    }
+}
+
+impl ChunkingSettings {
+    fn validate(&self) -> Result<(), String> {
+        if self.chunk_minutes.is_nan() || self.chunk_minutes < 1.0 {
+            return Err("Teillänge muss mindestens 1 Minute betragen".to_string());
+        }
+        if !(0.0..=30.0).contains(&self.overlap_seconds) {
+            return Err("Überlappung muss zwischen 0 und 30 Sekunden liegen".to_string());
+        }
+        Ok(())
+    }
+}
+
+fn default_chunking_enabled() -> bool {
+    true
+}
+
+fn default_min_duration_minutes() -> f32 {
+    40.0
+}
+
+fn default_chunk_minutes() -> f32 {
+    10.0
+}
+
+fn default_overlap_seconds() -> f32 {
+    5.0
+}
+
+fn get_chunking_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("chunking.json"))
+}
+
+fn load_chunking_settings() -> ChunkingSettings {
+    get_chunking_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<ChunkingSettings>(&content).ok())
+        // A hand-edited file with e.g. chunk_minutes: 0 must not reach chunk_windows
+        .filter(|settings| settings.validate().is_ok())
+        .unwrap_or_default()
+}
+
+/// Get the chunked transcription settings
+#[command]
+pub async fn get_chunking_settings() -> Result<ChunkingSettings, String> {
+    Ok(load_chunking_settings())
+}
+
+/// Save the chunked transcription settings
+#[command]
+pub async fn save_chunking_settings(settings: ChunkingSettings) -> Result<(), String> {
+    settings.validate()?;
+
+    let path = get_chunking_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize chunking settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write chunking settings: {}", e))
+}
+
+/// Language Whisper transcribes in, and how sure its detection must be of another language before
+/// the user is warned; stored in user-data/settings/transcription_language.json
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct TranscriptionLanguageSettings {
+    #[serde(default = "default_expected_language")]
+    pub expected_language: String,
+    #[serde(default = "default_mismatch_probability")]
+    pub mismatch_probability: f32,
+}
+
+impl Default for TranscriptionLanguageSettings {
+    fn default() -> Self {
+        TranscriptionLanguageSettings {
+            expected_language: default_expected_language(),
+            mismatch_probability: default_mismatch_probability(),
+        }
+    }
+}
+
+fn default_expected_language() -> String {
+    "de".to_string()
+}
+
+fn default_mismatch_probability() -> f32 {
+    0.8
+}
+
+fn get_language_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("transcription_language.json"))
+}
+
+fn load_language_settings() -> TranscriptionLanguageSettings {
+    get_language_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Get the transcription language settings
+#[command]
+pub async fn get_transcription_language_settings() -> Result<TranscriptionLanguageSettings, String> {
+    Ok(load_language_settings())
+}
+
+/// Save the transcription language settings
+#[command]
+pub async fn save_transcription_language_settings(settings: TranscriptionLanguageSettings) -> Result<(), String> {
+    if settings.expected_language.len() != 2 || !settings.expected_language.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!("Ungültiger Sprachcode: {} (erwartet z. B. \"de\")", settings.expected_language));
+    }
+    if !(0.5..=1.0).contains(&settings.mismatch_probability) {
+        return Err("Die Schwelle für die Sprachwarnung muss zwischen 0,5 und 1 liegen".to_string());
+    }
+
+    let path = get_language_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize language settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write language settings: {}", e))
+}
+
+/// Another language than the expected one, detected with at least the configured probability
+pub fn language_mismatch(result: &TranscriptionResult, settings: &TranscriptionLanguageSettings) -> Option<LanguageMismatch> {
+    let top = result.language_probabilities.first()?;
+    (top.language != settings.expected_language && top.probability >= settings.mismatch_probability)
+        .then(|| LanguageMismatch {
+            expected_language: settings.expected_language.clone(),
+            detected_language: top.language.clone(),
+            probability: top.probability,
+        })
+}
+
+/// Attach a warning and tell the UI, which can offer a re-run in the detected language
+fn warn_on_language_mismatch(result: &mut TranscriptionResult, settings: &TranscriptionLanguageSettings, window: &Window) {
+    // A re-run in the detected language is what the user asked for
+    if result.language != settings.expected_language {
+        return;
+    }
+    if let Some(mismatch) = language_mismatch(result, settings) {
+        println!("⚠️ Recording looks like '{}' ({:.2}), expected '{}'", mismatch.detected_language, mismatch.probability, mismatch.expected_language);
+        result.warnings.push(format!(
+            "Die Aufnahme scheint nicht in der erwarteten Sprache zu sein (erkannt: {}, {:.0} %, erwartet: {})",
+            mismatch.detected_language, mismatch.probability * 100.0, mismatch.expected_language
+        ));
+        let _ = window.emit("transcription_language_mismatch", mismatch);
+    }
+}
+
+/// (start, end) of each chunk; neighbouring chunks share `overlap_s` seconds.
+/// A chunk length that is not positive yields the whole recording as one window.
+pub fn chunk_windows(duration_s: f32, chunk_s: f32, overlap_s: f32) -> Vec<(f32, f32)> {
+    if chunk_s.is_nan() || chunk_s <= 0.0 {
+        return vec![(0.0, duration_s)];
+    }
+    let overlap_s = overlap_s.clamp(0.0, chunk_s / 2.0);
+    let mut windows = Vec::new();
+    let mut start = 0.0;
+    loop {
+        let end = (start + chunk_s).min(duration_s);
+        windows.push((start, end));
+        if end >= duration_s {
+            return windows;
+        }
+        start = end - overlap_s;
+    }
+}
+
+/// Words of the segments from `first` on, with their segment and word index
+fn indexed_words(segments: &[TranscriptionSegment], first: usize) -> Vec<(usize, usize, String)> {
+    segments.iter().enumerate().skip(first)
+        .flat_map(|(i, segment)| segment.text.split_whitespace()
+            .enumerate()
+            .map(move |(w, word)| (i, w, normalize_segment_text(word)))
+            .collect::<Vec<_>>())
+        .collect()
+}
+
+/// End indices (inclusive) and length of the longest run of words both lists share
+fn longest_common_run(a: &[(usize, usize, String)], b: &[(usize, usize, String)]) -> Option<(usize, usize, usize)> {
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    let mut best: Option<(usize, usize, usize)> = None;
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            if !a[i - 1].2.is_empty() && a[i - 1].2 == b[j - 1].2 {
+                lengths[i][j] = lengths[i - 1][j - 1] + 1;
+                if best.is_none_or(|(_, _, len)| lengths[i][j] > len) {
+                    best = Some((i - 1, j - 1, lengths[i][j]));
+                }
+            }
+        }
+    }
+    best
+}
+
+/// Append the next chunk's segments (already on the original timeline) to the stitched ones.
+/// The words both chunks transcribed in the overlap [overlap_start, overlap_end] are kept once:
+/// the earlier chunk keeps everything up to the end of the shared run (words cut off at its end
+/// are dropped), the next chunk continues after it. Without a shared run of two words, the
+/// overlap is cut in the middle by segment start times.
+pub fn stitch_chunk_segments(stitched: &mut Vec<TranscriptionSegment>, mut next: Vec<TranscriptionSegment>, overlap_start: f32, overlap_end: f32) {
+    let tail_start = stitched.iter().position(|s| s.end_time > overlap_start).unwrap_or(stitched.len());
+    let head_len = next.iter().take_while(|s| s.start_time < overlap_end).count();
+    let tail = indexed_words(stitched, tail_start);
+    let head = indexed_words(&next[..head_len], 0);
+
+    match longest_common_run(&tail, &head).filter(|(_, _, len)| *len >= 2) {
+        Some((tail_end, head_end, _)) => {
+            let (segment, word) = (tail[tail_end].0, tail[tail_end].1);
+            stitched.truncate(segment + 1);
+            stitched[segment].text = stitched[segment].text.split_whitespace().take(word + 1).collect::<Vec<_>>().join(" ");
+
+            let (segment, word) = (head[head_end].0, head[head_end].1);
+            next.drain(..segment);
+            next[0].text = next[0].text.split_whitespace().skip(word + 1).collect::<Vec<_>>().join(" ");
+            next[0].start_time = next[0].start_time.max(stitched[stitched.len() - 1].end_time);
+            if next[0].text.is_empty() {
+                next.remove(0);
+            }
+        }
+        None => {
+            let middle = (overlap_start + overlap_end) / 2.0;
+            stitched.retain(|s| s.start_time < middle);
+            next.retain(|s| s.start_time >= middle);
+        }
+    }
+    stitched.extend(next);
+}
+
+/// Transcribe a long WAV chunk by chunk and stitch the results; progress reports "Teil k von n"
+fn transcribe_in_chunks(wav_path: &std::path::Path, duration_s: f32, settings: &ChunkingSettings, language: &str, window: &Window) -> Result<WhisperTranscriptionResult, String> {
+    let windows = chunk_windows(duration_s, settings.chunk_minutes * 60.0, settings.overlap_seconds);
+    let mut segments: Vec<TranscriptionSegment> = Vec::new();
+    let mut detection = None;  // The first chunk's, like an unchunked run detects on the first 30 s
+    let mut confidences = Vec::new();
+    let mut previous_end = 0.0;
+    let mut partial = false;
+
+    for (i, (start, end)) in windows.iter().enumerate() {
+        let _ = window.emit("audio_processing_progress", AudioProcessingProgress {
+            progress: i as f32 / windows.len() as f32,
+            stage: "transcribing".to_string(),
+            message: format!("Teil {} von {}", i + 1, windows.len()),
+        });
+        let chunk_path = processing_temp_dir().join(format!("whisper_input_chunk_{}_{}.wav", uuid::Uuid::new_v4().simple(), i + 1));
+        let result = cut_audio_range_with_ffmpeg(&wav_path.to_path_buf(), &chunk_path, *start, *end)
+            .and_then(|_| perform_whisper_transcription_in(&chunk_path, DEFAULT_WHISPER_MODEL, language));
+        let _ = fs::remove_file(&chunk_path);
+        let result = result?;
+
+        println!("Transcribed chunk {}/{} ({:.0}s-{:.0}s): {} segments", i + 1, windows.len(), start, end, result.segments.len());
+        confidences.push(result.confidence);
+        detection.get_or_insert((result.detected_language, result.language_probabilities));
+        stitch_chunk_segments(&mut segments, offset_segments(result.segments, *start), *start, previous_end);
+        previous_end = *end;
+        // Later chunks would leave a gap after the crashed one
+        if result.partial {
+            partial = true;
+            break;
+        }
+    }
+
+    Ok(WhisperTranscriptionResult {
+        text: segments.iter().map(|s| s.text.trim()).filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" "),
+        confidence: confidences.iter().sum::<f32>() / confidences.len().max(1) as f32,
+        segments,
+        partial,
+        language: language.to_string(),
+        detected_language: detection.as_ref().and_then(|(detected, _)| detected.clone()),
+        language_probabilities: detection.map(|(_, probabilities)| probabilities).unwrap_or_default(),
+    })
+}
+
+/// Flag (and optionally drop) segments that look like Whisper hallucinations:
+/// near-duplicate text repeated beyond `max_repetitions`, high no_speech probability,
+/// or an anomalous compression ratio. Every flagged segment is reported in
+/// `filtered_segments` so nothing disappears silently.
+/// Batch pipelines should call this with the default options (filter on).
+pub fn filter_hallucinated_segments(result: &mut TranscriptionResult, options: &HallucinationFilterOptions) {
+    let mut kept: Vec<TranscriptionSegment> = Vec::new();
+    let mut seen: Vec<(String, usize)> = Vec::new();  // (normalized text, occurrences)
+
+    for segment in result.segments.drain(..) {
+        let mut reasons = Vec::new();
+
+        let normalized = normalize_segment_text(&segment.text);
+        if !normalized.is_empty() {
+            let existing = seen.iter_mut()
+                .find(|(text, _)| text_similarity(text, &normalized) >= options.similarity_threshold);
+            let occurrences = match existing {
+                Some((_, count)) => {
+                    *count += 1;
+                    *count
+                }
+                None => {
+                    seen.push((normalized.clone(), 1));
+                    1
+                }
+            };
+            if occurrences > options.max_repetitions {
+                reasons.push(format!("repeated {} times", occurrences));
+            }
+        }
+
+        if let Some(no_speech) = segment.no_speech_prob {
+            if no_speech > options.no_speech_threshold {
+                reasons.push(format!("no_speech_prob {:.2}", no_speech));
+            }
+        }
+
+        if let Some(ratio) = segment.compression_ratio {
+            if ratio > options.max_compression_ratio {
+                reasons.push(format!("compression_ratio {:.2}", ratio));
+            }
+        }
+
+        if reasons.is_empty() {
+            kept.push(segment);
+        } else {
+            println!("Hallucination filter flagged segment {:.1}s-{:.1}s: {:?}",
+                segment.start_time, segment.end_time, reasons);
+            if !options.drop_flagged {
+                kept.push(segment.clone());
+            }
+            result.filtered_segments.push(FilteredSegment {
+                segment,
+                reasons,
+                removed: options.drop_flagged,
+            });
+        }
+    }
+
+    // Rebuild the flat text only if something was actually dropped
+    if options.drop_flagged && result.filtered_segments.iter().any(|f| f.removed) {
+        result.text = kept.iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+
+    result.segments = kept;
+}
+
+const DEFAULT_DEDUPE_SIMILARITY: f32 = 0.9;
+
+/// Merge runs of consecutive segments with near-identical text (Whisper stutter) into the
+/// first one, spanning all their time ranges. Other segments are left untouched.
+pub fn dedupe_consecutive_segments(mut result: TranscriptionResult, similarity_threshold: f32) -> TranscriptionResult {
+    let mut merged: Vec<TranscriptionSegment> = Vec::new();
+    let mut merged_any = false;
+
+    for segment in result.segments.drain(..) {
+        let normalized = normalize_segment_text(&segment.text);
+        if let Some(previous) = merged.last_mut() {
+            let previous_normalized = normalize_segment_text(&previous.text);
+            if !normalized.is_empty()
+                && text_similarity(&previous_normalized, &normalized) >= similarity_threshold
+            {
+                println!("Merging repeated segment {:.1}s-{:.1}s into {:.1}s-{:.1}s",
+                    segment.start_time, segment.end_time, previous.start_time, previous.end_time);
+                previous.end_time = previous.end_time.max(segment.end_time);
+                previous.confidence = previous.confidence.max(segment.confidence);
+                previous.no_speech_prob = match (previous.no_speech_prob, segment.no_speech_prob) {
+                    (Some(a), Some(b)) => Some(a.min(b)),
+                    (a, b) => a.or(b),
+                };
+                merged_any = true;
+                continue;
+            }
+        }
+        merged.push(segment);
+    }
+
+    if merged_any {
+        result.text = merged.iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    result.segments = merged;
+    result
+}
+
+/// Merge consecutive near-identical segments of a transcription result
+#[command]
+pub async fn dedupe_segments(
+    result: TranscriptionResult,
+    similarity_threshold: Option<f32>,
+) -> Result<TranscriptionResult, String> {
+    let threshold = similarity_threshold.unwrap_or(DEFAULT_DEDUPE_SIMILARITY).clamp(0.0, 1.0);
+    Ok(dedupe_consecutive_segments(result, threshold))
+}
+
+/// Phrases Whisper produces on silence at the start or end of German recordings (subtitle
+/// credits from its training data). A trailing "..." matches any sentence starting with the phrase.
+pub const DEFAULT_ARTIFACT_PHRASES: &[&str] = &[
+    "Untertitel...",
+    "Copyright WDR...",
+    "Vielen Dank.",
+    "Vielen Dank fürs Zuschauen.",
+    "Danke fürs Zuschauen.",
+    "Bis zum nächsten Mal.",
+    "Tschüss.",
+];
+
+/// Hallucination phrases stripped from transcript edges; stored in user-data/settings
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArtifactSettings {
+    pub phrases: Vec<String>,
+}
+
+impl Default for ArtifactSettings {
+    fn default() -> Self {
+        ArtifactSettings {
+            phrases: formats::to_strings(DEFAULT_ARTIFACT_PHRASES),
+        }
+    }
+}
+
+fn get_artifact_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("transcription_artifacts.json"))
+}
+
+fn load_artifact_settings() -> ArtifactSettings {
+    get_artifact_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ArtifactStripResult {
+    pub text: String,
+    pub removed: Vec<String>,  // Removed sentences, in text order
+}
+
+/// Byte ranges of the sentences of `text` (ends at ".", "!", "?" not after an abbreviation, or a line break)
+fn sentence_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if start.is_none() {
+            if c.is_whitespace() {
+                continue;
+            }
+            start = Some(i);
+        }
+        let at_word_end = chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        let end = match c {
+            '\n' => Some(i),
+            '.' | '!' | '?' if at_word_end => {
+                let word_start = text[..i].rfind(char::is_whitespace).map_or(0, |w| w + 1);
+                let word = &text[word_start..i + 1];
+                (!is_abbreviation(word)).then_some(i + 1)
+            }
+            _ => None,
+        };
+        if let (Some(s), Some(e)) = (start, end) {
+            if !text[s..e].trim().is_empty() {
+                spans.push((s, s + text[s..e].trim_end().len()));
+            }
+            start = None;
+        }
+    }
+    if let Some(s) = start {
+        spans.push((s, s + text[s..].trim_end().len()));
+    }
+    spans
+}
+
+fn is_artifact(sentence: &str, phrases: &[String]) -> bool {
+    let sentence = normalize_segment_text(sentence);
+    !sentence.is_empty() && phrases.iter().any(|phrase| match phrase.trim().strip_suffix("...") {
+        Some(prefix) => sentence.starts_with(&normalize_segment_text(prefix)),
+        None => sentence == normalize_segment_text(phrase),
     })
 }
 
+/// Remove artifact sentences from the start and end of a transcript; the text between the
+/// first and last kept sentence is returned unchanged
+pub fn strip_artifacts(text: &str, phrases: &[String]) -> ArtifactStripResult {
+    let spans = sentence_spans(text);
+    let first = spans.iter().position(|(s, e)| !is_artifact(&text[*s..*e], phrases));
+    let Some(first) = first else {
+        return ArtifactStripResult {
+            text: String::new(),
+            removed: spans.iter().map(|(s, e)| text[*s..*e].to_string()).collect(),
+        };
+    };
+    let last = spans.iter().rposition(|(s, e)| !is_artifact(&text[*s..*e], phrases)).unwrap_or(first);
+
+    let removed = spans[..first].iter().chain(&spans[last + 1..])
+        .map(|(s, e)| text[*s..*e].to_string())
+        .collect();
+    ArtifactStripResult {
+        text: text[spans[first].0..spans[last].1].to_string(),
+        removed,
+    }
+}
+
+/// Strip artifacts from the transcript text and drop edge segments that consist only of artifacts
+fn strip_transcription_edges(transcription: &mut TranscriptionResult, phrases: &[String]) {
+    let result = strip_artifacts(&transcription.text, phrases);
+    if result.removed.is_empty() {
+        return;
+    }
+    println!("Stripped transcription artifacts: {:?}", result.removed);
+    transcription.text = result.text;
+    let is_artifact_segment = |segment: &TranscriptionSegment| strip_artifacts(&segment.text, phrases).text.is_empty();
+    while transcription.segments.first().is_some_and(is_artifact_segment) {
+        transcription.segments.remove(0);
+    }
+    while transcription.segments.last().is_some_and(is_artifact_segment) {
+        transcription.segments.pop();
+    }
+}
+
+/// Drop known hallucination phrases from the edges of a transcript
+#[command]
+pub async fn strip_transcription_artifacts(text: String, phrases: Option<Vec<String>>) -> Result<ArtifactStripResult, String> {
+    let phrases = phrases.unwrap_or_else(|| load_artifact_settings().phrases);
+    let result = strip_artifacts(&text, &phrases);
+    if !result.removed.is_empty() {
+        println!("Stripped transcription artifacts: {:?}", result.removed);
+    }
+    Ok(result)
+}
+
+/// Lowercase, strip punctuation and collapse whitespace for duplicate detection
+fn normalize_segment_text(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c.is_whitespace() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Similarity of two strings in [0, 1] based on Levenshtein distance over chars
+pub(crate) fn text_similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let max_len = a.len().max(b.len());
+    if max_len == 0 {
+        return 1.0;
+    }
+
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    1.0 - previous[b.len()] as f32 / max_len as f32
+}
+
+/// Options for the silence check before transcription
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AudioLevelOptions {
+    pub silence_threshold_dbfs: f32,  // Windows quieter than this count as silent
+    pub min_active_ratio: f32,        // Share of non-silent windows below which the recording is "mostly silent"
+}
+
+impl Default for AudioLevelOptions {
+    fn default() -> Self {
+        AudioLevelOptions {
+            silence_threshold_dbfs: -50.0,
+            min_active_ratio: 0.05,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AudioLevels {
+    pub mean_dbfs: f32,       // RMS level of the whole recording
+    pub peak_dbfs: f32,
+    pub active_ratio: f32,    // Share of 50 ms windows above the silence threshold
+    pub duration_s: f32,
+    pub is_mostly_silent: bool,
+}
+
+/// Level floor for digital silence (log of 0 would be -inf, which JSON can't carry)
+const MIN_DBFS: f32 = -120.0;
+const LEVEL_WINDOW_S: f32 = 0.05;
+
+fn to_dbfs(amplitude: f32) -> f32 {
+    if amplitude <= 0.0 {
+        return MIN_DBFS;
+    }
+    (20.0 * amplitude.log10()).max(MIN_DBFS)
+}
+
+/// Decode a PCM (8/16/24/32 bit) or 32-bit float WAV into mono samples in [-1, 1]
+fn read_wav_samples(bytes: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Err("Not a WAV file".to_string());
+    }
+
+    let mut format: Option<(u16, u16, u32, u16)> = None;  // (format tag, channels, sample rate, bits)
+    let mut position = 12;
+    while position + 8 <= bytes.len() {
+        let id = &bytes[position..position + 4];
+        let size = u32::from_le_bytes([bytes[position + 4], bytes[position + 5], bytes[position + 6], bytes[position + 7]]) as usize;
+        let body_start = position + 8;
+        // Recorders that are killed mid-write leave the data size wrong; take what is there
+        let body = &bytes[body_start..(body_start + size).min(bytes.len())];
+
+        if id == b"fmt " && body.len() >= 16 {
+            let tag = u16::from_le_bytes([body[0], body[1]]);
+            // WAVE_FORMAT_EXTENSIBLE carries the real format in the sub-format GUID
+            let tag = if tag == 0xFFFE && body.len() >= 26 { u16::from_le_bytes([body[24], body[25]]) } else { tag };
+            format = Some((
+                tag,
+                u16::from_le_bytes([body[2], body[3]]).max(1),
+                u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                u16::from_le_bytes([body[14], body[15]]),
+            ));
+        } else if id == b"data" {
+            let (tag, channels, sample_rate, bits) = format.ok_or("WAV data before format chunk")?;
+            let bytes_per_sample = (bits as usize).div_ceil(8);
+            let decode = |sample: &[u8]| -> Option<f32> {
+                match (tag, bits) {
+                    (1, 8) => Some((sample[0] as f32 - 128.0) / 128.0),
+                    (1, 16) => Some(i16::from_le_bytes([sample[0], sample[1]]) as f32 / 32768.0),
+                    (1, 24) => Some(((i32::from_le_bytes([0, sample[0], sample[1], sample[2]])) >> 8) as f32 / 8_388_608.0),
+                    (1, 32) => Some(i32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]) as f32 / 2_147_483_648.0),
+                    (3, 32) => Some(f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]])),
+                    _ => None,
+                }
+            };
+            if decode(&[0; 4]).is_none() {
+                return Err(format!("Unsupported WAV encoding (format {}, {} bit)", tag, bits));
+            }
+
+            // Average the channels of each frame
+            let frame_size = bytes_per_sample * channels as usize;
+            let samples = body.chunks_exact(frame_size)
+                .map(|frame| {
+                    let sum: f32 = frame.chunks_exact(bytes_per_sample).filter_map(decode).sum();
+                    sum / channels as f32
+                })
+                .collect();
+            return Ok((samples, sample_rate));
+        }
+        position = body_start + size + (size % 2);  // Chunks are word-aligned
+    }
+
+    Err("WAV file has no data chunk".to_string())
+}
+
+/// RMS/peak levels and the share of non-silent 50 ms windows. Clips shorter than one window
+/// are measured as a single window; an empty clip is silent.
+pub fn compute_audio_levels(samples: &[f32], sample_rate: u32, options: &AudioLevelOptions) -> AudioLevels {
+    let duration_s = if sample_rate > 0 { samples.len() as f32 / sample_rate as f32 } else { 0.0 };
+    if samples.is_empty() {
+        return AudioLevels { mean_dbfs: MIN_DBFS, peak_dbfs: MIN_DBFS, active_ratio: 0.0, duration_s, is_mostly_silent: true };
+    }
+
+    let rms = |window: &[f32]| (window.iter().map(|s| s * s).sum::<f32>() / window.len() as f32).sqrt();
+    let peak = samples.iter().fold(0.0f32, |max, s| max.max(s.abs()));
+
+    let window_len = ((sample_rate as f32 * LEVEL_WINDOW_S) as usize).max(1);
+    let windows: Vec<f32> = samples.chunks(window_len).map(|w| to_dbfs(rms(w))).collect();
+    let active = windows.iter().filter(|level| **level > options.silence_threshold_dbfs).count();
+    let active_ratio = active as f32 / windows.len() as f32;
+
+    AudioLevels {
+        mean_dbfs: to_dbfs(rms(samples)),
+        peak_dbfs: to_dbfs(peak),
+        active_ratio,
+        duration_s,
+        is_mostly_silent: active_ratio < options.min_active_ratio,
+    }
+}
+
+/// Measure a recording; non-WAV files are decoded through FFmpeg first
+fn analyze_audio_levels_from_file(path: &PathBuf, options: &AudioLevelOptions) -> Result<AudioLevels, String> {
+    let is_wav = path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("wav"));
+    let (samples, sample_rate) = if is_wav {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read audio file: {}", e))?;
+        read_wav_samples(&bytes)?
+    } else {
+        let temp_wav = processing_temp_dir().join(format!("levels_{}.wav", uuid::Uuid::new_v4()));
+        let decoded = convert_to_wav_with_ffmpeg(path, &temp_wav)
+            .and_then(|_| fs::read(&temp_wav).map_err(|e| format!("Failed to read decoded audio: {}", e)));
+        let _ = fs::remove_file(&temp_wav);
+        read_wav_samples(&decoded?)?
+    };
+
+    Ok(compute_audio_levels(&samples, sample_rate, options))
+}
+
+/// Check a recording's loudness before transcription so near-silent files can be flagged
+#[command]
+pub async fn analyze_audio_levels(
+    path: String,
+    options: Option<AudioLevelOptions>,
+) -> Result<AudioLevels, String> {
+    let audio_path = PathBuf::from(&path);
+    if !audio_path.exists() {
+        return Err(format!("File does not exist: {}", path));
+    }
+    let options = options.unwrap_or_default();
+
+    let levels = tokio::task::spawn_blocking(move || analyze_audio_levels_from_file(&audio_path, &options))
+        .await
+        .map_err(|e| format!("Level analysis failed: {}", e))??;
+
+    println!("Audio levels: mean {:.1} dBFS, peak {:.1} dBFS, {:.0}% active{}",
+        levels.mean_dbfs, levels.peak_dbfs, levels.active_ratio * 100.0,
+        if levels.is_mostly_silent { " (mostly silent)" } else { "" });
+    Ok(levels)
+}
+
 /// Validate audio file for processing
 #[command]
 pub async fn validate_audio_file(file_path: String) -> Result<bool, String> {
@@ -275,11 +1377,10 @@ pub async fn validate_audio_file(file_path: String) -> Result<bool, String> {
         .unwrap_or("")
         .to_lowercase();
     
-    let supported_formats = ["wav", "mp3", "m4a", "flac", "ogg", "webm"];
-    if !supported_formats.contains(&extension.as_str()) {
+    if !formats::is_audio_format(&extension) {
         return Err(format!(
             "Unsupported audio format: {}. Supported formats: {:?}",
-            extension, supported_formats
+            extension, AUDIO_FORMATS
         ));
     }
     
@@ -287,29 +1388,175 @@ pub async fn validate_audio_file(file_path: String) -> Result<bool, String> {
 }
 
 
+/// Run FFmpeg with the given arguments, trying each known executable; returns the first successful run
+fn run_ffmpeg(args: &[String]) -> Result<std::process::Output, String> {
+    let mut last_error = String::new();
+
+    for ffmpeg_cmd in &FFMPEG_COMMANDS {
+        match Command::new(ffmpeg_cmd).args(args).output() {
+            Ok(output) if output.status.success() => return Ok(output),
+            Ok(output) => {
+                last_error = format!("FFmpeg failed with {}: {}", ffmpeg_cmd, String::from_utf8_lossy(&output.stderr));
+                println!("{}", last_error);
+            },
+            Err(e) => {
+                last_error = format!("Failed to execute {}: {}", ffmpeg_cmd, e);
+                println!("{}", last_error);
+            }
+        }
+    }
+
+    Err(explain_error(format!("All FFmpeg attempts failed. Last error: {}. Please ensure FFmpeg is installed and accessible.", last_error)))
+}
+
+/// "Duration: 00:01:23.45" from FFmpeg's stderr
+fn parse_ffmpeg_duration(ffmpeg_log: &str) -> Option<f32> {
+    let duration_re = regex::Regex::new(r"Duration:\s*(\d+):(\d{2}):(\d{2}(?:\.\d+)?)").unwrap();
+    let caps = duration_re.captures(ffmpeg_log)?;
+    let hours: f32 = caps[1].parse().ok()?;
+    let minutes: f32 = caps[2].parse().ok()?;
+    let seconds: f32 = caps[3].parse().ok()?;
+    Some(hours * 3600.0 + minutes * 60.0 + seconds)
+}
+
+/// Length of a recording in seconds. FFmpeg without an output file exits with an error but
+/// has printed the input's duration by then.
+pub(crate) fn probe_audio_duration(input_path: &std::path::Path) -> Result<f32, String> {
+    let input = input_path.to_str().ok_or("Invalid input path")?;
+    for ffmpeg_cmd in &FFMPEG_COMMANDS {
+        if let Ok(output) = Command::new(ffmpeg_cmd).args(["-hide_banner", "-i", input]).output() {
+            return parse_ffmpeg_duration(&String::from_utf8_lossy(&output.stderr))
+                .ok_or_else(|| format!("Dauer der Aufnahme nicht lesbar: {}", input_path.display()));
+        }
+    }
+    Err("FFmpeg not found. Please ensure FFmpeg is installed and accessible.".to_string())
+}
+
+/// FFmpeg arguments for a silencedetect pass (analysis only, no output file)
+fn build_silencedetect_args(input_path: &str, options: &SilenceSplitOptions) -> Vec<String> {
+    vec![
+        "-i".to_string(),
+        input_path.to_string(),
+        "-af".to_string(),
+        format!("silencedetect=noise={}dB:d={}", options.noise_threshold_db, options.min_silence_s),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]
+}
+
+/// FFmpeg arguments cutting the input at the given times into 16kHz mono WAV takes
+fn build_segment_args(input_path: &str, output_pattern: &str, cut_points: &[f32]) -> Vec<String> {
+    let segment_times = cut_points.iter()
+        .map(|t| format!("{:.3}", t))
+        .collect::<Vec<_>>()
+        .join(",");
+
+    vec![
+        "-i".to_string(),
+        input_path.to_string(),
+        "-ac".to_string(),
+        "1".to_string(),
+        "-ar".to_string(),
+        "16000".to_string(),
+        "-f".to_string(),
+        "segment".to_string(),
+        "-segment_times".to_string(),
+        segment_times,
+        "-segment_start_number".to_string(),
+        "1".to_string(),
+        "-reset_timestamps".to_string(),
+        "1".to_string(),
+        "-y".to_string(),
+        output_pattern.to_string(),
+    ]
+}
+
+/// Cut points (middle of each pause) from silencedetect's stderr log.
+/// Leading and trailing silence is not a cut point.
+fn parse_silence_cut_points(ffmpeg_log: &str) -> Vec<f32> {
+    let start_re = regex::Regex::new(r"silence_start:\s*(-?[\d.]+)").unwrap();
+    let end_re = regex::Regex::new(r"silence_end:\s*([\d.]+)").unwrap();
+
+    let mut cut_points = Vec::new();
+    let mut silence_start: Option<f32> = None;
+
+    for line in ffmpeg_log.lines() {
+        if let Some(caps) = start_re.captures(line) {
+            silence_start = caps[1].parse::<f32>().ok();
+        } else if let Some(caps) = end_re.captures(line) {
+            if let (Some(start), Ok(end)) = (silence_start.take(), caps[1].parse::<f32>()) {
+                if start > 0.0 {
+                    cut_points.push((start + end) / 2.0);
+                }
+            }
+        }
+    }
+
+    cut_points
+}
+
+fn split_audio_by_silence_with_ffmpeg(
+    input_path: &PathBuf,
+    output_dir: &PathBuf,
+    options: &SilenceSplitOptions,
+) -> Result<Vec<String>, String> {
+    let input = input_path.to_str().ok_or("Invalid input path")?;
+
+    let detection = run_ffmpeg(&build_silencedetect_args(input, options))?;
+    let cut_points = parse_silence_cut_points(&String::from_utf8_lossy(&detection.stderr));
+    println!("Detected {} pause(s) of at least {}s", cut_points.len(), options.min_silence_s);
+
+    if cut_points.is_empty() {
+        // No pause: the whole recording is a single take
+        let output_path = output_dir.join("take_001.wav");
+        convert_to_wav_with_ffmpeg(input_path, &output_path)?;
+        return Ok(vec![output_path.to_string_lossy().to_string()]);
+    }
+
+    let pattern = output_dir.join("take_%03d.wav");
+    run_ffmpeg(&build_segment_args(input, pattern.to_str().ok_or("Invalid output path")?, &cut_points))?;
+
+    // Collect the written takes in order, skipping empty files
+    let takes: Vec<String> = (1..=cut_points.len() + 1)
+        .map(|i| output_dir.join(format!("take_{:03}.wav", i)))
+        .filter(|path| fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false))
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+
+    if takes.is_empty() {
+        return Err("FFmpeg did not produce any audio segments".to_string());
+    }
+    Ok(takes)
+}
+
 /// Internal result structure for Whisper transcription
-struct WhisperTranscriptionResult {
-    text: String,
-    confidence: f32,
-    segments: Vec<TranscriptionSegment>,
+pub(crate) struct WhisperTranscriptionResult {
+    pub(crate) text: String,
+    pub(crate) confidence: f32,
+    pub(crate) segments: Vec<TranscriptionSegment>,
+    pub(crate) partial: bool,  // The script crashed after these segments
+    pub(crate) language: String,  // Requested language
+    pub(crate) detected_language: Option<String>,
+    pub(crate) language_probabilities: Vec<LanguageProbability>,
 }
 
+/// FFmpeg executable locations, tried in order
+const FFMPEG_COMMANDS: [&str; 4] = [
+    "ffmpeg",                    // In PATH
+    "ffmpeg.exe",               // Windows with extension
+    r"C:\ffmpeg\bin\ffmpeg.exe", // Common installation path
+    r"C:\Program Files\ffmpeg\bin\ffmpeg.exe",
+];
+
 /// Convert audio file to WAV using FFmpeg subprocess
 fn convert_to_wav_with_ffmpeg(input_path: &PathBuf, output_path: &PathBuf) -> Result<(), String> {
     println!("Converting {} to WAV format using FFmpeg...", input_path.display());
 
-    // Try multiple FFmpeg executable locations
-    let ffmpeg_commands = [
-        "ffmpeg",                    // In PATH
-        "ffmpeg.exe",               // Windows with extension
-        r"C:\ffmpeg\bin\ffmpeg.exe", // Common installation path
-        r"C:\Program Files\ffmpeg\bin\ffmpeg.exe",
-    ];
-
     let mut last_error = String::new();
     let mut conversion_success = false;
 
-    for ffmpeg_cmd in &ffmpeg_commands {
+    for ffmpeg_cmd in &FFMPEG_COMMANDS {
         println!("Trying FFmpeg command: {}", ffmpeg_cmd);
 
         match Command::new(ffmpeg_cmd)
@@ -343,7 +1590,7 @@ fn convert_to_wav_with_ffmpeg(input_path: &PathBuf, output_path: &PathBuf) -> Re
     }
 
     if !conversion_success {
-        return Err(format!("All FFmpeg attempts failed. Last error: {}. Please ensure FFmpeg is installed and accessible.", last_error));
+        return Err(explain_error(format!("All FFmpeg attempts failed. Last error: {}. Please ensure FFmpeg is installed and accessible.", last_error)));
     }
 
     // Verify the output file was created
@@ -363,18 +1610,69 @@ fn convert_to_wav_with_ffmpeg(input_path: &PathBuf, output_path: &PathBuf) -> Re
     Ok(())
 }
 
-/// Perform Whisper transcription using Python subprocess
-fn perform_whisper_transcription(audio_path: &PathBuf) -> Result<WhisperTranscriptionResult, String> {
-    // Use the Tauri-compatible Python script in project root
-    let script_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\whisper_transcribe_tauri.py");
+/// Cut the time range [start_s, end_s] out of an audio file into a 16kHz mono WAV
+pub(crate) fn cut_audio_range_with_ffmpeg(
+    input_path: &PathBuf,
+    output_path: &PathBuf,
+    start_s: f32,
+    end_s: f32,
+) -> Result<(), String> {
+    println!("Cutting {:.2}s-{:.2}s from {} using FFmpeg...", start_s, end_s, input_path.display());
 
-    println!("Looking for Tauri Python script at: {}", script_path.display());
+    let mut last_error = String::new();
 
-    if !script_path.exists() {
-        return Err(format!("Whisper Tauri script not found at: {}", script_path.display()));
+    for ffmpeg_cmd in &FFMPEG_COMMANDS {
+        match Command::new(ffmpeg_cmd)
+            .arg("-ss")
+            .arg(format!("{:.3}", start_s))
+            .arg("-to")
+            .arg(format!("{:.3}", end_s))
+            .arg("-i")
+            .arg(input_path.to_str().ok_or("Invalid input path")?)
+            .arg("-ac")
+            .arg("1")
+            .arg("-ar")
+            .arg("16000")
+            .arg("-y")
+            .arg(output_path.to_str().ok_or("Invalid output path")?)
+            .output()
+        {
+            Ok(output) if output.status.success() => {
+                let file_size = fs::metadata(output_path).map(|m| m.len()).unwrap_or(0);
+                if file_size == 0 {
+                    return Err("FFmpeg created empty output file".to_string());
+                }
+                return Ok(());
+            },
+            Ok(output) => {
+                last_error = format!("FFmpeg failed with {}: {}", ffmpeg_cmd, String::from_utf8_lossy(&output.stderr));
+                println!("{}", last_error);
+            },
+            Err(e) => {
+                last_error = format!("Failed to execute {}: {}", ffmpeg_cmd, e);
+                println!("{}", last_error);
+            }
+        }
     }
 
-    println!("Tauri Python script found successfully!");
+    Err(explain_error(format!("All FFmpeg attempts failed. Last error: {}. Please ensure FFmpeg is installed and accessible.", last_error)))
+}
+
+/// Perform Whisper transcription using Python subprocess
+pub(crate) fn perform_whisper_transcription(audio_path: &PathBuf) -> Result<WhisperTranscriptionResult, String> {
+    perform_whisper_transcription_with_model(audio_path, DEFAULT_WHISPER_MODEL)
+}
+
+/// Perform Whisper transcription with a given model size in the expected language
+pub(crate) fn perform_whisper_transcription_with_model(audio_path: &std::path::Path, model: &str) -> Result<WhisperTranscriptionResult, String> {
+    perform_whisper_transcription_in(audio_path, model, &load_language_settings().expected_language)
+}
+
+/// Perform Whisper transcription with a given model size and language; waits for any other Whisper run
+pub(crate) fn perform_whisper_transcription_in(audio_path: &std::path::Path, model: &str, language: &str) -> Result<WhisperTranscriptionResult, String> {
+    let _whisper_run = WHISPER_RUN.lock().unwrap_or_else(|e| e.into_inner());
+    let script_path = locate_script(WHISPER_SCRIPT)?;
+    println!("Using Whisper script: {}", script_path.display());
 
     // Call Python script with json output format - try multiple Python paths
     println!("Attempting to call Python script with arguments:");
@@ -397,6 +1695,8 @@ fn perform_whisper_transcription(audio_path: &PathBuf) -> Result<WhisperTranscri
             .arg(script_path.to_str().ok_or("Invalid script path")?)
             .arg(audio_path.to_str().ok_or("Invalid audio path")?)
             .arg("json")  // Request JSON output format
+            .arg(model)
+            .arg(language)
             .env("PYTHONIOENCODING", "utf-8")  // Force UTF-8 output on Windows
             .output()
         {
@@ -413,21 +1713,68 @@ fn perform_whisper_transcription(audio_path: &PathBuf) -> Result<WhisperTranscri
         }
     }
 
-    let output = output.ok_or(format!("All Python commands failed. Last error: {}", last_error))?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Python script failed: {}", stderr));
-    }
+    let output = output.ok_or_else(|| explain_error(format!("All Python commands failed. Last error: {}", last_error)))?;
 
     // Parse stdout as UTF-8 (Python outputs UTF-8 encoded JSON)
     let stdout = String::from_utf8(output.stdout.clone())
         .unwrap_or_else(|_| String::from_utf8_lossy(&output.stdout).into_owned());
+    crate::commands::text_commands::warn_on_mojibake("Whisper", &stdout);
+
+    let mut result = parse_whisper_output(&stdout, output.status.success(), &String::from_utf8_lossy(&output.stderr))?;
+    result.language = language.to_string();
+    Ok(result)
+}
+
+fn parse_segment(segment: &serde_json::Value) -> Option<TranscriptionSegment> {
+    Some(TranscriptionSegment {
+        start_time: segment.get("start_time")?.as_f64()? as f32,
+        end_time: segment.get("end_time")?.as_f64()? as f32,
+        text: segment.get("text")?.as_str()?.to_string(),
+        confidence: segment.get("confidence")?.as_f64()? as f32,
+        no_speech_prob: segment.get("no_speech_prob")
+            .and_then(|v| v.as_f64())
+            .map(|v| v as f32),
+        compression_ratio: segment.get("compression_ratio")
+            .and_then(|v| v.as_f64())
+            .map(|v| v as f32),
+    })
+}
 
-    // Parse JSON response
-    let json_result: serde_json::Value = serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse JSON response: {} - stdout: {}", e, stdout))?;
+/// Whisper's stdout: one JSON line per segment as it is decoded, then the result
+/// (older scripts print only the result, possibly indented). Without a result the
+/// script crashed; the segments it wrote are returned marked partial.
+fn parse_whisper_output(stdout: &str, succeeded: bool, stderr: &str) -> Result<WhisperTranscriptionResult, String> {
+    // A line cut off by the crash doesn't parse and is dropped
+    let lines: Vec<serde_json::Value> = match serde_json::from_str(stdout.trim()) {
+        Ok(result) => vec![result],
+        Err(_) => stdout.lines().filter_map(|line| serde_json::from_str(line.trim()).ok()).collect(),
+    };
+    let is_segment = |line: &serde_json::Value| line.get("type").and_then(|t| t.as_str()) == Some("segment");
+
+    let Some(json_result) = lines.iter().rev().find(|line| !is_segment(line)) else {
+        let segments: Vec<TranscriptionSegment> = lines.iter().filter_map(parse_segment).collect();
+        if segments.is_empty() {
+            return Err(if succeeded {
+                format!("Failed to parse JSON response - stdout: {}", stdout)
+            } else {
+                explain_error(format!("Python script failed: {}", stderr))
+            });
+        }
+        println!("⚠️ Whisper stopped after {} segments, keeping them: {}", segments.len(), stderr.trim());
+        return Ok(WhisperTranscriptionResult {
+            text: segments.iter().map(|s| s.text.trim()).filter(|t| !t.is_empty()).collect::<Vec<_>>().join(" "),
+            confidence: 0.0,
+            segments,
+            partial: true,
+            language: String::new(),
+            detected_language: None,
+            language_probabilities: Vec::new(),
+        });
+    };
 
+    if !succeeded && json_result.get("error").is_none() {
+        println!("Warning: Whisper exited with an error after its result: {}", stderr.trim());
+    }
     if let Some(error) = json_result.get("error") {
         return Err(format!("Transcription error: {}", error.as_str().unwrap_or("Unknown error")));
     }
@@ -444,22 +1791,342 @@ fn perform_whisper_transcription(audio_path: &PathBuf) -> Result<WhisperTranscri
 
     let segments = json_result.get("segments")
         .and_then(|s| s.as_array())
-        .map(|segments_array| {
-            segments_array.iter().filter_map(|segment| {
-                Some(TranscriptionSegment {
-                    start_time: segment.get("start_time")?.as_f64()? as f32,
-                    end_time: segment.get("end_time")?.as_f64()? as f32,
-                    text: segment.get("text")?.as_str()?.to_string(),
-                    confidence: segment.get("confidence")?.as_f64()? as f32,
-                })
-            }).collect()
-        })
+        .map(|segments_array| segments_array.iter().filter_map(parse_segment).collect())
         .unwrap_or_default();
 
+    // Older scripts report no detection
+    let mut language_probabilities: Vec<LanguageProbability> = json_result.get("language_probabilities")
+        .and_then(|p| serde_json::from_value(p.clone()).ok())
+        .unwrap_or_default();
+    language_probabilities.sort_by(|a, b| b.probability.total_cmp(&a.probability));
+
     Ok(WhisperTranscriptionResult {
         text,
         confidence,
         segments,
+        partial: false,
+        language: json_result.get("language").and_then(|l| l.as_str()).unwrap_or("").to_string(),
+        detected_language: language_probabilities.first().map(|p| p.language.clone()),
+        language_probabilities,
     })
 }
 
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_test_wav(path: &PathBuf, samples: &[i16], sample_rate: u32) {
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());  // PCM
+        wav.extend_from_slice(&1u16.to_le_bytes());  // Mono
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        wav.extend_from_slice(&2u16.to_le_bytes());
+        wav.extend_from_slice(&16u16.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        wav.extend_from_slice(&data);
+        fs::write(path, wav).unwrap();
+    }
+
+    #[test]
+    fn test_silent_wav_is_flagged_and_tone_is_not() {
+        let options = AudioLevelOptions::default();
+        let dir = std::env::temp_dir();
+        let silent_path = dir.join(format!("levels_silent_{}.wav", std::process::id()));
+        let tone_path = dir.join(format!("levels_tone_{}.wav", std::process::id()));
+
+        // One second of faint hiss (about -70 dBFS) and of a 440 Hz tone at half scale
+        let silent: Vec<i16> = (0..16000).map(|i| if i % 2 == 0 { 10 } else { -10 }).collect();
+        let tone: Vec<i16> = (0..16000)
+            .map(|i| (16384.0 * (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 16000.0).sin()) as i16)
+            .collect();
+        write_test_wav(&silent_path, &silent, 16000);
+        write_test_wav(&tone_path, &tone, 16000);
+
+        let silent_levels = analyze_audio_levels_from_file(&silent_path, &options).unwrap();
+        let tone_levels = analyze_audio_levels_from_file(&tone_path, &options).unwrap();
+        let _ = fs::remove_file(&silent_path);
+        let _ = fs::remove_file(&tone_path);
+
+        assert!(silent_levels.is_mostly_silent, "{:?}", silent_levels);
+        assert!(silent_levels.mean_dbfs < -60.0);
+        assert!(!tone_levels.is_mostly_silent, "{:?}", tone_levels);
+        assert!((tone_levels.peak_dbfs - -6.0).abs() < 0.5, "{:?}", tone_levels);
+        assert!((tone_levels.duration_s - 1.0).abs() < 0.001);
+
+        // Very short and empty clips
+        let short = compute_audio_levels(&[0.5; 10], 16000, &options);
+        assert!(!short.is_mostly_silent && short.active_ratio == 1.0);
+        assert!(compute_audio_levels(&[], 16000, &options).is_mostly_silent);
+    }
+
+    fn segment(start: f32, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            start_time: start,
+            end_time: start + 1.0,
+            text: text.to_string(),
+            confidence: 0.9,
+            no_speech_prob: None,
+            compression_ratio: None,
+        }
+    }
+
+    fn result_with(segments: Vec<TranscriptionSegment>) -> TranscriptionResult {
+        TranscriptionResult {
+            text: segments.iter().map(|s| s.text.clone()).collect::<Vec<_>>().join(" "),
+            confidence: 0.9,
+            processing_time_ms: 0,
+            language: "de".to_string(),
+            segments,
+            filtered_segments: Vec::new(),
+            partial: false,
+            detected_language: None,
+            language_probabilities: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_hallucination_filter_drops_repeats_and_silence() {
+        let mut silent = segment(5.0, "Untertitel im Auftrag des ZDF");
+        silent.no_speech_prob = Some(0.92);
+
+        let mut result = result_with(vec![
+            segment(0.0, "Der Patient berichtet über Rückenschmerzen."),
+            segment(1.0, "Vielen Dank."),
+            segment(2.0, "Vielen Dank."),
+            segment(3.0, "vielen dank"),
+            segment(4.0, "Vielen Dank!"),
+            silent,
+        ]);
+
+        filter_hallucinated_segments(&mut result, &HallucinationFilterOptions::default());
+
+        assert_eq!(result.segments.len(), 3);
+        assert_eq!(result.filtered_segments.len(), 3);
+        assert!(result.filtered_segments.iter().all(|f| f.removed));
+        assert!(result.text.starts_with("Der Patient berichtet"));
+        assert!(!result.text.contains("Untertitel"));
+    }
+
+    #[test]
+    fn test_silence_split_ffmpeg_args() {
+        let options = SilenceSplitOptions { noise_threshold_db: -40.0, min_silence_s: 1.5 };
+        let detect = build_silencedetect_args("in.m4a", &options);
+        assert!(detect.contains(&"silencedetect=noise=-40dB:d=1.5".to_string()));
+        assert_eq!(&detect[detect.len() - 3..], ["-f", "null", "-"]);
+
+        let log = "[silencedetect @ 0x1] silence_start: 0\n\
+            [silencedetect @ 0x1] silence_end: 0.8 | silence_duration: 0.8\n\
+            [silencedetect @ 0x1] silence_start: 10\n\
+            [silencedetect @ 0x1] silence_end: 15 | silence_duration: 5\n\
+            [silencedetect @ 0x1] silence_start: 40.5\n";
+        let cut_points = parse_silence_cut_points(log);
+        assert_eq!(cut_points, vec![12.5]);
+
+        let segment = build_segment_args("in.m4a", "out/take_%03d.wav", &cut_points);
+        let position = |flag: &str| segment.iter().position(|a| a == flag).unwrap();
+        assert_eq!(segment[position("-f") + 1], "segment");
+        assert_eq!(segment[position("-segment_times") + 1], "12.500");
+        assert_eq!(segment.last().map(String::as_str), Some("out/take_%03d.wav"));
+    }
+
+    #[test]
+    fn test_hallucination_filter_flag_only_keeps_segments() {
+        let mut noisy = segment(0.0, "la la la la la la la la la la");
+        noisy.compression_ratio = Some(3.1);
+        let mut result = result_with(vec![noisy]);
+
+        let options = HallucinationFilterOptions { drop_flagged: false, ..Default::default() };
+        filter_hallucinated_segments(&mut result, &options);
+
+        assert_eq!(result.segments.len(), 1);
+        assert_eq!(result.filtered_segments.len(), 1);
+        assert!(!result.filtered_segments[0].removed);
+    }
+
+    #[test]
+    fn test_stuttered_segments_collapse_into_one() {
+        let result = dedupe_consecutive_segments(result_with(vec![
+            segment(0.0, "Der Patient berichtet"),
+            segment(1.0, "Der Patient berichtet,"),
+            segment(2.0, "über Schmerzen im Knie."),
+            segment(3.0, "Der Patient berichtet"),
+        ]), DEFAULT_DEDUPE_SIMILARITY);
+
+        assert_eq!(result.segments.len(), 3);
+        assert_eq!(result.segments[0].start_time, 0.0);
+        assert_eq!(result.segments[0].end_time, 2.0);
+        assert_eq!(result.segments[0].text, "Der Patient berichtet");
+        // Non-adjacent repeats are not merged
+        assert_eq!(result.segments[2].start_time, 3.0);
+        assert_eq!(result.text, "Der Patient berichtet über Schmerzen im Knie. Der Patient berichtet");
+    }
+
+    #[test]
+    fn test_trailing_hallucination_removed_and_content_kept() {
+        let phrases = formats::to_strings(DEFAULT_ARTIFACT_PHRASES);
+        let text = "Untertitel im Auftrag des ZDF, 2017.\nDer Patient stellt sich bei Dr. Meier vor.\n\nBefund: unauffällig. Vielen Dank.  Vielen Dank fürs Zuschauen.";
+        let result = strip_artifacts(text, &phrases);
+
+        assert_eq!(result.text, "Der Patient stellt sich bei Dr. Meier vor.\n\nBefund: unauffällig.");
+        assert_eq!(result.removed, vec!["Untertitel im Auftrag des ZDF, 2017.", "Vielen Dank.", "Vielen Dank fürs Zuschauen."]);
+
+        // Phrases in the middle and sentences merely starting like a phrase stay
+        let kept = strip_artifacts("Vielen Dank für die Zuweisung. Tschüss. Diagnose folgt.", &phrases);
+        assert!(kept.removed.is_empty());
+        assert_eq!(strip_artifacts("Vielen Dank.", &phrases).text, "");
+    }
+
+    #[test]
+    fn test_range_segments_are_offset_by_start() {
+        let segment = |start: f32, end: f32| TranscriptionSegment {
+            start_time: start, end_time: end, text: "Befund".to_string(), confidence: 0.9,
+            no_speech_prob: None, compression_ratio: None,
+        };
+        let segments = offset_segments(vec![segment(0.0, 4.5), segment(4.5, 12.0)], 90.0);
+        let times: Vec<(f32, f32)> = segments.iter().map(|s| (s.start_time, s.end_time)).collect();
+        assert_eq!(times, vec![(90.0, 94.5), (94.5, 102.0)]);
+
+        let log = "Input #0, wav, from 'diktat.wav':\n  Duration: 00:02:03.50, bitrate: 256 kb/s\n";
+        assert_eq!(parse_ffmpeg_duration(log), Some(123.5));
+        assert!(check_time_range(90.0, 120.0, 123.5).is_ok());
+        assert!(check_time_range(90.0, 130.0, 123.5).is_err());
+        assert!(check_time_range(30.0, 30.0, 123.5).is_err());
+    }
+
+    #[test]
+    fn test_chunks_are_stitched_without_duplicates_at_the_seam() {
+        assert_eq!(chunk_windows(1150.0, 600.0, 5.0), vec![(0.0, 600.0), (595.0, 1150.0)]);
+        assert_eq!(chunk_windows(300.0, 600.0, 5.0), vec![(0.0, 300.0)]);
+        assert_eq!(chunk_windows(1150.0, 0.0, 5.0), vec![(0.0, 1150.0)]);
+        assert!(ChunkingSettings { chunk_minutes: 0.0, ..ChunkingSettings::default() }.validate().is_err());
+
+        let timed = |start: f32, end: f32, text: &str| TranscriptionSegment { end_time: end, ..segment(start, text) };
+        // Chunk 1 covers 0-600s and cuts "zwei" off at its end; chunk 2 starts at 595s
+        let first = vec![
+            timed(580.0, 590.0, "Der Patient berichtet über Schmerzen"),
+            timed(590.0, 600.0, "im linken Knie seit zw"),
+        ];
+        let second = vec![
+            timed(1.0, 6.0, "Knie seit zwei Jahren."),
+            timed(6.0, 10.0, "Keine Vorerkrankungen."),
+        ];
+
+        let mut stitched = Vec::new();
+        stitch_chunk_segments(&mut stitched, first, 0.0, 0.0);
+        stitch_chunk_segments(&mut stitched, offset_segments(second, 595.0), 595.0, 600.0);
+
+        let text = stitched.iter().map(|s| s.text.as_str()).collect::<Vec<_>>().join(" ");
+        assert_eq!(text, "Der Patient berichtet über Schmerzen im linken Knie seit zwei Jahren. Keine Vorerkrankungen.");
+        assert_eq!(stitched[2].start_time, 600.0);
+        assert_eq!(stitched[3].start_time, 601.0);
+    }
+
+    #[test]
+    fn test_model_comparison_labels_results_and_reuses_cache() {
+        let cache_dir = std::env::temp_dir().join(format!("whisper_compare_{}", uuid::Uuid::new_v4().simple()));
+        let models = vec!["medium".to_string(), "large".to_string()];
+        let mut runs = Vec::new();
+        let mut transcribe = |model: &str| {
+            runs.push(model.to_string());
+            Ok(WhisperTranscriptionResult {
+                text: format!("Befund ({})", model),
+                confidence: 0.9,
+                segments: vec![segment(0.0, &format!("Befund ({})", model))],
+                partial: false,
+                language: "de".to_string(),
+                detected_language: Some("de".to_string()),
+                language_probabilities: Vec::new(),
+            })
+        };
+
+        let entries = compare_models_with("abc123", &models, "de", &cache_dir, &mut transcribe).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].model.as_str(), entries[0].result.text.as_str()), ("medium", "Befund (medium)"));
+        assert_eq!((entries[1].model.as_str(), entries[1].result.text.as_str()), ("large", "Befund (large)"));
+        assert!(entries.iter().all(|e| !e.cached));
+
+        // Same file again: both models come from the cache
+        let again = compare_models_with("abc123", &models, "de", &cache_dir, &mut transcribe).unwrap();
+        assert!(again.iter().all(|e| e.cached));
+        assert_eq!(again[1].result.text, "Befund (large)");
+
+        // Another expected language is transcribed again, not taken from the "de" cache
+        let english = compare_models_with("abc123", &models[..1], "en", &cache_dir, &mut transcribe).unwrap();
+        assert!(!english[0].cached);
+        assert_eq!(runs, vec!["medium", "large", "medium"]);
+
+        let _ = fs::remove_dir_all(&cache_dir);
+    }
+
+    #[test]
+    fn test_conversion_output_goes_to_configured_temp_dir() {
+        use crate::commands::temp_file_commands::{temp_dir_from, TempDirSettings};
+
+        let configured = std::env::temp_dir().join(format!("gutachten_temp_{}", uuid::Uuid::new_v4().simple())).join("audio");
+        let settings = TempDirSettings { temp_dir: Some(configured.to_string_lossy().to_string()) };
+        let dir = temp_dir_from(&settings);
+        assert_eq!(dir, configured);
+        assert!(configured.is_dir());
+
+        let output = converted_wav_path(&dir, Some("diktat".to_string()));
+        assert_eq!(output.parent(), Some(configured.as_path()));
+        assert!(output.file_name().unwrap().to_string_lossy().starts_with("diktat_"));
+
+        assert_eq!(temp_dir_from(&TempDirSettings::default()), std::env::temp_dir());
+        let _ = fs::remove_dir_all(configured.parent().unwrap());
+    }
+
+    #[test]
+    fn test_crash_after_two_segments_returns_them_as_partial() {
+        let stdout = concat!(
+            r#"{"type": "segment", "start_time": 0.0, "end_time": 4.5, "text": "Der Patient berichtet", "confidence": 0.0}"#, "\n",
+            r#"{"type": "segment", "start_time": 4.5, "end_time": 9.0, "text": "über Knieschmerzen.", "confidence": 0.0}"#, "\n",
+            r#"{"type": "segment", "start_time": 9.0, "end_ti"#,
+        );
+        let result = parse_whisper_output(stdout, false, "RuntimeError: CUDA error: an illegal memory access").unwrap();
+        assert!(result.partial);
+        assert_eq!(result.segments.len(), 2);
+        assert_eq!(result.text, "Der Patient berichtet über Knieschmerzen.");
+
+        let complete = format!("{}{}\n", &stdout[..stdout.rfind('{').unwrap()],
+            r#"{"type": "result", "text": "Befund", "confidence": 0.95, "segments": [{"start_time": 0.0, "end_time": 1.0, "text": "Befund", "confidence": 0.8}]}"#);
+        let result = parse_whisper_output(&complete, true, "").unwrap();
+        assert!(!result.partial);
+        assert_eq!((result.text.as_str(), result.segments.len()), ("Befund", 1));
+
+        // Older scripts print only the (indented) result; a crash before any segment stays an error
+        assert_eq!(parse_whisper_output("{\n  \"text\": \"Befund\",\n  \"segments\": []\n}", true, "").unwrap().text, "Befund");
+        assert!(parse_whisper_output("", false, "Traceback: boom").err().unwrap().contains("boom"));
+    }
+    #[test]
+    fn test_confident_other_language_is_a_mismatch() {
+        let stdout = r#"{"type": "result", "text": "The patient", "language": "de", "segments": [], "language_probabilities": [{"language": "de", "probability": 0.03}, {"language": "en", "probability": 0.95}]}"#;
+        let whisper = parse_whisper_output(stdout, true, "").unwrap();
+        assert_eq!(whisper.detected_language.as_deref(), Some("en"));
+
+        let mut result = result_with(Vec::new());
+        result.language_probabilities = whisper.language_probabilities;
+        let settings = TranscriptionLanguageSettings::default();
+        assert_eq!(language_mismatch(&result, &settings), Some(LanguageMismatch {
+            expected_language: "de".to_string(),
+            detected_language: "en".to_string(),
+            probability: 0.95,
+        }));
+
+        // Unsure detection or the expected language: no warning
+        result.language_probabilities[0].probability = 0.6;
+        assert_eq!(language_mismatch(&result, &settings), None);
+        result.language_probabilities = vec![LanguageProbability { language: "de".to_string(), probability: 0.99 }];
+        assert_eq!(language_mismatch(&result, &settings), None);
+    }
+}
@@ -0,0 +1,229 @@
+// Autosave commands - Debounced, crash-safe copies of in-progress editor content
+// under user-data/sessions/<session_id>/autosave/<section>/
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::fs;
+
+use crate::commands::session_commands::{check_section_name, get_session_dir, load_session_structured_content, load_session_transcription};
+use crate::cloud_sync::{rename_with_retry, write_with_retry};
+
+/// One autosaved revision of a section's editor content
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AutosavedContent {
+    pub session_id: String,
+    pub section: String,
+    pub revision: u64,
+    pub content: String,
+    pub saved_at: String,
+}
+
+const AUTOSAVE_DEBOUNCE_MS: u64 = 2000;
+const MAX_AUTOSAVE_REVISIONS: usize = 10;
+
+/// Autosaves waiting for the debounce delay, keyed by (session, section); only the latest is kept
+static PENDING_AUTOSAVES: Lazy<Mutex<HashMap<(String, String), AutosavedContent>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn get_autosave_section_dir(session_id: &str, section: &str) -> Result<PathBuf, String> {
+    check_section_name(section)?;
+    Ok(get_session_dir(session_id)?.join("autosave").join(section))
+}
+
+/// Keep an autosave for the debounce delay; an older revision never replaces a newer one
+fn queue_autosave(entry: AutosavedContent) -> Result<bool, String> {
+    let mut pending = PENDING_AUTOSAVES.lock()
+        .map_err(|e| format!("Failed to acquire autosave lock: {}", e))?;
+    let key = (entry.session_id.clone(), entry.section.clone());
+    if pending.get(&key).is_some_and(|queued| queued.revision > entry.revision) {
+        return Ok(false);
+    }
+    pending.insert(key, entry);
+    Ok(true)
+}
+
+/// Write the pending autosaves of a session (all sections, or one section if its revision still matches)
+fn flush_pending_autosaves(session_id: &str, only: Option<(&str, u64)>) -> Result<(), String> {
+    let entries: Vec<AutosavedContent> = {
+        let mut pending = PENDING_AUTOSAVES.lock()
+            .map_err(|e| format!("Failed to acquire autosave lock: {}", e))?;
+        let keys: Vec<(String, String)> = pending.iter()
+            .filter(|((session, section), entry)| session == session_id && match only {
+                Some((only_section, revision)) => section == only_section && entry.revision == revision,
+                None => true,
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.iter().filter_map(|key| pending.remove(key)).collect()
+    };
+
+    for entry in &entries {
+        write_autosave(entry)?;
+    }
+    Ok(())
+}
+
+/// Write one revision (temp file + rename, so a crash never leaves a truncated autosave)
+/// and prune the section to the newest MAX_AUTOSAVE_REVISIONS files
+fn write_autosave(entry: &AutosavedContent) -> Result<(), String> {
+    let dir = get_autosave_section_dir(&entry.session_id, &entry.section)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create autosave directory: {}", e))?;
+
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S%3f");
+    let path = dir.join(format!("{}_r{}.json", timestamp, entry.revision));
+    let temp_path = path.with_extension("tmp");
+    let json = serde_json::to_string_pretty(entry)
+        .map_err(|e| format!("Failed to serialize autosave: {}", e))?;
+    write_with_retry(&temp_path, json)
+        .map_err(|e| format!("Failed to write autosave: {}", e))?;
+    rename_with_retry(&temp_path, &path)
+        .map_err(|e| format!("Failed to store autosave: {}", e))?;
+
+    let files = list_autosave_files(&dir)?;
+    if files.len() > MAX_AUTOSAVE_REVISIONS {
+        for old in &files[..files.len() - MAX_AUTOSAVE_REVISIONS] {
+            let _ = fs::remove_file(old);
+        }
+    }
+    Ok(())
+}
+
+/// Autosave files of a section, oldest first (names start with the timestamp)
+fn list_autosave_files(dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read autosave directory: {}", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Time of the last explicit save of the session (structured content or transcript)
+fn last_explicit_save(session_id: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    let structured = load_session_structured_content(session_id).ok().map(|s| s.updated_at);
+    let transcription = load_session_transcription(session_id).ok().map(|t| t.updated_at);
+    [structured, transcription].into_iter()
+        .flatten()
+        .filter_map(|time| chrono::DateTime::parse_from_rfc3339(&time).ok())
+        .max()
+}
+
+/// Latest autosave of every section that is newer than the last explicit save
+fn recoverable_autosaves(session_id: &str) -> Result<Vec<AutosavedContent>, String> {
+    let autosave_dir = get_session_dir(session_id)?.join("autosave");
+    if !autosave_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let saved_at = last_explicit_save(session_id);
+
+    let mut section_dirs: Vec<PathBuf> = fs::read_dir(&autosave_dir)
+        .map_err(|e| format!("Failed to read autosave directory: {}", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    section_dirs.sort();
+
+    let mut recoverable = Vec::new();
+    for dir in section_dirs {
+        let latest = list_autosave_files(&dir)?.pop()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str::<AutosavedContent>(&content).ok());
+        let Some(latest) = latest else { continue };
+
+        let newer = match (saved_at, chrono::DateTime::parse_from_rfc3339(&latest.saved_at).ok()) {
+            (Some(saved), Some(autosaved)) => autosaved > saved,
+            _ => true,
+        };
+        if newer {
+            recoverable.push(latest);
+        }
+    }
+    Ok(recoverable)
+}
+
+/// Autosave the editor content of a section. Writes are debounced: only the latest
+/// revision per section within the delay reaches the disk.
+#[command]
+pub async fn autosave_editor_content(
+    session_id: String,
+    section: String,
+    content: String,
+    revision: u64,
+) -> Result<bool, String> {
+    get_autosave_section_dir(&session_id, &section)?;
+
+    let queued = queue_autosave(AutosavedContent {
+        session_id: session_id.clone(),
+        section: section.clone(),
+        revision,
+        content,
+        saved_at: chrono::Utc::now().to_rfc3339(),
+    })?;
+    if !queued {
+        return Ok(false);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(AUTOSAVE_DEBOUNCE_MS)).await;
+        // A newer revision in the meantime has its own timer and replaced this entry
+        if let Err(e) = flush_pending_autosaves(&session_id, Some((&section, revision))) {
+            println!("Autosave failed for {}/{}: {}", session_id, section, e);
+        }
+    });
+    Ok(true)
+}
+
+/// Autosaved content newer than the last explicit save, for crash recovery
+#[command]
+pub async fn get_autosaved_content(session_id: String) -> Result<Vec<AutosavedContent>, String> {
+    flush_pending_autosaves(&session_id, None)?;
+    recoverable_autosaves(&session_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(session_id: &str, revision: u64) -> AutosavedContent {
+        AutosavedContent {
+            session_id: session_id.to_string(),
+            section: "befund".to_string(),
+            revision,
+            content: format!("Befund Revision {}", revision),
+            saved_at: chrono::Utc::now().to_rfc3339(),
+        }
+    }
+
+    #[test]
+    fn test_autosave_keeps_latest_revision_and_prunes_old_ones() {
+        let session_id = format!("autosave-test-{}", std::process::id());
+
+        // Debounced: only the latest of several quick edits is written, stale ones are refused
+        assert!(queue_autosave(entry(&session_id, 1)).unwrap());
+        assert!(queue_autosave(entry(&session_id, 3)).unwrap());
+        assert!(!queue_autosave(entry(&session_id, 2)).unwrap());
+        flush_pending_autosaves(&session_id, Some(("befund", 1))).unwrap();
+        let dir = get_autosave_section_dir(&session_id, "befund").unwrap();
+        assert!(!dir.exists());
+        flush_pending_autosaves(&session_id, None).unwrap();
+        assert_eq!(list_autosave_files(&dir).unwrap().len(), 1);
+
+        for revision in 4..20 {
+            write_autosave(&entry(&session_id, revision)).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let files = list_autosave_files(&dir).unwrap();
+        let recoverable = recoverable_autosaves(&session_id).unwrap();
+        let _ = fs::remove_dir_all(get_session_dir(&session_id).unwrap());
+
+        assert_eq!(files.len(), MAX_AUTOSAVE_REVISIONS);
+        assert_eq!(recoverable.len(), 1);
+        assert_eq!(recoverable[0].revision, 19);
+        assert_eq!(recoverable[0].content, "Befund Revision 19");
+    }
+}
@@ -0,0 +1,169 @@
+// Backup commands - Timestamped copies of DOCX files taken before a command overwrites them
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::fs;
+
+/// Backups kept per document; the oldest are removed first
+pub const MAX_DOCX_BACKUPS: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DocxBackup {
+    pub timestamp: String,  // "%Y%m%d_%H%M%S%3f", also the file stem
+    pub backup_path: String,
+    pub size_bytes: u64,
+}
+
+fn user_data_dir() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data"))
+}
+
+/// Absolute form of the path; the parent is canonicalized so the file itself need not exist
+fn normalize_path(path: &Path) -> PathBuf {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    match (fs::canonicalize(&parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Files inside user-data keep their backups next to them, all others under user-data/backups/<path hash>
+fn backup_dir_in(user_data: &Path, docx_path: &Path) -> PathBuf {
+    let normalized = normalize_path(docx_path);
+    let user_data = fs::canonicalize(user_data).unwrap_or_else(|_| user_data.to_path_buf());
+    match (normalized.starts_with(&user_data), normalized.parent(), normalized.file_name()) {
+        (true, Some(parent), Some(name)) => parent.join(".backups").join(name),
+        _ => {
+            let key = format!("{:x}", Sha256::digest(normalized.to_string_lossy().as_bytes()));
+            user_data.join("backups").join(&key[..16])
+        }
+    }
+}
+
+fn list_backups_in(backup_dir: &Path) -> Vec<DocxBackup> {
+    let Ok(entries) = fs::read_dir(backup_dir) else { return Vec::new() };
+    let mut backups: Vec<DocxBackup> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().extension().and_then(|x| x.to_str()) == Some("docx"))
+        .filter_map(|e| Some(DocxBackup {
+            timestamp: e.path().file_stem()?.to_string_lossy().to_string(),
+            backup_path: e.path().to_string_lossy().to_string(),
+            size_bytes: e.metadata().ok()?.len(),
+        }))
+        .collect();
+    backups.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    backups
+}
+
+fn backup_in(user_data: &Path, docx_path: &Path, max_backups: usize) -> Result<Option<DocxBackup>, String> {
+    if !docx_path.is_file() {
+        return Ok(None);
+    }
+
+    let backup_dir = backup_dir_in(user_data, docx_path);
+    fs::create_dir_all(&backup_dir)
+        .map_err(|e| format!("Failed to create backup directory: {}", e))?;
+
+    let mut timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S%3f").to_string();
+    while backup_dir.join(format!("{}.docx", timestamp)).exists() {
+        timestamp.push('_');
+    }
+    let backup_path = backup_dir.join(format!("{}.docx", timestamp));
+    let size_bytes = fs::copy(docx_path, &backup_path)
+        .map_err(|e| format!("Failed to back up document: {}", e))?;
+
+    let backups = list_backups_in(&backup_dir);
+    if backups.len() > max_backups {
+        for old in &backups[..backups.len() - max_backups] {
+            let _ = fs::remove_file(&old.backup_path);
+        }
+    }
+
+    Ok(Some(DocxBackup {
+        timestamp,
+        backup_path: backup_path.to_string_lossy().to_string(),
+        size_bytes,
+    }))
+}
+
+/// Back up a DOCX before it is overwritten in place; does nothing if the file does not exist yet
+pub fn backup_before_overwrite(docx_path: &Path) -> Result<Option<DocxBackup>, String> {
+    let backup = backup_in(&user_data_dir()?, docx_path, MAX_DOCX_BACKUPS)?;
+    if let Some(backup) = &backup {
+        println!("💾 Backup created: {}", backup.backup_path);
+    }
+    Ok(backup)
+}
+
+fn restore_in(user_data: &Path, docx_path: &Path, timestamp: &str) -> Result<DocxBackup, String> {
+    let backup = list_backups_in(&backup_dir_in(user_data, docx_path))
+        .into_iter()
+        .find(|b| b.timestamp == timestamp)
+        .ok_or_else(|| format!("Keine Sicherung vom {} für dieses Dokument gefunden", timestamp))?;
+
+    // Read first: backing up the current state may prune the backup being restored
+    let content = fs::read(&backup.backup_path)
+        .map_err(|e| format!("Failed to read backup: {}", e))?;
+    // The current state becomes a backup of its own, so a restore can be undone
+    backup_in(user_data, docx_path, MAX_DOCX_BACKUPS)?;
+    fs::write(docx_path, content)
+        .map_err(|e| format!("Failed to restore backup: {}", e))?;
+    Ok(backup)
+}
+
+/// Backups of a document, oldest first
+#[command]
+pub async fn list_docx_backups(path: String) -> Result<Vec<DocxBackup>, String> {
+    Ok(list_backups_in(&backup_dir_in(&user_data_dir()?, Path::new(&path))))
+}
+
+/// Put a backup back in place of the document
+#[command]
+pub async fn restore_docx_backup(path: String, timestamp: String) -> Result<DocxBackup, String> {
+    let backup = restore_in(&user_data_dir()?, Path::new(&path), &timestamp)?;
+    println!("♻️ Restored {} from backup {}", path, backup.timestamp);
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backups_are_capped_and_restorable() {
+        let root = std::env::temp_dir().join(format!("docx_backups_{}", uuid::Uuid::new_v4().simple()));
+        let user_data = root.join("user-data");
+        let outside = root.join("extern");
+        fs::create_dir_all(user_data.join("exports")).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+
+        let inside_doc = user_data.join("exports").join("gutachten.docx");
+        fs::write(&inside_doc, b"v0").unwrap();
+        let inside_dir = backup_dir_in(&user_data, &inside_doc);
+        assert!(inside_dir.ends_with(Path::new("exports/.backups/gutachten.docx")));
+
+        let outside_doc = outside.join("brief.docx");
+        assert!(backup_in(&user_data, &outside_doc, 3).unwrap().is_none());
+        fs::write(&outside_doc, b"v0").unwrap();
+        for version in 1..=4 {
+            backup_in(&user_data, &outside_doc, 3).unwrap();
+            fs::write(&outside_doc, format!("v{}", version)).unwrap();
+        }
+        let backups = list_backups_in(&backup_dir_in(&user_data, &outside_doc));
+        assert_eq!(backups.len(), 3);
+        assert!(backups[0].backup_path.contains("backups"));
+        assert_eq!(fs::read(&backups[0].backup_path).unwrap(), b"v1");
+
+        restore_in(&user_data, &outside_doc, &backups[0].timestamp).unwrap();
+        assert_eq!(fs::read(&outside_doc).unwrap(), b"v1");
+        assert!(restore_in(&user_data, &outside_doc, "19990101_000000000").unwrap_err().contains("Keine Sicherung"));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
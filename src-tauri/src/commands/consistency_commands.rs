@@ -0,0 +1,219 @@
+// Consistency commands - Cross-checks the active TemplateSpec against the StyleProfile
+// (sections vs anchors, style roles vs formatting); both are produced by different tools and drift apart
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::style_profile_commands::{get_template_path, read_style_profile, StyleProfile};
+use crate::commands::template_commands::{load_family_spec, resolve_style_roles_for_docx, RoleResolution};
+use crate::heading_normalizer::canonical_heading;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ConsistencyIssue {
+    pub kind: String,  // "missing_in_profile", "missing_in_spec", "section_order", "font_family", "font_size" or "formatting_unchecked"
+    pub message: String,
+    pub suggestion: String,
+}
+
+/// Mismatches grouped by severity; errors break rendering or structuring, warnings degrade it
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConsistencyReport {
+    pub consistent: bool,  // No errors and no warnings
+    pub errors: Vec<ConsistencyIssue>,
+    pub warnings: Vec<ConsistencyIssue>,
+    pub infos: Vec<ConsistencyIssue>,
+}
+
+fn issue(kind: &str, message: String, suggestion: &str) -> ConsistencyIssue {
+    ConsistencyIssue {
+        kind: kind.to_string(),
+        message,
+        suggestion: suggestion.to_string(),
+    }
+}
+
+/// Spec anchors as (canonical text, normalized name), in spec order
+fn spec_anchors(spec: &Value) -> Vec<(String, String)> {
+    spec.get("anchors")
+        .and_then(|a| a.as_array())
+        .map(|anchors| anchors.iter()
+            .filter_map(|a| a.get("canonical_text").and_then(|t| t.as_str()))
+            .map(|text| (text.to_string(), canonical_heading(text)))
+            .collect())
+        .unwrap_or_default()
+}
+
+/// Compare anchors with profile sections (name-normalized) and the spec's BODY style with the
+/// profile formatting. `roles` are the style roles resolved in the base DOCX, if available.
+pub fn compare_spec_and_profile(spec: &Value, profile: &StyleProfile, roles: Option<&[RoleResolution]>) -> ConsistencyReport {
+    let mut report = ConsistencyReport::default();
+    let anchors = spec_anchors(spec);
+    let section_names = |normalized: &str, display: &str| [canonical_heading(normalized), canonical_heading(display)];
+
+    for (text, normalized) in &anchors {
+        let in_profile = profile.sections.iter()
+            .any(|s| section_names(&s.normalized_name, &s.display_name).contains(normalized));
+        if !in_profile {
+            report.warnings.push(issue(
+                "missing_in_profile",
+                format!("Anker '{}' fehlt im StyleProfile", text),
+                "Beispieldokumente mit diesem Abschnitt neu analysieren oder den Anker aus der Vorlage entfernen",
+            ));
+        }
+    }
+
+    let anchor_position = |names: &[String; 2]| anchors.iter().position(|(_, n)| names.contains(n));
+    let mut sections: Vec<_> = profile.sections.iter().collect();
+    sections.sort_by_key(|s| s.order);
+    let mut last_required: Option<(usize, &str)> = None;
+    for section in sections {
+        let names = section_names(&section.normalized_name, &section.display_name);
+        match (anchor_position(&names), section.is_required) {
+            (None, true) => report.errors.push(issue(
+                "missing_in_spec",
+                format!("Pflichtabschnitt '{}' des StyleProfiles hat keinen Anker in der Vorlage", section.display_name),
+                "Anker und Slot in der Vorlage ergänzen (add_skeleton_item) oder einen Anker umbenennen (rename_anchor)",
+            )),
+            (None, false) => report.infos.push(issue(
+                "missing_in_spec",
+                format!("Optionaler Abschnitt '{}' ist nicht Teil der Vorlage", section.display_name),
+                "Nur nötig, wenn der Abschnitt im Gutachten erscheinen soll",
+            )),
+            (Some(position), true) => {
+                if let Some((previous_position, previous)) = last_required {
+                    if position < previous_position {
+                        report.warnings.push(issue(
+                            "section_order",
+                            format!("'{}' steht in der Vorlage vor '{}', im StyleProfile danach", section.display_name, previous),
+                            "Reihenfolge im Vorlagen-Skelett anpassen (move_skeleton_item)",
+                        ));
+                    }
+                }
+                last_required = Some((position, &section.display_name));
+            }
+            (Some(_), false) => {}
+        }
+    }
+
+    let body = roles.and_then(|roles| roles.iter().find(|r| r.role == "BODY" && r.found));
+    match body {
+        None => report.infos.push(issue(
+            "formatting_unchecked",
+            "Formatierung nicht geprüft: BODY-Stil der Vorlage nicht auflösbar".to_string(),
+            "Vorlagen-DOCX erzeugen oder BODY in den style_roles der Vorlage setzen",
+        )),
+        Some(body) => {
+            if let Some(font) = &body.effective_font {
+                if !font.eq_ignore_ascii_case(&profile.formatting.font_family) {
+                    report.warnings.push(issue(
+                        "font_family",
+                        format!("Schriftart der Vorlage ({}) weicht vom StyleProfile ab ({})", font, profile.formatting.font_family),
+                        "Stil 'Normal' in der Vorlagen-DOCX anpassen oder die Beispiele neu analysieren",
+                    ));
+                }
+            }
+            if let Some(size) = body.effective_size {
+                if (size - profile.formatting.font_size_pt).abs() > 0.5 {
+                    report.warnings.push(issue(
+                        "font_size",
+                        format!("Schriftgröße der Vorlage ({}pt) weicht vom StyleProfile ab ({}pt)", size, profile.formatting.font_size_pt),
+                        "Schriftgröße des Stils 'Normal' in der Vorlagen-DOCX anpassen",
+                    ));
+                }
+            }
+        }
+    }
+
+    report.consistent = report.errors.is_empty() && report.warnings.is_empty();
+    report
+}
+
+/// Check the given spec (or the active family's) against the StyleProfile; style roles are
+/// resolved in the profile's template DOCX when it exists
+pub(crate) fn check_consistency(spec: Option<Value>) -> Result<ConsistencyReport, String> {
+    let spec = match spec {
+        Some(spec) => spec,
+        None => load_family_spec(None)?,
+    };
+    let profile = read_style_profile()?;
+    let template_path = get_template_path()?;
+    let roles = if template_path.exists() {
+        resolve_style_roles_for_docx(&spec, &template_path).ok()
+    } else {
+        None
+    };
+    Ok(compare_spec_and_profile(&spec, &profile, roles.as_deref()))
+}
+
+/// Messages of known inconsistencies for the render and prompt paths; empty if spec or profile is missing
+pub(crate) fn consistency_warnings(spec: Option<Value>) -> Vec<String> {
+    match check_consistency(spec) {
+        Ok(report) => report.errors.iter().chain(&report.warnings)
+            .map(|i| format!("Vorlage/StyleProfile: {}", i.message))
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Cross-reference the active template spec with the StyleProfile
+#[command]
+pub async fn check_spec_profile_consistency(family_id: Option<String>) -> Result<ConsistencyReport, String> {
+    let spec = load_family_spec(family_id)?;
+    let report = check_consistency(Some(spec))?;
+    println!("[RUST] Spec/profile consistency: {} errors, {} warnings", report.errors.len(), report.warnings.len());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::style_profile_commands::{FormattingInfo, SectionInfo};
+
+    fn section(name: &str, required: bool, order: i32) -> SectionInfo {
+        SectionInfo {
+            normalized_name: name.to_lowercase(),
+            display_name: name.to_string(),
+            is_required: required,
+            occurrence_count: 3,
+            occurrence_percentage: 100.0,
+            order,
+        }
+    }
+
+    #[test]
+    fn test_mismatches_grouped_by_severity() {
+        let profile = StyleProfile {
+            version: "1.0".to_string(),
+            created_at: String::new(),
+            analyzed_documents: 3,
+            source_files: Vec::new(),
+            sections: vec![section("Anamnese", true, 1), section("Befund", true, 2), section("Beurteilung", true, 3), section("Anlagen", false, 4)],
+            formatting: FormattingInfo { font_family: "Arial".to_string(), font_size_pt: 11.0, line_spacing: 1.15, quote: Default::default() },
+        };
+        let spec = serde_json::json!({"anchors": [
+            {"id": "befund", "canonical_text": "2. Befund:"},
+            {"id": "anamnese", "canonical_text": "1. Anamnese"},
+            {"id": "diagnosen", "canonical_text": "DIAGNOSEN"},
+        ]});
+        let roles = vec![RoleResolution {
+            role: "BODY".to_string(),
+            style_name: "Normal".to_string(),
+            style_id: Some("Normal".to_string()),
+            found: true,
+            effective_font: Some("Times New Roman".to_string()),
+            effective_size: Some(11.0),
+        }];
+
+        let report = compare_spec_and_profile(&spec, &profile, Some(&roles));
+        assert!(!report.consistent);
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.errors[0].message.contains("'Beurteilung'"));
+        let warnings: Vec<&str> = report.warnings.iter().map(|w| w.kind.as_str()).collect();
+        assert_eq!(warnings, vec!["missing_in_profile", "section_order", "font_family"]);
+        assert_eq!(report.warnings[0].message, "Anker 'DIAGNOSEN' fehlt im StyleProfile");
+        assert_eq!(report.infos.len(), 1);
+
+        let unresolved = compare_spec_and_profile(&spec, &profile, None);
+        assert_eq!(unresolved.infos.last().unwrap().kind, "formatting_unchecked");
+    }
+}
@@ -3,11 +3,20 @@
 
 use tauri::{command, Window, Emitter};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
 use zip::ZipArchive;
 use std::io::{Read, BufReader};
 use regex::Regex;
+use std::collections::{BTreeSet, HashMap};
+use crate::commands::style_profile_commands::FormattingInfo;
+use crate::commands::format_commands::sha256_file;
+use crate::color_utils::{low_contrast_warning, resolve_color_element, ThemeColors};
+use crate::analysis_trace::AnalysisTrace;
+use crate::cloud_sync::ensure_local;
+use crate::heading_normalizer::{canonical_heading, same_heading};
+use crate::template_schema;
+use crate::docx_patterns::*;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DocumentStyleInfo {
@@ -25,6 +34,52 @@ pub struct DocumentStyleInfo {
     pub header_footer_info: HeaderFooterInfo,
     pub style_summary: String,
     pub headers_found: Vec<String>,  // Actual header text content found in document
+    #[serde(default)]
+    pub indentation: IndentationInfo,
+    #[serde(default)]
+    pub alignments: RoleAlignments,
+    #[serde(default = "default_style_source")]
+    pub source: String,  // "docx" or "pdf"
+    #[serde(default = "default_style_confidence")]
+    pub confidence: f32,  // Lower for styles estimated from a PDF
+    #[serde(default)]
+    pub page_break_count: usize,     // Explicit <w:br w:type="page"/> breaks
+    #[serde(default)]
+    pub section_break_count: usize,  // Paragraph-level section breaks (the final sectPr is not a break)
+    #[serde(default)]
+    pub used_heading_levels: Vec<u8>,  // Heading levels body paragraphs use, not just those styles.xml defines
+    #[serde(default)]
+    pub heading_level_gaps: Vec<String>,  // Skipped levels, e.g. H1 followed directly by H3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace: Option<AnalysisTrace>,  // Decisions of the analysis; returned only on request, always cached
+}
+
+fn default_style_source() -> String {
+    "docx".to_string()
+}
+
+fn default_style_confidence() -> f32 {
+    1.0
+}
+
+/// Paragraph alignment per content role ("left", "center", "right", "justify")
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RoleAlignments {
+    pub body: String,
+    pub heading: String,
+    pub header: String,
+    pub footer: String,
+}
+
+impl Default for RoleAlignments {
+    fn default() -> Self {
+        Self {
+            body: "left".to_string(),
+            heading: "left".to_string(),
+            header: "left".to_string(),
+            footer: "left".to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -46,6 +101,17 @@ pub struct PageMargins {
     pub right: f32,
 }
 
+/// Paragraph indentation and tab stops (all values in cm)
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct IndentationInfo {
+    pub left_indent: f32,
+    pub first_line_indent: f32,
+    pub hanging_indent: f32,
+    pub tab_stops: Vec<f32>,
+    pub indented_paragraphs: usize,
+    pub total_paragraphs: usize,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct HeaderFooterInfo {
     pub has_header: bool,
@@ -54,9 +120,27 @@ pub struct HeaderFooterInfo {
     pub footer_content: String,
     pub header_style: Option<HeaderFooterStyle>,
     pub footer_style: Option<HeaderFooterStyle>,
+    #[serde(default)]
+    pub header_candidates: Vec<HeaderCandidate>,  // Every header part, so the user can pick another one
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+/// A header part of the DOCX and whether the analysis took its content and style
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeaderCandidate {
+    pub part_name: String,        // e.g. "word/header2.xml"
+    pub content_preview: String,
+    pub priority: u8,             // 1 = word/header1.xml; lower wins
+    pub chosen: bool,
+    #[serde(default)]
+    pub content: String,          // Full header text, for exporting with this header
+    #[serde(default)]
+    pub style: Option<HeaderFooterStyle>,  // None for empty header parts
+}
+
+/// Characters of header text shown per candidate
+const HEADER_PREVIEW_CHARS: usize = 80;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct HeaderFooterStyle {
     pub font_family: String,
     pub font_size: f32,
@@ -73,11 +157,13 @@ pub struct DocumentAnalysisProgress {
     pub document_id: String,
 }
 
-/// Analyze a DOCX document to extract style and formatting information
+/// Analyze a DOCX document to extract style and formatting information;
+/// `include_trace` attaches the analyzer's decisions (see `get_analysis_trace`)
 #[command]
 pub async fn analyze_document_style(
     file_path: String,
     document_id: String,
+    include_trace: Option<bool>,
     window: Window,
 ) -> Result<DocumentStyleInfo, String> {
     // Validate input
@@ -100,6 +186,9 @@ pub async fn analyze_document_style(
         return Err(format!("Unsupported document format: {}. Only .docx and .doc files are supported.", extension));
     }
 
+    // An empty ID means: derive it from the file contents
+    let document_id = if document_id.is_empty() { document_id_for(&path)? } else { document_id };
+
     // Start analysis process
     window.emit("document_analysis_progress", DocumentAnalysisProgress {
         progress: 0.0,
@@ -115,12 +204,16 @@ pub async fn analyze_document_style(
 
     // Analyze DOCX file
     let document_id_clone = document_id.clone();
-    let analysis_result = tokio::task::spawn_blocking(move || {
-        analyze_docx_file(&path, &document_id_clone)
+    let (mut analysis_result, from_cache) = tokio::task::spawn_blocking(move || {
+        analyze_docx_file_reporting_hit(&path, &document_id_clone)
     }).await.map_err(|e| format!("Analysis task failed: {}", e))??;
+    if !include_trace.unwrap_or(false) {
+        analysis_result.trace = None;
+    }
 
-    // Emit progress updates during analysis
-    for progress in [20.0, 40.0, 60.0, 80.0] {
+    // Emit progress updates during analysis (a cached result is reported as done right away)
+    let steps: &[f32] = if from_cache { &[] } else { &[20.0, 40.0, 60.0, 80.0] };
+    for &progress in steps {
         window.emit("document_analysis_progress", DocumentAnalysisProgress {
             progress,
             stage: "analyzing".to_string(),
@@ -142,12 +235,101 @@ pub async fn analyze_document_style(
     Ok(analysis_result)
 }
 
+/// Extract only the indentation and tab-stop settings of a DOCX document
+#[command]
+pub async fn extract_document_indentation(file_path: String) -> Result<IndentationInfo, String> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(format!("Document file not found: {}", file_path));
+    }
+
+    tokio::task::spawn_blocking(move || {
+        let file = fs::File::open(&path)
+            .map_err(|e| format!("Failed to open DOCX file: {}", e))?;
+        let mut archive = ZipArchive::new(BufReader::new(file))
+            .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+        let part = archive.by_name("word/document.xml")
+            .map_err(|_| "document.xml not found in DOCX file".to_string())?;
+        let declared_size = part.size();
+        let mut indentation = IndentationScan::default();
+        for chunk in XmlChunks::new(part, declared_size, MAX_DOCX_PART_BYTES) {
+            for paragraph in parse_paragraphs(&chunk?) {
+                indentation.add(&paragraph);
+            }
+        }
+        Ok(indentation.finish(&mut AnalysisTrace::default()))
+    }).await.map_err(|e| format!("Analysis task failed: {}", e))?
+}
+
+/// Get the dominant font, size and line spacing across several documents
+/// (a lightweight alternative to building a full StyleProfile)
+#[command]
+pub async fn aggregate_formatting(paths: Vec<String>) -> Result<FormattingInfo, String> {
+    tokio::task::spawn_blocking(move || aggregate_formatting_from_files(&paths))
+        .await
+        .map_err(|e| format!("Analysis task failed: {}", e))?
+}
+
+/// Modal font, median size and median line spacing; unreadable files are skipped
+fn aggregate_formatting_from_files(paths: &[String]) -> Result<FormattingInfo, String> {
+    let infos: Vec<DocumentStyleInfo> = paths.iter()
+        .filter_map(|path| match analyze_docx_file(&PathBuf::from(path), "aggregate") {
+            Ok(info) => Some(info),
+            Err(e) => {
+                println!("⚠️ Skipping {}: {}", path, e);
+                None
+            }
+        })
+        .collect();
+
+    if infos.is_empty() {
+        return Err("None of the documents could be analyzed".to_string());
+    }
+
+    // Most frequent font; ties go to the font seen first
+    let mut font_counts: Vec<(String, usize)> = Vec::new();
+    for info in &infos {
+        match font_counts.iter_mut().find(|(font, _)| *font == info.font_family) {
+            Some((_, count)) => *count += 1,
+            None => font_counts.push((info.font_family.clone(), 1)),
+        }
+    }
+    let max_count = font_counts.iter().map(|(_, count)| *count).max().unwrap_or(0);
+    let font_family = font_counts.into_iter()
+        .find(|(_, count)| *count == max_count)
+        .map(|(font, _)| font)
+        .unwrap_or_else(|| "Times New Roman".to_string());
+
+    Ok(FormattingInfo {
+        font_family,
+        font_size_pt: median(infos.iter().map(|i| i.font_size).collect()),
+        line_spacing: median(infos.iter().map(|i| i.line_spacing).collect()),
+        quote: Default::default(),
+    })
+}
+
+fn median(mut values: Vec<f32>) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
 /// Save analyzed style information as a user template
 #[command]
 pub async fn save_style_template(
-    style_info: DocumentStyleInfo,
+    mut style_info: DocumentStyleInfo,
     template_name: String,
 ) -> Result<String, String> {
+    // The trace belongs to the analysis cache, not to the template
+    style_info.trace = None;
+
     // Create user-data directory if it doesn't exist
     let app_dir = std::env::current_dir()
         .map_err(|e| format!("Failed to get current directory: {}", e))?;
@@ -241,121 +423,727 @@ pub async fn get_saved_templates() -> Result<Vec<String>, String> {
     Ok(templates)
 }
 
-/// Internal function to analyze DOCX file structure
-fn analyze_docx_file(file_path: &PathBuf, document_id: &str) -> Result<DocumentStyleInfo, String> {
-    println!("🔍 Starting DOCX analysis for: {}", file_path.display());
+/// Load a template saved by `save_style_template`, by its file name from `get_saved_templates`
+pub(crate) fn load_style_template(template_name: &str) -> Result<DocumentStyleInfo, String> {
+    if template_name.is_empty() || template_name.contains(['/', '\\']) || template_name.contains("..") {
+        return Err(format!("Ungültiger Vorlagenname: {}", template_name));
+    }
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    let path = app_dir.join("user-data").join("templates").join(template_name);
+    let content = fs::read_to_string(&path)
+        .map_err(|_| format!("Vorlage nicht gefunden: {}", template_name))?;
+    let (style_info, report) = template_schema::parse_checked::<DocumentStyleInfo>(&content, template_schema::DOCUMENT_STYLE_INFO)
+        .map_err(|e| format!("Vorlage {} ist beschädigt: {}", template_name, e))?;
+    if !report.defaults_applied.is_empty() {
+        println!("Template {} migrated, defaults applied: {}", template_name, report.defaults_applied.join(", "));
+    }
+    Ok(style_info)
+}
+
+/// Check a template JSON (DocumentStyleInfo or StyleProfile) without loading it: every missing or
+/// mistyped field with its JSON path, the defaults older files get, and ignored unknown fields
+#[command]
+pub async fn validate_template_file(path: String) -> Result<TemplateValidation, String> {
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Datei konnte nicht gelesen werden: {}", e))?;
+    let value: serde_json::Value = serde_json::from_str(&content)
+        .map_err(|e| format!("Kein gültiges JSON: {}", e))?;
+    let validation = validate_template_value(&value);
+    println!("Validated {} as {}: {} issue(s), {} default(s)", path, validation.kind,
+        validation.report.issues.len(), validation.report.defaults_applied.len());
+    Ok(validation)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateValidation {
+    pub kind: String,  // "style_info" or "style_profile"
+    #[serde(flatten)]
+    pub report: template_schema::SchemaReport,
+}
+
+/// StyleProfiles are recognized by their `sections` list
+pub fn validate_template_value(value: &serde_json::Value) -> TemplateValidation {
+    if value.get("sections").is_some() {
+        TemplateValidation { kind: "style_profile".to_string(), report: template_schema::validate_json(value, template_schema::STYLE_PROFILE) }
+    } else {
+        TemplateValidation { kind: "style_info".to_string(), report: template_schema::validate_json(value, template_schema::DOCUMENT_STYLE_INFO) }
+    }
+}
+
+/// Header/footer (and page margins, which the letterhead is laid out for) from `header`;
+/// body font, spacing, headings and indentation from `body`
+pub fn compose_style_info(header: &DocumentStyleInfo, body: &DocumentStyleInfo) -> DocumentStyleInfo {
+    let mut composed = body.clone();
+    composed.document_id = format!("composed_{}", chrono::Utc::now().format("%Y%m%d_%H%M%S"));
+    composed.filename = format!("{} + {}", header.filename, body.filename);
+    composed.analysis_date = chrono::Utc::now().to_rfc3339();
+    composed.page_margins = header.page_margins.clone();
+    composed.header_footer_info = header.header_footer_info.clone();
+    composed.alignments.header = header.alignments.header.clone();
+    composed.alignments.footer = header.alignments.footer.clone();
+    composed.confidence = header.confidence.min(body.confidence);
+    composed.style_summary = format!(
+        "Kopf-/Fußzeile aus {}, Textkörper aus {} ({} {}pt, Zeilenabstand {})",
+        header.filename, body.filename, body.font_family, body.font_size, body.line_spacing
+    );
+    composed
+}
+
+/// Combine the letterhead of one saved template with the body style of another
+#[command]
+pub async fn compose_style(header_template: String, body_template: String) -> Result<DocumentStyleInfo, String> {
+    let header = load_style_template(&header_template)?;
+    let body = load_style_template(&body_template)?;
+    println!("Composing style: header from {}, body from {}", header_template, body_template);
+    Ok(compose_style_info(&header, &body))
+}
+
+/// Bump whenever the analysis output changes; cache entries of other versions are ignored and replaced
+pub const ANALYZER_VERSION: u32 = 9;
+
+/// Cached analysis of one file, keyed by its SHA-256
+#[derive(Debug, Serialize, Deserialize)]
+struct AnalysisCacheEntry {
+    analyzer_version: u32,
+    style_info: DocumentStyleInfo,
+    #[serde(default)]
+    trace: AnalysisTrace,
+}
+
+fn get_analysis_cache_dir() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("cache").join("analysis"))
+}
+
+/// The cached result with its trace attached
+fn read_cached_analysis(cache_dir: &Path, hash: &str) -> Option<DocumentStyleInfo> {
+    let content = fs::read_to_string(cache_dir.join(format!("{}.json", hash))).ok()?;
+    let entry: AnalysisCacheEntry = serde_json::from_str(&content).ok()?;
+    (entry.analyzer_version == ANALYZER_VERSION).then_some(DocumentStyleInfo { trace: Some(entry.trace), ..entry.style_info })
+}
+
+/// The trace is stored next to the result, not inside it
+fn write_cached_analysis(cache_dir: &Path, hash: &str, style_info: &DocumentStyleInfo) -> Result<(), String> {
+    fs::create_dir_all(cache_dir)
+        .map_err(|e| format!("Failed to create analysis cache directory: {}", e))?;
+    let entry = AnalysisCacheEntry {
+        analyzer_version: ANALYZER_VERSION,
+        style_info: DocumentStyleInfo { trace: None, ..style_info.clone() },
+        trace: style_info.trace.clone().unwrap_or_default(),
+    };
+    let json = serde_json::to_string(&entry)
+        .map_err(|e| format!("Failed to serialize analysis cache entry: {}", e))?;
+    fs::write(cache_dir.join(format!("{}.json", hash)), json)
+        .map_err(|e| format!("Failed to write analysis cache entry: {}", e))
+}
+
+/// Analyze a DOCX file, reusing the cached result for identical file contents.
+/// Cache failures never fail the analysis.
+pub(crate) fn analyze_docx_file(file_path: &PathBuf, document_id: &str) -> Result<DocumentStyleInfo, String> {
+    analyze_docx_file_reporting_hit(file_path, document_id).map(|(style_info, _)| DocumentStyleInfo { trace: None, ..style_info })
+}
+
+/// Like `analyze_docx_file` but with the trace attached, plus whether the result came from the cache
+fn analyze_docx_file_reporting_hit(file_path: &PathBuf, document_id: &str) -> Result<(DocumentStyleInfo, bool), String> {
+    // Example documents on OneDrive may be cloud-only placeholders
+    ensure_local(file_path)?;
+    match get_analysis_cache_dir() {
+        Ok(cache_dir) => analyze_docx_file_cached(file_path, document_id, &cache_dir),
+        Err(_) => Ok((parse_docx_file(file_path, document_id)?, false)),
+    }
+}
+
+fn analyze_docx_file_cached(file_path: &PathBuf, document_id: &str, cache_dir: &Path) -> Result<(DocumentStyleInfo, bool), String> {
+    let hash = match sha256_file(file_path) {
+        Ok(hash) => hash,
+        Err(_) => return Ok((parse_docx_file(file_path, document_id)?, false)),
+    };
+
+    if let Some(mut cached) = read_cached_analysis(cache_dir, &hash) {
+        println!("📦 Using cached analysis for {} ({})", file_path.display(), &hash[..12]);
+        cached.document_id = document_id.to_string();
+        cached.filename = format!("Document_{}", document_id);
+        return Ok((cached, true));
+    }
+
+    let style_info = parse_docx_file(file_path, document_id)?;
+    if let Err(e) = write_cached_analysis(cache_dir, &hash, &style_info) {
+        println!("Warning: {}", e);
+    }
+    Ok((style_info, false))
+}
+
+/// Cached analysis by document ID (see `document_id_for`) or full file hash, without its trace
+pub(crate) fn load_cached_analysis(analysis_id: &str) -> Result<DocumentStyleInfo, String> {
+    load_cached_analysis_with_trace(analysis_id).map(|style_info| DocumentStyleInfo { trace: None, ..style_info })
+}
+
+fn load_cached_analysis_with_trace(analysis_id: &str) -> Result<DocumentStyleInfo, String> {
+    let hash_prefix = analysis_id.trim().trim_start_matches("doc_");
+    let cache_dir = get_analysis_cache_dir()?;
+    let not_found = || format!("Keine gespeicherte Analyse für {} – bitte das Dokument erneut analysieren", analysis_id);
+    if hash_prefix.is_empty() {
+        return Err(not_found());
+    }
+
+    let entries = fs::read_dir(&cache_dir).map_err(|_| not_found())?;
+    entries.filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_suffix(".json").filter(|hash| hash.starts_with(hash_prefix)).map(String::from)
+        })
+        .find_map(|hash| read_cached_analysis(&cache_dir, &hash))
+        .map(|mut style_info| {
+            style_info.document_id = analysis_id.to_string();
+            style_info
+        })
+        .ok_or_else(not_found)
+}
+
+/// Decisions of a cached analysis (which pattern matched, which value was chosen, what was
+/// rejected), for diagnosing an unexpected font or heading after the fact
+#[command]
+pub async fn get_analysis_trace(analysis_id: String) -> Result<AnalysisTrace, String> {
+    let style_info = load_cached_analysis_with_trace(&analysis_id)?;
+    Ok(style_info.trace.unwrap_or_default())
+}
+
+/// Stable ID of a document derived from its bytes, so the same file always gets the same ID
+pub(crate) fn document_id_for(path: &Path) -> Result<String, String> {
+    Ok(format!("doc_{}", &sha256_file(path)?[..16]))
+}
+
+/// Content-derived document ID (same file contents, same ID)
+#[command]
+pub async fn compute_document_id(file_path: String) -> Result<String, String> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(format!("Document file not found: {}", file_path));
+    }
+    document_id_for(&path)
+}
+
+/// Remove all cached document analyses; returns the number of removed entries
+#[command]
+pub async fn clear_analysis_cache() -> Result<usize, String> {
+    let cache_dir = get_analysis_cache_dir()?;
+    if !cache_dir.exists() {
+        return Ok(0);
+    }
+    let removed = fs::read_dir(&cache_dir)
+        .map_err(|e| format!("Failed to read analysis cache: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("json"))
+        .filter(|entry| fs::remove_file(entry.path()).is_ok())
+        .count();
+    println!("Cleared {} cached document analyses", removed);
+    Ok(removed)
+}
+
+/// Parse a DOCX file and analyze its structure (uncached); the result carries the trace
+fn parse_docx_file(file_path: &PathBuf, document_id: &str) -> Result<DocumentStyleInfo, String> {
+    let mut trace = AnalysisTrace::default();
 
     // Check file size
     let metadata = fs::metadata(file_path)
         .map_err(|e| format!("Failed to read file metadata: {}", e))?;
-    println!("📄 File size: {} bytes", metadata.len());
 
     // Open DOCX as ZIP archive
     let file = fs::File::open(file_path)
         .map_err(|e| format!("Failed to open DOCX file: {}", e))?;
-    println!("✅ File opened successfully");
 
     let reader = BufReader::new(file);
     let mut archive = ZipArchive::new(reader)
-        .map_err(|e| {
-            println!("❌ ZIP archive error: {}", e);
-            format!("Failed to read DOCX archive (file may be corrupted or not a valid DOCX): {}", e)
-        })?;
-    println!("✅ ZIP archive opened, {} files found", archive.len());
+        .map_err(|e| format!("Failed to read DOCX archive (file may be corrupted or not a valid DOCX): {}", e))?;
+    let mut part_names: Vec<&str> = archive.file_names().collect();
+    part_names.sort();
+    trace.info("archive", format!("{} bytes, {} parts: {}", metadata.len(), part_names.len(), part_names.join(", ")));
 
-    // List all files in the archive for debugging
-    for i in 0..archive.len() {
-        if let Ok(file) = archive.by_index(i) {
-            println!("📁 Archive contains: {}", file.name());
-        }
+    // Extract styles.xml for style definitions; it is needed while document.xml streams past
+    let styles_xml = extract_styles_xml(&mut archive)?;
+    let styles = StyleSheet::parse(&styles_xml);
+    if styles_xml.is_empty() {
+        trace.defaulted("styles", "no styles.xml, only document.xml is analyzed");
+    } else {
+        trace.info("styles", format!("styles.xml: {} chars, {} style definitions", styles_xml.len(), styles.styles.len()));
     }
 
-    // Extract document.xml for content analysis
-    println!("🔍 Extracting document.xml...");
-    let document_xml = extract_document_xml(&mut archive)?;
-    println!("✅ document.xml extracted ({} chars)", document_xml.len());
-
-    // Extract styles.xml for style definitions
-    println!("🔍 Extracting styles.xml...");
-    let styles_xml = extract_styles_xml(&mut archive)?;
-    println!("✅ styles.xml extracted ({} chars)", styles_xml.len());
+    // Stream document.xml through the extractors, one chunk at a time
+    let scan = {
+        let part = archive.by_name("word/document.xml")
+            .map_err(|_| "document.xml not found in DOCX file".to_string())?;
+        let declared_size = part.size();
+        let mut scan = DocumentScan::new(&styles);
+        for chunk in XmlChunks::new(part, declared_size, MAX_DOCX_PART_BYTES) {
+            scan.feed(&chunk?, &styles);
+        }
+        scan
+    };
+    trace.info("document", format!("document.xml: {} bytes", scan.xml_bytes));
 
     // Analyze the extracted XML content
-    println!("🔍 Analyzing document content...");
-    let style_info = analyze_document_content(&document_xml, &styles_xml, document_id, &mut archive)?;
-    println!("✅ Content analysis completed");
+    let mut style_info = analyze_document_content(scan, &styles, document_id, &mut archive, &mut trace)?;
 
-    println!("🎉 DOCX analysis completed successfully");
+    println!("📄 Analyzed {}: {} {}pt ({} trace entries)",
+        file_path.display(), style_info.font_family, style_info.font_size, trace.entries.len());
+    if trace_enabled() {
+        for line in trace.lines() {
+            println!("  {}", line);
+        }
+    }
+    style_info.trace = Some(trace);
     Ok(style_info)
 }
 
-/// Extract document.xml from DOCX archive
-fn extract_document_xml(archive: &mut ZipArchive<BufReader<fs::File>>) -> Result<String, String> {
-    let mut document_xml = String::new();
+/// Number of text paragraphs per role; headings are keyed by level (Title counts as level 1)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ParagraphRoleCounts {
+    pub(crate) body: usize,
+    pub(crate) headings: std::collections::BTreeMap<u8, usize>,
+}
 
-    match archive.by_name("word/document.xml") {
-        Ok(mut file) => {
-            file.read_to_string(&mut document_xml)
-                .map_err(|e| format!("Failed to read document.xml: {}", e))?;
-        },
-        Err(_) => {
-            return Err("document.xml not found in DOCX file".to_string());
+impl ParagraphRoleCounts {
+    pub(crate) fn total(&self) -> usize {
+        self.body + self.headings.values().sum::<usize>()
+    }
+}
+
+/// One <w:p> of document.xml, split up once and shared by all extractors
+pub(crate) struct DocxParagraph<'a> {
+    pub(crate) xml: &'a str,
+    pub(crate) properties: &'a str,      // The <w:pPr> element, empty if there is none
+    pub(crate) style_id: Option<&'a str>,
+    pub(crate) runs: Vec<&'a str>,       // w:t contents in order
+}
+
+impl DocxParagraph<'_> {
+    pub(crate) fn text(&self) -> String {
+        self.runs.concat()
+    }
+
+    pub(crate) fn has_text(&self) -> bool {
+        self.runs.iter().any(|run| !run.trim().is_empty())
+    }
+
+    /// Heading level of the paragraph style (Title counts as level 1)
+    pub(crate) fn heading_level(&self) -> Option<u8> {
+        let captures = HEADING_STYLE_ID.captures(self.style_id?)?;
+        Some(captures.get(1).and_then(|m| m.as_str().parse::<u8>().ok()).unwrap_or(1))
+    }
+}
+
+/// All paragraphs of document.xml in a single pass
+pub(crate) fn parse_paragraphs(document_xml: &str) -> Vec<DocxParagraph<'_>> {
+    PARAGRAPH.find_iter(document_xml)
+        .map(|paragraph| {
+            let xml = paragraph.as_str();
+            let properties = PARAGRAPH_PROPERTIES.find(xml).map(|m| m.as_str()).unwrap_or("");
+            DocxParagraph {
+                xml,
+                properties,
+                style_id: PARAGRAPH_STYLE.captures(properties).and_then(|c| c.get(1)).map(|m| m.as_str()),
+                runs: TEXT_RUN.captures_iter(xml).filter_map(|c| c.get(1)).map(|m| m.as_str()).collect(),
+            }
+        })
+        .collect()
+}
+
+/// Text paragraphs in document order with their heading level, if any
+/// (empty spacing paragraphs are skipped, Title counts as level 1)
+pub(crate) fn extract_paragraphs_with_levels(document_xml: &str) -> Vec<(Option<u8>, String)> {
+    parse_paragraphs(document_xml).iter()
+        .filter(|paragraph| paragraph.has_text())
+        .map(|paragraph| (paragraph.heading_level(), paragraph.text().trim().to_string()))
+        .collect()
+}
+
+/// styles.xml with its style definitions indexed by styleId, parsed once per document
+pub(crate) struct StyleSheet<'a> {
+    xml: &'a str,
+    styles: HashMap<&'a str, &'a str>,  // styleId -> whole <w:style> element
+}
+
+impl<'a> StyleSheet<'a> {
+    pub(crate) fn parse(xml: &'a str) -> Self {
+        let mut styles = HashMap::new();
+        for style in STYLE.captures_iter(xml) {
+            let (Some(element), Some(attributes)) = (style.get(0), style.get(1)) else { continue };
+            if let Some(id) = STYLE_ID.captures(attributes.as_str()).and_then(|c| c.get(1)) {
+                styles.entry(id.as_str()).or_insert(element.as_str());
+            }
         }
+        StyleSheet { xml, styles }
     }
 
-    Ok(document_xml)
+    pub(crate) fn style(&self, style_id: &str) -> Option<&'a str> {
+        self.styles.get(style_id).copied()
+    }
+
+    /// Level of a numbered heading style, also for custom styles named or based on one
+    /// (Title has no level here)
+    pub(crate) fn heading_level(&self, style_id: &str) -> Option<u8> {
+        let mut current = Some(style_id.to_string());
+        // Bounded in case of a basedOn cycle
+        for _ in 0..10 {
+            let id = current.take()?;
+            let style = self.style(&id);
+            let level = HEADING_STYLE_ID.captures(&id).and_then(|c| c.get(1).map(|m| m.as_str().to_string()))
+                .or_else(|| {
+                    let name = STYLE_NAME.captures(style?)?.get(1)?.as_str();
+                    HEADING_STYLE_NAME.captures(name).map(|c| c[1].to_string())
+                });
+            if let Some(level) = level {
+                return level.parse().ok();
+            }
+            current = BASED_ON.captures(style?).map(|c| c[1].to_string());
+        }
+        None
+    }
 }
 
-/// Extract styles.xml from DOCX archive
-fn extract_styles_xml(archive: &mut ZipArchive<BufReader<fs::File>>) -> Result<String, String> {
-    let mut styles_xml = String::new();
+/// Missing heading levels between the top level and the deepest one used, as warnings
+/// (uses H1 and H3 but not H2)
+pub(crate) fn heading_level_gaps(used_levels: &[u8]) -> Vec<String> {
+    let Some(&deepest) = used_levels.iter().max() else { return Vec::new() };
+    (1..deepest)
+        .filter(|level| !used_levels.contains(level))
+        .map(|level| {
+            let above = used_levels.iter().filter(|l| **l < level).max();
+            let below = used_levels.iter().filter(|l| **l > level).min().unwrap_or(&deepest);
+            match above {
+                Some(above) => format!("Überschriftenebene {} fehlt zwischen Ebene {} und {}", level, above, below),
+                None => format!("Überschriftenebene {} fehlt, der Text beginnt mit Ebene {}", level, below),
+            }
+        })
+        .collect()
+}
+
+/// Count body and heading paragraphs (empty spacing paragraphs are ignored)
+pub(crate) fn count_paragraphs_by_role(document_xml: &str) -> ParagraphRoleCounts {
+    let mut counts = ParagraphRoleCounts::default();
+    for (level, _) in extract_paragraphs_with_levels(document_xml) {
+        match level {
+            Some(level) => *counts.headings.entry(level).or_insert(0) += 1,
+            None => counts.body += 1,
+        }
+    }
+    counts
+}
 
+/// Extract document.xml from DOCX archive
+pub(crate) fn extract_document_xml(archive: &mut ZipArchive<BufReader<fs::File>>) -> Result<String, String> {
+    match archive.by_name("word/document.xml") {
+        Ok(file) => {
+            let declared_size = file.size();
+            read_capped(file, declared_size, "document.xml")
+        },
+        Err(_) => Err("document.xml not found in DOCX file".to_string()),
+    }
+}
+
+/// Extract styles.xml from DOCX archive
+pub(crate) fn extract_styles_xml(archive: &mut ZipArchive<BufReader<fs::File>>) -> Result<String, String> {
     match archive.by_name("word/styles.xml") {
-        Ok(mut file) => {
-            file.read_to_string(&mut styles_xml)
-                .map_err(|e| format!("Failed to read styles.xml: {}", e))?;
+        Ok(file) => {
+            let declared_size = file.size();
+            read_capped(file, declared_size, "styles.xml")
         },
         Err(_) => {
             // styles.xml is optional, provide default
             println!("Warning: styles.xml not found, using defaults");
+            Ok(String::new())
+        }
+    }
+}
+
+/// Color scheme of word/theme/theme1.xml, for resolving theme color references; empty without a theme
+pub(crate) fn extract_theme_colors<R: Read + std::io::Seek>(archive: &mut ZipArchive<R>) -> Result<ThemeColors, String> {
+    match archive.by_name("word/theme/theme1.xml") {
+        Ok(file) => {
+            let declared_size = file.size();
+            Ok(ThemeColors::parse(&read_capped(file, declared_size, "theme1.xml")?))
+        }
+        Err(_) => Ok(ThemeColors::default()),
+    }
+}
+
+/// Largest decompressed size accepted for a single DOCX part; beyond this the file is a zip bomb
+/// or broken, and reading on would only exhaust memory
+pub(crate) const MAX_DOCX_PART_BYTES: u64 = 256 * 1024 * 1024;
+/// document.xml is analyzed in pieces of about this size
+const XML_CHUNK_BYTES: usize = 256 * 1024;
+const XML_READ_BLOCK_BYTES: usize = 64 * 1024;
+const PARAGRAPH_END: &[u8] = b"</w:p>";
+
+fn trace_enabled() -> bool {
+    std::env::var_os("GUTACHTEN_TRACE").is_some()
+}
+
+fn part_too_large(name: &str) -> String {
+    format!(
+        "{} ist entpackt größer als {} MB – die Datei ist beschädigt oder keine gewöhnliche Word-Datei",
+        name, MAX_DOCX_PART_BYTES / (1024 * 1024)
+    )
+}
+
+/// Read a DOCX part as text, refusing parts that decompress to more than MAX_DOCX_PART_BYTES
+/// (the declared size is checked first, the actual one while reading)
+pub(crate) fn read_capped<R: Read>(part: R, declared_size: u64, name: &str) -> Result<String, String> {
+    if declared_size > MAX_DOCX_PART_BYTES {
+        return Err(part_too_large(name));
+    }
+    let mut content = String::new();
+    part.take(MAX_DOCX_PART_BYTES + 1).read_to_string(&mut content)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+    if content.len() as u64 > MAX_DOCX_PART_BYTES {
+        return Err(part_too_large(name));
+    }
+    Ok(content)
+}
+
+/// document.xml read from the zip entry in pieces that end right after a </w:p>, so no piece splits
+/// a paragraph or an element the extractors match. Memory bound: one piece of at most
+/// XML_CHUNK_BYTES + XML_READ_BLOCK_BYTES, unless a single paragraph is longer than that.
+pub(crate) struct XmlChunks<R: Read> {
+    reader: R,
+    pending: Vec<u8>,
+    block: Vec<u8>,
+    last_paragraph_end: Option<usize>,  // In `pending`
+    read_bytes: u64,
+    max_bytes: u64,
+    finished: bool,
+}
+
+impl<R: Read> XmlChunks<R> {
+    pub(crate) fn new(reader: R, declared_size: u64, max_bytes: u64) -> Self {
+        XmlChunks {
+            reader,
+            pending: Vec::new(),
+            block: vec![0; XML_READ_BLOCK_BYTES],
+            last_paragraph_end: None,
+            // An oversized declaration fails before anything is read
+            read_bytes: if declared_size > max_bytes { declared_size } else { 0 },
+            max_bytes,
+            finished: false,
+        }
+    }
+
+    fn take_chunk(&mut self, end: usize) -> Result<String, String> {
+        let rest = self.pending.split_off(end);
+        let chunk = std::mem::replace(&mut self.pending, rest);
+        self.last_paragraph_end = None;
+        String::from_utf8(chunk).map_err(|e| format!("document.xml is not valid UTF-8: {}", e))
+    }
+}
+
+impl<R: Read> Iterator for XmlChunks<R> {
+    type Item = Result<String, String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.read_bytes > self.max_bytes {
+                self.finished = true;
+                self.pending = Vec::new();
+                self.read_bytes = 0;
+                return Some(Err(part_too_large("document.xml")));
+            }
+            if self.finished {
+                return (!self.pending.is_empty()).then(|| self.take_chunk(self.pending.len()));
+            }
+            if self.pending.len() >= XML_CHUNK_BYTES {
+                if let Some(end) = self.last_paragraph_end {
+                    return Some(self.take_chunk(end));
+                }
+            }
+
+            match self.reader.read(&mut self.block) {
+                Ok(0) => self.finished = true,
+                Ok(read) => {
+                    self.read_bytes += read as u64;
+                    // Only the new bytes (and a tag cut at the previous block end) need searching
+                    let search_from = self.pending.len().saturating_sub(PARAGRAPH_END.len() - 1);
+                    self.pending.extend_from_slice(&self.block[..read]);
+                    if let Some(position) = self.pending[search_from..].windows(PARAGRAPH_END.len()).rposition(|w| w == PARAGRAPH_END) {
+                        self.last_paragraph_end = Some(search_from + position + PARAGRAPH_END.len());
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.finished = true;
+                    self.pending = Vec::new();
+                    return Some(Err(format!("Failed to read document.xml: {}", e)));
+                }
+            }
+        }
+    }
+}
+
+/// First match of each pattern while document.xml streams past; the value kept is the last
+/// capture group (the whole match for patterns without one)
+struct FirstMatches {
+    patterns: Vec<&'static Regex>,
+    found: Vec<Option<String>>,
+}
+
+impl FirstMatches {
+    fn new(patterns: Vec<&'static Regex>) -> Self {
+        let found = vec![None; patterns.len()];
+        FirstMatches { patterns, found }
+    }
+
+    fn feed(&mut self, xml: &str) {
+        for (regex, found) in self.patterns.iter().zip(self.found.iter_mut()) {
+            if found.is_none() {
+                *found = regex.captures(xml)
+                    .and_then(|c| c.get(c.len() - 1))
+                    .map(|m| m.as_str().to_string());
+            }
+        }
+    }
+
+    /// Index and value of the first pattern, in pattern order, that matched anywhere
+    fn first(&self) -> Option<(usize, &str)> {
+        self.found.iter().enumerate().find_map(|(i, found)| found.as_deref().map(|value| (i, value)))
+    }
+
+    /// Every pattern that matched; those after the first lose to it unless they found the same value
+    fn trace_candidates(&self, step: &str, source: &str, trace: &mut AnalysisTrace) {
+        let Some((first_index, first_value)) = self.first() else { return };
+        trace.matched(step, format!("{}: \"{}\" via pattern {} `{}`", source, first_value, first_index, self.patterns[first_index].as_str()));
+        for (i, (regex, found)) in self.patterns.iter().zip(&self.found).enumerate().skip(first_index + 1) {
+            match found {
+                Some(value) if value != first_value => trace.rejected(step, format!(
+                    "{}: \"{}\" via pattern {} `{}` (lower priority than pattern {})", source, value, i, regex.as_str(), first_index
+                )),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Direct font attribute, high ANSI font, complex script font, font name in styles, simple ascii pattern
+fn font_family_matches() -> FirstMatches {
+    FirstMatches::new(vec![&*RUN_FONT, &*RUN_FONT_HANSI, &*RUN_FONT_CS, &*STYLE_NAME, &*ASCII_FONT])
+}
+
+/// Size element with val attribute, direct size attribute, complex script size, size value in content
+fn font_size_matches() -> FirstMatches {
+    FirstMatches::new(vec![&*RUN_SIZE, &*SIZE_ATTRIBUTE, &*RUN_SIZE_CS, &*SIZE_CONTENT])
+}
+
+/// Everything the analyzer takes from document.xml, collected chunk by chunk (see XmlChunks)
+struct DocumentScan {
+    xml_bytes: usize,
+    fonts: FirstMatches,
+    sizes: FirstMatches,
+    line_spacing: FirstMatches,
+    alignment: FirstMatches,
+    auto_line_rule: bool,
+    page_breaks: usize,
+    section_properties: usize,
+    body_section: bool,
+    has_header_reference: bool,
+    has_footer_reference: bool,
+    heading_paragraphs: HashMap<String, String>,  // Style ID -> first paragraph with that style
+    used_heading_levels: BTreeSet<u8>,
+    indentation: IndentationScan,
+    alignments: AlignmentScan,
+    headers: HeaderScan,
+}
+
+impl DocumentScan {
+    fn new(styles: &StyleSheet) -> Self {
+        DocumentScan {
+            xml_bytes: 0,
+            fonts: font_family_matches(),
+            sizes: font_size_matches(),
+            line_spacing: FirstMatches::new(LINE_SPACING.iter().collect()),
+            alignment: FirstMatches::new(ALIGNMENT.iter().map(|(regex, _)| regex).collect()),
+            auto_line_rule: false,
+            page_breaks: 0,
+            section_properties: 0,
+            body_section: false,
+            has_header_reference: false,
+            has_footer_reference: false,
+            heading_paragraphs: HashMap::new(),
+            used_heading_levels: BTreeSet::new(),
+            indentation: IndentationScan::default(),
+            alignments: AlignmentScan::new(styles),
+            headers: HeaderScan::default(),
         }
     }
 
-    Ok(styles_xml)
+    fn feed(&mut self, xml: &str, styles: &StyleSheet) {
+        self.xml_bytes += xml.len();
+
+        self.fonts.feed(xml);
+        self.sizes.feed(xml);
+        self.line_spacing.feed(xml);
+        self.alignment.feed(xml);
+        self.auto_line_rule |= xml.contains(r#"w:lineRule="auto""#);
+
+        self.page_breaks += PAGE_BREAK.find_iter(xml).count();
+        // Every section but the last ends with a sectPr inside a paragraph's pPr; the body's own sectPr closes the document
+        self.section_properties += xml.matches("<w:sectPr").count();
+        self.body_section |= BODY_SECTION.is_match(xml);
+        self.has_header_reference |= xml.contains("headerReference") || xml.contains("w:hdr");
+        self.has_footer_reference |= xml.contains("footerReference") || xml.contains("w:ftr");
+
+        for paragraph in parse_paragraphs(xml) {
+            if let Some(style_id) = paragraph.style_id {
+                if HEADING_PARAGRAPH_STYLES.iter().any(|(id, _)| *id == style_id) {
+                    self.heading_paragraphs.entry(style_id.to_string()).or_insert_with(|| paragraph.xml.to_string());
+                }
+                // Levels the text actually uses, unlike the heading styles styles.xml merely defines
+                if paragraph.has_text() {
+                    self.used_heading_levels.extend(styles.heading_level(style_id));
+                }
+            }
+            self.indentation.add(&paragraph);
+            self.alignments.add(&paragraph, styles);
+            self.headers.add(&paragraph);
+        }
+    }
+
+    /// Explicit page breaks and section breaks
+    fn breaks(&self) -> (usize, usize) {
+        (self.page_breaks, self.section_properties.saturating_sub(usize::from(self.body_section)))
+    }
 }
 
 /// Analyze document content and extract style information
 fn analyze_document_content(
-    document_xml: &str,
-    styles_xml: &str,
+    scan: DocumentScan,
+    styles: &StyleSheet,
     document_id: &str,
-    archive: &mut ZipArchive<BufReader<fs::File>>
+    archive: &mut ZipArchive<BufReader<fs::File>>,
+    trace: &mut AnalysisTrace,
 ) -> Result<DocumentStyleInfo, String> {
-    println!("📊 Starting document content analysis...");
-    println!("📄 Document XML length: {} chars", document_xml.len());
-    println!("🎨 Styles XML length: {} chars", styles_xml.len());
-
-    // Debug: Print first 500 chars of document_xml to see structure
-    println!("📋 Document XML preview:\n{}", &document_xml[..document_xml.len().min(500)]);
-
     // Parse basic document properties with improved extraction
-    let font_family = extract_font_family(document_xml, styles_xml);
-    let font_size = extract_font_size(document_xml, styles_xml);
-    let line_spacing = extract_line_spacing(document_xml);
-    let text_alignment = extract_text_alignment(document_xml);
-
-    println!("🔍 Extracted properties:");
-    println!("  Font Family: {}", font_family);
-    println!("  Font Size: {}pt", font_size);
-    println!("  Line Spacing: {}", line_spacing);
-    println!("  Text Alignment: {}", text_alignment);
+    let font_family = extract_font_family(&scan.fonts, styles.xml, trace);
+    let font_size = extract_font_size(&scan.sizes, styles.xml, trace);
+    let line_spacing = extract_line_spacing(&scan.line_spacing, scan.auto_line_rule, trace);
+    let text_alignment = extract_text_alignment(&scan.alignment, trace);
+    let (page_break_count, section_break_count) = scan.breaks();
+    trace.info("breaks", format!("{} page breaks, {} section breaks", page_break_count, section_break_count));
+    let used_heading_levels: Vec<u8> = scan.used_heading_levels.iter().copied().collect();
+    let heading_level_gaps = heading_level_gaps(&used_heading_levels);
+    trace.chosen("used_heading_levels", format!("{:?}", used_heading_levels));
+    for gap in &heading_level_gaps {
+        trace.info("used_heading_levels", gap.clone());
+    }
 
-    // Extract heading styles
-    let heading_styles = extract_heading_styles(document_xml, styles_xml);
+    // Extract heading styles; theme colors (accent1, ...) are resolved against the document's theme
+    let theme = extract_theme_colors(archive)?;
+    let heading_styles = extract_heading_styles(&scan.heading_paragraphs, styles, &theme, trace);
 
     // Extract actual header text content from the document
-    let headers_found = extract_header_text_content(document_xml);
-    println!("📋 Headers found in document: {:?}", headers_found);
+    let headers_found = scan.headers.finish(trace);
+
+    // Extract paragraph indentation and tab stops
+    let indentation = scan.indentation.finish(trace);
 
     // Extract page margins (simplified)
     let page_margins = PageMargins {
@@ -366,13 +1154,16 @@ fn analyze_document_content(
     };
 
     // Extract header/footer info with improved detection
-    let header_footer_info = extract_header_footer_info(document_xml, archive);
+    let header_footer_info = extract_header_footer_info(scan.has_header_reference, scan.has_footer_reference, archive, &theme, trace)?;
+
+    // Alignment per role: body text is often justified while headings stay left
+    let alignments = scan.alignments.finish(&header_footer_info, trace);
 
     // Generate style summary with header/footer info
     let mut summary_parts = vec![
         format!("Hauptschrift: {} ({}pt)", font_family, font_size),
         format!("Zeilenabstand: {}", line_spacing),
-        format!("Ausrichtung: {}", text_alignment),
+        format!("Ausrichtung: {} (Überschriften: {})", alignments.body, alignments.heading),
         format!("{} Überschriftenebenen erkannt", heading_styles.len()),
     ];
 
@@ -394,6 +1185,18 @@ fn analyze_document_content(
         }
     }
 
+    if indentation.hanging_indent > 0.0 {
+        summary_parts.push(format!("Hängender Einzug: {:.2}cm", indentation.hanging_indent));
+    } else if indentation.first_line_indent > 0.0 {
+        summary_parts.push(format!("Erstzeileneinzug: {:.2}cm", indentation.first_line_indent));
+    }
+
+    if page_break_count + section_break_count > 0 {
+        summary_parts.push(format!("{} Seitenumbrüche, {} Abschnittsumbrüche", page_break_count, section_break_count));
+    }
+
+    summary_parts.extend(heading_level_gaps.iter().cloned());
+
     let style_summary = summary_parts.join(", ");
 
     Ok(DocumentStyleInfo {
@@ -411,464 +1214,502 @@ fn analyze_document_content(
         header_footer_info,
         style_summary,
         headers_found,
+        indentation,
+        alignments,
+        source: default_style_source(),
+        confidence: default_style_confidence(),
+        page_break_count,
+        section_break_count,
+        used_heading_levels,
+        heading_level_gaps,
+        trace: None,
     })
 }
 
 /// Extract primary font family from document
-fn extract_font_family(document_xml: &str, styles_xml: &str) -> String {
-    println!("🔤 Extracting font family...");
-
-    // Try multiple patterns for font family extraction
-    let font_patterns = vec![
-        r#"<w:rFonts[^>]*w:ascii="([^"]+)""#,           // Direct font attribute
-        r#"<w:rFonts[^>]*w:hAnsi="([^"]+)""#,           // High ANSI font
-        r#"<w:rFonts[^>]*w:cs="([^"]+)""#,              // Complex script font
-        r#"<w:name[^>]*w:val="([^"]+)""#,               // Font name in styles
-        r#"w:ascii="([^"]+)""#,                         // Simple ascii pattern
-    ];
-
-    for pattern in &font_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            if let Some(captures) = regex.captures(document_xml) {
-                if let Some(font) = captures.get(1) {
-                    let font_name = font.as_str().to_string();
-                    println!("  ✅ Found font in document: {}", font_name);
-                    return font_name;
-                }
-            }
-        }
+fn extract_font_family(document_fonts: &FirstMatches, styles_xml: &str, trace: &mut AnalysisTrace) -> String {
+    document_fonts.trace_candidates("font_family", "document.xml", trace);
+    if let Some((_, font_name)) = document_fonts.first() {
+        trace.chosen("font_family", format!("{} from document.xml", font_name));
+        return font_name.to_string();
     }
 
     // Try styles.xml as well
-    for pattern in &font_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            if let Some(captures) = regex.captures(styles_xml) {
-                if let Some(font) = captures.get(1) {
-                    let font_name = font.as_str().to_string();
-                    println!("  ✅ Found font in styles: {}", font_name);
-                    return font_name;
-                }
-            }
-        }
+    let mut style_fonts = font_family_matches();
+    style_fonts.feed(styles_xml);
+    style_fonts.trace_candidates("font_family", "styles.xml", trace);
+    if let Some((_, font_name)) = style_fonts.first() {
+        trace.chosen("font_family", format!("{} from styles.xml (no font in document.xml)", font_name));
+        return font_name.to_string();
     }
 
-    println!("  ❌ No font found, using default");
+    trace.defaulted("font_family", "no font in document.xml or styles.xml, using Times New Roman");
     "Times New Roman".to_string()
 }
 
 /// Extract primary font size from document
-fn extract_font_size(document_xml: &str, styles_xml: &str) -> f32 {
-    println!("📏 Extracting font size...");
-
-    // Try multiple patterns for font size extraction
-    let size_patterns = vec![
-        r#"<w:sz[^>]*w:val="(\d+)""#,                  // Size element with val attribute
-        r#"w:sz="(\d+)""#,                             // Direct size attribute
-        r#"<w:szCs[^>]*w:val="(\d+)""#,                // Complex script size
-        r#"w:val="(\d+)"[^>]*>[^<]*</w:sz>"#,          // Size value in content
-    ];
-
-    for pattern in &size_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            if let Some(captures) = regex.captures(document_xml) {
-                if let Some(size_str) = captures.get(1) {
-                    if let Ok(half_points) = size_str.as_str().parse::<f32>() {
-                        let points = half_points / 2.0; // Convert from half-points to points
-                        println!("  ✅ Found font size in document: {} half-points = {}pt", half_points, points);
-                        return points;
-                    }
-                }
-            }
-        }
+fn extract_font_size(document_sizes: &FirstMatches, styles_xml: &str, trace: &mut AnalysisTrace) -> f32 {
+    document_sizes.trace_candidates("font_size", "document.xml", trace);
+    if let Some(half_points) = document_sizes.first().and_then(|(_, size)| size.parse::<f32>().ok()) {
+        let points = half_points / 2.0; // Convert from half-points to points
+        trace.chosen("font_size", format!("{}pt ({} half-points) from document.xml", points, half_points));
+        return points;
     }
 
     // Try styles.xml as well
-    for pattern in &size_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            if let Some(captures) = regex.captures(styles_xml) {
-                if let Some(size_str) = captures.get(1) {
-                    if let Ok(half_points) = size_str.as_str().parse::<f32>() {
-                        let points = half_points / 2.0;
-                        println!("  ✅ Found font size in styles: {} half-points = {}pt", half_points, points);
-                        return points;
-                    }
-                }
-            }
-        }
+    let mut style_sizes = font_size_matches();
+    style_sizes.feed(styles_xml);
+    style_sizes.trace_candidates("font_size", "styles.xml", trace);
+    if let Some(half_points) = style_sizes.first().and_then(|(_, size)| size.parse::<f32>().ok()) {
+        let points = half_points / 2.0;
+        trace.chosen("font_size", format!("{}pt ({} half-points) from styles.xml (no size in document.xml)", points, half_points));
+        return points;
     }
 
-    println!("  ❌ No font size found, using default");
+    trace.defaulted("font_size", "no font size in document.xml or styles.xml, using 12pt");
     12.0
 }
 
 /// Extract line spacing information
-fn extract_line_spacing(document_xml: &str) -> f32 {
-    println!("📐 Extracting line spacing...");
-
-    // Try multiple patterns for line spacing extraction
-    let spacing_patterns = vec![
-        r#"<w:spacing[^>]*w:line="(\d+)""#,                // Line spacing in spacing element
-        r#"<w:spacing[^>]*w:lineRule="([^"]+)"[^>]*w:line="(\d+)""#, // With line rule
-        r#"w:line="(\d+)""#,                               // Simple line attribute
-        r#"<w:pPr[^>]*><w:spacing[^>]*w:line="(\d+)""#,     // In paragraph properties
-    ];
-
-    for pattern in spacing_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            if let Some(captures) = regex.captures(document_xml) {
-                // Get the last capture group (line value)
-                if let Some(spacing_str) = captures.get(captures.len() - 1) {
-                    if let Ok(spacing_value) = spacing_str.as_str().parse::<f32>() {
-                        // Convert from twips to line spacing multiplier (240 twips = 1.0 spacing)
-                        let line_spacing = spacing_value / 240.0;
-                        println!("  ✅ Found line spacing: {} twips = {}", spacing_value, line_spacing);
-                        return line_spacing;
-                    }
-                }
-            }
-        }
+fn extract_line_spacing(document_spacing: &FirstMatches, auto_line_rule: bool, trace: &mut AnalysisTrace) -> f32 {
+    document_spacing.trace_candidates("line_spacing", "document.xml", trace);
+    if let Some(spacing_value) = document_spacing.first().and_then(|(_, line)| line.parse::<f32>().ok()) {
+        // Convert from twips to line spacing multiplier (240 twips = 1.0 spacing)
+        let line_spacing = spacing_value / 240.0;
+        trace.chosen("line_spacing", format!("{} ({} twips)", line_spacing, spacing_value));
+        return line_spacing;
     }
 
     // Check for specific line spacing rules
-    if document_xml.contains(r#"w:lineRule="auto""#) {
-        println!("  ✅ Found auto line spacing");
+    if auto_line_rule {
+        trace.chosen("line_spacing", "1.0 (lineRule auto without a line value)");
         return 1.0; // Auto spacing
     }
 
-    println!("  ❌ No line spacing found, using default");
+    trace.defaulted("line_spacing", "no line spacing in document.xml, using 1.15");
     1.15
 }
 
 /// Extract text alignment information
-fn extract_text_alignment(document_xml: &str) -> String {
-    println!("🔄 Extracting text alignment...");
-
-    // Look for justification elements
-    let alignment_patterns = vec![
-        (r#"<w:jc[^>]*w:val="center""#, "center"),
-        (r#"<w:jc[^>]*w:val="right""#, "right"),
-        (r#"<w:jc[^>]*w:val="both""#, "justify"),
-        (r#"<w:jc[^>]*w:val="distribute""#, "justify"),
-        (r#"<w:jc[^>]*w:val="left""#, "left"),
-        (r#"w:val="center""#, "center"),
-        (r#"w:val="right""#, "right"),
-        (r#"w:val="both""#, "justify"),
-    ];
+fn extract_text_alignment(document_alignment: &FirstMatches, trace: &mut AnalysisTrace) -> String {
+    // Justification elements, in order of ALIGNMENT
+    document_alignment.trace_candidates("text_alignment", "document.xml", trace);
+    if let Some((index, _)) = document_alignment.first() {
+        let alignment = ALIGNMENT[index].1;
+        trace.chosen("text_alignment", alignment);
+        return alignment.to_string();
+    }
+
+    trace.defaulted("text_alignment", "no alignment in document.xml, using left");
+    "left".to_string()
+}
 
-    for (pattern, alignment) in alignment_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            if regex.is_match(document_xml) {
-                println!("  ✅ Found text alignment: {}", alignment);
-                return alignment.to_string();
+/// Convert twips (1/1440 inch) to centimeters, rounded to 2 decimals
+fn twips_to_cm(twips: f32) -> f32 {
+    (twips / 1440.0 * 2.54 * 100.0).round() / 100.0
+}
+
+/// Paragraph indentation (<w:ind>) and tab stops (<w:tabs>) of body paragraphs, collected
+/// paragraph by paragraph; headings are ignored
+#[derive(Default)]
+struct IndentationScan {
+    // (left, firstLine, hanging) in twips -> occurrence count
+    indent_counts: HashMap<(i32, i32, i32), usize>,
+    tab_stops: Vec<i32>,
+    indented_paragraphs: usize,
+    total_paragraphs: usize,
+}
+
+impl IndentationScan {
+    fn add(&mut self, paragraph: &DocxParagraph) {
+        let properties = paragraph.properties;
+
+        if paragraph.heading_level().is_some() {
+            return;
+        }
+        self.total_paragraphs += 1;
+
+        let mut indent = (0, 0, 0);
+        if let Some(ind) = INDENTATION.find(properties) {
+            for captures in INDENTATION_ATTRIBUTE.captures_iter(ind.as_str()) {
+                let value: i32 = captures[2].parse().unwrap_or(0);
+                match &captures[1] {
+                    "left" | "start" => indent.0 = value,
+                    "firstLine" => indent.1 = value,
+                    "hanging" => indent.2 = value,
+                    _ => {}
+                }
+            }
+            if indent != (0, 0, 0) {
+                self.indented_paragraphs += 1;
+            }
+        }
+        *self.indent_counts.entry(indent).or_insert(0) += 1;
+
+        if let Some(tabs) = TABS.captures(properties) {
+            for pos in TAB_POSITION.captures_iter(&tabs[1]) {
+                if let Ok(value) = pos[1].parse::<i32>() {
+                    if !self.tab_stops.contains(&value) {
+                        self.tab_stops.push(value);
+                    }
+                }
             }
         }
     }
 
-    println!("  ❌ No specific alignment found, using default: left");
-    "left".to_string()
+    /// The dominant (most frequent) body indentation and all tab stops
+    fn finish(self, trace: &mut AnalysisTrace) -> IndentationInfo {
+        let IndentationScan { indent_counts, mut tab_stops, indented_paragraphs, total_paragraphs } = self;
+        let variants = indent_counts.len();
+
+        tab_stops.sort();
+
+        // Ties are broken towards the indented variant so a hanging-indent list isn't hidden by blank lines
+        let dominant = indent_counts.into_iter()
+            .max_by_key(|(indent, count)| (*count, *indent != (0, 0, 0)))
+            .map(|(indent, _)| indent)
+            .unwrap_or((0, 0, 0));
+
+        let info = IndentationInfo {
+            left_indent: twips_to_cm(dominant.0 as f32),
+            first_line_indent: twips_to_cm(dominant.1 as f32),
+            hanging_indent: twips_to_cm(dominant.2 as f32),
+            tab_stops: tab_stops.into_iter().map(|t| twips_to_cm(t as f32)).collect(),
+            indented_paragraphs,
+            total_paragraphs,
+        };
+
+        trace.chosen("indentation", format!(
+            "left {}cm, first line {}cm, hanging {}cm: most frequent of {} variants ({} of {} body paragraphs indented)",
+            info.left_indent, info.first_line_indent, info.hanging_indent, variants, indented_paragraphs, total_paragraphs
+        ));
+
+        info
+    }
+}
+
+/// Normalize a w:jc value to the analyzer's alignment names
+fn normalize_jc_value(value: &str) -> String {
+    match value {
+        "both" | "distribute" => "justify".to_string(),
+        "start" => "left".to_string(),
+        "end" => "right".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Look up the paragraph alignment defined by a style in styles.xml
+fn style_alignment(styles: &StyleSheet, style_id: &str) -> Option<String> {
+    JUSTIFICATION.captures(styles.style(style_id)?).map(|c| normalize_jc_value(&c[1]))
+}
+
+/// Alignment of body paragraphs and heading paragraphs, counted separately paragraph by paragraph;
+/// header/footer alignment comes from their extracted styles
+struct AlignmentScan {
+    normal_alignment: String,
+    body_counts: HashMap<String, usize>,
+    heading_counts: HashMap<String, usize>,
 }
 
-/// Extract heading styles from document
-fn extract_heading_styles(document_xml: &str, styles_xml: &str) -> Vec<HeadingStyle> {
-    println!("🔍 Extracting heading styles from document...");
-    println!("📊 Document XML length: {} chars", document_xml.len());
-    println!("📊 Styles XML length: {} chars", styles_xml.len());
+impl AlignmentScan {
+    fn new(styles: &StyleSheet) -> Self {
+        AlignmentScan {
+            normal_alignment: style_alignment(styles, "Normal")
+                .or_else(|| style_alignment(styles, "Standard"))
+                .unwrap_or_else(|| "left".to_string()),
+            body_counts: HashMap::new(),
+            heading_counts: HashMap::new(),
+        }
+    }
+
+    fn add(&mut self, paragraph: &DocxParagraph, styles: &StyleSheet) {
+        // Empty spacing paragraphs say nothing about the alignment of the text
+        if !paragraph.has_text() {
+            return;
+        }
+
+        let alignment = JUSTIFICATION.captures(paragraph.properties)
+            .map(|c| normalize_jc_value(&c[1]))
+            .or_else(|| paragraph.style_id.and_then(|id| style_alignment(styles, id)))
+            .unwrap_or_else(|| self.normal_alignment.clone());
+
+        let counts = if paragraph.heading_level().is_some() { &mut self.heading_counts } else { &mut self.body_counts };
+        *counts.entry(alignment).or_insert(0) += 1;
+    }
+
+    /// The dominant alignment per role
+    fn finish(self, header_footer_info: &HeaderFooterInfo, trace: &mut AnalysisTrace) -> RoleAlignments {
+        // "justify 12, left 3"
+        let tally = |counts: &HashMap<String, usize>| {
+            let mut counts: Vec<(&String, &usize)> = counts.iter().collect();
+            counts.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+            counts.iter().map(|(alignment, count)| format!("{} {}", alignment, count)).collect::<Vec<_>>().join(", ")
+        };
+        let (body_tally, heading_tally) = (tally(&self.body_counts), tally(&self.heading_counts));
+
+        let dominant = |counts: HashMap<String, usize>| {
+            counts.into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))
+                .map(|(alignment, _)| alignment)
+        };
+
+        let body = dominant(self.body_counts).unwrap_or_else(|| self.normal_alignment.clone());
+        let heading = dominant(self.heading_counts).unwrap_or_else(|| "left".to_string());
+        let header = header_footer_info.header_style.as_ref()
+            .map(|style| style.alignment.clone())
+            .unwrap_or_else(|| "left".to_string());
+        let footer = header_footer_info.footer_style.as_ref()
+            .map(|style| style.alignment.clone())
+            .unwrap_or_else(|| "left".to_string());
+
+        trace.chosen("alignments", format!(
+            "body {} (paragraphs: {}), headings {} (paragraphs: {}), header {}, footer {}",
+            body, body_tally, heading, heading_tally, header, footer
+        ));
+
+        RoleAlignments { body, heading, header, footer }
+    }
+}
+
+/// Heading spacing (pt) when neither the style, its basedOn chain nor the paragraph defaults set one
+const DEFAULT_HEADING_SPACING_BEFORE: f32 = 12.0;
+const DEFAULT_HEADING_SPACING_AFTER: f32 = 6.0;
+
+/// Paragraph spacing before/after in pt of a style: the first value found along the basedOn
+/// chain, then in the document's paragraph defaults. None where nothing sets it.
+fn style_paragraph_spacing(styles: &StyleSheet, style_id: &str) -> (Option<f32>, Option<f32>) {
+    // Only inside w:pPr; w:rPr has a w:spacing for letter spacing
+    let ppr_spacing = |xml: &str| -> Option<String> {
+        let ppr = PARAGRAPH_PROPERTIES_CONTENT.captures(xml)?;
+        SPACING_ATTRIBUTES.captures(&ppr[1]).map(|c| c[1].to_string())
+    };
+    let twips_to_pt = |attrs: &str, regex: &Regex| -> Option<f32> {
+        regex.captures(attrs).and_then(|c| c[1].parse::<f32>().ok()).map(|twips| twips / 20.0)
+    };
+
+    let (mut before, mut after) = (None, None);
+    let mut current = Some(style_id.to_string());
+    // Bounded in case of a basedOn cycle
+    for _ in 0..10 {
+        let Some(id) = current.take() else { break };
+        let Some(style) = styles.style(&id) else { break };
+        if let Some(spacing) = ppr_spacing(style) {
+            before = before.or_else(|| twips_to_pt(&spacing, &SPACING_BEFORE));
+            after = after.or_else(|| twips_to_pt(&spacing, &SPACING_AFTER));
+        }
+        current = BASED_ON.captures(style).map(|c| c[1].to_string());
+    }
+
+    if let Some(spacing) = PARAGRAPH_DEFAULTS.captures(styles.xml).and_then(|d| ppr_spacing(&d[1])) {
+        before = before.or_else(|| twips_to_pt(&spacing, &SPACING_BEFORE));
+        after = after.or_else(|| twips_to_pt(&spacing, &SPACING_AFTER));
+    }
+    (before, after)
+}
 
+/// Heading paragraph styles looked for in document.xml when styles.xml defines none
+const HEADING_PARAGRAPH_STYLES: &[(&str, u8)] = &[
+    ("Heading1", 1), ("Heading2", 2), ("Heading3", 3),
+    ("berschrift1", 1), ("berschrift2", 2), ("Title", 1),
+];
+
+/// Extract heading styles from document; `heading_paragraphs` holds the first document paragraph
+/// of each style in HEADING_PARAGRAPH_STYLES
+fn extract_heading_styles(
+    heading_paragraphs: &HashMap<String, String>,
+    styles: &StyleSheet,
+    theme: &ThemeColors,
+    trace: &mut AnalysisTrace,
+) -> Vec<HeadingStyle> {
     let mut heading_styles = Vec::new();
 
     // First, try to find heading styles in styles.xml
-    if !styles_xml.is_empty() {
-        println!("📋 Analyzing styles.xml for heading definitions...");
-
-        // Look for heading style definitions with specific patterns (no generic patterns to avoid duplicates)
-        let heading_patterns = vec![
-            // English heading patterns
-            (r#"<w:style[^>]*w:styleId="Heading1"[^>]*>.*?</w:style>"#, "Heading1", 1),
-            (r#"<w:style[^>]*w:styleId="Heading2"[^>]*>.*?</w:style>"#, "Heading2", 2),
-            (r#"<w:style[^>]*w:styleId="Heading3"[^>]*>.*?</w:style>"#, "Heading3", 3),
-            (r#"<w:style[^>]*w:styleId="Heading4"[^>]*>.*?</w:style>"#, "Heading4", 4),
-            (r#"<w:style[^>]*w:styleId="Heading5"[^>]*>.*?</w:style>"#, "Heading5", 5),
-            (r#"<w:style[^>]*w:styleId="Heading6"[^>]*>.*?</w:style>"#, "Heading6", 6),
-            // German heading patterns
-            (r#"<w:style[^>]*w:styleId="berschrift1"[^>]*>.*?</w:style>"#, "Überschrift1", 1),
-            (r#"<w:style[^>]*w:styleId="berschrift2"[^>]*>.*?</w:style>"#, "Überschrift2", 2),
-            (r#"<w:style[^>]*w:styleId="berschrift3"[^>]*>.*?</w:style>"#, "Überschrift3", 3),
-            (r#"<w:style[^>]*w:styleId="berschrift4"[^>]*>.*?</w:style>"#, "Überschrift4", 4),
-            (r#"<w:style[^>]*w:styleId="berschrift5"[^>]*>.*?</w:style>"#, "Überschrift5", 5),
-            (r#"<w:style[^>]*w:styleId="berschrift6"[^>]*>.*?</w:style>"#, "Überschrift6", 6),
-            // Alternative patterns (specific only)
-            (r#"<w:style[^>]*w:styleId="Title"[^>]*>.*?</w:style>"#, "Title", 1),
-            (r#"<w:style[^>]*w:styleId="Subtitle"[^>]*>.*?</w:style>"#, "Subtitle", 2),
+    if !styles.xml.is_empty() {
+        // Specific style IDs only (no generic patterns to avoid duplicates)
+        let heading_style_ids = [
+            // English heading styles
+            ("Heading1", "Heading1", 1), ("Heading2", "Heading2", 2), ("Heading3", "Heading3", 3),
+            ("Heading4", "Heading4", 4), ("Heading5", "Heading5", 5), ("Heading6", "Heading6", 6),
+            // German heading styles
+            ("berschrift1", "Überschrift1", 1), ("berschrift2", "Überschrift2", 2), ("berschrift3", "Überschrift3", 3),
+            ("berschrift4", "Überschrift4", 4), ("berschrift5", "Überschrift5", 5), ("berschrift6", "Überschrift6", 6),
+            // Alternative styles
+            ("Title", "Title", 1), ("Subtitle", "Subtitle", 2),
         ];
 
-        for (pattern, name, level) in heading_patterns.iter() {
-            println!("🔍 Searching for pattern: {}", name);
-            if let Ok(regex) = Regex::new(pattern) {
-                if let Some(style_match) = regex.find(styles_xml) {
-                    let style_content = style_match.as_str();
-                    println!("✅ Found heading style {}: {} chars", name, style_content.len());
-
-                    // Extract font info from this heading style
-                    let font_family = extract_font_from_style(style_content);
-                    let font_size = extract_size_from_style(style_content);
-                    let font_weight = if style_content.contains("<w:b") { "bold".to_string() } else { "normal".to_string() };
-
-                    println!("   📝 Extracted: {} {}pt {} (level {})", font_family, font_size, font_weight, level);
-
-                    heading_styles.push(HeadingStyle {
-                        level: *level as u8,
-                        font_family,
-                        font_size,
-                        font_weight,
-                        color: "#000000".to_string(),
-                        spacing_before: 12.0,
-                        spacing_after: 6.0,
-                    });
-                } else {
-                    println!("❌ No match found for {}", name);
-                }
-            } else {
-                println!("❌ Failed to compile regex for {}", name);
+        for (style_id, name, level) in heading_style_ids {
+            if let Some(style_content) = styles.style(style_id) {
+                // Extract font info from this heading style
+                let font_family = extract_font_from_style(style_content);
+                let font_size = extract_size_from_style(style_content);
+                let font_weight = if style_content.contains("<w:b") { "bold".to_string() } else { "normal".to_string() };
+                let color = extract_color_from_style(style_content, theme);
+                let (spacing_before, spacing_after) = style_paragraph_spacing(styles, style_id);
+
+                trace.matched("heading_styles", format!(
+                    "styles.xml style {} (level {}): {} {}pt {} {}", name, level, font_family, font_size, font_weight, color
+                ));
+
+                heading_styles.push(HeadingStyle {
+                    level,
+                    font_family,
+                    font_size,
+                    font_weight,
+                    color,
+                    spacing_before: spacing_before.unwrap_or(DEFAULT_HEADING_SPACING_BEFORE),
+                    spacing_after: spacing_after.unwrap_or(DEFAULT_HEADING_SPACING_AFTER),
+                });
             }
         }
-    } else {
-        println!("⚠️ Styles XML is empty");
     }
 
     // If no styles found in styles.xml, look for actual heading paragraphs in document.xml
     if heading_styles.is_empty() {
-        println!("📄 No heading styles in styles.xml, scanning document.xml for heading paragraphs...");
-
-        // Look for paragraphs that use heading styles or have heading-like formatting
-        let heading_paragraph_patterns = vec![
-            (r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="Heading1"[^>]*/>.*?</w:p>"#, "Heading1 paragraph", 1),
-            (r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="Heading2"[^>]*/>.*?</w:p>"#, "Heading2 paragraph", 2),
-            (r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="Heading3"[^>]*/>.*?</w:p>"#, "Heading3 paragraph", 3),
-            (r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="berschrift1"[^>]*/>.*?</w:p>"#, "Überschrift1 paragraph", 1),
-            (r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="berschrift2"[^>]*/>.*?</w:p>"#, "Überschrift2 paragraph", 2),
-            (r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="Title"[^>]*/>.*?</w:p>"#, "Title paragraph", 1),
-        ];
-
-        for (pattern, name, level) in heading_paragraph_patterns.iter() {
-            println!("🔍 Searching for paragraph pattern: {}", name);
-            if let Ok(regex) = Regex::new(pattern) {
-                if let Some(paragraph_match) = regex.find(document_xml) {
-                    let paragraph_content = paragraph_match.as_str();
-                    println!("✅ Found heading paragraph {}: {} chars", name, paragraph_content.len());
-
-                    let font_family = extract_font_from_paragraph(paragraph_content);
-                    let font_size = extract_size_from_paragraph(paragraph_content);
-                    let font_weight = if paragraph_content.contains("<w:b") { "bold".to_string() } else { "normal".to_string() };
-
-                    println!("   📝 Extracted from paragraph: {} {}pt {} (level {})", font_family, font_size, font_weight, level);
-
-                    heading_styles.push(HeadingStyle {
-                        level: *level as u8,
-                        font_family,
-                        font_size,
-                        font_weight,
-                        color: "#000000".to_string(),
-                        spacing_before: 12.0,
-                        spacing_after: 6.0,
-                    });
-                } else {
-                    println!("❌ No match found for {}", name);
-                }
+        trace.info("heading_styles", "no heading styles in styles.xml, using the first heading paragraphs of document.xml");
+
+        for &(style_id, level) in HEADING_PARAGRAPH_STYLES {
+            if let Some(paragraph_content) = heading_paragraphs.get(style_id) {
+                let font_family = extract_font_from_paragraph(paragraph_content);
+                let font_size = extract_size_from_paragraph(paragraph_content);
+                let font_weight = if paragraph_content.contains("<w:b") { "bold".to_string() } else { "normal".to_string() };
+
+                trace.matched("heading_styles", format!(
+                    "document.xml paragraph {} (level {}): {} {}pt {}", style_id, level, font_family, font_size, font_weight
+                ));
+
+                heading_styles.push(HeadingStyle {
+                    level,
+                    font_family,
+                    font_size,
+                    font_weight,
+                    color: extract_color_from_style(paragraph_content, theme),
+                    spacing_before: 12.0,
+                    spacing_after: 6.0,
+                });
             }
         }
     }
 
     if heading_styles.is_empty() {
-        println!("⚠️ No heading styles found in document, returning empty list");
-    } else {
-        println!("✅ Extracted {} heading styles", heading_styles.len());
-        for (i, style) in heading_styles.iter().enumerate() {
-            println!("   Style {}: {} {}pt {}", i + 1, style.font_family, style.font_size, style.font_weight);
-        }
+        trace.defaulted("heading_styles", "no heading styles in styles.xml or document.xml");
     }
 
     // Deduplicate heading styles by level - keep only the first occurrence of each level
-    println!("🔧 Deduplicating heading styles...");
-    println!("📊 Before deduplication: {} heading styles found", heading_styles.len());
-
     let mut unique_levels = std::collections::HashSet::new();
     let mut deduplicated_styles = Vec::new();
 
     for style in heading_styles {
         if unique_levels.insert(style.level) {
-            println!("✅ Keeping heading level {} ({} {}pt {})",
-                style.level, style.font_family, style.font_size, style.font_weight);
+            trace.chosen("heading_styles", format!("level {}: {} {}pt {}",
+                style.level, style.font_family, style.font_size, style.font_weight));
             deduplicated_styles.push(style);
         } else {
-            println!("⚠️ Removing duplicate heading level {} ({} {}pt {})",
-                style.level, style.font_family, style.font_size, style.font_weight);
+            trace.rejected("heading_styles", format!("level {}: {} {}pt {} (an earlier style already defines this level)",
+                style.level, style.font_family, style.font_size, style.font_weight));
         }
     }
 
-    println!("📊 After deduplication: {} unique heading styles", deduplicated_styles.len());
-
     // Sort by level for consistent output
     deduplicated_styles.sort_by_key(|style| style.level);
 
     deduplicated_styles
 }
 
-/// Extract actual header text content from document (like "FAMILIENANAMNESE", "DIAGNOSE", etc.)
-fn extract_header_text_content(document_xml: &str) -> Vec<String> {
-    println!("🔍 Extracting header text content from document...");
-
-    let mut headers = Vec::new();
-
-    // Common German medical report section headers to look for
-    let known_headers = vec![
-        "FAMILIENANAMNESE", "EIGENANAMNESE", "AKTUELLE BESCHWERDEN",
-        "BEFUND", "DIAGNOSE", "DIAGNOSEN", "THERAPIE", "EPIKRISE",
-        "BEURTEILUNG", "SOZIALANAMNESE", "ARBEITSANAMNESE",
-        "NEUROLOGISCHER BEFUND", "PSYCHIATRISCHER BEFUND",
-        "PSYCHOPATHOLOGISCHER BEFUND", "KÖRPERLICHE UNTERSUCHUNG",
-        "ZUSAMMENFASSUNG", "EMPFEHLUNG", "EMPFEHLUNGEN",
-        "ANAMNESE", "VORGESCHICHTE", "MEDIKATION", "MEDIKAMENTE",
-        "LABORWERTE", "APPARATIVE DIAGNOSTIK", "BILDGEBUNG",
-        "PSYCHOLOGISCHE TESTUNG", "NEUROPSYCHOLOGISCHE TESTUNG",
-        "SOZIALMEDIZINISCHE BEURTEILUNG", "LEISTUNGSBEURTEILUNG",
-        "PROGNOSE", "VERLAUF", "KRANKHEITSVERLAUF",
-        // Also check for lowercase and mixed case variations
-        "Familienanamnese", "Eigenanamnese", "Aktuelle Beschwerden",
-        "Befund", "Diagnose", "Diagnosen", "Therapie", "Epikrise",
-        "Beurteilung", "Sozialanamnese", "Arbeitsanamnese",
-    ];
+/// Common German medical report section headers to look for
+const KNOWN_HEADERS: &[&str] = &[
+    "FAMILIENANAMNESE", "EIGENANAMNESE", "AKTUELLE BESCHWERDEN",
+    "BEFUND", "DIAGNOSE", "DIAGNOSEN", "THERAPIE", "EPIKRISE",
+    "BEURTEILUNG", "SOZIALANAMNESE", "ARBEITSANAMNESE",
+    "NEUROLOGISCHER BEFUND", "PSYCHIATRISCHER BEFUND",
+    "PSYCHOPATHOLOGISCHER BEFUND", "KÖRPERLICHE UNTERSUCHUNG",
+    "ZUSAMMENFASSUNG", "EMPFEHLUNG", "EMPFEHLUNGEN",
+    "ANAMNESE", "VORGESCHICHTE", "MEDIKATION", "MEDIKAMENTE",
+    "LABORWERTE", "APPARATIVE DIAGNOSTIK", "BILDGEBUNG",
+    "PSYCHOLOGISCHE TESTUNG", "NEUROPSYCHOLOGISCHE TESTUNG",
+    "SOZIALMEDIZINISCHE BEURTEILUNG", "LEISTUNGSBEURTEILUNG",
+    "PROGNOSE", "VERLAUF", "KRANKHEITSVERLAUF",
+];
+
+/// Actual header text content of the document (like "FAMILIENANAMNESE", "DIAGNOSE", etc.),
+/// collected paragraph by paragraph. Styled headings come first in the result, as when
+/// the whole document was searched for them before the text runs.
+#[derive(Default)]
+struct HeaderScan {
+    styled: Vec<String>,
+    from_runs: Vec<String>,
+}
 
-    // Method 1: Look for paragraphs with heading styles that contain text
-    let heading_paragraph_patterns = vec![
-        r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="(Heading\d|berschrift\d|Title)"[^>]*/>.*?<w:t[^>]*>([^<]+)</w:t>.*?</w:p>"#,
-        r#"<w:p[^>]*>.*?<w:pStyle[^>]*w:val="(Heading\d)"[^>]*/>.*?</w:p>"#,
-    ];
+impl HeaderScan {
+    fn add(&mut self, paragraph: &DocxParagraph) {
+        let is_new_header = |headers: &Vec<String>, text: &str| !headers.iter().any(|h| same_heading(h, text));
 
-    for pattern in heading_paragraph_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            for captures in regex.captures_iter(document_xml) {
-                // Try to get the text content
-                if let Some(text) = captures.get(2) {
-                    let header_text = text.as_str().trim().to_string();
-                    if !header_text.is_empty() && !headers.contains(&header_text) {
-                        println!("✅ Found header from style: {}", header_text);
-                        headers.push(header_text);
-                    }
-                }
+        // Method 1: Paragraphs with heading styles that contain text
+        if paragraph.heading_level().is_some() {
+            let header_text = paragraph.text().trim().to_string();
+            if !header_text.is_empty() && is_new_header(&self.styled, &header_text) {
+                self.styled.push(header_text);
             }
         }
-    }
 
-    // Method 2: Look for known medical report headers in the document text
-    // Extract all text elements and check if any match known headers
-    if let Ok(text_regex) = Regex::new(r#"<w:t[^>]*>([^<]+)</w:t>"#) {
-        for captures in text_regex.captures_iter(document_xml) {
-            if let Some(text) = captures.get(1) {
-                let text_content = text.as_str().trim();
-
-                // Check if this text matches any known header
-                for known_header in &known_headers {
-                    if text_content.eq_ignore_ascii_case(known_header) ||
-                       text_content.to_uppercase() == known_header.to_uppercase() {
-                        let header_text = text_content.to_string();
-                        if !headers.contains(&header_text) &&
-                           !headers.iter().any(|h| h.eq_ignore_ascii_case(&header_text)) {
-                            println!("✅ Found known header: {}", header_text);
-                            headers.push(header_text);
-                        }
-                    }
-                }
+        // Method 2: Known medical report headers and all-caps lines among the text runs
+        // (bold runs need no own pass: only known headers are taken from them, which this finds anyway)
+        for run in &paragraph.runs {
+            let text_content = run.trim();
 
-                // Also check for all-caps text that looks like a header (short, no punctuation)
-                if text_content.len() >= 4 &&
-                   text_content.len() <= 50 &&
-                   text_content.chars().all(|c| c.is_uppercase() || c.is_whitespace()) &&
-                   !text_content.contains('.') &&
-                   !text_content.contains(',') {
-                    let header_text = text_content.to_string();
-                    if !headers.contains(&header_text) {
-                        println!("✅ Found uppercase header: {}", header_text);
-                        headers.push(header_text);
-                    }
+            // Spelling variants ("Eigen-Anamnese:", "Körperliche Untersuchung") match via the canonical form
+            let canonical = canonical_heading(text_content);
+            if KNOWN_HEADERS.iter().any(|known| canonical_heading(known) == canonical) && is_new_header(&self.from_runs, text_content) {
+                self.from_runs.push(text_content.to_string());
+            }
+
+            // Also check for all-caps text that looks like a header (short, no punctuation)
+            if text_content.len() >= 4 &&
+               text_content.len() <= 50 &&
+               text_content.chars().all(|c| c.is_uppercase() || c.is_whitespace()) &&
+               !text_content.contains('.') &&
+               !text_content.contains(',') {
+                let header_text = text_content.to_string();
+                if is_new_header(&self.from_runs, &header_text) {
+                    self.from_runs.push(header_text);
                 }
             }
         }
     }
 
-    // Method 3: Look for bold paragraphs that might be headers
-    // These are often marked with <w:b/> or <w:b w:val="true"/>
-    let bold_patterns = vec![
-        r#"<w:p[^>]*>.*?<w:b[^>]*/?>.*?<w:t[^>]*>([^<]+)</w:t>.*?</w:p>"#,
-        r#"<w:r[^>]*>.*?<w:b[^>]*/?>.*?<w:t[^>]*>([^<]+)</w:t>.*?</w:r>"#,
-    ];
-
-    for pattern in bold_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            for captures in regex.captures_iter(document_xml) {
-                if let Some(text) = captures.get(1) {
-                    let text_content = text.as_str().trim();
-
-                    // Check if it's a short text that could be a header
-                    if text_content.len() >= 4 && text_content.len() <= 50 {
-                        // Check against known headers
-                        for known_header in &known_headers {
-                            if text_content.eq_ignore_ascii_case(known_header) {
-                                let header_text = text_content.to_string();
-                                if !headers.contains(&header_text) &&
-                                   !headers.iter().any(|h| h.eq_ignore_ascii_case(&header_text)) {
-                                    println!("✅ Found bold header: {}", header_text);
-                                    headers.push(header_text);
-                                }
-                            }
-                        }
-                    }
-                }
+    fn finish(self, trace: &mut AnalysisTrace) -> Vec<String> {
+        let styled_count = self.styled.len();
+        let mut headers = self.styled;
+        for header in self.from_runs {
+            if !headers.iter().any(|h| same_heading(h, &header)) {
+                headers.push(header);
             }
         }
-    }
 
-    println!("📊 Total headers extracted: {}", headers.len());
-    for (i, header) in headers.iter().enumerate() {
-        println!("   {}: {}", i + 1, header);
+        trace.chosen("headers_found", format!("{} headers, {} from heading paragraphs, {} known or all-caps text runs",
+            headers.len(), styled_count, headers.len() - styled_count));
+        for (i, header) in headers.iter().enumerate() {
+            let source = if i < styled_count { "heading paragraph" } else { "text run" };
+            trace.matched("headers_found", format!("{} ({})", header, source));
+        }
+        headers
     }
-
-    headers
 }
 
 /// Extract font family from a style definition
 fn extract_font_from_style(style_content: &str) -> String {
-    let font_patterns = vec![
-        r#"<w:rFonts[^>]*w:ascii="([^"]+)""#,
-        r#"<w:name[^>]*w:val="([^"]+)""#,
-    ];
-
-    for pattern in font_patterns {
-        if let Ok(regex) = Regex::new(pattern) {
-            if let Some(captures) = regex.captures(style_content) {
-                if let Some(font) = captures.get(1) {
-                    return font.as_str().to_string();
-                }
-            }
-        }
-    }
-
-    "Arial".to_string() // fallback
+    [&*RUN_FONT, &*STYLE_NAME].iter()
+        .find_map(|regex| regex.captures(style_content))
+        .map(|captures| captures[1].to_string())
+        .unwrap_or_else(|| "Arial".to_string()) // fallback
 }
 
 /// Extract font size from a style definition
 fn extract_size_from_style(style_content: &str) -> f32 {
-    if let Ok(regex) = Regex::new(r#"<w:sz[^>]*w:val="([^"]+)""#) {
-        if let Some(captures) = regex.captures(style_content) {
-            if let Some(size_str) = captures.get(1) {
-                // Word uses half-points, so divide by 2
-                if let Ok(size) = size_str.as_str().parse::<f32>() {
-                    return size / 2.0;
-                }
-            }
-        }
-    }
-
-    16.0 // fallback
+    // Word uses half-points, so divide by 2
+    RUN_SIZE.captures(style_content)
+        .and_then(|captures| captures[1].parse::<f32>().ok())
+        .map(|size| size / 2.0)
+        .unwrap_or(16.0) // fallback
 }
 
 /// Extract font family from a paragraph
@@ -882,26 +1723,24 @@ fn extract_size_from_paragraph(paragraph_content: &str) -> f32 {
 }
 
 /// Extract header and footer information from DOCX
-fn extract_header_footer_info(document_xml: &str, archive: &mut ZipArchive<BufReader<fs::File>>) -> HeaderFooterInfo {
-    println!("🔍 Extracting header/footer information...");
-
+/// `doc_has_header`/`doc_has_footer`: document.xml references a header/footer part
+fn extract_header_footer_info(
+    doc_has_header: bool,
+    doc_has_footer: bool,
+    archive: &mut ZipArchive<BufReader<fs::File>>,
+    theme: &ThemeColors,
+    trace: &mut AnalysisTrace,
+) -> Result<HeaderFooterInfo, String> {
     let mut has_header = false;
     let mut has_footer = false;
     let mut header_content = String::new();
     let mut footer_content = String::new();
     let mut header_style = None;
     let mut footer_style = None;
+    let mut header_candidates = Vec::new();
+    let mut chosen_header: Option<(String, u8)> = None;
 
-    // Check for header/footer references in document.xml first
-    let doc_has_header = document_xml.contains("<w:headerReference") ||
-                        document_xml.contains("w:hdr") ||
-                        document_xml.contains("headerReference");
-    let doc_has_footer = document_xml.contains("<w:footerReference") ||
-                        document_xml.contains("w:ftr") ||
-                        document_xml.contains("footerReference");
-
-    println!("📋 Header reference found in document.xml: {}", doc_has_header);
-    println!("📋 Footer reference found in document.xml: {}", doc_has_footer);
+    trace.info("header_footer", format!("document.xml references a header: {}, a footer: {}", doc_has_header, doc_has_footer));
 
     // Collect all file names first to avoid borrowing conflicts
     let mut file_names = Vec::new();
@@ -911,11 +1750,9 @@ fn extract_header_footer_info(document_xml: &str, archive: &mut ZipArchive<BufRe
         }
     }
 
-    println!("📁 Found {} files in DOCX archive", file_names.len());
-
     // Prioritize header files - main header first, then fallbacks
     let mut header_files: Vec<(String, u8)> = file_names.iter()
-        .filter(|name| name.contains("header") || name.starts_with("word/header"))
+        .filter(|name| name.contains("header") && name.ends_with(".xml"))
         .map(|name| {
             let priority = if name == "word/header1.xml" { 1 }
                           else if name.contains("header1") { 2 }
@@ -930,107 +1767,110 @@ fn extract_header_footer_info(document_xml: &str, archive: &mut ZipArchive<BufRe
 
     // Now search for header files in priority order
     for (file_name, priority) in header_files {
-        println!("🔍 Checking header file: {} (priority: {})", file_name, priority);
-
         // Try to extract content and style from header file
-        if let Ok(mut header_file) = archive.by_name(&file_name) {
-            let mut content = String::new();
-            if header_file.read_to_string(&mut content).is_ok() {
-                // Extract text content from header XML
-                let extracted_content = extract_text_from_xml(&content);
-
-                // Only consider it a real header if it has actual content
-                if !extracted_content.trim().is_empty() {
-                    // Only update if we haven't found a header yet, or this is higher priority
-                    if !has_header || priority <= 2 {
-                        has_header = true;
-                        header_content = extracted_content;
-                        println!("✅ Using header from {}: {}...",
-                            file_name, header_content.chars().take(50).collect::<String>());
-
-                        // Extract style information from header XML
-                        let new_style = extract_header_footer_style(&content, "header");
-                        header_style = Some(new_style);
-                        if let Some(ref style) = header_style {
-                            println!("🎨 Selected header style: {} {}pt {} {}",
-                                style.font_family, style.font_size, style.font_weight, style.alignment);
-                        }
-
-                        // If this is the main header, we're done
-                        if priority == 1 {
-                            println!("🎯 Found main header file, stopping search");
-                            break;
-                        }
-                    } else {
-                        println!("⏭️ Skipping {} - already have higher priority header", file_name);
-                    }
+        if let Ok(header_file) = archive.by_name(&file_name) {
+            let declared_size = header_file.size();
+            let content = read_capped(header_file, declared_size, &file_name)?;
+            // Extract text content from header XML
+            let extracted_content = extract_text_from_xml(&content);
+            let candidate_style = (!extracted_content.trim().is_empty())
+                .then(|| extract_header_footer_style(&content, theme));
+            header_candidates.push(HeaderCandidate {
+                part_name: file_name.clone(),
+                content_preview: extracted_content.chars().take(HEADER_PREVIEW_CHARS).collect(),
+                priority,
+                chosen: false,
+                content: extracted_content.clone(),
+                style: candidate_style.clone(),
+            });
+
+            // Only consider it a real header if it has actual content
+            if !extracted_content.trim().is_empty() {
+                // Only update if we haven't found a header yet, or this is higher priority;
+                // the main header is never replaced
+                let main_header_chosen = chosen_header.as_ref().is_some_and(|(_, p)| *p == 1);
+                if !has_header || (priority <= 2 && !main_header_chosen) {
+                    has_header = true;
+                    chosen_header = Some((file_name.clone(), priority));
+                    header_content = extracted_content;
+                    trace.matched("header", format!("{} (priority {}): {}",
+                        file_name, priority, header_content.chars().take(50).collect::<String>()));
+
+                    // Style information extracted from header XML
+                    header_style = candidate_style;
                 } else {
-                    println!("⚠️ Header file exists but has no content: {}", file_name);
+                    trace.rejected("header", format!("{} (priority {}): a header of higher priority is already chosen", file_name, priority));
                 }
+            } else {
+                trace.rejected("header", format!("{}: no text", file_name));
             }
         }
     }
 
+    if let Some((chosen_part, _)) = &chosen_header {
+        for candidate in header_candidates.iter_mut() {
+            candidate.chosen = candidate.part_name == *chosen_part;
+        }
+    }
+    let filled_headers = header_candidates.iter().filter(|c| !c.content_preview.trim().is_empty()).count();
+    if filled_headers > 1 {
+        trace.info("header", format!("{} header parts have text; the choice can be changed via header_candidates", filled_headers));
+    }
+
     // Now search for footer files
     for file_name in &file_names {
         if file_name.contains("footer") || file_name.starts_with("word/footer") {
-            println!("🔍 Checking footer file: {}", file_name);
-
             // Try to extract content and style from footer file
-            if let Ok(mut footer_file) = archive.by_name(file_name) {
-                let mut content = String::new();
-                if footer_file.read_to_string(&mut content).is_ok() {
-                    // Extract text content from footer XML
-                    let extracted_content = extract_text_from_xml(&content);
-
-                    // Only consider it a real footer if it has meaningful content (not just page numbers)
-                    let trimmed_content = extracted_content.trim();
-                    if !trimmed_content.is_empty() && !is_just_page_number(trimmed_content) {
-                        has_footer = true;
-                        footer_content = extracted_content;
-                        println!("✅ Found real footer with content: {}...",
-                            footer_content.chars().take(50).collect::<String>());
-
-                        // Extract style information from footer XML
-                        footer_style = Some(extract_header_footer_style(&content, "footer"));
-                        if let Some(ref style) = footer_style {
-                            println!("🎨 Footer style: {} {}pt {} {}",
-                                style.font_family, style.font_size, style.font_weight, style.alignment);
-                        }
-                    } else {
-                        println!("⚠️ Footer file exists but only contains page numbers or empty content: {}", file_name);
-                    }
+            if let Ok(footer_file) = archive.by_name(file_name) {
+                let declared_size = footer_file.size();
+                let content = read_capped(footer_file, declared_size, file_name)?;
+                // Extract text content from footer XML
+                let extracted_content = extract_text_from_xml(&content);
+
+                // Only consider it a real footer if it has meaningful content (not just page numbers)
+                let trimmed_content = extracted_content.trim();
+                if !trimmed_content.is_empty() && !is_just_page_number(trimmed_content) {
+                    has_footer = true;
+                    footer_content = extracted_content;
+                    trace.matched("footer", format!("{}: {}", file_name, footer_content.chars().take(50).collect::<String>()));
+
+                    // Extract style information from footer XML
+                    footer_style = Some(extract_header_footer_style(&content, theme));
+                } else {
+                    trace.rejected("footer", format!("{}: empty or only a page number", file_name));
                 }
             }
         }
     }
 
-    // Only use document.xml references as a fallback if we found references but no actual content
-    if doc_has_header && !has_header {
-        println!("📋 Header reference found in document.xml but no content extracted");
-        // Don't automatically set has_header = true without content
+    // A document.xml reference without any text in the part does not count as a header/footer
+    let describe = |part: &str, style: &Option<HeaderFooterStyle>| match style {
+        Some(style) => format!("{}: {} {}pt {} {} {}", part, style.font_family, style.font_size, style.font_weight, style.color, style.alignment),
+        None => part.to_string(),
+    };
+    match &chosen_header {
+        Some((part, _)) => trace.chosen("header", describe(part, &header_style)),
+        None => trace.defaulted("header", "no header part with text"),
     }
-    if doc_has_footer && !has_footer {
-        println!("📋 Footer reference found in document.xml but no content extracted");
-        // Don't automatically set has_footer = true without content
+    if has_footer {
+        trace.chosen("footer", describe("last footer part with text", &footer_style));
+    } else {
+        trace.defaulted("footer", "no footer part with text besides page numbers");
     }
 
-    println!("🎯 Final header/footer detection: Header={}, Footer={}", has_header, has_footer);
-
-    HeaderFooterInfo {
+    Ok(HeaderFooterInfo {
         has_header,
         has_footer,
         header_content,
         footer_content,
         header_style,
         footer_style,
-    }
+        header_candidates,
+    })
 }
 
 /// Extract style information from header/footer XML content
-fn extract_header_footer_style(xml_content: &str, element_type: &str) -> HeaderFooterStyle {
-    println!("🎨 Extracting {} style information...", element_type);
-
+fn extract_header_footer_style(xml_content: &str, theme: &ThemeColors) -> HeaderFooterStyle {
     // Extract font family from run properties (w:rPr > w:rFonts)
     let font_family = extract_font_from_style(xml_content);
 
@@ -1045,14 +1885,11 @@ fn extract_header_footer_style(xml_content: &str, element_type: &str) -> HeaderF
     };
 
     // Extract color (w:rPr > w:color)
-    let color = extract_color_from_style(xml_content);
+    let color = extract_color_from_style(xml_content, theme);
 
     // Extract alignment from paragraph properties (w:pPr > w:jc)
     let alignment = extract_alignment_from_xml(xml_content);
 
-    println!("🎨 Extracted {} style: {} {}pt {} {} {}",
-        element_type, font_family, font_size, font_weight, color, alignment);
-
     HeaderFooterStyle {
         font_family,
         font_size,
@@ -1065,20 +1902,15 @@ fn extract_header_footer_style(xml_content: &str, element_type: &str) -> HeaderF
 /// Extract alignment from XML content
 fn extract_alignment_from_xml(xml_content: &str) -> String {
     // Look for paragraph justification (w:jc w:val="...")
-    if let Ok(regex) = Regex::new(r#"<w:jc[^>]*w:val="([^"]*)"[^>]*/?>"#) {
-        if let Some(capture) = regex.captures(xml_content) {
-            if let Some(alignment) = capture.get(1) {
-                let alignment_value = alignment.as_str();
-                println!("📐 Found alignment: {}", alignment_value);
-                return match alignment_value {
-                    "left" => "left".to_string(),
-                    "right" => "right".to_string(),
-                    "center" => "center".to_string(),
-                    "both" | "distribute" => "justify".to_string(),
-                    _ => alignment_value.to_string(),
-                };
-            }
-        }
+    if let Some(capture) = JUSTIFICATION.captures(xml_content) {
+        let alignment_value = &capture[1];
+        return match alignment_value {
+            "left" => "left".to_string(),
+            "right" => "right".to_string(),
+            "center" => "center".to_string(),
+            "both" | "distribute" => "justify".to_string(),
+            _ => alignment_value.to_string(),
+        };
     }
     "left".to_string() // default
 }
@@ -1093,51 +1925,513 @@ fn is_just_page_number(content: &str) -> bool {
     }
 
     // Check if it's a simple page number pattern like "Page 1", "Seite 1", etc.
-    if let Ok(regex) = Regex::new(r"^(page|seite|p\.)\s*\d+$") {
-        if regex.is_match(&trimmed.to_lowercase()) {
-            return true;
-        }
+    if PAGE_NUMBER_LABEL.is_match(&trimmed.to_lowercase()) {
+        return true;
     }
 
     // Check if it's just "- N -" pattern
-    if let Ok(regex) = Regex::new(r"^-\s*\d+\s*-$") {
-        if regex.is_match(trimmed) {
-            return true;
-        }
-    }
-
-    false
+    DASHED_PAGE_NUMBER.is_match(trimmed)
 }
 
-/// Extract color from style content
-fn extract_color_from_style(style_content: &str) -> String {
-    // Look for color definitions (w:color w:val="...")
-    if let Ok(regex) = Regex::new(r#"<w:color[^>]*w:val="([^"]*)"[^>]*/?>"#) {
-        if let Some(capture) = regex.captures(style_content) {
-            if let Some(color) = capture.get(1) {
-                let color_value = color.as_str();
-                if color_value != "auto" && !color_value.is_empty() {
-                    println!("🎨 Found color: #{}", color_value);
-                    return format!("#{}", color_value);
-                }
-            }
-        }
+/// Extract color from style content; theme references are resolved, "auto" is black
+fn extract_color_from_style(style_content: &str, theme: &ThemeColors) -> String {
+    // First color definition (w:color w:val="..." w:themeColor="...") that resolves
+    if let Some(color) = COLOR_ELEMENT.find_iter(style_content).find_map(|m| resolve_color_element(m.as_str(), theme)) {
+        return color;
     }
     "#000000".to_string() // default black
 }
 
+/// Heading styles of a written DOCX whose color is too light for white paper; the colors are kept,
+/// the warnings let the user catch a template extraction mistake before printing
+pub(crate) fn heading_contrast_warnings(docx_path: &Path) -> Result<Vec<String>, String> {
+    let file = fs::File::open(docx_path)
+        .map_err(|e| format!("Failed to open DOCX: {}", e))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+    let styles_xml = extract_styles_xml(&mut archive)?;
+    let styles = StyleSheet::parse(&styles_xml);
+    let theme = extract_theme_colors(&mut archive)?;
+
+    let mut style_ids: Vec<&str> = styles.styles.keys().copied().filter(|id| HEADING_STYLE_ID.is_match(id)).collect();
+    style_ids.sort();
+    Ok(style_ids.into_iter()
+        .filter_map(|id| {
+            let color = COLOR_ELEMENT.find_iter(styles.style(id)?).find_map(|m| resolve_color_element(m.as_str(), &theme))?;
+            let name = STYLE_NAME.captures(styles.style(id)?).map(|c| c[1].to_string()).unwrap_or_else(|| id.to_string());
+            low_contrast_warning(&format!("Formatvorlage \"{}\"", name), &color)
+        })
+        .collect())
+}
+
 /// Extract text content from XML (simplified)
 fn extract_text_from_xml(xml_content: &str) -> String {
     // Simple text extraction - removes XML tags and extracts text content
-    if let Ok(regex) = Regex::new(r"<w:t[^>]*>([^<]*)</w:t>") {
-        let mut text_parts = Vec::new();
-        for capture in regex.captures_iter(xml_content) {
-            if let Some(text) = capture.get(1) {
-                text_parts.push(text.as_str().trim());
+    let text_parts: Vec<&str> = TEXT_RUN.captures_iter(xml_content)
+        .filter_map(|capture| capture.get(1))
+        .map(|text| text.as_str().trim())
+        .collect();
+    text_parts.join(" ").trim().to_string()
+}
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Write a minimal DOCX archive with the given document.xml body and optional extra parts
+    pub(crate) fn write_test_docx(path: &PathBuf, body_xml: &str, extra_parts: &[(&str, &str)]) {
+        let file = fs::File::create(path).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default();
+
+        zip.start_file("[Content_Types].xml", options).unwrap();
+        zip.write_all(br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="xml" ContentType="application/xml"/></Types>"#).unwrap();
+
+        zip.start_file("word/document.xml", options).unwrap();
+        let document = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>{}</w:body></w:document>"#,
+            body_xml
+        );
+        zip.write_all(document.as_bytes()).unwrap();
+
+        for (name, content) in extra_parts {
+            zip.start_file(*name, options).unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+
+        zip.finish().unwrap();
+    }
+
+    pub(crate) fn test_docx_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("gutachten_test_{}_{}.docx", name, uuid::Uuid::new_v4().simple()))
+    }
+
+    #[test]
+    fn test_aggregate_formatting_uses_majority_font() {
+        let body = |font: &str, half_points: u32, line: u32| format!(
+            r#"<w:p><w:pPr><w:spacing w:line="{}"/></w:pPr><w:r><w:rPr><w:rFonts w:ascii="{}"/><w:sz w:val="{}"/></w:rPr><w:t>Befund</w:t></w:r></w:p>"#,
+            line, font, half_points
+        );
+        let docs = [("Arial", 24, 360), ("Calibri", 28, 240), ("Arial", 22, 276)];
+        let mut paths: Vec<PathBuf> = Vec::new();
+        for (i, (font, size, line)) in docs.iter().enumerate() {
+            let path = test_docx_path(&format!("aggregate{}", i));
+            write_test_docx(&path, &body(font, *size, *line), &[]);
+            paths.push(path);
+        }
+        let broken = test_docx_path("aggregate_broken");
+        fs::write(&broken, b"not a zip").unwrap();
+        paths.push(broken);
+
+        let path_strings: Vec<String> = paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+        let info = aggregate_formatting_from_files(&path_strings).unwrap();
+        for path in &paths {
+            let _ = fs::remove_file(path);
+        }
+
+        assert_eq!(info.font_family, "Arial");
+        assert_eq!(info.font_size_pt, 12.0);
+        assert_eq!(info.line_spacing, 1.15);
+    }
+
+    #[test]
+    fn test_hanging_indent_is_extracted_in_cm() {
+        let hanging = r#"<w:p><w:pPr><w:ind w:left="720" w:hanging="720"/><w:tabs><w:tab w:val="left" w:pos="720"/></w:tabs></w:pPr><w:r><w:t>1.	Lumbago</w:t></w:r></w:p>"#;
+        let body = format!(
+            "{}{}{}<w:p><w:r><w:t>Fließtext ohne Einzug</w:t></w:r></w:p>",
+            hanging, hanging, hanging
+        );
+        let path = test_docx_path("indent");
+        write_test_docx(&path, &body, &[]);
+
+        let info = analyze_docx_file(&path, "indent-test").unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(info.indentation.hanging_indent, 1.27);
+        assert_eq!(info.indentation.left_indent, 1.27);
+        assert_eq!(info.indentation.first_line_indent, 0.0);
+        assert_eq!(info.indentation.tab_stops, vec![1.27]);
+        assert_eq!(info.indentation.indented_paragraphs, 3);
+        assert_eq!(info.indentation.total_paragraphs, 4);
+    }
+
+    #[test]
+    fn test_document_id_is_stable_for_same_contents() {
+        let dir = std::env::temp_dir().join(format!("gutachten_doc_id_{}", uuid::Uuid::new_v4().simple()));
+        fs::create_dir_all(&dir).unwrap();
+        let (first, copy, other) = (dir.join("a.docx"), dir.join("kopie.docx"), dir.join("b.docx"));
+        fs::write(&first, b"PK gutachten").unwrap();
+        fs::write(&copy, b"PK gutachten").unwrap();
+        fs::write(&other, b"PK anderes gutachten").unwrap();
+
+        let id = document_id_for(&first).unwrap();
+        assert_eq!(document_id_for(&first).unwrap(), id);
+        assert_eq!(document_id_for(&copy).unwrap(), id);
+        assert_ne!(document_id_for(&other).unwrap(), id);
+        assert!(id.starts_with("doc_") && id.len() == 20);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_analysis_is_cached_by_hash_and_version() {
+        let path = test_docx_path("analysis_cache");
+        write_test_docx(&path, r#"<w:p><w:r><w:t>BEFUND</w:t></w:r></w:p>"#, &[]);
+        let cache_dir = std::env::temp_dir().join(format!("gutachten_analysis_cache_{}", uuid::Uuid::new_v4().simple()));
+
+        let (first, hit) = analyze_docx_file_cached(&path, "doc-1", &cache_dir).unwrap();
+        assert!(!hit);
+        let hash = sha256_file(&path).unwrap();
+        let entry_path = cache_dir.join(format!("{}.json", hash));
+        assert!(entry_path.exists());
+
+        // The unchanged file hits the cache and yields identical data
+        let (second, hit) = analyze_docx_file_cached(&path, "doc-1", &cache_dir).unwrap();
+        assert!(hit);
+        assert_eq!(serde_json::to_value(&second).unwrap(), serde_json::to_value(&first).unwrap());
+
+        // A hit returns the stored result under the new id instead of re-parsing
+        let mut entry: serde_json::Value = serde_json::from_str(&fs::read_to_string(&entry_path).unwrap()).unwrap();
+        entry["style_info"]["font_family"] = serde_json::Value::from("Aus dem Cache");
+        fs::write(&entry_path, entry.to_string()).unwrap();
+        let (cached, _) = analyze_docx_file_cached(&path, "doc-2", &cache_dir).unwrap();
+        assert_eq!(cached.font_family, "Aus dem Cache");
+        assert_eq!(cached.document_id, "doc-2");
+        assert_eq!(cached.headers_found, first.headers_found);
+
+        // Entries of another analyzer version are ignored and replaced
+        entry["analyzer_version"] = serde_json::Value::from(ANALYZER_VERSION + 1);
+        fs::write(&entry_path, entry.to_string()).unwrap();
+        let (reparsed, hit) = analyze_docx_file_cached(&path, "doc-3", &cache_dir).unwrap();
+        assert!(!hit);
+        assert_eq!(reparsed.font_family, first.font_family);
+
+        // A content change misses the cache
+        write_test_docx(&path, r#"<w:p><w:r><w:t>ANAMNESE</w:t></w:r></w:p>"#, &[]);
+        assert!(!analyze_docx_file_cached(&path, "doc-4", &cache_dir).unwrap().1);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir_all(&cache_dir);
+    }
+
+    #[test]
+    fn test_trace_explains_font_choice_and_is_cached() {
+        use crate::analysis_trace::TraceOutcome;
+        let path = test_docx_path("analysis_trace");
+        // The ascii font wins over the differing hAnsi font, which is traced as rejected
+        write_test_docx(&path, r#"<w:p><w:r><w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Times New Roman"/><w:sz w:val="22"/></w:rPr><w:t>BEFUND</w:t></w:r></w:p>"#, &[]);
+        let cache_dir = std::env::temp_dir().join(format!("gutachten_analysis_trace_{}", uuid::Uuid::new_v4().simple()));
+
+        let (info, _) = analyze_docx_file_cached(&path, "doc-1", &cache_dir).unwrap();
+        let trace = info.trace.expect("fresh analysis carries its trace");
+        let font_entries: Vec<_> = trace.entries.iter().filter(|e| e.step == "font_family").collect();
+        assert!(font_entries.iter().any(|e| e.outcome == TraceOutcome::Chosen && e.detail.starts_with("Arial")));
+        assert!(font_entries.iter().any(|e| e.outcome == TraceOutcome::Rejected && e.detail.contains("Times New Roman")));
+        assert!(trace.entries.iter().any(|e| e.step == "font_size" && e.outcome == TraceOutcome::Chosen && e.detail.starts_with("11pt")));
+
+        // The cache entry keeps the trace next to, not inside, the style info; a hit returns it again
+        let entry: serde_json::Value = serde_json::from_str(
+            &fs::read_to_string(cache_dir.join(format!("{}.json", sha256_file(&path).unwrap()))).unwrap()
+        ).unwrap();
+        assert!(entry["style_info"].get("trace").is_none());
+        assert_eq!(entry["trace"]["entries"].as_array().unwrap().len(), trace.entries.len());
+        let (cached, hit) = analyze_docx_file_cached(&path, "doc-1", &cache_dir).unwrap();
+        assert!(hit);
+        assert_eq!(cached.trace.unwrap().entries, trace.entries);
+
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_dir_all(&cache_dir);
+    }
+
+    #[test]
+    fn test_page_and_section_breaks_are_counted() {
+        let body = concat!(
+            r#"<w:p><w:r><w:t>Seite eins</w:t></w:r><w:r><w:br w:type="page"/></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>Seite zwei</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:sectPr><w:type w:val="nextPage"/></w:sectPr></w:pPr></w:p>"#,
+            r#"<w:p><w:r><w:lastRenderedPageBreak/><w:t>Anlage</w:t><w:br/></w:r></w:p>"#,
+            r#"<w:sectPr><w:pgSz w:w="11906" w:h="16838"/></w:sectPr>"#,
+        );
+        let path = test_docx_path("breaks");
+        write_test_docx(&path, body, &[]);
+
+        let info = parse_docx_file(&path, "breaks").unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(info.page_break_count, 1);
+        assert_eq!(info.section_break_count, 1);
+    }
+
+    const SPEED_SECTIONS: [&str; 5] = ["ANAMNESE", "BEFUND", "DIAGNOSE", "BEURTEILUNG", "EMPFEHLUNG"];
+
+    /// Five sections of 19 body paragraphs each
+    fn write_hundred_paragraph_docx(name: &str) -> PathBuf {
+        let sections = SPEED_SECTIONS;
+        let mut body = String::new();
+        for i in 0..100 {
+            if i % 20 == 0 {
+                body.push_str(&format!(
+                    r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>{}</w:t></w:r></w:p>"#,
+                    sections[i / 20]
+                ));
+            } else {
+                body.push_str(&format!(
+                    r#"<w:p><w:pPr><w:jc w:val="both"/><w:ind w:firstLine="284"/></w:pPr><w:r><w:rPr><w:rFonts w:ascii="Arial"/><w:sz w:val="22"/></w:rPr><w:t>Absatz {} mit Befundtext über mehrere Zeilen.</w:t></w:r></w:p>"#,
+                    i
+                ));
             }
         }
-        text_parts.join(" ").trim().to_string()
-    } else {
-        String::new()
+        let styles = r#"<w:styles><w:style w:type="paragraph" w:styleId="Heading1"><w:pPr><w:jc w:val="left"/><w:spacing w:before="240"/></w:pPr><w:rPr><w:b/><w:sz w:val="28"/></w:rPr></w:style></w:styles>"#;
+        let path = test_docx_path(name);
+        write_test_docx(&path, &body, &[("word/styles.xml", styles)]);
+        path
+    }
+
+    #[test]
+    fn test_hundred_paragraph_document_is_analyzed() {
+        let path = write_hundred_paragraph_docx("analysis_hundred");
+        let info = parse_docx_file(&path, "hundred").unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(info.headers_found, SPEED_SECTIONS);
+        assert_eq!(info.alignments.body, "justify");
+        assert_eq!(info.alignments.heading, "left");
+        assert_eq!(info.indentation.total_paragraphs, 95);
+        assert_eq!(info.heading_styles[0].spacing_before, 12.0);
+    }
+
+    #[test]
+    #[ignore = "benchmark; run with --ignored on an idle release build"]
+    fn bench_hundred_paragraph_analysis() {
+        let path = write_hundred_paragraph_docx("analysis_speed");
+        let start = std::time::Instant::now();
+        parse_docx_file(&path, "speed").unwrap();
+        let elapsed = start.elapsed();
+        let _ = fs::remove_file(&path);
+
+        assert!(elapsed < std::time::Duration::from_secs(2), "analysis took {:?}", elapsed);
+    }
+
+    #[test]
+    fn test_all_filled_headers_are_reported_as_candidates() {
+        let header = |text: &str| format!(
+            r#"<w:hdr xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:p><w:r><w:t>{}</w:t></w:r></w:p></w:hdr>"#,
+            text
+        );
+        let (first_page, main) = (header("Praxis Dr. Muster – Erstseite"), header("Gutachten Mustermann"));
+        let path = test_docx_path("header_candidates");
+        write_test_docx(
+            &path,
+            r#"<w:p><w:r><w:t>BEFUND</w:t></w:r></w:p>"#,
+            &[("word/header2.xml", &first_page), ("word/header1.xml", &main), ("word/_rels/header1.xml.rels", "<Relationships/>")],
+        );
+
+        let info = parse_docx_file(&path, "headers").unwrap().header_footer_info;
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(info.header_content, "Gutachten Mustermann");
+        let candidates: Vec<_> = info.header_candidates.iter()
+            .map(|c| (c.part_name.as_str(), c.content_preview.as_str(), c.priority, c.chosen))
+            .collect();
+        assert_eq!(candidates, vec![
+            ("word/header1.xml", "Gutachten Mustermann", 1, true),
+            ("word/header2.xml", "Praxis Dr. Muster – Erstseite", 9, false),
+        ]);
+        assert_eq!(info.header_candidates[1].content, "Praxis Dr. Muster – Erstseite");
+        assert_eq!(info.header_candidates[0].style, info.header_style);
+        assert!(info.header_candidates[1].style.is_some());
+    }
+
+    fn test_style_info(font_family: &str, header_font: &str) -> DocumentStyleInfo {
+        DocumentStyleInfo {
+            document_id: font_family.to_lowercase(),
+            filename: format!("{}.docx", font_family),
+            analysis_date: String::new(),
+            font_family: font_family.to_string(),
+            font_size: 11.0,
+            line_spacing: 1.5,
+            paragraph_spacing_before: 0.0,
+            paragraph_spacing_after: 6.0,
+            heading_styles: Vec::new(),
+            text_alignment: "justify".to_string(),
+            page_margins: PageMargins { top: 2.5, bottom: 2.0, left: 2.5, right: 2.0 },
+            header_footer_info: HeaderFooterInfo {
+                has_header: true,
+                has_footer: false,
+                header_content: format!("Briefkopf {}", header_font),
+                footer_content: String::new(),
+                header_style: Some(HeaderFooterStyle {
+                    font_family: header_font.to_string(),
+                    font_size: 9.0,
+                    font_weight: "normal".to_string(),
+                    color: "000000".to_string(),
+                    alignment: "right".to_string(),
+                }),
+                footer_style: None,
+                header_candidates: Vec::new(),
+            },
+            style_summary: String::new(),
+            headers_found: Vec::new(),
+            indentation: IndentationInfo::default(),
+            alignments: RoleAlignments::default(),
+            source: "docx".to_string(),
+            confidence: 1.0,
+            page_break_count: 0,
+            section_break_count: 0,
+            used_heading_levels: Vec::new(),
+            heading_level_gaps: Vec::new(),
+            trace: None,
+        }
+    }
+
+    #[test]
+    fn test_composed_style_takes_header_and_body_from_different_templates() {
+        let mut letterhead = test_style_info("Times New Roman", "Helvetica");
+        letterhead.page_margins.top = 4.5;
+        let body = test_style_info("Arial", "Calibri");
+
+        let composed = compose_style_info(&letterhead, &body);
+        assert_eq!(composed.header_footer_info.header_style.unwrap().font_family, "Helvetica");
+        assert_eq!(composed.header_footer_info.header_content, "Briefkopf Helvetica");
+        assert_eq!(composed.page_margins.top, 4.5);
+        assert_eq!(composed.font_family, "Arial");
+        assert_eq!(composed.text_alignment, "justify");
+
+        assert!(load_style_template("../settings/startup.json").unwrap_err().starts_with("Ungültiger Vorlagenname"));
+    }
+
+    #[test]
+    fn test_heading_spacing_follows_based_on_chain() {
+        let styles = concat!(
+            r#"<w:styles><w:docDefaults><w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="259"/></w:pPr></w:pPrDefault></w:docDefaults>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Standard"><w:name w:val="Normal"/></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="GutachtenTitel"><w:basedOn w:val="Standard"/><w:pPr><w:spacing w:before="480" w:after="240"/></w:pPr></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="GutachtenTitel"/><w:pPr><w:keepNext/></w:pPr><w:rPr><w:b/><w:sz w:val="28"/></w:rPr></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Heading2"><w:basedOn w:val="Standard"/><w:pPr><w:spacing w:before="200"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>"#,
+            r#"</w:styles>"#,
+        );
+        let headings = extract_heading_styles(&HashMap::new(), &StyleSheet::parse(styles), &ThemeColors::default(), &mut AnalysisTrace::default());
+        let spacing = |level: u8| headings.iter().find(|h| h.level == level).map(|h| (h.spacing_before, h.spacing_after));
+        assert_eq!(spacing(1), Some((24.0, 12.0)));
+        // Before from the style itself, after from the paragraph defaults
+        assert_eq!(spacing(2), Some((10.0, 8.0)));
+
+        let no_defaults = styles.replace(r#"<w:spacing w:after="160" w:line="259"/>"#, "");
+        let headings = extract_heading_styles(&HashMap::new(), &StyleSheet::parse(&no_defaults), &ThemeColors::default(), &mut AnalysisTrace::default());
+        assert_eq!(headings.iter().find(|h| h.level == 2).map(|h| h.spacing_after), Some(DEFAULT_HEADING_SPACING_AFTER));
+    }
+
+    #[test]
+    fn test_used_heading_levels_report_gaps() {
+        // Heading2 is defined but never used; the custom style is a level-3 heading by name
+        let styles_xml = concat!(
+            r#"<w:styles><w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="GutachtenPunkt"><w:name w:val="Überschrift 3"/></w:style></w:styles>"#,
+        );
+        let document_xml = concat!(
+            r#"<w:body><w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>1. Anamnese</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="GutachtenPunkt"/></w:pPr><w:r><w:t>Vorgeschichte</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading2"/></w:pPr></w:p>"#,
+            r#"<w:p><w:r><w:t>Text</w:t></w:r></w:p></w:body>"#,
+        );
+        let styles = StyleSheet::parse(styles_xml);
+        let mut scan = DocumentScan::new(&styles);
+        scan.feed(document_xml, &styles);
+
+        let used: Vec<u8> = scan.used_heading_levels.into_iter().collect();
+        assert_eq!(used, vec![1, 3]);
+        assert_eq!(heading_level_gaps(&used), vec!["Überschriftenebene 2 fehlt zwischen Ebene 1 und 3".to_string()]);
+        assert!(heading_level_gaps(&[1, 2]).is_empty());
+        assert_eq!(heading_level_gaps(&[2]).len(), 1);
+    }
+
+    #[test]
+    fn test_theme_heading_colors_resolve_and_light_headings_warn() {
+        use std::io::Write;
+        let styles = concat!(
+            r#"<w:styles><w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:rPr><w:b/><w:color w:val="2F5496" w:themeColor="accent1"/></w:rPr></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:rPr><w:color w:val="F2F2F2"/></w:rPr></w:style></w:styles>"#,
+        );
+        let theme_xml = r#"<a:theme><a:themeElements><a:clrScheme name="Office"><a:accent1><a:srgbClr val="4472C4"/></a:accent1></a:clrScheme></a:themeElements></a:theme>"#;
+
+        let headings = extract_heading_styles(&HashMap::new(), &StyleSheet::parse(styles), &ThemeColors::parse(theme_xml), &mut AnalysisTrace::default());
+        let color = |level: u8| headings.iter().find(|h| h.level == level).map(|h| h.color.clone());
+        assert_eq!(color(1).as_deref(), Some("#4472C4"));
+        assert_eq!(color(2).as_deref(), Some("#F2F2F2"));
+
+        let path = std::env::temp_dir().join(format!("heading_colors_{}.docx", std::process::id()));
+        let mut writer = zip::ZipWriter::new(fs::File::create(&path).unwrap());
+        for (name, content) in [("word/styles.xml", styles), ("word/theme/theme1.xml", theme_xml)] {
+            writer.start_file(name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+        let warnings = heading_contrast_warnings(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].starts_with("Formatvorlage \"heading 2\" hat die Farbe #F2F2F2"), "{:?}", warnings);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_saved_template_matches_schema_and_old_files_get_defaults() {
+        let mut value = serde_json::to_value(test_style_info("Arial", "Calibri")).unwrap();
+        let validation = validate_template_value(&value);
+        assert_eq!(validation.kind, "style_info");
+        assert!(validation.report.valid, "{:?}", validation.report.issues);
+        assert!(validation.report.defaults_applied.is_empty());
+
+        let object = value.as_object_mut().unwrap();
+        object.remove("source");
+        object.remove("page_break_count");
+        let (style_info, report) = template_schema::parse_checked::<DocumentStyleInfo>(&value.to_string(), template_schema::DOCUMENT_STYLE_INFO).unwrap();
+        assert_eq!(style_info.source, "docx");
+        assert_eq!(report.defaults_applied, vec!["$.source = \"docx\"".to_string(), "$.page_break_count = 0".to_string()]);
+    }
+
+    /// document.xml of about 60 MB, generated while it is read instead of held in memory
+    struct GeneratedDocumentXml {
+        paragraphs_left: usize,
+        pending: Vec<u8>,
+    }
+
+    impl Read for GeneratedDocumentXml {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            if self.pending.is_empty() && self.paragraphs_left > 0 {
+                self.paragraphs_left -= 1;
+                self.pending = format!(
+                    r#"<w:p><w:pPr><w:jc w:val="both"/></w:pPr><w:r><w:t>Absatz {} des Befundberichts mit Fließtext.</w:t></w:r></w:p>"#,
+                    self.paragraphs_left
+                ).into_bytes();
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_large_document_xml_is_read_in_bounded_paragraph_chunks() {
+        let paragraphs = 560_000;
+        let source = GeneratedDocumentXml { paragraphs_left: paragraphs, pending: Vec::new() };
+
+        let (mut total_bytes, mut paragraph_ends, mut largest_chunk) = (0, 0, 0);
+        for chunk in XmlChunks::new(source, 0, MAX_DOCX_PART_BYTES) {
+            let chunk = chunk.unwrap();
+            assert!(chunk.ends_with("</w:p>"));
+            total_bytes += chunk.len();
+            paragraph_ends += chunk.matches("</w:p>").count();
+            largest_chunk = largest_chunk.max(chunk.len());
+        }
+
+        assert!(total_bytes > 60 * 1024 * 1024, "only {} bytes generated", total_bytes);
+        assert_eq!(paragraph_ends, paragraphs);
+        assert!(largest_chunk <= XML_CHUNK_BYTES + XML_READ_BLOCK_BYTES, "chunk of {} bytes", largest_chunk);
+
+        let small = GeneratedDocumentXml { paragraphs_left: 100, pending: Vec::new() };
+        let results: Vec<_> = XmlChunks::new(small, 0, 1024).collect();
+        assert!(results.last().unwrap().as_ref().unwrap_err().contains("größer als"));
+        assert!(XmlChunks::new(std::io::empty(), MAX_DOCX_PART_BYTES + 1, MAX_DOCX_PART_BYTES).next().unwrap().is_err());
+        assert!(read_capped(std::io::empty(), MAX_DOCX_PART_BYTES + 1, "word/header1.xml").unwrap_err().contains("header1.xml"));
+    }
+}
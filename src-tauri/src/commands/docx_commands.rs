@@ -4,8 +4,17 @@
 use tauri::{command, AppHandle};
 use tauri_plugin_dialog::DialogExt;
 use docx_rs::*;
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::commands::backup_commands::backup_before_overwrite;
+use crate::commands::document_commands::{load_cached_analysis, HeaderFooterInfo, HeaderFooterStyle, HeadingStyle};
+use crate::commands::signature_commands::{select_signature_blocks, signature_lines, SignatureBlock};
+use crate::commands::placeholder_commands::{placeholder_values, resolve_placeholders_in_docx, UnresolvedPlaceholderMode};
+use crate::heading_normalizer::KNOWN_SECTIONS;
+use crate::text_segmentation::{segment_lines, SegmentationOptions};
 
 /// Create a styled DOCX document from text with save dialog
 /// Includes optional document header (repeated text at top of every page)
@@ -17,7 +26,20 @@ pub async fn create_styled_docx(
     font_size: f32,
     line_spacing: f32,
     header_content: Option<String>,
+    settings: Option<StyledExportSettings>,
 ) -> Result<String, String> {
+    let settings = settings.unwrap_or_default();
+    if settings.first_line_indent_cm.is_some() && settings.hanging_indent_cm.is_some() {
+        return Err("Erstzeileneinzug und hängender Einzug können nicht gleichzeitig gesetzt werden".to_string());
+    }
+
+    let signature_blocks = if settings.append_signature.unwrap_or(false) {
+        select_signature_blocks(settings.signature_names.as_deref())?
+    } else {
+        Vec::new()
+    };
+    let placeholder_values = placeholder_values(settings.session_id.as_deref())?;
+
     // Generate default filename with timestamp
     let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
     let default_filename = format!("Gutachten_{}.docx", timestamp);
@@ -41,6 +63,238 @@ pub async fn create_styled_docx(
         None => return Err("Speichern abgebrochen".to_string())
     };
 
+    let options = StyledDocxOptions {
+        font_family,
+        font_size,
+        line_spacing,
+        header_content,
+        indentation: BodyIndentation {
+            left_indent_cm: settings.left_indent_cm,
+            first_line_indent_cm: settings.first_line_indent_cm,
+            hanging_indent_cm: settings.hanging_indent_cm,
+        },
+        alignment: ParagraphAlignments {
+            body: settings.body_alignment,
+            heading: settings.heading_alignment,
+            header: settings.header_alignment,
+        },
+        signature_blocks,
+        font_sizing: settings.font_sizing.unwrap_or_default(),
+        page_break_marker: settings.page_break_marker,
+        heading_spacing: settings.heading_spacing.unwrap_or_default(),
+        quote_style: QuoteStyle::default(),
+        header_style: None,
+    };
+    let doc = build_styled_docx(&text, &options);
+
+    // Write the document to file
+    backup_before_overwrite(&output_path)?;
+    let file = fs::File::create(&output_path)
+        .map_err(|e| format!("Fehler beim Erstellen der Datei: {}", e))?;
+
+    doc.build()
+        .pack(file)
+        .map_err(|e| format!("Fehler beim Schreiben des Dokuments: {}", e))?;
+
+    // {{FELD}} placeholders in header, text or signature; an aborted export leaves no file behind
+    if let Err(e) = resolve_placeholders_in_docx(&output_path, &placeholder_values, settings.unresolved_placeholders.unwrap_or_default()) {
+        let _ = fs::remove_file(&output_path);
+        return Err(e);
+    }
+
+    println!("DOCX created: {}", output_path.display());
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// Optional settings of `create_styled_docx`; anything left out keeps its default
+#[derive(Debug, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct StyledExportSettings {
+    pub first_line_indent_cm: Option<f32>,
+    pub hanging_indent_cm: Option<f32>,
+    pub left_indent_cm: Option<f32>,
+    pub body_alignment: Option<String>,
+    pub heading_alignment: Option<String>,
+    pub header_alignment: Option<String>,
+    pub append_signature: Option<bool>,
+    pub signature_names: Option<Vec<String>>,
+    pub session_id: Option<String>,
+    pub unresolved_placeholders: Option<UnresolvedPlaceholderMode>,
+    pub font_sizing: Option<FontSizing>,
+    pub page_break_marker: Option<String>,
+    pub heading_spacing: Option<Vec<HeadingSpacing>>,
+}
+
+/// Formatting applied by `create_styled_docx`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StyledDocxOptions {
+    pub font_family: String,
+    pub font_size: f32,
+    pub line_spacing: f32,
+    pub header_content: Option<String>,
+    pub indentation: BodyIndentation,
+    pub alignment: ParagraphAlignments,
+    #[serde(default)]
+    pub signature_blocks: Vec<SignatureBlock>,  // Appended after the text, in order
+    #[serde(default)]
+    pub font_sizing: FontSizing,
+    #[serde(default)]
+    pub page_break_marker: Option<String>,  // Inserts a page break like a form feed ("\f") does
+    #[serde(default)]
+    pub heading_spacing: Vec<HeadingSpacing>,  // Per level (index 0 = H1); empty = no heading spacing
+    #[serde(default)]
+    pub quote_style: QuoteStyle,
+    #[serde(default)]
+    pub header_style: Option<HeaderFooterStyle>,  // Look of an analyzed header; None = bold, body font
+}
+
+/// Lines starting with this are rendered as quote blocks (cited prior findings)
+pub const QUOTE_PREFIX: &str = "> ";
+
+/// Lines holding a manual page or section break of an imported report
+pub const PAGE_BREAK_MARKER: &str = "[SEITENUMBRUCH]";
+pub const SECTION_BREAK_MARKER: &str = "[ABSCHNITTSUMBRUCH]";
+
+pub fn is_break_marker(line: &str) -> bool {
+    matches!(line.trim(), PAGE_BREAK_MARKER | SECTION_BREAK_MARKER)
+}
+
+/// Look of quote blocks: indented, by default italic and 1 pt smaller than the body
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuoteStyle {
+    pub indent_cm: f32,
+    pub italic: bool,
+    pub size_delta: f32,  // pt smaller than the body text; 0 = same size
+}
+
+impl Default for QuoteStyle {
+    fn default() -> Self {
+        QuoteStyle { indent_cm: 1.0, italic: true, size_delta: 1.0 }
+    }
+}
+
+/// Space before and after a heading in pt
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct HeadingSpacing {
+    pub before: f32,
+    pub after: f32,
+}
+
+/// Heading spacing per level from an analyzed template; levels the template lacks get Word's 12/6
+pub fn heading_spacing_from_styles(styles: &[HeadingStyle]) -> Vec<HeadingSpacing> {
+    let max_level = styles.iter().map(|h| h.level).max().unwrap_or(0);
+    (1..=max_level)
+        .map(|level| styles.iter()
+            .find(|h| h.level == level)
+            .map(|h| HeadingSpacing { before: h.spacing_before, after: h.spacing_after })
+            .unwrap_or(HeadingSpacing { before: 12.0, after: 6.0 }))
+        .collect()
+}
+
+/// Heading and header sizes relative to the body font size (in pt), for matching a house style
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FontSizing {
+    #[serde(default = "default_heading_delta")]
+    pub heading_delta: f32,
+    #[serde(default = "default_header_delta")]
+    pub header_delta: f32,
+    #[serde(default)]
+    pub heading_sizes: Vec<f32>,  // Explicit sizes per level (index 0 = H1); overrides the delta
+}
+
+impl Default for FontSizing {
+    fn default() -> Self {
+        FontSizing {
+            heading_delta: default_heading_delta(),
+            header_delta: default_header_delta(),
+            heading_sizes: Vec::new(),
+        }
+    }
+}
+
+fn default_heading_delta() -> f32 {
+    2.0
+}
+
+fn default_header_delta() -> f32 {
+    -1.0
+}
+
+impl FontSizing {
+    /// Heading size in half-points (DOCX unit) for a heading level (1 = H1)
+    pub fn heading_half_points(&self, font_size: f32, level: usize) -> usize {
+        let size = self.heading_sizes.get(level.saturating_sub(1))
+            .copied()
+            .unwrap_or(font_size + self.heading_delta);
+        (size * 2.0) as usize
+    }
+
+    pub fn header_half_points(&self, font_size: f32) -> usize {
+        ((font_size + self.header_delta) * 2.0) as usize
+    }
+}
+
+/// Alignment per content role ("left", "center", "right", "justify"); None keeps the default
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ParagraphAlignments {
+    pub body: Option<String>,
+    pub heading: Option<String>,
+    pub header: Option<String>,
+}
+
+/// Map an alignment name to docx-rs. Justified text uses w:jc="both" (never "distribute",
+/// which would also stretch the last line of each paragraph).
+fn parse_alignment(alignment: &str) -> Option<AlignmentType> {
+    match alignment.trim().to_lowercase().as_str() {
+        "left" | "start" => Some(AlignmentType::Left),
+        "center" => Some(AlignmentType::Center),
+        "right" | "end" => Some(AlignmentType::Right),
+        "justify" | "both" | "distribute" => Some(AlignmentType::Both),
+        _ => None,
+    }
+}
+
+/// Body paragraph indentation in cm (None = no indentation). Headings stay unindented.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BodyIndentation {
+    pub left_indent_cm: Option<f32>,
+    pub first_line_indent_cm: Option<f32>,
+    pub hanging_indent_cm: Option<f32>,
+}
+
+/// Convert centimeters to twips (1/1440 inch) as used by DOCX
+fn cm_to_twips(cm: f32) -> i32 {
+    (cm / 2.54 * 1440.0).round() as i32
+}
+
+/// Apply the configured body indentation to a paragraph
+fn apply_body_indentation(paragraph: Paragraph, indentation: &BodyIndentation) -> Paragraph {
+    let special = indentation.hanging_indent_cm
+        .map(|cm| SpecialIndentType::Hanging(cm_to_twips(cm)))
+        .or_else(|| indentation.first_line_indent_cm.map(|cm| SpecialIndentType::FirstLine(cm_to_twips(cm))));
+    let left = indentation.left_indent_cm.map(cm_to_twips);
+
+    if left.is_none() && special.is_none() {
+        return paragraph;
+    }
+
+    paragraph.indent(left, special, None, None)
+}
+
+/// Build the styled document (header, headings, body paragraphs) from plain text
+pub(crate) fn build_styled_docx(text: &str, options: &StyledDocxOptions) -> Docx {
+    let font_family = &options.font_family;
+    let font_size = options.font_size;
+    let line_spacing = options.line_spacing;
+    let header_content = &options.header_content;
+    let body_alignment = options.alignment.body.as_deref().and_then(parse_alignment);
+    let heading_alignment = options.alignment.heading.as_deref().and_then(parse_alignment);
+    let header_alignment = options.alignment.header.as_deref()
+        .or(options.header_style.as_ref().map(|style| style.alignment.as_str()))
+        .and_then(parse_alignment)
+        .unwrap_or(AlignmentType::Left);
+
     // Convert font size from points to half-points (DOCX uses half-points)
     let font_size_half_points = (font_size * 2.0) as usize;
 
@@ -52,26 +306,33 @@ pub async fn create_styled_docx(
 
     // Add document header if provided (appears at top of every page)
     // Supports multi-line headers (separated by newlines)
-    // Header is BOLD and LEFT-ALIGNED (linksbündig)
+    // Header is BOLD and LEFT-ALIGNED (linksbündig) unless another alignment or an analyzed header style is requested
     if let Some(ref header_text) = header_content {
         if !header_text.trim().is_empty() {
             println!("Adding document header: {}", header_text);
 
             // Create header with multiple lines
             let mut header = Header::new();
-            let header_font_size = ((font_size - 1.0) * 2.0) as usize; // Slightly smaller than body
+            let header_font_size = match &options.header_style {
+                Some(style) => (style.font_size * 2.0) as usize,
+                None => options.font_sizing.header_half_points(font_size), // Slightly smaller than body by default
+            };
+            let header_font = options.header_style.as_ref().map(|style| style.font_family.as_str()).unwrap_or(font_family);
+            let header_bold = options.header_style.as_ref().is_none_or(|style| style.font_weight == "bold");
 
             for line in header_text.lines() {
                 if !line.trim().is_empty() {
-                    let header_run = Run::new()
+                    let mut header_run = Run::new()
                         .add_text(line.trim())
                         .size(header_font_size)
-                        .bold()  // BOLD
-                        .fonts(RunFonts::new().ascii(&font_family).hi_ansi(&font_family));
+                        .fonts(RunFonts::new().ascii(header_font).hi_ansi(header_font));
+                    if header_bold {
+                        header_run = header_run.bold();
+                    }
 
                     let header_paragraph = Paragraph::new()
                         .add_run(header_run)
-                        .align(AlignmentType::Left);  // LEFT-ALIGNED (linksbündig)
+                        .align(header_alignment);
 
                     header = header.add_paragraph(header_paragraph);
                 }
@@ -81,10 +342,21 @@ pub async fn create_styled_docx(
         }
     }
 
-    // Split text into paragraphs
-    let paragraphs: Vec<&str> = text.split('\n').collect();
-
-    for para_text in paragraphs {
+    // Split text into paragraphs (line endings normalized, repeated blank lines and soft wraps collapsed);
+    // a page break ends the paragraphs of one page
+    let pages = split_pages(text, options.page_break_marker.as_deref());
+    let paragraphs: Vec<Option<String>> = pages.iter().enumerate()
+        .flat_map(|(i, page)| {
+            let page_break = (i > 0).then_some(None);
+            page_break.into_iter().chain(segment_lines(page, &SegmentationOptions::default()).into_iter().map(Some))
+        })
+        .collect();
+
+    for para_text in &paragraphs {
+        let Some(para_text) = para_text else {
+            doc = doc.add_paragraph(Paragraph::new().add_run(Run::new().add_break(BreakType::Page)));
+            continue;
+        };
         if para_text.trim().is_empty() {
             // Empty paragraph for spacing
             doc = doc.add_paragraph(
@@ -96,18 +368,44 @@ pub async fn create_styled_docx(
             let trimmed = para_text.trim();
             let is_heading = is_section_heading(trimmed);
 
-            if is_heading {
-                // Format as heading: bold, slightly larger
-                let heading_size = ((font_size + 2.0) * 2.0) as usize;
+            if let Some(quote) = trimmed.strip_prefix(QUOTE_PREFIX) {
+                let style = &options.quote_style;
+                let size = ((font_size - style.size_delta.max(0.0)) * 2.0) as usize;
+                let mut run = Run::new()
+                    .add_text(quote.trim())
+                    .size(size)
+                    .fonts(RunFonts::new().ascii(font_family).hi_ansi(font_family));
+                if style.italic {
+                    run = run.italic();
+                }
+                let paragraph = Paragraph::new()
+                    .add_run(run)
+                    .line_spacing(LineSpacing::new().line(line_spacing_twips))
+                    .indent(Some(cm_to_twips(style.indent_cm)), None, None, None);
+                doc = doc.add_paragraph(paragraph);
+            } else if is_heading {
+                // Format as heading: bold, larger per the font sizing (sub-sections may be smaller)
+                let level = heading_level(trimmed);
+                let heading_size = options.font_sizing.heading_half_points(font_size, level);
                 let run = Run::new()
                     .add_text(para_text)
                     .size(heading_size)
                     .bold()
-                    .fonts(RunFonts::new().ascii(&font_family).hi_ansi(&font_family));
-
-                let paragraph = Paragraph::new()
+                    .fonts(RunFonts::new().ascii(font_family).hi_ansi(font_family));
+
+                let mut spacing = LineSpacing::new().line(line_spacing_twips);
+                if let Some(heading_spacing) = options.heading_spacing.get(level.saturating_sub(1)) {
+                    // Points to twips
+                    spacing = spacing
+                        .before((heading_spacing.before * 20.0).round() as u32)
+                        .after((heading_spacing.after * 20.0).round() as u32);
+                }
+                let mut paragraph = Paragraph::new()
                     .add_run(run)
-                    .line_spacing(LineSpacing::new().line(line_spacing_twips));
+                    .line_spacing(spacing);
+                if let Some(alignment) = heading_alignment {
+                    paragraph = paragraph.align(alignment);
+                }
 
                 doc = doc.add_paragraph(paragraph);
             } else {
@@ -115,38 +413,185 @@ pub async fn create_styled_docx(
                 let run = Run::new()
                     .add_text(para_text)
                     .size(font_size_half_points)
-                    .fonts(RunFonts::new().ascii(&font_family).hi_ansi(&font_family));
+                    .fonts(RunFonts::new().ascii(font_family).hi_ansi(font_family));
 
-                let paragraph = Paragraph::new()
+                let mut paragraph = Paragraph::new()
                     .add_run(run)
                     .line_spacing(LineSpacing::new().line(line_spacing_twips));
+                if let Some(alignment) = body_alignment {
+                    paragraph = paragraph.align(alignment);
+                }
 
-                doc = doc.add_paragraph(paragraph);
+                doc = doc.add_paragraph(apply_body_indentation(paragraph, &options.indentation));
             }
         }
     }
 
-    // Write the document to file
+    // Signature blocks: configured font or the document font, date/place line right-aligned
+    let today = chrono::Local::now().date_naive();
+    for block in &options.signature_blocks {
+        let block_font = block.font_family.as_deref().unwrap_or(font_family);
+        let block_size = block.font_size.map(|pt| (pt * 2.0) as usize).unwrap_or(font_size_half_points);
+
+        for line in signature_lines(block, today) {
+            let mut run = Run::new()
+                .add_text(&line.text)
+                .size(block_size)
+                .fonts(RunFonts::new().ascii(block_font).hi_ansi(block_font));
+            if line.bold {
+                run = run.bold();
+            }
+
+            let mut paragraph = Paragraph::new().add_run(run);
+            if line.right_aligned {
+                paragraph = paragraph.align(AlignmentType::Right);
+            }
+            doc = doc.add_paragraph(paragraph);
+        }
+    }
+
+    doc
+}
+
+/// Take content and style of analyzed header candidate `header_index` for the export
+pub(crate) fn apply_selected_header(options: &mut StyledDocxOptions, info: &HeaderFooterInfo, header_index: usize) -> Result<(), String> {
+    let candidate = info.header_candidates.get(header_index).ok_or_else(|| format!(
+        "Kopfzeile {} gibt es nicht – die Analyse enthält {} Kopfzeilen",
+        header_index + 1, info.header_candidates.len()
+    ))?;
+    if candidate.content.trim().is_empty() {
+        return Err(format!("Die Kopfzeile aus {} ist leer", candidate.part_name));
+    }
+    options.header_content = Some(candidate.content.clone());
+    options.header_style = candidate.style.clone();
+    Ok(())
+}
+
+/// Export to `output_path` with a header taken from an analyzed document: `header_index` selects
+/// one of the analysis' header candidates, whose text, font, size, weight and alignment are used
+#[command]
+pub async fn export_with_selected_header(
+    text: String,
+    analysis_id: String,
+    header_index: usize,
+    output_path: String,
+    mut options: StyledDocxOptions,
+) -> Result<String, String> {
+    let analysis = load_cached_analysis(&analysis_id)?;
+    apply_selected_header(&mut options, &analysis.header_footer_info, header_index)?;
+
+    let output_path = PathBuf::from(&output_path);
+    backup_before_overwrite(&output_path)?;
     let file = fs::File::create(&output_path)
         .map_err(|e| format!("Fehler beim Erstellen der Datei: {}", e))?;
-
-    doc.build()
+    build_styled_docx(&text, &options)
+        .build()
         .pack(file)
         .map_err(|e| format!("Fehler beim Schreiben des Dokuments: {}", e))?;
 
-    println!("DOCX created: {}", output_path.display());
-
+    println!("DOCX created with header {} of {}: {}", header_index, analysis_id, output_path.display());
     Ok(output_path.to_string_lossy().to_string())
 }
 
+/// Split the text at form feeds, break marker lines and the configured page break marker (if any).
+/// The document has a single section, so section breaks also start a new page.
+pub(crate) fn split_pages(text: &str, marker: Option<&str>) -> Vec<String> {
+    let text = text.lines()
+        .map(|line| if is_break_marker(line) { "\u{000C}" } else { line })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let text = match marker.map(str::trim).filter(|m| !m.is_empty()) {
+        Some(marker) => text.replace(marker, "\u{000C}"),
+        None => text,
+    };
+    text.split('\u{000C}').map(String::from).collect()
+}
+
+/// Rewrite XML parts of an existing DOCX in place. `edit` gets each part name and content
+/// and returns the new content, or None to keep the part unchanged.
+pub(crate) fn rewrite_docx_xml_parts<F>(docx_path: &Path, mut edit: F) -> Result<(), String>
+where
+    F: FnMut(&str, &str) -> Result<Option<String>, String>,
+{
+    let bytes = fs::read(docx_path)
+        .map_err(|e| format!("Failed to read DOCX: {}", e))?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .map_err(|e| format!("Failed to read DOCX entry: {}", e))?;
+        let name = file.name().to_string();
+
+        let edited = if name.ends_with(".xml") {
+            let mut content = String::new();
+            file.read_to_string(&mut content)
+                .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+            Some(edit(&name, &content)?.unwrap_or(content))
+        } else {
+            None
+        };
+
+        match edited {
+            Some(content) => {
+                writer.start_file(name.as_str(), zip::write::FileOptions::default())
+                    .map_err(|e| format!("Failed to write DOCX entry: {}", e))?;
+                writer.write_all(content.as_bytes())
+                    .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+            }
+            None => writer.raw_copy_file(file)
+                .map_err(|e| format!("Failed to copy DOCX entry: {}", e))?,
+        }
+    }
+    let output = writer.finish()
+        .map_err(|e| format!("Failed to finish DOCX: {}", e))?;
+
+    fs::write(docx_path, output.into_inner())
+        .map_err(|e| format!("Failed to write DOCX: {}", e))
+}
+
+/// How the DOCX builder will treat one line of text
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LineClassification {
+    pub line: String,
+    pub kind: String,  // "heading", "body", "list" or "quote"
+    pub confidence: f32,
+}
+
+/// The rule that made a line a heading
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeadingKind {
+    AllCaps,          // "BEFUND"
+    NumberedSection,  // "1.2 AKTUELLE BESCHWERDEN"
+    KnownSection,     // A known German medical report section
+    Label,            // Short "Hinweis:" line
+}
+
+impl HeadingKind {
+    fn confidence(self) -> f32 {
+        match self {
+            HeadingKind::AllCaps => 0.9,
+            HeadingKind::NumberedSection => 0.85,
+            HeadingKind::KnownSection => 0.7,
+            HeadingKind::Label => 0.6,
+        }
+    }
+}
+
 /// Detect if a line is a section heading
-/// Matches: all caps text, numbered sections, or known German medical report sections
 fn is_section_heading(text: &str) -> bool {
+    heading_kind(text).is_some()
+}
+
+/// The heading rule a line matches, None for non-headings.
+/// Matches: all caps text, numbered sections, known German medical report sections, short "Label:" lines
+fn heading_kind(text: &str) -> Option<HeadingKind> {
     let trimmed = text.trim();
 
     // Skip if empty or too long (headings are usually short)
     if trimmed.is_empty() || trimmed.len() > 80 {
-        return false;
+        return None;
     }
 
     // Check if all uppercase (common heading pattern)
@@ -155,7 +600,7 @@ fn is_section_heading(text: &str) -> bool {
         .all(|c| c.is_uppercase());
 
     if is_all_caps && trimmed.len() >= 3 && trimmed.chars().filter(|c| c.is_alphabetic()).count() >= 3 {
-        return true;
+        return Some(HeadingKind::AllCaps);
     }
 
     // Check for numbered section patterns: "1.", "I.", "1)", "A.", etc.
@@ -173,26 +618,16 @@ fn is_section_heading(text: &str) -> bool {
             let upper_count = after_number.chars().filter(|c| c.is_uppercase()).count();
             let alpha_count = after_number.chars().filter(|c| c.is_alphabetic()).count();
             if alpha_count > 0 && upper_count as f32 / alpha_count as f32 > 0.7 {
-                return true;
+                return Some(HeadingKind::NumberedSection);
             }
         }
     }
 
     // Known German medical section headings (case-insensitive contains check)
-    let known_sections = [
-        "ANAMNESE", "FAMILIENANAMNESE", "EIGENANAMNESE", "SOZIALANAMNESE",
-        "BEFUND", "DIAGNOSE", "DIAGNOSEN", "BEURTEILUNG",
-        "ZUSAMMENFASSUNG", "EPIKRISE", "PROGNOSE", "THERAPIE",
-        "MEDIKATION", "MEDIKAMENTE", "LABOR", "BILDGEBUNG",
-        "NEUROLOGISCH", "PSYCHIATRISCH", "PSYCHOPATHOLOGISCH",
-        "VORGESCHICHTE", "KRANKENGESCHICHTE", "BESCHWERDEN",
-        "LEISTUNGSBEURTEILUNG", "SOZIALMEDIZINISCH", "EMPFEHLUNG",
-    ];
-
     let upper_trimmed = trimmed.to_uppercase();
-    for section in &known_sections {
+    for section in KNOWN_SECTIONS {
         if upper_trimmed.contains(section) && trimmed.len() < 60 {
-            return true;
+            return Some(HeadingKind::KnownSection);
         }
     }
 
@@ -201,9 +636,249 @@ fn is_section_heading(text: &str) -> bool {
         let without_colon = &trimmed[..trimmed.len()-1];
         let word_count = without_colon.split_whitespace().count();
         if word_count <= 4 {
-            return true;
+            return Some(HeadingKind::Label);
         }
     }
 
-    false
+    None
+}
+
+/// Heading level: "1.2 ..." sub-sections and short "Label:" lines are H2, everything else H1
+fn heading_level(text: &str) -> usize {
+    let trimmed = text.trim();
+    let number: String = trimmed.chars().take_while(|c| c.is_ascii_digit() || *c == '.').collect();
+    let is_subsection = number.trim_end_matches('.').split('.').filter(|part| !part.is_empty()).count() >= 2;
+    if is_subsection || heading_kind(trimmed) == Some(HeadingKind::Label) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Classify a non-empty line exactly as build_styled_docx decides; list items are
+/// rendered as body paragraphs but reported separately
+pub fn classify_line(text: &str) -> LineClassification {
+    let trimmed = text.trim();
+    let (kind, confidence) = if trimmed.starts_with(QUOTE_PREFIX) {
+        ("quote", 1.0)
+    } else if let Some(heading) = heading_kind(trimmed) {
+        ("heading", heading.confidence())
+    } else if ["- ", "• ", "* ", "– "].iter().any(|bullet| trimmed.starts_with(bullet)) {
+        ("list", 0.9)
+    } else if trimmed.ends_with(['.', '!', '?']) || trimmed.split_whitespace().count() > 8 {
+        // Full sentences are clearly body text; short fragments might be missed headings
+        ("body", 0.9)
+    } else {
+        ("body", 0.6)
+    };
+
+    LineClassification {
+        line: trimmed.to_string(),
+        kind: kind.to_string(),
+        confidence,
+    }
+}
+
+/// Preview the heading/body decision for each non-empty line before exporting
+#[command]
+pub async fn preview_paragraph_classification(text: String) -> Result<Vec<LineClassification>, String> {
+    Ok(segment_lines(&text, &SegmentationOptions::default()).iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| classify_line(line))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Cursor, Read};
+
+    /// Pack a document in memory and return its word/document.xml
+    pub(crate) fn packed_document_xml(doc: Docx) -> String {
+        let mut buffer = Cursor::new(Vec::new());
+        doc.build().pack(&mut buffer).unwrap();
+
+        let mut archive = zip::ZipArchive::new(Cursor::new(buffer.into_inner())).unwrap();
+        let mut xml = String::new();
+        archive.by_name("word/document.xml").unwrap().read_to_string(&mut xml).unwrap();
+        xml
+    }
+
+    pub(crate) fn test_options() -> StyledDocxOptions {
+        StyledDocxOptions {
+            font_family: "Arial".to_string(),
+            font_size: 11.0,
+            line_spacing: 1.5,
+            header_content: None,
+            indentation: BodyIndentation::default(),
+            alignment: ParagraphAlignments::default(),
+            signature_blocks: Vec::new(),
+            font_sizing: FontSizing::default(),
+            page_break_marker: None,
+            heading_spacing: Vec::new(),
+            quote_style: QuoteStyle::default(),
+            header_style: None,
+        }
+    }
+
+    #[test]
+    fn test_hanging_indent_is_written_in_twips() {
+        let mut options = test_options();
+        options.indentation = BodyIndentation {
+            left_indent_cm: Some(1.27),
+            first_line_indent_cm: None,
+            hanging_indent_cm: Some(1.27),
+        };
+
+        let xml = packed_document_xml(build_styled_docx("ANAMNESE\nDer Patient berichtet.", &options));
+
+        assert!(xml.contains(r#"w:hanging="720""#), "missing hanging indent: {}", xml);
+        assert!(xml.contains(r#"w:left="720""#));
+        // Only the body paragraph is indented, the heading is not
+        assert_eq!(xml.matches("<w:ind ").count(), 1);
+    }
+
+    #[test]
+    fn test_preview_classifies_headings_body_and_lists() {
+        let text = "ANAMNESE\n\nDer Patient berichtet über Rückenschmerzen seit 2019.\n- Operation 2020\nBeurteilung:\nkeine weiteren";
+        let preview: Vec<(String, String, f32)> = text.split('\n')
+            .filter(|line| !line.trim().is_empty())
+            .map(classify_line)
+            .map(|c| (c.line, c.kind, c.confidence))
+            .collect();
+
+        assert_eq!(preview, vec![
+            ("ANAMNESE".to_string(), "heading".to_string(), 0.9),
+            ("Der Patient berichtet über Rückenschmerzen seit 2019.".to_string(), "body".to_string(), 0.9),
+            ("- Operation 2020".to_string(), "list".to_string(), 0.9),
+            ("Beurteilung:".to_string(), "heading".to_string(), 0.7),
+            ("keine weiteren".to_string(), "body".to_string(), 0.6),
+        ]);
+        // Same decision the DOCX builder uses
+        assert!(preview.iter().all(|(line, kind, _)| is_section_heading(line) == (kind == "heading")));
+    }
+
+    #[test]
+    fn test_heading_level_follows_the_matched_rule() {
+        assert_eq!(heading_kind("Hinweis:"), Some(HeadingKind::Label));
+        assert_eq!(classify_line("Hinweis:").confidence, HeadingKind::Label.confidence());
+        assert_eq!((heading_level("BEFUND"), heading_level("1.2 AKTUELLE BESCHWERDEN"), heading_level("Hinweis:")), (1, 2, 2));
+        assert_eq!(heading_kind("Der Patient berichtet über Schmerzen."), None);
+    }
+
+    #[test]
+    fn test_custom_heading_delta_sets_half_point_size() {
+        let mut options = test_options();
+        options.font_sizing = FontSizing { heading_delta: 4.0, header_delta: -1.0, heading_sizes: vec![16.0, 13.0] };
+        assert_eq!(options.font_sizing.heading_half_points(11.0, 1), 32);
+        assert_eq!(options.font_sizing.heading_half_points(11.0, 2), 26);
+        assert_eq!(options.font_sizing.heading_half_points(11.0, 3), 30);
+        assert_eq!((heading_level("BEFUND"), heading_level("1.2 AKTUELLE BESCHWERDEN"), heading_level("Hinweis:")), (1, 2, 2));
+
+        options.font_sizing.heading_sizes.clear();
+        let xml = packed_document_xml(build_styled_docx("BEFUND\nUnauffälliger Befund.", &options));
+        assert!(xml.contains(r#"w:sz w:val="30""#), "missing heading size: {}", xml);
+        assert!(xml.contains(r#"w:sz w:val="22""#));
+    }
+
+    #[test]
+    fn test_justified_body_keeps_headings_left() {
+        let mut options = test_options();
+        options.alignment.body = Some("justify".to_string());
+
+        let xml = packed_document_xml(build_styled_docx("BEFUND\nUnauffälliger Befund.", &options));
+
+        assert_eq!(xml.matches(r#"w:val="both""#).count(), 1);
+        assert!(!xml.contains("distribute"));
+    }
+
+    #[test]
+    fn test_page_break_inserted_at_form_feed_and_marker() {
+        let mut options = test_options();
+        options.page_break_marker = Some("[SEITENUMBRUCH]".to_string());
+        let text = "BEFUND\nUnauffällig.\u{000C}ANLAGE 1\nBefundbericht.\n[SEITENUMBRUCH]\nANLAGE 2";
+
+        assert_eq!(split_pages(text, options.page_break_marker.as_deref()).len(), 3);
+        assert_eq!(split_pages("Seite 1\n[ABSCHNITTSUMBRUCH]\nSeite 2", None).len(), 2);
+        let xml = packed_document_xml(build_styled_docx(text, &options));
+        assert_eq!(xml.matches(r#"w:type="page""#).count(), 2, "page breaks missing: {}", xml);
+        assert!(!xml.contains("SEITENUMBRUCH"));
+    }
+
+    #[test]
+    fn test_quote_lines_are_indented_italic_and_smaller() {
+        let options = test_options();
+        let xml = packed_document_xml(build_styled_docx("BEFUND\nUnauffällig.\n\n> Vorbefund 2021: unauffällig.", &options));
+
+        assert!(xml.contains(r#"w:left="567""#), "missing quote indent: {}", xml);
+        assert!(xml.contains(r#"w:sz w:val="20""#));
+        assert!(xml.contains("<w:i />") || xml.contains("<w:i/>"));
+        assert!(!xml.contains("&gt; Vorbefund"));
+    }
+
+    #[test]
+    fn test_heading_spacing_from_template_is_applied() {
+        let heading = |level: u8, before: f32, after: f32| HeadingStyle {
+            level,
+            font_family: "Arial".to_string(),
+            font_size: 14.0,
+            font_weight: "bold".to_string(),
+            color: "#000000".to_string(),
+            spacing_before: before,
+            spacing_after: after,
+        };
+        let mut options = test_options();
+        options.heading_spacing = heading_spacing_from_styles(&[heading(1, 24.0, 12.0)]);
+        assert_eq!(options.heading_spacing, vec![HeadingSpacing { before: 24.0, after: 12.0 }]);
+
+        let xml = packed_document_xml(build_styled_docx("ANAMNESE\nDer Patient berichtet über Schmerzen.", &options));
+        assert!(xml.contains(r#"w:before="480""#) && xml.contains(r#"w:after="240""#), "heading spacing missing: {}", xml);
+    }
+
+    #[test]
+    fn test_selected_header_alignment_is_applied() {
+        use crate::commands::document_commands::HeaderCandidate;
+
+        let candidate = |part_name: &str, content: &str, alignment: &str| HeaderCandidate {
+            part_name: part_name.to_string(),
+            content_preview: content.to_string(),
+            priority: 9,
+            chosen: false,
+            content: content.to_string(),
+            style: Some(HeaderFooterStyle {
+                font_family: "Georgia".to_string(),
+                font_size: 9.0,
+                font_weight: "normal".to_string(),
+                color: "#000000".to_string(),
+                alignment: alignment.to_string(),
+            }),
+        };
+        let info = HeaderFooterInfo {
+            has_header: true,
+            has_footer: false,
+            header_content: "Gutachten Mustermann".to_string(),
+            footer_content: String::new(),
+            header_style: None,
+            footer_style: None,
+            header_candidates: vec![
+                candidate("word/header1.xml", "Gutachten Mustermann", "left"),
+                candidate("word/header2.xml", "Praxis Dr. Muster", "right"),
+            ],
+        };
+
+        let mut options = test_options();
+        assert!(apply_selected_header(&mut options, &info, 2).unwrap_err().contains("Kopfzeile 3 gibt es nicht"));
+        apply_selected_header(&mut options, &info, 1).unwrap();
+
+        let mut buffer = Cursor::new(Vec::new());
+        build_styled_docx("BEFUND\nUnauffällig.", &options).build().pack(&mut buffer).unwrap();
+        let mut archive = zip::ZipArchive::new(Cursor::new(buffer.into_inner())).unwrap();
+        let mut header_xml = String::new();
+        archive.by_name("word/header1.xml").unwrap().read_to_string(&mut header_xml).unwrap();
+
+        assert!(header_xml.contains("Praxis Dr. Muster"));
+        assert!(header_xml.contains(r#"<w:jc w:val="right"/>"#), "header not right-aligned: {}", header_xml);
+        assert!(header_xml.contains(r#"w:ascii="Georgia""#) && header_xml.contains(r#"w:sz w:val="18""#));
+        assert!(!header_xml.contains("<w:b />") && !header_xml.contains("<w:b/>"));
+    }
 }
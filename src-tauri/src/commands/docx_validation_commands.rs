@@ -0,0 +1,305 @@
+// DOCX validation commands - Checks an exported DOCX for problems that make Word open it only
+// after a "needs repair" prompt (missing settings part, header/footer content types, dangling
+// relationship IDs)
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+
+use crate::commands::backup_commands::backup_before_overwrite;
+
+const CONTENT_TYPES_PART: &str = "[Content_Types].xml";
+const DOCUMENT_PART: &str = "word/document.xml";
+const DOCUMENT_RELS_PART: &str = "word/_rels/document.xml.rels";
+const SETTINGS_PART: &str = "word/settings.xml";
+
+const SETTINGS_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml";
+const SETTINGS_RELATIONSHIP_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings";
+const HEADER_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.header+xml";
+const FOOTER_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.wordprocessingml.footer+xml";
+
+/// Smallest settings part Word accepts without repairing
+const MINIMAL_SETTINGS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:settings xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:defaultTabStop w:val="709"/><w:characterSpacingControl w:val="doNotCompress"/><w:compat><w:compatSetting w:name="compatibilityMode" w:uri="http://schemas.microsoft.com/office/word" w:val="15"/></w:compat></w:settings>"#;
+
+static OVERRIDE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<Override\b[^>]*PartName="([^"]+)"[^>]*ContentType="([^"]+)""#).unwrap());
+static RELATIONSHIP: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<Relationship\b[^>]*>"#).unwrap());
+static RELATIONSHIP_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(Id|Target|TargetMode)="([^"]*)""#).unwrap());
+/// r:id, r:embed etc. in a part referencing its relationships
+static RELATIONSHIP_REFERENCE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\br:(?:id|embed|link|pict)="([^"]+)""#).unwrap());
+static HEADER_FOOTER_PART: Lazy<Regex> = Lazy::new(|| Regex::new(r"^word/(header|footer)\d*\.xml$").unwrap());
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DocxWarning {
+    pub code: String,  // "missing_settings", "content_type", "unresolved_relationship", "missing_target"
+    pub part: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocxValidation {
+    pub valid: bool,               // Structurally a Word document (content types and main part present)
+    pub errors: Vec<String>,
+    pub warnings: Vec<DocxWarning>,  // Word may offer to repair the file
+    pub fixed: Vec<String>,        // Codes of the warnings that were fixed
+}
+
+struct Relationship {
+    id: String,
+    target: String,
+    external: bool,
+}
+
+fn parse_relationships(rels_xml: &str) -> Vec<Relationship> {
+    RELATIONSHIP.find_iter(rels_xml)
+        .map(|tag| {
+            let attributes: HashMap<&str, &str> = RELATIONSHIP_ATTRIBUTE.captures_iter(tag.as_str())
+                .map(|c| (c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()))
+                .collect();
+            Relationship {
+                id: attributes.get("Id").unwrap_or(&"").to_string(),
+                target: attributes.get("Target").unwrap_or(&"").to_string(),
+                external: attributes.get("TargetMode") == Some(&"External"),
+            }
+        })
+        .collect()
+}
+
+/// Archive path a relationship target of `source_part` points to
+fn resolve_target(source_part: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut path: Vec<&str> = source_part.rsplit_once('/').map(|(dir, _)| dir.split('/').collect()).unwrap_or_default();
+    for segment in target.split('/') {
+        match segment {
+            ".." => { path.pop(); }
+            "." | "" => {}
+            other => path.push(other),
+        }
+    }
+    path.join("/")
+}
+
+/// The rels part belonging to a part (word/header1.xml -> word/_rels/header1.xml.rels)
+fn rels_part_of(part: &str) -> String {
+    match part.rsplit_once('/') {
+        Some((dir, name)) => format!("{}/_rels/{}.rels", dir, name),
+        None => format!("_rels/{}.rels", part),
+    }
+}
+
+fn warning(code: &str, part: &str, message: String) -> DocxWarning {
+    DocxWarning { code: code.to_string(), part: part.to_string(), message }
+}
+
+/// Run all checks on the XML parts of a DOCX (part name -> content)
+fn check_docx_parts(parts: &HashMap<String, String>) -> DocxValidation {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    let Some(content_types) = parts.get(CONTENT_TYPES_PART) else {
+        errors.push(format!("{} fehlt", CONTENT_TYPES_PART));
+        return DocxValidation { valid: false, errors, warnings, fixed: Vec::new() };
+    };
+    if !parts.contains_key(DOCUMENT_PART) {
+        errors.push(format!("{} fehlt", DOCUMENT_PART));
+    }
+
+    if !parts.contains_key(SETTINGS_PART) {
+        warnings.push(warning("missing_settings", SETTINGS_PART,
+            "Die Einstellungen (word/settings.xml) fehlen; Word bietet beim Öffnen eine Reparatur an".to_string()));
+    }
+
+    // Headers and footers need their own content type
+    let overrides: HashMap<String, String> = OVERRIDE.captures_iter(content_types)
+        .map(|c| (c[1].trim_start_matches('/').to_string(), c[2].to_string()))
+        .collect();
+    let mut header_footer_parts: Vec<&String> = parts.keys().filter(|p| HEADER_FOOTER_PART.is_match(p)).collect();
+    header_footer_parts.sort();
+    for part in header_footer_parts {
+        let expected = if part.starts_with("word/header") { HEADER_CONTENT_TYPE } else { FOOTER_CONTENT_TYPE };
+        match overrides.get(part.as_str()) {
+            Some(content_type) if content_type == expected => {}
+            Some(content_type) => warnings.push(warning("content_type", part,
+                format!("{} hat den Inhaltstyp {} statt {}", part, content_type, expected))),
+            None => warnings.push(warning("content_type", part,
+                format!("Für {} ist kein Inhaltstyp eingetragen", part))),
+        }
+    }
+
+    // Every referenced relationship ID must exist, every internal target must be in the archive
+    let mut sources: Vec<&String> = parts.keys().filter(|p| p.ends_with(".xml") && p.as_str() != CONTENT_TYPES_PART).collect();
+    sources.sort();
+    for source in sources {
+        let rels_part = rels_part_of(source);
+        let relationships = parts.get(&rels_part).map(|xml| parse_relationships(xml)).unwrap_or_default();
+        let ids: HashSet<&str> = relationships.iter().map(|r| r.id.as_str()).collect();
+
+        let mut unresolved: Vec<&str> = RELATIONSHIP_REFERENCE.captures_iter(&parts[source])
+            .map(|c| c.get(1).unwrap().as_str())
+            .filter(|id| !ids.contains(id))
+            .collect();
+        unresolved.sort();
+        unresolved.dedup();
+        for id in unresolved {
+            warnings.push(warning("unresolved_relationship", source,
+                format!("{} verweist auf {}, das in {} nicht definiert ist", source, id, rels_part)));
+        }
+
+        for relationship in relationships.iter().filter(|r| !r.external) {
+            let target = resolve_target(source, &relationship.target);
+            if !parts.contains_key(&target) && !parts.contains_key(&format!("{}#binary", target)) {
+                warnings.push(warning("missing_target", &rels_part,
+                    format!("Beziehung {} zeigt auf {}, das im Dokument fehlt", relationship.id, target)));
+            }
+        }
+    }
+
+    DocxValidation { valid: errors.is_empty(), errors, warnings, fixed: Vec::new() }
+}
+
+/// XML parts by name; binary parts (images) are listed as "<name>#binary" so targets resolve
+fn read_docx_parts(docx_path: &Path) -> Result<HashMap<String, String>, String> {
+    let bytes = fs::read(docx_path)
+        .map_err(|e| format!("Failed to read DOCX: {}", e))?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|_| "Die Datei ist kein gültiges DOCX-Archiv".to_string())?;
+
+    let mut parts = HashMap::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .map_err(|e| format!("Failed to read DOCX entry: {}", e))?;
+        let name = file.name().to_string();
+        if name.ends_with(".xml") || name.ends_with(".rels") {
+            let mut content = String::new();
+            file.read_to_string(&mut content)
+                .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+            parts.insert(name, content);
+        } else {
+            parts.insert(format!("{}#binary", name), String::new());
+        }
+    }
+    Ok(parts)
+}
+
+fn next_relationship_id(rels_xml: &str) -> String {
+    let used: HashSet<String> = parse_relationships(rels_xml).into_iter().map(|r| r.id).collect();
+    (1..).map(|n| format!("rId{}", n)).find(|id| !used.contains(id)).unwrap()
+}
+
+/// Add a minimal word/settings.xml with its content type and relationship
+fn add_settings_part(docx_path: &Path) -> Result<(), String> {
+    let bytes = fs::read(docx_path)
+        .map_err(|e| format!("Failed to read DOCX: {}", e))?;
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))
+        .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+
+    let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .map_err(|e| format!("Failed to read DOCX entry: {}", e))?;
+        let name = file.name().to_string();
+        if name != CONTENT_TYPES_PART && name != DOCUMENT_RELS_PART {
+            writer.raw_copy_file(file)
+                .map_err(|e| format!("Failed to copy DOCX entry: {}", e))?;
+            continue;
+        }
+
+        let mut content = String::new();
+        file.read_to_string(&mut content)
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        if name == CONTENT_TYPES_PART && !content.contains("/word/settings.xml") {
+            content = content.replacen("</Types>",
+                &format!(r#"<Override PartName="/{}" ContentType="{}"/></Types>"#, SETTINGS_PART, SETTINGS_CONTENT_TYPE), 1);
+        } else if name == DOCUMENT_RELS_PART && !content.contains(SETTINGS_RELATIONSHIP_TYPE) {
+            content = content.replacen("</Relationships>",
+                &format!(r#"<Relationship Id="{}" Type="{}" Target="settings.xml"/></Relationships>"#,
+                    next_relationship_id(&content), SETTINGS_RELATIONSHIP_TYPE), 1);
+        }
+        writer.start_file(name.as_str(), zip::write::FileOptions::default())
+            .map_err(|e| format!("Failed to write DOCX entry: {}", e))?;
+        writer.write_all(content.as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+    writer.start_file(SETTINGS_PART, zip::write::FileOptions::default())
+        .map_err(|e| format!("Failed to write DOCX entry: {}", e))?;
+    writer.write_all(MINIMAL_SETTINGS_XML.as_bytes())
+        .map_err(|e| format!("Failed to write {}: {}", SETTINGS_PART, e))?;
+    let output = writer.finish()
+        .map_err(|e| format!("Failed to finish DOCX: {}", e))?;
+
+    backup_before_overwrite(docx_path)?;
+    fs::write(docx_path, output.into_inner())
+        .map_err(|e| format!("Failed to write DOCX: {}", e))
+}
+
+pub fn validate_docx_file(docx_path: &Path, fix_missing_settings: bool) -> Result<DocxValidation, String> {
+    let mut validation = check_docx_parts(&read_docx_parts(docx_path)?);
+    let missing_settings = validation.warnings.iter().any(|w| w.code == "missing_settings");
+    if fix_missing_settings && missing_settings && validation.errors.is_empty() {
+        add_settings_part(docx_path)?;
+        validation.warnings.retain(|w| w.code != "missing_settings");
+        validation.fixed.push("missing_settings".to_string());
+    }
+    Ok(validation)
+}
+
+/// Check an exported DOCX for what makes Word ask to repair it; optionally add a missing settings part
+#[command]
+pub async fn validate_docx(docx_path: String, fix_missing_settings: Option<bool>) -> Result<DocxValidation, String> {
+    let path = Path::new(&docx_path);
+    if !path.exists() {
+        return Err(format!("Datei nicht gefunden: {}", docx_path));
+    }
+    let validation = validate_docx_file(path, fix_missing_settings.unwrap_or(false))?;
+    println!("DOCX validation of {}: {} errors, {} warnings, fixed {:?}",
+        docx_path, validation.errors.len(), validation.warnings.len(), validation.fixed);
+    Ok(validation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_docx(path: &Path, entries: &[(&str, &str)]) {
+        let mut writer = zip::ZipWriter::new(fs::File::create(path).unwrap());
+        for (name, content) in entries {
+            writer.start_file(*name, zip::write::FileOptions::default()).unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_missing_settings_is_flagged_and_fixed() {
+        let path = std::env::temp_dir().join(format!("validate_docx_{}.docx", std::process::id()));
+        write_docx(&path, &[
+            (CONTENT_TYPES_PART, r#"<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/><Override PartName="/word/header1.xml" ContentType="application/xml"/></Types>"#),
+            ("_rels/.rels", r#"<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/></Relationships>"#),
+            (DOCUMENT_PART, r#"<w:document><w:body><w:sectPr><w:headerReference r:id="rId1"/><w:footerReference r:id="rId7"/></w:sectPr></w:body></w:document>"#),
+            (DOCUMENT_RELS_PART, r#"<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/header" Target="header1.xml"/></Relationships>"#),
+            ("word/header1.xml", "<w:hdr/>"),
+        ]);
+
+        let validation = validate_docx_file(&path, false).unwrap();
+        assert!(validation.valid);
+        let codes: Vec<(&str, &str)> = validation.warnings.iter().map(|w| (w.code.as_str(), w.part.as_str())).collect();
+        assert_eq!(codes, vec![
+            ("missing_settings", SETTINGS_PART),
+            ("content_type", "word/header1.xml"),
+            ("unresolved_relationship", DOCUMENT_PART),
+        ]);
+
+        let fixed = validate_docx_file(&path, true).unwrap();
+        assert_eq!(fixed.fixed, vec!["missing_settings"]);
+        let rechecked = validate_docx_file(&path, false).unwrap();
+        assert!(rechecked.warnings.iter().all(|w| w.code != "missing_settings" && w.code != "missing_target"), "{:?}", rechecked.warnings);
+        assert_eq!(rechecked.warnings.len(), 2);
+        fs::remove_file(&path).ok();
+    }
+}
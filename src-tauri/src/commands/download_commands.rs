@@ -0,0 +1,238 @@
+// Download commands - Resumes model downloads that were interrupted (app closed, connection lost)
+// A running download writes "<model>.part" plus a sidecar "<model>.part.meta" with its source into
+// the directory the LLM workers load models from. Checked once at startup (spawn_download_resume).
+use tauri::{command, AppHandle, Emitter};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::fs;
+use crate::cloud_sync::rename_with_retry;
+use crate::commands::llama_commands::llm_model_path;
+
+/// Sidecar of a ".part" file: where the download comes from and how large it will be
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DownloadMeta {
+    pub url: String,
+    pub expected_size: u64,
+}
+
+/// A ".part" file that can be continued
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PendingDownload {
+    pub part_path: PathBuf,
+    pub final_path: PathBuf,
+    pub meta: DownloadMeta,
+    pub downloaded: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadFailure {
+    pub file: String,
+    pub error: String,
+}
+
+/// Outcome of resuming all pending downloads; orphaned ".part" files have no metadata
+/// and can only be deleted by the user
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ResumeDownloadsReport {
+    pub resumed: Vec<String>,
+    pub failed: Vec<DownloadFailure>,
+    pub orphaned: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadProgressEvent {
+    pub file: String,
+    pub downloaded: u64,
+    pub total: u64,
+    pub progress: f32,
+}
+
+/// "<dir>/model.gguf.part" -> "<dir>/model.gguf.part.meta"
+fn meta_path(part_path: &Path) -> PathBuf {
+    PathBuf::from(format!("{}.meta", part_path.to_string_lossy()))
+}
+
+/// Store the sidecar for a download that is about to start
+pub(crate) fn write_download_meta(part_path: &Path, meta: &DownloadMeta) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(meta)
+        .map_err(|e| format!("Failed to serialize download metadata: {}", e))?;
+    fs::write(meta_path(part_path), json)
+        .map_err(|e| format!("Failed to write download metadata: {}", e))
+}
+
+/// Find ".part" files in the models directory; those without readable metadata are orphaned
+pub fn scan_pending_downloads(models_dir: &Path) -> Result<(Vec<PendingDownload>, Vec<String>), String> {
+    let mut resumable = Vec::new();
+    let mut orphaned = Vec::new();
+    if !models_dir.exists() {
+        return Ok((resumable, orphaned));
+    }
+
+    let mut part_files: Vec<PathBuf> = fs::read_dir(models_dir)
+        .map_err(|e| format!("Failed to read models directory: {}", e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("part"))
+        .collect();
+    part_files.sort();
+
+    for part_path in part_files {
+        let meta = fs::read_to_string(meta_path(&part_path)).ok()
+            .and_then(|content| serde_json::from_str::<DownloadMeta>(&content).ok());
+        match meta {
+            Some(meta) => resumable.push(PendingDownload {
+                final_path: part_path.with_extension(""),
+                downloaded: fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0),
+                part_path,
+                meta,
+            }),
+            None => orphaned.push(part_path.to_string_lossy().to_string()),
+        }
+    }
+    Ok((resumable, orphaned))
+}
+
+/// Move a complete ".part" file to its final name and drop the sidecar
+fn finish_download(download: &PendingDownload) -> Result<(), String> {
+    let size = fs::metadata(&download.part_path)
+        .map_err(|e| format!("Failed to read downloaded file: {}", e))?
+        .len();
+    if size != download.meta.expected_size {
+        return Err(format!("Download unvollständig: {} von {} Bytes", size, download.meta.expected_size));
+    }
+
+    rename_with_retry(&download.part_path, &download.final_path)
+        .map_err(|e| format!("Failed to move downloaded file: {}", e))?;
+    let _ = fs::remove_file(meta_path(&download.part_path));
+    Ok(())
+}
+
+/// Resume every pending download in the models directory with the given resume function
+pub fn resume_pending_downloads_with<F>(models_dir: &Path, mut resume: F) -> Result<ResumeDownloadsReport, String>
+where
+    F: FnMut(&PendingDownload) -> Result<(), String>,
+{
+    let (resumable, orphaned) = scan_pending_downloads(models_dir)?;
+    let mut report = ResumeDownloadsReport { orphaned, ..Default::default() };
+
+    for download in &resumable {
+        let file = download.final_path.to_string_lossy().to_string();
+        let result = if download.downloaded >= download.meta.expected_size {
+            finish_download(download)
+        } else {
+            resume(download).and_then(|_| finish_download(download))
+        };
+        match result {
+            Ok(()) => report.resumed.push(file),
+            Err(error) => {
+                println!("Resuming download of {} failed: {}", file, error);
+                report.failed.push(DownloadFailure { file, error });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Continue a download with a Range request (curl -C), reporting the file size while it runs
+fn resume_with_curl<P>(download: &PendingDownload, mut progress: P) -> Result<(), String>
+where
+    P: FnMut(u64),
+{
+    println!("Resuming download of {} at {} bytes", download.meta.url, download.downloaded);
+    let mut child = Command::new("curl")
+        .args(["-L", "-f", "-s", "-C", &download.downloaded.to_string(), "-o"])
+        .arg(&download.part_path)
+        .arg(&download.meta.url)
+        .spawn()
+        .map_err(|e| format!("Failed to start curl: {}", e))?;
+
+    loop {
+        let status = child.try_wait()
+            .map_err(|e| format!("Failed to wait for curl: {}", e))?;
+        progress(fs::metadata(&download.part_path).map(|m| m.len()).unwrap_or(0));
+        match status {
+            Some(status) if status.success() => return Ok(()),
+            Some(status) => return Err(format!("Download fehlgeschlagen (curl exit code {:?})", status.code())),
+            None => std::thread::sleep(std::time::Duration::from_millis(500)),
+        }
+    }
+}
+
+/// The directory the LLM workers load their GGUF files from, where a download must end up
+fn llm_models_dir() -> Result<PathBuf, String> {
+    llm_model_path(true).parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "Failed to determine the models directory".to_string())
+}
+
+/// Resume model downloads interrupted by a restart. Progress is emitted as "model_download_progress";
+/// ".part" files without metadata are reported as orphaned.
+#[command]
+pub async fn resume_pending_downloads(app: AppHandle) -> Result<ResumeDownloadsReport, String> {
+    let models_dir = llm_models_dir()?;
+
+    tokio::task::spawn_blocking(move || {
+        resume_pending_downloads_with(&models_dir, |download| {
+            let file = download.final_path.to_string_lossy().to_string();
+            let total = download.meta.expected_size;
+            resume_with_curl(download, |downloaded| {
+                let _ = app.emit("model_download_progress", DownloadProgressEvent {
+                    file: file.clone(),
+                    downloaded,
+                    total,
+                    progress: if total > 0 { downloaded as f32 / total as f32 } else { 0.0 },
+                });
+            })
+        })
+    }).await.map_err(|e| format!("Download task failed: {}", e))?
+}
+
+/// Resume pending downloads in the background at startup. The report is emitted as
+/// "model_downloads_resumed" when there was anything to resume or clean up.
+pub fn spawn_download_resume(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        match resume_pending_downloads(app.clone()).await {
+            Ok(report) => {
+                if report.resumed.is_empty() && report.failed.is_empty() && report.orphaned.is_empty() {
+                    return;
+                }
+                println!("Pending downloads: {} resumed, {} failed, {} orphaned",
+                    report.resumed.len(), report.failed.len(), report.orphaned.len());
+                let _ = app.emit("model_downloads_resumed", report);
+            }
+            Err(e) => eprintln!("Failed to resume pending downloads: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_part_file_with_metadata_is_resumed() {
+        let dir = std::env::temp_dir().join(format!("resume_downloads_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let part = dir.join("qwen.gguf.part");
+        fs::write(&part, b"GGUF").unwrap();
+        write_download_meta(&part, &DownloadMeta { url: "https://example.org/qwen.gguf".to_string(), expected_size: 8 }).unwrap();
+        fs::write(dir.join("llama.gguf.part"), b"GG").unwrap();
+
+        let mut calls = Vec::new();
+        let report = resume_pending_downloads_with(&dir, |download| {
+            calls.push((download.meta.url.clone(), download.downloaded));
+            let mut file = fs::OpenOptions::new().append(true).open(&download.part_path).unwrap();
+            std::io::Write::write_all(&mut file, b"DATA").unwrap();
+            Ok(())
+        }).unwrap();
+        let completed = dir.join("qwen.gguf").exists();
+        let meta_left = meta_path(&part).exists();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(calls, vec![("https://example.org/qwen.gguf".to_string(), 4)]);
+        assert_eq!(report.resumed.len(), 1);
+        assert!(completed && !meta_left);
+        assert_eq!(report.orphaned.len(), 1);
+        assert!(report.orphaned[0].ends_with("llama.gguf.part"));
+    }
+}
@@ -0,0 +1,476 @@
+// Export commands - Writes one structured Gutachten to several formats (DOCX, text, Markdown),
+// or each of its sections to a DOCX of its own
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::fs;
+
+use crate::commands::backup_commands::backup_before_overwrite;
+use crate::commands::docx_commands::{build_styled_docx, BodyIndentation, FontSizing, ParagraphAlignments, StyledDocxOptions, PAGE_BREAK_MARKER, QUOTE_PREFIX, SECTION_BREAK_MARKER};
+use crate::commands::style_profile_commands::read_style_profile;
+use crate::commands::template_commands::load_family_spec;
+
+/// Outcome of one format; a failed format doesn't stop the others
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FormatExportResult {
+    pub format: String,  // "docx", "txt" or "md"
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// A block of the rendered document, independent of the output format
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportBlock {
+    Heading { level: u8, text: String },
+    Paragraph(String),
+    Bullet(String),
+    Quote(String),  // Cited passage, e.g. from a prior report
+    PageBreak,      // Manual break kept from an imported report
+    SectionBreak,
+}
+
+/// A plain paragraph; "- " and "• " lines become bullets, break marker lines breaks
+fn paragraph_block(paragraph: &str) -> Option<ExportBlock> {
+    let p = paragraph.trim();
+    if p.is_empty() {
+        return None;
+    }
+    Some(match p {
+        PAGE_BREAK_MARKER => ExportBlock::PageBreak,
+        SECTION_BREAK_MARKER => ExportBlock::SectionBreak,
+        _ => match p.strip_prefix("- ").or_else(|| p.strip_prefix("• ")) {
+            Some(item) => ExportBlock::Bullet(item.trim().to_string()),
+            None => ExportBlock::Paragraph(p.to_string()),
+        },
+    })
+}
+
+/// Blocks of one slot item: a paragraph string or a typed block
+/// ({"type": "normal"|"quote", "text"}, {"type": "list", "items"}, {"type": "table", "rows"},
+/// {"type": "page_break"|"section_break"}); table rows are written as " | "-separated paragraphs
+pub(crate) fn item_blocks(item: &Value) -> Vec<ExportBlock> {
+    let strings = |value: Option<&Value>| -> Vec<String> {
+        value.and_then(|v| v.as_array())
+            .map(|items| items.iter().filter_map(|i| i.as_str()).map(str::trim).filter(|i| !i.is_empty()).map(String::from).collect())
+            .unwrap_or_default()
+    };
+    let text = item.get("text").and_then(|t| t.as_str()).map(str::trim).filter(|t| !t.is_empty()).map(String::from);
+
+    match item {
+        Value::String(paragraph) => paragraph_block(paragraph).into_iter().collect(),
+        Value::Object(_) => match item.get("type").and_then(|t| t.as_str()).unwrap_or("normal") {
+            "quote" => text.map(ExportBlock::Quote).into_iter().collect(),
+            "page_break" => vec![ExportBlock::PageBreak],
+            "section_break" => vec![ExportBlock::SectionBreak],
+            "list" => strings(item.get("items")).into_iter().map(ExportBlock::Bullet).collect(),
+            "table" => item.get("rows").and_then(|r| r.as_array()).into_iter().flatten()
+                .map(|row| strings(Some(row)).join(" | "))
+                .filter(|row| !row.is_empty())
+                .map(ExportBlock::Paragraph)
+                .collect(),
+            _ => text.map(ExportBlock::Paragraph).into_iter().collect(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Blocks of a slot value (a string or a list of paragraphs and typed blocks)
+fn slot_blocks(value: &Value) -> Vec<ExportBlock> {
+    match value {
+        Value::String(text) => text.lines().filter_map(paragraph_block).collect(),
+        Value::Array(items) => items.iter().flat_map(item_blocks).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// "anamnese_body" -> "Anamnese"
+pub(crate) fn slot_heading(slot_id: &str) -> String {
+    let name = slot_id.trim_end_matches("_body").replace('_', " ");
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Lay out structured content along the spec skeleton (fixed headings + slots).
+/// Without a skeleton every slot gets its own heading.
+pub fn content_blocks(content: &Value, spec: Option<&Value>) -> Vec<ExportBlock> {
+    let empty = serde_json::Map::new();
+    let slots = content.get("slots").and_then(|s| s.as_object()).unwrap_or(&empty);
+    let mut blocks = Vec::new();
+
+    let skeleton = spec.and_then(|s| s.get("skeleton")).and_then(|s| s.as_array());
+    let Some(skeleton) = skeleton else {
+        for (slot_id, value) in slots {
+            blocks.push(ExportBlock::Heading { level: 2, text: slot_heading(slot_id) });
+            blocks.extend(slot_blocks(value));
+        }
+        return blocks;
+    };
+
+    for item in skeleton {
+        match item.get("type").and_then(|t| t.as_str()) {
+            Some("fixed") => {
+                let paragraphs = item.get("paragraphs").and_then(|p| p.as_array()).cloned().unwrap_or_default();
+                for paragraph in paragraphs {
+                    let text = paragraph.get("text").and_then(|t| t.as_str()).unwrap_or("").trim().to_string();
+                    if text.is_empty() {
+                        continue;
+                    }
+                    blocks.push(match paragraph.get("style").and_then(|s| s.as_str()) {
+                        Some("Title") => ExportBlock::Heading { level: 1, text },
+                        Some("H1") => ExportBlock::Heading { level: 2, text },
+                        Some("H2") | Some("H3") => ExportBlock::Heading { level: 3, text },
+                        _ => ExportBlock::Paragraph(text),
+                    });
+                }
+            }
+            Some("slot") => {
+                if let Some(value) = item.get("slot_id").and_then(|s| s.as_str()).and_then(|id| slots.get(id)) {
+                    blocks.extend(slot_blocks(value));
+                }
+            }
+            _ => {}
+        }
+    }
+    blocks
+}
+
+/// Plain text: headings in capitals, quotes as "> " lines and breaks as marker lines (so the DOCX
+/// builder picks them up), blank lines between blocks
+pub fn blocks_to_text(blocks: &[ExportBlock]) -> String {
+    blocks.iter()
+        .map(|block| match block {
+            ExportBlock::Heading { text, .. } => text.to_uppercase(),
+            ExportBlock::Paragraph(text) => text.clone(),
+            ExportBlock::Bullet(text) => format!("- {}", text),
+            ExportBlock::Quote(text) => format!("{}{}", QUOTE_PREFIX, text),
+            ExportBlock::PageBreak => PAGE_BREAK_MARKER.to_string(),
+            ExportBlock::SectionBreak => SECTION_BREAK_MARKER.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+pub fn blocks_to_markdown(blocks: &[ExportBlock]) -> String {
+    let mut markdown = String::new();
+    for (i, block) in blocks.iter().enumerate() {
+        let previous_is_bullet = i > 0 && matches!(blocks[i - 1], ExportBlock::Bullet(_));
+        match block {
+            // Consecutive bullets form one list
+            ExportBlock::Bullet(text) if previous_is_bullet => markdown.push_str(&format!("\n- {}", text)),
+            ExportBlock::Bullet(text) => markdown.push_str(&format!("\n\n- {}", text)),
+            ExportBlock::Heading { level, text } => markdown.push_str(&format!("\n\n{} {}", "#".repeat(*level as usize), text)),
+            ExportBlock::Paragraph(text) => markdown.push_str(&format!("\n\n{}", text)),
+            ExportBlock::Quote(text) => markdown.push_str(&format!("\n\n> {}", text)),
+            // Markdown has no pages
+            ExportBlock::PageBreak | ExportBlock::SectionBreak => {}
+        }
+    }
+    format!("{}\n", markdown.trim_start())
+}
+
+/// Body formatting of exported DOCX files
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportFormatting {
+    pub font_family: String,
+    pub font_size: f32,
+    pub line_spacing: f32,
+}
+
+impl Default for ExportFormatting {
+    fn default() -> Self {
+        ExportFormatting {
+            font_family: "Arial".to_string(),
+            font_size: 11.0,
+            line_spacing: 1.5,
+        }
+    }
+}
+
+fn export_docx(text: &str, path: &Path, formatting: &ExportFormatting) -> Result<(), String> {
+    let options = StyledDocxOptions {
+        font_family: formatting.font_family.clone(),
+        font_size: formatting.font_size,
+        line_spacing: formatting.line_spacing,
+        header_content: None,
+        indentation: BodyIndentation::default(),
+        alignment: ParagraphAlignments::default(),
+        signature_blocks: Vec::new(),
+        font_sizing: FontSizing::default(),
+        page_break_marker: None,
+        heading_spacing: Vec::new(),
+        quote_style: read_style_profile().map(|profile| profile.formatting.quote).unwrap_or_default(),
+        header_style: None,
+    };
+    backup_before_overwrite(path)?;
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Fehler beim Erstellen der Datei: {}", e))?;
+    build_styled_docx(text, &options)
+        .build()
+        .pack(file)
+        .map_err(|e| format!("Fehler beim Schreiben des Dokuments: {}", e))?;
+    Ok(())
+}
+
+/// Append the extension ("Gutachten_09.03.2026" must not lose its ".2026"); a given
+/// .docx/.txt/.md extension on the base path is replaced
+fn format_output_path(base_path: &Path, format: &str) -> PathBuf {
+    let base = base_path.to_string_lossy();
+    let stem = [".docx", ".txt", ".md"].iter()
+        .find_map(|ext| base.strip_suffix(ext))
+        .unwrap_or(&base);
+    PathBuf::from(format!("{}.{}", stem, format))
+}
+
+/// Write all formats next to each other ("<base>.docx", "<base>.txt", "<base>.md")
+pub(crate) fn export_blocks(blocks: &[ExportBlock], base_path: &Path) -> Vec<FormatExportResult> {
+    let text = blocks_to_text(blocks);
+    let markdown = blocks_to_markdown(blocks);
+
+    ["docx", "txt", "md"].iter()
+        .map(|format| {
+            let path = format_output_path(base_path, format);
+            let written = match *format {
+                "docx" => export_docx(&text, &path, &ExportFormatting::default()),
+                "txt" => fs::write(&path, &text).map_err(|e| format!("Failed to write text file: {}", e)),
+                _ => fs::write(&path, &markdown).map_err(|e| format!("Failed to write Markdown file: {}", e)),
+            };
+            match written {
+                Ok(()) => FormatExportResult {
+                    format: format.to_string(),
+                    path: Some(path.to_string_lossy().to_string()),
+                    error: None,
+                },
+                Err(e) => {
+                    println!("[RUST] Export to {} failed: {}", format, e);
+                    FormatExportResult { format: format.to_string(), path: None, error: Some(e) }
+                }
+            }
+        })
+        .collect()
+}
+
+/// Render structured content to DOCX, plain text and Markdown from the same snapshot.
+/// `base_path` is the output path without extension; sections follow the template family's skeleton.
+#[command]
+pub async fn export_all_formats(
+    content_json: Value,
+    base_path: String,
+    family_id: Option<String>,
+) -> Result<Vec<FormatExportResult>, String> {
+    let base_path = PathBuf::from(&base_path);
+    if let Some(parent) = base_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    }
+
+    // Without an extracted template, slots are exported in their own order
+    let spec = load_family_spec(family_id).ok();
+    let blocks = content_blocks(&content_json, spec.as_ref());
+    let results = export_blocks(&blocks, &base_path);
+
+    println!("[RUST] Exported {} of {} formats to {}",
+        results.iter().filter(|r| r.error.is_none()).count(), results.len(), base_path.display());
+    Ok(results)
+}
+
+/// Slots exported together as one document
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SectionGroup {
+    pub name: String,       // Heading and file name of the document
+    pub slots: Vec<String>,
+}
+
+/// Each top-level section with its blocks: along the skeleton every H1 heading starts one (the
+/// Title and what precedes the first H1 belong to none), without a skeleton every slot is one
+fn top_level_sections(content: &Value, spec: Option<&Value>) -> Vec<(String, Vec<ExportBlock>)> {
+    let mut sections: Vec<(String, Vec<ExportBlock>)> = Vec::new();
+    for block in content_blocks(content, spec) {
+        match block {
+            ExportBlock::Heading { level: 2, text } => sections.push((text.clone(), vec![ExportBlock::Heading { level: 2, text }])),
+            ExportBlock::Heading { level: 1, .. } => {}
+            block => {
+                if let Some((_, blocks)) = sections.last_mut() {
+                    blocks.push(block);
+                }
+            }
+        }
+    }
+    sections
+}
+
+/// Blocks of caller-defined groups, each under its name as heading
+fn grouped_sections(content: &Value, groups: &[SectionGroup]) -> Vec<(String, Vec<ExportBlock>)> {
+    let slots = content.get("slots");
+    groups.iter()
+        .map(|group| {
+            let mut blocks = vec![ExportBlock::Heading { level: 2, text: group.name.clone() }];
+            for slot_id in &group.slots {
+                if let Some(value) = slots.and_then(|s| s.get(slot_id)) {
+                    blocks.extend(slot_blocks(value));
+                }
+            }
+            (group.name.clone(), blocks)
+        })
+        .collect()
+}
+
+/// "1. Anamnese" -> "1_Anamnese"
+fn section_file_stem(name: &str) -> String {
+    let stem = name.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect::<String>()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if stem.is_empty() { "Abschnitt".to_string() } else { stem }
+}
+
+/// Write each section with content to "<output_dir>/<section>.docx"; sections with nothing but
+/// their heading are skipped
+pub(crate) fn export_sections(
+    sections: &[(String, Vec<ExportBlock>)],
+    output_dir: &Path,
+    formatting: &ExportFormatting,
+) -> Result<Vec<String>, String> {
+    let mut used_stems: Vec<String> = Vec::new();
+    let mut paths = Vec::new();
+    for (name, blocks) in sections {
+        if blocks.iter().all(|b| matches!(b, ExportBlock::Heading { .. } | ExportBlock::PageBreak | ExportBlock::SectionBreak)) {
+            println!("[RUST] Section {} is empty, not exported", name);
+            continue;
+        }
+        let base = section_file_stem(name);
+        let stem = (1..).map(|n| if n == 1 { base.clone() } else { format!("{}_{}", base, n) })
+            .find(|stem| !used_stems.contains(stem))
+            .unwrap();
+        let path = output_dir.join(format!("{}.docx", stem));
+        export_docx(&blocks_to_text(blocks), &path, formatting)?;
+        used_stems.push(stem);
+        paths.push(path.to_string_lossy().to_string());
+    }
+    Ok(paths)
+}
+
+/// Export each top-level section (or each of the given groups of slots) as its own DOCX,
+/// e.g. the parts of a composite assessment; returns the written paths
+#[command]
+pub async fn export_sections_separately(
+    content_json: Value,
+    output_dir: String,
+    formatting: Option<ExportFormatting>,
+    groups: Option<Vec<SectionGroup>>,
+    family_id: Option<String>,
+) -> Result<Vec<String>, String> {
+    let output_dir = PathBuf::from(&output_dir);
+    fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let sections = match groups {
+        Some(groups) => grouped_sections(&content_json, &groups),
+        None => top_level_sections(&content_json, load_family_spec(family_id).ok().as_ref()),
+    };
+    let paths = export_sections(&sections, &output_dir, &formatting.unwrap_or_default())?;
+
+    println!("[RUST] Exported {} of {} sections to {}", paths.len(), sections.len(), output_dir.display());
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_formats_are_written_from_one_content_object() {
+        let spec = serde_json::json!({"skeleton": [
+            {"type": "fixed", "paragraphs": [{"text": "GUTACHTEN", "style": "Title"}, {"text": "", "style": "Normal"}]},
+            {"type": "fixed", "paragraphs": [{"text": "1. Anamnese", "style": "H1"}]},
+            {"type": "slot", "slot_id": "anamnese_body", "optional": false},
+            {"type": "fixed", "paragraphs": [{"text": "2. Befund", "style": "H1"}]},
+            {"type": "slot", "slot_id": "befund_body", "optional": false},
+        ]});
+        let content = serde_json::json!({
+            "slots": {
+                "anamnese_body": ["Seit 2019 Rückenschmerzen.", "- Operation 2020", "- Physiotherapie"],
+                "befund_body": "Keine Auffälligkeiten.",
+            },
+            "unclear_spans": [],
+            "missing_slots": [],
+        });
+
+        let blocks = content_blocks(&content, Some(&spec));
+        let base = std::env::temp_dir().join(format!("export_all_{}", std::process::id())).join("Gutachten_09.03.2026");
+        fs::create_dir_all(base.parent().unwrap()).unwrap();
+        let results = export_blocks(&blocks, &base);
+
+        let formats: Vec<&str> = results.iter().map(|r| r.format.as_str()).collect();
+        assert_eq!(formats, vec!["docx", "txt", "md"]);
+        assert!(results.iter().all(|r| r.error.is_none() && Path::new(r.path.as_ref().unwrap()).exists()));
+
+        assert!(results[0].path.as_ref().unwrap().ends_with("Gutachten_09.03.2026.docx"));
+        let text = fs::read_to_string(format_output_path(&base, "txt")).unwrap();
+        let markdown = fs::read_to_string(format_output_path(&base, "md")).unwrap();
+        let _ = fs::remove_dir_all(base.parent().unwrap());
+
+        assert_eq!(text, "GUTACHTEN\n\n1. ANAMNESE\n\nSeit 2019 Rückenschmerzen.\n\n- Operation 2020\n\n- Physiotherapie\n\n2. BEFUND\n\nKeine Auffälligkeiten.");
+        assert_eq!(markdown, "# GUTACHTEN\n\n## 1. Anamnese\n\nSeit 2019 Rückenschmerzen.\n\n- Operation 2020\n- Physiotherapie\n\n## 2. Befund\n\nKeine Auffälligkeiten.\n");
+    }
+
+    #[test]
+    fn test_sections_are_exported_to_one_file_each() {
+        let spec = serde_json::json!({"skeleton": [
+            {"type": "fixed", "paragraphs": [{"text": "GUTACHTEN", "style": "Title"}]},
+            {"type": "fixed", "paragraphs": [{"text": "1. Anamnese", "style": "H1"}]},
+            {"type": "slot", "slot_id": "anamnese_body"},
+            {"type": "fixed", "paragraphs": [{"text": "2. Befund", "style": "H1"}]},
+            {"type": "slot", "slot_id": "befund_body"},
+            {"type": "fixed", "paragraphs": [{"text": "3. Beurteilung", "style": "H1"}]},
+            {"type": "slot", "slot_id": "beurteilung_body"},
+            {"type": "fixed", "paragraphs": [{"text": "4. Anlagen", "style": "H1"}]},
+            {"type": "slot", "slot_id": "anlagen_body"},
+        ]});
+        let content = serde_json::json!({"slots": {
+            "anamnese_body": "Seit 2019 Rückenschmerzen.",
+            "befund_body": ["Keine Auffälligkeiten."],
+            "beurteilung_body": "Arbeitsfähig.",
+            "anlagen_body": "",
+        }});
+
+        let sections = top_level_sections(&content, Some(&spec));
+        assert_eq!(sections.len(), 4);
+        assert_eq!(blocks_to_text(&sections[1].1), "2. BEFUND\n\nKeine Auffälligkeiten.");
+
+        let dir = std::env::temp_dir().join(format!("export_sections_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let paths = export_sections(&sections, &dir, &ExportFormatting::default()).unwrap();
+        let names: Vec<String> = paths.iter().map(|p| Path::new(p).file_name().unwrap().to_string_lossy().to_string()).collect();
+        assert!(paths.iter().all(|p| Path::new(p).exists()));
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(names, vec!["1_Anamnese.docx", "2_Befund.docx", "3_Beurteilung.docx"]);
+
+        let grouped = grouped_sections(&content, &[SectionGroup { name: "Teil A".to_string(), slots: vec!["anamnese_body".to_string(), "befund_body".to_string()] }]);
+        assert_eq!(blocks_to_text(&grouped[0].1), "TEIL A\n\nSeit 2019 Rückenschmerzen.\n\nKeine Auffälligkeiten.");
+    }
+
+    #[test]
+    fn test_typed_slot_blocks_render_quotes_lists_and_tables() {
+        let slot = serde_json::json!([
+            "Eigene Untersuchung: unauffällig.",
+            {"type": "quote", "text": "Vorbefund 2021: Bandscheibenvorfall L4/5."},
+            {"type": "list", "items": ["Ibuprofen", "Physiotherapie"]},
+            {"type": "table", "rows": [["Datum", "Befund"], ["03/2021", "MRT LWS"]]},
+        ]);
+        let blocks = slot_blocks(&slot);
+        assert_eq!(blocks[1], ExportBlock::Quote("Vorbefund 2021: Bandscheibenvorfall L4/5.".to_string()));
+        assert_eq!(blocks.len(), 6);
+
+        let text = blocks_to_text(&blocks);
+        assert_eq!(text, "Eigene Untersuchung: unauffällig.\n\n> Vorbefund 2021: Bandscheibenvorfall L4/5.\n\n- Ibuprofen\n\n- Physiotherapie\n\nDatum | Befund\n\n03/2021 | MRT LWS");
+        assert!(blocks_to_markdown(&blocks).contains("\n\n> Vorbefund 2021"));
+        assert_eq!(crate::commands::docx_commands::classify_line("> Vorbefund 2021").kind, "quote");
+    }
+}
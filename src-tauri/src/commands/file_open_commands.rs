@@ -0,0 +1,141 @@
+// File open commands - Audio and DOCX files handed to the app by the OS (double-click, "Öffnen mit",
+// or a second app instance forwarding its arguments) are queued for the frontend
+use tauri::{command, AppHandle, Emitter};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Mutex;
+use once_cell::sync::Lazy;
+
+use crate::formats;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExternalFileKind {
+    Audio,  // Goes to transcription
+    Docx,   // Goes to the document/template workflow
+}
+
+/// Payload of the "open_external_file" event
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExternalFile {
+    pub path: String,
+    pub kind: ExternalFileKind,
+}
+
+/// Payload of the "external_file_notice" event for files that were not opened
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ExternalFileNotice {
+    pub path: String,
+    pub message: String,
+}
+
+/// Files waiting for the frontend; events sent before the window listens are not lost
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PendingExternalFiles {
+    pub files: VecDeque<ExternalFile>,
+    pub notices: Vec<ExternalFileNotice>,
+}
+
+static PENDING_EXTERNAL_FILES: Lazy<Mutex<PendingExternalFiles>> = Lazy::new(|| Mutex::new(PendingExternalFiles::default()));
+
+/// File arguments of a command line: the executable and flags ("--minimized") are skipped;
+/// relative paths are resolved against `cwd`, the directory the command was started in
+pub fn external_file_arguments(argv: &[String], cwd: &Path) -> Vec<String> {
+    argv.iter().skip(1)
+        .filter(|arg| !arg.starts_with('-'))
+        .map(|arg| cwd.join(arg).to_string_lossy().to_string())
+        .collect()
+}
+
+/// Which workflow a file belongs to, or why it can't be opened
+pub fn classify_external_file(path: &Path) -> Result<ExternalFileKind, String> {
+    if !path.is_file() {
+        return Err(format!("Datei nicht gefunden: {}", path.display()));
+    }
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+    if formats::is_audio_format(extension) {
+        Ok(ExternalFileKind::Audio)
+    } else if extension.eq_ignore_ascii_case("docx") {
+        Ok(ExternalFileKind::Docx)
+    } else {
+        Err(format!(
+            "Dateityp wird nicht unterstützt: {} (möglich sind Audiodateien ({}) und DOCX)",
+            path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            formats::AUDIO_FORMATS.join(", ")
+        ))
+    }
+}
+
+/// Queue the files of a command line (own start or forwarded by a second instance) and tell
+/// the frontend about each one, in argument order
+pub fn queue_external_files(app: &AppHandle, argv: &[String], cwd: &Path) {
+    let arguments = external_file_arguments(argv, cwd);
+    if arguments.is_empty() {
+        return;
+    }
+    let Ok(mut pending) = PENDING_EXTERNAL_FILES.lock() else {
+        return;
+    };
+    for path in arguments {
+        match classify_external_file(Path::new(&path)) {
+            Ok(kind) => {
+                println!("Queued external {:?} file: {}", kind, path);
+                let file = ExternalFile { path, kind };
+                pending.files.push_back(file.clone());
+                let _ = app.emit("open_external_file", file);
+            }
+            Err(message) => {
+                println!("Rejected external file {}: {}", path, message);
+                let notice = ExternalFileNotice { path, message };
+                pending.notices.push(notice.clone());
+                let _ = app.emit("external_file_notice", notice);
+            }
+        }
+    }
+}
+
+/// Files and notices not yet handled, e.g. those passed at app start before the window listened
+#[command]
+pub async fn get_pending_external_files() -> Result<PendingExternalFiles, String> {
+    let pending = PENDING_EXTERNAL_FILES.lock()
+        .map_err(|e| format!("Failed to acquire external file lock: {}", e))?;
+    Ok(pending.clone())
+}
+
+/// Take the next queued file for its workflow; the notices are cleared as shown
+#[command]
+pub async fn take_next_external_file() -> Result<Option<ExternalFile>, String> {
+    let mut pending = PENDING_EXTERNAL_FILES.lock()
+        .map_err(|e| format!("Failed to acquire external file lock: {}", e))?;
+    pending.notices.clear();
+    Ok(pending.files.pop_front())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_external_files_are_classified_by_type() {
+        let dir = std::env::temp_dir().join(format!("external_files_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["diktat.MP3", "gutachten.docx", "notiz.pdf"] {
+            std::fs::write(dir.join(name), b"x").unwrap();
+        }
+
+        // Relative arguments of a second instance resolve against its working directory
+        let absolute = dir.join("b.docx").to_string_lossy().to_string();
+        let argv: Vec<String> = ["app.exe", "--minimized", "diktat.MP3", absolute.as_str()].iter().map(|s| s.to_string()).collect();
+        let arguments = external_file_arguments(&argv, &dir);
+        assert_eq!(arguments, vec![dir.join("diktat.MP3").to_string_lossy().to_string(), absolute]);
+        assert_eq!(classify_external_file(Path::new(&arguments[0])), Ok(ExternalFileKind::Audio));
+
+        assert_eq!(classify_external_file(&dir.join("diktat.MP3")), Ok(ExternalFileKind::Audio));
+        assert_eq!(classify_external_file(&dir.join("gutachten.docx")), Ok(ExternalFileKind::Docx));
+        assert!(classify_external_file(&dir.join("notiz.pdf")).unwrap_err().starts_with("Dateityp wird nicht unterstützt"));
+        assert!(classify_external_file(&dir.join("fehlt.wav")).unwrap_err().starts_with("Datei nicht gefunden"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,9 +1,21 @@
 // DOCX Formatting commands - Uses Python docx_format_tauri.py for formatting
 use tauri::command;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use std::process::Command;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::BufReader;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+
+use crate::commands::document_commands::{analyze_docx_file, count_paragraphs_by_role, extract_document_xml};
+use crate::commands::backup_commands::backup_before_overwrite;
+use crate::commands::docx_commands::rewrite_docx_xml_parts;
+use crate::script_locator::{locate_script, DOCX_FORMAT_SCRIPT};
+use crate::error_classifier::explain_error;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FormatDocxResponse {
@@ -12,33 +24,166 @@ pub struct FormatDocxResponse {
     pub applied_changes: Value,
     pub warnings: Vec<String>,
     pub errors: Vec<String>,
+    #[serde(default)]
+    pub parse_trace: Vec<String>,  // How the request was understood (rule-based parsing only)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DetectFormattingResponse {
     pub is_formatting_request: bool,
     pub request: String,
+    pub method: String,  // "rules" or "llm"
+    pub matched_keywords: Vec<String>,
+}
+
+/// Result of the keyword rules; `is_formatting` is None if the rules can't decide
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleClassification {
+    pub is_formatting: Option<bool>,
+    pub formatting_score: u32,
+    pub content_score: u32,
+    pub matched_keywords: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RuleKind {
+    Formatting,
+    Content,
+    ContentHint,  // Generic editing vocabulary, outweighed by any formatting keyword
+}
+
+/// German formatting vs. content-editing vocabulary (matched on the lowercased request)
+static CLASSIFIER_RULES: Lazy<Vec<(Regex, RuleKind)>> = Lazy::new(|| {
+    let rules: [(&str, RuleKind); 36] = [
+        // Formatting
+        (r"\bschrift(art|typ|größe|grösse|farbe|en)?\b", RuleKind::Formatting),
+        (r"\bfont\b", RuleKind::Formatting),
+        (r"\b(arial|calibri|cambria|verdana|helvetica|times new roman)\b", RuleKind::Formatting),
+        (r"\b\d+([.,]\d+)?\s?pt\b", RuleKind::Formatting),
+        (r"\w*abst(a|ä)nd\w*", RuleKind::Formatting),
+        (r"\bzeilen(höhe|umbruch)\b", RuleKind::Formatting),
+        (r"\b(seiten)?r(a|ä)nd(er|ern)?\b", RuleKind::Formatting),
+        (r"\bfett(gedruckt|druck|er)?\b", RuleKind::Formatting),
+        (r"\bkursiv\w*", RuleKind::Formatting),
+        (r"\bunterstr(eich|ich)\w*", RuleKind::Formatting),
+        (r"\bzentrier\w*|\bmittig\b", RuleKind::Formatting),
+        (r"\b(links|rechts)bündig\b|\bblocksatz\b", RuleKind::Formatting),
+        (r"\bausricht\w*|\bausgerichtet\b", RuleKind::Formatting),
+        (r"\bkopfzeile\w*|\bfu(ß|ss)zeile\w*", RuleKind::Formatting),
+        (r"\bseiten(zahl|nummer|umbruch)\w*", RuleKind::Formatting),
+        (r"\bnummerier\w*|\baufzählung\w*", RuleKind::Formatting),
+        (r"\beinzug\w*|\beinrück\w*", RuleKind::Formatting),
+        (r"\bformatier\w*|\b(hoch|quer)format\b", RuleKind::Formatting),
+        // Content editing
+        (r"\bumformulier\w*|\bformulier\w*", RuleKind::Content),
+        (r"\bkorrigier\w*|\bkorrektur\w*", RuleKind::Content),
+        (r"\brechtschreib\w*|\bgrammatik\w*|\btippfehler\w*", RuleKind::Content),
+        (r"\bzusammenfass\w*|\bzusammenfassung\b", RuleKind::Content),
+        (r"\bübersetz\w*", RuleKind::Content),
+        (r"\bergänz\w*|\berweiter\w*", RuleKind::Content),
+        (r"\bersetz\w*|\bumschreib\w*", RuleKind::Content),
+        (r"\bverbesser\w*|\bpräzisier\w*", RuleKind::Content),
+        (r"\bkürz\w*", RuleKind::Content),
+        (r"\bsynonym\w*", RuleKind::Content),
+        (r"\b(sachlich|verständlich|ausführlich|professionell)\w*", RuleKind::Content),
+        // Generic editing vocabulary
+        (r"\bs(a|ä)tz(e|es)?\b", RuleKind::ContentHint),
+        (r"\bw(o|ö)rt(er|es)?\b", RuleKind::ContentHint),
+        (r"\b(text|inhalt)\b", RuleKind::ContentHint),
+        (r"\blösch\w*|\bentfern\w*", RuleKind::ContentHint),
+        (r"\bschreib\w*", RuleKind::ContentHint),
+        (r"\bänder\w*", RuleKind::ContentHint),
+        (r"\b(diagnose|befund|anamnese|epikrise)\w*", RuleKind::ContentHint),
+    ];
+
+    rules.iter()
+        .map(|(pattern, kind)| (Regex::new(pattern).expect("invalid classifier rule"), *kind))
+        .collect()
+});
+
+/// Classify a request with keyword rules. Formatting and content keywords score 2,
+/// generic editing words 1. The rules stay undecided if nothing matches, if the scores
+/// tie, or if the request contains strong keywords of both kinds.
+pub fn classify_formatting_request(request: &str) -> RuleClassification {
+    let text = request.to_lowercase();
+    let mut formatting_score = 0;
+    let mut content_score = 0;
+    let mut has_formatting = false;
+    let mut has_content = false;
+    let mut matched_keywords = Vec::new();
+
+    for (regex, kind) in CLASSIFIER_RULES.iter() {
+        for m in regex.find_iter(&text) {
+            matched_keywords.push(m.as_str().to_string());
+            match kind {
+                RuleKind::Formatting => { formatting_score += 2; has_formatting = true; }
+                RuleKind::Content => { content_score += 2; has_content = true; }
+                RuleKind::ContentHint => content_score += 1,
+            }
+        }
+    }
+
+    let is_formatting = if (has_formatting && has_content) || formatting_score == content_score {
+        None
+    } else {
+        Some(formatting_score > content_score)
+    };
+
+    RuleClassification { is_formatting, formatting_score, content_score, matched_keywords }
 }
 
-/// Detect if a user request is about formatting (vs. text editing)
+/// Detect if a user request is about formatting (vs. text editing).
+/// Keyword rules decide first; only ambiguous requests go to the Python/LLM detector.
 #[command]
 pub async fn detect_formatting_request(request: String) -> Result<DetectFormattingResponse, String> {
-    // Use the Python script's detection functionality
+    let rules = classify_formatting_request(&request);
+
+    if let Some(is_formatting_request) = rules.is_formatting {
+        return Ok(DetectFormattingResponse {
+            is_formatting_request,
+            request,
+            method: "rules".to_string(),
+            matched_keywords: rules.matched_keywords,
+        });
+    }
+
+    match detect_formatting_with_python(&request) {
+        Ok(is_formatting_request) => Ok(DetectFormattingResponse {
+            is_formatting_request,
+            request,
+            method: "llm".to_string(),
+            matched_keywords: rules.matched_keywords,
+        }),
+        Err(e) => {
+            // Offline fallback: lean on the scores instead of failing
+            println!("Formatting detection falling back to rules: {}", e);
+            Ok(DetectFormattingResponse {
+                is_formatting_request: rules.formatting_score > rules.content_score,
+                request,
+                method: "rules".to_string(),
+                matched_keywords: rules.matched_keywords,
+            })
+        }
+    }
+}
+
+/// Ask the Python script whether a request is about formatting
+fn detect_formatting_with_python(request: &str) -> Result<bool, String> {
     let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
-    let script_path = r"C:\Users\kalin\Desktop\gutachten-assistant\docx_format_tauri.py";
+    let script_path = locate_script(DOCX_FORMAT_SCRIPT)?;
 
     let output = Command::new(python_exe)
         .arg(script_path)
         .arg("--detect-only")
         .arg("--request")
-        .arg(&request)
+        .arg(request)
         .env("PYTHONIOENCODING", "utf-8")
         .output()
         .map_err(|e| format!("Failed to run detection script: {}", e))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("Detection script failed: {}", stderr));
+        return Err(explain_error(format!("Detection script failed: {}", stderr)));
     }
 
     let stdout = String::from_utf8(output.stdout.clone())
@@ -47,30 +192,519 @@ pub async fn detect_formatting_request(request: String) -> Result<DetectFormatti
     let json_result: Value = serde_json::from_str(&stdout)
         .map_err(|e| format!("Failed to parse JSON: {} - stdout: {}", e, stdout))?;
 
-    Ok(DetectFormattingResponse {
-        is_formatting_request: json_result.get("is_formatting_request")
-            .and_then(|v| v.as_bool())
-            .unwrap_or(false),
-        request: request,
-    })
+    Ok(json_result.get("is_formatting_request")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false))
+}
+
+/// Typed FormatSpec, serialized to the JSON the formatter script accepts via --spec-json
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FormatSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub header: Option<HeaderSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub footer: Option<FooterSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page: Option<PageSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub defaults: Option<DefaultsSpec>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub styles: BTreeMap<String, StyleSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FontSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size_pt: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bold: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub italic: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,  // RRGGBB hex as in w:color
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct HeaderSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<HeaderContentSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct HeaderContentSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub center_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right_text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<FontSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FooterSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<PageNumberSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct PageNumberSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<String>,  // "left", "center" or "right"
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct PageSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margins: Option<MarginsSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct MarginsSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_mm: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bottom_mm: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub left_mm: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub right_mm: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct DefaultsSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<FontSpec>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paragraph: Option<ParagraphSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ParagraphSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line_spacing: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub space_after_pt: Option<f32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct StyleSpec {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub font: Option<FontSpec>,
+}
+
+impl FormatSpec {
+    pub fn is_empty(&self) -> bool {
+        *self == FormatSpec::default()
+    }
+}
+
+/// Result of the rule-based request parser
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FormatParseResult {
+    pub spec: FormatSpec,
+    pub trace: Vec<String>,             // One line per understood setting: clause → field = value
+    pub unparsed_clauses: Vec<String>,  // Parts the rules could not interpret
+    pub needs_llm: bool,
+}
+
+/// Which part of the document a clause talks about
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FormatScope {
+    Body,
+    Headings(Option<u8>),
+    Header,
+    Footer,
+}
+
+const KNOWN_FONTS: [&str; 12] = [
+    "Times New Roman", "Arial", "Calibri", "Cambria", "Verdana", "Helvetica",
+    "Georgia", "Garamond", "Tahoma", "Segoe UI", "Courier New", "Aptos",
+];
+
+const COLOR_NAMES: [(&str, &str); 12] = [
+    ("dunkelblau", "1F3864"),
+    ("dunkelgrau", "404040"),
+    ("dunkelrot", "8B0000"),
+    ("hellgrau", "A6A6A6"),
+    ("schwarz", "000000"),
+    ("weiß", "FFFFFF"),
+    ("blau", "0000FF"),
+    ("rot", "FF0000"),
+    ("grün", "008000"),
+    ("grau", "808080"),
+    ("orange", "FFA500"),
+    ("gelb", "FFFF00"),
+];
+
+// Patterns of the natural-language formatting request parser (clauses are lowercased)
+static CLAUSE_SEPARATOR_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r",\s+|;|\.\s+|\s+und\s+|\s+sowie\s+").unwrap());
+static HEADING_SCOPE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"überschrift(?:en)?(?:\s+(?:der\s+)?(?:ebene\s+)?(\d))?").unwrap());
+static BODY_SCOPE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(fließtext|text|absätze|dokument|alles|gesamte)\b").unwrap());
+/// "Schriftart Garamond" in the original (not lowercased) request
+static NAMED_FONT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"[Ss]chrift(?:art)?\s+(?:auf\s+|in\s+|zu\s+)?[„\x22]?([A-ZÄÖÜ][\w-]+(?:\s+[A-Z][\w-]+)*)").unwrap());
+static POINT_SIZE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+(?:[.,]\d+)?)\s*(?:pt|punkt)\b").unwrap());
+static NAMED_SIZE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:schrift)?gr(?:ö|oe)(?:ß|ss)e\s+(?:auf\s+|von\s+)?(\d+(?:[.,]\d+)?)").unwrap());
+static BOLD_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\bfett").unwrap());
+static HEX_COLOR_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"#?\b([0-9a-f]{6})\b").unwrap());
+/// Any of COLOR_NAMES, also inflected ("dunkelblaue Überschriften")
+static COLOR_NAME_REGEX: Lazy<Regex> = Lazy::new(|| {
+    let names: Vec<&str> = COLOR_NAMES.iter().map(|(name, _)| *name).collect();
+    Regex::new(&format!(r"\b({})(?:e|en|er|es)?\b", names.join("|"))).unwrap()
+});
+static NUMBER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+(?:[.,]\d+)?)").unwrap());
+static MARGIN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(seiten)?r(a|ä)nd(er|ern)?\b").unwrap());
+static LENGTH_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\d+(?:[.,]\d+)?)\s*(cm|mm)\b").unwrap());
+static REMOVAL_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\b(entfern\w*|lösch\w*|ohne|keine?)\b").unwrap());
+static QUOTED_TEXT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"["„“]([^"„“”]+)["“”]"#).unwrap());
+
+/// Parse a German number like "1,5" or "12"
+fn parse_german_number(value: &str) -> Option<f32> {
+    value.replace(',', ".").parse::<f32>().ok()
+}
+
+/// Split a request into clauses at commas, "und", "sowie" and sentence ends,
+/// but never inside quoted text or decimal numbers like "1,5"
+fn split_format_clauses(request: &str) -> Vec<String> {
+    let is_quote = |c: char| matches!(c, '"' | '„' | '“' | '”');
+
+    let mut clauses = Vec::new();
+    let mut last = 0;
+    for m in CLAUSE_SEPARATOR_REGEX.find_iter(request) {
+        let quotes_before = request[..m.start()].chars().filter(|c| is_quote(*c)).count();
+        if quotes_before % 2 == 1 {
+            continue;
+        }
+        clauses.push(request[last..m.start()].to_string());
+        last = m.end();
+    }
+    clauses.push(request[last..].to_string());
+
+    clauses.into_iter()
+        .map(|c| c.trim().trim_end_matches(['.', '!']).trim().to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+fn detect_format_scope(clause: &str) -> Option<FormatScope> {
+    if let Some(caps) = HEADING_SCOPE_REGEX.captures(clause) {
+        let level = caps.get(1).and_then(|m| m.as_str().parse::<u8>().ok());
+        return Some(FormatScope::Headings(level));
+    }
+    if clause.contains("kopfzeile") {
+        return Some(FormatScope::Header);
+    }
+    if clause.contains("fußzeile") || clause.contains("fusszeile") || clause.contains("seitenzahl") {
+        return Some(FormatScope::Footer);
+    }
+    if BODY_SCOPE_REGEX.is_match(clause) {
+        return Some(FormatScope::Body);
+    }
+    None
+}
+
+/// Font settings mentioned in a clause (empty if none)
+fn parse_clause_font(original: &str, clause: &str) -> FontSpec {
+    // Font name: known families first, then "Schriftart <Name>"
+    let name = KNOWN_FONTS.iter()
+        .find(|name| clause.contains(&name.to_lowercase()))
+        .map(|name| name.to_string())
+        .or_else(|| {
+            NAMED_FONT_REGEX.captures(original)
+                .map(|caps| caps[1].to_string())
+                .filter(|name| detect_format_scope(&name.to_lowercase()).is_none())
+        });
+    let mut font = FontSpec { name, ..Default::default() };
+
+    // Point sizes belong to the font unless the clause is about spacing
+    if !clause.contains("abstand") {
+        font.size_pt = POINT_SIZE_REGEX.captures(clause)
+            .or_else(|| NAMED_SIZE_REGEX.captures(clause))
+            .and_then(|caps| parse_german_number(&caps[1]));
+    }
+
+    let negated = |keyword: &str| clause.contains(&format!("nicht {}", keyword))
+        || clause.contains(&format!("kein {}", keyword));
+    if BOLD_REGEX.is_match(clause) {
+        font.bold = Some(!negated("fett"));
+    }
+    if clause.contains("kursiv") {
+        font.italic = Some(!negated("kursiv"));
+    }
+
+    font.color = HEX_COLOR_REGEX.captures(clause)
+        .filter(|caps| caps[0].starts_with('#') || clause.contains("farbe"))
+        .map(|caps| caps[1].to_uppercase())
+        .or_else(|| {
+            let name = COLOR_NAME_REGEX.captures(clause)?;
+            COLOR_NAMES.iter()
+                .find(|(known, _)| *known == &name[1])
+                .map(|(_, hex)| hex.to_string())
+        });
+
+    font
+}
+
+/// Merge the set fields of `update` into `target`
+fn merge_font(target: &mut Option<FontSpec>, update: &FontSpec) {
+    let font = target.get_or_insert_with(FontSpec::default);
+    if update.name.is_some() { font.name = update.name.clone(); }
+    if update.size_pt.is_some() { font.size_pt = update.size_pt; }
+    if update.bold.is_some() { font.bold = update.bold; }
+    if update.italic.is_some() { font.italic = update.italic; }
+    if update.color.is_some() { font.color = update.color.clone(); }
+}
+
+fn describe_font(prefix: &str, font: &FontSpec) -> Vec<String> {
+    let mut fields = Vec::new();
+    if let Some(name) = &font.name { fields.push(format!("{}.name = {}", prefix, name)); }
+    if let Some(size) = font.size_pt { fields.push(format!("{}.size_pt = {}", prefix, size)); }
+    if let Some(bold) = font.bold { fields.push(format!("{}.bold = {}", prefix, bold)); }
+    if let Some(italic) = font.italic { fields.push(format!("{}.italic = {}", prefix, italic)); }
+    if let Some(color) = &font.color { fields.push(format!("{}.color = {}", prefix, color)); }
+    fields
+}
+
+/// Parse one clause into `spec`; returns the assignments made (empty if nothing was understood)
+fn parse_format_clause(original: &str, scope: FormatScope, spec: &mut FormatSpec) -> Vec<String> {
+    let clause = original.to_lowercase();
+    let mut assignments = Vec::new();
+
+    // Line spacing (always document-wide)
+    if clause.contains("zeilenabstand") || clause.contains("zeilenabständ") {
+        let value = if clause.contains("einfach") {
+            Some(1.0)
+        } else if clause.contains("anderthalb") || clause.contains("eineinhalb") {
+            Some(1.5)
+        } else if clause.contains("doppelt") {
+            Some(2.0)
+        } else {
+            NUMBER_REGEX.captures(&clause).and_then(|caps| parse_german_number(&caps[1]))
+        };
+        if let Some(value) = value {
+            let paragraph = spec.defaults.get_or_insert_with(DefaultsSpec::default)
+                .paragraph.get_or_insert_with(ParagraphSpec::default);
+            paragraph.line_spacing = Some(value);
+            assignments.push(format!("defaults.paragraph.line_spacing = {}", value));
+        }
+    }
+
+    // Paragraph spacing in pt
+    if clause.contains("absatzabstand") || clause.contains("abstand nach") {
+        if let Some(value) = POINT_SIZE_REGEX.captures(&clause).and_then(|caps| parse_german_number(&caps[1])) {
+            let paragraph = spec.defaults.get_or_insert_with(DefaultsSpec::default)
+                .paragraph.get_or_insert_with(ParagraphSpec::default);
+            paragraph.space_after_pt = Some(value);
+            assignments.push(format!("defaults.paragraph.space_after_pt = {}", value));
+        }
+    }
+
+    // Page margins in cm or mm
+    if MARGIN_REGEX.is_match(&clause) {
+        if let Some(caps) = LENGTH_REGEX.captures(&clause) {
+            if let Some(value) = parse_german_number(&caps[1]) {
+                let mm = if &caps[2] == "cm" { value * 10.0 } else { value };
+                let sides: Vec<&str> = ["oben", "unten", "links", "rechts"].into_iter()
+                    .filter(|side| clause.contains(side))
+                    .collect();
+                let sides = if sides.is_empty() { vec!["oben", "unten", "links", "rechts"] } else { sides };
+
+                let margins = spec.page.get_or_insert_with(PageSpec::default)
+                    .margins.get_or_insert_with(MarginsSpec::default);
+                for side in sides {
+                    let (field, name) = match side {
+                        "oben" => (&mut margins.top_mm, "top_mm"),
+                        "unten" => (&mut margins.bottom_mm, "bottom_mm"),
+                        "links" => (&mut margins.left_mm, "left_mm"),
+                        _ => (&mut margins.right_mm, "right_mm"),
+                    };
+                    *field = Some(mm);
+                    assignments.push(format!("page.margins.{} = {}", name, mm));
+                }
+            }
+        }
+    }
+
+    let removes = REMOVAL_REGEX.is_match(&clause);
+
+    // Header text / removal
+    if clause.contains("kopfzeile") {
+        let header = spec.header.get_or_insert_with(HeaderSpec::default);
+        if removes {
+            header.enabled = Some(false);
+            assignments.push("header.enabled = false".to_string());
+        } else if let Some(caps) = QUOTED_TEXT_REGEX.captures(original) {
+            let text = caps[1].trim().to_string();
+            let content = header.content.get_or_insert_with(HeaderContentSpec::default);
+            let field = if clause.contains("links") {
+                content.left_text = Some(text.clone());
+                "left_text"
+            } else if clause.contains("rechts") {
+                content.right_text = Some(text.clone());
+                "right_text"
+            } else {
+                content.center_text = Some(text.clone());
+                "center_text"
+            };
+            header.enabled = Some(true);
+            assignments.push(format!("header.content.{} = {}", field, text));
+        }
+    }
+
+    // Footer page numbers / removal
+    if clause.contains("seitenzahl") || clause.contains("seitennummer") {
+        let footer = spec.footer.get_or_insert_with(FooterSpec::default);
+        if removes {
+            footer.page_number = Some(PageNumberSpec { enabled: Some(false), ..Default::default() });
+            assignments.push("footer.page_number.enabled = false".to_string());
+        } else {
+            let position = if clause.contains("links") {
+                "left"
+            } else if clause.contains("rechts") {
+                "right"
+            } else {
+                "center"
+            };
+            footer.enabled = Some(true);
+            footer.page_number = Some(PageNumberSpec {
+                enabled: Some(true),
+                format: if clause.contains(" von ") { Some("Seite X von Y".to_string()) } else { None },
+                position: Some(position.to_string()),
+            });
+            assignments.push(format!("footer.page_number.position = {}", position));
+        }
+    } else if (clause.contains("fußzeile") || clause.contains("fusszeile")) && removes {
+        spec.footer.get_or_insert_with(FooterSpec::default).enabled = Some(false);
+        assignments.push("footer.enabled = false".to_string());
+    }
+
+    // Font settings go to the scope of the clause
+    let font = parse_clause_font(original, &clause);
+    if font != FontSpec::default() {
+        match scope {
+            FormatScope::Body => {
+                let defaults = spec.defaults.get_or_insert_with(DefaultsSpec::default);
+                merge_font(&mut defaults.font, &font);
+                assignments.extend(describe_font("defaults.font", &font));
+            }
+            FormatScope::Headings(level) => {
+                let levels = match level {
+                    Some(level) => vec![level],
+                    None => vec![1, 2, 3],
+                };
+                for level in levels {
+                    let style_id = format!("Heading{}", level);
+                    let style = spec.styles.entry(style_id.clone()).or_default();
+                    merge_font(&mut style.font, &font);
+                    assignments.extend(describe_font(&format!("styles.{}.font", style_id), &font));
+                }
+            }
+            FormatScope::Header => {
+                let content = spec.header.get_or_insert_with(HeaderSpec::default)
+                    .content.get_or_insert_with(HeaderContentSpec::default);
+                merge_font(&mut content.font, &font);
+                assignments.extend(describe_font("header.content.font", &font));
+            }
+            FormatScope::Footer => {
+                // The formatter has no footer font settings; leave it to the LLM path
+            }
+        }
+    }
+
+    assignments
+}
+
+/// Interpret a German formatting request with rules, e.g.
+/// "Mach die Überschriften blau und den Zeilenabstand 1,5"
+pub fn parse_format_request(request: &str) -> FormatParseResult {
+    let mut spec = FormatSpec::default();
+    let mut trace = Vec::new();
+    let mut unparsed_clauses = Vec::new();
+    let mut scope = FormatScope::Body;
+
+    for clause in split_format_clauses(request) {
+        // Clauses without their own scope continue the previous one ("Überschriften blau und fett")
+        if let Some(clause_scope) = detect_format_scope(&clause.to_lowercase()) {
+            scope = clause_scope;
+        }
+
+        let assignments = parse_format_clause(&clause, scope, &mut spec);
+        if assignments.is_empty() {
+            unparsed_clauses.push(clause);
+        } else {
+            for assignment in assignments {
+                trace.push(format!("„{}“ → {}", clause, assignment));
+            }
+        }
+    }
+
+    FormatParseResult {
+        needs_llm: !unparsed_clauses.is_empty(),
+        spec,
+        trace,
+        unparsed_clauses,
+    }
 }
 
-/// Apply formatting to a DOCX file using natural language request
+/// Parse a natural-language formatting request into a FormatSpec with rules.
+/// Unparsed clauses are returned so the caller can send only those to the LLM.
+#[command]
+pub async fn parse_formatting_request(request: String) -> Result<FormatParseResult, String> {
+    let result = parse_format_request(&request);
+    println!("Parsed formatting request: {} settings, {} unparsed clauses", result.trace.len(), result.unparsed_clauses.len());
+    Ok(result)
+}
+
+/// Apply formatting to a DOCX file using natural language request.
+/// Requests the rules fully understand are applied as a FormatSpec; others use the script's LLM path.
 #[command]
 pub async fn format_docx_with_request(
     input_docx: String,
     output_docx: String,
     request: String,
+    dry_run: Option<bool>,
 ) -> Result<FormatDocxResponse, String> {
     println!("Formatting DOCX with request: {}", request);
+    let dry_run = dry_run.unwrap_or(false);
 
     // Verify input file exists
     if !PathBuf::from(&input_docx).exists() {
         return Err(format!("Input file not found: {}", input_docx));
     }
 
+    let parsed = parse_format_request(&request);
+    if !parsed.needs_llm && !parsed.spec.is_empty() {
+        if dry_run {
+            let mut response = preview_format_spec(&input_docx, &output_docx, &parsed.spec, None)?;
+            response.parse_trace = parsed.trace;
+            return Ok(response);
+        }
+        let spec_json = serde_json::to_string(&parsed.spec)
+            .map_err(|e| format!("Failed to serialize FormatSpec: {}", e))?;
+        let mut response = apply_format_spec(input_docx, output_docx, spec_json, Some(request)).await?;
+        response.parse_trace = parsed.trace;
+        return Ok(response);
+    }
+
+    // A dry run must leave the filesystem untouched: no snapshot, and the script only plans
+    let snapshot = if dry_run { None } else { Some(capture_format_snapshot(&input_docx, &output_docx)?) };
+    let output_existed = PathBuf::from(&output_docx).exists();
+
     let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
-    let script_path = r"C:\Users\kalin\Desktop\gutachten-assistant\docx_format_tauri.py";
+    let script_path = locate_script(DOCX_FORMAT_SCRIPT)?;
 
     let output = Command::new(python_exe)
         .arg(script_path)
@@ -78,6 +712,7 @@ pub async fn format_docx_with_request(
         .arg(&output_docx)
         .arg("--request")
         .arg(&request)
+        .args(if dry_run { &["--dry-run"][..] } else { &[][..] })
         .env("PYTHONIOENCODING", "utf-8")
         .output()
         .map_err(|e| format!("Failed to run formatting script: {}", e))?;
@@ -92,8 +727,9 @@ pub async fn format_docx_with_request(
     }
 
     // Parse JSON response even if exit code was non-zero (script outputs JSON in both cases)
+    // A script that crashed before printing JSON explains itself on stderr
     let json_result: Value = serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse JSON: {} - stdout: {}", e, stdout))?;
+        .map_err(|e| explain_error(format!("Failed to parse JSON: {} - stdout: {} - stderr: {}", e, stdout, stderr)))?;
 
     let success = json_result.get("success")
         .and_then(|v| v.as_bool())
@@ -123,13 +759,30 @@ pub async fn format_docx_with_request(
         return Err(format!("Formatting failed without specific error. Output: {}", stdout));
     }
 
-    Ok(FormatDocxResponse {
+    let mut response = FormatDocxResponse {
         success,
         output_file: output_docx,
         applied_changes,
         warnings,
         errors,
-    })
+        parse_trace: Vec::new(),
+    };
+
+    match snapshot {
+        Some(snapshot) => record_format_history(snapshot, &response, Some(request), None)?,
+        None => {
+            if let Some(changes) = response.applied_changes.as_object_mut() {
+                changes.insert("preview".to_string(), Value::Bool(true));
+            }
+            // Guard against a script that ignores --dry-run
+            if !output_existed && PathBuf::from(&response.output_file).exists() {
+                let _ = fs::remove_file(&response.output_file);
+                response.warnings.push("Dry run: unexpected output file was removed".to_string());
+            }
+        }
+    }
+
+    Ok(response)
 }
 
 /// Apply formatting to a DOCX file using a FormatSpec JSON
@@ -138,6 +791,198 @@ pub async fn format_docx_with_spec(
     input_docx: String,
     output_docx: String,
     spec_json: String,
+    dry_run: Option<bool>,
+) -> Result<FormatDocxResponse, String> {
+    if dry_run.unwrap_or(false) {
+        if !PathBuf::from(&input_docx).exists() {
+            return Err(format!("Input file not found: {}", input_docx));
+        }
+        let spec_value: Value = serde_json::from_str(&spec_json)
+            .map_err(|e| format!("Invalid spec JSON: {}", e))?;
+        let spec: FormatSpec = serde_json::from_value(spec_value.clone())
+            .map_err(|e| format!("Invalid FormatSpec: {}", e))?;
+        return preview_format_spec(&input_docx, &output_docx, &spec, Some(&spec_value));
+    }
+
+    apply_format_spec(input_docx, output_docx, spec_json, None).await
+}
+
+/// Top-level FormatSpec sections the native preview understands
+const PREVIEW_SECTIONS: [&str; 5] = ["header", "footer", "page", "defaults", "styles"];
+
+fn push_preview_change(changes: &mut Vec<Value>, target: &str, old: Value, new: Value, affected: usize) {
+    if old == new {
+        return;
+    }
+    changes.push(serde_json::json!({
+        "target": target,
+        "old": old,
+        "new": new,
+        "affected_paragraphs": affected,
+    }));
+}
+
+/// Page margin in mm from the document's w:pgMar, if set
+fn page_margin_mm(document_xml: &str, side: &str) -> Option<f32> {
+    let regex = Regex::new(&format!(r#"<w:pgMar[^>]*w:{}="(\d+)""#, side)).ok()?;
+    regex.captures(document_xml)
+        .and_then(|c| c[1].parse::<f32>().ok())
+        .map(|twips| (twips / 1440.0 * 25.4 * 10.0).round() / 10.0)
+}
+
+/// Dry run: compare a spec with the current document and list the planned changes (old → new)
+/// with estimated affected paragraphs. Reads the input only; nothing is written.
+fn preview_format_spec(
+    input_docx: &str,
+    output_docx: &str,
+    spec: &FormatSpec,
+    spec_value: Option<&Value>,
+) -> Result<FormatDocxResponse, String> {
+    let path = PathBuf::from(input_docx);
+    let info = analyze_docx_file(&path, "format-preview")?;
+    let file = fs::File::open(&path)
+        .map_err(|e| format!("Failed to open DOCX file: {}", e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+    let document_xml = extract_document_xml(&mut archive)?;
+    let counts = count_paragraphs_by_role(&document_xml);
+
+    let mut changes: Vec<Value> = Vec::new();
+    let mut body_affected = false;
+    let mut whole_document = false;
+    let mut heading_levels: BTreeSet<u8> = BTreeSet::new();
+    let or_null = |v: Option<Value>| v.unwrap_or(Value::Null);
+
+    if let Some(defaults) = &spec.defaults {
+        if let Some(font) = &defaults.font {
+            let before = changes.len();
+            push_preview_change(&mut changes, "defaults.font.name", json!(info.font_family), or_null(font.name.as_ref().map(|v| json!(v))), counts.body);
+            push_preview_change(&mut changes, "defaults.font.size_pt", json!(info.font_size), or_null(font.size_pt.map(|v| json!(v))), counts.body);
+            for (target, value) in [("defaults.font.bold", font.bold), ("defaults.font.italic", font.italic)] {
+                if let Some(value) = value {
+                    push_preview_change(&mut changes, target, Value::Null, json!(value), counts.body);
+                }
+            }
+            if let Some(color) = &font.color {
+                push_preview_change(&mut changes, "defaults.font.color", Value::Null, json!(color), counts.body);
+            }
+            body_affected |= changes.len() > before;
+        }
+        if let Some(paragraph) = &defaults.paragraph {
+            let before = changes.len();
+            if let Some(line_spacing) = paragraph.line_spacing {
+                push_preview_change(&mut changes, "defaults.paragraph.line_spacing", json!(info.line_spacing), json!(line_spacing), counts.total());
+            }
+            if let Some(space_after) = paragraph.space_after_pt {
+                push_preview_change(&mut changes, "defaults.paragraph.space_after_pt", json!(info.paragraph_spacing_after), json!(space_after), counts.total());
+            }
+            whole_document |= changes.len() > before;
+        }
+    }
+
+    for (style_id, style) in &spec.styles {
+        let Some(font) = &style.font else { continue };
+        let level = style_id.trim_start_matches("Heading").parse::<u8>().unwrap_or(1);
+        let affected = counts.headings.get(&level).copied().unwrap_or(0);
+        let current = info.heading_styles.iter().find(|h| h.level == level);
+        let before = changes.len();
+
+        let prefix = format!("styles.{}.font", style_id);
+        if let Some(name) = &font.name {
+            push_preview_change(&mut changes, &format!("{}.name", prefix), or_null(current.map(|h| json!(h.font_family))), json!(name), affected);
+        }
+        if let Some(size) = font.size_pt {
+            push_preview_change(&mut changes, &format!("{}.size_pt", prefix), or_null(current.map(|h| json!(h.font_size))), json!(size), affected);
+        }
+        if let Some(bold) = font.bold {
+            push_preview_change(&mut changes, &format!("{}.bold", prefix), or_null(current.map(|h| json!(h.font_weight == "bold"))), json!(bold), affected);
+        }
+        if let Some(italic) = font.italic {
+            push_preview_change(&mut changes, &format!("{}.italic", prefix), Value::Null, json!(italic), affected);
+        }
+        if let Some(color) = &font.color {
+            push_preview_change(&mut changes, &format!("{}.color", prefix), or_null(current.map(|h| json!(h.color))), json!(color), affected);
+        }
+        if changes.len() > before {
+            heading_levels.insert(level);
+        }
+    }
+
+    // Page, header and footer changes don't reformat paragraphs
+    if let Some(margins) = spec.page.as_ref().and_then(|p| p.margins.as_ref()) {
+        for (side, value) in [("top", margins.top_mm), ("bottom", margins.bottom_mm), ("left", margins.left_mm), ("right", margins.right_mm)] {
+            if let Some(value) = value {
+                push_preview_change(&mut changes, &format!("page.margins.{}_mm", side), or_null(page_margin_mm(&document_xml, side).map(|v| json!(v))), json!(value), 0);
+            }
+        }
+    }
+    let header_footer = &info.header_footer_info;
+    if let Some(header) = &spec.header {
+        if let Some(enabled) = header.enabled {
+            push_preview_change(&mut changes, "header.enabled", json!(header_footer.has_header), json!(enabled), 0);
+        }
+        if let Some(content) = &header.content {
+            for (target, text) in [("center_text", &content.center_text), ("left_text", &content.left_text), ("right_text", &content.right_text)] {
+                if let Some(text) = text {
+                    push_preview_change(&mut changes, &format!("header.content.{}", target), json!(header_footer.header_content), json!(text), 0);
+                }
+            }
+            if let Some(font) = &content.font {
+                let current = header_footer.header_style.as_ref();
+                if let Some(name) = &font.name {
+                    push_preview_change(&mut changes, "header.content.font.name", or_null(current.map(|h| json!(h.font_family))), json!(name), 0);
+                }
+                if let Some(size) = font.size_pt {
+                    push_preview_change(&mut changes, "header.content.font.size_pt", or_null(current.map(|h| json!(h.font_size))), json!(size), 0);
+                }
+            }
+        }
+    }
+    if let Some(footer) = &spec.footer {
+        if let Some(enabled) = footer.enabled {
+            push_preview_change(&mut changes, "footer.enabled", json!(header_footer.has_footer), json!(enabled), 0);
+        }
+        if let Some(page_number) = &footer.page_number {
+            push_preview_change(&mut changes, "footer.page_number", Value::Null, json!(page_number), 0);
+        }
+    }
+
+    let affected_paragraphs = if whole_document {
+        counts.total()
+    } else {
+        (if body_affected { counts.body } else { 0 })
+            + heading_levels.iter().map(|l| counts.headings.get(l).copied().unwrap_or(0)).sum::<usize>()
+    };
+
+    let warnings: Vec<String> = spec_value
+        .and_then(|v| v.as_object())
+        .map(|sections| sections.keys()
+            .filter(|key| !PREVIEW_SECTIONS.contains(&key.as_str()))
+            .map(|key| format!("Section '{}' is not included in the preview", key))
+            .collect())
+        .unwrap_or_default();
+
+    Ok(FormatDocxResponse {
+        success: true,
+        output_file: output_docx.to_string(),
+        applied_changes: serde_json::json!({
+            "preview": true,
+            "changes": changes,
+            "affected_paragraphs": affected_paragraphs,
+            "total_paragraphs": counts.total(),
+        }),
+        warnings,
+        errors: Vec::new(),
+        parse_trace: Vec::new(),
+    })
+}
+
+/// Run the formatter with a spec and record the change in the format history
+async fn apply_format_spec(
+    input_docx: String,
+    output_docx: String,
+    spec_json: String,
+    request: Option<String>,
 ) -> Result<FormatDocxResponse, String> {
     println!("Formatting DOCX with spec JSON");
 
@@ -146,8 +991,10 @@ pub async fn format_docx_with_spec(
         return Err(format!("Input file not found: {}", input_docx));
     }
 
+    let snapshot = capture_format_snapshot(&input_docx, &output_docx)?;
+
     let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
-    let script_path = r"C:\Users\kalin\Desktop\gutachten-assistant\docx_format_tauri.py";
+    let script_path = locate_script(DOCX_FORMAT_SCRIPT)?;
 
     let output = Command::new(python_exe)
         .arg(script_path)
@@ -167,8 +1014,9 @@ pub async fn format_docx_with_spec(
         println!("Format script stderr: {}", stderr);
     }
 
+    // A script that crashed before printing JSON explains itself on stderr
     let json_result: Value = serde_json::from_str(&stdout)
-        .map_err(|e| format!("Failed to parse JSON: {} - stdout: {}", e, stdout))?;
+        .map_err(|e| explain_error(format!("Failed to parse JSON: {} - stdout: {} - stderr: {}", e, stdout, stderr)))?;
 
     let success = json_result.get("success")
         .and_then(|v| v.as_bool())
@@ -193,11 +1041,766 @@ pub async fn format_docx_with_spec(
                 .unwrap_or_default()
         });
 
-    Ok(FormatDocxResponse {
+    let response = FormatDocxResponse {
         success,
         output_file: output_docx,
         applied_changes,
         warnings,
         errors,
+        parse_trace: Vec::new(),
+    };
+    let spec: Option<Value> = serde_json::from_str(&spec_json).ok();
+    record_format_history(snapshot, &response, request, spec)?;
+
+    Ok(response)
+}
+
+/// One applied formatting operation that can be undone
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FormatHistoryEntry {
+    pub id: String,
+    pub timestamp: String,
+    pub request: Option<String>,
+    pub spec: Option<Value>,
+    pub applied_changes: Value,
+    pub snapshot_file: String,  // Pre-change copy inside the history directory
+    pub hash_before: String,    // SHA-256 of the document before formatting
+    pub hash_after: String,     // SHA-256 right after formatting; undo requires it to still match
+}
+
+const MAX_FORMAT_HISTORY: usize = 5;
+
+/// Pre-change copy of a document; removed again unless the formatting succeeds
+struct PendingFormatSnapshot {
+    history_dir: PathBuf,
+    snapshot_path: PathBuf,
+    docx_path: PathBuf,
+    hash_before: String,
+    committed: bool,
+}
+
+impl Drop for PendingFormatSnapshot {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.snapshot_path);
+        }
+    }
+}
+
+pub(crate) fn sha256_file(path: &Path) -> Result<String, String> {
+    let bytes = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Get the history directory of a document (one per absolute document path)
+fn get_format_history_dir(docx_path: &str) -> Result<PathBuf, String> {
+    // Canonicalize the parent so the key is the same before and after the file is created
+    let path = PathBuf::from(docx_path);
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let normalized = match (fs::canonicalize(&parent), path.file_name()) {
+        (Ok(parent), Some(name)) => parent.join(name),
+        _ => path.clone(),
+    }.to_string_lossy().to_string();
+    let key = format!("{:x}", Sha256::digest(normalized.as_bytes()));
+
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("format-history").join(&key[..16]))
+}
+
+fn load_format_history(history_dir: &Path) -> Result<Vec<FormatHistoryEntry>, String> {
+    let path = history_dir.join("history.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read format history: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse format history: {}", e))
+}
+
+fn write_format_history(history_dir: &Path, entries: &[FormatHistoryEntry]) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(entries)
+        .map_err(|e| format!("Failed to serialize format history: {}", e))?;
+    fs::write(history_dir.join("history.json"), json)
+        .map_err(|e| format!("Failed to write format history: {}", e))
+}
+
+/// Copy the state the output document is about to lose: the output file itself if it
+/// exists, otherwise the unformatted input
+fn capture_format_snapshot(input_docx: &str, output_docx: &str) -> Result<PendingFormatSnapshot, String> {
+    let output_path = PathBuf::from(output_docx);
+    let source = if output_path.exists() { output_path.clone() } else { PathBuf::from(input_docx) };
+
+    backup_before_overwrite(&output_path)?;
+    let history_dir = get_format_history_dir(output_docx)?;
+    fs::create_dir_all(&history_dir)
+        .map_err(|e| format!("Failed to create format history directory: {}", e))?;
+
+    let snapshot_path = history_dir.join(format!(
+        "{}_{}.docx",
+        chrono::Local::now().format("%Y%m%d_%H%M%S"),
+        &uuid::Uuid::new_v4().simple().to_string()[..8]
+    ));
+    fs::copy(&source, &snapshot_path)
+        .map_err(|e| format!("Failed to save pre-format copy: {}", e))?;
+
+    Ok(PendingFormatSnapshot {
+        hash_before: sha256_file(&snapshot_path)?,
+        history_dir,
+        snapshot_path,
+        docx_path: output_path,
+        committed: false,
     })
 }
+
+/// Add a successful formatting run to the document's history (oldest entries beyond the cap are dropped)
+fn record_format_history(
+    mut snapshot: PendingFormatSnapshot,
+    response: &FormatDocxResponse,
+    request: Option<String>,
+    spec: Option<Value>,
+) -> Result<(), String> {
+    if !response.success || !snapshot.docx_path.exists() {
+        return Ok(());
+    }
+
+    let mut entries = load_format_history(&snapshot.history_dir)?;
+    entries.push(FormatHistoryEntry {
+        id: uuid::Uuid::new_v4().to_string(),
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        request,
+        spec,
+        applied_changes: response.applied_changes.clone(),
+        snapshot_file: snapshot.snapshot_path.file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        hash_before: snapshot.hash_before.clone(),
+        hash_after: sha256_file(&snapshot.docx_path)?,
+    });
+
+    if entries.len() > MAX_FORMAT_HISTORY {
+        for old in entries.drain(..entries.len() - MAX_FORMAT_HISTORY) {
+            let _ = fs::remove_file(snapshot.history_dir.join(&old.snapshot_file));
+        }
+    }
+
+    write_format_history(&snapshot.history_dir, &entries)?;
+    snapshot.committed = true;
+    Ok(())
+}
+
+/// List the stored pre-format copies of a document (oldest first)
+#[command]
+pub async fn list_format_history(file_path: String) -> Result<Vec<FormatHistoryEntry>, String> {
+    load_format_history(&get_format_history_dir(&file_path)?)
+}
+
+/// Revert the last formatting operation on a document by restoring its pre-format copy.
+/// Refuses if the file was changed since that formatting was applied.
+#[command]
+pub async fn undo_format(file_path: String) -> Result<FormatHistoryEntry, String> {
+    let history_dir = get_format_history_dir(&file_path)?;
+    let mut entries = load_format_history(&history_dir)?;
+    let entry = entries.last().cloned()
+        .ok_or("Keine Formatierung zum Rückgängigmachen vorhanden")?;
+
+    let path = PathBuf::from(&file_path);
+    let current_hash = sha256_file(&path)?;
+    if current_hash != entry.hash_after {
+        return Err("Das Dokument wurde seit der letzten Formatierung verändert. Rückgängigmachen ist nicht möglich.".to_string());
+    }
+
+    let snapshot_path = history_dir.join(&entry.snapshot_file);
+    fs::copy(&snapshot_path, &path)
+        .map_err(|e| format!("Failed to restore pre-format copy: {}", e))?;
+    let _ = fs::remove_file(&snapshot_path);
+
+    entries.pop();
+    write_format_history(&history_dir, &entries)?;
+
+    println!("Undid formatting {} on {}", entry.id, file_path);
+    Ok(entry)
+}
+
+/// Runs (`<w:r>` / `<w:r w:rsidR="...">`, not `<w:rPr>`) and their properties
+static RUN_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:r(?:\s[^>]*)?>.*?</w:r>").unwrap());
+static RUN_PROPERTIES_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:rPr>(.*?)</w:rPr>|<w:rPr/>").unwrap());
+static RUN_FONTS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<w:rFonts\b[^>]*/>").unwrap());
+static RUN_SIZE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:sz w:val="(\d+)"\s*/>"#).unwrap());
+static RUN_SIZE_CS_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<w:szCs\b[^>]*/>").unwrap());
+static RUN_STYLE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<w:rStyle\b[^>]*/>").unwrap());
+static ASCII_FONT_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"w:ascii="([^"]*)""#).unwrap());
+/// First run property that comes after w:sz/w:szCs in the schema order of w:rPr
+static AFTER_SIZE_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<w:(?:highlight|u|effect|bdr|shd|fitText|vertAlign|rtl|cs|em|lang|eastAsianLayout|specVanish|oMath|rPrChange)[\s/>]").unwrap()
+});
+
+/// Result of unifying the run fonts of document.xml
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontUnification {
+    pub document_xml: String,
+    pub runs_total: usize,
+    pub runs_changed: usize,
+    pub fonts_before: BTreeMap<String, usize>,  // "Times New Roman 12pt" -> runs; "(Formatvorlage)" when inherited
+}
+
+/// Run properties with font and size set to the target; everything else (bold, italic, color, ...)
+/// stays where it is. Elements are inserted in schema order so Word accepts the file.
+fn set_run_font(properties: &str, font: &str, half_points: u32) -> String {
+    let fonts = format!(r#"<w:rFonts w:ascii="{0}" w:hAnsi="{0}" w:cs="{0}" w:eastAsia="{0}"/>"#, font);
+    let sizes = format!(r#"<w:sz w:val="{0}"/><w:szCs w:val="{0}"/>"#, half_points);
+
+    let without_sizes = RUN_SIZE_CS_REGEX.replace_all(&RUN_SIZE_REGEX.replace_all(properties, ""), "").to_string();
+    let mut properties = match RUN_FONTS_REGEX.find(&without_sizes) {
+        Some(existing) => format!("{}{}{}", &without_sizes[..existing.start()], fonts, &without_sizes[existing.end()..]),
+        None => {
+            let position = RUN_STYLE_REGEX.find(&without_sizes).map(|m| m.end()).unwrap_or(0);
+            format!("{}{}{}", &without_sizes[..position], fonts, &without_sizes[position..])
+        }
+    };
+    let position = AFTER_SIZE_REGEX.find(&properties).map(|m| m.start()).unwrap_or(properties.len());
+    properties.insert_str(position, &sizes);
+    properties
+}
+
+/// Give every run of document.xml the same font and size; runs that already match are left alone
+pub fn unify_run_fonts(document_xml: &str, font: &str, size_pt: f32) -> FontUnification {
+    let half_points = (size_pt * 2.0).round() as u32;
+    let font = escape_xml_attribute(font);
+    let mut runs_total = 0;
+    let mut runs_changed = 0;
+    let mut fonts_before: BTreeMap<String, usize> = BTreeMap::new();
+
+    let unified = RUN_REGEX.replace_all(document_xml, |caps: &regex::Captures| {
+        let run = &caps[0];
+        runs_total += 1;
+        let properties = RUN_PROPERTIES_REGEX.captures(run);
+        let inner = properties.as_ref().and_then(|p| p.get(1)).map(|m| m.as_str()).unwrap_or("");
+        let current_font = RUN_FONTS_REGEX.find(inner)
+            .and_then(|fonts| ASCII_FONT_REGEX.captures(fonts.as_str()).map(|c| c[1].to_string()));
+        let current_size = RUN_SIZE_REGEX.captures(inner).and_then(|c| c[1].parse::<u32>().ok());
+
+        let label = match (&current_font, current_size) {
+            (Some(f), Some(sz)) => format!("{} {}pt", f, sz as f32 / 2.0),
+            (Some(f), None) => f.clone(),
+            (None, Some(sz)) => format!("(Formatvorlage) {}pt", sz as f32 / 2.0),
+            (None, None) => "(Formatvorlage)".to_string(),
+        };
+        *fonts_before.entry(label).or_insert(0) += 1;
+
+        if current_font.as_deref() == Some(font.as_str()) && current_size == Some(half_points) {
+            return run.to_string();
+        }
+        runs_changed += 1;
+        let new_properties = format!("<w:rPr>{}</w:rPr>", set_run_font(inner, &font, half_points));
+        match properties.and_then(|p| p.get(0)) {
+            Some(existing) => format!("{}{}{}", &run[..existing.start()], new_properties, &run[existing.end()..]),
+            None => {
+                // No properties yet: they go right after the run's start tag
+                let start_end = run.find('>').map(|i| i + 1).unwrap_or(0);
+                format!("{}{}{}", &run[..start_end], new_properties, &run[start_end..])
+            }
+        }
+    }).to_string();
+
+    FontUnification { document_xml: unified, runs_total, runs_changed, fonts_before }
+}
+
+fn escape_xml_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Set every run of the document body to one font and size (fixes pasted text in a foreign font).
+/// Native ZIP edit of document.xml; bold, italic and color are kept.
+#[command]
+pub async fn unify_document_font(
+    input_path: String,
+    output_path: String,
+    target_font: String,
+    target_size: f32,
+) -> Result<FormatDocxResponse, String> {
+    let target_font = target_font.trim().to_string();
+    if target_font.is_empty() {
+        return Err("Keine Zielschriftart angegeben".to_string());
+    }
+    if !(1.0..=400.0).contains(&target_size) {
+        return Err(format!("Ungültige Schriftgröße: {} pt", target_size));
+    }
+    if !PathBuf::from(&input_path).exists() {
+        return Err(format!("Input file not found: {}", input_path));
+    }
+
+    let snapshot = capture_format_snapshot(&input_path, &output_path)?;
+    if input_path != output_path {
+        fs::copy(&input_path, &output_path)
+            .map_err(|e| format!("Failed to copy document: {}", e))?;
+    }
+
+    let mut unification = None;
+    rewrite_docx_xml_parts(Path::new(&output_path), |name, content| {
+        if name != "word/document.xml" {
+            return Ok(None);
+        }
+        let result = unify_run_fonts(content, &target_font, target_size);
+        let xml = result.document_xml.clone();
+        unification = Some(result);
+        Ok(Some(xml))
+    })?;
+    let unification = unification.ok_or("Dokument enthält kein word/document.xml")?;
+
+    println!("Unified font of {} to {} {}pt: {} of {} runs changed",
+        output_path, target_font, target_size, unification.runs_changed, unification.runs_total);
+    let response = FormatDocxResponse {
+        success: true,
+        output_file: output_path,
+        applied_changes: json!({
+            "font": target_font,
+            "size_pt": target_size,
+            "runs_total": unification.runs_total,
+            "runs_changed": unification.runs_changed,
+            "fonts_before": unification.fonts_before,
+        }),
+        warnings: Vec::new(),
+        errors: Vec::new(),
+        parse_trace: Vec::new(),
+    };
+    let request = format!("Schriftart vereinheitlichen: {} {} pt", target_font, target_size);
+    record_format_history(snapshot, &response, Some(request), None)?;
+    Ok(response)
+}
+
+/// Paragraphs, including self-closing empty ones (`<w:p/>`), and their properties
+static PARAGRAPH_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:p(?:\s[^>]*?)?(?:/>|>.*?</w:p>)").unwrap());
+static PARAGRAPH_PROPERTIES_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:pPr>(.*?)</w:pPr>|<w:pPr/>").unwrap());
+static PARAGRAPH_STYLE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:pStyle w:val="([^"]*)""#).unwrap());
+static PARAGRAPH_SPACING_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<w:spacing\b[^>]*?/>").unwrap());
+static LINE_ATTRIBUTE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"\s+w:line(?:Rule)?="[^"]*""#).unwrap());
+static LINE_VALUE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"w:line="(\d+)""#).unwrap());
+static LINE_RULE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r#"w:lineRule="([^"]*)""#).unwrap());
+/// First paragraph property that comes after w:spacing in the schema order of w:pPr
+static AFTER_SPACING_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"<w:(?:ind|contextualSpacing|mirrorIndents|suppressOverlap|jc|textDirection|textAlignment|textboxTightWrap|outlineLvl|divId|cnfStyle|rPr|sectPr|pPrChange)[\s/>]").unwrap()
+});
+
+/// Result of normalizing the line spacing of document.xml
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpacingNormalization {
+    pub document_xml: String,
+    pub paragraphs_total: usize,
+    pub paragraphs_changed: usize,
+    pub spacings_before: BTreeMap<String, usize>,  // "1.5" / "genau 12pt" -> paragraphs; "(Formatvorlage)" when inherited
+}
+
+/// Paragraph properties with a proportional line spacing of `line` twips; spacing before/after
+/// stays. The paragraph mark's w:rPr has its own w:spacing (letter spacing), which is not touched.
+fn set_paragraph_line_spacing(properties: &str, line: u32) -> String {
+    let own_end = properties.find("<w:rPr").unwrap_or(properties.len());
+    let (own, rest) = properties.split_at(own_end);
+    let own = match PARAGRAPH_SPACING_REGEX.find(own) {
+        Some(existing) => {
+            let attributes = LINE_ATTRIBUTE_REGEX.replace_all(&existing.as_str()[..existing.as_str().len() - 2], "").to_string();
+            format!("{}{} w:line=\"{}\" w:lineRule=\"auto\"/>{}",
+                &own[..existing.start()], attributes.trim_end(), line, &own[existing.end()..])
+        }
+        None => {
+            let spacing = format!(r#"<w:spacing w:line="{}" w:lineRule="auto"/>"#, line);
+            let position = AFTER_SPACING_REGEX.find(own).map(|m| m.start()).unwrap_or(own.len());
+            format!("{}{}{}", &own[..position], spacing, &own[position..])
+        }
+    };
+    format!("{}{}", own, rest)
+}
+
+/// Give every body paragraph of document.xml the same line spacing (multiplier, 1.5 = 360 twips).
+/// Headings get `heading_spacing` or keep theirs; paragraphs already at the target are left alone.
+pub fn normalize_paragraph_line_spacing(document_xml: &str, target_spacing: f32, heading_spacing: Option<f32>) -> SpacingNormalization {
+    let to_twips = |spacing: f32| (spacing * 240.0).round() as u32;
+    let mut paragraphs_total = 0;
+    let mut paragraphs_changed = 0;
+    let mut spacings_before: BTreeMap<String, usize> = BTreeMap::new();
+
+    let normalized = PARAGRAPH_REGEX.replace_all(document_xml, |caps: &regex::Captures| {
+        let paragraph = &caps[0];
+        let properties = PARAGRAPH_PROPERTIES_REGEX.captures(paragraph);
+        let inner = properties.as_ref().and_then(|p| p.get(1)).map(|m| m.as_str()).unwrap_or("");
+        let is_heading = PARAGRAPH_STYLE_REGEX.captures(inner)
+            .is_some_and(|c| crate::docx_patterns::HEADING_STYLE_ID.is_match(&c[1]));
+        let target = match (is_heading, heading_spacing) {
+            (false, _) => to_twips(target_spacing),
+            (true, Some(spacing)) => to_twips(spacing),
+            (true, None) => return paragraph.to_string(),
+        };
+        paragraphs_total += 1;
+
+        let own = &inner[..inner.find("<w:rPr").unwrap_or(inner.len())];
+        let spacing = PARAGRAPH_SPACING_REGEX.find(own).map(|m| m.as_str()).unwrap_or("");
+        let current_line = LINE_VALUE_REGEX.captures(spacing).and_then(|c| c[1].parse::<u32>().ok());
+        let current_rule = LINE_RULE_REGEX.captures(spacing).map(|c| c[1].to_string()).unwrap_or_else(|| "auto".to_string());
+        let label = match (current_line, current_rule.as_str()) {
+            (None, _) => "(Formatvorlage)".to_string(),
+            (Some(line), "auto") => format!("{}", (line as f32 / 240.0 * 100.0).round() / 100.0),
+            (Some(line), "exact") => format!("genau {}pt", line as f32 / 20.0),
+            (Some(line), _) => format!("mindestens {}pt", line as f32 / 20.0),
+        };
+        *spacings_before.entry(label).or_insert(0) += 1;
+
+        if current_line == Some(target) && current_rule == "auto" {
+            return paragraph.to_string();
+        }
+        paragraphs_changed += 1;
+        let new_properties = format!("<w:pPr>{}</w:pPr>", set_paragraph_line_spacing(inner, target));
+        match properties.and_then(|p| p.get(0)) {
+            Some(existing) => format!("{}{}{}", &paragraph[..existing.start()], new_properties, &paragraph[existing.end()..]),
+            None if paragraph.ends_with("/>") => format!("{}>{}</w:p>", &paragraph[..paragraph.len() - 2], new_properties),
+            None => {
+                // No properties yet: they go right after the paragraph's start tag
+                let start_end = paragraph.find('>').map(|i| i + 1).unwrap_or(0);
+                format!("{}{}{}", &paragraph[..start_end], new_properties, &paragraph[start_end..])
+            }
+        }
+    }).to_string();
+
+    SpacingNormalization { document_xml: normalized, paragraphs_total, paragraphs_changed, spacings_before }
+}
+
+/// Set every body paragraph to one line spacing (fixes mixed spacing from pasted text);
+/// headings get `heading_spacing` when given and are left alone otherwise. Native ZIP edit of document.xml.
+#[command]
+pub async fn normalize_line_spacing(
+    input_path: String,
+    output_path: String,
+    target_spacing: f32,
+    heading_spacing: Option<f32>,
+) -> Result<FormatDocxResponse, String> {
+    for spacing in std::iter::once(target_spacing).chain(heading_spacing) {
+        if !(0.5..=5.0).contains(&spacing) {
+            return Err(format!("Ungültiger Zeilenabstand: {} (erlaubt sind 0,5 bis 5)", spacing));
+        }
+    }
+    if !PathBuf::from(&input_path).exists() {
+        return Err(format!("Input file not found: {}", input_path));
+    }
+
+    let snapshot = capture_format_snapshot(&input_path, &output_path)?;
+    if input_path != output_path {
+        fs::copy(&input_path, &output_path)
+            .map_err(|e| format!("Failed to copy document: {}", e))?;
+    }
+
+    let mut normalization = None;
+    rewrite_docx_xml_parts(Path::new(&output_path), |name, content| {
+        if name != "word/document.xml" {
+            return Ok(None);
+        }
+        let result = normalize_paragraph_line_spacing(content, target_spacing, heading_spacing);
+        let xml = result.document_xml.clone();
+        normalization = Some(result);
+        Ok(Some(xml))
+    })?;
+    let normalization = normalization.ok_or("Dokument enthält kein word/document.xml")?;
+
+    println!("Normalized line spacing of {} to {}: {} of {} paragraphs changed",
+        output_path, target_spacing, normalization.paragraphs_changed, normalization.paragraphs_total);
+    let response = FormatDocxResponse {
+        success: true,
+        output_file: output_path,
+        applied_changes: json!({
+            "line_spacing": target_spacing,
+            "heading_line_spacing": heading_spacing,
+            "paragraphs_total": normalization.paragraphs_total,
+            "paragraphs_changed": normalization.paragraphs_changed,
+            "spacings_before": normalization.spacings_before,
+        }),
+        warnings: Vec::new(),
+        errors: Vec::new(),
+        parse_trace: Vec::new(),
+    };
+    let request = match heading_spacing {
+        Some(heading) => format!("Zeilenabstand vereinheitlichen: {} (Überschriften {})", target_spacing, heading),
+        None => format!("Zeilenabstand vereinheitlichen: {}", target_spacing),
+    };
+    record_format_history(snapshot, &response, Some(request), None)?;
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_classifier_on_german_requests() {
+        let formatting = [
+            "Ändere die Schriftart auf Arial",
+            "Schriftgröße 12 Punkt bitte",
+            "Mach die Schrift größer",
+            "Setze den Zeilenabstand auf 1,5",
+            "Zeilenabstand doppelt",
+            "Vergrößere den Abstand zwischen den Absätzen",
+            "Ränder auf 2,5 cm einstellen",
+            "Seitenrand links 3 cm",
+            "Überschriften fett",
+            "Mache das Wort fett",
+            "Schreib den Titel fett",
+            "Alles kursiv setzen",
+            "Die Überschrift unterstreichen",
+            "Titel zentrieren",
+            "Den ganzen Text im Blocksatz",
+            "Bitte linksbündig ausrichten",
+            "Füge eine Kopfzeile mit dem Namen hinzu",
+            "Entferne die Kopfzeile",
+            "Fußzeile mit Seitenzahl einfügen",
+            "Seitenzahlen unten rechts",
+            "Abschnitte nummerieren",
+            "Diagnosen als Aufzählung darstellen",
+            "Erste Zeile um 1 cm einrücken",
+            "Hängenden Einzug entfernen",
+            "Alles in Times New Roman 11 pt",
+            "Formatiere das Dokument wie die Vorlage",
+            "Dokument im Querformat",
+            "Schriftfarbe schwarz",
+        ];
+        let content = [
+            "Formuliere den zweiten Absatz um",
+            "Korrigiere die Rechtschreibung",
+            "Bitte Grammatik prüfen",
+            "Entferne Tippfehler",
+            "Fasse die Anamnese zusammen",
+            "Kürze die Epikrise",
+            "Übersetze den Befund ins Englische",
+            "Ergänze die Diagnose",
+            "Ersetze Patient durch Versicherter",
+            "Verbessere den Stil",
+            "Schreib den Befund sachlicher",
+            "Lösche den letzten Satz",
+            "Streiche die Wörter doppelt und dreifach",
+            "Präzisiere die Leistungsbeurteilung",
+            "Erweitere die Epikrise um die Laborwerte",
+            "Formulierung verständlicher machen",
+            "Finde ein Synonym für Schmerzen",
+            "Ändere den Inhalt der Diagnose",
+            "Schreibe eine Zusammenfassung",
+            "Korrektur der Zahlen im Text",
+        ];
+        let ambiguous = [
+            "Mach es schöner",
+            "Bitte überarbeiten",
+            "Korrigiere die Rechtschreibung und mache die Überschriften fett",
+        ];
+
+        for phrase in formatting {
+            let result = classify_formatting_request(phrase);
+            assert_eq!(result.is_formatting, Some(true), "expected formatting: {} ({:?})", phrase, result);
+        }
+        for phrase in content {
+            let result = classify_formatting_request(phrase);
+            assert_eq!(result.is_formatting, Some(false), "expected content: {} ({:?})", phrase, result);
+        }
+        for phrase in ambiguous {
+            let result = classify_formatting_request(phrase);
+            assert_eq!(result.is_formatting, None, "expected ambiguous: {} ({:?})", phrase, result);
+        }
+    }
+
+    #[test]
+    fn test_format_request_parser_builds_spec() {
+        let result = parse_format_request("Mach die Überschriften blau und den Zeilenabstand 1,5");
+        assert!(!result.needs_llm, "unparsed: {:?}", result.unparsed_clauses);
+        assert_eq!(result.spec.defaults.as_ref().and_then(|d| d.paragraph.as_ref()).and_then(|p| p.line_spacing), Some(1.5));
+        for level in 1..=3 {
+            let color = result.spec.styles[&format!("Heading{}", level)].font.as_ref().and_then(|f| f.color.clone());
+            assert_eq!(color.as_deref(), Some("0000FF"));
+        }
+        assert!(result.spec.defaults.as_ref().unwrap().font.is_none());
+
+        // Inflected color names; "dunkelblau" is not read as "blau"
+        let result = parse_format_request("Überschrift 1 in dunkelblauer Schrift");
+        let color = result.spec.styles["Heading1"].font.as_ref().and_then(|f| f.color.clone());
+        assert_eq!(color.as_deref(), Some("1F3864"));
+
+        let result = parse_format_request("Schriftart Arial 11 pt, Ränder links 3 cm und Kopfzeile „Dr. Müller und Partner“");
+        assert!(!result.needs_llm, "unparsed: {:?}", result.unparsed_clauses);
+        let font = result.spec.defaults.as_ref().unwrap().font.clone().unwrap();
+        assert_eq!(font.name.as_deref(), Some("Arial"));
+        assert_eq!(font.size_pt, Some(11.0));
+        let margins = result.spec.page.as_ref().unwrap().margins.clone().unwrap();
+        assert_eq!(margins.left_mm, Some(30.0));
+        assert_eq!(margins.top_mm, None);
+        let header = result.spec.header.as_ref().unwrap();
+        assert_eq!(header.content.as_ref().unwrap().center_text.as_deref(), Some("Dr. Müller und Partner"));
+        assert!(result.trace.iter().any(|t| t.contains("page.margins.left_mm = 30")));
+
+        let json = serde_json::to_value(&result.spec).unwrap();
+        assert!(json.get("footer").is_none());
+    }
+
+    #[test]
+    fn test_format_request_parser_reports_unparsed_clauses() {
+        let result = parse_format_request("Überschrift 2 in #1F3864 und mach es schöner");
+        assert!(result.needs_llm);
+        assert_eq!(result.unparsed_clauses, vec!["mach es schöner".to_string()]);
+        let color = result.spec.styles["Heading2"].font.as_ref().and_then(|f| f.color.clone());
+        assert_eq!(color.as_deref(), Some("1F3864"));
+        assert!(!result.spec.styles.contains_key("Heading1"));
+    }
+
+    #[test]
+    fn test_rule_classifier_ignores_medical_words() {
+        // "Fettleber" must not count as the formatting keyword "fett"
+        let result = classify_formatting_request("Ergänze die Fettleber im Befund");
+        assert_eq!(result.is_formatting, Some(false));
+        assert!(!result.matched_keywords.iter().any(|k| k.starts_with("fett")));
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_preview_leaves_files_untouched() {
+        use crate::commands::document_commands::tests::{test_docx_path, write_test_docx};
+
+        let body = concat!(
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Befund</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:rPr><w:rFonts w:ascii="Arial"/><w:sz w:val="24"/></w:rPr><w:t>Der Patient</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>klagt über Schmerzen.</w:t></w:r></w:p>"#,
+            r#"<w:p></w:p>"#,
+            r#"<w:sectPr><w:pgMar w:top="1417" w:left="1417"/></w:sectPr>"#,
+        );
+        let input = test_docx_path("preview");
+        write_test_docx(&input, body, &[]);
+        let input_bytes = fs::read(&input).unwrap();
+        let output = test_docx_path("preview_out");
+
+        let spec_json = r#"{"defaults":{"font":{"name":"Times New Roman"}},"page":{"margins":{"left_mm":30}},"title_page":{}}"#;
+        let response = format_docx_with_spec(
+            input.to_string_lossy().to_string(),
+            output.to_string_lossy().to_string(),
+            spec_json.to_string(),
+            Some(true),
+        ).await.unwrap();
+
+        assert!(!output.exists());
+        assert_eq!(fs::read(&input).unwrap(), input_bytes);
+        let changes = &response.applied_changes;
+        assert_eq!(changes["preview"], true);
+        assert_eq!(changes["affected_paragraphs"], 2);
+        assert_eq!(changes["total_paragraphs"], 3);
+        let margin = changes["changes"].as_array().unwrap().iter()
+            .find(|c| c["target"] == "page.margins.left_mm").unwrap();
+        assert_eq!(margin["old"], json!(25.0));
+        assert_eq!(margin["new"], json!(30.0));
+        assert!(response.warnings.iter().any(|w| w.contains("title_page")));
+
+        let _ = fs::remove_file(&input);
+    }
+
+    #[tokio::test]
+    async fn test_undo_format_restores_original_bytes() {
+        use crate::commands::document_commands::tests::test_docx_path;
+
+        let path = test_docx_path("undo");
+        let file_path = path.to_string_lossy().to_string();
+        fs::write(&path, b"original document").unwrap();
+
+        // In-place formatting: snapshot, overwrite, record
+        let snapshot = capture_format_snapshot(&file_path, &file_path).unwrap();
+        fs::write(&path, b"formatted document").unwrap();
+        let response = FormatDocxResponse {
+            success: true,
+            output_file: file_path.clone(),
+            applied_changes: json!({"font": "Arial"}),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            parse_trace: Vec::new(),
+        };
+        record_format_history(snapshot, &response, Some("Schriftart Arial".to_string()), None).unwrap();
+
+        let history = list_format_history(file_path.clone()).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].request.as_deref(), Some("Schriftart Arial"));
+
+        let entry = undo_format(file_path.clone()).await.unwrap();
+        assert_eq!(entry.id, history[0].id);
+        assert_eq!(fs::read(&path).unwrap(), b"original document");
+        assert!(list_format_history(file_path.clone()).await.unwrap().is_empty());
+        assert!(undo_format(file_path.clone()).await.is_err());
+
+        let _ = fs::remove_dir_all(get_format_history_dir(&file_path).unwrap());
+        let _ = fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_two_font_document_becomes_single_font() {
+        use crate::commands::document_commands::tests::{test_docx_path, write_test_docx};
+
+        let run = |properties: &str, text: &str| format!(r#"<w:p><w:r w:rsidR="00A1">{}<w:t>{}</w:t></w:r></w:p>"#, properties, text);
+        let body = [
+            run(r#"<w:rPr><w:rFonts w:ascii="Arial" w:hAnsi="Arial" w:cs="Arial" w:eastAsia="Arial"/><w:sz w:val="22"/><w:szCs w:val="22"/></w:rPr>"#, "Befund"),
+            run(r#"<w:rPr><w:rFonts w:ascii="Times New Roman"/><w:b/><w:i/><w:color w:val="FF0000"/><w:sz w:val="24"/><w:u w:val="single"/></w:rPr>"#, "Eingefügt"),
+            run("", "Ohne Format"),
+        ].concat();
+        let input = test_docx_path("unify_font_in");
+        let output = test_docx_path("unify_font_out");
+        write_test_docx(&input, &body, &[]);
+
+        let response = unify_document_font(input.to_string_lossy().to_string(), output.to_string_lossy().to_string(), "Arial".to_string(), 11.0).await.unwrap();
+        assert_eq!(response.applied_changes["runs_total"], 3);
+        assert_eq!(response.applied_changes["runs_changed"], 2);
+        assert_eq!(response.applied_changes["fonts_before"]["Times New Roman 12pt"], 1);
+
+        let mut archive = zip::ZipArchive::new(BufReader::new(fs::File::open(&output).unwrap())).unwrap();
+        let xml = extract_document_xml(&mut archive).unwrap();
+        assert!(!xml.contains("Times New Roman") && !xml.contains(r#"w:val="24""#));
+        assert_eq!(xml.matches(r#"<w:rFonts w:ascii="Arial""#).count(), 3);
+        assert!(xml.contains(r#"<w:b/><w:i/><w:color w:val="FF0000"/><w:sz w:val="22"/><w:szCs w:val="22"/><w:u w:val="single"/>"#), "{}", xml);
+        assert_eq!(unify_run_fonts(&xml, "Arial", 11.0).runs_changed, 0);
+
+        let _ = fs::remove_dir_all(get_format_history_dir(&output.to_string_lossy()).unwrap());
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+    }
+
+    #[tokio::test]
+    async fn test_mixed_line_spacing_becomes_uniform() {
+        use crate::commands::document_commands::tests::{test_docx_path, write_test_docx};
+
+        let paragraph = |properties: &str, text: &str| format!(r#"<w:p w:rsidR="00B2">{}<w:r><w:t>{}</w:t></w:r></w:p>"#, properties, text);
+        let body = [
+            paragraph(r#"<w:pPr><w:pStyle w:val="Heading1"/><w:spacing w:before="240" w:line="240" w:lineRule="auto"/></w:pPr>"#, "Befund"),
+            paragraph(r#"<w:pPr><w:spacing w:after="120" w:line="360" w:lineRule="auto"/></w:pPr>"#, "Schon richtig"),
+            paragraph(r#"<w:pPr><w:spacing w:line="480" w:lineRule="auto"/><w:jc w:val="both"/><w:rPr><w:spacing w:val="20"/></w:rPr></w:pPr>"#, "Doppelt"),
+            paragraph(r#"<w:pPr><w:spacing w:line="280" w:lineRule="exact"/></w:pPr>"#, "Eingefügt"),
+            paragraph(r#"<w:pPr><w:jc w:val="both"/></w:pPr>"#, "Aus der Formatvorlage"),
+            paragraph("", "Ohne Absatzformat"),
+        ].concat();
+        let input = test_docx_path("line_spacing_in");
+        let output = test_docx_path("line_spacing_out");
+        write_test_docx(&input, &body, &[]);
+
+        let response = normalize_line_spacing(input.to_string_lossy().to_string(), output.to_string_lossy().to_string(), 1.5, None).await.unwrap();
+        assert_eq!(response.applied_changes["paragraphs_total"], 5);
+        assert_eq!(response.applied_changes["paragraphs_changed"], 4);
+        assert_eq!(response.applied_changes["spacings_before"]["genau 14pt"], 1);
+        assert_eq!(response.applied_changes["spacings_before"]["(Formatvorlage)"], 2);
+
+        let mut archive = zip::ZipArchive::new(BufReader::new(fs::File::open(&output).unwrap())).unwrap();
+        let xml = extract_document_xml(&mut archive).unwrap();
+        assert_eq!(xml.matches(r#"w:line="360" w:lineRule="auto""#).count(), 5);
+        // Spacing before/after, alignment and letter spacing stay; the heading is untouched
+        assert!(xml.contains(r#"<w:spacing w:after="120" w:line="360" w:lineRule="auto"/>"#));
+        assert!(xml.contains(r#"<w:spacing w:line="360" w:lineRule="auto"/><w:jc w:val="both"/><w:rPr><w:spacing w:val="20"/></w:rPr>"#), "{}", xml);
+        assert!(xml.contains(r#"<w:spacing w:before="240" w:line="240" w:lineRule="auto"/>"#));
+        assert_eq!(normalize_paragraph_line_spacing(&xml, 1.5, None).paragraphs_changed, 0);
+
+        // With a separate heading spacing the heading is set as well
+        let headings = normalize_paragraph_line_spacing(&xml, 1.5, Some(1.0));
+        assert_eq!((headings.paragraphs_total, headings.paragraphs_changed), (6, 0));
+        assert_eq!(normalize_paragraph_line_spacing(&xml, 1.5, Some(1.15)).paragraphs_changed, 1);
+
+        let _ = fs::remove_dir_all(get_format_history_dir(&output.to_string_lossy()).unwrap());
+        let _ = fs::remove_file(&input);
+        let _ = fs::remove_file(&output);
+    }
+}
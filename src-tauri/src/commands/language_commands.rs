@@ -0,0 +1,87 @@
+// Language commands - Detects the language of text (OCR output, edited transcripts)
+use tauri::command;
+use serde::{Deserialize, Serialize};
+
+/// Inputs shorter than this are too short for a reliable guess
+const MIN_RELIABLE_CHARS: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TextLanguageResult {
+    pub language: String,  // ISO 639-1 code ("de", "en", ...) or "unknown"
+    pub confidence: f32,
+    pub is_reliable: bool,
+}
+
+/// Detect the language of a text without a subprocess
+pub fn detect_language(text: &str) -> TextLanguageResult {
+    let trimmed = text.trim();
+    let info = match whatlang::detect(trimmed) {
+        Some(info) => info,
+        None => {
+            return TextLanguageResult {
+                language: "unknown".to_string(),
+                confidence: 0.0,
+                is_reliable: false,
+            };
+        }
+    };
+
+    // Scale the confidence down for very short inputs
+    let char_count = trimmed.chars().filter(|c| c.is_alphabetic()).count();
+    let mut confidence = info.confidence() as f32;
+    if char_count < MIN_RELIABLE_CHARS {
+        confidence *= char_count as f32 / MIN_RELIABLE_CHARS as f32 * 0.5;
+    }
+
+    TextLanguageResult {
+        language: iso_639_1(info.lang()).to_string(),
+        confidence,
+        is_reliable: info.is_reliable() && char_count >= MIN_RELIABLE_CHARS,
+    }
+}
+
+/// Map the languages relevant for Gutachten to their two-letter codes
+fn iso_639_1(lang: whatlang::Lang) -> &'static str {
+    use whatlang::Lang;
+    match lang {
+        Lang::Deu => "de",
+        Lang::Eng => "en",
+        Lang::Fra => "fr",
+        Lang::Ita => "it",
+        Lang::Spa => "es",
+        Lang::Nld => "nl",
+        Lang::Pol => "pl",
+        Lang::Tur => "tr",
+        Lang::Rus => "ru",
+        Lang::Por => "pt",
+        other => other.code(),  // ISO 639-3 for everything else
+    }
+}
+
+/// Detect the language of a text
+#[command]
+pub async fn detect_text_language(text: String) -> Result<TextLanguageResult, String> {
+    Ok(detect_language(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_german_paragraph_is_detected() {
+        let text = "Der Patient berichtet, dass er seit 2019 unter Rückenschmerzen leidet. \
+            Die Schmerzen strahlen in das linke Bein aus und verstärken sich beim Gehen.";
+        let result = detect_language(text);
+        assert_eq!(result.language, "de");
+        assert!(result.is_reliable);
+        assert!(result.confidence > 0.5);
+    }
+
+    #[test]
+    fn test_short_input_has_low_confidence() {
+        let result = detect_language("Befund");
+        assert!(!result.is_reliable);
+        assert!(result.confidence < 0.5);
+    }
+}
@@ -5,24 +5,21 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::path::PathBuf;
 use std::process::{Command, Stdio, Child, ChildStdin, ChildStdout};
+use std::collections::VecDeque;
 use std::fs;
-use std::io::{BufRead, BufReader, Write, BufWriter};
+use std::io::{BufRead, BufReader, Read, Write, BufWriter};
 use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use once_cell::sync::Lazy;
+use thiserror::Error;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct GrammarCorrectionResponse {
-    pub corrected_text: String,
-    pub changes_made: Vec<String>,
-    pub confidence: f32,
-    pub processing_time_ms: u64,
-    pub guardrail_status: String,
-    pub violations: Vec<String>,
-    pub notes: Vec<String>,
-    pub attempts: u32,
-    pub removed_tokens: Vec<String>,
-    pub tokens_per_sec: Option<f32>,
-}
+use crate::text_segmentation::{chunk_paragraphs, split_paragraphs, SegmentationOptions};
+use crate::commands::warmup_commands::{warmup_memory_check, yield_llm_warmup};
+use crate::commands::section_prompt_commands::active_section_prompts;
+use crate::commands::docx_commands::is_break_marker;
+use crate::memory_manager::{get_gpu_memory_info, get_system_memory_info, GpuMemoryInfo};
+use crate::script_locator::{locate_script, LLAMA_SCRIPT, QWEN_SCRIPT};
+use crate::dto::GrammarCorrectionResponse;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StructuredContent {
@@ -31,6 +28,442 @@ pub struct StructuredContent {
     pub missing_slots: Vec<String>,
     pub processing_time_ms: u64,
     pub tokens_per_sec: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_debug: Option<Value>,  // Composed prompt, only with prompt debugging enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<Value>,    // Sampling parameters the worker actually used
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker_notice: Option<String>,  // Set when the other model's worker had to be stopped for this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub section_prompts: Vec<String>,   // Sections whose custom instruction was sent with the request
+}
+
+/// The prompt the worker composed for the last request (kept in memory only)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LastPrompt {
+    pub timestamp: String,
+    pub model_type: String,
+    pub prompt: Value,
+}
+
+/// Prompt debugging setting; off by default because prompts contain the transcript
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PromptDebugSettings {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Sampling parameters of one task
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GenerationParams {
+    pub temperature: f32,
+    pub top_p: f32,
+    pub repeat_penalty: f32,
+    pub max_tokens: u32,
+}
+
+/// Generation parameters per task; structuring wants deterministic output,
+/// stylistic smoothing some variability
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GenerationSettings {
+    #[serde(default = "default_grammar_generation")]
+    pub grammar: GenerationParams,
+    #[serde(default = "default_structuring_generation")]
+    pub structuring: GenerationParams,
+    #[serde(default = "default_clarification_generation")]
+    pub clarification: GenerationParams,
+}
+
+impl Default for GenerationSettings {
+    fn default() -> Self {
+        GenerationSettings {
+            grammar: default_grammar_generation(),
+            structuring: default_structuring_generation(),
+            clarification: default_clarification_generation(),
+        }
+    }
+}
+
+// Factory values match the worker scripts' built-in CONFIG
+fn default_grammar_generation() -> GenerationParams {
+    GenerationParams { temperature: 0.0, top_p: 1.0, repeat_penalty: 1.0, max_tokens: 3000 }
+}
+
+fn default_structuring_generation() -> GenerationParams {
+    GenerationParams { temperature: 0.1, top_p: 0.95, repeat_penalty: 1.1, max_tokens: 2000 }
+}
+
+fn default_clarification_generation() -> GenerationParams {
+    GenerationParams { temperature: 0.3, top_p: 0.9, repeat_penalty: 1.1, max_tokens: 500 }
+}
+
+impl GenerationParams {
+    fn validate(&self, task: &str) -> Result<(), String> {
+        if !(0.0..=2.0).contains(&self.temperature) {
+            return Err(format!("{}: Temperatur muss zwischen 0 und 2 liegen", task));
+        }
+        if !(self.top_p > 0.0 && self.top_p <= 1.0) {
+            return Err(format!("{}: top_p muss größer als 0 und höchstens 1 sein", task));
+        }
+        if !(1.0..=2.0).contains(&self.repeat_penalty) {
+            return Err(format!("{}: Wiederholungsstrafe muss zwischen 1 und 2 liegen", task));
+        }
+        if !(1..=4096).contains(&self.max_tokens) {
+            return Err(format!("{}: max_tokens muss zwischen 1 und 4096 liegen", task));
+        }
+        Ok(())
+    }
+}
+
+impl GenerationSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        self.grammar.validate("Grammatik")?;
+        self.structuring.validate("Strukturierung")?;
+        self.clarification.validate("Rückfragen")
+    }
+}
+
+/// What a worker script supports, announced in its ping response.
+/// Older scripts without the field get the minimal protocol (version 1).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkerCapabilities {
+    #[serde(default = "default_protocol_version")]
+    pub protocol_version: u32,
+    #[serde(default = "default_worker_commands")]
+    pub commands: Vec<String>,
+    #[serde(default)]
+    pub streaming: bool,
+    #[serde(default)]
+    pub n_ctx: Option<u32>,
+    #[serde(default)]
+    pub features: Vec<String>,  // e.g. "debug", "generation_params", "section_schemas"
+}
+
+impl Default for WorkerCapabilities {
+    fn default() -> Self {
+        WorkerCapabilities {
+            protocol_version: default_protocol_version(),
+            commands: default_worker_commands(),
+            streaming: false,
+            n_ctx: None,
+            features: Vec::new(),
+        }
+    }
+}
+
+fn default_protocol_version() -> u32 {
+    1
+}
+
+fn default_worker_commands() -> Vec<String> {
+    ["ping", "shutdown", "metrics", "text"].iter().map(|c| c.to_string()).collect()
+}
+
+impl WorkerCapabilities {
+    pub fn supports_command(&self, command: &str) -> bool {
+        self.commands.iter().any(|c| c == command)
+    }
+
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    /// Error for the UI when a feature needs a newer worker script
+    pub fn require_feature(&self, feature: &str) -> Result<(), String> {
+        let supported = match feature {
+            "streaming" => self.streaming,
+            _ => self.has_feature(feature),
+        };
+        if supported {
+            return Ok(());
+        }
+        Err(match feature {
+            "streaming" => "Worker-Skript unterstützt kein Streaming – bitte aktualisieren".to_string(),
+            "generation_params" => "Worker-Skript unterstützt keine Generierungsparameter – bitte aktualisieren".to_string(),
+            "section_schemas" => "Worker-Skript unterstützt keine Abschnitts-Schemata – bitte aktualisieren".to_string(),
+            "grammar" => "Worker-Skript unterstützt keine Grammatikkorrektur – bitte aktualisieren".to_string(),
+            _ => format!("Worker-Skript unterstützt '{}' nicht – bitte aktualisieren", feature),
+        })
+    }
+
+    pub fn require_command(&self, command: &str) -> Result<(), String> {
+        if self.supports_command(command) {
+            Ok(())
+        } else {
+            Err(format!("Worker-Skript unterstützt den Befehl '{}' nicht – bitte aktualisieren", command))
+        }
+    }
+}
+
+/// Measurements of a single benchmark request
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BenchmarkRun {
+    pub tokens_generated: u32,
+    pub tokens_per_sec: f32,
+    pub latency_ms: u64,
+}
+
+/// Benchmark averaged over several runs of the same prompt
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BenchmarkResult {
+    pub timestamp: String,
+    pub model_type: String,
+    pub runs: Vec<BenchmarkRun>,
+    pub tokens_generated: f32,
+    pub tokens_per_sec: f32,
+    pub latency_ms: u64,
+}
+
+const BENCHMARK_RUNS: usize = 3;
+const BENCHMARK_PROMPT: &str = "Eigenanamnese punkt Der Patient berichtet komma dass er seit 2019 unter \
+Rückenschmerzen leidet punkt Eine Operation wurde im Jahr 2020 durchgeführt punkt \
+Aktuelle Beschwerden punkt Die Schmerzen strahlen in das linke Bein aus punkt";
+const MAX_PERFORMANCE_HISTORY: usize = 100;
+
+/// Timing of one structuring request, for the time estimate
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StructuringSample {
+    pub timestamp: String,
+    pub transcript_tokens: u32,    // Estimated from the transcript length
+    pub processing_ms: u64,        // Request only, without model load
+    pub load_ms: Option<u64>,      // Set when the worker had to be started first
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StructuringEstimate {
+    pub estimated_seconds: f32,
+    pub based_on_samples: usize,   // 0: no measurement yet, default rate used
+    pub transcript_tokens: u32,
+    pub includes_model_load: bool,
+}
+
+/// German text with the Qwen/Llama tokenizers, roughly
+const CHARS_PER_TOKEN: f32 = 3.5;
+const MAX_STRUCTURING_SAMPLES: usize = 20;
+/// Before any measurement: a slow CPU-only machine
+const DEFAULT_STRUCTURING_TOKENS_PER_SEC: f32 = 8.0;
+const DEFAULT_MODEL_LOAD_SECS: f32 = 30.0;
+
+static LAST_PROMPT: Lazy<Mutex<Option<LastPrompt>>> = Lazy::new(|| Mutex::new(None));
+
+const MAX_WORKER_LOG_LINES: usize = 500;
+const DEFAULT_WORKER_LOG_LINES: usize = 100;
+
+/// Bounded buffer of the worker's stderr (its own messages and the forwarded llama-server log)
+pub struct WorkerLogBuffer {
+    lines: VecDeque<String>,
+    capacity: usize,
+}
+
+impl WorkerLogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        WorkerLogBuffer { lines: VecDeque::new(), capacity }
+    }
+
+    pub fn push(&mut self, line: String) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+    }
+
+    /// The last `count` lines, oldest first
+    pub fn tail(&self, count: usize) -> Vec<String> {
+        self.lines.iter().skip(self.lines.len().saturating_sub(count)).cloned().collect()
+    }
+}
+
+static WORKER_LOGS: Lazy<Mutex<WorkerLogBuffer>> = Lazy::new(|| Mutex::new(WorkerLogBuffer::new(MAX_WORKER_LOG_LINES)));
+
+/// Copy a worker's stderr into the buffer until it closes; lines are echoed to the app's stderr.
+/// Invalid UTF-8 (console code pages) is replaced rather than ending the capture.
+fn read_worker_log<R: Read>(stderr: R, buffer: &Mutex<WorkerLogBuffer>) {
+    let mut reader = BufReader::new(stderr);
+    let mut raw = Vec::new();
+    loop {
+        raw.clear();
+        match reader.read_until(b'\n', &mut raw) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {
+                let line = String::from_utf8_lossy(&raw).trim_end().to_string();
+                if line.is_empty() {
+                    continue;
+                }
+                eprintln!("{}", line);
+                if let Ok(mut buffer) = buffer.lock() {
+                    buffer.push(format!("[{}] {}", chrono::Local::now().format("%H:%M:%S"), line));
+                }
+            }
+        }
+    }
+}
+
+const QWEN_MODEL_PATH: &str = r"C:\Users\kalin\Desktop\gutachten-assistant\models\qwen2.5-7b-instruct-q4_k_m.gguf";
+const LLAMA_MODEL_PATH: &str = r"C:\Users\kalin\Desktop\gutachten-assistant\models\llama-3.1-8b-instruct-q4_k_m.gguf";
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+const MAX_GGUF_METADATA_ENTRIES: u64 = 10_000;  // Real models have a few dozen; more means a corrupt header
+const MAX_GGUF_STRING_LEN: u64 = 1 << 20;
+
+#[derive(Debug, Error)]
+pub enum GgufError {
+    #[error("Modelldatei nicht gefunden: {0}")]
+    NotFound(String),
+    #[error("Keine GGUF-Modelldatei (falsche Signatur)")]
+    BadMagic,
+    #[error("GGUF-Version {0} wird nicht unterstützt")]
+    UnsupportedVersion(u32),
+    #[error("Modelldatei ist abgeschnitten oder beschädigt (Download unvollständig?)")]
+    Truncated,
+    #[error("GGUF-Kopfdaten sind beschädigt: {0}")]
+    Corrupt(String),
+    #[error("Failed to read model file: {0}")]
+    Io(std::io::Error),
+}
+
+/// Errors choosing the model for a request
+#[derive(Debug, Error, PartialEq)]
+pub enum ModelSelectionError {
+    #[error("Kein Sprachmodell gefunden. Erwartet: {} im Ordner {models_dir}", expected.join(" oder "))]
+    ModelMissing { expected: Vec<String>, models_dir: String },
+    #[error("Unbekanntes Modell: {0} (erlaubt: llama, qwen, auto)")]
+    UnknownModel(String),
+}
+
+impl ModelSelectionError {
+    fn model_missing(paths: &[&str]) -> Self {
+        // The model paths are Windows paths; split at the last backslash on every platform
+        let split = |p: &str| p.rsplit_once('\\').map(|(dir, file)| (dir.to_string(), file.to_string())).unwrap_or_default();
+        ModelSelectionError::ModelMissing {
+            expected: paths.iter().map(|p| split(p).1).collect(),
+            models_dir: paths.first().map(|p| split(p).0).unwrap_or_default(),
+        }
+    }
+}
+
+/// Pick the grammar model: "llama", "qwen" or "auto" (default: Llama, the grammar model, else
+/// whichever GGUF is installed). Returns whether Qwen is used.
+fn select_grammar_model(requested: Option<&str>, llama_exists: bool, qwen_exists: bool) -> Result<bool, ModelSelectionError> {
+    match requested.unwrap_or("auto") {
+        "llama" if llama_exists => Ok(false),
+        "llama" => Err(ModelSelectionError::model_missing(&[LLAMA_MODEL_PATH])),
+        "qwen" if qwen_exists => Ok(true),
+        "qwen" => Err(ModelSelectionError::model_missing(&[QWEN_MODEL_PATH])),
+        "auto" if llama_exists || qwen_exists => Ok(!llama_exists),
+        "auto" => Err(ModelSelectionError::model_missing(&[LLAMA_MODEL_PATH, QWEN_MODEL_PATH])),
+        other => Err(ModelSelectionError::UnknownModel(other.to_string())),
+    }
+}
+
+impl From<std::io::Error> for GgufError {
+    fn from(e: std::io::Error) -> Self {
+        if e.kind() == std::io::ErrorKind::UnexpectedEof {
+            GgufError::Truncated
+        } else {
+            GgufError::Io(e)
+        }
+    }
+}
+
+/// Result of checking a model file's GGUF header
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct GgufInfo {
+    pub valid: bool,
+    pub version: u32,
+    pub architecture: Option<String>,  // "general.architecture", e.g. "qwen2" or "llama"
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, GgufError> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> Result<u64, GgufError> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// Counts and string lengths are u32 in GGUF v1, u64 from v2 on
+fn read_gguf_len<R: Read>(reader: &mut R, version: u32) -> Result<u64, GgufError> {
+    if version == 1 { read_u32(reader).map(u64::from) } else { read_u64(reader) }
+}
+
+fn read_gguf_string<R: Read>(reader: &mut R, version: u32) -> Result<String, GgufError> {
+    let len = read_gguf_len(reader, version)?;
+    if len > MAX_GGUF_STRING_LEN {
+        return Err(GgufError::Corrupt(format!("Zeichenkette mit {} Bytes", len)));
+    }
+    let mut buf = vec![0u8; len as usize];
+    reader.read_exact(&mut buf)?;
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+/// Skip a metadata value of the given GGUF type
+fn skip_gguf_value<R: Read>(reader: &mut R, value_type: u32, version: u32) -> Result<(), GgufError> {
+    let size = match value_type {
+        0 | 1 | 7 => 1,      // u8, i8, bool
+        2 | 3 => 2,          // u16, i16
+        4..=6 => 4,          // u32, i32, f32
+        10..=12 => 8,        // u64, i64, f64
+        8 => {
+            read_gguf_string(reader, version)?;
+            return Ok(());
+        }
+        9 => {
+            let item_type = read_u32(reader)?;
+            let count = read_gguf_len(reader, version)?;
+            for _ in 0..count {
+                skip_gguf_value(reader, item_type, version)?;
+            }
+            return Ok(());
+        }
+        other => return Err(GgufError::Corrupt(format!("unbekannter Werttyp {}", other))),
+    };
+    std::io::copy(&mut reader.by_ref().take(size), &mut std::io::sink())
+        .map_err(GgufError::from)
+        .and_then(|n| if n == size { Ok(()) } else { Err(GgufError::Truncated) })
+}
+
+/// Read magic, version and metadata up to "general.architecture" without touching the tensors
+pub fn read_gguf_header<R: Read>(reader: &mut R) -> Result<GgufInfo, GgufError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != GGUF_MAGIC {
+        return Err(GgufError::BadMagic);
+    }
+    let version = read_u32(reader)?;
+    if !(1..=3).contains(&version) {
+        return Err(GgufError::UnsupportedVersion(version));
+    }
+    let _tensor_count = read_gguf_len(reader, version)?;
+    let metadata_count = read_gguf_len(reader, version)?;
+    if metadata_count > MAX_GGUF_METADATA_ENTRIES {
+        return Err(GgufError::Corrupt(format!("{} Metadaten-Einträge", metadata_count)));
+    }
+
+    let mut architecture = None;
+    for _ in 0..metadata_count {
+        let key = read_gguf_string(reader, version)?;
+        let value_type = read_u32(reader)?;
+        if key == "general.architecture" && value_type == 8 {
+            architecture = Some(read_gguf_string(reader, version)?);
+            break;
+        }
+        skip_gguf_value(reader, value_type, version)?;
+    }
+
+    Ok(GgufInfo { valid: true, version, architecture })
+}
+
+/// Check a model file before handing it to a worker, so broken downloads fail instantly
+pub fn validate_gguf_file(path: &std::path::Path) -> Result<GgufInfo, GgufError> {
+    if !path.exists() {
+        return Err(GgufError::NotFound(path.to_string_lossy().to_string()));
+    }
+    let mut reader = BufReader::new(fs::File::open(path)?);
+    read_gguf_header(&mut reader)
 }
 
 // Persistent worker process manager
@@ -39,6 +472,7 @@ struct LlamaWorker {
     stdin: Option<BufWriter<ChildStdin>>,
     stdout: Option<BufReader<ChildStdout>>,
     model_type: String,
+    capabilities: WorkerCapabilities,  // From the ping handshake; minimal protocol for older scripts
 }
 
 impl LlamaWorker {
@@ -48,6 +482,7 @@ impl LlamaWorker {
             stdin: None,
             stdout: None,
             model_type: "none".to_string(),
+            capabilities: WorkerCapabilities::default(),
         }
     }
 
@@ -59,6 +494,7 @@ impl LlamaWorker {
                     self.stdin = None;
                     self.stdout = None;
                     self.model_type = "none".to_string();
+                    self.capabilities = WorkerCapabilities::default();
                     false
                 }
                 Ok(None) => true,
@@ -70,6 +506,12 @@ impl LlamaWorker {
     }
 
     fn start(&mut self, use_qwen: bool) -> Result<(), String> {
+        self.start_with(use_qwen, None, &|_| {})
+    }
+
+    /// Start the worker; `cancel` aborts the wait for the model (the worker is stopped again),
+    /// `on_progress` receives the elapsed share of the maximum wait
+    fn start_with(&mut self, use_qwen: bool, cancel: Option<&AtomicBool>, on_progress: &dyn Fn(f32)) -> Result<(), String> {
         let model_name = if use_qwen { "qwen" } else { "llama" };
 
         // If already running with correct model, return
@@ -84,12 +526,12 @@ impl LlamaWorker {
             self.stop();
         }
 
+        let model_path = PathBuf::from(if use_qwen { QWEN_MODEL_PATH } else { LLAMA_MODEL_PATH });
+        let gguf = validate_gguf_file(&model_path).map_err(|e| e.to_string())?;
+        println!("[RUST] Model file OK: GGUF v{}, architecture {}", gguf.version, gguf.architecture.as_deref().unwrap_or("unknown"));
+
         let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
-        let script_path = if use_qwen {
-            r"C:\Users\kalin\Desktop\gutachten-assistant\qwen_structurer.py"
-        } else {
-            r"C:\Users\kalin\Desktop\gutachten-assistant\llama_worker.py"
-        };
+        let script_path = locate_script(if use_qwen { QWEN_SCRIPT } else { LLAMA_SCRIPT })?;
 
         println!("[RUST] Starting {} worker process...", model_name);
 
@@ -97,12 +539,19 @@ impl LlamaWorker {
             .arg(script_path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::inherit())
+            .stderr(Stdio::piped())
             .env("PYTHONIOENCODING", "utf-8")
             .env("PYTHONUNBUFFERED", "1")
             .spawn()
             .map_err(|e| format!("Failed to start worker: {}", e))?;
 
+        if let Ok(mut logs) = WORKER_LOGS.lock() {
+            logs.push(format!("--- {} worker gestartet ---", model_name));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            std::thread::spawn(move || read_worker_log(stderr, &WORKER_LOGS));
+        }
+
         let stdin = child.stdin.take()
             .ok_or("Failed to capture stdin")?;
         let stdout = child.stdout.take()
@@ -121,7 +570,13 @@ impl LlamaWorker {
 
         // Send ping and wait for ready response
         for attempt in 1..=max_wait {
+            if cancel.is_some_and(|c| c.load(Ordering::SeqCst)) {
+                println!("[RUST] Loading {} cancelled", model_name);
+                self.stop();
+                return Err("Laden des Modells abgebrochen".to_string());
+            }
             std::thread::sleep(std::time::Duration::from_millis(500));
+            on_progress(attempt as f32 / max_wait as f32);
 
             // Try to ping the worker
             if let (Some(stdin), Some(stdout)) = (self.stdin.as_mut(), self.stdout.as_mut()) {
@@ -129,7 +584,12 @@ impl LlamaWorker {
                 if writeln!(stdin, "{}", ping).is_ok() && stdin.flush().is_ok() {
                     let mut response = String::new();
                     if stdout.read_line(&mut response).is_ok() {
-                        if response.contains("\"model_loaded\":true") || response.contains("\"server_ready\":true") {
+                        // Parsed rather than matched textually: json.dumps writes "model_loaded": true with a space
+                        let ping: Value = serde_json::from_str(&response).unwrap_or(Value::Null);
+                        let ready = ["model_loaded", "server_ready"].iter()
+                            .any(|key| ping.get(*key).and_then(|v| v.as_bool()) == Some(true));
+                        self.capabilities = parse_capabilities(&ping);
+                        if ready {
                             println!("[RUST] {} worker ready after {:.1}s", model_name, attempt as f32 * 0.5);
                             return Ok(());
                         }
@@ -146,28 +606,27 @@ impl LlamaWorker {
         Ok(())
     }
 
+    /// Add the task's generation parameters if the worker script understands them
+    fn attach_generation(&self, request: &mut Value, params: &GenerationParams) {
+        match self.capabilities.require_feature("generation_params") {
+            Ok(()) => request["generation"] = serde_json::json!(params),
+            Err(e) => println!("[RUST] {} worker: {}; using its defaults", self.model_type, e),
+        }
+    }
+
     fn send_request(&mut self, request: &Value, use_qwen: bool) -> Result<Value, String> {
         if !self.is_running() || (use_qwen && self.model_type != "qwen") || (!use_qwen && self.model_type != "llama") {
             self.start(use_qwen)?;
         }
 
+        self.request_running(request)
+    }
+
+    /// Send a request to the already running worker without starting or switching models
+    fn request_running(&mut self, request: &Value) -> Result<Value, String> {
         let stdin = self.stdin.as_mut().ok_or("Worker stdin not available")?;
         let stdout = self.stdout.as_mut().ok_or("Worker stdout not available")?;
-
-        let request_str = serde_json::to_string(request)
-            .map_err(|e| format!("Failed to serialize request: {}", e))?;
-
-        writeln!(stdin, "{}", request_str)
-            .map_err(|e| format!("Failed to write to worker: {}", e))?;
-        stdin.flush()
-            .map_err(|e| format!("Failed to flush stdin: {}", e))?;
-
-        let mut response_line = String::new();
-        stdout.read_line(&mut response_line)
-            .map_err(|e| format!("Failed to read from worker: {}", e))?;
-
-        serde_json::from_str(&response_line)
-            .map_err(|e| format!("Failed to parse worker response: {} - got: {}", e, response_line))
+        exchange_request(stdin, stdout, request)
     }
 
     fn stop(&mut self) {
@@ -184,6 +643,7 @@ impl LlamaWorker {
         self.stdin = None;
         self.stdout = None;
         self.model_type = "none".to_string();
+        self.capabilities = WorkerCapabilities::default();
         println!("[RUST] Worker stopped");
     }
 }
@@ -194,16 +654,350 @@ impl Drop for LlamaWorker {
     }
 }
 
-// Global worker instance
+/// Capabilities a worker announces in its ping response; a plain feature list
+/// (protocol 1 scripts) or a missing field fall back to the minimal protocol
+fn parse_capabilities(ping: &Value) -> WorkerCapabilities {
+    match ping.get("capabilities") {
+        Some(Value::Object(_)) => serde_json::from_value(ping["capabilities"].clone()).unwrap_or_default(),
+        Some(Value::Array(features)) => WorkerCapabilities {
+            features: features.iter().filter_map(|f| f.as_str().map(String::from)).collect(),
+            ..Default::default()
+        },
+        _ => WorkerCapabilities::default(),
+    }
+}
+
+/// Write one JSON request line to the worker and read one JSON response line back
+fn exchange_request<W: Write, R: BufRead>(stdin: &mut W, stdout: &mut R, request: &Value) -> Result<Value, String> {
+    let request_str = serde_json::to_string(request)
+        .map_err(|e| format!("Failed to serialize request: {}", e))?;
+
+    writeln!(stdin, "{}", request_str)
+        .map_err(|e| format!("Failed to write to worker: {}", e))?;
+    stdin.flush()
+        .map_err(|e| format!("Failed to flush stdin: {}", e))?;
+
+    let mut response_line = String::new();
+    stdout.read_line(&mut response_line)
+        .map_err(|e| format!("Failed to read from worker: {}", e))?;
+    crate::commands::text_commands::warn_on_mojibake("Worker", &response_line);
+
+    serde_json::from_str(&response_line)
+        .map_err(|e| format!("Failed to parse worker response: {} - got: {}", e, response_line))
+}
+
+/// Send the benchmark prompt once and measure the response
+fn run_benchmark_request<W: Write, R: BufRead>(stdin: &mut W, stdout: &mut R) -> Result<BenchmarkRun, String> {
+    let start = std::time::Instant::now();
+    let response = exchange_request(stdin, stdout, &serde_json::json!({"text": BENCHMARK_PROMPT}))?;
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+
+    if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
+        return Err(format!("Benchmark request failed: {}", error));
+    }
+
+    Ok(parse_benchmark_run(&response, elapsed_ms))
+}
+
+/// Read token count and timing from a worker response.
+/// Llama reports completion_tokens/total_time_ms, Qwen tokens_predicted/processing_time_s.
+fn parse_benchmark_run(response: &Value, elapsed_ms: u64) -> BenchmarkRun {
+    let metrics = response.get("metrics").unwrap_or(response);
+
+    let tokens_generated = metrics.get("completion_tokens")
+        .or_else(|| metrics.get("tokens_predicted"))
+        .and_then(|t| t.as_u64())
+        .unwrap_or(0) as u32;
+
+    let latency_ms = metrics.get("total_time_ms")
+        .and_then(|t| t.as_u64())
+        .or_else(|| metrics.get("processing_time_s")
+            .and_then(|t| t.as_f64())
+            .map(|s| (s * 1000.0) as u64))
+        .unwrap_or(elapsed_ms);
+
+    let tokens_per_sec = metrics.get("tokens_per_sec")
+        .and_then(|t| t.as_f64())
+        .map(|t| t as f32)
+        .filter(|t| *t > 0.0)
+        .unwrap_or_else(|| {
+            if latency_ms > 0 {
+                tokens_generated as f32 * 1000.0 / latency_ms as f32
+            } else {
+                0.0
+            }
+        });
+
+    BenchmarkRun { tokens_generated, tokens_per_sec, latency_ms }
+}
+
+/// Average the runs of one benchmark
+fn aggregate_benchmark_runs(model_type: &str, runs: Vec<BenchmarkRun>) -> BenchmarkResult {
+    let count = runs.len().max(1);
+
+    BenchmarkResult {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        model_type: model_type.to_string(),
+        tokens_generated: runs.iter().map(|r| r.tokens_generated as f32).sum::<f32>() / count as f32,
+        tokens_per_sec: runs.iter().map(|r| r.tokens_per_sec).sum::<f32>() / count as f32,
+        latency_ms: runs.iter().map(|r| r.latency_ms).sum::<u64>() / count as u64,
+        runs,
+    }
+}
+
+/// Get the path to the performance history file
+fn get_performance_history_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("performance").join("history.json"))
+}
+
+fn load_performance_history() -> Result<Vec<BenchmarkResult>, String> {
+    let path = get_performance_history_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read performance history: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse performance history: {}", e))
+}
+
+fn append_performance_history(result: &BenchmarkResult) -> Result<(), String> {
+    let path = get_performance_history_path()?;
+    let mut history = load_performance_history().unwrap_or_default();
+    history.push(result.clone());
+    if history.len() > MAX_PERFORMANCE_HISTORY {
+        history.drain(..history.len() - MAX_PERFORMANCE_HISTORY);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create performance directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&history)
+        .map_err(|e| format!("Failed to serialize performance history: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write performance history: {}", e))
+}
+
+fn get_structuring_samples_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("performance").join("structuring.json"))
+}
+
+fn load_structuring_samples() -> Vec<StructuringSample> {
+    get_structuring_samples_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn append_structuring_sample(sample: StructuringSample) -> Result<(), String> {
+    let path = get_structuring_samples_path()?;
+    let mut samples = load_structuring_samples();
+    samples.push(sample);
+    if samples.len() > MAX_STRUCTURING_SAMPLES {
+        samples.drain(..samples.len() - MAX_STRUCTURING_SAMPLES);
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create performance directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&samples)
+        .map_err(|e| format!("Failed to serialize structuring samples: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write structuring samples: {}", e))
+}
+
+fn estimate_token_count(text: &str) -> u32 {
+    (text.chars().count() as f32 / CHARS_PER_TOKEN).ceil() as u32
+}
+
+/// Structuring time for a transcript of `transcript_tokens`. Measured structuring runs come first;
+/// without them the Qwen benchmark rates are used (the structured output is about as long as the
+/// transcript), otherwise a conservative default. Model load is added when the worker is cold.
+fn estimate_structuring(
+    transcript_tokens: u32,
+    samples: &[StructuringSample],
+    benchmark_rates: &[f32],
+    worker_warm: bool,
+) -> StructuringEstimate {
+    let measured: Vec<f32> = samples.iter()
+        .filter(|s| s.transcript_tokens > 0 && s.processing_ms > 0)
+        .map(|s| s.transcript_tokens as f32 * 1000.0 / s.processing_ms as f32)
+        .collect();
+    let benchmarks: Vec<f32> = benchmark_rates.iter().copied().filter(|r| *r > 0.0).collect();
+    let (rates, based_on_samples) = if !measured.is_empty() {
+        (measured, samples.len())
+    } else if !benchmarks.is_empty() {
+        let count = benchmarks.len();
+        (benchmarks, count)
+    } else {
+        (vec![DEFAULT_STRUCTURING_TOKENS_PER_SEC], 0)
+    };
+    let rate = rates.iter().sum::<f32>() / rates.len() as f32;
+
+    let mut estimated_seconds = transcript_tokens as f32 / rate;
+    if !worker_warm {
+        let loads: Vec<u64> = samples.iter().filter_map(|s| s.load_ms).collect();
+        estimated_seconds += if loads.is_empty() {
+            DEFAULT_MODEL_LOAD_SECS
+        } else {
+            loads.iter().sum::<u64>() as f32 / loads.len() as f32 / 1000.0
+        };
+    }
+
+    StructuringEstimate {
+        estimated_seconds,
+        based_on_samples,
+        transcript_tokens,
+        includes_model_load: !worker_warm,
+    }
+}
+
+// Worker slots, one per model type: grammar (Llama) and structuring (Qwen) stay loaded side by
+// side when memory allows, so alternating tasks doesn't cold-start a model each time
 static LLAMA_WORKER: Lazy<Mutex<LlamaWorker>> = Lazy::new(|| {
     Mutex::new(LlamaWorker::new())
 });
+static QWEN_WORKER: Lazy<Mutex<LlamaWorker>> = Lazy::new(|| {
+    Mutex::new(LlamaWorker::new())
+});
+
+fn worker_slot(use_qwen: bool) -> &'static Mutex<LlamaWorker> {
+    if use_qwen { &QWEN_WORKER } else { &LLAMA_WORKER }
+}
+
+/// Whether a model of `model_bytes` fits into the memory left beside the already loaded worker
+fn second_worker_fits(model_bytes: u64, available_ram: u64, gpu: Option<&GpuMemoryInfo>) -> bool {
+    warmup_memory_check(model_bytes, available_ram, gpu).is_ok()
+}
+
+/// Make room before starting the worker of `use_qwen`: the other model's worker stays loaded if
+/// both fit, otherwise it is stopped (the old swap behaviour). Returns a notice for the command
+/// result when a worker was stopped. Must be called without holding a worker lock.
+fn make_room_for_worker(use_qwen: bool) -> Option<String> {
+    if worker_slot(use_qwen).lock().ok()?.is_running() {
+        return None;
+    }
+    let mut other = worker_slot(!use_qwen).lock().ok()?;
+    if !other.is_running() {
+        return None;
+    }
+
+    let model_bytes = fs::metadata(llm_model_path(use_qwen)).map(|m| m.len()).unwrap_or(0);
+    let (_, available_ram) = get_system_memory_info();
+    if second_worker_fits(model_bytes, available_ram, get_gpu_memory_info().as_ref()) {
+        println!("[RUST] Keeping the {} worker loaded beside {}", other.model_type, if use_qwen { "qwen" } else { "llama" });
+        return None;
+    }
+
+    let stopped = other.model_type.clone();
+    println!("[RUST] Not enough memory for two workers, stopping {}", stopped);
+    other.stop();
+    Some(format!("Zu wenig Speicher für beide Sprachmodelle: {} wurde entladen", stopped))
+}
+
+fn get_prompt_debug_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("prompt_debug.json"))
+}
+
+fn load_prompt_debug_settings() -> PromptDebugSettings {
+    get_prompt_debug_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn get_generation_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("generation.json"))
+}
+
+fn load_generation_settings() -> GenerationSettings {
+    get_generation_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_generation_settings(settings: &GenerationSettings) -> Result<(), String> {
+    let path = get_generation_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize generation settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write generation settings: {}", e))
+}
+
+/// Worker request for a text; asks the worker to echo its prompt when debugging is on
+fn text_request(text: &str, debug: bool) -> Value {
+    let mut request = serde_json::json!({ "text": text });
+    if debug {
+        request["debug"] = Value::Bool(true);
+    }
+    request
+}
+
+/// Take the echoed prompt out of a worker response and remember it for get_last_prompt
+fn record_prompt_debug(model_type: &str, response: &Value) -> Option<Value> {
+    let prompt = response.get("prompt_debug").filter(|p| !p.is_null())?.clone();
+    if let Ok(mut last) = LAST_PROMPT.lock() {
+        *last = Some(LastPrompt {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            model_type: model_type.to_string(),
+            prompt: prompt.clone(),
+        });
+    }
+    Some(prompt)
+}
+
+/// Models whose worker process is running ("llama", "qwen")
+pub(crate) fn llm_worker_state() -> Vec<String> {
+    [false, true].into_iter()
+        .filter_map(|use_qwen| {
+            let mut worker = worker_slot(use_qwen).lock().ok()?;
+            worker.is_running().then(|| worker.model_type.clone())
+        })
+        .collect()
+}
+
+/// Start the worker in the background (app start warm-up); holds the worker lock while loading,
+/// so user requests for another model must set `cancel` to take over
+pub(crate) fn warm_up_worker(use_qwen: bool, cancel: &AtomicBool, on_progress: &dyn Fn(f32)) -> Result<(), String> {
+    make_room_for_worker(use_qwen);
+    let mut worker = worker_slot(use_qwen).lock()
+        .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
+    worker.start_with(use_qwen, Some(cancel), on_progress)
+}
+
+/// Model file of the grammar (Llama) or structuring (Qwen) worker
+pub(crate) fn llm_model_path(use_qwen: bool) -> PathBuf {
+    PathBuf::from(if use_qwen { QWEN_MODEL_PATH } else { LLAMA_MODEL_PATH })
+}
+
+/// Whether at least one of the LLM model files is on disk
+pub(crate) fn llm_model_available() -> bool {
+    PathBuf::from(QWEN_MODEL_PATH).exists()
+        || PathBuf::from(LLAMA_MODEL_PATH).exists()
+}
 
 /// Check if Qwen model exists
 #[command]
 pub async fn get_llama_model_info() -> Result<Value, String> {
-    let qwen_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\models\qwen2.5-7b-instruct-q4_k_m.gguf");
-    let llama_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\models\llama-3.1-8b-instruct-q4_k_m.gguf");
+    let qwen_path = PathBuf::from(QWEN_MODEL_PATH);
+    let llama_path = PathBuf::from(LLAMA_MODEL_PATH);
 
     let qwen_exists = qwen_path.exists();
     let llama_exists = llama_path.exists();
@@ -235,11 +1029,84 @@ pub async fn get_llama_model_info() -> Result<Value, String> {
     }))
 }
 
+/// Details of the model loaded in the running worker
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoadedModelDetails {
+    pub running: bool,
+    pub model_type: String,  // "qwen", "llama" or "none"
+    pub model_loaded: bool,
+    pub model_name: Option<String>,
+    pub model_path: Option<String>,
+    pub context_length: Option<u32>,
+    pub device: Option<String>,  // "cpu" or "gpu"
+}
+
+impl LoadedModelDetails {
+    fn not_running() -> Self {
+        LoadedModelDetails {
+            running: false,
+            model_type: "none".to_string(),
+            model_loaded: false,
+            model_name: None,
+            model_path: None,
+            context_length: None,
+            device: None,
+        }
+    }
+}
+
+/// Build LoadedModelDetails from a worker "info" response
+fn parse_loaded_model_details(model_type: &str, response: &Value) -> Result<LoadedModelDetails, String> {
+    if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
+        return Err(format!("Worker did not return model details: {}", error));
+    }
+
+    let get_str = |key: &str| response.get(key)
+        .and_then(|v| v.as_str())
+        .filter(|v| !v.is_empty())
+        .map(String::from);
+
+    Ok(LoadedModelDetails {
+        running: true,
+        model_type: model_type.to_string(),
+        model_loaded: response.get("model_loaded")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+        model_name: get_str("model_name"),
+        model_path: get_str("model_path"),
+        context_length: response.get("n_ctx")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32),
+        device: get_str("device").map(|d| d.to_lowercase()),
+    })
+}
+
+/// Get the model details of the currently running worker (does not start a worker)
+#[command]
+pub async fn get_loaded_model_details(model_type: Option<String>) -> Result<LoadedModelDetails, String> {
+    // Without a model type: the structuring worker if it runs, else the grammar worker
+    let use_qwen = match model_type.as_deref() {
+        Some(model_type) => model_type == "qwen",
+        None => QWEN_WORKER.lock().map(|mut w| w.is_running()).unwrap_or(false),
+    };
+    let mut worker = worker_slot(use_qwen).lock()
+        .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
+
+    if !worker.is_running() {
+        return Ok(LoadedModelDetails::not_running());
+    }
+
+    let model_type = worker.model_type.clone();
+    worker.capabilities.require_command("info")?;
+    let response = worker.request_running(&serde_json::json!({"command": "info"}))?;
+    parse_loaded_model_details(&model_type, &response)
+}
+
 /// Check if model is ready
 #[command]
 pub async fn is_llama_model_ready() -> Result<bool, String> {
-    let qwen_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\models\qwen2.5-7b-instruct-q4_k_m.gguf");
-    let llama_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\models\llama-3.1-8b-instruct-q4_k_m.gguf");
+    let qwen_path = PathBuf::from(QWEN_MODEL_PATH);
+    let llama_path = PathBuf::from(LLAMA_MODEL_PATH);
     Ok(qwen_path.exists() || llama_path.exists())
 }
 
@@ -255,9 +1122,11 @@ pub async fn load_llama_model() -> Result<Value, String> {
     println!("[RUST] Initializing Qwen worker...");
 
     // Use Qwen by default
-    let qwen_exists = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\models\qwen2.5-7b-instruct-q4_k_m.gguf").exists();
+    let qwen_exists = PathBuf::from(QWEN_MODEL_PATH).exists();
 
-    let mut worker = LLAMA_WORKER.lock()
+    yield_llm_warmup(if qwen_exists { "qwen" } else { "llama" });
+    let worker_notice = make_room_for_worker(qwen_exists);
+    let mut worker = worker_slot(qwen_exists).lock()
         .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
 
     worker.start(qwen_exists)?;
@@ -272,36 +1141,226 @@ pub async fn load_llama_model() -> Result<Value, String> {
         "success": true,
         "message": if server_ready { "Worker ready with model loaded" } else { "Worker started, model loading..." },
         "model_loaded": server_ready,
-        "model_type": if qwen_exists { "qwen" } else { "llama" }
+        "model_type": if qwen_exists { "qwen" } else { "llama" },
+        "worker_notice": worker_notice,
     }))
 }
 
+/// Benchmark the active worker with a fixed German prompt, averaged over a few runs.
+/// The worker lock is taken per run, so queued user requests can run in between.
+#[command]
+pub async fn benchmark_llm() -> Result<BenchmarkResult, String> {
+    let mut model_type = String::new();
+    let mut runs = Vec::with_capacity(BENCHMARK_RUNS);
+
+    // Benchmark whatever is loaded (structuring worker first); otherwise start the default model like load_llama_model
+    let running = llm_worker_state();
+    let use_qwen = if running.is_empty() {
+        PathBuf::from(QWEN_MODEL_PATH).exists()
+    } else {
+        running.iter().any(|m| m == "qwen")
+    };
+    if running.is_empty() {
+        make_room_for_worker(use_qwen);
+    }
+
+    for run in 1..=BENCHMARK_RUNS {
+        let mut guard = worker_slot(use_qwen).lock()
+            .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
+        let worker = &mut *guard;
+
+        if !worker.is_running() {
+            worker.start(use_qwen)?;
+        }
+        if model_type.is_empty() {
+            model_type = worker.model_type.clone();
+        } else if worker.model_type != model_type {
+            return Err("Worker model changed during benchmark".to_string());
+        }
+
+        let stdin = worker.stdin.as_mut().ok_or("Worker stdin not available")?;
+        let stdout = worker.stdout.as_mut().ok_or("Worker stdout not available")?;
+        let result = run_benchmark_request(stdin, stdout)?;
+        println!("[RUST] Benchmark run {}/{}: {} tokens, {:.1} tok/s, {} ms",
+            run, BENCHMARK_RUNS, result.tokens_generated, result.tokens_per_sec, result.latency_ms);
+        runs.push(result);
+    }
+
+    let result = aggregate_benchmark_runs(&model_type, runs);
+    append_performance_history(&result)?;
+
+    Ok(result)
+}
+
+/// Get all stored benchmark results (oldest first)
+#[command]
+pub async fn get_performance_history() -> Result<Vec<BenchmarkResult>, String> {
+    load_performance_history()
+}
+
 /// Correct German grammar using Llama worker (legacy - kept for compatibility)
 #[command]
 pub async fn correct_german_grammar(
     text: String,
     preserve_style: Option<bool>,
+    quick_fix: Option<bool>,
+    model: Option<String>,
 ) -> Result<GrammarCorrectionResponse, String> {
     println!("[RUST] Correcting German grammar (length: {} chars)", text.len());
 
+    let use_qwen = select_grammar_model(
+        model.as_deref(),
+        PathBuf::from(LLAMA_MODEL_PATH).exists(),
+        PathBuf::from(QWEN_MODEL_PATH).exists(),
+    ).map_err(|e| e.to_string())?;
+    let model_type = if use_qwen { "qwen" } else { "llama" };
+
+    // Optional deterministic pre-pass so the model gets cleaner input
+    let (text, quick_fix_rules) = if quick_fix.unwrap_or(false) {
+        let fixed = crate::commands::quick_fix_commands::quick_fix(&text);
+        (fixed.text, fixed.applied_rules)
+    } else {
+        (text, Vec::new())
+    };
+
+    // The grammar model only handles German; warn instead of silently "correcting" other languages
+    let language = crate::commands::language_commands::detect_language(&text);
+    let language_warning = if language.is_reliable && language.language != "de" {
+        println!("[RUST] WARNING: Input looks like '{}' (confidence {:.2}), not German", language.language, language.confidence);
+        Some(format!("Warnung: Der Text scheint nicht deutsch zu sein (erkannt: {}).", language.language))
+    } else {
+        None
+    };
+
     let start = std::time::Instant::now();
 
-    let mut worker = LLAMA_WORKER.lock()
+    yield_llm_warmup(model_type);
+    let worker_notice = make_room_for_worker(use_qwen);
+    let mut worker = worker_slot(use_qwen).lock()
         .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
 
-    // Use Llama for simple grammar correction
-    let request = serde_json::json!({
-        "text": text
-    });
+    worker.start(use_qwen)?;
+    // The Qwen worker structures by default; grammar correction is a separate task there
+    if use_qwen {
+        worker.capabilities.require_feature("grammar")?;
+    }
+    let debug = load_prompt_debug_settings().enabled && worker.capabilities.has_feature("debug");
+    let generation = load_generation_settings().grammar;
+
+    // Long dictations exceed the model context; correct them paragraph-wise in chunks.
+    // Page/section break markers are not sent to the model and are put back afterwards.
+    let pieces = grammar_pieces(&text);
+    let chunks: Vec<&str> = pieces.iter()
+        .filter_map(|piece| match piece {
+            GrammarPiece::Text(chunk) => Some(chunk.as_str()),
+            GrammarPiece::Marker(_) => None,
+        })
+        .collect();
+    if chunks.len() > 1 {
+        println!("[RUST] Correcting in {} chunks", chunks.len());
+    }
+    let mut parts = Vec::with_capacity(chunks.len());
+    for chunk in &chunks {
+        let chunk_start = std::time::Instant::now();
+        let mut request = text_request(chunk, debug);
+        if use_qwen {
+            request["task"] = Value::from("grammar");
+        }
+        worker.attach_generation(&mut request, &generation);
+        let response = worker.send_request(&request, use_qwen)?;
+        parts.push(parse_grammar_response(&response, model_type, chunk_start.elapsed().as_millis() as u64, debug)?);
+    }
 
-    let response = worker.send_request(&request, false)?;
+    let corrected: Vec<String> = parts.iter().map(|p| p.corrected_text.clone()).collect();
+    let mut result = merge_grammar_responses(parts);
+    if chunks.len() > 1 {
+        result.processing_time_ms = start.elapsed().as_millis() as u64;
+    }
+    if chunks.len() < pieces.len() {
+        result.corrected_text = join_grammar_pieces(&pieces, &corrected);
+    }
+    if !quick_fix_rules.is_empty() {
+        result.notes.insert(0, format!("Vorkorrektur: {}", quick_fix_rules.join(", ")));
+    }
+    if let Some(warning) = language_warning {
+        result.notes.insert(0, warning);
+    }
+    if let Some(notice) = worker_notice {
+        result.notes.push(notice);
+    }
+    Ok(result)
+}
 
-    let elapsed = start.elapsed().as_millis() as u64;
+/// Longest text sent to the grammar worker in one request
+const MAX_GRAMMAR_CHUNK_CHARS: usize = 2000;
+
+/// The text as one request, or paragraph/sentence chunks if it is too long
+fn grammar_chunks(text: &str) -> Vec<String> {
+    if text.chars().count() <= MAX_GRAMMAR_CHUNK_CHARS {
+        return vec![text.to_string()];
+    }
+    let paragraphs = split_paragraphs(text, &SegmentationOptions::default());
+    chunk_paragraphs(&paragraphs, MAX_GRAMMAR_CHUNK_CHARS)
+}
 
+/// Part of a text to correct: text for the model, or a break marker line kept out of it
+#[derive(Debug, Clone, PartialEq)]
+enum GrammarPiece {
+    Text(String),
+    Marker(String),
+}
+
+/// Split the text at break marker lines; long text between them is chunked
+fn grammar_pieces(text: &str) -> Vec<GrammarPiece> {
+    if !text.lines().any(is_break_marker) {
+        return grammar_chunks(text).into_iter().map(GrammarPiece::Text).collect();
+    }
+    let mut pieces = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    let flush = |lines: &mut Vec<&str>, pieces: &mut Vec<GrammarPiece>| {
+        let block = lines.join("\n");
+        if !block.trim().is_empty() {
+            pieces.extend(grammar_chunks(block.trim()).into_iter().map(GrammarPiece::Text));
+        }
+        lines.clear();
+    };
+    for line in text.lines() {
+        if is_break_marker(line) {
+            flush(&mut lines, &mut pieces);
+            pieces.push(GrammarPiece::Marker(line.trim().to_string()));
+        } else {
+            lines.push(line);
+        }
+    }
+    flush(&mut lines, &mut pieces);
+    pieces
+}
+
+/// The corrected text pieces (in order) with the markers back between them
+fn join_grammar_pieces(pieces: &[GrammarPiece], corrected: &[String]) -> String {
+    let mut corrected = corrected.iter();
+    pieces.iter()
+        .filter_map(|piece| match piece {
+            GrammarPiece::Text(_) => corrected.next().cloned(),
+            GrammarPiece::Marker(marker) => Some(marker.clone()),
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Build the correction result from a Llama worker response
+fn parse_grammar_response(response: &Value, model_type: &str, elapsed: u64, debug: bool) -> Result<GrammarCorrectionResponse, String> {
     if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
         return Err(error.to_string());
     }
 
+    let strings = |key: &str| -> Vec<String> {
+        response.get(key)
+            .and_then(|v| v.as_array())
+            .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+            .unwrap_or_default()
+    };
+
     let corrected_text = response.get("clean_text")
         .or_else(|| response.get("corrected_text"))
         .and_then(|t| t.as_str())
@@ -317,39 +1376,65 @@ pub async fn correct_german_grammar(
         .unwrap_or("unknown")
         .to_string();
 
-    let violations: Vec<String> = response.get("violations")
-        .and_then(|v| v.as_array())
-        .map(|arr| arr.iter().filter_map(|v| v.as_str().map(String::from)).collect())
-        .unwrap_or_default();
-
-    let notes: Vec<String> = response.get("notes")
-        .and_then(|n| n.as_array())
-        .map(|arr| arr.iter().filter_map(|n| n.as_str().map(String::from)).collect())
-        .unwrap_or_default();
-
-    let removed_tokens: Vec<String> = response.get("removed_tokens")
-        .and_then(|r| r.as_array())
-        .map(|arr| arr.iter().filter_map(|t| t.as_str().map(String::from)).collect())
-        .unwrap_or_default();
-
     let tokens_per_sec = response.get("tokens_per_sec")
         .and_then(|t| t.as_f64())
         .map(|t| t as f32);
 
+    let prompt_debug = if debug { record_prompt_debug(model_type, response) } else { None };
+    let generation = response.get("generation").filter(|g| !g.is_null()).cloned();
+
     Ok(GrammarCorrectionResponse {
         corrected_text,
         changes_made: vec![],
         confidence: 0.0,
         processing_time_ms,
         guardrail_status,
-        violations,
-        notes,
+        violations: strings("violations"),
+        notes: strings("notes"),
         attempts: 1,
-        removed_tokens,
+        removed_tokens: strings("removed_tokens"),
         tokens_per_sec,
+        prompt_debug,
+        generation,
+        model_used: model_type.to_string(),
     })
 }
 
+/// Combine the results of all chunks; the worst guardrail status wins
+fn merge_grammar_responses(mut parts: Vec<GrammarCorrectionResponse>) -> GrammarCorrectionResponse {
+    if parts.len() == 1 {
+        return parts.remove(0);
+    }
+
+    let severity = |status: &str| match status {
+        "passed" => 0,
+        "unknown" => 1,
+        "violations" => 2,
+        _ => 3,
+    };
+    let guardrail_status = parts.iter()
+        .map(|p| p.guardrail_status.clone())
+        .max_by_key(|status| severity(status))
+        .unwrap_or_else(|| "unknown".to_string());
+    let rates: Vec<f32> = parts.iter().filter_map(|p| p.tokens_per_sec).collect();
+
+    GrammarCorrectionResponse {
+        corrected_text: parts.iter().map(|p| p.corrected_text.as_str()).collect::<Vec<_>>().join("\n\n"),
+        changes_made: parts.iter().flat_map(|p| p.changes_made.clone()).collect(),
+        confidence: 0.0,
+        processing_time_ms: parts.iter().map(|p| p.processing_time_ms).sum(),
+        guardrail_status,
+        violations: parts.iter().flat_map(|p| p.violations.clone()).collect(),
+        notes: parts.iter().flat_map(|p| p.notes.clone()).collect(),
+        attempts: parts.len() as u32,
+        removed_tokens: parts.iter().flat_map(|p| p.removed_tokens.clone()).collect(),
+        tokens_per_sec: (!rates.is_empty()).then(|| rates.iter().sum::<f32>() / rates.len() as f32),
+        prompt_debug: parts.last().and_then(|p| p.prompt_debug.clone()),
+        generation: parts.first().and_then(|p| p.generation.clone()),
+        model_used: parts.first().map(|p| p.model_used.clone()).unwrap_or_default(),
+    }
+}
+
 /// Structure transcript into Gutachten sections using Qwen
 #[command]
 pub async fn structure_gutachten_transcript(
@@ -359,15 +1444,34 @@ pub async fn structure_gutachten_transcript(
 
     let start = std::time::Instant::now();
 
-    let mut worker = LLAMA_WORKER.lock()
+    yield_llm_warmup("qwen");
+    let worker_notice = make_room_for_worker(true);
+    let mut worker = worker_slot(true).lock()
         .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
 
     // Use Qwen for structuring
-    let request = serde_json::json!({
-        "text": transcript
-    });
+    let worker_warm = worker.is_running() && worker.model_type == "qwen";
+    let load_start = std::time::Instant::now();
+    worker.start(true)?;
+    let load_ms = (!worker_warm).then(|| load_start.elapsed().as_millis() as u64);
+    let debug = load_prompt_debug_settings().enabled && worker.capabilities.has_feature("debug");
+    let mut request = text_request(&transcript, debug);
+    worker.attach_generation(&mut request, &load_generation_settings().structuring);
+    let mut applied_section_prompts = Vec::new();
+    let section_prompts = active_section_prompts();
+    if !section_prompts.is_empty() {
+        match worker.capabilities.require_feature("section_prompts") {
+            Ok(()) => {
+                request["section_prompts"] = serde_json::json!(section_prompts);
+                applied_section_prompts = section_prompts.into_keys().collect();
+            }
+            Err(e) => println!("[RUST] qwen worker: {}; custom section prompts not applied", e),
+        }
+    }
 
+    let request_start = std::time::Instant::now();
     let response = worker.send_request(&request, true)?;
+    let processing_ms = request_start.elapsed().as_millis() as u64;
 
     let elapsed = start.elapsed().as_millis() as u64;
 
@@ -375,6 +1479,40 @@ pub async fn structure_gutachten_transcript(
         return Err(error.to_string());
     }
 
+    let sample = StructuringSample {
+        timestamp: chrono::Local::now().to_rfc3339(),
+        transcript_tokens: estimate_token_count(&transcript),
+        processing_ms,
+        load_ms,
+    };
+    if let Err(e) = append_structuring_sample(sample) {
+        println!("[RUST] {}", e);
+    }
+
+    let mut content = parse_structured_content(&response, elapsed, debug);
+    content.worker_notice = worker_notice;
+    content.section_prompts = applied_section_prompts;
+    Ok(content)
+}
+
+/// Expected structuring time for a transcript, so the UI can show progress while it runs
+#[command]
+pub async fn estimate_structuring_time(transcript: String) -> Result<StructuringEstimate, String> {
+    let benchmark_rates: Vec<f32> = load_performance_history().unwrap_or_default()
+        .iter()
+        .filter(|result| result.model_type == "qwen")
+        .map(|result| result.tokens_per_sec)
+        .collect();
+    // A held lock means a request is running, so the model is loaded
+    let worker_warm = match worker_slot(true).try_lock() {
+        Ok(mut worker) => worker.is_running() && worker.model_type == "qwen",
+        Err(_) => true,
+    };
+    Ok(estimate_structuring(estimate_token_count(&transcript), &load_structuring_samples(), &benchmark_rates, worker_warm))
+}
+
+/// Build the structured result from a Qwen worker response
+fn parse_structured_content(response: &Value, elapsed: u64, debug: bool) -> StructuredContent {
     let slots = response.get("slots")
         .cloned()
         .unwrap_or(serde_json::json!({}));
@@ -394,25 +1532,386 @@ pub async fn structure_gutachten_transcript(
         .and_then(|t| t.as_f64())
         .map(|t| t as f32);
 
-    Ok(StructuredContent {
+    let prompt_debug = if debug { record_prompt_debug("qwen", response) } else { None };
+    let generation = response.get("generation").filter(|g| !g.is_null()).cloned();
+
+    StructuredContent {
         slots,
         unclear_spans,
         missing_slots,
         processing_time_ms: elapsed,
         tokens_per_sec,
-    })
+        prompt_debug,
+        generation,
+        worker_notice: None,
+        section_prompts: Vec::new(),
+    }
+}
+
+/// The prompt composed for the last grammar/structuring request (None unless prompt debugging is on)
+#[command]
+pub async fn get_last_prompt() -> Result<Option<LastPrompt>, String> {
+    let last = LAST_PROMPT.lock()
+        .map_err(|e| format!("Failed to acquire prompt lock: {}", e))?;
+    Ok(last.clone())
 }
 
-/// Shutdown the worker
+/// Whether the worker echoes its prompts
 #[command]
-pub async fn shutdown_llama_worker() -> Result<Value, String> {
-    let mut worker = LLAMA_WORKER.lock()
+pub async fn get_prompt_debug_enabled() -> Result<bool, String> {
+    Ok(load_prompt_debug_settings().enabled)
+}
+
+/// Enable or disable prompt debugging; disabling also forgets the last prompt
+#[command]
+pub async fn set_prompt_debug_enabled(enabled: bool) -> Result<bool, String> {
+    let path = get_prompt_debug_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(&PromptDebugSettings { enabled })
+        .map_err(|e| format!("Failed to serialize prompt debug settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write prompt debug settings: {}", e))?;
+
+    if !enabled {
+        if let Ok(mut last) = LAST_PROMPT.lock() {
+            *last = None;
+        }
+    }
+    println!("[RUST] Prompt debugging {}", if enabled { "enabled" } else { "disabled" });
+    Ok(enabled)
+}
+
+/// Capabilities of the running worker of a model type (default: the structuring worker if it
+/// runs, else the grammar worker); None if that worker is not running
+#[command]
+pub async fn get_worker_capabilities(model_type: Option<String>) -> Result<Option<WorkerCapabilities>, String> {
+    let use_qwen = match model_type.as_deref() {
+        Some(model_type) => model_type == "qwen",
+        None => QWEN_WORKER.lock().map(|mut w| w.is_running()).unwrap_or(false),
+    };
+    let mut worker = worker_slot(use_qwen).lock()
         .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
+    if !worker.is_running() {
+        return Ok(None);
+    }
+    Ok(Some(worker.capabilities.clone()))
+}
 
-    worker.stop();
+/// Last lines of the worker log (default 100), e.g. to see why the Qwen server failed to load
+/// (VRAM exhausted, damaged GGUF)
+#[command]
+pub async fn get_worker_logs(lines: Option<usize>) -> Result<Vec<String>, String> {
+    let logs = WORKER_LOGS.lock()
+        .map_err(|e| format!("Failed to acquire worker log lock: {}", e))?;
+    Ok(logs.tail(lines.unwrap_or(DEFAULT_WORKER_LOG_LINES)))
+}
+
+/// Get the generation parameters per task
+#[command]
+pub async fn get_generation_settings() -> Result<GenerationSettings, String> {
+    Ok(load_generation_settings())
+}
+
+/// Save the generation parameters per task (rejected if a value is out of range)
+#[command]
+pub async fn save_generation_settings(settings: GenerationSettings) -> Result<GenerationSettings, String> {
+    settings.validate()?;
+    write_generation_settings(&settings)?;
+    Ok(settings)
+}
+
+/// Restore the factory generation parameters
+#[command]
+pub async fn reset_generation_defaults() -> Result<GenerationSettings, String> {
+    let settings = GenerationSettings::default();
+    write_generation_settings(&settings)?;
+    println!("[RUST] Generation parameters reset to defaults");
+    Ok(settings)
+}
+
+/// Check that a file is a readable GGUF model without loading it
+#[command]
+pub async fn validate_gguf(path: String) -> Result<GgufInfo, String> {
+    let info = validate_gguf_file(std::path::Path::new(&path)).map_err(|e| e.to_string())?;
+    println!("[RUST] {} is GGUF v{} ({})", path, info.version, info.architecture.as_deref().unwrap_or("unknown"));
+    Ok(info)
+}
+
+/// Shutdown the worker of one model type ("llama" or "qwen"), or all workers
+#[command]
+pub async fn shutdown_llama_worker(model_type: Option<String>) -> Result<Value, String> {
+    let slots: Vec<bool> = match model_type.as_deref() {
+        None => vec![false, true],
+        Some("llama") => vec![false],
+        Some("qwen") => vec![true],
+        Some(other) => return Err(format!("Unknown model type: {}", other)),
+    };
+    for use_qwen in slots {
+        let mut worker = worker_slot(use_qwen).lock()
+            .map_err(|e| format!("Failed to acquire worker lock: {}", e))?;
+        worker.stop();
+    }
 
     Ok(serde_json::json!({
         "success": true,
-        "message": "Worker stopped"
+        "message": match model_type {
+            Some(model_type) => format!("{} worker stopped", model_type),
+            None => "Workers stopped".to_string(),
+        }
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_loaded_model_details_from_stub_worker() {
+        let mut stdin: Vec<u8> = Vec::new();
+        let mut stdout = Cursor::new(
+            b"{\"model_loaded\": true, \"model_name\": \"qwen2.5-7b-instruct-q4_k_m.gguf\", \"n_ctx\": 4096, \"device\": \"CPU\"}\n".to_vec()
+        );
+
+        let response = exchange_request(&mut stdin, &mut stdout, &serde_json::json!({"command": "info"})).unwrap();
+        let details = parse_loaded_model_details("qwen", &response).unwrap();
+
+        assert_eq!(String::from_utf8(stdin).unwrap(), "{\"command\":\"info\"}\n");
+        assert!(details.running);
+        assert!(details.model_loaded);
+        assert_eq!(details.model_type, "qwen");
+        assert_eq!(details.model_name.as_deref(), Some("qwen2.5-7b-instruct-q4_k_m.gguf"));
+        assert_eq!(details.context_length, Some(4096));
+        assert_eq!(details.device.as_deref(), Some("cpu"));
+        assert!(details.model_path.is_none());
+    }
+
+    #[test]
+    fn test_benchmark_runs_are_averaged_from_stub_worker() {
+        let responses = [
+            r#"{"clean_text": "x", "metrics": {"completion_tokens": 100, "tokens_per_sec": 10.0, "total_time_ms": 10000}}"#,
+            r#"{"clean_text": "x", "metrics": {"completion_tokens": 120, "tokens_per_sec": 12.0, "total_time_ms": 10000}}"#,
+            r#"{"slots": {}, "metrics": {"tokens_predicted": 140, "processing_time_s": 10.0}}"#,
+        ];
+        let mut stdin: Vec<u8> = Vec::new();
+        let mut stdout = Cursor::new(format!("{}\n", responses.join("\n")).into_bytes());
+
+        let runs: Vec<BenchmarkRun> = (0..3)
+            .map(|_| run_benchmark_request(&mut stdin, &mut stdout).unwrap())
+            .collect();
+        assert_eq!(runs[2], BenchmarkRun { tokens_generated: 140, tokens_per_sec: 14.0, latency_ms: 10000 });
+
+        let result = aggregate_benchmark_runs("llama", runs);
+        assert_eq!(result.runs.len(), 3);
+        assert_eq!(result.tokens_generated, 120.0);
+        assert!((result.tokens_per_sec - 12.0).abs() < 0.001);
+        assert_eq!(result.latency_ms, 10000);
+        assert_eq!(String::from_utf8(stdin).unwrap().lines().count(), 3);
+    }
+
+    #[test]
+    fn test_debug_request_surfaces_prompt() {
+        let mut stdin: Vec<u8> = Vec::new();
+        let mut stdout = Cursor::new(
+            b"{\"slots\": {\"anamnese\": \"x\"}, \"missing_slots\": [], \"prompt_debug\": {\"prompt\": \"<|im_start|>system ...\"}}\n".to_vec()
+        );
+
+        let response = exchange_request(&mut stdin, &mut stdout, &text_request("Diktat", true)).unwrap();
+        assert_eq!(String::from_utf8(stdin).unwrap(), "{\"debug\":true,\"text\":\"Diktat\"}\n");
+
+        // Without debug mode the echoed prompt is dropped
+        assert!(parse_structured_content(&response, 5, false).prompt_debug.is_none());
+
+        let content = parse_structured_content(&response, 5, true);
+        assert_eq!(content.prompt_debug.unwrap()["prompt"], "<|im_start|>system ...");
+        let last = LAST_PROMPT.lock().unwrap().clone().unwrap();
+        assert_eq!(last.model_type, "qwen");
+        assert!(text_request("Diktat", false).get("debug").is_none());
+    }
+
+    #[test]
+    fn test_long_text_is_corrected_in_merged_chunks() {
+        assert_eq!(grammar_chunks("Kurzer Text.\r\n\r\n\r\nZweiter Absatz."), vec!["Kurzer Text.\r\n\r\n\r\nZweiter Absatz."]);
+
+        let paragraph = "Der Patient berichtet über anhaltende Rückenschmerzen seit dem Jahr 2019. ".repeat(12);
+        let text = format!("{}\r\n\r\n\r\n{}\n\n{}", paragraph.trim(), paragraph.trim(), paragraph.trim());
+        let chunks = grammar_chunks(&text);
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|c| c.chars().count() <= MAX_GRAMMAR_CHUNK_CHARS && !c.contains('\r')));
+
+        let part = |text: &str, status: &str, rate: f64| parse_grammar_response(&serde_json::json!({
+            "clean_text": text, "guardrail_status": status, "notes": [format!("{} ok", text)],
+            "tokens_per_sec": rate, "processing_time_ms": 100,
+        }), "llama", 0, false).unwrap();
+        let merged = merge_grammar_responses(vec![part("Erster.", "passed", 10.0), part("Zweiter.", "violations", 20.0)]);
+        assert_eq!(merged.corrected_text, "Erster.\n\nZweiter.");
+        assert_eq!(merged.guardrail_status, "violations");
+        assert_eq!(merged.notes, vec!["Erster. ok", "Zweiter. ok"]);
+        assert_eq!((merged.attempts, merged.processing_time_ms, merged.tokens_per_sec), (2, 200, Some(15.0)));
+        assert!(parse_grammar_response(&serde_json::json!({"error": "Model not loaded"}), "llama", 0, false).is_err());
+    }
+
+    #[test]
+    fn test_break_markers_are_kept_out_of_grammar_requests() {
+        let text = "Befund unaufällig.\n[SEITENUMBRUCH]\nAnlage eins.\n\n[ABSCHNITTSUMBRUCH]\n\nAnlage zwei.";
+        let pieces = grammar_pieces(text);
+        assert_eq!(pieces, vec![
+            GrammarPiece::Text("Befund unaufällig.".to_string()),
+            GrammarPiece::Marker("[SEITENUMBRUCH]".to_string()),
+            GrammarPiece::Text("Anlage eins.".to_string()),
+            GrammarPiece::Marker("[ABSCHNITTSUMBRUCH]".to_string()),
+            GrammarPiece::Text("Anlage zwei.".to_string()),
+        ]);
+        let corrected = ["Befund unauffällig.", "Anlage 1.", "Anlage 2."].map(String::from);
+        assert_eq!(join_grammar_pieces(&pieces, &corrected),
+            "Befund unauffällig.\n\n[SEITENUMBRUCH]\n\nAnlage 1.\n\n[ABSCHNITTSUMBRUCH]\n\nAnlage 2.");
+        assert_eq!(grammar_pieces("Ohne Umbruch.\r\n"), vec![GrammarPiece::Text("Ohne Umbruch.\r\n".to_string())]);
+    }
+
+    #[test]
+    fn test_structuring_estimate_scales_with_transcript_length() {
+        let short = estimate_structuring(500, &[], &[10.0], true);
+        let long = estimate_structuring(1000, &[], &[10.0], true);
+        assert!((short.estimated_seconds - 50.0).abs() < 0.001);
+        assert!((long.estimated_seconds - 2.0 * short.estimated_seconds).abs() < 0.001);
+        assert_eq!(short.based_on_samples, 1);
+
+        let samples = vec![StructuringSample {
+            timestamp: "2025-01-01T10:00:00+01:00".to_string(),
+            transcript_tokens: 400,
+            processing_ms: 20_000,
+            load_ms: Some(12_000),
+        }];
+        let measured = estimate_structuring(1000, &samples, &[10.0], false);
+        assert!((measured.estimated_seconds - 62.0).abs() < 0.001);
+        assert!(measured.includes_model_load);
+        assert_eq!(estimate_structuring(1000, &[], &[], true).based_on_samples, 0);
+    }
+
+    #[test]
+    fn test_worker_error_is_reported() {
+        let response = serde_json::json!({"error": "Unknown request"});
+        assert!(parse_loaded_model_details("llama", &response).is_err());
+    }
+
+    #[test]
+    fn test_capabilities_from_ping_and_old_worker_fallback() {
+        let ping = serde_json::json!({"status": "ready", "model_loaded": true, "capabilities": {
+            "protocol_version": 2,
+            "commands": ["ping", "shutdown", "metrics", "info", "text"],
+            "streaming": false,
+            "n_ctx": 4096,
+            "features": ["debug", "generation_params"],
+        }});
+        let capabilities = parse_capabilities(&ping);
+        assert_eq!(capabilities.protocol_version, 2);
+        assert_eq!(capabilities.n_ctx, Some(4096));
+        assert!(capabilities.require_command("info").is_ok());
+        assert!(capabilities.require_feature("generation_params").is_ok());
+        assert_eq!(capabilities.require_feature("streaming").unwrap_err(),
+            "Worker-Skript unterstützt kein Streaming – bitte aktualisieren");
+
+        // Old scripts answer the ping without capabilities
+        let old = parse_capabilities(&serde_json::json!({"status": "ready", "model_loaded": true}));
+        assert_eq!(old, WorkerCapabilities::default());
+        assert!(old.require_command("info").is_err());
+        assert!(!old.has_feature("debug"));
+        assert_eq!(parse_capabilities(&serde_json::json!({"capabilities": ["debug"]})).features, vec!["debug"]);
+    }
+
+    #[test]
+    fn test_generation_params_only_sent_to_capable_workers() {
+        let settings = GenerationSettings::default();
+        assert!(settings.validate().is_ok());
+        let mut invalid = settings.clone();
+        invalid.structuring.temperature = 2.5;
+        assert!(invalid.validate().unwrap_err().starts_with("Strukturierung"));
+
+        let mut worker = LlamaWorker::new();
+        let mut request = text_request("Diktat", false);
+        worker.attach_generation(&mut request, &settings.structuring);
+        assert!(request.get("generation").is_none());
+
+        worker.capabilities = parse_capabilities(&serde_json::json!({"status": "ready", "capabilities": {"features": ["debug", "generation_params"]}}));
+        worker.attach_generation(&mut request, &settings.structuring);
+        assert_eq!(request["generation"]["max_tokens"], 2000);
+
+        let response = serde_json::json!({"slots": {}, "generation": {"temperature": 0.1, "max_tokens": 2000}});
+        assert_eq!(parse_structured_content(&response, 5, false).generation.unwrap()["max_tokens"], 2000);
+    }
+
+    #[test]
+    fn test_worker_stderr_lines_reach_bounded_buffer() {
+        let buffer = Mutex::new(WorkerLogBuffer::new(3));
+        let stderr = Cursor::new(b"[STRUCTURER] Starting Qwen server\n\n[llama-server] llama_model_load: error loading model\r\n[llama-server] \xff out of memory\n".to_vec());
+        read_worker_log(stderr, &buffer);
+
+        let lines = buffer.lock().unwrap().tail(10);
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("] [llama-server] llama_model_load: error loading model"));
+        assert!(lines[2].ends_with("\u{FFFD} out of memory"));
+
+        let mut bounded = WorkerLogBuffer::new(2);
+        for i in 0..5 {
+            bounded.push(format!("Zeile {}", i));
+        }
+        assert_eq!(bounded.tail(10), vec!["Zeile 3", "Zeile 4"]);
+        assert_eq!(bounded.tail(1), vec!["Zeile 4"]);
+    }
+
+    #[test]
+    fn test_gguf_header_validation() {
+        let string = |s: &str| [(s.len() as u64).to_le_bytes().to_vec(), s.as_bytes().to_vec()].concat();
+        let mut header = b"GGUF".to_vec();
+        header.extend(3u32.to_le_bytes());
+        header.extend(291u64.to_le_bytes());  // Tensors
+        header.extend(2u64.to_le_bytes());    // Metadata entries
+        header.extend(string("general.alignment"));
+        header.extend(4u32.to_le_bytes());
+        header.extend(32u32.to_le_bytes());
+        header.extend(string("general.architecture"));
+        header.extend(8u32.to_le_bytes());
+        header.extend(string("qwen2"));
+
+        let info = read_gguf_header(&mut Cursor::new(header.clone())).unwrap();
+        assert_eq!(info, GgufInfo { valid: true, version: 3, architecture: Some("qwen2".to_string()) });
+
+        let mut bad_magic = header.clone();
+        bad_magic[..4].copy_from_slice(b"<!DO");
+        assert!(matches!(read_gguf_header(&mut Cursor::new(bad_magic)), Err(GgufError::BadMagic)));
+        assert!(matches!(read_gguf_header(&mut Cursor::new(header[..30].to_vec())), Err(GgufError::Truncated)));
+        assert!(matches!(validate_gguf_file(std::path::Path::new("/nonexistent/model.gguf")), Err(GgufError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_second_worker_kept_only_when_it_fits() {
+        const GB: u64 = 1_000_000_000;
+        let qwen_bytes = 5 * GB;
+        // Llama already loaded on a 12 GB card: 7 GB VRAM left fits Qwen beside it
+        let gpu = GpuMemoryInfo { name: "RTX".to_string(), used_bytes: 5 * GB, total_bytes: 12 * GB };
+        assert!(second_worker_fits(qwen_bytes, 2 * GB, Some(&gpu)));
+        // Same card nearly full and little RAM: the other worker has to go
+        let gpu = GpuMemoryInfo { name: "RTX".to_string(), used_bytes: 11 * GB, total_bytes: 12 * GB };
+        assert!(!second_worker_fits(qwen_bytes, 2 * GB, Some(&gpu)));
+        assert!(second_worker_fits(qwen_bytes, 16 * GB, None));
+    }
+
+    #[test]
+    fn test_grammar_model_selection() {
+        assert_eq!(select_grammar_model(None, true, true), Ok(false));
+        assert_eq!(select_grammar_model(Some("auto"), false, true), Ok(true));
+        assert_eq!(select_grammar_model(Some("qwen"), true, true), Ok(true));
+        assert_eq!(select_grammar_model(Some("mistral"), true, true), Err(ModelSelectionError::UnknownModel("mistral".to_string())));
+
+        let missing = select_grammar_model(Some("auto"), false, false).unwrap_err();
+        assert_eq!(missing, ModelSelectionError::ModelMissing {
+            expected: vec!["llama-3.1-8b-instruct-q4_k_m.gguf".to_string(), "qwen2.5-7b-instruct-q4_k_m.gguf".to_string()],
+            models_dir: r"C:\Users\kalin\Desktop\gutachten-assistant\models".to_string(),
+        });
+        assert!(missing.to_string().contains("llama-3.1-8b-instruct-q4_k_m.gguf oder qwen2.5-7b-instruct-q4_k_m.gguf"));
+    }
+}
@@ -9,6 +9,30 @@ pub mod docx_commands;
 pub mod format_commands;
 pub mod style_profile_commands;
 pub mod template_commands;
+pub mod session_commands;
+pub mod language_commands;
+pub mod text_commands;
+pub mod signature_commands;
+pub mod placeholder_commands;
+pub mod quality_gate_commands;
+pub mod export_commands;
+pub mod autosave_commands;
+pub mod section_history_commands;
+pub mod download_commands;
+pub mod pdf_style_commands;
+pub mod consistency_commands;
+pub mod anchor_commands;
+pub mod warmup_commands;
+pub mod quick_fix_commands;
+pub mod support_commands;
+pub mod file_open_commands;
+pub mod temp_file_commands;
+pub mod transcript_commands;
+pub mod pagination_commands;
+pub mod docx_validation_commands;
+pub mod section_prompt_commands;
+pub mod qc_report_commands;
+pub mod backup_commands;
 
 
 // Re-export all commands for easy access in main.rs
@@ -20,4 +44,28 @@ pub use llama_commands::*;
 pub use docx_commands::*;
 pub use format_commands::*;
 pub use style_profile_commands::*;
-pub use template_commands::*;
\ No newline at end of file
+pub use template_commands::*;
+pub use session_commands::*;
+pub use language_commands::*;
+pub use text_commands::*;
+pub use signature_commands::*;
+pub use placeholder_commands::*;
+pub use quality_gate_commands::*;
+pub use export_commands::*;
+pub use autosave_commands::*;
+pub use section_history_commands::*;
+pub use download_commands::*;
+pub use pdf_style_commands::*;
+pub use consistency_commands::*;
+pub use anchor_commands::*;
+pub use warmup_commands::*;
+pub use quick_fix_commands::*;
+pub use support_commands::*;
+pub use file_open_commands::*;
+pub use temp_file_commands::*;
+pub use transcript_commands::*;
+pub use backup_commands::*;
+pub use qc_report_commands::*;
+pub use section_prompt_commands::*;
+pub use pagination_commands::*;
+pub use docx_validation_commands::*;
\ No newline at end of file
@@ -2,8 +2,13 @@
 
 use tauri::{command, AppHandle, Window, Manager, Emitter};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use crate::memory_manager::MemoryManager;
+use std::fs;
+use crate::memory_manager::{get_system_memory_info, MemoryManager, MemoryUsage};
+use crate::services::ModelService;
+use crate::error_classifier::explain_error;
 // use crate::models::whisper_model::{WhisperModel, ModelLoadingProgress};
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,6 +21,32 @@ pub struct ModelInfo {
     pub memory_usage: u64,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LlmWorkerStatus {
+    pub running: bool,
+    pub model_type: Option<String>,  // "qwen" or "llama"; the first running worker
+    #[serde(default)]
+    pub model_types: Vec<String>,    // All running workers (grammar and structuring can run side by side)
+}
+
+/// Downloaded Whisper checkpoints (the Python library caches them as <name>.pt)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WhisperCacheStatus {
+    pub path: String,
+    pub exists: bool,
+    pub cached_models: Vec<String>,
+}
+
+/// Live model state: allocations, LLM worker, Whisper cache and last use
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModelStatusReport {
+    pub models: Vec<ModelInfo>,
+    pub memory: MemoryUsage,
+    pub llm_worker: LlmWorkerStatus,
+    pub whisper_cache: WhisperCacheStatus,
+    pub last_used: HashMap<String, String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ModelLoadingEvent {
     pub progress: f32,
@@ -23,6 +54,70 @@ pub struct ModelLoadingEvent {
     pub message: String,
 }
 
+/// Load-time memory check; stored in user-data/settings/memory.json
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MemorySettings {
+    pub safety_margin: f32,  // Available memory should be >= requirement x margin (room for other allocations)
+}
+
+impl Default for MemorySettings {
+    fn default() -> Self {
+        MemorySettings { safety_margin: 1.2 }
+    }
+}
+
+impl MemorySettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1.0..=3.0).contains(&self.safety_margin) {
+            return Err(format!("Sicherheitsfaktor muss zwischen 1,0 und 3,0 liegen (ist {})", self.safety_margin));
+        }
+        Ok(())
+    }
+}
+
+/// Payload of the "memory_warning" event: enough memory to load, but less than the margin asks for
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MemoryWarningEvent {
+    pub model: String,
+    pub required_bytes: u64,
+    pub available_bytes: u64,
+    pub safety_margin: f32,
+    pub message: String,
+}
+
+fn get_memory_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("memory.json"))
+}
+
+fn load_memory_settings() -> MemorySettings {
+    get_memory_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str::<MemorySettings>(&content).ok())
+        .filter(|settings| settings.validate().is_ok())
+        .unwrap_or_default()
+}
+
+/// Ok(None) with room to spare, Ok(Some(warning)) if only the bare requirement fits, Err if not even that
+pub fn check_load_memory(required: u64, available: u64, safety_margin: f32) -> Result<Option<String>, String> {
+    const GB: f64 = 1_000_000_000.0;
+    if available < required {
+        return Err(format!(
+            "Insufficient memory. Need {:.1} GB, have {:.1} GB available",
+            required as f64 / GB, available as f64 / GB
+        ));
+    }
+    let with_margin = (required as f64 * safety_margin as f64) as u64;
+    if available < with_margin {
+        return Ok(Some(format!(
+            "Wenig freier Arbeitsspeicher: {:.1} GB frei, empfohlen {:.1} GB. Andere Programme schließen, falls das Laden fehlschlägt.",
+            available as f64 / GB, with_margin as f64 / GB
+        )));
+    }
+    Ok(None)
+}
+
 /// Get information about available AI models
 #[command]
 pub async fn model_info() -> Result<Vec<ModelInfo>, String> {
@@ -65,19 +160,27 @@ pub async fn model_info() -> Result<Vec<ModelInfo>, String> {
 pub async fn load_whisper_model(
     window: Window,
     memory_manager: tauri::State<'_, Arc<MemoryManager>>,
+    model_service: tauri::State<'_, Arc<ModelService>>,
 ) -> Result<String, String> {
-    // Check memory availability before loading
-    let available_memory = memory_manager.get_available_memory().await
+    // Check memory availability before loading: the model budget and the RAM actually free
+    let budget = memory_manager.get_available_memory().await
         .map_err(|e| format!("Memory check failed: {}", e))?;
+    let (_, system_available) = tokio::task::spawn_blocking(get_system_memory_info).await
+        .map_err(|e| format!("Memory check failed: {}", e))?;
+    let available_memory = budget.min(system_available);
 
     const WHISPER_MODEL_SIZE: u64 = 3_200_000_000; // 3.2GB (with overhead)
 
-    if available_memory < WHISPER_MODEL_SIZE {
-        return Err(format!(
-            "Insufficient memory. Need {} GB, have {} GB available",
-            WHISPER_MODEL_SIZE / 1_000_000_000,
-            available_memory / 1_000_000_000
-        ));
+    let settings = load_memory_settings();
+    if let Some(message) = check_load_memory(WHISPER_MODEL_SIZE, available_memory, settings.safety_margin)? {
+        println!("Memory warning: {}", message);
+        window.emit("memory_warning", MemoryWarningEvent {
+            model: "whisper".to_string(),
+            required_bytes: WHISPER_MODEL_SIZE,
+            available_bytes: available_memory,
+            safety_margin: settings.safety_margin,
+            message,
+        }).map_err(|e| format!("Failed to emit event: {}", e))?;
     }
 
     // Emit loading started event
@@ -121,7 +224,7 @@ pub async fn load_whisper_model(
             Ok(())
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(format!("Python Whisper check failed: {}", stderr))
+            Err(explain_error(format!("Python Whisper check failed: {}", stderr)))
         }
     }).await.map_err(|e| format!("Python check task failed: {}", e))?;
 
@@ -153,6 +256,7 @@ pub async fn load_whisper_model(
     // Mark memory as allocated
     memory_manager.allocate_model_memory("whisper", WHISPER_MODEL_SIZE).await
         .map_err(|e| format!("Failed to allocate memory: {}", e))?;
+    model_service.mark_model_used("whisper");
 
     // Emit completion event
     window.emit("model_loading_progress", ModelLoadingEvent {
@@ -164,6 +268,28 @@ pub async fn load_whisper_model(
     Ok("Python Whisper Large-v3 model ready for use".to_string())
 }
 
+/// Get the load-time memory settings
+#[command]
+pub async fn get_memory_settings() -> Result<MemorySettings, String> {
+    Ok(load_memory_settings())
+}
+
+/// Save the load-time memory settings (rejected if the margin is out of range)
+#[command]
+pub async fn save_memory_settings(settings: MemorySettings) -> Result<MemorySettings, String> {
+    settings.validate()?;
+    let path = get_memory_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize memory settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write memory settings: {}", e))?;
+    Ok(settings)
+}
+
 /// Cleanup all loaded models and free memory
 #[command]
 pub async fn cleanup_models(
@@ -175,10 +301,94 @@ pub async fn cleanup_models(
     Ok("All models cleaned up successfully".to_string())
 }
 
+/// Cache directory of the Python Whisper library ($XDG_CACHE_HOME or ~/.cache, also on Windows)
+fn whisper_cache_dir() -> Option<PathBuf> {
+    let cache_root = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".cache")))?;
+    Some(cache_root.join("whisper"))
+}
+
+pub(crate) fn whisper_cache_status() -> WhisperCacheStatus {
+    let Some(dir) = whisper_cache_dir() else {
+        return WhisperCacheStatus { path: String::new(), exists: false, cached_models: Vec::new() };
+    };
+
+    let mut cached_models: Vec<String> = std::fs::read_dir(&dir)
+        .map(|entries| entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().and_then(|e| e.to_str()) == Some("pt"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().to_string()))
+            .collect())
+        .unwrap_or_default();
+    cached_models.sort();
+
+    WhisperCacheStatus {
+        path: dir.to_string_lossy().to_string(),
+        exists: !cached_models.is_empty(),
+        cached_models,
+    }
+}
+
+/// Apply live state to the static model list: loaded = memory is allocated for it
+fn apply_live_state(models: &mut [ModelInfo], memory: &MemoryUsage, whisper_cached: bool) {
+    for model in models.iter_mut() {
+        let key = match model.name.as_str() {
+            name if name.starts_with("Whisper") => "whisper",
+            name if name.starts_with("Tesseract") => "ocr",
+            _ => "nlp",
+        };
+        if let Some(size) = memory.models.get(key) {
+            model.loaded = true;
+            model.memory_usage = *size;
+            model.status = "Loaded".to_string();
+        } else if key == "whisper" {
+            model.status = if whisper_cached { "Downloaded" } else { "Not downloaded" }.to_string();
+        }
+    }
+}
+
 /// Get current model loading status
 #[command]
-pub async fn get_model_status() -> Result<Vec<ModelInfo>, String> {
-    // This would return the actual status of loaded models
-    // For now, return the same as model_info but with updated status
-    model_info().await
-}
\ No newline at end of file
+pub async fn get_model_status(
+    memory_manager: tauri::State<'_, Arc<MemoryManager>>,
+    model_service: tauri::State<'_, Arc<ModelService>>,
+) -> Result<ModelStatusReport, String> {
+    let memory = memory_manager.get_memory_usage().await;
+    let whisper_cache = whisper_cache_status();
+    let model_types = crate::commands::llama_commands::llm_worker_state();
+
+    let mut models = model_info().await?;
+    apply_live_state(&mut models, &memory, whisper_cache.exists);
+
+    Ok(ModelStatusReport {
+        models,
+        memory,
+        llm_worker: LlmWorkerStatus {
+            running: !model_types.is_empty(),
+            model_type: model_types.first().cloned(),
+            model_types,
+        },
+        whisper_cache,
+        last_used: model_service.last_used_timestamps(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tight_memory_warns_and_short_memory_fails() {
+        const GB: u64 = 1_000_000_000;
+        let required = 3_200_000_000;
+        assert_eq!(check_load_memory(required, 8 * GB, 1.2), Ok(None));
+
+        // 3.5 GB free: above the requirement, below 3.2 x 1.2 = 3.84 GB
+        let warning = check_load_memory(required, 3_500_000_000, 1.2).unwrap();
+        assert!(warning.unwrap().starts_with("Wenig freier Arbeitsspeicher"));
+
+        assert!(check_load_memory(required, 3 * GB, 1.2).unwrap_err().starts_with("Insufficient memory"));
+        assert!(MemorySettings { safety_margin: 0.8 }.validate().is_err());
+    }
+}
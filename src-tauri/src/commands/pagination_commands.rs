@@ -0,0 +1,324 @@
+// Pagination commands - Approximate page count of a DOCX export before it is written
+// ("wird das ungefähr 14 Seiten?"), from bundled character widths of common fonts
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::commands::docx_commands::{classify_line, split_pages, QUOTE_PREFIX};
+use crate::commands::document_commands::PageMargins;
+use crate::commands::export_commands::{blocks_to_text, content_blocks};
+use crate::commands::style_profile_commands::read_style_profile;
+use crate::commands::template_commands::load_family_spec;
+use crate::text_segmentation::{segment_lines, SegmentationOptions};
+
+/// A4 in cm
+const PAGE_WIDTH_CM: f32 = 21.0;
+const PAGE_HEIGHT_CM: f32 = 29.7;
+/// Relative spread given with the estimate
+const ESTIMATE_TOLERANCE: f32 = 0.1;
+/// Export defaults (see docx_commands): headings 2 pt larger with 12/6 pt spacing, quotes 1 cm in and 1 pt smaller
+const HEADING_SIZE_DELTA: f32 = 2.0;
+const HEADING_SPACING_PT: f32 = 18.0;
+const BOLD_WIDTH_FACTOR: f32 = 1.05;
+const QUOTE_INDENT_CM: f32 = 1.0;
+const QUOTE_SIZE_DELTA: f32 = 1.0;
+
+/// Advance widths in 1/1000 em; umlauts take the width of their base letter
+struct FontMetrics {
+    name: &'static str,
+    lowercase: [u16; 26],
+    uppercase: [u16; 26],
+    digit: u16,
+    space: u16,
+    punctuation: &'static [(char, u16)],
+    fallback: u16,      // Characters not in the table
+    line_height: f32,   // Single line spacing in em
+}
+
+const ARIAL: FontMetrics = FontMetrics {
+    name: "Arial",
+    lowercase: [556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, 556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500],
+    uppercase: [667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, 667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611],
+    digit: 556,
+    space: 278,
+    punctuation: &[('.', 278), (',', 278), (':', 278), (';', 278), ('-', 333), ('–', 556), ('(', 333), (')', 333),
+        ('/', 278), ('!', 278), ('?', 556), ('%', 889), ('§', 556), ('"', 355), ('„', 333), ('“', 333), ('\'', 191), ('ß', 611)],
+    fallback: 556,
+    line_height: 1.15,
+};
+
+const TIMES_NEW_ROMAN: FontMetrics = FontMetrics {
+    name: "Times New Roman",
+    lowercase: [444, 500, 444, 500, 444, 333, 500, 500, 278, 278, 500, 278, 778, 500, 500, 500, 500, 333, 389, 278, 500, 500, 722, 500, 500, 444],
+    uppercase: [722, 667, 667, 722, 611, 556, 722, 722, 333, 389, 722, 611, 889, 722, 722, 556, 722, 667, 556, 611, 722, 722, 944, 722, 722, 611],
+    digit: 500,
+    space: 250,
+    punctuation: &[('.', 250), (',', 250), (':', 278), (';', 278), ('-', 333), ('–', 500), ('(', 333), (')', 333),
+        ('/', 278), ('!', 333), ('?', 444), ('%', 833), ('§', 500), ('"', 408), ('„', 444), ('“', 444), ('\'', 180), ('ß', 500)],
+    fallback: 500,
+    line_height: 1.15,
+};
+
+const CALIBRI: FontMetrics = FontMetrics {
+    name: "Calibri",
+    lowercase: [479, 525, 423, 525, 498, 305, 471, 525, 229, 239, 455, 229, 799, 525, 527, 525, 525, 349, 391, 335, 525, 452, 715, 433, 453, 395],
+    uppercase: [579, 544, 533, 615, 488, 459, 631, 623, 252, 319, 520, 420, 855, 646, 662, 517, 673, 543, 459, 487, 642, 567, 890, 519, 487, 468],
+    digit: 507,
+    space: 226,
+    punctuation: &[('.', 252), (',', 250), (':', 268), (';', 268), ('-', 306), ('–', 498), ('(', 303), (')', 303),
+        ('/', 386), ('!', 326), ('?', 463), ('%', 715), ('§', 498), ('"', 401), ('„', 418), ('“', 418), ('\'', 221), ('ß', 527)],
+    fallback: 498,
+    line_height: 1.22,
+};
+
+/// Width table for a font family; metric-compatible families share one, unknown fonts use Arial's
+fn font_metrics(font_family: &str) -> (&'static FontMetrics, bool) {
+    let name = font_family.to_lowercase();
+    if name.contains("times") || name.contains("liberation serif") {
+        (&TIMES_NEW_ROMAN, true)
+    } else if name.contains("calibri") || name.contains("carlito") {
+        (&CALIBRI, true)
+    } else if name.contains("arial") || name.contains("helvetica") || name.contains("liberation sans") {
+        (&ARIAL, true)
+    } else {
+        (&ARIAL, false)
+    }
+}
+
+impl FontMetrics {
+    fn char_width(&self, c: char) -> u16 {
+        let base = match c {
+            'ä' => 'a', 'ö' => 'o', 'ü' => 'u', 'Ä' => 'A', 'Ö' => 'O', 'Ü' => 'U',
+            other => other,
+        };
+        match base {
+            'a'..='z' => self.lowercase[base as usize - 'a' as usize],
+            'A'..='Z' => self.uppercase[base as usize - 'A' as usize],
+            '0'..='9' => self.digit,
+            ' ' => self.space,
+            _ => self.punctuation.iter().find(|(p, _)| *p == base).map(|(_, w)| *w).unwrap_or(self.fallback),
+        }
+    }
+
+    /// Width of a text in pt at the given font size
+    fn text_width(&self, text: &str, size_pt: f32) -> f32 {
+        text.chars().map(|c| self.char_width(c) as f32).sum::<f32>() / 1000.0 * size_pt
+    }
+}
+
+/// Formatting the page count depends on; margins in cm
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PaginationFormatting {
+    pub font_family: String,
+    pub font_size: f32,
+    pub line_spacing: f32,
+    #[serde(default = "default_export_margins")]
+    pub margins: PageMargins,
+}
+
+/// Page margins of the DOCX export (docx-rs defaults: 1985/1701 twips)
+fn default_export_margins() -> PageMargins {
+    PageMargins { top: 3.5, bottom: 3.0, left: 3.0, right: 3.0 }
+}
+
+impl Default for PaginationFormatting {
+    fn default() -> Self {
+        PaginationFormatting {
+            font_family: "Arial".to_string(),
+            font_size: 11.0,
+            line_spacing: 1.5,
+            margins: default_export_margins(),
+        }
+    }
+}
+
+/// Pages one section spans (1-based, inclusive)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SectionPages {
+    pub section: String,
+    pub first_page: usize,
+    pub last_page: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PaginationEstimate {
+    pub pages: usize,
+    pub pages_min: usize,   // ±10 %
+    pub pages_max: usize,
+    pub lines: usize,
+    pub sections: Vec<SectionPages>,  // Text before the first heading is not listed
+    pub font_metrics: String,  // Width table used
+    pub font_known: bool,      // False: no table for the font, Arial's widths were used
+}
+
+/// Number of lines a paragraph wraps to at `width_pt`
+fn wrapped_lines(text: &str, metrics: &FontMetrics, size_pt: f32, width_pt: f32) -> usize {
+    let space = metrics.char_width(' ') as f32 / 1000.0 * size_pt;
+    let mut lines = 1;
+    let mut line_width = 0.0;
+    for word in text.split_whitespace() {
+        let word_width = metrics.text_width(word, size_pt);
+        if line_width > 0.0 && line_width + space + word_width > width_pt {
+            lines += 1;
+            line_width = 0.0;
+        }
+        if line_width > 0.0 {
+            line_width += space;
+        }
+        if line_width == 0.0 && word_width > width_pt {
+            // Words wider than the line are broken
+            lines += (word_width / width_pt).ceil() as usize - 1;
+            line_width = word_width % width_pt;
+        } else {
+            line_width += word_width;
+        }
+    }
+    lines
+}
+
+/// Lay the text out like the DOCX export does and count the pages
+pub fn estimate_text_pagination(text: &str, formatting: &PaginationFormatting) -> PaginationEstimate {
+    let (metrics, font_known) = font_metrics(&formatting.font_family);
+    let cm_to_pt = |cm: f32| cm / 2.54 * 72.0;
+    let width_pt = cm_to_pt(PAGE_WIDTH_CM - formatting.margins.left - formatting.margins.right).max(72.0);
+    let height_pt = cm_to_pt(PAGE_HEIGHT_CM - formatting.margins.top - formatting.margins.bottom).max(72.0);
+    let line_height = |size_pt: f32| size_pt * metrics.line_height * formatting.line_spacing.max(0.5);
+
+    let mut page = 0;
+    let mut used_pt = 0.0;
+    let mut total_lines = 0;
+    let mut sections: Vec<SectionPages> = Vec::new();
+
+    // Manual breaks and form feeds start a new page, as in the export
+    let lines = split_pages(text, None).into_iter().flat_map(|page_text| {
+        std::iter::once(None).chain(segment_lines(&page_text, &SegmentationOptions::default()).into_iter().map(Some))
+    });
+    for line in lines {
+        let Some(line) = line else {
+            page += 1;
+            used_pt = 0.0;
+            continue;
+        };
+
+        let trimmed = line.trim();
+        let (lines, size_pt, spacing_pt) = if trimmed.is_empty() {
+            (1, formatting.font_size, 0.0)
+        } else if let Some(quote) = trimmed.strip_prefix(QUOTE_PREFIX) {
+            let size = formatting.font_size - QUOTE_SIZE_DELTA;
+            (wrapped_lines(quote, metrics, size, width_pt - cm_to_pt(QUOTE_INDENT_CM)), size, 0.0)
+        } else if classify_line(trimmed).kind == "heading" {
+            let size = formatting.font_size + HEADING_SIZE_DELTA;
+            (wrapped_lines(trimmed, metrics, size * BOLD_WIDTH_FACTOR, width_pt), size, HEADING_SPACING_PT)
+        } else {
+            (wrapped_lines(trimmed, metrics, formatting.font_size, width_pt), formatting.font_size, 0.0)
+        };
+
+        used_pt += spacing_pt;
+        for _ in 0..lines {
+            if used_pt + line_height(size_pt) > height_pt {
+                page += 1;
+                used_pt = 0.0;
+            }
+            used_pt += line_height(size_pt);
+        }
+        total_lines += lines;
+
+        if spacing_pt > 0.0 {
+            sections.push(SectionPages { section: trimmed.to_string(), first_page: page, last_page: page });
+        } else if let Some(section) = sections.last_mut() {
+            section.last_page = page;
+        }
+    }
+
+    PaginationEstimate {
+        pages: page,
+        pages_min: ((page as f32 * (1.0 - ESTIMATE_TOLERANCE)).floor() as usize).max(1),
+        pages_max: (page as f32 * (1.0 + ESTIMATE_TOLERANCE)).ceil() as usize,
+        lines: total_lines,
+        sections,
+        font_metrics: metrics.name.to_string(),
+        font_known,
+    }
+}
+
+/// Text as the export writes it: plain text as is, structured content along the template family's skeleton
+fn export_text(content_or_text: &Value, spec: Option<&Value>) -> String {
+    match content_or_text {
+        Value::String(text) => text.clone(),
+        content => blocks_to_text(&content_blocks(content, spec)),
+    }
+}
+
+/// Formatting of the style profile, else the export defaults
+pub(crate) fn default_pagination_formatting() -> PaginationFormatting {
+    match read_style_profile() {
+        Ok(profile) => PaginationFormatting {
+            font_family: profile.formatting.font_family,
+            font_size: profile.formatting.font_size_pt,
+            line_spacing: profile.formatting.line_spacing,
+            margins: default_export_margins(),
+        },
+        Err(_) => PaginationFormatting::default(),
+    }
+}
+
+/// Approximate page count of the export of plain text or structured content, with the pages of each section
+pub(crate) fn estimate_content_pagination(content_or_text: &Value, spec: Option<&Value>, formatting: &PaginationFormatting) -> PaginationEstimate {
+    estimate_text_pagination(&export_text(content_or_text, spec), formatting)
+}
+
+/// Approximate page count before exporting (some courts cap report length)
+#[command]
+pub async fn estimate_pagination(content_or_text: Value, formatting: Option<PaginationFormatting>) -> Result<PaginationEstimate, String> {
+    let formatting = formatting.unwrap_or_else(default_pagination_formatting);
+    if formatting.font_size <= 0.0 {
+        return Err("Die Schriftgröße muss größer als 0 sein".to_string());
+    }
+    let spec = load_family_spec(None).ok();
+    let estimate = estimate_content_pagination(&content_or_text, spec.as_ref(), &formatting);
+    println!("Pagination estimate: {} pages ({}-{}), {} lines, {} metrics",
+        estimate.pages, estimate.pages_min, estimate.pages_max, estimate.lines, estimate.font_metrics);
+    Ok(estimate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_count_follows_font_metrics_and_sections() {
+        let paragraph = "Der Versicherte berichtet über seit mehreren Jahren bestehende Rückenschmerzen mit Ausstrahlung in das linke Bein, die sich unter Belastung verstärken und in Ruhe nur langsam abklingen.";
+        let mut text = String::new();
+        for section in ["ANAMNESE", "BEFUND", "BEURTEILUNG"] {
+            text.push_str(&format!("{}\n\n", section));
+            for _ in 0..30 {
+                text.push_str(&format!("{}\n\n", paragraph));
+            }
+        }
+
+        let formatting = PaginationFormatting {
+            font_family: "Times New Roman".to_string(),
+            font_size: 12.0,
+            line_spacing: 1.5,
+            margins: PageMargins { top: 2.5, bottom: 2.0, left: 2.5, right: 2.0 },
+        };
+        let estimate = estimate_text_pagination(&text, &formatting);
+
+        // By hand: 16.5 cm = 468 pt per line, the paragraph is 917 pt wide -> 3 lines once wrapped at words;
+        // 25.2 cm = 714 pt per page at 12 * 1.15 * 1.5 = 20.7 pt per line -> 34 lines per page.
+        // 90 paragraphs * 3 lines + 90 blank lines + headings ≈ 366 lines ≈ 10.8 pages.
+        assert!((10..=12).contains(&estimate.pages), "{:?}", estimate);
+        assert!(estimate.font_known);
+        assert_eq!(estimate.sections.iter().map(|s| s.section.as_str()).collect::<Vec<_>>(), vec!["ANAMNESE", "BEFUND", "BEURTEILUNG"]);
+        assert_eq!(estimate.sections[0].first_page, 1);
+        assert!(estimate.sections.windows(2).all(|w| w[0].last_page <= w[1].first_page));
+        assert_eq!(estimate.sections[2].last_page, estimate.pages);
+        assert!(estimate.pages_min < estimate.pages && estimate.pages < estimate.pages_max);
+
+        // Arial runs wider than Times, a manual break starts a new page
+        let arial = PaginationFormatting { font_family: "Arial".to_string(), ..formatting.clone() };
+        let long_paragraph = [paragraph; 10].join(" ");
+        assert!(estimate_text_pagination(&long_paragraph, &arial).lines > estimate_text_pagination(&long_paragraph, &formatting).lines);
+        assert_eq!(estimate_text_pagination("BEFUND\n\nKurz.\n[SEITENUMBRUCH]\nANLAGE", &formatting).pages, 2);
+    }
+}
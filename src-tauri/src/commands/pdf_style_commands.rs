@@ -0,0 +1,723 @@
+// PDF style commands - Best-effort style import from a PDF sample when no DOCX example exists
+// Fonts, sizes and text positions are read from the PDF text layer; all values are estimates
+use tauri::command;
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::PathBuf;
+use flate2::read::ZlibDecoder;
+use regex::Regex;
+use thiserror::Error;
+
+use crate::commands::document_commands::{
+    DocumentStyleInfo, HeaderFooterInfo, HeadingStyle, IndentationInfo, PageMargins, RoleAlignments,
+};
+use crate::commands::docx_commands::classify_line;
+
+/// Confidence of a style read from a PDF; formatting fidelity is lower than with DOCX
+const PDF_STYLE_CONFIDENCE: f32 = 0.6;
+const POINTS_PER_CM: f32 = 72.0 / 2.54;
+const A4_MEDIA_BOX: [f32; 4] = [0.0, 0.0, 595.0, 842.0];
+
+#[derive(Debug, Error)]
+pub enum PdfStyleError {
+    #[error("Datei ist kein PDF-Dokument")]
+    NotAPdf,
+    #[error("PDF enthält keine Textebene (gescanntes Dokument) – bitte zuerst die Texterkennung (OCR) verwenden")]
+    NoTextLayer,
+    #[error("Failed to read PDF: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Operand of a content stream operator
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Number(f32),
+    Name(String),
+    Text(Vec<u8>),
+    Array(Vec<Operand>),
+}
+
+/// Text shown by one Tj/TJ operator, in PDF points (origin bottom left)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfTextRun {
+    pub page: usize,
+    pub text: String,
+    pub font: String,  // Resource name, e.g. "F1"
+    pub size: f32,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Runs on the same baseline joined to a line
+#[derive(Debug, Clone, PartialEq)]
+struct PdfLine {
+    text: String,
+    font: String,  // Base font family, e.g. "Arial-BoldMT"
+    size: f32,
+    x: f32,
+    y: f32,
+}
+
+fn find_bytes(data: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from >= data.len() {
+        return None;
+    }
+    data[from..].windows(needle.len()).position(|w| w == needle).map(|p| p + from)
+}
+
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    // A truncated stream still yields the part decoded so far
+    let _ = ZlibDecoder::new(data).read_to_end(&mut decoded);
+    (!decoded.is_empty()).then_some(decoded)
+}
+
+/// Decoded content of every stream that is uncompressed or FlateDecode-compressed
+fn extract_streams(data: &[u8]) -> Vec<Vec<u8>> {
+    let mut streams = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = find_bytes(data, b"stream", pos) {
+        if data[..start].ends_with(b"end") {
+            pos = start + 6;
+            continue;
+        }
+        let dict_start = data[..start].windows(3).rposition(|w| w == b"obj").unwrap_or(0);
+        let dict = String::from_utf8_lossy(&data[dict_start..start]);
+
+        let mut body_start = start + 6;
+        if data.get(body_start) == Some(&b'\r') {
+            body_start += 1;
+        }
+        if data.get(body_start) == Some(&b'\n') {
+            body_start += 1;
+        }
+        let Some(end) = find_bytes(data, b"endstream", body_start) else { break };
+        let body = &data[body_start..end];
+
+        let decoded = if dict.contains("/FlateDecode") {
+            inflate(body)
+        } else if dict.contains("/Filter") {
+            None  // Images (DCT, JBIG2, CCITT) and other filters carry no text
+        } else {
+            Some(body.to_vec())
+        };
+        streams.extend(decoded);
+        pos = end + 9;
+    }
+    streams
+}
+
+fn is_delimiter(c: u8) -> bool {
+    c.is_ascii_whitespace() || b"()<>[]{}/%".contains(&c)
+}
+
+enum Lexeme {
+    Operand(Operand),
+    ArrayStart,
+    ArrayEnd,
+    Keyword(String),
+}
+
+struct ContentLexer<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ContentLexer<'a> {
+    fn read_regular(&mut self) -> &'a [u8] {
+        let start = self.pos;
+        while self.pos < self.data.len() && !is_delimiter(self.data[self.pos]) {
+            self.pos += 1;
+        }
+        &self.data[start..self.pos]
+    }
+
+    /// Literal string after "(" with nesting, escapes and octal codes
+    fn read_literal(&mut self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut depth = 1;
+        while self.pos < self.data.len() {
+            let c = self.data[self.pos];
+            self.pos += 1;
+            match c {
+                b'(' => {
+                    depth += 1;
+                    bytes.push(c);
+                }
+                b')' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                    bytes.push(c);
+                }
+                b'\\' => {
+                    let Some(&escaped) = self.data.get(self.pos) else { break };
+                    self.pos += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'b' => bytes.push(0x08),
+                        b'f' => bytes.push(0x0c),
+                        b'0'..=b'7' => {
+                            let mut value = (escaped - b'0') as u32;
+                            for _ in 0..2 {
+                                match self.data.get(self.pos) {
+                                    Some(&d @ b'0'..=b'7') => {
+                                        value = value * 8 + (d - b'0') as u32;
+                                        self.pos += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            bytes.push(value as u8);
+                        }
+                        b'\r' | b'\n' => {
+                            // Line continuation
+                            if escaped == b'\r' && self.data.get(self.pos) == Some(&b'\n') {
+                                self.pos += 1;
+                            }
+                        }
+                        other => bytes.push(other),
+                    }
+                }
+                _ => bytes.push(c),
+            }
+        }
+        bytes
+    }
+
+    fn read_hex(&mut self) -> Vec<u8> {
+        let mut digits = Vec::new();
+        while self.pos < self.data.len() && self.data[self.pos] != b'>' {
+            if let Some(d) = (self.data[self.pos] as char).to_digit(16) {
+                digits.push(d as u8);
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        if digits.len() % 2 == 1 {
+            digits.push(0);
+        }
+        digits.chunks(2).map(|pair| pair[0] * 16 + pair[1]).collect()
+    }
+
+    /// Skip inline image data ("ID ... EI")
+    fn skip_inline_image(&mut self) {
+        match find_bytes(self.data, b"EI", self.pos) {
+            Some(end) => self.pos = end + 2,
+            None => self.pos = self.data.len(),
+        }
+    }
+
+    fn next_lexeme(&mut self) -> Option<Lexeme> {
+        loop {
+            let c = *self.data.get(self.pos)?;
+            match c {
+                c if c.is_ascii_whitespace() => self.pos += 1,
+                b'%' => {
+                    while self.pos < self.data.len() && !matches!(self.data[self.pos], b'\n' | b'\r') {
+                        self.pos += 1;
+                    }
+                }
+                b'(' => {
+                    self.pos += 1;
+                    return Some(Lexeme::Operand(Operand::Text(self.read_literal())));
+                }
+                b'<' if self.data.get(self.pos + 1) == Some(&b'<') => {
+                    self.pos += 2;
+                    return Some(Lexeme::Keyword("<<".to_string()));
+                }
+                b'>' if self.data.get(self.pos + 1) == Some(&b'>') => {
+                    self.pos += 2;
+                    return Some(Lexeme::Keyword(">>".to_string()));
+                }
+                b'<' => {
+                    self.pos += 1;
+                    return Some(Lexeme::Operand(Operand::Text(self.read_hex())));
+                }
+                b'[' => {
+                    self.pos += 1;
+                    return Some(Lexeme::ArrayStart);
+                }
+                b']' => {
+                    self.pos += 1;
+                    return Some(Lexeme::ArrayEnd);
+                }
+                b'/' => {
+                    self.pos += 1;
+                    let name = String::from_utf8_lossy(self.read_regular()).to_string();
+                    return Some(Lexeme::Operand(Operand::Name(name)));
+                }
+                b'{' | b'}' | b')' | b'>' => self.pos += 1,
+                _ => {
+                    let word = String::from_utf8_lossy(self.read_regular()).to_string();
+                    return Some(match word.parse::<f32>() {
+                        Ok(number) => Lexeme::Operand(Operand::Number(number)),
+                        Err(_) => Lexeme::Keyword(word),
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Operators of a content stream with their operands
+fn parse_content_stream(content: &[u8]) -> Vec<(String, Vec<Operand>)> {
+    let mut lexer = ContentLexer { data: content, pos: 0 };
+    let mut operations = Vec::new();
+    let mut operands: Vec<Operand> = Vec::new();
+    let mut arrays: Vec<Vec<Operand>> = Vec::new();
+
+    while let Some(lexeme) = lexer.next_lexeme() {
+        match lexeme {
+            Lexeme::Operand(operand) => match arrays.last_mut() {
+                Some(array) => array.push(operand),
+                None => operands.push(operand),
+            },
+            Lexeme::ArrayStart => arrays.push(Vec::new()),
+            Lexeme::ArrayEnd => {
+                if let Some(array) = arrays.pop() {
+                    match arrays.last_mut() {
+                        Some(outer) => outer.push(Operand::Array(array)),
+                        None => operands.push(Operand::Array(array)),
+                    }
+                }
+            }
+            Lexeme::Keyword(keyword) => {
+                if keyword == "ID" {
+                    lexer.skip_inline_image();
+                }
+                // Inline dictionaries (marked content properties) are dropped with their operands
+                if keyword != "<<" && keyword != ">>" {
+                    operations.push((keyword, std::mem::take(&mut operands)));
+                }
+            }
+        }
+    }
+    operations
+}
+
+/// Simple fonts use single-byte codes; control characters come from CID fonts and are dropped
+fn decode_pdf_text(bytes: &[u8]) -> String {
+    bytes.iter().map(|&b| b as char).filter(|c| !c.is_control()).collect()
+}
+
+fn number(operands: &[Operand], index: usize) -> f32 {
+    match operands.get(index) {
+        Some(Operand::Number(n)) => *n,
+        _ => 0.0,
+    }
+}
+
+/// Follow the text state of a content stream and collect the shown text with position and size
+pub fn extract_text_runs(content: &[u8], page: usize) -> Vec<PdfTextRun> {
+    let mut runs = Vec::new();
+    let (mut font, mut font_size) = (String::new(), 0.0_f32);
+    let (mut scale, mut line_x, mut line_y, mut leading) = (1.0_f32, 0.0_f32, 0.0_f32, 0.0_f32);
+
+    for (operator, operands) in parse_content_stream(content) {
+        let mut shown: Option<String> = None;
+        match operator.as_str() {
+            "BT" => (scale, line_x, line_y) = (1.0, 0.0, 0.0),
+            "Tf" => {
+                if let Some(Operand::Name(name)) = operands.first() {
+                    font = name.clone();
+                }
+                font_size = number(&operands, 1);
+            }
+            "Tm" => {
+                let (c, d) = (number(&operands, 2), number(&operands, 3));
+                scale = match (c * c + d * d).sqrt() {
+                    s if s > 0.0 => s,
+                    _ => 1.0,
+                };
+                (line_x, line_y) = (number(&operands, 4), number(&operands, 5));
+            }
+            "Td" | "TD" => {
+                line_x += number(&operands, 0) * scale;
+                line_y += number(&operands, 1) * scale;
+                if operator == "TD" {
+                    leading = -number(&operands, 1);
+                }
+            }
+            "TL" => leading = number(&operands, 0),
+            "T*" => line_y -= leading * scale,
+            "Tj" | "'" | "\"" => {
+                if operator != "Tj" {
+                    line_y -= leading * scale;
+                }
+                if let Some(Operand::Text(bytes)) = operands.last() {
+                    shown = Some(decode_pdf_text(bytes));
+                }
+            }
+            "TJ" => {
+                if let Some(Operand::Array(parts)) = operands.first() {
+                    let mut text = String::new();
+                    for part in parts {
+                        match part {
+                            Operand::Text(bytes) => text.push_str(&decode_pdf_text(bytes)),
+                            // A large negative adjustment is a word gap
+                            Operand::Number(n) if *n < -200.0 => text.push(' '),
+                            _ => {}
+                        }
+                    }
+                    shown = Some(text);
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(text) = shown.filter(|t| !t.trim().is_empty()) {
+            runs.push(PdfTextRun {
+                page,
+                text,
+                font: font.clone(),
+                size: font_size * scale,
+                x: line_x,
+                y: line_y,
+            });
+        }
+    }
+    runs
+}
+
+/// "ABCDEF+Arial-BoldMT" -> "Arial-BoldMT"
+fn strip_subset_prefix(base_font: &str) -> &str {
+    match base_font.split_once('+') {
+        Some((prefix, name)) if prefix.len() == 6 && prefix.chars().all(|c| c.is_ascii_uppercase()) => name,
+        _ => base_font,
+    }
+}
+
+/// "Arial-BoldMT" -> "Arial", "TimesNewRomanPSMT" -> "Times New Roman"
+pub fn font_family_name(base_font: &str) -> String {
+    let name = strip_subset_prefix(base_font);
+    let family = name.split(['-', ',']).next().unwrap_or(name);
+    let family = family.trim_end_matches("PSMT").trim_end_matches("MT").trim_end_matches("PS");
+
+    let mut spaced = String::new();
+    let chars: Vec<char> = family.chars().collect();
+    for (i, &c) in chars.iter().enumerate() {
+        let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+        if i > 0 && c.is_uppercase() && (chars[i - 1].is_lowercase() || (next_is_lower && chars[i - 1].is_uppercase())) {
+            spaced.push(' ');
+        }
+        spaced.push(c);
+    }
+    spaced
+}
+
+fn is_bold_font(base_font: &str) -> bool {
+    let lower = base_font.to_lowercase();
+    lower.contains("bold") || lower.contains("black") || lower.contains("heavy")
+}
+
+/// Base font names of the font objects and the font resource names pointing at them
+fn font_resources(searchable: &str) -> (HashMap<String, String>, Vec<String>) {
+    let object_regex = Regex::new(r"(?s)(\d+)\s+0\s+obj(.*?)endobj").unwrap();
+    let base_font_regex = Regex::new(r"/BaseFont\s*/([^\s/<>\[\]()]+)").unwrap();
+    let font_dict_regex = Regex::new(r"(?s)/Font\s*<<(.*?)>>").unwrap();
+    let reference_regex = Regex::new(r"/([^\s/<>\[\]()]+)\s+(\d+)\s+0\s+R").unwrap();
+
+    let mut base_fonts_by_object: HashMap<String, String> = HashMap::new();
+    for object in object_regex.captures_iter(searchable) {
+        if let Some(base_font) = base_font_regex.captures(&object[2]) {
+            base_fonts_by_object.insert(object[1].to_string(), base_font[1].to_string());
+        }
+    }
+
+    let mut resources = HashMap::new();
+    for font_dict in font_dict_regex.captures_iter(searchable) {
+        for reference in reference_regex.captures_iter(&font_dict[1]) {
+            if let Some(base_font) = base_fonts_by_object.get(&reference[2]) {
+                resources.insert(reference[1].to_string(), base_font.clone());
+            }
+        }
+    }
+
+    let mut embedded: Vec<String> = Vec::new();
+    for base_font in base_font_regex.captures_iter(searchable) {
+        let name = strip_subset_prefix(&base_font[1]).to_string();
+        if !embedded.contains(&name) {
+            embedded.push(name);
+        }
+    }
+    (resources, embedded)
+}
+
+/// Crop box if present, otherwise media box (x0, y0, x1, y1 in points)
+fn page_box(searchable: &str) -> [f32; 4] {
+    for key in ["CropBox", "MediaBox"] {
+        let regex = Regex::new(&format!(r"/{}\s*\[\s*([-\d.]+)\s+([-\d.]+)\s+([-\d.]+)\s+([-\d.]+)\s*\]", key)).unwrap();
+        if let Some(captures) = regex.captures(searchable) {
+            let values: Vec<f32> = (1..=4).filter_map(|i| captures[i].parse().ok()).collect();
+            if values.len() == 4 && values[2] > values[0] && values[3] > values[1] {
+                return [values[0], values[1], values[2], values[3]];
+            }
+        }
+    }
+    A4_MEDIA_BOX
+}
+
+/// Join runs on the same baseline (per page, in content order) into lines
+fn group_lines(runs: &[PdfTextRun], resources: &HashMap<String, String>) -> Vec<PdfLine> {
+    let mut sorted: Vec<&PdfTextRun> = runs.iter().collect();
+    sorted.sort_by(|a, b| a.page.cmp(&b.page).then(b.y.total_cmp(&a.y)));
+
+    let mut lines: Vec<(usize, PdfLine, usize)> = Vec::new();  // (page, line, length of the longest run)
+    for run in sorted {
+        let base_font = resources.get(&run.font).cloned().unwrap_or_else(|| run.font.clone());
+        let run_length = run.text.chars().count();
+        match lines.last_mut() {
+            Some((page, line, longest)) if *page == run.page && (line.y - run.y).abs() < run.size.max(1.0) * 0.3 => {
+                line.text = format!("{} {}", line.text.trim_end(), run.text.trim_start());
+                line.size = line.size.max(run.size);
+                line.x = line.x.min(run.x);
+                if run_length > *longest {
+                    *longest = run_length;
+                    line.font = base_font;
+                }
+            }
+            _ => lines.push((run.page, PdfLine {
+                text: run.text.trim().to_string(),
+                font: base_font,
+                size: run.size,
+                x: run.x,
+                y: run.y,
+            }, run_length)),
+        }
+    }
+    lines.into_iter()
+        .map(|(_, mut line, _)| {
+            line.text = line.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            line
+        })
+        .collect()
+}
+
+/// Value carrying the most characters (sizes rounded to half points)
+fn dominant<T: Clone + PartialEq>(values: impl Iterator<Item = (T, usize)>) -> Option<T> {
+    let mut weights: Vec<(T, usize)> = Vec::new();
+    for (value, weight) in values {
+        match weights.iter_mut().find(|(v, _)| *v == value) {
+            Some((_, total)) => *total += weight,
+            None => weights.push((value, weight)),
+        }
+    }
+    weights.into_iter().max_by_key(|(_, weight)| *weight).map(|(value, _)| value)
+}
+
+fn round_half(size: f32) -> f32 {
+    (size * 2.0).round() / 2.0
+}
+
+fn to_cm(points: f32) -> f32 {
+    (points.max(0.0) / POINTS_PER_CM * 100.0).round() / 100.0
+}
+
+/// A line is a heading if it is set larger than the body text, in a bold font (and short)
+/// or is short and matches the DOCX heading rules (all caps, numbered, known section)
+fn is_pdf_heading(line: &PdfLine, body_size: f32) -> bool {
+    let short = line.text.split_whitespace().count() <= 12 && !line.text.ends_with('.');
+    let classification = classify_line(&line.text);
+    line.size >= body_size + 1.0
+        || (short && is_bold_font(&line.font))
+        || (short && classification.kind == "heading" && classification.confidence >= 0.7)
+}
+
+/// Derive a DocumentStyleInfo from the raw bytes of a PDF
+pub fn analyze_pdf_bytes(data: &[u8], document_id: &str, filename: &str) -> Result<DocumentStyleInfo, PdfStyleError> {
+    if !data.starts_with(b"%PDF-") {
+        return Err(PdfStyleError::NotAPdf);
+    }
+
+    let streams = extract_streams(data);
+    let mut searchable = String::from_utf8_lossy(data).to_string();
+    for stream in &streams {
+        searchable.push_str(&String::from_utf8_lossy(stream));
+    }
+    let (resources, embedded_fonts) = font_resources(&searchable);
+    let [box_x0, box_y0, box_x1, box_y1] = page_box(&searchable);
+
+    let runs: Vec<PdfTextRun> = streams.iter()
+        .filter(|s| find_bytes(s, b"BT", 0).is_some())
+        .enumerate()
+        .flat_map(|(page, stream)| extract_text_runs(stream, page))
+        .collect();
+    let lines = group_lines(&runs, &resources);
+    if lines.iter().all(|line| !line.text.chars().any(char::is_alphanumeric)) {
+        return Err(PdfStyleError::NoTextLayer);
+    }
+
+    let weight = |line: &PdfLine| line.text.chars().count();
+    let body_size = dominant(lines.iter().map(|l| (round_half(l.size), weight(l)))).unwrap_or(11.0);
+    let body_font = dominant(lines.iter()
+        .filter(|l| round_half(l.size) == body_size && !is_bold_font(&l.font))
+        .map(|l| (l.font.clone(), weight(l))))
+        .or_else(|| embedded_fonts.first().cloned())
+        .map(|font| font_family_name(&font))
+        .unwrap_or_else(|| "Arial".to_string());
+
+    // Heading levels by size: the largest heading size is level 1
+    let heading_lines: Vec<&PdfLine> = lines.iter().filter(|l| is_pdf_heading(l, body_size)).collect();
+    let mut heading_sizes: Vec<f32> = heading_lines.iter().map(|l| round_half(l.size)).collect();
+    heading_sizes.sort_by(|a, b| b.total_cmp(a));
+    heading_sizes.dedup();
+    let heading_styles: Vec<HeadingStyle> = heading_sizes.iter().take(3).enumerate()
+        .map(|(i, &size)| {
+            let sample = heading_lines.iter().find(|l| round_half(l.size) == size);
+            let bold = sample.is_some_and(|l| is_bold_font(&l.font));
+            HeadingStyle {
+                level: i as u8 + 1,
+                font_family: sample.map(|l| font_family_name(&l.font)).unwrap_or_else(|| body_font.clone()),
+                font_size: size,
+                font_weight: if bold { "bold" } else { "normal" }.to_string(),
+                color: "#000000".to_string(),
+                spacing_before: 0.0,
+                spacing_after: 0.0,
+            }
+        })
+        .collect();
+    let headers_found: Vec<String> = heading_lines.iter().map(|l| l.text.clone()).collect();
+
+    // Margins from the text bounding box; line widths are estimated at half an em per character
+    let left = lines.iter().map(|l| l.x).fold(f32::MAX, f32::min);
+    let right = lines.iter().map(|l| l.x + l.text.chars().count() as f32 * l.size * 0.5).fold(f32::MIN, f32::max);
+    let top = lines.iter().map(|l| l.y + l.size).fold(f32::MIN, f32::max);
+    let bottom = lines.iter().map(|l| l.y).fold(f32::MAX, f32::min);
+    let page_margins = PageMargins {
+        top: to_cm(box_y1 - top),
+        bottom: to_cm(bottom - box_y0),
+        left: to_cm(left - box_x0),
+        right: to_cm(box_x1 - right),
+    };
+
+    // Line spacing from the median baseline distance of consecutive body lines
+    let body_lines: Vec<&PdfLine> = lines.iter().filter(|l| round_half(l.size) == body_size).collect();
+    let mut gaps: Vec<f32> = body_lines.windows(2)
+        .map(|pair| pair[0].y - pair[1].y)
+        .filter(|gap| *gap > 0.0 && *gap < body_size * 3.0)
+        .collect();
+    gaps.sort_by(f32::total_cmp);
+    let line_spacing = match gaps.get(gaps.len() / 2) {
+        Some(gap) => (gap / (body_size * 1.2) * 10.0).round() / 10.0,
+        None => 1.0,
+    };
+
+    // Less confidence when font objects could not be resolved or there is little text
+    let mut confidence = PDF_STYLE_CONFIDENCE;
+    if embedded_fonts.is_empty() {
+        confidence -= 0.1;
+    }
+    if lines.len() < 5 {
+        confidence -= 0.1;
+    }
+
+    let style_summary = format!(
+        "Aus PDF abgeleitet (geschätzt): Hauptschrift: {} ({}pt), Zeilenabstand: {}, {} Überschriftenebenen erkannt",
+        body_font, body_size, line_spacing, heading_styles.len()
+    );
+
+    Ok(DocumentStyleInfo {
+        document_id: document_id.to_string(),
+        filename: filename.to_string(),
+        analysis_date: chrono::Utc::now().to_rfc3339(),
+        font_family: body_font,
+        font_size: body_size,
+        line_spacing,
+        paragraph_spacing_before: 0.0,
+        paragraph_spacing_after: 0.0,
+        heading_styles,
+        text_alignment: "left".to_string(),
+        page_margins,
+        header_footer_info: HeaderFooterInfo {
+            has_header: false,
+            has_footer: false,
+            header_content: String::new(),
+            footer_content: String::new(),
+            header_style: None,
+            footer_style: None,
+            header_candidates: Vec::new(),
+        },
+        style_summary,
+        headers_found,
+        indentation: IndentationInfo::default(),
+        alignments: RoleAlignments::default(),
+        source: "pdf".to_string(),
+        confidence,
+        page_break_count: 0,  // Page boundaries of a PDF are layout, not forced breaks
+        section_break_count: 0,
+        used_heading_levels: Vec::new(),  // A PDF has no paragraph styles
+        heading_level_gaps: Vec::new(),
+        trace: None,
+    })
+}
+
+/// Analyze a PDF sample (e.g. an old Gutachten) when no DOCX example is available. The result
+/// is flagged with source "pdf" and lower confidence; scanned PDFs need OCR first.
+#[command]
+pub async fn analyze_pdf_style(file_path: String, document_id: Option<String>) -> Result<DocumentStyleInfo, String> {
+    let path = PathBuf::from(&file_path);
+    if !path.exists() {
+        return Err(format!("Document file not found: {}", file_path));
+    }
+    let document_id = document_id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let filename = path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| format!("Document_{}", document_id));
+
+    let info = tokio::task::spawn_blocking(move || {
+        let data = std::fs::read(&path)?;
+        analyze_pdf_bytes(&data, &document_id, &filename)
+    }).await
+        .map_err(|e| format!("Analysis task failed: {}", e))?
+        .map_err(|e| e.to_string())?;
+
+    println!("PDF style analyzed: {} ({} headings, confidence {:.1})", info.filename, info.headers_found.len(), info.confidence);
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_pdf(content: &str) -> Vec<u8> {
+        format!(
+            "%PDF-1.4\n1 0 obj << /Type /Page /MediaBox [0 0 595 842] /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 3 0 R >> endobj\n\
+             3 0 obj << /Length {} >>\nstream\n{}\nendstream\nendobj\n\
+             4 0 obj << /Type /Font /Subtype /TrueType /BaseFont /ABCDEF+TimesNewRomanPSMT >> endobj\n\
+             5 0 obj << /Type /Font /Subtype /TrueType /BaseFont /ABCDEF+TimesNewRomanPS-BoldMT >> endobj\n%%EOF",
+            content.len(), content
+        ).into_bytes()
+    }
+
+    #[test]
+    fn test_pdf_text_layer_yields_style() {
+        let content = "BT /F2 14 Tf 1 0 0 1 70.87 770 Tm (ANAMNESE) Tj ET\n\
+            BT /F1 11 Tf 70.87 740 Td 16 TL (Der Patient berichtet \\374ber Schmerzen im R\\374cken.) Tj \
+            T* [(Seit 2019 bestehen Beschwerden) -250 (beim Gehen.)] TJ (Keine Operation.) ' ET\n\
+            BT /F2 11 Tf 70.87 680 Td (Befund) Tj ET";
+        let info = analyze_pdf_bytes(&sample_pdf(content), "doc-1", "alt.pdf").unwrap();
+
+        assert_eq!(info.source, "pdf");
+        assert!(info.confidence < 1.0);
+        assert_eq!(info.font_family, "Times New Roman");
+        assert_eq!(info.font_size, 11.0);
+        assert_eq!(info.headers_found, vec!["ANAMNESE", "Befund"]);
+        assert_eq!(info.heading_styles[0].font_size, 14.0);
+        assert_eq!(info.heading_styles[0].font_weight, "bold");
+        assert_eq!(info.page_margins.left, 2.5);
+        assert_eq!(info.page_margins.top, 2.05);
+
+        let runs = extract_text_runs(content.as_bytes(), 0);
+        assert_eq!(runs[1].text, "Der Patient berichtet über Schmerzen im Rücken.");
+        assert_eq!(runs[2].text, "Seit 2019 bestehen Beschwerden beim Gehen.");
+        assert_eq!(runs[3].y, 708.0);
+
+        let scanned = sample_pdf("q 595 0 0 842 0 0 cm /Im1 Do Q");
+        assert!(matches!(analyze_pdf_bytes(&scanned, "doc-2", "scan.pdf"), Err(PdfStyleError::NoTextLayer)));
+        assert!(matches!(analyze_pdf_bytes(b"PK\x03\x04", "doc-3", "x.docx"), Err(PdfStyleError::NotAPdf)));
+    }
+}
@@ -0,0 +1,234 @@
+// Placeholder commands - Resolves {{FELD}} placeholders (dates, case number, examiner) at export time
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::commands::backup_commands::backup_before_overwrite;
+use crate::commands::docx_commands::rewrite_docx_xml_parts;
+use crate::commands::session_commands::load_session_metadata;
+use crate::commands::signature_commands::escape_xml;
+
+/// What to do with placeholders that have no value
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UnresolvedPlaceholderMode {
+    #[default]
+    Abort,   // Fail the export and list the missing fields
+    Marker,  // Render a visible ⟦fehlend⟧ marker
+}
+
+pub const MISSING_PLACEHOLDER_MARKER: &str = "⟦fehlend⟧";
+
+static PLACEHOLDER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{\{\s*([A-ZÄÖÜ][A-ZÄÖÜ0-9_]*)\s*\}\}").unwrap()
+});
+
+/// A placeholder in document XML: the braces and name may be split over several runs
+/// (Word does this after spell-checking or editing), so tags between the characters are allowed.
+static XML_PLACEHOLDER_REGEX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\{(?:<[^>]*>)*\{((?:[^{}<]|<[^>]*>)*?)\}(?:<[^>]*>)*\}").unwrap()
+});
+
+static TAG_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]*>").unwrap());
+
+static ISO_DATE_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap());
+
+/// Placeholder values for an export: session metadata plus BERICHTSDATUM (today).
+/// ISO dates are formatted the German way (09.03.2026).
+pub(crate) fn placeholder_values(session_id: Option<&str>) -> Result<BTreeMap<String, String>, String> {
+    let mut values = match session_id {
+        Some(id) => load_session_metadata(id)?,
+        None => BTreeMap::new(),
+    };
+    values.entry("BERICHTSDATUM".to_string())
+        .or_insert_with(|| chrono::Local::now().date_naive().format("%Y-%m-%d").to_string());
+
+    for value in values.values_mut() {
+        if ISO_DATE_REGEX.is_match(value) {
+            if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+                *value = date.format("%d.%m.%Y").to_string();
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Replace placeholders in plain text; returns the text and the names without a value
+pub fn resolve_placeholders(
+    text: &str,
+    values: &BTreeMap<String, String>,
+    mode: UnresolvedPlaceholderMode,
+) -> (String, Vec<String>) {
+    let mut unresolved = BTreeSet::new();
+    let resolved = PLACEHOLDER_REGEX.replace_all(text, |c: &regex::Captures| {
+        match values.get(&c[1]) {
+            Some(value) => value.clone(),
+            None => {
+                unresolved.insert(c[1].to_string());
+                match mode {
+                    UnresolvedPlaceholderMode::Abort => c[0].to_string(),
+                    UnresolvedPlaceholderMode::Marker => MISSING_PLACEHOLDER_MARKER.to_string(),
+                }
+            }
+        }
+    });
+    (resolved.into_owned(), unresolved.into_iter().collect())
+}
+
+/// Replace placeholders inside WordprocessingML. The value goes where the placeholder started;
+/// tags inside a split placeholder are dropped (they only close and reopen runs).
+fn resolve_placeholders_in_xml(
+    xml: &str,
+    values: &BTreeMap<String, String>,
+    mode: UnresolvedPlaceholderMode,
+    unresolved: &mut BTreeSet<String>,
+) -> String {
+    XML_PLACEHOLDER_REGEX.replace_all(xml, |c: &regex::Captures| {
+        let whole = &c[0];
+        let name = TAG_REGEX.replace_all(&c[1], "").trim().to_string();
+        let crosses_paragraph = whole.contains("<w:p>") || whole.contains("<w:p ") || whole.contains("</w:p>");
+        if crosses_paragraph || !PLACEHOLDER_REGEX.is_match(&format!("{{{{{}}}}}", name)) {
+            return whole.to_string();
+        }
+
+        match values.get(&name) {
+            Some(value) => escape_xml(value),
+            None => {
+                unresolved.insert(name);
+                match mode {
+                    UnresolvedPlaceholderMode::Abort => whole.to_string(),
+                    UnresolvedPlaceholderMode::Marker => MISSING_PLACEHOLDER_MARKER.to_string(),
+                }
+            }
+        }
+    }).into_owned()
+}
+
+/// Resolve placeholders in the body, headers and footers of a DOCX in place.
+/// In abort mode the file is left unchanged and the missing names are returned as an error.
+pub(crate) fn resolve_placeholders_in_docx(
+    docx_path: &Path,
+    values: &BTreeMap<String, String>,
+    mode: UnresolvedPlaceholderMode,
+) -> Result<Vec<String>, String> {
+    let is_content_part = |name: &str| {
+        name == "word/document.xml"
+            || (name.starts_with("word/header") && name.ends_with(".xml"))
+            || (name.starts_with("word/footer") && name.ends_with(".xml"))
+    };
+
+    // Dry pass first so an aborted export doesn't leave a half-resolved file
+    let mut unresolved = BTreeSet::new();
+    if mode == UnresolvedPlaceholderMode::Abort {
+        read_docx_xml_parts(docx_path, |name, content| {
+            if is_content_part(name) {
+                resolve_placeholders_in_xml(content, values, mode, &mut unresolved);
+            }
+        })?;
+        if !unresolved.is_empty() {
+            return Err(format!(
+                "Nicht aufgelöste Platzhalter: {}",
+                unresolved.iter().map(|n| format!("{{{{{}}}}}", n)).collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+
+    rewrite_docx_xml_parts(docx_path, |name, content| {
+        if !is_content_part(name) {
+            return Ok(None);
+        }
+        Ok(Some(resolve_placeholders_in_xml(content, values, mode, &mut unresolved)))
+    })?;
+    Ok(unresolved.into_iter().collect())
+}
+
+/// Read the XML parts of a DOCX without writing anything
+fn read_docx_xml_parts<F: FnMut(&str, &str)>(docx_path: &Path, mut inspect: F) -> Result<(), String> {
+    use std::io::Read;
+
+    let file = std::fs::File::open(docx_path)
+        .map_err(|e| format!("Failed to open DOCX: {}", e))?;
+    let mut archive = zip::ZipArchive::new(std::io::BufReader::new(file))
+        .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)
+            .map_err(|e| format!("Failed to read DOCX entry: {}", e))?;
+        let name = entry.name().to_string();
+        if !name.ends_with(".xml") {
+            continue;
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content)
+            .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        inspect(&name, &content);
+    }
+    Ok(())
+}
+
+/// Resolve the placeholders of an exported DOCX from a session's metadata
+#[command]
+pub async fn resolve_docx_placeholders(
+    docx_path: String,
+    session_id: Option<String>,
+    mode: Option<UnresolvedPlaceholderMode>,
+) -> Result<Vec<String>, String> {
+    let values = placeholder_values(session_id.as_deref())?;
+    backup_before_overwrite(Path::new(&docx_path))?;
+    resolve_placeholders_in_docx(Path::new(&docx_path), &values, mode.unwrap_or_default())
+}
+
+/// List the placeholder values a session would provide (for previews in the UI)
+#[command]
+pub async fn get_placeholder_values(session_id: Option<String>) -> Result<BTreeMap<String, String>, String> {
+    placeholder_values(session_id.as_deref())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::document_commands::tests::{test_docx_path, write_test_docx};
+    use std::io::Read;
+
+    fn values() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("AKTENZEICHEN".to_string(), "AZ 12/345 & B".to_string()),
+            ("UNTERSUCHUNGSDATUM".to_string(), "09.03.2026".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_placeholders_resolve_in_text_and_split_runs() {
+        let (text, unresolved) = resolve_placeholders(
+            "Az.: {{AKTENZEICHEN}}, untersucht am {{ UNTERSUCHUNGSDATUM }} von {{GUTACHTER}}",
+            &values(),
+            UnresolvedPlaceholderMode::Marker,
+        );
+        assert_eq!(text, "Az.: AZ 12/345 & B, untersucht am 09.03.2026 von ⟦fehlend⟧");
+        assert_eq!(unresolved, vec!["GUTACHTER".to_string()]);
+
+        // Word split the placeholder over two runs
+        let body = r#"<w:p><w:r><w:t>Az.: {{AKTEN</w:t></w:r><w:r><w:rPr><w:b/></w:rPr><w:t>ZEICHEN}}</w:t></w:r></w:p><w:p><w:r><w:t>{{GUTACHTER}}</w:t></w:r></w:p>"#;
+        let path = test_docx_path("placeholders");
+        write_test_docx(&path, body, &[("word/header1.xml", "<w:hdr><w:p><w:r><w:t>{{UNTERSUCHUNGSDATUM}}</w:t></w:r></w:p></w:hdr>")]);
+
+        let error = resolve_placeholders_in_docx(&path, &values(), UnresolvedPlaceholderMode::Abort).unwrap_err();
+        assert!(error.contains("{{GUTACHTER}}"), "{}", error);
+
+        let unresolved = resolve_placeholders_in_docx(&path, &values(), UnresolvedPlaceholderMode::Marker).unwrap();
+        assert_eq!(unresolved, vec!["GUTACHTER".to_string()]);
+
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&path).unwrap()).unwrap();
+        let mut document = String::new();
+        archive.by_name("word/document.xml").unwrap().read_to_string(&mut document).unwrap();
+        let mut header = String::new();
+        archive.by_name("word/header1.xml").unwrap().read_to_string(&mut header).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert!(document.contains("<w:t>Az.: AZ 12/345 &amp; B</w:t></w:r></w:p>"), "{}", document);
+        assert!(document.contains("⟦fehlend⟧"));
+        assert!(header.contains("09.03.2026"));
+    }
+}
@@ -0,0 +1,322 @@
+// QC report commands - One Markdown report per document for reviewers: profile sections,
+// fonts, word counts and tracked changes
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::HashMap;
+use std::io::BufReader;
+use std::path::Path;
+use std::fs;
+
+use crate::commands::document_commands::{extract_document_xml, extract_paragraphs_with_levels, read_capped};
+use crate::commands::style_profile_commands::{compare_sections_with_profile, read_style_profile, StyleProfile};
+
+/// Runs (`<w:r>` / `<w:r w:rsidR="...">`, not `<w:rPr>`); paragraph marks have no text and are not counted
+static RUN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:r(?:\s[^>]*)?>.*?</w:r>").unwrap());
+static RUN_FONTS: Lazy<Regex> = Lazy::new(|| Regex::new(r"<w:rFonts\b[^>]*>").unwrap());
+static ASCII_FONT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"w:ascii="([^"]+)""#).unwrap());
+static ASCII_THEME_FONT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"w:asciiTheme="(major|minor)[A-Za-z]*""#).unwrap());
+/// <a:majorFont><a:latin typeface="Calibri Light" .../>
+static THEME_FONT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?s)<a:(major|minor)Font>\s*<a:latin typeface="([^"]*)""#).unwrap());
+static INSERTION: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:ins[ >]"#).unwrap());
+static DELETION: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:del[ >]"#).unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn badge(self) -> &'static str {
+        match self {
+            Severity::Error => "🔴 **Fehler**",
+            Severity::Warning => "🟡 **Warnung**",
+            Severity::Info => "🔵 **Hinweis**",
+        }
+    }
+}
+
+struct Finding {
+    severity: Severity,
+    message: String,
+}
+
+fn finding(severity: Severity, message: String) -> Finding {
+    Finding { severity, message }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FontUsage {
+    pub font: String,
+    pub run_count: usize,
+}
+
+/// Heading ("major") and body ("minor") fonts of theme1.xml, which w:asciiTheme refers to
+fn theme_fonts(theme_xml: &str) -> HashMap<String, String> {
+    THEME_FONT.captures_iter(theme_xml)
+        .filter(|c| !c[2].is_empty())
+        .map(|c| (c[1].to_string(), c[2].to_string()))
+        .collect()
+}
+
+/// Font of every run that sets one directly, most used first (ties alphabetically). A theme
+/// reference wins over w:ascii, as in Word; without a theme it is listed by its reference.
+fn count_run_fonts<'a>(xml_parts: impl IntoIterator<Item = &'a str>, theme_fonts: &HashMap<String, String>) -> Vec<FontUsage> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for xml in xml_parts {
+        for run in RUN.find_iter(xml) {
+            let Some(fonts) = RUN_FONTS.find(run.as_str()).map(|m| m.as_str()) else { continue };
+            let font = match ASCII_THEME_FONT.captures(fonts) {
+                Some(theme) => theme_fonts.get(&theme[1]).cloned()
+                    .unwrap_or_else(|| format!("({}-Designschriftart)", &theme[1])),
+                None => match ASCII_FONT.captures(fonts) {
+                    Some(ascii) => ascii[1].to_string(),
+                    None => continue,
+                },
+            };
+            *counts.entry(font).or_insert(0) += 1;
+        }
+    }
+
+    let mut usage: Vec<FontUsage> = counts.into_iter().map(|(font, run_count)| FontUsage { font, run_count }).collect();
+    usage.sort_by(|a, b| b.run_count.cmp(&a.run_count).then_with(|| a.font.cmp(&b.font)));
+    usage
+}
+
+/// Fonts of the body, headers and footers, with theme references resolved through theme1.xml
+fn document_fonts<R: std::io::Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>) -> Result<Vec<FontUsage>, String> {
+    let mut part_names: Vec<String> = archive.file_names()
+        .filter(|name| *name == "word/document.xml"
+            || ((name.starts_with("word/header") || name.starts_with("word/footer")) && name.ends_with(".xml")))
+        .map(String::from)
+        .collect();
+    part_names.sort();
+    let mut parts = Vec::new();
+    for name in &part_names {
+        if let Ok(part) = archive.by_name(name) {
+            let declared_size = part.size();
+            parts.push(read_capped(part, declared_size, name)?);
+        }
+    }
+
+    let theme_name = "word/theme/theme1.xml";
+    let theme = match archive.by_name(theme_name) {
+        Ok(part) => {
+            let declared_size = part.size();
+            theme_fonts(&read_capped(part, declared_size, theme_name)?)
+        }
+        Err(_) => HashMap::new(),
+    };
+    Ok(count_run_fonts(parts.iter().map(String::as_str), &theme))
+}
+
+/// Words per section; text before the first heading counts as "(Einleitung)"
+fn section_word_counts(document_xml: &str) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for (level, text) in extract_paragraphs_with_levels(document_xml) {
+        match (level, counts.last_mut()) {
+            (Some(_), _) => counts.push((text, 0)),
+            (None, Some((_, words))) => *words += text.split_whitespace().count(),
+            (None, None) => counts.push(("(Einleitung)".to_string(), text.split_whitespace().count())),
+        }
+    }
+    counts
+}
+
+/// Markdown QC report of a document body and its fonts (see `document_fonts`);
+/// without a profile the profile checks are skipped
+pub fn build_qc_report(file_name: &str, document_xml: &str, fonts: &[FontUsage], profile: Option<&StyleProfile>) -> String {
+    let mut findings = Vec::new();
+
+    let sections = profile.map(|profile| compare_sections_with_profile(document_xml, &profile.sections));
+    match &sections {
+        Some(result) => {
+            for missing in &result.missing_sections {
+                findings.push(finding(Severity::Error, format!("Pflichtabschnitt fehlt: {}", missing)));
+            }
+            for extra in &result.extra_sections {
+                findings.push(finding(Severity::Info, format!("Abschnitt nicht im Stilprofil: {}", extra)));
+            }
+        }
+        None => findings.push(finding(Severity::Info, "Kein Stilprofil vorhanden, Abschnitte nicht geprüft".to_string())),
+    }
+
+    if fonts.len() > 1 {
+        let list: Vec<&str> = fonts.iter().map(|usage| usage.font.as_str()).collect();
+        findings.push(finding(Severity::Warning, format!("Mehrere Schriftarten verwendet: {}", list.join(", "))));
+    }
+    if let Some(profile) = profile {
+        let expected = &profile.formatting.font_family;
+        if !fonts.is_empty() && !fonts.iter().any(|usage| usage.font.eq_ignore_ascii_case(expected)) {
+            findings.push(finding(Severity::Warning, format!("Schriftart des Stilprofils ({}) wird nicht verwendet", expected)));
+        }
+    }
+
+    let insertions = INSERTION.find_iter(document_xml).count();
+    let deletions = DELETION.find_iter(document_xml).count();
+    if insertions + deletions > 0 {
+        findings.push(finding(Severity::Error, format!(
+            "Nachverfolgte Änderungen nicht angenommen: {} Einfügungen, {} Löschungen", insertions, deletions
+        )));
+    }
+    findings.sort_by_key(|f| f.severity);
+
+    let word_counts = section_word_counts(document_xml);
+    let total_words: usize = word_counts.iter().map(|(_, words)| words).sum();
+
+    let mut report = format!(
+        "# QC-Bericht: {}\n\nErstellt am {}\n\n## Zusammenfassung\n\n",
+        file_name,
+        chrono::Local::now().format("%d.%m.%Y %H:%M")
+    );
+    let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+    report.push_str(&format!(
+        "- {}: {}\n- {}: {}\n- {}: {}\n- Wörter gesamt: {}\n",
+        Severity::Error.badge(), count(Severity::Error),
+        Severity::Warning.badge(), count(Severity::Warning),
+        Severity::Info.badge(), count(Severity::Info),
+        total_words
+    ));
+
+    report.push_str("\n## Befunde\n\n");
+    if findings.is_empty() {
+        report.push_str("Keine Auffälligkeiten.\n");
+    }
+    for f in &findings {
+        report.push_str(&format!("- {} {}\n", f.severity.badge(), f.message));
+    }
+
+    report.push_str("\n## Schriftarten\n\n");
+    if fonts.is_empty() {
+        report.push_str("Keine direkt gesetzten Schriftarten (nur Formatvorlagen).\n");
+    }
+    for usage in fonts {
+        report.push_str(&format!("- {} ({} Textläufe)\n", usage.font, usage.run_count));
+    }
+
+    report.push_str("\n## Wortzahlen je Abschnitt\n\n| Abschnitt | Wörter |\n|---|---:|\n");
+    for (section, words) in &word_counts {
+        report.push_str(&format!("| {} | {} |\n", section.replace('|', "\\|"), words));
+    }
+    report
+}
+
+/// QC report of a DOCX as Markdown; also written to `output_path` if given
+#[command]
+pub async fn generate_qc_report(file_path: String, output_path: Option<String>) -> Result<String, String> {
+    let path = Path::new(&file_path);
+    let file = fs::File::open(path)
+        .map_err(|e| format!("Dokument konnte nicht geöffnet werden: {}", e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+    let document_xml = extract_document_xml(&mut archive)?;
+    let fonts = document_fonts(&mut archive)?;
+
+    let profile = read_style_profile().ok();
+    let file_name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or(file_path.clone());
+    let report = build_qc_report(&file_name, &document_xml, &fonts, profile.as_ref());
+
+    if let Some(output_path) = output_path {
+        fs::write(&output_path, &report)
+            .map_err(|e| format!("Failed to write QC report: {}", e))?;
+        println!("📝 QC report written to {}", output_path);
+    }
+    Ok(report)
+}
+
+/// Every font set on runs of the body, headers and footers with its run count, most used first;
+/// the data behind the "Mehrere Schriftarten" finding
+#[command]
+pub async fn list_document_fonts(file_path: String) -> Result<Vec<FontUsage>, String> {
+    let file = fs::File::open(&file_path)
+        .map_err(|e| format!("Dokument konnte nicht geöffnet werden: {}", e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read DOCX archive: {}", e))?;
+
+    let usage = document_fonts(&mut archive)?;
+    println!("Fonts of {}: {}", file_path,
+        usage.iter().map(|u| format!("{} ({})", u.font, u.run_count)).collect::<Vec<_>>().join(", "));
+    Ok(usage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::docx_commands::QuoteStyle;
+    use crate::commands::style_profile_commands::{FormattingInfo, SectionInfo};
+
+    fn section(name: &str, order: i32) -> SectionInfo {
+        SectionInfo {
+            normalized_name: name.to_uppercase(),
+            display_name: name.to_string(),
+            is_required: true,
+            occurrence_count: 3,
+            occurrence_percentage: 100.0,
+            order,
+        }
+    }
+
+    #[test]
+    fn test_report_lists_missing_section_and_mixed_fonts() {
+        let profile = StyleProfile {
+            version: "1.0".to_string(),
+            created_at: "2025-01-01".to_string(),
+            analyzed_documents: 3,
+            source_files: Vec::new(),
+            sections: vec![section("Anamnese", 1), section("Befund", 2)],
+            formatting: FormattingInfo { font_family: "Arial".to_string(), font_size_pt: 11.0, line_spacing: 1.5, quote: QuoteStyle::default() },
+        };
+        let paragraph = |font: &str, text: &str| format!(
+            r#"<w:p><w:r><w:rPr><w:rFonts w:ascii="{}"/></w:rPr><w:t>{}</w:t></w:r></w:p>"#, font, text
+        );
+        let document_xml = format!(
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Anamnese</w:t></w:r></w:p>{}{}"#,
+            paragraph("Arial", "Der Patient berichtet über Schmerzen."),
+            paragraph("Times New Roman", "Seit drei Wochen.")
+        );
+
+        let fonts = count_run_fonts([document_xml.as_str()], &HashMap::new());
+        let report = build_qc_report("gutachten.docx", &document_xml, &fonts, Some(&profile));
+        assert!(report.contains("🔴 **Fehler** Pflichtabschnitt fehlt: Befund"));
+        assert!(report.contains("🟡 **Warnung** Mehrere Schriftarten verwendet: Arial, Times New Roman"));
+        assert!(report.contains("| Anamnese | 8 |"));
+        assert!(!report.contains("Nachverfolgte Änderungen"));
+    }
+
+    #[tokio::test]
+    async fn test_fonts_of_body_header_and_footer_are_counted() {
+        use crate::commands::document_commands::tests::{test_docx_path, write_test_docx};
+
+        let run = |fonts: &str| format!(r#"<w:r><w:rPr><w:rFonts {}/><w:b/></w:rPr><w:t>Text</w:t></w:r>"#, fonts);
+        // The paragraph mark's font is not a run; the run without rFonts comes from the style
+        let body = format!(
+            r#"<w:p><w:pPr><w:rPr><w:rFonts w:ascii="Wingdings"/></w:rPr></w:pPr>{}{}<w:r><w:t>Formatvorlage</w:t></w:r></w:p>"#,
+            run(r#"w:ascii="Times New Roman" w:hAnsi="Times New Roman""#),
+            run(r#"w:ascii="Arial" w:asciiTheme="minorHAnsi" w:hAnsiTheme="minorHAnsi""#),
+        );
+        let header = format!(r#"<w:hdr><w:p>{}{}</w:p></w:hdr>"#, run(r#"w:ascii="Calibri""#), run(r#"w:ascii="Times New Roman""#));
+        let footer = format!(r#"<w:ftr><w:p>{}</w:p></w:ftr>"#, run(r#"w:ascii="Times New Roman""#));
+        let theme = r#"<a:theme><a:fontScheme name="Office"><a:majorFont><a:latin typeface="Calibri Light"/></a:majorFont><a:minorFont><a:latin typeface="Calibri"/></a:minorFont></a:fontScheme></a:theme>"#;
+        let path = test_docx_path("font_list");
+        write_test_docx(&path, &body, &[("word/header1.xml", &header), ("word/footer1.xml", &footer), ("word/theme/theme1.xml", theme)]);
+
+        let fonts = list_document_fonts(path.to_string_lossy().to_string()).await.unwrap();
+        assert_eq!(fonts, vec![
+            FontUsage { font: "Times New Roman".to_string(), run_count: 3 },
+            FontUsage { font: "Calibri".to_string(), run_count: 2 },
+        ]);
+
+        // Without a theme the reference itself is listed
+        let without_theme = count_run_fonts([body.as_str()], &HashMap::new());
+        assert!(without_theme.iter().any(|u| u.font == "(minor-Designschriftart)"));
+
+        // The QC report warns about the same fonts the list shows
+        let report = generate_qc_report(path.to_string_lossy().to_string(), None).await.unwrap();
+        assert!(report.contains("Mehrere Schriftarten verwendet: Times New Roman, Calibri"));
+
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,401 @@
+// Quality gate commands - Checks a session must pass before it is exported (marked "exportiert")
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::fs;
+use std::io::Write;
+
+use crate::commands::pagination_commands::{default_pagination_formatting, estimate_content_pagination, PaginationEstimate};
+use crate::commands::session_commands::{is_unclear_span_resolved, load_session_structured_content, SessionStructuredContent};
+use crate::commands::style_profile_commands::template_approval_problem;
+use crate::commands::template_commands::load_family_spec;
+use crate::commands::text_commands::{find_duplicates, slot_text, DuplicateParagraph, DuplicateParagraphOptions};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QualityGateSettings {
+    #[serde(default = "default_enforce")]
+    pub enforce_by_default: bool,     // Used when an export doesn't pass enforce_quality_gate
+    #[serde(default = "default_max_spelling_issues")]
+    pub max_spelling_issues: usize,   // Exports need fewer issues than this
+}
+
+impl Default for QualityGateSettings {
+    fn default() -> Self {
+        QualityGateSettings {
+            enforce_by_default: default_enforce(),
+            max_spelling_issues: default_max_spelling_issues(),
+        }
+    }
+}
+
+fn default_enforce() -> bool {
+    true
+}
+
+fn default_max_spelling_issues() -> usize {
+    5
+}
+
+/// One criterion the session does not meet
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UnmetCriterion {
+    pub criterion: String,  // "missing_sections", "spelling_issues", "unclear_spans" or "template_approval"
+    pub message: String,
+}
+
+/// Returned instead of an exported file when the gate blocks the export
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QualityGateFailure {
+    pub session_id: String,
+    pub unmet: Vec<UnmetCriterion>,
+}
+
+fn get_quality_gate_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("quality_gate.json"))
+}
+
+fn get_audit_log_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("audit_log.jsonl"))
+}
+
+fn load_quality_gate_settings() -> Result<QualityGateSettings, String> {
+    let path = get_quality_gate_settings_path()?;
+    if !path.exists() {
+        return Ok(QualityGateSettings::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read quality gate settings: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse quality gate settings: {}", e))
+}
+
+/// Append one entry to the audit log (one JSON object per line)
+pub(crate) fn append_audit_log(action: &str, session_id: Option<&str>, details: Value) -> Result<(), String> {
+    let path = get_audit_log_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create audit log directory: {}", e))?;
+    }
+
+    let entry = serde_json::json!({
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "action": action,
+        "session_id": session_id,
+        "details": details,
+    });
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("Failed to open audit log: {}", e))?;
+    writeln!(file, "{}", entry)
+        .map_err(|e| format!("Failed to write audit log: {}", e))
+}
+
+fn is_empty_slot(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => true,
+        Some(Value::String(text)) => text.trim().is_empty(),
+        Some(Value::Array(items)) => items.is_empty(),
+        Some(_) => false,
+    }
+}
+
+/// Non-optional slots of the spec skeleton; None without a skeleton
+fn required_slots(spec: Option<&Value>) -> Option<Vec<&Value>> {
+    let skeleton = spec.and_then(|s| s.get("skeleton")).and_then(|s| s.as_array())?;
+    Some(skeleton.iter()
+        .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("slot"))
+        .filter(|item| !item.get("optional").and_then(|o| o.as_bool()).unwrap_or(false))
+        .collect())
+}
+
+/// Required sections without content: non-optional skeleton slots that are empty or reported
+/// missing by the structurer. Without a spec, the structurer's missing_slots are used as is.
+pub(crate) fn missing_required_sections(slots: &Value, reported: &[String], spec: Option<&Value>) -> Vec<String> {
+    let Some(skeleton) = required_slots(spec) else {
+        return reported.to_vec();
+    };
+
+    skeleton.into_iter()
+        .filter_map(|item| {
+            let slot_id = item.get("slot_id").and_then(|s| s.as_str())?;
+            let missing = reported.iter().any(|m| m == slot_id)
+                || is_empty_slot(slots.get(slot_id));
+            missing.then(|| item.get("section_name")
+                .and_then(|n| n.as_str())
+                .unwrap_or(slot_id)
+                .to_string())
+        })
+        .collect()
+}
+
+fn open_unclear_spans(spans: &[Value]) -> usize {
+    spans.iter().filter(|span| !is_unclear_span_resolved(span)).count()
+}
+
+/// Evaluate all criteria; an unchecked session (no spell check recorded) counts as zero issues
+pub fn evaluate_quality_gate(
+    content: &SessionStructuredContent,
+    spec: Option<&Value>,
+    template_problem: Option<String>,
+    settings: &QualityGateSettings,
+) -> Vec<UnmetCriterion> {
+    let mut unmet = Vec::new();
+
+    let missing = missing_required_sections(&content.content.slots, &content.content.missing_slots, spec);
+    if !missing.is_empty() {
+        unmet.push(UnmetCriterion {
+            criterion: "missing_sections".to_string(),
+            message: format!("Pflichtabschnitte ohne Inhalt: {}", missing.join(", ")),
+        });
+    }
+
+    let spelling_issues = content.spelling_issues.unwrap_or(0);
+    if spelling_issues >= settings.max_spelling_issues {
+        unmet.push(UnmetCriterion {
+            criterion: "spelling_issues".to_string(),
+            message: format!("{} Rechtschreibfehler (erlaubt: weniger als {})", spelling_issues, settings.max_spelling_issues),
+        });
+    }
+
+    let open_spans = open_unclear_spans(&content.content.unclear_spans);
+    if open_spans > 0 {
+        unmet.push(UnmetCriterion {
+            criterion: "unclear_spans".to_string(),
+            message: format!("{} unklare Stellen sind noch nicht geklärt", open_spans),
+        });
+    }
+
+    if let Some(problem) = template_problem {
+        unmet.push(UnmetCriterion {
+            criterion: "template_approval".to_string(),
+            message: problem,
+        });
+    }
+
+    unmet
+}
+
+/// Run the gate for an export. Returns the failure if the export must not produce a file;
+/// a forced export passes but the overridden criteria are written to the audit log.
+pub(crate) fn run_quality_gate(
+    session_id: &str,
+    spec: Option<&Value>,
+    enforce: Option<bool>,
+    force: bool,
+) -> Result<Option<QualityGateFailure>, String> {
+    let settings = load_quality_gate_settings()?;
+    if !enforce.unwrap_or(settings.enforce_by_default) {
+        return Ok(None);
+    }
+
+    let content = load_session_structured_content(session_id)?;
+    let unmet = evaluate_quality_gate(&content, spec, template_approval_problem()?, &settings);
+    if unmet.is_empty() {
+        return Ok(None);
+    }
+
+    if force {
+        println!("[RUST] Quality gate overridden for session {} ({} criteria)", session_id, unmet.len());
+        append_audit_log("quality_gate_override", Some(session_id), serde_json::json!({ "unmet": unmet }))?;
+        return Ok(None);
+    }
+
+    println!("[RUST] Quality gate blocked export of session {} ({} criteria)", session_id, unmet.len());
+    Ok(Some(QualityGateFailure {
+        session_id: session_id.to_string(),
+        unmet,
+    }))
+}
+
+/// Combined readiness of structured content before rendering
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrerenderCheck {
+    pub required_coverage: f32,         // Share of required sections with content (1.0 = complete)
+    pub missing_required: Vec<String>,  // Section names of the required sections without content
+    pub empty_slots: Vec<String>,       // All slots without content, required or not
+    pub unclear_count: usize,           // Unresolved unclear spans
+    pub duplicate_paragraphs: Vec<DuplicateParagraph>,
+    pub ready: bool,                    // Gate for the export button
+    #[serde(default)]
+    pub pagination: Option<PaginationEstimate>,  // Expected length of the export; informational, doesn't gate
+}
+
+/// Run the individual checks on structured content (the content object with "slots",
+/// "unclear_spans" and "missing_slots"); empty optional slots don't block rendering
+pub fn evaluate_prerender(content: &Value, spec: Option<&Value>) -> PrerenderCheck {
+    let empty = Value::Object(serde_json::Map::new());
+    let slots = content.get("slots").unwrap_or(&empty);
+    let reported: Vec<String> = content.get("missing_slots")
+        .and_then(|m| serde_json::from_value(m.clone()).ok())
+        .unwrap_or_default();
+    let unclear_spans: Vec<Value> = content.get("unclear_spans")
+        .and_then(|u| u.as_array().cloned())
+        .unwrap_or_default();
+
+    let missing_required = missing_required_sections(slots, &reported, spec);
+    let required_count = required_slots(spec).map(|r| r.len()).unwrap_or(missing_required.len());
+    let required_coverage = if required_count == 0 {
+        1.0
+    } else {
+        required_count.saturating_sub(missing_required.len()) as f32 / required_count as f32
+    };
+
+    let empty_slots: Vec<String> = slots.as_object()
+        .map(|map| map.iter()
+            .filter(|(_, value)| is_empty_slot(Some(value)))
+            .map(|(slot_id, _)| slot_id.clone())
+            .collect())
+        .unwrap_or_default();
+
+    let all_text = slots.as_object()
+        .map(|map| map.values().map(slot_text).collect::<Vec<_>>().join("\n\n"))
+        .unwrap_or_default();
+    let duplicate_paragraphs = find_duplicates(&all_text, &DuplicateParagraphOptions {
+        similarity_threshold: None,
+        min_words: None,
+    });
+
+    let unclear_count = open_unclear_spans(&unclear_spans);
+    PrerenderCheck {
+        ready: missing_required.is_empty() && unclear_count == 0 && duplicate_paragraphs.is_empty(),
+        required_coverage,
+        missing_required,
+        empty_slots,
+        unclear_count,
+        duplicate_paragraphs,
+        pagination: None,
+    }
+}
+
+/// Pre-render gate: one verdict for the export button. Without a spec the active template
+/// family's skeleton defines the required sections.
+#[command]
+pub async fn prerender_check(content_json: Value, spec: Option<Value>) -> Result<PrerenderCheck, String> {
+    let spec = spec.or_else(|| load_family_spec(None).ok());
+    let mut check = evaluate_prerender(&content_json, spec.as_ref());
+    check.pagination = Some(estimate_content_pagination(&content_json, spec.as_ref(), &default_pagination_formatting()));
+    println!("[RUST] Pre-render check: ready={} ({} required sections missing)", check.ready, check.missing_required.len());
+    Ok(check)
+}
+
+/// Check a session against the quality gate without exporting (empty list = ready)
+#[command]
+pub async fn check_quality_gate(session_id: String, spec: Option<Value>) -> Result<Vec<UnmetCriterion>, String> {
+    let settings = load_quality_gate_settings()?;
+    let content = load_session_structured_content(&session_id)?;
+    Ok(evaluate_quality_gate(&content, spec.as_ref(), template_approval_problem()?, &settings))
+}
+
+/// Get the quality gate settings
+#[command]
+pub async fn get_quality_gate_settings() -> Result<QualityGateSettings, String> {
+    load_quality_gate_settings()
+}
+
+/// Save the quality gate settings
+#[command]
+pub async fn save_quality_gate_settings(settings: QualityGateSettings) -> Result<(), String> {
+    let path = get_quality_gate_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize quality gate settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write quality gate settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::llama_commands::StructuredContent;
+    use crate::commands::session_commands::SessionStatus;
+
+    fn session(slots: Value, unclear_spans: Vec<Value>, spelling_issues: Option<usize>) -> SessionStructuredContent {
+        SessionStructuredContent {
+            session_id: "s1".to_string(),
+            status: SessionStatus::Strukturiert,
+            content: StructuredContent {
+                slots,
+                unclear_spans,
+                missing_slots: Vec::new(),
+                processing_time_ms: 0,
+                tokens_per_sec: None,
+                prompt_debug: None,
+                generation: None,
+                worker_notice: None,
+                section_prompts: Vec::new(),
+            },
+            updated_at: String::new(),
+            spelling_issues,
+        }
+    }
+
+    #[test]
+    fn test_each_unmet_criterion_is_listed() {
+        let spec = serde_json::json!({"skeleton": [
+            {"type": "slot", "slot_id": "anamnese_body", "section_name": "Anamnese", "optional": false},
+            {"type": "slot", "slot_id": "befund_body", "section_name": "Befund", "optional": false},
+            {"type": "slot", "slot_id": "anlagen_body", "section_name": "Anlagen", "optional": true},
+        ]});
+        let settings = QualityGateSettings::default();
+
+        let failing = session(
+            serde_json::json!({"anamnese_body": "Seit 2019 Rückenschmerzen.", "befund_body": ""}),
+            vec![serde_json::json!({"text": "L4?"}), serde_json::json!({"text": "rechts", "resolved": true})],
+            Some(7),
+        );
+        let unmet = evaluate_quality_gate(&failing, Some(&spec), Some("Die Vorlage wurde seit der Freigabe geändert".to_string()), &settings);
+        let criteria: Vec<&str> = unmet.iter().map(|c| c.criterion.as_str()).collect();
+        assert_eq!(criteria, vec!["missing_sections", "spelling_issues", "unclear_spans", "template_approval"]);
+        assert_eq!(unmet[0].message, "Pflichtabschnitte ohne Inhalt: Befund");
+        assert!(unmet[2].message.starts_with("1 "));
+
+        let passing = session(
+            serde_json::json!({"anamnese_body": "Seit 2019 Rückenschmerzen.", "befund_body": ["Keine Auffälligkeiten."]}),
+            vec![serde_json::json!({"text": "rechts", "resolved": true})],
+            None,
+        );
+        assert!(evaluate_quality_gate(&passing, Some(&spec), None, &settings).is_empty());
+    }
+
+    #[test]
+    fn test_prerender_reports_missing_required_section() {
+        let spec = serde_json::json!({"skeleton": [
+            {"type": "slot", "slot_id": "anamnese_body", "section_name": "Anamnese", "optional": false},
+            {"type": "slot", "slot_id": "befund_body", "section_name": "Befund", "optional": false},
+            {"type": "slot", "slot_id": "anlagen_body", "section_name": "Anlagen", "optional": true},
+        ]});
+        let content = serde_json::json!({
+            "slots": {
+                "anamnese_body": "Seit 2019 bestehen Rückenschmerzen.\n\nSeit 2019 bestehen Rückenschmerzen.",
+                "befund_body": "",
+                "anlagen_body": [],
+            },
+            "unclear_spans": [{"text": "L4?"}],
+            "missing_slots": [],
+        });
+
+        let check = evaluate_prerender(&content, Some(&spec));
+        assert!(!check.ready);
+        assert_eq!(check.missing_required, vec!["Befund"]);
+        assert_eq!(check.required_coverage, 0.5);
+        assert_eq!(check.empty_slots, vec!["anlagen_body", "befund_body"]);
+        assert_eq!(check.unclear_count, 1);
+        assert_eq!(check.duplicate_paragraphs[0].occurrences, 2);
+
+        let complete = serde_json::json!({"slots": {"anamnese_body": "Seit 2019 Rückenschmerzen.", "befund_body": "Unauffällig."}});
+        let check = evaluate_prerender(&complete, Some(&spec));
+        assert!(check.ready && check.required_coverage == 1.0);
+    }
+}
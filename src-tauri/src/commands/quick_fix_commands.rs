@@ -0,0 +1,178 @@
+// Quick fix commands - Deterministic corrections of dictated text without the LLM worker
+// (spacing, quotation marks, fillers, stutters, capitalization); also a pre-pass for the grammar model
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::text_segmentation::is_abbreviation;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuickFixResult {
+    pub text: String,
+    pub applied_rules: Vec<String>,  // Rules that changed the text, in application order
+}
+
+/// Fillers of dictation, with a following comma if any
+static FILLER_WORDS: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?i)\b(?:ähm|äh|öhm|öh|hm+|mhm)\b,?[ \t]*").unwrap());
+static SPACE_BEFORE_PUNCTUATION: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ \t]+([,.;:!?])").unwrap());
+/// "Befund,unauffällig" but not "1,5"
+static MISSING_SPACE_AFTER_COMMA: Lazy<Regex> = Lazy::new(|| Regex::new(r"([,;])(\p{L})").unwrap());
+/// "Schmerzen.Der Patient" but not "z.B." or "3.5" (abbreviations are skipped when replacing)
+static MISSING_SPACE_AFTER_PERIOD: Lazy<Regex> = Lazy::new(|| Regex::new(r"(\p{L}{3,})([.!?])(\p{L}{2,})").unwrap());
+static MULTIPLE_SPACES: Lazy<Regex> = Lazy::new(|| Regex::new(r"[ \t]{2,}").unwrap());
+
+fn replace_all(regex: &Regex, text: &str, replacement: &str) -> String {
+    regex.replace_all(text, replacement).into_owned()
+}
+
+fn remove_fillers(text: &str) -> String {
+    let removed = replace_all(&FILLER_WORDS, text, "");
+    // A filler at the sentence start leaves the next word lower case; capitalization fixes that later
+    removed.lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+}
+
+/// "der der" -> "der", "Pat- Patient" -> "Patient"; only within a line, not across punctuation
+fn remove_repeats(text: &str) -> String {
+    text.split('\n').map(|line| {
+        let words: Vec<&str> = line.split(' ').collect();
+        let mut kept: Vec<&str> = Vec::with_capacity(words.len());
+        for word in words {
+            if let Some(previous) = kept.last() {
+                let duplicate = !word.is_empty()
+                    && word.chars().any(|c| c.is_alphabetic())
+                    && previous.to_lowercase() == word.to_lowercase();
+                let stutter = previous.len() > 1
+                    && previous.ends_with('-')
+                    && word.to_lowercase().starts_with(&previous.trim_end_matches('-').to_lowercase());
+                if duplicate {
+                    continue;
+                }
+                if stutter {
+                    kept.pop();
+                }
+            }
+            kept.push(word);
+        }
+        kept.join(" ")
+    }).collect::<Vec<_>>().join("\n")
+}
+
+fn fix_punctuation_spacing(text: &str) -> String {
+    let text = replace_all(&SPACE_BEFORE_PUNCTUATION, text, "$1");
+    let text = replace_all(&MISSING_SPACE_AFTER_COMMA, &text, "$1 $2");
+    MISSING_SPACE_AFTER_PERIOD.replace_all(&text, |caps: &regex::Captures| {
+        if is_abbreviation(&format!("{}{}", &caps[1], &caps[2])) {
+            caps[0].to_string()
+        } else {
+            format!("{}{} {}", &caps[1], &caps[2], &caps[3])
+        }
+    }).into_owned()
+}
+
+fn collapse_spaces(text: &str) -> String {
+    replace_all(&MULTIPLE_SPACES, text, " ")
+}
+
+/// Straight double quotes in pairs become „…“; lines with an odd count are left alone
+fn german_quotes(text: &str) -> String {
+    text.split('\n').map(|line| {
+        if line.matches('"').count() % 2 == 1 {
+            return line.to_string();
+        }
+        let mut opening = true;
+        line.chars().map(|c| {
+            if c != '"' {
+                return c;
+            }
+            let quote = if opening { '„' } else { '“' };
+            opening = !opening;
+            quote
+        }).collect()
+    }).collect::<Vec<_>>().join("\n")
+}
+
+/// Upper case at the text start and after ".", "!" or "?" unless the period belongs to an
+/// abbreviation or an ordinal number ("3. lendenwirbel" stays)
+fn capitalize_sentences(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut capitalize_next = true;
+    let mut current_word = String::new();
+    for c in text.chars() {
+        if capitalize_next && c.is_alphabetic() {
+            result.extend(c.to_uppercase());
+            capitalize_next = false;
+        } else {
+            result.push(c);
+            if capitalize_next && !c.is_whitespace() {
+                capitalize_next = false;
+            }
+        }
+
+        if c.is_whitespace() {
+            let ends_sentence = current_word.ends_with(['!', '?'])
+                || (current_word.ends_with('.')
+                    && !is_abbreviation(&current_word)
+                    && !current_word.trim_end_matches('.').chars().all(|c| c.is_ascii_digit()));
+            if ends_sentence || (c == '\n' && current_word.is_empty()) {
+                capitalize_next = true;
+            }
+            current_word.clear();
+        } else {
+            current_word.push(c);
+        }
+    }
+    result
+}
+
+type QuickFixRule = (&'static str, fn(&str) -> String);
+
+/// Apply all rules in order; a rule is reported only if it changed the text
+pub fn quick_fix(text: &str) -> QuickFixResult {
+    let rules: [QuickFixRule; 6] = [
+        ("filler_words", remove_fillers),
+        ("punctuation_spacing", fix_punctuation_spacing),  // Before repeats: "Meier.Pat- Patient"
+        ("repeated_words", remove_repeats),
+        ("double_spaces", collapse_spaces),
+        ("german_quotes", german_quotes),
+        ("capitalization", capitalize_sentences),
+    ];
+
+    let mut text = text.to_string();
+    let mut applied_rules = Vec::new();
+    for (name, rule) in rules {
+        let fixed = rule(&text);
+        if fixed != text {
+            applied_rules.push(name.to_string());
+            text = fixed;
+        }
+    }
+    QuickFixResult { text, applied_rules }
+}
+
+/// Deterministic quick fix of a short text, no LLM involved
+#[command]
+pub async fn quick_fix_text(text: String) -> Result<QuickFixResult, String> {
+    let result = quick_fix(&text);
+    println!("Quick fix applied: {:?}", result.applied_rules);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quick_fix_rules() {
+        let result = quick_fix("ähm der der Patient berichtet ,dass er \"keine\" Schmerzen hat.er  kommt am 3. april zu Dr. Meier.Pat- Patient ist wach");
+        assert_eq!(result.text, "Der Patient berichtet, dass er „keine“ Schmerzen hat. Er kommt am 3. april zu Dr. Meier. Patient ist wach");
+        assert_eq!(result.applied_rules, vec![
+            "filler_words", "punctuation_spacing", "repeated_words", "double_spaces", "german_quotes", "capitalization",
+        ]);
+
+        // Decimal commas, abbreviations and unbalanced quotes stay untouched
+        let unchanged = quick_fix("Der Wert lag bei 1,5 mg, z.B. morgens. Er sagte \"nein");
+        assert_eq!(unchanged.text, "Der Wert lag bei 1,5 mg, z.B. morgens. Er sagte \"nein");
+        assert!(unchanged.applied_rules.is_empty());
+    }
+}
@@ -0,0 +1,240 @@
+// Section history commands - Undo/redo of applied corrections and manual saves per session section
+// under user-data/sessions/<session_id>/section_history/<section>.json
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::fs;
+
+use crate::commands::session_commands::{check_section_name, get_session_dir};
+use crate::commands::text_commands::{word_diff, DiffOp};
+
+/// One stored wording of a section
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SectionRevision {
+    pub revision: u64,
+    pub text: String,
+    pub source: String,  // "grammar_correction", "manual" or "original"
+    pub timestamp: String,
+}
+
+/// Revision list of a section; `current` points at the active revision (later ones can be redone)
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct SectionHistory {
+    pub revisions: Vec<SectionRevision>,
+    pub current: usize,
+}
+
+/// A revision as shown in the history, with the changes against its predecessor
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SectionRevisionEntry {
+    pub revision: SectionRevision,
+    pub is_current: bool,
+    pub diff_to_previous: Vec<DiffOp>,
+}
+
+/// Result of undo/redo: the now active text and what changed compared to the text before
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SectionRevisionChange {
+    pub revision: u64,
+    pub text: String,
+    pub diff: Vec<DiffOp>,
+    pub can_undo: bool,
+    pub can_redo: bool,
+}
+
+const MAX_SECTION_REVISIONS: usize = 20;
+
+impl SectionHistory {
+    fn current_revision(&self) -> Option<&SectionRevision> {
+        self.revisions.get(self.current)
+    }
+
+    /// Add a revision after the current one (dropping the redo branch); identical text is ignored.
+    /// The oldest revisions are dropped beyond MAX_SECTION_REVISIONS.
+    pub fn push(&mut self, text: &str, source: &str) -> bool {
+        if self.current_revision().is_some_and(|r| r.text == text) {
+            return false;
+        }
+
+        let next_revision = self.revisions.last().map(|r| r.revision + 1).unwrap_or(1);
+        self.revisions.truncate(self.current + 1);
+        self.revisions.push(SectionRevision {
+            revision: next_revision,
+            text: text.to_string(),
+            source: source.to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+        if self.revisions.len() > MAX_SECTION_REVISIONS {
+            let excess = self.revisions.len() - MAX_SECTION_REVISIONS;
+            self.revisions.drain(..excess);
+        }
+        self.current = self.revisions.len() - 1;
+        true
+    }
+
+    /// Move the active revision one step back (undo) or forward (redo)
+    fn step(&mut self, backwards: bool) -> Result<SectionRevisionChange, String> {
+        let before = self.current_revision()
+            .ok_or("Keine Fassungen für diesen Abschnitt gespeichert")?
+            .text.clone();
+        if backwards {
+            if self.current == 0 {
+                return Err("Keine ältere Fassung vorhanden".to_string());
+            }
+            self.current -= 1;
+        } else {
+            if self.current + 1 >= self.revisions.len() {
+                return Err("Keine neuere Fassung vorhanden".to_string());
+            }
+            self.current += 1;
+        }
+
+        let active = &self.revisions[self.current];
+        Ok(SectionRevisionChange {
+            revision: active.revision,
+            text: active.text.clone(),
+            diff: word_diff(&before, &active.text),
+            can_undo: self.current > 0,
+            can_redo: self.current + 1 < self.revisions.len(),
+        })
+    }
+
+    pub fn undo(&mut self) -> Result<SectionRevisionChange, String> {
+        self.step(true)
+    }
+
+    pub fn redo(&mut self) -> Result<SectionRevisionChange, String> {
+        self.step(false)
+    }
+
+    pub fn entries(&self) -> Vec<SectionRevisionEntry> {
+        self.revisions.iter().enumerate()
+            .map(|(i, revision)| SectionRevisionEntry {
+                revision: revision.clone(),
+                is_current: i == self.current,
+                diff_to_previous: match i {
+                    0 => Vec::new(),
+                    _ => word_diff(&self.revisions[i - 1].text, &revision.text),
+                },
+            })
+            .collect()
+    }
+}
+
+fn get_section_history_path(session_id: &str, section: &str) -> Result<PathBuf, String> {
+    check_section_name(section)?;
+    Ok(get_session_dir(session_id)?.join("section_history").join(format!("{}.json", section)))
+}
+
+fn load_section_history(session_id: &str, section: &str) -> Result<SectionHistory, String> {
+    let path = get_section_history_path(session_id, section)?;
+    if !path.exists() {
+        return Ok(SectionHistory::default());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read section history: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse section history: {}", e))
+}
+
+fn write_section_history(session_id: &str, section: &str, history: &SectionHistory) -> Result<(), String> {
+    let path = get_section_history_path(session_id, section)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create section history directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(history)
+        .map_err(|e| format!("Failed to serialize section history: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write section history: {}", e))
+}
+
+/// Record a new wording of a section, e.g. when a grammar correction is applied or the
+/// section is saved manually. Pass `previous_text` to keep the wording before the first correction.
+#[command]
+pub async fn save_section_revision(
+    session_id: String,
+    section: String,
+    text: String,
+    source: Option<String>,
+    previous_text: Option<String>,
+) -> Result<Vec<SectionRevisionEntry>, String> {
+    let mut history = load_section_history(&session_id, &section)?;
+    if history.revisions.is_empty() {
+        if let Some(previous) = previous_text {
+            history.push(&previous, "original");
+        }
+    }
+
+    if history.push(&text, source.as_deref().unwrap_or("manual")) {
+        write_section_history(&session_id, &section, &history)?;
+        println!("Section revision saved: {}/{}", session_id, section);
+    }
+    Ok(history.entries())
+}
+
+/// Go back to the previous wording of a section
+#[command]
+pub async fn undo_section_revision(session_id: String, section: String) -> Result<SectionRevisionChange, String> {
+    let mut history = load_section_history(&session_id, &section)?;
+    let change = history.undo()?;
+    write_section_history(&session_id, &section, &history)?;
+    Ok(change)
+}
+
+/// Re-apply the wording that was undone last
+#[command]
+pub async fn redo_section_revision(session_id: String, section: String) -> Result<SectionRevisionChange, String> {
+    let mut history = load_section_history(&session_id, &section)?;
+    let change = history.redo()?;
+    write_section_history(&session_id, &section, &history)?;
+    Ok(change)
+}
+
+/// All stored wordings of a section with the changes between consecutive revisions
+#[command]
+pub async fn get_section_history(session_id: String, section: String) -> Result<Vec<SectionRevisionEntry>, String> {
+    Ok(load_section_history(&session_id, &section)?.entries())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_undo_redo_and_branching_history() {
+        let mut history = SectionHistory::default();
+        assert!(history.push("Der Patient klagt uber Schmerzen", "original"));
+        assert!(history.push("Der Patient klagt über Schmerzen.", "grammar_correction"));
+        assert!(!history.push("Der Patient klagt über Schmerzen.", "manual"));
+
+        let undone = history.undo().unwrap();
+        assert_eq!(undone.text, "Der Patient klagt uber Schmerzen");
+        assert_eq!(undone.diff, vec![
+            DiffOp { kind: "equal".to_string(), text: "Der Patient klagt".to_string() },
+            DiffOp { kind: "delete".to_string(), text: "über Schmerzen.".to_string() },
+            DiffOp { kind: "insert".to_string(), text: "uber Schmerzen".to_string() },
+        ]);
+        assert!(!undone.can_undo && undone.can_redo);
+        assert!(history.undo().is_err());
+
+        let redone = history.redo().unwrap();
+        assert_eq!(redone.revision, 2);
+        assert!(history.redo().is_err());
+
+        // A new wording after an undo replaces the redo branch
+        history.undo().unwrap();
+        history.push("Der Patient klagt über Rückenschmerzen.", "manual");
+        let revisions: Vec<u64> = history.revisions.iter().map(|r| r.revision).collect();
+        assert_eq!(revisions, vec![1, 3]);
+
+        for i in 0..30 {
+            history.push(&format!("Fassung {}", i), "manual");
+        }
+        assert_eq!(history.revisions.len(), MAX_SECTION_REVISIONS);
+        assert_eq!(history.current, MAX_SECTION_REVISIONS - 1);
+        assert_eq!(history.entries()[1].diff_to_previous.len(), 3);
+    }
+}
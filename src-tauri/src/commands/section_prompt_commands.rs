@@ -0,0 +1,199 @@
+// Section prompt commands - User instructions per profile section for the structurer
+// (e.g. "BEURTEILUNG strikt im Wortlaut des Gutachters belassen"); sections without one keep
+// the built-in behaviour
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::fs;
+
+use crate::commands::style_profile_commands::{read_style_profile, SectionInfo};
+use crate::heading_normalizer::canonical_heading;
+
+/// Per template; keeps a handful of instructions within the structurer's context budget
+pub const MAX_SECTION_PROMPT_CHARS: usize = 400;
+/// All custom templates together
+pub const MAX_TOTAL_SECTION_PROMPT_CHARS: usize = 1600;
+/// Placeholders a template may use
+pub const SECTION_PROMPT_PLACEHOLDERS: &[&str] = &["section", "display_name"];
+
+static PLACEHOLDER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{([^{}]*)\}").unwrap());
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SectionPrompt {
+    pub section: String,       // Normalized section name from the style profile
+    pub display_name: String,
+    pub template: Option<String>,  // None: built-in behaviour
+}
+
+fn get_section_prompts_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("section_prompts.json"))
+}
+
+fn load_section_prompts() -> BTreeMap<String, String> {
+    get_section_prompts_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_section_prompts(prompts: &BTreeMap<String, String>) -> Result<(), String> {
+    let path = get_section_prompts_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(prompts)
+        .map_err(|e| format!("Failed to serialize section prompts: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write section prompts: {}", e))
+}
+
+/// Check placeholders, braces and length of a template
+pub fn validate_section_prompt(template: &str) -> Result<(), String> {
+    let length = template.chars().count();
+    if template.trim().is_empty() {
+        return Err("Die Anweisung ist leer".to_string());
+    }
+    if length > MAX_SECTION_PROMPT_CHARS {
+        return Err(format!("Die Anweisung ist zu lang ({} Zeichen, höchstens {})", length, MAX_SECTION_PROMPT_CHARS));
+    }
+    for captures in PLACEHOLDER.captures_iter(template) {
+        if !SECTION_PROMPT_PLACEHOLDERS.contains(&&captures[1]) {
+            return Err(format!(
+                "Unbekannter Platzhalter {{{}}} (erlaubt: {})",
+                &captures[1],
+                SECTION_PROMPT_PLACEHOLDERS.iter().map(|p| format!("{{{}}}", p)).collect::<Vec<_>>().join(", ")
+            ));
+        }
+    }
+    if PLACEHOLDER.replace_all(template, "").contains(['{', '}']) {
+        return Err("Geschweifte Klammern sind nur für Platzhalter erlaubt".to_string());
+    }
+    Ok(())
+}
+
+/// The profile section a name refers to (normalized or display name, any spelling)
+fn find_section<'a>(sections: &'a [SectionInfo], name: &str) -> Option<&'a SectionInfo> {
+    let wanted = canonical_heading(name);
+    sections.iter().find(|s| canonical_heading(&s.normalized_name) == wanted || canonical_heading(&s.display_name) == wanted)
+}
+
+fn render_section_prompt(template: &str, section: &str, display_name: &str) -> String {
+    template.replace("{section}", section).replace("{display_name}", display_name)
+}
+
+/// Rendered custom instructions keyed by section, for the structuring request
+pub fn section_prompts_for_request(prompts: &BTreeMap<String, String>, sections: &[SectionInfo]) -> BTreeMap<String, String> {
+    prompts.iter()
+        .map(|(section, template)| {
+            let display_name = find_section(sections, section).map(|s| s.display_name.as_str()).unwrap_or(section);
+            (section.clone(), render_section_prompt(template, section, display_name))
+        })
+        .collect()
+}
+
+/// Custom instructions currently in effect, rendered
+pub(crate) fn active_section_prompts() -> BTreeMap<String, String> {
+    let sections = read_style_profile().map(|profile| profile.sections).unwrap_or_default();
+    section_prompts_for_request(&load_section_prompts(), &sections)
+}
+
+fn set_prompt_in(
+    prompts: &mut BTreeMap<String, String>,
+    sections: &[SectionInfo],
+    section: &str,
+    template: &str,
+) -> Result<String, String> {
+    let key = find_section(sections, section)
+        .map(|s| s.normalized_name.clone())
+        .ok_or_else(|| format!("Abschnitt \"{}\" ist nicht im Stilprofil", section))?;
+    validate_section_prompt(template)?;
+
+    let total: usize = prompts.iter()
+        .filter(|(name, _)| **name != key)
+        .map(|(_, text)| text.chars().count())
+        .sum::<usize>() + template.chars().count();
+    if total > MAX_TOTAL_SECTION_PROMPT_CHARS {
+        return Err(format!(
+            "Alle Anweisungen zusammen sind zu lang ({} Zeichen, höchstens {})",
+            total, MAX_TOTAL_SECTION_PROMPT_CHARS
+        ));
+    }
+    prompts.insert(key.clone(), template.trim().to_string());
+    Ok(key)
+}
+
+/// Every profile section with its custom template, if any
+#[command]
+pub async fn get_section_prompts() -> Result<Vec<SectionPrompt>, String> {
+    let profile = read_style_profile()?;
+    let prompts = load_section_prompts();
+    Ok(profile.sections.iter()
+        .map(|s| SectionPrompt {
+            section: s.normalized_name.clone(),
+            display_name: s.display_name.clone(),
+            template: prompts.get(&s.normalized_name).cloned(),
+        })
+        .collect())
+}
+
+/// Set the structurer instruction for a profile section
+#[command]
+pub async fn set_section_prompt(section: String, template: String) -> Result<(), String> {
+    let profile = read_style_profile()?;
+    let mut prompts = load_section_prompts();
+    let key = set_prompt_in(&mut prompts, &profile.sections, &section, &template)?;
+    write_section_prompts(&prompts)?;
+    println!("Section prompt set for {}", key);
+    Ok(())
+}
+
+/// Back to the built-in behaviour for a section
+#[command]
+pub async fn reset_section_prompt(section: String) -> Result<(), String> {
+    let sections = read_style_profile().map(|profile| profile.sections).unwrap_or_default();
+    let mut prompts = load_section_prompts();
+    let key = find_section(&sections, &section).map(|s| s.normalized_name.clone()).unwrap_or(section);
+    if prompts.remove(&key).is_some() {
+        write_section_prompts(&prompts)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn section(name: &str, display_name: &str) -> SectionInfo {
+        SectionInfo {
+            normalized_name: name.to_string(),
+            display_name: display_name.to_string(),
+            is_required: true,
+            occurrence_count: 3,
+            occurrence_percentage: 100.0,
+            order: 1,
+        }
+    }
+
+    #[test]
+    fn test_section_prompts_are_validated_capped_and_rendered() {
+        let sections = vec![section("BEURTEILUNG", "Beurteilung"), section("BEFUND", "Befund")];
+        let mut prompts = BTreeMap::new();
+
+        let key = set_prompt_in(&mut prompts, &sections, "beurteilung", "{display_name} strikt im Wortlaut des Gutachters belassen").unwrap();
+        assert_eq!(key, "BEURTEILUNG");
+        assert!(set_prompt_in(&mut prompts, &sections, "Anamnese", "Kurz halten").unwrap_err().contains("nicht im Stilprofil"));
+        assert!(set_prompt_in(&mut prompts, &sections, "Befund", "Nur {patient} nennen").unwrap_err().contains("Unbekannter Platzhalter {patient}"));
+        assert!(set_prompt_in(&mut prompts, &sections, "Befund", "Werte {in Klammern").unwrap_err().contains("Geschweifte Klammern"));
+        assert!(set_prompt_in(&mut prompts, &sections, "Befund", &"x".repeat(MAX_SECTION_PROMPT_CHARS + 1)).unwrap_err().contains("zu lang"));
+
+        let rendered = section_prompts_for_request(&prompts, &sections);
+        assert_eq!(rendered.get("BEURTEILUNG").map(String::as_str), Some("Beurteilung strikt im Wortlaut des Gutachters belassen"));
+        assert_eq!(rendered.len(), 1);
+    }
+}
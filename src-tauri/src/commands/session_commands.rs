@@ -0,0 +1,702 @@
+// Session commands - Persists the working state of one Gutachten (transcript, revisions)
+// under user-data/sessions/<session_id>/
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::fs;
+
+use crate::commands::llama_commands::StructuredContent;
+use crate::commands::audio_commands::{
+    cut_audio_range_with_ffmpeg, filter_hallucinated_segments, perform_whisper_transcription,
+    probe_audio_duration, HallucinationFilterOptions, TranscriptionResult, TranscriptionSegment,
+};
+use crate::commands::format_commands::sha256_file;
+use crate::commands::temp_file_commands::processing_temp_dir;
+
+/// A stored transcript together with the audio it was produced from
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionTranscription {
+    pub session_id: String,
+    pub audio_path: String,
+    pub result: TranscriptionResult,
+    pub revisions: Vec<TranscriptRevision>,
+    pub updated_at: String,
+}
+
+/// History entry for a re-transcribed audio range
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscriptRevision {
+    pub revision: usize,
+    pub timestamp: String,
+    pub start_s: f32,
+    pub end_s: f32,
+    pub accepted: bool,
+    pub old_confidence: f32,
+    pub new_confidence: f32,
+    pub replaced_segments: Vec<TranscriptionSegment>,
+    pub inserted_segments: Vec<TranscriptionSegment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetranscribeOptions {
+    pub prefer_higher_confidence: Option<bool>,  // Default true: keep old text if new is less confident
+    pub filter_hallucinations: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetranscribeResult {
+    pub accepted: bool,
+    pub start_s: f32,  // Range actually re-transcribed (snapped to segment edges)
+    pub end_s: f32,
+    pub replaced_count: usize,
+    pub inserted_count: usize,
+    pub old_confidence: f32,
+    pub new_confidence: f32,
+    pub transcription: TranscriptionResult,
+}
+
+/// The session's own copy of the source audio (user-data/sessions/<id>/audio/), so moving or
+/// deleting the original doesn't break a running job; the hash proves which recording was used
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionAudioInfo {
+    pub original_path: String,
+    pub session_path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+    pub duration_s: Option<f32>,
+    pub hard_linked: bool,     // Same file on disk instead of a copy (source on the same volume)
+    pub imported_at: String,
+    #[serde(default)]
+    pub copy_matches: bool,    // Session copy still has the recorded hash (checked when queried)
+}
+
+/// Processing stage of a session; later stages compare greater
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    Neu,
+    Transkribiert,
+    Strukturiert,
+    Exportiert,
+}
+
+/// Structured (slot-filled) content of a session, ready for rendering
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionStructuredContent {
+    pub session_id: String,
+    pub status: SessionStatus,
+    pub content: StructuredContent,
+    pub updated_at: String,
+    #[serde(default)]
+    pub spelling_issues: Option<usize>,  // Issues found by the last spell check; None = not checked
+}
+
+/// Get the root directory for all sessions
+pub(crate) fn get_sessions_dir() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("sessions"))
+}
+
+/// Get the directory of a single session (session IDs are restricted to safe characters)
+pub(crate) fn get_session_dir(session_id: &str) -> Result<PathBuf, String> {
+    if session_id.is_empty()
+        || !session_id.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("Invalid session ID: {}", session_id));
+    }
+    Ok(get_sessions_dir()?.join(session_id))
+}
+
+/// Section names are used as file names; restrict them like session IDs
+pub(crate) fn check_section_name(section: &str) -> Result<(), String> {
+    if section.is_empty() || !section.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_') {
+        return Err(format!("Invalid section name: {}", section));
+    }
+    Ok(())
+}
+
+fn get_session_transcription_path(session_id: &str) -> Result<PathBuf, String> {
+    Ok(get_session_dir(session_id)?.join("transcription.json"))
+}
+
+pub(crate) fn load_session_transcription(session_id: &str) -> Result<SessionTranscription, String> {
+    let path = get_session_transcription_path(session_id)?;
+    if !path.exists() {
+        return Err(format!("No transcription stored for session {}", session_id));
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read session transcription: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse session transcription: {}", e))
+}
+
+pub(crate) fn write_session_transcription(session: &SessionTranscription) -> Result<(), String> {
+    let dir = get_session_dir(&session.session_id)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create session directory: {}", e))?;
+
+    let json = serde_json::to_string_pretty(session)
+        .map_err(|e| format!("Failed to serialize session transcription: {}", e))?;
+    fs::write(dir.join("transcription.json"), json)
+        .map_err(|e| format!("Failed to write session transcription: {}", e))
+}
+
+fn read_session_audio_info(session_dir: &Path) -> Option<SessionAudioInfo> {
+    let content = fs::read_to_string(session_dir.join("audio.json")).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Hard-link (or copy) `source` into `session_dir/audio/` and record original path, hash and
+/// duration in `audio.json`. Importing the same recording again reuses the existing copy.
+pub(crate) fn import_audio_into_session(session_dir: &Path, source: &Path) -> Result<SessionAudioInfo, String> {
+    if !source.is_file() {
+        return Err(format!("Audiodatei nicht gefunden: {}", source.display()));
+    }
+    let source_hash = sha256_file(source)?;
+    if let Some(existing) = read_session_audio_info(session_dir) {
+        if existing.sha256 == source_hash && Path::new(&existing.session_path).is_file() {
+            return Ok(SessionAudioInfo { copy_matches: true, ..existing });
+        }
+    }
+
+    let audio_dir = session_dir.join("audio");
+    fs::create_dir_all(&audio_dir)
+        .map_err(|e| format!("Failed to create session audio directory: {}", e))?;
+    let file_name = source.file_name().ok_or_else(|| format!("Invalid audio path: {}", source.display()))?;
+    let target = audio_dir.join(file_name);
+    if target.exists() {
+        fs::remove_file(&target)
+            .map_err(|e| format!("Failed to replace session audio: {}", e))?;
+    }
+
+    let hard_linked = fs::hard_link(source, &target).is_ok();
+    if !hard_linked {
+        fs::copy(source, &target)
+            .map_err(|e| format!("Audiodatei konnte nicht in die Sitzung kopiert werden: {}", e))?;
+    }
+    if sha256_file(&target)? != source_hash {
+        let _ = fs::remove_file(&target);
+        return Err("Kopie der Audiodatei stimmt nicht mit dem Original überein".to_string());
+    }
+
+    let info = SessionAudioInfo {
+        original_path: source.to_string_lossy().to_string(),
+        session_path: target.to_string_lossy().to_string(),
+        sha256: source_hash,
+        size_bytes: fs::metadata(&target).map(|m| m.len()).unwrap_or(0),
+        duration_s: probe_audio_duration(&target).ok(),
+        hard_linked,
+        imported_at: chrono::Utc::now().to_rfc3339(),
+        copy_matches: true,
+    };
+    let json = serde_json::to_string_pretty(&info)
+        .map_err(|e| format!("Failed to serialize session audio info: {}", e))?;
+    fs::write(session_dir.join("audio.json"), json)
+        .map_err(|e| format!("Failed to write session audio info: {}", e))?;
+
+    println!("Session audio {} ({}): {}", if hard_linked { "linked" } else { "copied" }, info.sha256, info.session_path);
+    Ok(info)
+}
+
+/// Path a transcription job for the session should read: the session copy of `source`
+pub(crate) fn lock_session_audio(session_id: &str, source: &Path) -> Result<PathBuf, String> {
+    let info = import_audio_into_session(&get_session_dir(session_id)?, source)?;
+    Ok(PathBuf::from(info.session_path))
+}
+
+/// Session copy of the source audio, if one was imported
+fn session_audio_path(session_id: &str) -> Option<PathBuf> {
+    let info = read_session_audio_info(&get_session_dir(session_id).ok()?)?;
+    Some(PathBuf::from(info.session_path)).filter(|path| path.is_file())
+}
+
+fn get_session_structured_path(session_id: &str) -> Result<PathBuf, String> {
+    Ok(get_session_dir(session_id)?.join("structured.json"))
+}
+
+pub(crate) fn load_session_structured_content(session_id: &str) -> Result<SessionStructuredContent, String> {
+    let path = get_session_structured_path(session_id)?;
+    if !path.exists() {
+        return Err(format!("No structured content stored for session {}", session_id));
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read structured content: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse structured content: {}", e))
+}
+
+pub(crate) fn write_session_structured_content(structured: &SessionStructuredContent) -> Result<(), String> {
+    let dir = get_session_dir(&structured.session_id)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create session directory: {}", e))?;
+
+    let json = serde_json::to_string_pretty(structured)
+        .map_err(|e| format!("Failed to serialize structured content: {}", e))?;
+    fs::write(dir.join("structured.json"), json)
+        .map_err(|e| format!("Failed to write structured content: {}", e))
+}
+
+/// Current stage of a session, derived from what has been stored for it
+pub(crate) fn session_status(session_id: &str) -> Result<SessionStatus, String> {
+    if let Ok(structured) = load_session_structured_content(session_id) {
+        return Ok(structured.status);
+    }
+    if get_session_transcription_path(session_id)?.exists() {
+        return Ok(SessionStatus::Transkribiert);
+    }
+    Ok(SessionStatus::Neu)
+}
+
+/// Mark a session as exported (sessions without structured content are left alone)
+pub(crate) fn mark_session_exported(session_id: &str) -> Result<(), String> {
+    let mut structured = match load_session_structured_content(session_id) {
+        Ok(structured) => structured,
+        Err(_) => return Ok(()),
+    };
+    structured.status = SessionStatus::Exportiert;
+    structured.updated_at = chrono::Utc::now().to_rfc3339();
+    write_session_structured_content(&structured)
+}
+
+/// Case metadata of a session (UNTERSUCHUNGSDATUM, AKTENZEICHEN, GUTACHTER, ...), keyed by
+/// placeholder name; dates are stored as YYYY-MM-DD
+pub(crate) fn load_session_metadata(session_id: &str) -> Result<BTreeMap<String, String>, String> {
+    let path = get_session_dir(session_id)?.join("metadata.json");
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read session metadata: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse session metadata: {}", e))
+}
+
+/// Store the case metadata of a session (keys are normalized to upper case)
+#[command]
+pub async fn save_session_metadata(
+    session_id: String,
+    fields: BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, String> {
+    let fields: BTreeMap<String, String> = fields.into_iter()
+        .map(|(key, value)| (key.trim().to_uppercase(), value.trim().to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .collect();
+
+    let dir = get_session_dir(&session_id)?;
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create session directory: {}", e))?;
+    let json = serde_json::to_string_pretty(&fields)
+        .map_err(|e| format!("Failed to serialize session metadata: {}", e))?;
+    fs::write(dir.join("metadata.json"), json)
+        .map_err(|e| format!("Failed to write session metadata: {}", e))?;
+
+    Ok(fields)
+}
+
+/// Get the case metadata of a session
+#[command]
+pub async fn get_session_metadata(session_id: String) -> Result<BTreeMap<String, String>, String> {
+    load_session_metadata(&session_id)
+}
+
+/// Store the structured content of a session (marks it as "strukturiert")
+#[command]
+pub async fn save_session_structured_content(
+    session_id: String,
+    content: StructuredContent,
+) -> Result<SessionStatus, String> {
+    let structured = SessionStructuredContent {
+        session_id: session_id.clone(),
+        status: SessionStatus::Strukturiert,
+        content,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+        spelling_issues: None,
+    };
+    write_session_structured_content(&structured)?;
+
+    println!("Session structured content saved: {}", session_id);
+    Ok(structured.status)
+}
+
+/// Store the issue count of the spell check run on a session's structured content
+#[command]
+pub async fn record_session_spelling_issues(session_id: String, count: usize) -> Result<(), String> {
+    let mut structured = load_session_structured_content(&session_id)?;
+    structured.spelling_issues = Some(count);
+    structured.updated_at = chrono::Utc::now().to_rfc3339();
+    write_session_structured_content(&structured)
+}
+
+/// Mark an unclear span as resolved (checked by the user); returns the number still open
+#[command]
+pub async fn resolve_unclear_span(session_id: String, index: usize) -> Result<usize, String> {
+    let mut structured = load_session_structured_content(&session_id)?;
+    let span = structured.content.unclear_spans.get_mut(index)
+        .ok_or_else(|| format!("Unclear span {} not found", index))?;
+    match span.as_object_mut() {
+        Some(object) => { object.insert("resolved".to_string(), serde_json::Value::Bool(true)); }
+        None => *span = serde_json::json!({ "text": span.clone(), "resolved": true }),
+    }
+    structured.updated_at = chrono::Utc::now().to_rfc3339();
+    write_session_structured_content(&structured)?;
+
+    Ok(structured.content.unclear_spans.iter().filter(|s| !is_unclear_span_resolved(s)).count())
+}
+
+pub(crate) fn is_unclear_span_resolved(span: &serde_json::Value) -> bool {
+    span.get("resolved").and_then(|r| r.as_bool()).unwrap_or(false)
+}
+
+/// Zip the whole session directory (transcript, structured content, metadata, section history,
+/// autosaves) into one file for archiving or transfer
+#[command]
+pub async fn export_session_bundle(session_id: String, output_path: String) -> Result<String, String> {
+    use std::io::Write;
+
+    let session_dir = get_session_dir(&session_id)?;
+    if !session_dir.exists() {
+        return Err(format!("Session not found: {}", session_id));
+    }
+
+    let file = fs::File::create(&output_path)
+        .map_err(|e| format!("Failed to create bundle: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let mut pending = vec![session_dir.clone()];
+    let mut file_count = 0;
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir)
+            .map_err(|e| format!("Failed to read session directory: {}", e))?;
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let name = path.strip_prefix(&session_dir)
+                .map_err(|e| format!("Invalid session file path: {}", e))?
+                .to_string_lossy()
+                .replace('\\', "/");
+            let bytes = fs::read(&path)
+                .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+            zip.start_file(format!("{}/{}", session_id, name), zip::write::FileOptions::default())
+                .map_err(|e| format!("Failed to write bundle entry: {}", e))?;
+            zip.write_all(&bytes)
+                .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+            file_count += 1;
+        }
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish bundle: {}", e))?;
+
+    println!("Session bundle exported: {} ({} files)", output_path, file_count);
+    Ok(output_path)
+}
+
+/// Get the processing stage of a session
+#[command]
+pub async fn get_session_status(session_id: String) -> Result<SessionStatus, String> {
+    session_status(&session_id)
+}
+
+/// Store a transcription result for a session
+#[command]
+pub async fn save_session_transcription(
+    session_id: String,
+    audio_path: String,
+    result: TranscriptionResult,
+) -> Result<String, String> {
+    // Keep revision history if the session already exists
+    let revisions = load_session_transcription(&session_id)
+        .map(|existing| existing.revisions)
+        .unwrap_or_default();
+
+    let session = SessionTranscription {
+        session_id: session_id.clone(),
+        audio_path,
+        result,
+        revisions,
+        updated_at: chrono::Utc::now().to_rfc3339(),
+    };
+    write_session_transcription(&session)?;
+
+    println!("Session transcription saved: {}", session_id);
+    Ok(session_id)
+}
+
+/// Original path, session copy, hash and duration of the audio the session is based on; the
+/// copy's hash is checked again so a report can be tied to its recording
+#[command]
+pub async fn get_session_audio_info(session_id: String) -> Result<SessionAudioInfo, String> {
+    let session_dir = get_session_dir(&session_id)?;
+    let mut info = read_session_audio_info(&session_dir)
+        .ok_or_else(|| format!("Keine Audiodatei in Sitzung {} gespeichert", session_id))?;
+    let session_path = PathBuf::from(&info.session_path);
+    info.copy_matches = tokio::task::spawn_blocking(move || sha256_file(&session_path))
+        .await.map_err(|e| format!("Hash task failed: {}", e))?
+        .is_ok_and(|hash| hash == info.sha256);
+    Ok(info)
+}
+
+/// Get the stored transcription of a session
+#[command]
+pub async fn get_session_transcription(session_id: String) -> Result<SessionTranscription, String> {
+    load_session_transcription(&session_id)
+}
+
+/// Re-transcribe a time range of the session audio and merge the result into the stored
+/// transcript. The range is widened to the surrounding segment edges so no word is cut,
+/// and by default the new text only replaces the old one if it is more confident.
+#[command]
+pub async fn retranscribe_range(
+    session_id: String,
+    start_s: f32,
+    end_s: f32,
+    options: Option<RetranscribeOptions>,
+) -> Result<RetranscribeResult, String> {
+    if start_s < 0.0 || end_s <= start_s {
+        return Err(format!("Invalid time range: {:.2}s - {:.2}s", start_s, end_s));
+    }
+
+    let mut session = load_session_transcription(&session_id)?;
+    let audio_path = session_audio_path(&session_id).unwrap_or_else(|| PathBuf::from(&session.audio_path));
+    if !audio_path.exists() {
+        return Err(format!("Session audio not found: {}", session.audio_path));
+    }
+
+    let prefer_higher_confidence = options.as_ref()
+        .and_then(|o| o.prefer_higher_confidence)
+        .unwrap_or(true);
+    let filter_hallucinations = options.as_ref()
+        .and_then(|o| o.filter_hallucinations)
+        .unwrap_or(false);
+
+    let (range_start, range_end) = snap_range_to_segments(&session.result.segments, start_s, end_s);
+    println!("[RUST] Re-transcribing session {} range {:.2}s-{:.2}s (requested {:.2}s-{:.2}s)",
+        session_id, range_start, range_end, start_s, end_s);
+
+    // Cut and transcribe just the selected span
+    let clip_path = processing_temp_dir().join(format!(
+        "retranscribe_{}_{}.wav", session_id, chrono::Utc::now().format("%Y%m%d_%H%M%S")
+    ));
+    let clip_path_clone = clip_path.clone();
+    let transcription = tokio::task::spawn_blocking(move || {
+        cut_audio_range_with_ffmpeg(&audio_path, &clip_path_clone, range_start, range_end)?;
+        perform_whisper_transcription(&clip_path_clone)
+    }).await.map_err(|e| format!("Transcription task failed: {}", e))?;
+    let _ = fs::remove_file(&clip_path);
+    let transcription = transcription?;
+
+    let mut clip_result = TranscriptionResult {
+        text: transcription.text,
+        confidence: transcription.confidence,
+        processing_time_ms: 0,
+        language: session.result.language.clone(),
+        segments: transcription.segments,
+        filtered_segments: Vec::new(),
+        partial: transcription.partial,
+        detected_language: transcription.detected_language,
+        language_probabilities: transcription.language_probabilities,
+        warnings: Vec::new(),
+    };
+    if filter_hallucinations {
+        filter_hallucinated_segments(&mut clip_result, &HallucinationFilterOptions::default());
+    }
+
+    let revision = splice_segments(&mut session, clip_result.segments, range_start, range_end, prefer_higher_confidence);
+    if !revision.accepted {
+        println!("[RUST] Keeping existing transcript: new confidence {:.2} < old {:.2}", revision.new_confidence, revision.old_confidence);
+    }
+    session.updated_at = chrono::Utc::now().to_rfc3339();
+    write_session_transcription(&session)?;
+
+    Ok(RetranscribeResult {
+        accepted: revision.accepted,
+        start_s: range_start,
+        end_s: range_end,
+        replaced_count: if revision.accepted { revision.replaced_segments.len() } else { 0 },
+        inserted_count: if revision.accepted { revision.inserted_segments.len() } else { 0 },
+        old_confidence: revision.old_confidence,
+        new_confidence: revision.new_confidence,
+        transcription: session.result,
+    })
+}
+
+/// Merge the segments of a clip cut from [range_start, range_end] into the transcript: the
+/// clip's timestamps are shifted onto the recording, segments inside the range are replaced and
+/// the ones around it kept. With `prefer_higher_confidence` a less confident result is rejected.
+/// Either way the attempt is recorded as a revision, which is returned.
+fn splice_segments(
+    session: &mut SessionTranscription,
+    clip_segments: Vec<TranscriptionSegment>,
+    range_start: f32,
+    range_end: f32,
+    prefer_higher_confidence: bool,
+) -> TranscriptRevision {
+    // Shift clip-relative timestamps onto the original timeline
+    let new_segments: Vec<TranscriptionSegment> = clip_segments.into_iter()
+        .map(|mut segment| {
+            segment.start_time += range_start;
+            segment.end_time = (segment.end_time + range_start).min(range_end);
+            segment
+        })
+        .filter(|segment| segment.start_time < range_end)
+        .collect();
+
+    let replaced: Vec<TranscriptionSegment> = session.result.segments.iter()
+        .filter(|s| is_within_range(s, range_start, range_end))
+        .cloned()
+        .collect();
+
+    let old_confidence = average_confidence(&replaced);
+    let new_confidence = average_confidence(&new_segments);
+    let accepted = !new_segments.is_empty()
+        && (!prefer_higher_confidence || replaced.is_empty() || new_confidence >= old_confidence);
+
+    if accepted {
+        let mut merged: Vec<TranscriptionSegment> = session.result.segments.iter()
+            .filter(|s| !is_within_range(s, range_start, range_end))
+            .cloned()
+            .collect();
+        merged.extend(new_segments.iter().cloned());
+        merged.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap_or(std::cmp::Ordering::Equal));
+
+        session.result.text = merged.iter()
+            .map(|s| s.text.trim())
+            .filter(|t| !t.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        session.result.segments = merged;
+    }
+
+    let revision = TranscriptRevision {
+        revision: session.revisions.len() + 1,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        start_s: range_start,
+        end_s: range_end,
+        accepted,
+        old_confidence,
+        new_confidence,
+        replaced_segments: replaced,
+        inserted_segments: new_segments,
+    };
+    session.revisions.push(revision.clone());
+    revision
+}
+
+/// Widen [start_s, end_s] so it never starts or ends inside a segment
+fn snap_range_to_segments(segments: &[TranscriptionSegment], start_s: f32, end_s: f32) -> (f32, f32) {
+    let mut start = start_s;
+    let mut end = end_s;
+
+    for segment in segments {
+        if segment.start_time < start_s && segment.end_time > start_s {
+            start = start.min(segment.start_time);
+        }
+        if segment.start_time < end_s && segment.end_time > end_s {
+            end = end.max(segment.end_time);
+        }
+    }
+
+    (start, end)
+}
+
+fn is_within_range(segment: &TranscriptionSegment, start: f32, end: f32) -> bool {
+    const EPSILON: f32 = 0.01;
+    segment.start_time >= start - EPSILON && segment.end_time <= end + EPSILON
+}
+
+fn average_confidence(segments: &[TranscriptionSegment]) -> f32 {
+    if segments.is_empty() {
+        return 0.0;
+    }
+    segments.iter().map(|s| s.confidence).sum::<f32>() / segments.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_audio_survives_removal_of_original() {
+        let root = std::env::temp_dir().join(format!("session_audio_{}", uuid::Uuid::new_v4()));
+        let session_dir = root.join("session");
+        fs::create_dir_all(&session_dir).unwrap();
+        let source = root.join("diktat.wav");
+        fs::write(&source, b"RIFF fake audio").unwrap();
+
+        let info = import_audio_into_session(&session_dir, &source).unwrap();
+        assert_eq!(info.sha256, sha256_file(&source).unwrap());
+        assert_eq!(Path::new(&info.session_path), session_dir.join("audio").join("diktat.wav"));
+        let again = import_audio_into_session(&session_dir, &source).unwrap();
+        assert_eq!(again.imported_at, info.imported_at);
+
+        fs::remove_file(&source).unwrap();
+        assert_eq!(fs::read(&info.session_path).unwrap(), b"RIFF fake audio");
+        assert_eq!(read_session_audio_info(&session_dir).unwrap().original_path, info.original_path);
+        assert!(import_audio_into_session(&session_dir, &source).unwrap_err().starts_with("Audiodatei nicht gefunden"));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_splice_keeps_neighbours_and_rejects_less_confident_result() {
+        let segment = |start: f32, end: f32, text: &str, confidence: f32| TranscriptionSegment {
+            start_time: start,
+            end_time: end,
+            text: text.to_string(),
+            confidence,
+            no_speech_prob: None,
+            compression_ratio: None,
+        };
+        let existing = vec![
+            segment(0.0, 4.0, "Der Patient berichtet", 0.9),
+            segment(4.0, 8.0, "über Schmerzen im Kie", 0.5),
+            segment(8.0, 12.0, "seit zwei Jahren.", 0.9),
+        ];
+        let mut session = SessionTranscription {
+            session_id: "s1".to_string(),
+            audio_path: "diktat.wav".to_string(),
+            result: TranscriptionResult {
+                text: String::new(),
+                confidence: 0.8,
+                processing_time_ms: 0,
+                language: "de".to_string(),
+                segments: existing.clone(),
+                filtered_segments: Vec::new(),
+                partial: false,
+                detected_language: None,
+                language_probabilities: Vec::new(),
+                warnings: Vec::new(),
+            },
+            revisions: Vec::new(),
+            updated_at: String::new(),
+        };
+
+        // A selection starting inside the middle segment is widened to its edges
+        let (start, end) = snap_range_to_segments(&existing, 5.0, 7.0);
+        assert_eq!((start, end), (4.0, 8.0));
+
+        // A less confident result is rejected, but recorded
+        let worse = splice_segments(&mut session, vec![segment(0.0, 4.0, "über Schmerzen", 0.3)], start, end, true);
+        assert!(!worse.accepted);
+        assert_eq!(session.result.segments[1].text, "über Schmerzen im Kie");
+        assert_eq!(session.revisions.len(), 1);
+
+        // Clip times are shifted onto the recording and the neighbours stay
+        let better = splice_segments(&mut session, vec![
+            segment(0.0, 2.5, "über Schmerzen", 0.8),
+            segment(2.5, 4.5, "im Knie", 0.85),
+        ], start, end, true);
+        assert!(better.accepted);
+        assert_eq!(better.revision, 2);
+        assert_eq!(better.replaced_segments.len(), 1);
+        let times: Vec<(f32, f32)> = session.result.segments.iter().map(|s| (s.start_time, s.end_time)).collect();
+        assert_eq!(times, vec![(0.0, 4.0), (4.0, 6.5), (6.5, 8.0), (8.0, 12.0)]);
+        assert_eq!(session.result.text, "Der Patient berichtet über Schmerzen im Knie seit zwei Jahren.");
+    }
+}
@@ -0,0 +1,282 @@
+// Signature commands - Named closing/signature blocks appended to exported Gutachten
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::fs;
+use std::fmt::Write;
+use chrono::format::{Item, StrftimeItems};
+
+use crate::commands::docx_commands::rewrite_docx_xml_parts;
+
+/// A configured signature block. `text` is multi-line; placeholders:
+/// {date}/{datum}, {place}/{ort}, and a line {signature}/{unterschrift} for the handwritten gap.
+/// Lines wrapped in **...** are bold; lines with date or place are right-aligned.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SignatureBlock {
+    pub name: String,
+    pub text: String,
+    pub place: String,
+    pub date_format: Option<String>,  // chrono format, default "%d.%m.%Y"
+    pub gap_lines: Option<usize>,     // Empty lines for the signature, default 3
+    pub font_family: Option<String>,  // None = document font
+    pub font_size: Option<f32>,
+}
+
+/// One output paragraph of a signature block (empty text = spacing line)
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureLine {
+    pub text: String,
+    pub bold: bool,
+    pub right_aligned: bool,
+}
+
+const DEFAULT_SIGNATURE_GAP_LINES: usize = 3;
+const DEFAULT_DATE_FORMAT: &str = "%d.%m.%Y";
+
+/// Format a date, or None if the format has unknown specifiers or needs a time of day
+fn format_date(date: chrono::NaiveDate, format: &str) -> Option<String> {
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return None;
+    }
+    let mut text = String::new();
+    write!(text, "{}", date.format(format)).ok()?;
+    Some(text)
+}
+
+fn get_signature_blocks_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("signature_blocks.json"))
+}
+
+fn load_signature_blocks() -> Result<Vec<SignatureBlock>, String> {
+    let path = get_signature_blocks_path()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read signature blocks: {}", e))?;
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse signature blocks: {}", e))
+}
+
+fn write_signature_blocks(blocks: &[SignatureBlock]) -> Result<(), String> {
+    let path = get_signature_blocks_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+
+    let json = serde_json::to_string_pretty(blocks)
+        .map_err(|e| format!("Failed to serialize signature blocks: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write signature blocks: {}", e))
+}
+
+/// Blocks to append for an export: the named ones in the given order, or the first configured block
+pub(crate) fn select_signature_blocks(names: Option<&[String]>) -> Result<Vec<SignatureBlock>, String> {
+    let blocks = load_signature_blocks()?;
+    match names {
+        Some(names) if !names.is_empty() => names.iter()
+            .map(|name| blocks.iter()
+                .find(|b| &b.name == name)
+                .cloned()
+                .ok_or_else(|| format!("Signaturblock '{}' nicht gefunden", name)))
+            .collect(),
+        _ => blocks.into_iter().next()
+            .map(|block| vec![block])
+            .ok_or_else(|| "Kein Signaturblock konfiguriert".to_string()),
+    }
+}
+
+/// Expand a block into paragraphs. Without a {signature} line, the gap follows the date/place line.
+pub fn signature_lines(block: &SignatureBlock, date: chrono::NaiveDate) -> Vec<SignatureLine> {
+    let date_text = block.date_format.as_deref()
+        .and_then(|format| format_date(date, format))
+        .unwrap_or_else(|| date.format(DEFAULT_DATE_FORMAT).to_string());
+    let gap = block.gap_lines.unwrap_or(DEFAULT_SIGNATURE_GAP_LINES);
+    let is_gap_marker = |line: &str| matches!(line.trim(), "{signature}" | "{unterschrift}");
+    let has_gap_marker = block.text.lines().any(is_gap_marker);
+
+    let empty = || SignatureLine { text: String::new(), bold: false, right_aligned: false };
+    // Keep the block apart from the last paragraph of the document
+    let mut lines = vec![empty()];
+    let mut gap_inserted = false;
+
+    for raw in block.text.lines() {
+        if is_gap_marker(raw) {
+            lines.extend(std::iter::repeat_with(empty).take(gap));
+            gap_inserted = true;
+            continue;
+        }
+
+        let has_date_or_place = ["{date}", "{datum}", "{place}", "{ort}"].iter().any(|p| raw.contains(p));
+        let text = raw
+            .replace("{date}", &date_text)
+            .replace("{datum}", &date_text)
+            .replace("{place}", &block.place)
+            .replace("{ort}", &block.place);
+        let trimmed = text.trim();
+        let bold = trimmed.len() > 4 && trimmed.starts_with("**") && trimmed.ends_with("**");
+        let text = if bold { trimmed[2..trimmed.len() - 2].to_string() } else { trimmed.to_string() };
+
+        lines.push(SignatureLine { text, bold, right_aligned: has_date_or_place });
+        if has_date_or_place && !has_gap_marker && !gap_inserted {
+            lines.extend(std::iter::repeat_with(empty).take(gap));
+            gap_inserted = true;
+        }
+    }
+
+    lines
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// WordprocessingML paragraphs for signature lines
+fn signature_paragraphs_xml(lines: &[SignatureLine], block: &SignatureBlock) -> String {
+    let mut run_properties = String::new();
+    if let Some(font) = &block.font_family {
+        let font = escape_xml(font);
+        run_properties.push_str(&format!(r#"<w:rFonts w:ascii="{}" w:hAnsi="{}"/>"#, font, font));
+    }
+    let size = block.font_size
+        .map(|pt| format!(r#"<w:sz w:val="{}"/>"#, (pt * 2.0).round() as u32))
+        .unwrap_or_default();
+
+    lines.iter()
+        .map(|line| {
+            let paragraph_properties = if line.right_aligned { r#"<w:pPr><w:jc w:val="right"/></w:pPr>"# } else { "" };
+            if line.text.is_empty() {
+                return format!("<w:p>{}</w:p>", paragraph_properties);
+            }
+            format!(
+                r#"<w:p>{}<w:r><w:rPr>{}{}{}</w:rPr><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+                paragraph_properties,
+                run_properties,
+                if line.bold { "<w:b/>" } else { "" },
+                size,
+                escape_xml(&line.text)
+            )
+        })
+        .collect()
+}
+
+/// Append signature blocks to the end of an existing DOCX body (before the final section properties)
+pub(crate) fn append_signature_to_docx(docx_path: &Path, blocks: &[SignatureBlock]) -> Result<(), String> {
+    let today = chrono::Local::now().date_naive();
+    let signature_xml: String = blocks.iter()
+        .map(|block| signature_paragraphs_xml(&signature_lines(block, today), block))
+        .collect();
+
+    rewrite_docx_xml_parts(docx_path, |name, content| {
+        if name != "word/document.xml" {
+            return Ok(None);
+        }
+        let insert_at = content.rfind("<w:sectPr")
+            .or_else(|| content.rfind("</w:body>"))
+            .ok_or("Invalid document.xml: no body")?;
+        let mut document_xml = content.to_string();
+        document_xml.insert_str(insert_at, &signature_xml);
+        Ok(Some(document_xml))
+    })
+}
+
+/// Get all configured signature blocks
+#[command]
+pub async fn get_signature_blocks() -> Result<Vec<SignatureBlock>, String> {
+    load_signature_blocks()
+}
+
+/// Add a signature block or replace the one with the same name
+#[command]
+pub async fn save_signature_block(block: SignatureBlock) -> Result<Vec<SignatureBlock>, String> {
+    if block.name.trim().is_empty() {
+        return Err("Signaturblock benötigt einen Namen".to_string());
+    }
+    if let Some(format) = block.date_format.as_deref() {
+        if format_date(chrono::Local::now().date_naive(), format).is_none() {
+            return Err(format!("Ungültiges Datumsformat: '{}' (Beispiel: %d.%m.%Y)", format));
+        }
+    }
+
+    let mut blocks = load_signature_blocks()?;
+    match blocks.iter_mut().find(|b| b.name == block.name) {
+        Some(existing) => *existing = block,
+        None => blocks.push(block),
+    }
+    write_signature_blocks(&blocks)?;
+    Ok(blocks)
+}
+
+/// Delete a signature block by name
+#[command]
+pub async fn delete_signature_block(name: String) -> Result<Vec<SignatureBlock>, String> {
+    let mut blocks = load_signature_blocks()?;
+    blocks.retain(|b| b.name != name);
+    write_signature_blocks(&blocks)?;
+    Ok(blocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use crate::commands::document_commands::tests::{test_docx_path, write_test_docx};
+
+    fn test_block() -> SignatureBlock {
+        SignatureBlock {
+            name: "Dr. Muster".to_string(),
+            text: "Mit freundlichen Grüßen\n{ort}, den {datum}\n**Dr. med. Max Muster**\nFacharzt für Orthopädie".to_string(),
+            place: "München".to_string(),
+            date_format: None,
+            gap_lines: Some(2),
+            font_family: Some("Arial".to_string()),
+            font_size: Some(11.0),
+        }
+    }
+
+    #[test]
+    fn test_signature_block_layout_and_docx_append() {
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        let lines = signature_lines(&test_block(), date);
+
+        let texts: Vec<&str> = lines.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, vec!["", "Mit freundlichen Grüßen", "München, den 09.03.2026", "", "", "Dr. med. Max Muster", "Facharzt für Orthopädie"]);
+        assert!(lines[2].right_aligned && !lines[1].right_aligned);
+        assert!(lines[5].bold);
+
+        let path = test_docx_path("signature");
+        write_test_docx(&path, r#"<w:p><w:r><w:t>Beurteilung</w:t></w:r></w:p><w:sectPr/>"#, &[]);
+        append_signature_to_docx(&path, &[test_block()]).unwrap();
+
+        let mut archive = zip::ZipArchive::new(fs::File::open(&path).unwrap()).unwrap();
+        let mut xml = String::new();
+        archive.by_name("word/document.xml").unwrap().read_to_string(&mut xml).unwrap();
+        let _ = fs::remove_file(&path);
+
+        let signature_at = xml.find("Mit freundlichen").unwrap();
+        assert!(xml.find("Beurteilung").unwrap() < signature_at);
+        assert!(signature_at < xml.find("<w:sectPr").unwrap());
+        assert!(xml.contains(r#"<w:jc w:val="right"/>"#));
+        assert!(xml.contains(r#"<w:sz w:val="22"/>"#));
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+    }
+
+    #[test]
+    fn test_invalid_date_format_falls_back_to_default() {
+        let date = chrono::NaiveDate::from_ymd_opt(2026, 3, 9).unwrap();
+        assert_eq!(format_date(date, "%Y-%m-%d").as_deref(), Some("2026-03-09"));
+        assert_eq!(format_date(date, "%Q"), None);
+        assert_eq!(format_date(date, "%H:%M"), None);
+
+        let block = SignatureBlock { date_format: Some("%Q".to_string()), ..test_block() };
+        let lines = signature_lines(&block, date);
+        assert_eq!(lines[2].text, "München, den 09.03.2026");
+    }
+}
@@ -4,8 +4,21 @@ use tauri_plugin_dialog::DialogExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::process::Command;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::fs;
+use std::io::BufReader;
+use regex::Regex;
+
+use crate::commands::backup_commands::backup_before_overwrite;
+use crate::commands::document_commands::{extract_document_xml, extract_paragraphs_with_levels};
+use crate::commands::format_commands::sha256_file;
+use crate::commands::consistency_commands::consistency_warnings;
+use crate::heading_normalizer::canonical_heading;
+use crate::template_schema;
+use crate::commands::docx_commands::QuoteStyle;
+use crate::script_locator::{locate_script, STYLE_PROFILE_SCRIPT};
+use crate::cloud_sync::{ensure_local, rename_with_retry, write_with_retry};
+use crate::error_classifier::explain_error;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SectionInfo {
@@ -23,6 +36,8 @@ pub struct FormattingInfo {
     pub font_family: String,
     pub font_size_pt: f32,
     pub line_spacing: f32,
+    #[serde(default)]
+    pub quote: QuoteStyle,  // Cited passages (quote blocks) in rendered reports
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -96,6 +111,8 @@ pub async fn analyze_example_documents(
 
         let dest = examples_dir.join(format!("{}_{}", i + 1, filename));
 
+        // An example on OneDrive may exist only in the cloud until it is read
+        ensure_local(&source)?;
         fs::copy(&source, &dest)
             .map_err(|e| format!("Failed to copy document {}: {}", doc_path, e))?;
 
@@ -116,7 +133,7 @@ pub async fn analyze_example_documents(
 
     // Run the Python analyzer
     let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
-    let script_path = r"C:\Users\kalin\Desktop\gutachten-assistant\style_profile_analyzer.py";
+    let script_path = locate_script(STYLE_PROFILE_SCRIPT)?;
     let output_path = get_style_profile_path()?;
 
     println!("Running StyleProfile analyzer...");
@@ -138,39 +155,188 @@ pub async fn analyze_example_documents(
     }
 
     if !output.status.success() {
-        return Err(format!("Analyzer script failed: {}", stderr));
+        return Err(explain_error(format!("Analyzer script failed: {}", stderr)));
     }
 
     // Parse and return the profile
     let stdout = String::from_utf8(output.stdout)
         .map_err(|e| format!("Failed to parse output: {}", e))?;
 
-    let profile: StyleProfile = serde_json::from_str(&stdout)
+    let mut profile: StyleProfile = serde_json::from_str(&stdout)
         .map_err(|e| format!("Failed to parse StyleProfile JSON: {} - output: {}", e, stdout))?;
 
+    // The analyzer keeps spelling variants apart; merge them so statistics aren't fragmented
+    let analyzed_sections = profile.sections.len();
+    profile.sections = merge_equivalent_sections(profile.sections);
+    if profile.sections.len() != analyzed_sections {
+        let json = serde_json::to_string_pretty(&profile)
+            .map_err(|e| format!("Failed to serialize StyleProfile: {}", e))?;
+        fs::write(&output_path, json)
+            .map_err(|e| format!("Failed to write StyleProfile: {}", e))?;
+    }
+
     println!("StyleProfile created successfully with {} sections", profile.sections.len());
 
     Ok(profile)
 }
 
+/// Merge sections whose headings have the same canonical form ("Eigen-Anamnese" and
+/// "EIGENANAMNESE"); the first spelling and order are kept, the statistics combined
+pub fn merge_equivalent_sections(sections: Vec<SectionInfo>) -> Vec<SectionInfo> {
+    let mut merged: Vec<SectionInfo> = Vec::new();
+    for mut section in sections {
+        section.normalized_name = canonical_heading(&section.display_name);
+        match merged.iter_mut().find(|m| m.normalized_name == section.normalized_name) {
+            Some(existing) => {
+                existing.is_required |= section.is_required;
+                existing.occurrence_count = existing.occurrence_count.max(section.occurrence_count);
+                existing.occurrence_percentage = existing.occurrence_percentage.max(section.occurrence_percentage);
+            }
+            None => merged.push(section),
+        }
+    }
+    merged
+}
+
 /// Load the existing StyleProfile
 #[command]
 pub async fn load_style_profile() -> Result<StyleProfile, String> {
+    read_style_profile()
+}
+
+pub(crate) fn read_style_profile() -> Result<StyleProfile, String> {
     let profile_path = get_style_profile_path()?;
 
     if !profile_path.exists() {
         return Err("StyleProfile not found. Please upload example documents first.".to_string());
     }
 
-    let content = fs::read_to_string(&profile_path)
+    read_profile_file(&profile_path)
+}
+
+fn read_profile_file(path: &Path) -> Result<StyleProfile, String> {
+    ensure_local(path)?;
+    let content = fs::read_to_string(path)
         .map_err(|e| format!("Failed to read StyleProfile: {}", e))?;
 
-    let profile: StyleProfile = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse StyleProfile: {}", e))?;
+    let (profile, _) = template_schema::parse_checked::<StyleProfile>(&content, template_schema::STYLE_PROFILE)
+        .map_err(|e| format!("StyleProfile {} ist ungültig: {}", path.display(), e))?;
 
     Ok(profile)
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RequiredChange {
+    pub section: String,
+    pub was_required: bool,
+    pub now_required: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OrderChange {
+    pub section: String,
+    pub old_position: usize,  // Among the sections present in both profiles
+    pub new_position: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FormattingChange {
+    pub field: String,
+    pub old_value: String,
+    pub new_value: String,
+}
+
+/// Differences from profile `a` (old) to profile `b` (new); sections are matched by canonical heading
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProfileDiff {
+    pub identical: bool,
+    pub added_sections: Vec<String>,
+    pub removed_sections: Vec<String>,
+    pub required_changes: Vec<RequiredChange>,
+    pub order_changes: Vec<OrderChange>,
+    pub formatting_changes: Vec<FormattingChange>,
+}
+
+fn sections_in_order(profile: &StyleProfile) -> Vec<&SectionInfo> {
+    let mut sections: Vec<&SectionInfo> = profile.sections.iter().collect();
+    sections.sort_by_key(|s| s.order);
+    sections
+}
+
+pub fn diff_style_profiles(a: &StyleProfile, b: &StyleProfile) -> ProfileDiff {
+    let mut diff = ProfileDiff::default();
+    let old_sections = sections_in_order(a);
+    let new_sections = sections_in_order(b);
+    let find = |sections: &[&SectionInfo], name: &str| sections.iter()
+        .position(|s| canonical_heading(&s.display_name) == name);
+
+    for section in &new_sections {
+        if find(&old_sections, &canonical_heading(&section.display_name)).is_none() {
+            diff.added_sections.push(section.display_name.clone());
+        }
+    }
+
+    let mut common_old: Vec<String> = Vec::new();
+    for section in &old_sections {
+        let name = canonical_heading(&section.display_name);
+        match find(&new_sections, &name) {
+            None => diff.removed_sections.push(section.display_name.clone()),
+            Some(index) => {
+                if section.is_required != new_sections[index].is_required {
+                    diff.required_changes.push(RequiredChange {
+                        section: section.display_name.clone(),
+                        was_required: section.is_required,
+                        now_required: new_sections[index].is_required,
+                    });
+                }
+                common_old.push(name);
+            }
+        }
+    }
+
+    let common_new: Vec<String> = new_sections.iter()
+        .map(|s| canonical_heading(&s.display_name))
+        .filter(|name| common_old.contains(name))
+        .collect();
+    for (old_position, name) in common_old.iter().enumerate() {
+        let new_position = common_new.iter().position(|n| n == name).unwrap_or(old_position);
+        if new_position != old_position {
+            let section = old_sections[find(&old_sections, name).unwrap_or(0)];
+            diff.order_changes.push(OrderChange { section: section.display_name.clone(), old_position, new_position });
+        }
+    }
+
+    let mut formatting = |field: &str, old: String, new: String| {
+        if old != new {
+            diff.formatting_changes.push(FormattingChange { field: field.to_string(), old_value: old, new_value: new });
+        }
+    };
+    formatting("font_family", a.formatting.font_family.clone(), b.formatting.font_family.clone());
+    formatting("font_size_pt", format!("{:.1}", a.formatting.font_size_pt), format!("{:.1}", b.formatting.font_size_pt));
+    formatting("line_spacing", format!("{:.2}", a.formatting.line_spacing), format!("{:.2}", b.formatting.line_spacing));
+
+    diff.identical = diff.added_sections.is_empty() && diff.removed_sections.is_empty()
+        && diff.required_changes.is_empty() && diff.order_changes.is_empty() && diff.formatting_changes.is_empty();
+    diff
+}
+
+/// "active" (or empty) is the current StyleProfile, anything else the path of a saved profile JSON
+fn resolve_profile(name: &str) -> Result<StyleProfile, String> {
+    match name.trim() {
+        "" | "active" => read_style_profile(),
+        path => read_profile_file(Path::new(path)),
+    }
+}
+
+/// Compare two StyleProfiles, e.g. the active one with a re-analysis, before adopting it
+#[command]
+pub async fn diff_profiles(a: String, b: String) -> Result<ProfileDiff, String> {
+    let diff = diff_style_profiles(&resolve_profile(&a)?, &resolve_profile(&b)?);
+    println!("Profile diff: +{} -{} sections, {} required changes, {} formatting changes",
+        diff.added_sections.len(), diff.removed_sections.len(), diff.required_changes.len(), diff.formatting_changes.len());
+    Ok(diff)
+}
+
 /// Get StyleProfile status (exists, document count, etc.)
 #[command]
 pub async fn get_style_profile_status() -> Result<StyleProfileStatus, String> {
@@ -186,6 +352,7 @@ pub async fn get_style_profile_status() -> Result<StyleProfileStatus, String> {
         });
     }
 
+    ensure_local(&profile_path)?;
     let content = fs::read_to_string(&profile_path)
         .map_err(|e| format!("Failed to read StyleProfile: {}", e))?;
 
@@ -227,40 +394,75 @@ pub async fn clear_style_profile() -> Result<(), String> {
     Ok(())
 }
 
-/// Get the StyleProfile as a formatted prompt string for Llama
-/// IMPORTANT: Only includes STRUCTURE (section names/order), NOT any content!
-#[command]
-pub async fn get_style_profile_prompt() -> Result<String, String> {
-    let profile = load_style_profile().await?;
-
-    let mut prompt = String::new();
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PromptSection {
+    pub name: String,
+    pub required: bool,
+}
 
-    prompt.push_str("ERLAUBTE ÜBERSCHRIFTEN (in dieser Reihenfolge einfügen):\n\n");
+/// Section structure of the prompt as data, for the frontend and the worker
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct StructuredProfilePrompt {
+    pub required: Vec<String>,
+    pub optional: Vec<String>,
+    pub ordered: Vec<PromptSection>,  // All sections in profile order
+}
 
-    let required_sections: Vec<_> = profile.sections.iter()
-        .filter(|s| s.is_required)
+pub fn structured_profile_prompt(profile: &StyleProfile) -> StructuredProfilePrompt {
+    let names = |required: bool| profile.sections.iter()
+        .filter(|s| s.is_required == required)
+        .map(|s| s.display_name.clone())
         .collect();
+    StructuredProfilePrompt {
+        required: names(true),
+        optional: names(false),
+        ordered: profile.sections.iter()
+            .map(|s| PromptSection { name: s.display_name.clone(), required: s.is_required })
+            .collect(),
+    }
+}
 
-    let optional_sections: Vec<_> = profile.sections.iter()
-        .filter(|s| !s.is_required)
-        .collect();
+/// Prose version of the structured prompt for the LLM
+fn format_profile_prompt(structured: &StructuredProfilePrompt) -> String {
+    let mut prompt = String::new();
+
+    prompt.push_str("ERLAUBTE ÜBERSCHRIFTEN (in dieser Reihenfolge einfügen):\n\n");
 
-    for (i, section) in required_sections.iter().enumerate() {
-        prompt.push_str(&format!("{}. {}\n", i + 1, section.display_name));
+    for (i, name) in structured.required.iter().enumerate() {
+        prompt.push_str(&format!("{}. {}\n", i + 1, name));
     }
 
-    if !optional_sections.is_empty() {
+    if !structured.optional.is_empty() {
         prompt.push_str("\nOptional:\n");
-        for section in &optional_sections {
-            prompt.push_str(&format!("- {}\n", section.display_name));
+        for name in &structured.optional {
+            prompt.push_str(&format!("- {}\n", name));
         }
     }
 
-    Ok(prompt)
+    prompt
+}
+
+/// Get the StyleProfile as a formatted prompt string for Llama
+/// IMPORTANT: Only includes STRUCTURE (section names/order), NOT any content!
+#[command]
+pub async fn get_style_profile_prompt() -> Result<String, String> {
+    let profile = load_style_profile().await?;
+    for warning in consistency_warnings(None) {
+        println!("[RUST] Warning: {}", warning);
+    }
+
+    Ok(format_profile_prompt(&structured_profile_prompt(&profile)))
+}
+
+/// Same information as get_style_profile_prompt, as required/optional/ordered section lists
+#[command]
+pub async fn get_style_profile_prompt_structured() -> Result<StructuredProfilePrompt, String> {
+    let profile = read_style_profile()?;
+    Ok(structured_profile_prompt(&profile))
 }
 
 /// Get the path to the template DOCX file
-fn get_template_path() -> Result<PathBuf, String> {
+pub(crate) fn get_template_path() -> Result<PathBuf, String> {
     Ok(get_style_profile_dir()?.join("profile_template.docx"))
 }
 
@@ -278,6 +480,20 @@ pub struct TemplateInfo {
     pub formatting: Option<FormattingInfo>,
 }
 
+/// Content of the approval marker: when and which template bytes were approved
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateApproval {
+    pub approved_at: String,
+    pub template_sha256: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateValidationResult {
+    pub ok: bool,
+    pub missing_sections: Vec<String>,  // Required profile sections not found in the template
+    pub extra_sections: Vec<String>,    // Template headings that are not part of the profile
+}
+
 /// Get information about the generated template
 #[command]
 pub async fn get_template_info() -> Result<TemplateInfo, String> {
@@ -327,6 +543,9 @@ pub async fn get_template_info() -> Result<TemplateInfo, String> {
                         line_spacing: fmt.get("line_spacing")
                             .and_then(|v| v.as_f64())
                             .unwrap_or(1.15) as f32,
+                        quote: fmt.get("quote")
+                            .and_then(|v| serde_json::from_value(v.clone()).ok())
+                            .unwrap_or_default(),
                     });
                 }
             }
@@ -384,6 +603,7 @@ pub async fn save_template_with_dialog(app: AppHandle) -> Result<String, String>
     };
 
     // Copy template to selected location
+    backup_before_overwrite(&output_path)?;
     fs::copy(&template_path, &output_path)
         .map_err(|e| format!("Fehler beim Speichern: {}", e))?;
 
@@ -407,12 +627,12 @@ pub async fn upload_corrected_template(
     // Backup the old template
     if template_path.exists() {
         let backup_path = profile_dir.join("profile_template_backup.docx");
-        let _ = fs::rename(&template_path, &backup_path);
+        let _ = rename_with_retry(&template_path, &backup_path);
         println!("Backed up old template to: {}", backup_path.display());
     }
 
     // Write the new template
-    fs::write(&template_path, file_data)
+    write_with_retry(&template_path, file_data)
         .map_err(|e| format!("Failed to write template file: {}", e))?;
 
     // Clear the approved marker (user needs to re-approve)
@@ -424,9 +644,67 @@ pub async fn upload_corrected_template(
     Ok(template_path.to_string_lossy().to_string())
 }
 
-/// Approve the current template for use
+/// Compare the headings of a template DOCX with the profile sections
+fn validate_template_sections(template_path: &Path, sections: &[SectionInfo]) -> Result<TemplateValidationResult, String> {
+    let file = fs::File::open(template_path)
+        .map_err(|e| format!("Failed to open template file: {}", e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read template DOCX: {}", e))?;
+    let document_xml = extract_document_xml(&mut archive)?;
+    Ok(compare_sections_with_profile(&document_xml, sections))
+}
+
+/// Compare the headings of a document body with the profile sections.
+/// Required sections may also appear as plain (e.g. bold) paragraphs; extras are styled headings only.
+pub(crate) fn compare_sections_with_profile(document_xml: &str, sections: &[SectionInfo]) -> TemplateValidationResult {
+    let paragraphs = extract_paragraphs_with_levels(document_xml);
+
+    let section_names = |section: &SectionInfo| [
+        canonical_heading(&section.display_name),
+        canonical_heading(&section.normalized_name),
+    ];
+
+    let present: Vec<String> = paragraphs.iter()
+        .map(|(_, text)| canonical_heading(text))
+        .collect();
+    let missing_sections: Vec<String> = sections.iter()
+        .filter(|section| section.is_required)
+        .filter(|section| !section_names(section).iter().any(|name| present.contains(name)))
+        .map(|section| section.display_name.clone())
+        .collect();
+
+    let known: Vec<String> = sections.iter().flat_map(section_names).collect();
+    let extra_sections: Vec<String> = paragraphs.iter()
+        .filter(|(level, text)| level.is_some() && !known.contains(&canonical_heading(text)))
+        .map(|(_, text)| text.clone())
+        .collect();
+
+    TemplateValidationResult {
+        ok: missing_sections.is_empty(),
+        missing_sections,
+        extra_sections,
+    }
+}
+
+/// Check that the (possibly user-corrected) template still contains all required profile sections
+#[command]
+pub async fn validate_template_against_profile() -> Result<TemplateValidationResult, String> {
+    let template_path = get_template_path()?;
+    if !template_path.exists() {
+        return Err("Template file not found. Please analyze documents first.".to_string());
+    }
+
+    let profile = load_style_profile().await?;
+    let result = validate_template_sections(&template_path, &profile.sections)?;
+    println!("Template validation: {} missing, {} extra sections",
+        result.missing_sections.len(), result.extra_sections.len());
+    Ok(result)
+}
+
+/// Approve the current template for use.
+/// Refuses if required profile sections are missing, unless `force` is set.
 #[command]
-pub async fn approve_template() -> Result<(), String> {
+pub async fn approve_template(force: Option<bool>) -> Result<(), String> {
     let template_path = get_template_path()?;
     let approved_marker = get_approved_marker_path()?;
 
@@ -434,8 +712,26 @@ pub async fn approve_template() -> Result<(), String> {
         return Err("Template file not found. Please analyze documents first.".to_string());
     }
 
-    // Create the approved marker file
-    fs::write(&approved_marker, chrono::Utc::now().to_rfc3339())
+    if !force.unwrap_or(false) {
+        if let Ok(profile) = load_style_profile().await {
+            let validation = validate_template_sections(&template_path, &profile.sections)?;
+            if !validation.ok {
+                return Err(format!(
+                    "Der Vorlage fehlen Pflichtabschnitte: {}",
+                    validation.missing_sections.join(", ")
+                ));
+            }
+        }
+    }
+
+    // Create the approved marker file; the hash detects later edits of the template
+    let approval = TemplateApproval {
+        approved_at: chrono::Utc::now().to_rfc3339(),
+        template_sha256: sha256_file(&template_path)?,
+    };
+    let json = serde_json::to_string_pretty(&approval)
+        .map_err(|e| format!("Failed to serialize approval: {}", e))?;
+    fs::write(&approved_marker, json)
         .map_err(|e| format!("Failed to create approval marker: {}", e))?;
 
     println!("Template approved at: {}", chrono::Utc::now().to_rfc3339());
@@ -443,9 +739,135 @@ pub async fn approve_template() -> Result<(), String> {
     Ok(())
 }
 
-/// Check if the template has been approved
+/// Check if the template has been approved and is unchanged since, as the export quality gate requires
 #[command]
 pub async fn is_template_approved() -> Result<bool, String> {
+    Ok(template_approval_problem()?.is_none())
+}
+
+/// Why the current template does not match its approval, or None if it does.
+/// Markers written before hashes were stored only hold a timestamp and need a new approval.
+pub(crate) fn template_approval_problem() -> Result<Option<String>, String> {
+    let template_path = get_template_path()?;
     let approved_marker = get_approved_marker_path()?;
-    Ok(approved_marker.exists())
+    if !template_path.exists() || !approved_marker.exists() {
+        return Ok(Some("Die Vorlage ist nicht freigegeben".to_string()));
+    }
+
+    let approval = fs::read_to_string(&approved_marker).ok()
+        .and_then(|content| serde_json::from_str::<TemplateApproval>(&content).ok());
+    Ok(match approval {
+        None => Some("Die Freigabe enthält keine Prüfsumme, bitte Vorlage erneut freigeben".to_string()),
+        Some(approval) if approval.template_sha256 != sha256_file(&template_path)? =>
+            Some("Die Vorlage wurde seit der Freigabe geändert".to_string()),
+        Some(_) => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::document_commands::tests::{test_docx_path, write_test_docx};
+
+    fn section(name: &str, is_required: bool) -> SectionInfo {
+        SectionInfo {
+            normalized_name: name.to_lowercase(),
+            display_name: name.to_string(),
+            is_required,
+            occurrence_count: 3,
+            occurrence_percentage: 100.0,
+            order: 0,
+        }
+    }
+
+    #[test]
+    fn test_template_missing_required_section_is_flagged() {
+        let heading = |text: &str| format!(
+            r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>{}</w:t></w:r></w:p>"#, text
+        );
+        let body = [
+            heading("1. Anamnese"),
+            r#"<w:p><w:r><w:t>[Text]</w:t></w:r></w:p>"#.to_string(),
+            r#"<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>Befund:</w:t></w:r></w:p>"#.to_string(),
+            heading("Anhang"),
+        ].concat();
+        let path = test_docx_path("template_validation");
+        write_test_docx(&path, &body, &[]);
+
+        let sections = [
+            section("Anamnese", true),
+            section("Befund", true),
+            section("Beurteilung", true),
+            section("Diagnose", false),
+        ];
+        let result = validate_template_sections(&path, &sections).unwrap();
+        let _ = fs::remove_file(&path);
+
+        assert!(!result.ok);
+        assert_eq!(result.missing_sections, vec!["Beurteilung".to_string()]);
+        assert_eq!(result.extra_sections, vec!["Anhang".to_string()]);
+    }
+
+    #[test]
+    fn test_heading_variants_merge_into_one_section() {
+        let mut variant = section("Eigen-Anamnese", false);
+        variant.occurrence_count = 1;
+        let merged = merge_equivalent_sections(vec![
+            section("EIGENANAMNESE", true),
+            section("Befund", true),
+            variant,
+            section("Eigenanamnese:", false),
+        ]);
+
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].display_name, "EIGENANAMNESE");
+        assert_eq!(merged[0].normalized_name, "EIGENANAMNESE");
+        assert!(merged[0].is_required);
+        assert_eq!(merged[0].occurrence_count, 3);
+    }
+
+    #[test]
+    fn test_profile_diff_reports_required_flip_and_added_section() {
+        let profile = |sections: Vec<SectionInfo>, font: &str| StyleProfile {
+            version: "1.0".to_string(),
+            created_at: String::new(),
+            analyzed_documents: 3,
+            source_files: Vec::new(),
+            sections,
+            formatting: FormattingInfo { font_family: font.to_string(), font_size_pt: 11.0, line_spacing: 1.15, quote: QuoteStyle::default() },
+        };
+        let ordered = |name: &str, required: bool, order: i32| SectionInfo { order, ..section(name, required) };
+
+        let old = profile(vec![ordered("Anamnese", true, 1), ordered("Befund", false, 2), ordered("Beurteilung", true, 3)], "Arial");
+        let new = profile(vec![ordered("ANAMNESE:", true, 1), ordered("Befund", true, 2), ordered("Diagnosen", false, 3), ordered("Beurteilung", true, 4)], "Arial");
+
+        let diff = diff_style_profiles(&old, &new);
+        assert!(!diff.identical);
+        assert_eq!(diff.added_sections, vec!["Diagnosen".to_string()]);
+        assert!(diff.removed_sections.is_empty());
+        assert_eq!(diff.required_changes, vec![RequiredChange { section: "Befund".to_string(), was_required: false, now_required: true }]);
+        assert!(diff.order_changes.is_empty());
+        assert!(diff.formatting_changes.is_empty());
+        assert!(diff_style_profiles(&old, &old).identical);
+    }
+
+    #[test]
+    fn test_structured_prompt_splits_required_and_optional_in_order() {
+        let profile = StyleProfile {
+            version: "1.0".to_string(),
+            created_at: String::new(),
+            analyzed_documents: 3,
+            source_files: Vec::new(),
+            sections: vec![section("Anamnese", true), section("Vorbefunde", false), section("Befund", true), section("Anlagen", false)],
+            formatting: FormattingInfo { font_family: "Arial".to_string(), font_size_pt: 11.0, line_spacing: 1.15, quote: QuoteStyle::default() },
+        };
+
+        let structured = structured_profile_prompt(&profile);
+        assert_eq!(structured.required, vec!["Anamnese", "Befund"]);
+        assert_eq!(structured.optional, vec!["Vorbefunde", "Anlagen"]);
+        assert_eq!(structured.ordered[1], PromptSection { name: "Vorbefunde".to_string(), required: false });
+        assert_eq!(structured.ordered.len(), 4);
+        assert_eq!(format_profile_prompt(&structured),
+            "ERLAUBTE ÜBERSCHRIFTEN (in dieser Reihenfolge einfügen):\n\n1. Anamnese\n2. Befund\n\nOptional:\n- Vorbefunde\n- Anlagen\n");
+    }
 }
@@ -0,0 +1,328 @@
+// Support commands - Builds a ZIP with logs, system and dependency info, redacted settings and
+// recent job records for bug reports; nothing is sent anywhere, the user passes the file on
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::fs;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::commands::docx_commands::rewrite_docx_xml_parts;
+use crate::commands::llama_commands::{get_worker_logs, llm_model_path, validate_gguf_file};
+use crate::commands::session_commands::{get_sessions_dir, load_session_metadata, session_status};
+use crate::commands::system_commands::{get_app_readiness, system_info, user_data_sync_folder};
+use crate::error_classifier::recent_errors;
+
+const DEFAULT_LOG_FILE_COUNT: usize = 5;
+const DEFAULT_JOB_COUNT: usize = 10;
+/// Shorter values (initials, "Dr") would match everywhere
+const MIN_KNOWN_NAME_CHARS: usize = 3;
+
+/// Text nodes of any XML part (w:t, w:instrText, w:delText, docProps author fields)
+static XML_TEXT_NODE: Lazy<Regex> = Lazy::new(|| Regex::new(r">([^<]+)<").unwrap());
+/// Attributes that carry person names (comment and revision authors)
+static XML_NAME_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(w:(?:author|initials)=")([^"]*)(")"#).unwrap());
+static XML_ENTITY: Lazy<Regex> = Lazy::new(|| Regex::new(r"&(?:#\d+|#x[0-9a-fA-F]+|[a-zA-Z]+);").unwrap());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SupportBundleOptions {
+    #[serde(default)]
+    pub log_file_count: Option<usize>,
+    #[serde(default)]
+    pub job_count: Option<usize>,
+    #[serde(default)]
+    pub include_document: bool,  // Explicit opt-in; the document is redacted before it is packed
+    #[serde(default)]
+    pub document_path: Option<String>,
+    #[serde(default)]
+    pub session_id: Option<String>,  // Its metadata values (patient name, case number) are known names
+    #[serde(default)]
+    pub known_names: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SupportBundleReport {
+    pub path: String,
+    pub entries: Vec<String>,
+    pub document_included: bool,
+}
+
+/// Replaces patient text with placeholder characters of equal length: letters become x/X,
+/// digits 0, whitespace and punctuation stay so the layout is unchanged
+#[derive(Debug, Clone, Default)]
+pub struct Anonymizer {
+    known_names: Vec<String>,
+}
+
+fn placeholder_char(c: char) -> char {
+    if c.is_uppercase() {
+        'X'
+    } else if c.is_alphabetic() {
+        'x'
+    } else if c.is_numeric() {
+        '0'
+    } else {
+        c
+    }
+}
+
+impl Anonymizer {
+    pub fn new<I: IntoIterator<Item = String>>(known_names: I) -> Self {
+        let mut known_names: Vec<String> = known_names.into_iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| name.chars().filter(|c| c.is_alphanumeric()).count() >= MIN_KNOWN_NAME_CHARS)
+            .collect();
+        // Longest first so "Anna Maria" is replaced before "Anna"
+        known_names.sort_by_key(|name| std::cmp::Reverse(name.chars().count()));
+        known_names.dedup();
+        Anonymizer { known_names }
+    }
+
+    /// Every letter and digit of the text
+    pub fn redact_all(&self, text: &str) -> String {
+        text.chars().map(placeholder_char).collect()
+    }
+
+    /// Only the known names (case-insensitive); for logs and records that should stay readable
+    pub fn redact_names(&self, text: &str) -> String {
+        self.known_names.iter().fold(text.to_string(), |text, name| {
+            let pattern = format!("(?i){}", regex::escape(name));
+            match Regex::new(&pattern) {
+                Ok(regex) => regex.replace_all(&text, |c: &regex::Captures| self.redact_all(&c[0])).into_owned(),
+                Err(_) => text,
+            }
+        })
+    }
+
+    /// XML text nodes and name attributes; an entity counts as one character
+    pub fn redact_xml(&self, xml: &str) -> String {
+        let xml = XML_TEXT_NODE.replace_all(xml, |c: &regex::Captures| {
+            let text = XML_ENTITY.replace_all(&c[1], "x");
+            format!(">{}<", self.redact_all(&text))
+        });
+        XML_NAME_ATTRIBUTE.replace_all(&xml, |c: &regex::Captures| {
+            format!("{}{}{}", &c[1], self.redact_all(&c[2]), &c[3])
+        }).into_owned()
+    }
+
+    /// Known names still present in the text
+    pub fn residual_names(&self, text: &str) -> Vec<String> {
+        let text = text.to_lowercase();
+        self.known_names.iter()
+            .filter(|name| text.contains(&name.to_lowercase()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// All string values of a JSON document redacted; keys, numbers and flags stay readable
+fn redact_json(value: &Value, anonymizer: &Anonymizer) -> Value {
+    match value {
+        Value::String(s) => Value::String(anonymizer.redact_all(s)),
+        Value::Array(items) => Value::Array(items.iter().map(|v| redact_json(v, anonymizer)).collect()),
+        Value::Object(map) => Value::Object(map.iter().map(|(k, v)| (k.clone(), redact_json(v, anonymizer))).collect()),
+        other => other.clone(),
+    }
+}
+
+fn user_data_dir() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data"))
+}
+
+/// Files of a directory with one of the extensions, newest first
+fn newest_files(dir: &Path, extensions: &[&str]) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries.filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter(|p| p.extension().and_then(|e| e.to_str()).is_some_and(|e| extensions.contains(&e)))
+        .filter_map(|p| Some((fs::metadata(&p).ok()?.modified().ok()?, p)))
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files.into_iter().map(|(_, p)| p).collect()
+}
+
+async fn dependency_report() -> Value {
+    let model_file = |use_qwen: bool| match validate_gguf_file(&llm_model_path(use_qwen)) {
+        Ok(info) => serde_json::to_value(info).unwrap_or(Value::Null),
+        Err(e) => Value::String(e.to_string()),
+    };
+    serde_json::json!({
+        "readiness": get_app_readiness().await.map_or_else(Value::String, |r| serde_json::to_value(r).unwrap_or(Value::Null)),
+        "qwen_model": model_file(true),
+        "llama_model": model_file(false),
+        // Synced user-data explains sharing violations and "not downloaded" errors
+        "user_data_sync_folder": user_data_sync_folder(),
+    })
+}
+
+/// Status and file list of the most recently changed sessions, without their content
+fn job_records(count: usize) -> Vec<Value> {
+    let Ok(sessions_dir) = get_sessions_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&sessions_dir) else {
+        return Vec::new();
+    };
+    let mut sessions: Vec<(std::time::SystemTime, String)> = entries.filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.file_name().to_string_lossy().to_string())))
+        .collect();
+    sessions.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    sessions.into_iter().take(count).map(|(modified, session_id)| {
+        let files: Vec<Value> = fs::read_dir(sessions_dir.join(&session_id)).into_iter().flatten()
+            .filter_map(|e| e.ok())
+            .filter_map(|e| Some(serde_json::json!({
+                "name": e.file_name().to_string_lossy(),
+                "bytes": e.metadata().ok()?.len(),
+            })))
+            .collect();
+        serde_json::json!({
+            "session_id": session_id,
+            "status": session_status(&session_id).ok(),
+            "modified": chrono::DateTime::<chrono::Local>::from(modified).to_rfc3339(),
+            "files": files,
+        })
+    }).collect()
+}
+
+/// The document with every text node replaced by placeholders, as DOCX bytes
+fn redacted_document(document_path: &Path, anonymizer: &Anonymizer) -> Result<Vec<u8>, String> {
+    let copy = std::env::temp_dir().join(format!("support_{}.docx", uuid::Uuid::new_v4()));
+    fs::copy(document_path, &copy)
+        .map_err(|e| format!("Dokument konnte nicht gelesen werden: {}", e))?;
+    let result = rewrite_docx_xml_parts(&copy, |_, xml| Ok(Some(anonymizer.redact_xml(xml))))
+        .and_then(|_| fs::read(&copy).map_err(|e| format!("Failed to read redacted document: {}", e)));
+    let _ = fs::remove_file(&copy);
+    result
+}
+
+/// Text of all XML parts of a DOCX, for the residual name scan
+fn docx_xml_text(bytes: &[u8]) -> Result<String, String> {
+    use std::io::Read;
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| format!("Failed to read redacted document: {}", e))?;
+    let mut text = String::new();
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)
+            .map_err(|e| format!("Failed to read redacted document: {}", e))?;
+        if file.name().ends_with(".xml") || file.name().ends_with(".rels") {
+            file.read_to_string(&mut text)
+                .map_err(|e| format!("Failed to read redacted document: {}", e))?;
+        }
+    }
+    Ok(text)
+}
+
+/// Collect the bundle entries; every text entry has been through the anonymizer
+async fn collect_bundle_entries(options: &SupportBundleOptions, anonymizer: &Anonymizer) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut add_text = |name: String, text: String| entries.push((name, anonymizer.redact_names(&text).into_bytes()));
+
+    let user_data = user_data_dir()?;
+    let log_files = [newest_files(&user_data.join("logs"), &["log", "txt"]), newest_files(&user_data, &["jsonl", "log"])].concat();
+    for path in log_files.iter().take(options.log_file_count.unwrap_or(DEFAULT_LOG_FILE_COUNT)) {
+        if let Ok(content) = fs::read(path) {
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            add_text(format!("logs/{}", name), String::from_utf8_lossy(&content).to_string());
+        }
+    }
+    add_text("logs/llm_worker.log".to_string(), get_worker_logs(Some(usize::MAX)).await.unwrap_or_default().join("\n"));
+
+    let system = system_info().await.map_or_else(Value::String, |info| serde_json::to_value(info).unwrap_or(Value::Null));
+    add_text("system_info.json".to_string(), serde_json::to_string_pretty(&system).unwrap_or_default());
+    add_text("dependencies.json".to_string(), serde_json::to_string_pretty(&dependency_report().await).unwrap_or_default());
+
+    for path in newest_files(&user_data.join("settings"), &["json"]) {
+        let Some(value) = fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str::<Value>(&c).ok()) else {
+            continue;
+        };
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        add_text(format!("settings/{}", name), serde_json::to_string_pretty(&redact_json(&value, anonymizer)).unwrap_or_default());
+    }
+
+    let jobs = job_records(options.job_count.unwrap_or(DEFAULT_JOB_COUNT));
+    add_text("jobs.json".to_string(), serde_json::to_string_pretty(&jobs).unwrap_or_default());
+    add_text("errors.json".to_string(), serde_json::to_string_pretty(&recent_errors()).unwrap_or_default());
+
+    if options.include_document {
+        let document_path = options.document_path.as_deref()
+            .ok_or("Kein Dokument für das Support-Paket angegeben")?;
+        // The original file name usually contains the patient name
+        let extension = Path::new(document_path).extension().and_then(|e| e.to_str()).unwrap_or("docx");
+        entries.push((format!("document/dokument.{}", extension), redacted_document(Path::new(document_path), anonymizer)?));
+    }
+    Ok(entries)
+}
+
+/// Create a support bundle ZIP. Known names come from the session metadata and the options;
+/// the bundle is only written if none of them is left in any entry.
+#[command]
+pub async fn create_support_bundle(output_path: String, options: Option<SupportBundleOptions>) -> Result<SupportBundleReport, String> {
+    let options = options.unwrap_or_default();
+    let mut known_names = options.known_names.clone();
+    if let Some(session_id) = &options.session_id {
+        known_names.extend(load_session_metadata(session_id)?.into_values());
+    }
+    let anonymizer = Anonymizer::new(known_names);
+
+    let entries = collect_bundle_entries(&options, &anonymizer).await?;
+    for (name, bytes) in &entries {
+        let text = if name.ends_with(".docx") { docx_xml_text(bytes)? } else { String::from_utf8_lossy(bytes).to_string() };
+        let residual = anonymizer.residual_names(&text);
+        if !residual.is_empty() {
+            return Err(format!(
+                "Support-Paket nicht erstellt: {} enthält nach der Anonymisierung noch {} bekannte(n) Namen",
+                name, residual.len()
+            ));
+        }
+    }
+
+    let file = fs::File::create(&output_path)
+        .map_err(|e| format!("Failed to create support bundle: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    for (name, bytes) in &entries {
+        zip.start_file(name.as_str(), zip::write::FileOptions::default())
+            .map_err(|e| format!("Failed to write bundle entry: {}", e))?;
+        zip.write_all(bytes)
+            .map_err(|e| format!("Failed to write {}: {}", name, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to finish support bundle: {}", e))?;
+
+    println!("Support bundle created: {} ({} entries)", output_path, entries.len());
+    Ok(SupportBundleReport {
+        path: output_path,
+        entries: entries.into_iter().map(|(name, _)| name).collect(),
+        document_included: options.include_document,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anonymizer_keeps_layout_and_removes_known_names() {
+        let anonymizer = Anonymizer::new(vec!["Heinen".to_string(), "Sarah".to_string(), "Dr".to_string()]);
+
+        let xml = r#"<w:p><w:r><w:t xml:space="preserve">Frau Sarah Heinen, geb. 15.03.1977 &amp; Co</w:t></w:r><w:commentRangeStart w:author="Dr. Meier"/></w:p>"#;
+        let redacted = anonymizer.redact_xml(xml);
+        assert_eq!(redacted, r#"<w:p><w:r><w:t xml:space="preserve">Xxxx Xxxxx Xxxxxx, xxx. 00.00.0000 x Xx</w:t></w:r><w:commentRangeStart w:author="Xx. Xxxxx"/></w:p>"#);
+        assert!(anonymizer.residual_names(&redacted).is_empty());
+
+        let log = anonymizer.redact_names("Structuring transcript for HEINEN, Sarah");
+        assert_eq!(log, "Structuring transcript for XXXXXX, Xxxxx");
+        assert_eq!(anonymizer.residual_names("Befund von Frau heinen"), vec!["Heinen".to_string()]);
+
+        let settings = redact_json(&serde_json::json!({"temp_dir": "C:/Users/kalin", "enabled": true}), &anonymizer);
+        assert_eq!(settings, serde_json::json!({"temp_dir": "X:/Xxxxx/xxxxx", "enabled": true}));
+    }
+}
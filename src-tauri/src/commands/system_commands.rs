@@ -1,7 +1,13 @@
 // System information and health check commands
 
-use tauri::command;
+use tauri::{command, AppHandle, Emitter};
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::formats::SupportedFormats;
+use crate::script_locator::{check_scripts, ScriptStatus};
+use crate::memory_manager::{get_gpu_memory_info, get_system_memory_info, GpuMemoryInfo, MemoryManager, MemoryUsage};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SystemInfo {
@@ -20,6 +26,111 @@ pub struct MemoryStatus {
     pub percentage_used: f32,
 }
 
+/// Payload of the `memory_status` event: model allocations plus real RAM and VRAM
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MemoryStatusEvent {
+    #[serde(flatten)]
+    pub usage: MemoryUsage,
+    pub system_total_bytes: u64,
+    pub system_available_bytes: u64,
+    pub gpu: Option<GpuMemoryInfo>,
+    pub timestamp: String,
+}
+
+const DEFAULT_MEMORY_SAMPLE_PERIOD_MS: u64 = 2000;
+const MIN_MEMORY_SAMPLE_PERIOD_MS: u64 = 250;
+const DEFAULT_MEMORY_HYSTERESIS_MB: u64 = 64;
+/// VRAM is read by starting nvidia-smi, so it is sampled far less often than RAM
+const GPU_SAMPLE_PERIOD: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Incremented on every (un)subscribe; a sampler stops once its generation is outdated
+static MEMORY_SAMPLER_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Whether a new sample differs enough from the last emitted one to be worth an event
+fn memory_status_changed(previous: &MemoryStatusEvent, current: &MemoryStatusEvent, threshold_bytes: u64) -> bool {
+    let moved = |a: u64, b: u64| a.abs_diff(b) > threshold_bytes;
+    let gpu_used = |event: &MemoryStatusEvent| event.gpu.as_ref().map(|g| g.used_bytes);
+
+    moved(previous.system_available_bytes, current.system_available_bytes)
+        || moved(previous.usage.total_allocated, current.usage.total_allocated)
+        || previous.usage.models.len() != current.usage.models.len()
+        || previous.gpu.is_some() != current.gpu.is_some()
+        || moved(gpu_used(previous).unwrap_or(0), gpu_used(current).unwrap_or(0))
+}
+
+/// `cached_gpu` is the last VRAM reading to reuse; None queries nvidia-smi again
+async fn sample_memory_status(memory_manager: &MemoryManager, cached_gpu: Option<Option<GpuMemoryInfo>>) -> MemoryStatusEvent {
+    let usage = memory_manager.get_memory_usage().await;
+    // nvidia-smi is an external process; keep it off the async workers
+    let ((system_total_bytes, system_available_bytes), gpu) = tokio::task::spawn_blocking(move || {
+        (get_system_memory_info(), cached_gpu.unwrap_or_else(get_gpu_memory_info))
+    })
+    .await
+    .unwrap_or((get_system_memory_info(), None));
+
+    MemoryStatusEvent {
+        usage,
+        system_total_bytes,
+        system_available_bytes,
+        gpu,
+        timestamp: chrono::Utc::now().to_rfc3339(),
+    }
+}
+
+/// Start or stop the background sampler emitting `memory_status` events.
+/// Events are only sent when a value moves by more than the hysteresis threshold.
+#[command]
+pub async fn subscribe_memory_updates(
+    app: AppHandle,
+    memory_manager: tauri::State<'_, Arc<MemoryManager>>,
+    enabled: bool,
+    period_ms: Option<u64>,
+    threshold_mb: Option<u64>,
+) -> Result<(), String> {
+    // Replaces any running sampler (a new period takes effect immediately)
+    let generation = MEMORY_SAMPLER_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    if !enabled {
+        println!("Memory sampler stopped");
+        return Ok(());
+    }
+
+    let period = std::time::Duration::from_millis(
+        period_ms.unwrap_or(DEFAULT_MEMORY_SAMPLE_PERIOD_MS).max(MIN_MEMORY_SAMPLE_PERIOD_MS)
+    );
+    let threshold_bytes = threshold_mb.unwrap_or(DEFAULT_MEMORY_HYSTERESIS_MB) * 1024 * 1024;
+    let memory_manager = memory_manager.inner().clone();
+    println!("Memory sampler started ({} ms, {} MB hysteresis)", period.as_millis(), threshold_bytes / 1024 / 1024);
+
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(period);
+        let mut last_emitted: Option<MemoryStatusEvent> = None;
+        let mut gpu_sample: Option<(std::time::Instant, Option<GpuMemoryInfo>)> = None;
+        loop {
+            interval.tick().await;
+            if MEMORY_SAMPLER_GENERATION.load(Ordering::SeqCst) != generation {
+                break;
+            }
+
+            let cached_gpu = gpu_sample.as_ref()
+                .filter(|(sampled_at, _)| sampled_at.elapsed() < GPU_SAMPLE_PERIOD)
+                .map(|(_, gpu)| gpu.clone());
+            let refreshed_gpu = cached_gpu.is_none();
+            let status = sample_memory_status(&memory_manager, cached_gpu).await;
+            if refreshed_gpu {
+                gpu_sample = Some((std::time::Instant::now(), status.gpu.clone()));
+            }
+            let changed = last_emitted.as_ref()
+                .is_none_or(|previous| memory_status_changed(previous, &status, threshold_bytes));
+            if changed {
+                let _ = app.emit("memory_status", status.clone());
+                last_emitted = Some(status);
+            }
+        }
+    });
+
+    Ok(())
+}
+
 /// Get comprehensive system information
 #[command]
 pub async fn system_info() -> Result<SystemInfo, String> {
@@ -38,6 +149,12 @@ pub async fn system_info() -> Result<SystemInfo, String> {
     })
 }
 
+/// File formats the backend accepts, so the frontend's file pickers offer the same extensions
+#[command]
+pub async fn get_supported_formats() -> Result<SupportedFormats, String> {
+    Ok(SupportedFormats::current())
+}
+
 /// Get current memory usage status
 #[command]
 pub async fn get_system_memory() -> Result<MemoryStatus, String> {
@@ -77,36 +194,81 @@ pub async fn check_system_requirements() -> Result<bool, String> {
     Ok(true)
 }
 
-// Helper functions for platform-specific memory detection
-async fn get_available_system_memory() -> Result<u64, anyhow::Error> {
-    // Platform-specific implementation would go here
-    // For now, return a conservative estimate for development
-    #[cfg(target_os = "windows")]
-    {
-        // Windows-specific memory detection
-        Ok(6_000_000_000) // 6GB available
+/// Presence and version of the bundled Python scripts; a mismatch means the scripts don't
+/// belong to this app version
+#[command]
+pub async fn check_dependencies() -> Result<Vec<ScriptStatus>, String> {
+    let scripts = check_scripts();
+    for script in scripts.iter().filter(|s| !s.ok) {
+        println!("Script problem: {} - {}", script.name, script.message.as_deref().unwrap_or(""));
     }
-    
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Cross-platform fallback
-        Ok(6_000_000_000)
+    Ok(scripts)
+}
+
+/// What the frontend needs at startup to decide which setup screens to show
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct AppReadiness {
+    pub system_requirements_met: bool,
+    pub whisper_model_cached: bool,
+    pub llm_model_available: bool,
+    pub llm_worker_running: bool,
+    pub template_available: bool,
+    pub setup_steps: Vec<String>,  // "system_requirements", "whisper_model", "llm_model", "template"
+    #[serde(default)]
+    pub user_data_sync_folder: Option<String>,  // "OneDrive" etc. when user-data is synced; files may be cloud-only
+}
+
+/// Setup screens still needed, in the order they should be shown
+fn pending_setup_steps(readiness: &AppReadiness) -> Vec<String> {
+    [
+        (readiness.system_requirements_met, "system_requirements"),
+        (readiness.whisper_model_cached, "whisper_model"),
+        (readiness.llm_model_available, "llm_model"),
+        (readiness.template_available, "template"),
+    ]
+    .iter()
+    .filter(|(ready, _)| !ready)
+    .map(|(_, step)| step.to_string())
+    .collect()
+}
+
+/// Sync client whose folder holds user-data (OneDrive, Dropbox, ...), if any
+pub(crate) fn user_data_sync_folder() -> Option<String> {
+    let user_data = std::env::current_dir().ok()?.join("user-data");
+    crate::cloud_sync::known_sync_folder(&user_data).map(String::from)
+}
+
+/// Combined readiness check for app startup
+#[command]
+pub async fn get_app_readiness() -> Result<AppReadiness, String> {
+    let llm_worker_running = !crate::commands::llama_commands::llm_worker_state().is_empty();
+    let mut readiness = AppReadiness {
+        system_requirements_met: check_system_requirements().await?,
+        whisper_model_cached: crate::commands::model_commands::whisper_cache_status().exists,
+        llm_model_available: crate::commands::llama_commands::llm_model_available(),
+        llm_worker_running,
+        template_available: crate::commands::template_commands::load_family_spec(None).is_ok(),
+        setup_steps: Vec::new(),
+        user_data_sync_folder: user_data_sync_folder(),
+    };
+    readiness.setup_steps = pending_setup_steps(&readiness);
+    println!("App readiness: {} setup steps pending", readiness.setup_steps.len());
+    if let Some(client) = &readiness.user_data_sync_folder {
+        println!("Warning: user-data is in a {} folder; cloud-only files are downloaded on first use", client);
     }
+    Ok(readiness)
+}
+
+// Helper functions for platform-specific memory detection (/proc/meminfo or GlobalMemoryStatusEx,
+// run off the async workers since reading /proc is blocking I/O)
+async fn get_available_system_memory() -> Result<u64, anyhow::Error> {
+    let (_, available) = tokio::task::spawn_blocking(get_system_memory_info).await?;
+    Ok(available)
 }
 
 async fn get_total_system_memory() -> Result<u64, anyhow::Error> {
-    // Platform-specific implementation
-    #[cfg(target_os = "windows")]
-    {
-        // Windows-specific total memory detection
-        Ok(8_000_000_000) // 8GB total
-    }
-    
-    #[cfg(not(target_os = "windows"))]
-    {
-        // Cross-platform fallback
-        Ok(8_000_000_000)
-    }
+    let (total, _) = tokio::task::spawn_blocking(get_system_memory_info).await?;
+    Ok(total)
 }
 
 #[cfg(test)]
@@ -137,6 +299,55 @@ mod tests {
         assert!(memory.percentage_used >= 0.0 && memory.percentage_used <= 100.0);
     }
 
+    #[test]
+    fn test_memory_events_respect_hysteresis_and_parse_real_sources() {
+        let (total, available) = crate::memory_manager::parse_meminfo(
+            "MemTotal:       16318128 kB\nMemFree:         1204816 kB\nMemAvailable:    9532116 kB\n"
+        ).unwrap();
+        assert_eq!(total, 16318128 * 1024);
+        assert_eq!(available, 9532116 * 1024);
+        let gpu = crate::memory_manager::parse_nvidia_smi_memory("NVIDIA GeForce RTX 3060, 5120, 12288\n").unwrap();
+        assert_eq!(gpu.used_bytes, 5120 * 1024 * 1024);
+
+        let event = |available_mb: u64, gpu_used_mb: u64| MemoryStatusEvent {
+            usage: MemoryUsage {
+                total_allocated: 0,
+                models: Default::default(),
+                available_system: 0,
+                percentage_used: 0.0,
+            },
+            system_total_bytes: total,
+            system_available_bytes: available_mb * 1024 * 1024,
+            gpu: Some(GpuMemoryInfo { used_bytes: gpu_used_mb * 1024 * 1024, ..gpu.clone() }),
+            timestamp: String::new(),
+        };
+        let threshold = 64 * 1024 * 1024;
+        assert!(!memory_status_changed(&event(9000, 5000), &event(9030, 5010), threshold));
+        assert!(memory_status_changed(&event(9000, 5000), &event(8900, 5000), threshold));
+        assert!(memory_status_changed(&event(9000, 5000), &event(9000, 8000), threshold));
+
+        let json = serde_json::to_value(event(9000, 5000)).unwrap();
+        assert!(json.get("total_allocated").is_some() && json["gpu"]["total_bytes"].is_u64());
+    }
+
+    #[test]
+    fn test_setup_steps_list_only_missing_parts() {
+        let mut readiness = AppReadiness {
+            system_requirements_met: true,
+            whisper_model_cached: false,
+            llm_model_available: true,
+            llm_worker_running: false,
+            template_available: false,
+            setup_steps: Vec::new(),
+            user_data_sync_folder: None,
+        };
+        assert_eq!(pending_setup_steps(&readiness), vec!["whisper_model", "template"]);
+
+        readiness.whisper_model_cached = true;
+        readiness.template_available = true;
+        assert!(pending_setup_steps(&readiness).is_empty());
+    }
+
     #[tokio::test]
     async fn test_system_requirements_check() {
         let result = check_system_requirements().await;
@@ -0,0 +1,205 @@
+// Temp file commands - Where processing temp files go (configurable), and finding and removing
+// the ones earlier runs left behind (converted audio, recordings, grammar inputs, render content)
+use tauri::{command, AppHandle, Manager};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::fs;
+
+use crate::commands::template_commands::TEMP_CONTENT_PATH;
+
+/// Name prefixes and extensions of the temp files the app writes
+const TEMP_FILE_PATTERNS: &[(&str, &str)] = &[
+    ("grammar_input_", "txt"),
+    ("whisper_input_", "wav"),
+    ("recording_", "webm"),
+    ("converted_", "wav"),
+    ("retranscribe_", "wav"),
+    ("range_", "wav"),
+    ("levels_", "wav"),
+    ("support_", "docx"),
+];
+const TEMP_FILE_NAMES: &[&str] = &["temp_content.json"];
+
+/// Files changed more recently may still be in use by a running job
+const MIN_ORPHAN_AGE: Duration = Duration::from_secs(10 * 60);
+
+/// Where audio conversion, recordings and chunks go; stored in user-data/settings/temp_dir.json.
+/// Unset means the system temp directory.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct TempDirSettings {
+    #[serde(default)]
+    pub temp_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct OrphanedTempFile {
+    pub path: String,
+    pub size: u64,
+    pub age_hours: f64,
+}
+
+fn is_app_temp_file(name: &str) -> bool {
+    TEMP_FILE_NAMES.contains(&name)
+        || TEMP_FILE_PATTERNS.iter().any(|(prefix, extension)| {
+            name.starts_with(prefix)
+                && Path::new(name).extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case(extension))
+        })
+}
+
+/// App temp files in the given directories (not recursive) older than `min_age`, oldest first
+pub fn find_orphaned_temp_files(dirs: &[PathBuf], now: SystemTime, min_age: Duration) -> Vec<OrphanedTempFile> {
+    let mut files: Vec<OrphanedTempFile> = dirs.iter()
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|e| e.ok()))
+        .filter(|entry| is_app_temp_file(&entry.file_name().to_string_lossy()))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok().filter(|m| m.is_file())?;
+            let age = now.duration_since(metadata.modified().ok()?).unwrap_or_default();
+            (age >= min_age).then(|| OrphanedTempFile {
+                path: entry.path().to_string_lossy().to_string(),
+                size: metadata.len(),
+                age_hours: age.as_secs_f64() / 3600.0,
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| b.age_hours.total_cmp(&a.age_hours));
+    files.dedup_by(|a, b| a.path == b.path);
+    files
+}
+
+fn get_temp_dir_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("temp_dir.json"))
+}
+
+fn load_temp_dir_settings() -> TempDirSettings {
+    get_temp_dir_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Create the directory if needed and write a probe file into it
+pub fn check_temp_dir_writable(dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(dir)
+        .map_err(|e| format!("Ordner {} kann nicht angelegt werden: {}", dir.display(), e))?;
+    let probe = dir.join(format!(".write_test_{}", uuid::Uuid::new_v4().simple()));
+    fs::write(&probe, b"ok")
+        .map_err(|e| format!("In den Ordner {} kann nicht geschrieben werden: {}", dir.display(), e))?;
+    let _ = fs::remove_file(&probe);
+    Ok(())
+}
+
+/// The configured directory (created if missing), or the system temp if unset or unusable
+pub fn temp_dir_from(settings: &TempDirSettings) -> PathBuf {
+    match settings.temp_dir.as_deref().filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => match fs::create_dir_all(dir) {
+            Ok(()) => PathBuf::from(dir),
+            Err(e) => {
+                println!("⚠️ Temp directory {} unusable ({}), using system temp", dir, e);
+                std::env::temp_dir()
+            }
+        },
+        None => std::env::temp_dir(),
+    }
+}
+
+/// Directory for audio conversion, recordings and chunks
+pub fn processing_temp_dir() -> PathBuf {
+    temp_dir_from(&load_temp_dir_settings())
+}
+
+/// Called at startup: a configured temp directory that is not writable is reported, not used
+pub fn validate_temp_dir_setting() -> Result<(), String> {
+    match load_temp_dir_settings().temp_dir.filter(|dir| !dir.trim().is_empty()) {
+        Some(dir) => check_temp_dir_writable(Path::new(&dir)),
+        None => Ok(()),
+    }
+}
+
+/// System temp dir, the configured temp dir, the app's temp dir and the folder of the render content file
+fn temp_dirs(app: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs = vec![std::env::temp_dir()];
+    if let Some(dir) = load_temp_dir_settings().temp_dir {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Ok(app_data_dir) = app.path().app_data_dir() {
+        dirs.push(app_data_dir.join("temp"));
+    }
+    if let Some(parent) = Path::new(TEMP_CONTENT_PATH).parent() {
+        dirs.push(parent.to_path_buf());
+    }
+    dirs
+}
+
+/// Temp files left behind by earlier runs, for the UI to show and offer to clean up
+#[command]
+pub async fn list_orphaned_temp_files(app: AppHandle) -> Result<Vec<OrphanedTempFile>, String> {
+    Ok(find_orphaned_temp_files(&temp_dirs(&app), SystemTime::now(), MIN_ORPHAN_AGE))
+}
+
+/// Delete orphaned temp files: the given paths, or all of them. Paths that are not (or no longer)
+/// orphaned app temp files are skipped. Returns the number of deleted files.
+#[command]
+pub async fn cleanup_orphaned_temp_files(app: AppHandle, paths: Option<Vec<String>>) -> Result<usize, String> {
+    let orphaned = find_orphaned_temp_files(&temp_dirs(&app), SystemTime::now(), MIN_ORPHAN_AGE);
+    let deleted = orphaned.iter()
+        .filter(|file| paths.as_ref().is_none_or(|paths| paths.contains(&file.path)))
+        .filter(|file| fs::remove_file(&file.path).is_ok())
+        .count();
+    println!("Deleted {} orphaned temp file(s)", deleted);
+    Ok(deleted)
+}
+
+/// Get the temp directory setting
+#[command]
+pub async fn get_temp_dir_settings() -> Result<TempDirSettings, String> {
+    Ok(load_temp_dir_settings())
+}
+
+/// Save the temp directory setting; a directory that cannot be written to is refused
+#[command]
+pub async fn save_temp_dir_settings(settings: TempDirSettings) -> Result<(), String> {
+    if let Some(dir) = settings.temp_dir.as_deref().filter(|dir| !dir.trim().is_empty()) {
+        check_temp_dir_writable(Path::new(dir))?;
+    }
+
+    let path = get_temp_dir_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize temp directory settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write temp directory settings: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_old_temp_files_are_listed_and_recent_ones_are_not() {
+        let dir = std::env::temp_dir().join(format!("orphaned_temp_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let two_hours_ago = SystemTime::now() - Duration::from_secs(2 * 3600);
+        for name in ["grammar_input_1.txt", "whisper_input_2.wav", "notizen.txt"] {
+            fs::write(dir.join(name), b"abc").unwrap();
+        }
+        for name in ["grammar_input_1.txt", "notizen.txt"] {
+            fs::File::options().write(true).open(dir.join(name)).unwrap().set_modified(two_hours_ago).unwrap();
+        }
+
+        let found = find_orphaned_temp_files(std::slice::from_ref(&dir), SystemTime::now(), MIN_ORPHAN_AGE);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found.len(), 1, "{:?}", found);
+        assert!(found[0].path.ends_with("grammar_input_1.txt"));
+        assert_eq!(found[0].size, 3);
+        assert!((found[0].age_hours - 2.0).abs() < 0.1);
+        assert!(is_app_temp_file("temp_content.json") && !is_app_temp_file("recording_1.wav"));
+    }
+}
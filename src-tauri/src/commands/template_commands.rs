@@ -1,21 +1,51 @@
 // Template extraction and DOCX rendering commands
-use tauri::{command, AppHandle};
+use tauri::{command, AppHandle, Emitter, Window};
 use tauri_plugin_dialog::DialogExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::fs;
+use std::io::BufReader;
+use once_cell::sync::Lazy;
+use regex::Regex;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::commands::backup_commands::backup_before_overwrite;
+use crate::commands::document_commands::{extract_styles_xml, heading_contrast_warnings};
+use crate::commands::signature_commands::{append_signature_to_docx, select_signature_blocks};
+use crate::commands::placeholder_commands::{placeholder_values, resolve_placeholders_in_docx, UnresolvedPlaceholderMode};
+use crate::commands::session_commands::{
+    load_session_structured_content, mark_session_exported, session_status, write_session_structured_content, SessionStatus,
+};
+use crate::commands::quality_gate_commands::{run_quality_gate, QualityGateFailure};
+use crate::commands::consistency_commands::consistency_warnings;
+use crate::commands::anchor_commands::build_template_spec_from_folder;
+use crate::script_locator::{locate_script, TEMPLATE_EXTRACTOR_SCRIPT, DOCX_RENDERER_SCRIPT};
+use crate::cloud_sync::{ensure_local, rename_with_retry, write_with_retry};
+use crate::error_classifier::explain_error;
+use crate::docx_patterns::{BASED_ON, RUN_FONT, RUN_SIZE, STYLE, STYLE_ID, STYLE_NAME};
+
+/// Content handed to the DOCX renderer; removed after rendering
+pub(crate) const TEMP_CONTENT_PATH: &str = r"C:\Users\kalin\Desktop\gutachten-assistant\temp_content.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct TemplateSpec {
+    #[serde(default)]
     pub version: String,
+    #[serde(default)]
     pub family_id: String,
+    #[serde(default)]
     pub family_name: String,
+    #[serde(default)]
     pub anchors: Vec<Value>,
+    #[serde(default)]
     pub skeleton: Vec<Value>,
+    #[serde(default)]
     pub style_roles: Value,
+    #[serde(default)]
     pub quality_metrics: Value,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,  // kopfzeile, fusszeile, render_rules, ... kept as they are
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -34,6 +64,289 @@ pub struct RenderResult {
     pub output_path: Option<String>,
     pub unclear_count: usize,
     pub missing_sections: Vec<String>,
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    #[serde(default)]
+    pub quality_gate_failure: Option<QualityGateFailure>,  // Set when the gate blocked the export
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchRenderProgress {
+    pub current: usize,
+    pub total: usize,
+    pub session_id: String,
+    pub stage: String,  // "rendering", "done" or "failed"
+    pub message: String,
+}
+
+/// Outcome of one session in a batch render
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchRenderItem {
+    pub session_id: String,
+    pub success: bool,
+    pub output_path: Option<String>,
+    pub unclear_count: usize,
+    pub missing_sections: Vec<String>,
+    pub warnings: Vec<String>,
+    pub validation_ok: bool,  // No missing sections and no style warnings
+    pub error: Option<String>,
+    pub quality_gate_failure: Option<QualityGateFailure>,
+}
+
+/// How a style role of the spec maps onto a style of the base DOCX
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RoleResolution {
+    pub role: String,
+    pub style_name: String,        // Style as referenced in the spec ("Heading 1")
+    pub style_id: Option<String>,  // Matching w:styleId in styles.xml ("Heading1")
+    pub found: bool,
+    pub effective_font: Option<String>,  // After following basedOn and docDefaults
+    pub effective_size: Option<f32>,     // pt
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TemplateFamilyInfo {
+    pub family_id: String,
+    pub family_name: String,
+    pub is_active: bool,
+    pub anchors_count: usize,
+    pub slot_count: usize,
+    pub spec_path: String,
+}
+
+/// Location the extractor and the Qwen structurer use for the single (legacy) spec
+const LEGACY_SPEC_PATH: &str = r"C:\Users\kalin\Desktop\gutachten-assistant\template_output\template_spec.json";
+
+/// The stored template families: one spec per family in `specs_dir`, the active family's ID in
+/// a marker file next to it, and a copy of the active spec at the legacy location
+struct TemplateFamilyStore {
+    specs_dir: PathBuf,
+    legacy_spec_path: PathBuf,
+}
+
+impl TemplateFamilyStore {
+    /// The store in user-data/templates/specs
+    fn open() -> Result<Self, String> {
+        let app_dir = std::env::current_dir()
+            .map_err(|e| format!("Failed to get current directory: {}", e))?;
+        Ok(TemplateFamilyStore {
+            specs_dir: app_dir.join("user-data").join("templates").join("specs"),
+            legacy_spec_path: PathBuf::from(LEGACY_SPEC_PATH),
+        })
+    }
+
+    /// Path of the file storing the active family ID
+    fn active_family_marker_path(&self) -> Result<PathBuf, String> {
+        Ok(self.specs_dir
+            .parent()
+            .map(|p| p.join("active_family"))
+            .ok_or("Invalid templates directory")?)
+    }
+
+    /// Spec path of a family (family IDs are restricted to safe characters)
+    fn family_spec_path(&self, family_id: &str) -> Result<PathBuf, String> {
+        if family_id.is_empty()
+            || !family_id.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(format!("Invalid template family ID: {}", family_id));
+        }
+        Ok(self.specs_dir.join(format!("{}.json", family_id)))
+    }
+
+    fn active_family_id(&self) -> Result<Option<String>, String> {
+        let marker = self.active_family_marker_path()?;
+        if !marker.exists() {
+            return Ok(None);
+        }
+        let id = fs::read_to_string(&marker)
+            .map_err(|e| format!("Failed to read active template family: {}", e))?
+            .trim()
+            .to_string();
+        Ok(if id.is_empty() { None } else { Some(id) })
+    }
+
+    fn write_active_family_id(&self, family_id: &str) -> Result<(), String> {
+        let marker = self.active_family_marker_path()?;
+        if let Some(parent) = marker.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create templates directory: {}", e))?;
+        }
+        fs::write(&marker, family_id)
+            .map_err(|e| format!("Failed to write active template family: {}", e))?;
+
+        // Keep the legacy location in sync so qwen_structurer.py sees the active slots
+        let spec_path = self.family_spec_path(family_id)?;
+        if spec_path.exists() {
+            if let Some(parent) = self.legacy_spec_path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            if let Err(e) = fs::copy(&spec_path, &self.legacy_spec_path) {
+                println!("[RUST] Warning: Could not update legacy template spec: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Store a spec as its family's file and return the family ID
+    fn store_family_spec(&self, spec: &Value, family_id: Option<&str>) -> Result<(String, PathBuf), String> {
+        let family_id = family_id.map(String::from).unwrap_or_else(|| spec_family_id(spec));
+        let spec_path = self.family_spec_path(&family_id)?;
+
+        fs::create_dir_all(&self.specs_dir)
+            .map_err(|e| format!("Failed to create template specs directory: {}", e))?;
+
+        let json = serde_json::to_string_pretty(spec)
+            .map_err(|e| format!("Failed to serialize template spec: {}", e))?;
+        fs::write(&spec_path, json)
+            .map_err(|e| format!("Failed to write template spec: {}", e))?;
+
+        Ok((family_id, spec_path))
+    }
+
+    /// Move the pre-family single spec into the per-family layout (runs once, when no family exists yet)
+    fn migrate_legacy_template_spec(&self) -> Result<(), String> {
+        let has_families = fs::read_dir(&self.specs_dir)
+            .map(|entries| entries.flatten().any(|e| e.path().extension().is_some_and(|ext| ext == "json")))
+            .unwrap_or(false);
+
+        if has_families || !self.legacy_spec_path.exists() {
+            return Ok(());
+        }
+
+        let content = fs::read_to_string(&self.legacy_spec_path)
+            .map_err(|e| format!("Failed to read legacy template spec: {}", e))?;
+        let spec: Value = serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse legacy template spec: {}", e))?;
+
+        let (family_id, spec_path) = self.store_family_spec(&spec, None)?;
+        if self.active_family_id()?.is_none() {
+            self.write_active_family_id(&family_id)?;
+        }
+
+        println!("[RUST] Migrated legacy template spec to: {:?}", spec_path);
+        Ok(())
+    }
+
+    /// Resolve the spec path of the given family, or of the active family if none is given
+    fn resolve_family_spec_path(&self, family_id: Option<String>) -> Result<PathBuf, String> {
+        self.migrate_legacy_template_spec()?;
+
+        let family_id = match family_id {
+            Some(id) => id,
+            None => self.active_family_id()?
+                .ok_or("No template spec found. Please extract a template first.")?,
+        };
+
+        let spec_path = self.family_spec_path(&family_id)?;
+        if !spec_path.exists() {
+            return Err(format!("Template family not found: {}", family_id));
+        }
+        Ok(spec_path)
+    }
+
+    /// All stored families, sorted by name
+    fn list_families(&self) -> Result<Vec<TemplateFamilyInfo>, String> {
+        self.migrate_legacy_template_spec()?;
+        self.read_families()
+    }
+
+    /// The families in `specs_dir` without migrating the legacy spec first
+    fn read_families(&self) -> Result<Vec<TemplateFamilyInfo>, String> {
+        if !self.specs_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let active_family = self.active_family_id()?;
+        let entries = fs::read_dir(&self.specs_dir)
+            .map_err(|e| format!("Failed to read template specs directory: {}", e))?;
+
+        let mut families = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let family_id = match path.file_stem().and_then(|s| s.to_str()) {
+                Some(id) => id.to_string(),
+                None => continue,
+            };
+
+            let spec: Value = match fs::read_to_string(&path).ok().and_then(|c| serde_json::from_str(&c).ok()) {
+                Some(spec) => spec,
+                None => {
+                    println!("[RUST] Warning: Skipping unreadable template spec: {:?}", path);
+                    continue;
+                }
+            };
+
+            let family_name = spec.get("family_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or(&family_id)
+                .to_string();
+            let anchors_count = spec.get("anchors")
+                .and_then(|a| a.as_array())
+                .map(|a| a.len())
+                .unwrap_or(0);
+            let slot_count = spec.get("skeleton")
+                .and_then(|s| s.as_array())
+                .map(|arr| arr.iter().filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("slot")).count())
+                .unwrap_or(0);
+
+            families.push(TemplateFamilyInfo {
+                is_active: active_family.as_deref() == Some(family_id.as_str()),
+                family_id,
+                family_name,
+                anchors_count,
+                slot_count,
+                spec_path: path.to_string_lossy().to_string(),
+            });
+        }
+
+        families.sort_by(|a, b| a.family_name.cmp(&b.family_name));
+        Ok(families)
+    }
+
+    /// Delete a family's spec; if it was active, another family becomes active
+    fn delete_family(&self, family_id: &str) -> Result<(), String> {
+        let spec_path = self.resolve_family_spec_path(Some(family_id.to_string()))?;
+        fs::remove_file(&spec_path)
+            .map_err(|e| format!("Failed to delete template family: {}", e))?;
+
+        if self.active_family_id()?.as_deref() == Some(family_id) {
+            // Not list_families: with no family left it would migrate the legacy copy back
+            match self.read_families()?.first() {
+                Some(next) => self.write_active_family_id(&next.family_id)?,
+                None => {
+                    let _ = fs::remove_file(self.active_family_marker_path()?);
+                    // The legacy copy would otherwise be migrated back on the next lookup
+                    let _ = fs::remove_file(&self.legacy_spec_path);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Read the family ID from a spec, falling back to "default"
+fn spec_family_id(spec: &Value) -> String {
+    spec.get("family_id")
+        .and_then(|v| v.as_str())
+        .map(|id| id.trim()
+            .chars()
+            .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+            .collect::<String>())
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+pub(crate) fn load_family_spec(family_id: Option<String>) -> Result<Value, String> {
+    let spec_path = TemplateFamilyStore::open()?.resolve_family_spec_path(family_id)?;
+
+    let content = fs::read_to_string(&spec_path)
+        .map_err(|e| format!("Failed to read template spec: {}", e))?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse template spec: {}", e))
 }
 
 /// Extract template from example Gutachten documents
@@ -45,30 +358,46 @@ pub async fn extract_template(
     println!("[RUST] Extracting template from: {}", input_folder);
 
     let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
-    let script_path = r"C:\Users\kalin\Desktop\gutachten-assistant\template_extractor.py";
+    let script_path = locate_script(TEMPLATE_EXTRACTOR_SCRIPT).ok();
 
     let output_dir = output_folder.unwrap_or_else(|| {
         r"C:\Users\kalin\Desktop\gutachten-assistant\template_output".to_string()
     });
 
-    // Run template extractor
-    let output = Command::new(python_exe)
-        .args(&[script_path, "extract", &input_folder, &output_dir])
-        .env("PYTHONIOENCODING", "utf-8")
-        .output()
-        .map_err(|e| format!("Failed to run template extractor: {}", e))?;
+    // Migrate an older single spec before the extractor overwrites it
+    let store = TemplateFamilyStore::open()?;
+    store.migrate_legacy_template_spec()?;
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    let spec_path = PathBuf::from(&output_dir).join("template_spec.json");
 
-    println!("[RUST] Extractor stderr: {}", stderr);
+    if let Some(script_path) = script_path.filter(|_| Path::new(python_exe).exists()) {
+        // Run template extractor
+        let output = Command::new(python_exe)
+            .arg(&script_path)
+            .args(["extract", &input_folder, &output_dir])
+            .env("PYTHONIOENCODING", "utf-8")
+            .output()
+            .map_err(|e| format!("Failed to run template extractor: {}", e))?;
 
-    if !output.status.success() {
-        return Err(format!("Template extraction failed: {}", stderr));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        println!("[RUST] Extractor stderr: {}", stderr);
+
+        if !output.status.success() {
+            return Err(explain_error(format!("Template extraction failed: {}", stderr)));
+        }
+    } else {
+        // No venv on this machine: anchors and order only, marked engine "rust" in quality_metrics
+        println!("[RUST] Python extractor not available, using built-in anchor extraction");
+        let spec = build_template_spec_from_folder(&input_folder)?;
+        fs::create_dir_all(&output_dir)
+            .map_err(|e| format!("Failed to create output directory: {}", e))?;
+        let json = serde_json::to_string_pretty(&spec)
+            .map_err(|e| format!("Failed to serialize template spec: {}", e))?;
+        fs::write(&spec_path, json)
+            .map_err(|e| format!("Failed to write template spec: {}", e))?;
     }
 
     // Parse the output JSON
-    let spec_path = PathBuf::from(&output_dir).join("template_spec.json");
 
     if spec_path.exists() {
         let spec_content = fs::read_to_string(&spec_path)
@@ -87,10 +416,15 @@ pub async fn extract_template(
             .and_then(|d| d.as_u64())
             .unwrap_or(0) as usize;
 
+        // Store as its own family and make it the active one
+        let (family_id, family_spec_path) = store.store_family_spec(&spec, None)?;
+        store.write_active_family_id(&family_id)?;
+        println!("[RUST] Template family '{}' stored at: {:?}", family_id, family_spec_path);
+
         Ok(ExtractionResult {
             success: true,
             message: format!("Template extracted successfully. Found {} anchors from {} documents.", anchors_found, docs_analyzed),
-            template_spec_path: Some(spec_path.to_string_lossy().to_string()),
+            template_spec_path: Some(family_spec_path.to_string_lossy().to_string()),
             anchors_found,
             documents_analyzed: docs_analyzed,
         })
@@ -99,30 +433,42 @@ pub async fn extract_template(
     }
 }
 
-/// Get the current template spec
+/// Get the template spec of the active (or given) family
 #[command]
-pub async fn get_template_spec() -> Result<Value, String> {
-    let spec_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\template_output\template_spec.json");
-
-    if !spec_path.exists() {
-        return Err("No template spec found. Please extract a template first.".to_string());
-    }
-
-    let content = fs::read_to_string(&spec_path)
-        .map_err(|e| format!("Failed to read template spec: {}", e))?;
-
-    serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse template spec: {}", e))
+pub async fn get_template_spec(family_id: Option<String>) -> Result<Value, String> {
+    load_family_spec(family_id)
 }
 
 /// Render a DOCX document from structured content with save dialog
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub async fn render_gutachten_docx(
     app: AppHandle,
     content_json: Value,
     template_spec_path: Option<String>,
     base_template_path: Option<String>,
+    family_id: Option<String>,
+    append_signature: Option<bool>,
+    signature_names: Option<Vec<String>>,
+    session_id: Option<String>,
+    unresolved_placeholders: Option<UnresolvedPlaceholderMode>,
+    enforce_quality_gate: Option<bool>,
+    force_export: Option<bool>,
 ) -> Result<RenderResult, String> {
+    // An explicit spec path wins over the family lookup
+    let spec_path = match template_spec_path {
+        Some(path) => path,
+        None => TemplateFamilyStore::open()?.resolve_family_spec_path(family_id)?.to_string_lossy().to_string(),
+    };
+
+    // Session exports must pass the quality gate before a file is written
+    if let Some(id) = &session_id {
+        let spec = read_spec(&spec_path);
+        if let Some(failure) = run_quality_gate(id, spec.as_ref(), enforce_quality_gate, force_export.unwrap_or(false))? {
+            return Ok(blocked_render_result(failure));
+        }
+    }
+
     // Generate default filename with timestamp
     let timestamp = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S");
     let default_filename = format!("Gutachten_{}.docx", timestamp);
@@ -146,30 +492,114 @@ pub async fn render_gutachten_docx(
     };
     println!("[RUST] Rendering Gutachten DOCX to: {}", output_path);
 
-    let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
-    let script_path = r"C:\Users\kalin\Desktop\gutachten-assistant\docx_renderer.py";
+    let signature_blocks = if append_signature.unwrap_or(false) {
+        select_signature_blocks(signature_names.as_deref())?
+    } else {
+        Vec::new()
+    };
 
-    let spec_path = template_spec_path.unwrap_or_else(|| {
-        r"C:\Users\kalin\Desktop\gutachten-assistant\template_output\template_spec.json".to_string()
-    });
+    let placeholder_values = placeholder_values(session_id.as_deref())?;
+
+    backup_before_overwrite(Path::new(&output_path))?;
+    let mut result = run_docx_renderer(&content_json, &spec_path, &output_path, base_template_path)?;
+    if !signature_blocks.is_empty() {
+        append_signature_to_docx(Path::new(&output_path), &signature_blocks)?;
+    }
+    result.warnings.extend(finish_placeholders(
+        Path::new(&output_path),
+        &placeholder_values,
+        unresolved_placeholders.unwrap_or_default(),
+    )?);
+    result.warnings.extend(consistency_warnings(read_spec(&spec_path)));
+    // Colors of the base template are honored; unreadably light headings only warn
+    match heading_contrast_warnings(Path::new(&output_path)) {
+        Ok(warnings) => result.warnings.extend(warnings),
+        Err(e) => println!("[RUST] Heading color check skipped: {}", e),
+    }
+    if let Some(id) = &session_id {
+        mark_session_exported(id)?;
+    }
+    Ok(result)
+}
+
+fn read_spec(spec_path: &str) -> Option<Value> {
+    fs::read_to_string(spec_path).ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+}
+
+/// Result of an export the quality gate refused (no file was written)
+fn blocked_render_result(failure: QualityGateFailure) -> RenderResult {
+    let criteria: Vec<&str> = failure.unmet.iter().map(|c| c.message.as_str()).collect();
+    RenderResult {
+        success: false,
+        message: format!("Qualitätsprüfung nicht bestanden: {}", criteria.join("; ")),
+        output_path: None,
+        unclear_count: 0,
+        missing_sections: Vec::new(),
+        warnings: Vec::new(),
+        quality_gate_failure: Some(failure),
+    }
+}
+
+/// Resolve placeholders in a rendered file; an aborted export removes the file again
+fn finish_placeholders(
+    output_path: &Path,
+    values: &std::collections::BTreeMap<String, String>,
+    mode: UnresolvedPlaceholderMode,
+) -> Result<Vec<String>, String> {
+    match resolve_placeholders_in_docx(output_path, values, mode) {
+        Ok(unresolved) => Ok(unresolved.iter()
+            .map(|name| format!("Platzhalter {{{{{}}}}} ohne Wert", name))
+            .collect()),
+        Err(e) => {
+            let _ = fs::remove_file(output_path);
+            Err(e)
+        }
+    }
+}
+
+/// Run docx_renderer.py for one document and collect unclear/missing counts and style warnings
+fn run_docx_renderer(
+    content_json: &Value,
+    spec_path: &str,
+    output_path: &str,
+    base_template_path: Option<String>,
+) -> Result<RenderResult, String> {
+    let python_exe = r"C:\Users\kalin\Desktop\gutachten-assistant\llama_venv_gpu\Scripts\python.exe";
+    let script_path = locate_script(DOCX_RENDERER_SCRIPT)?;
 
     // Write content JSON to temp file
-    let temp_content_path = r"C:\Users\kalin\Desktop\gutachten-assistant\temp_content.json";
-    let content_str = serde_json::to_string_pretty(&content_json)
+    let temp_content_path = TEMP_CONTENT_PATH;
+    let content_str = serde_json::to_string_pretty(content_json)
         .map_err(|e| format!("Failed to serialize content: {}", e))?;
     fs::write(temp_content_path, &content_str)
         .map_err(|e| format!("Failed to write temp content: {}", e))?;
 
     // Build command args
     let mut args = vec![
-        script_path.to_string(),
+        script_path.to_string_lossy().to_string(),
         "render".to_string(),
-        spec_path.clone(),
+        spec_path.to_string(),
         temp_content_path.to_string(),
-        output_path.clone(),
+        output_path.to_string(),
     ];
 
+    // Unresolved roles would silently fall back to Normal in the rendered document
+    let mut warnings = Vec::new();
     if let Some(base_path) = base_template_path {
+        if let Some(spec) = read_spec(spec_path) {
+            match resolve_style_roles_for_docx(&spec, Path::new(&base_path)) {
+                Ok(resolutions) => {
+                    for resolution in resolutions.iter().filter(|r| !r.found) {
+                        warnings.push(format!(
+                            "Formatvorlage '{}' für Rolle {} fehlt in der Basisvorlage",
+                            resolution.style_name, resolution.role
+                        ));
+                    }
+                }
+                Err(e) => warnings.push(format!("Style roles could not be checked: {}", e)),
+            }
+        }
         args.push(base_path);
     }
 
@@ -187,7 +617,7 @@ pub async fn render_gutachten_docx(
     println!("[RUST] Renderer stderr: {}", stderr);
 
     if !output.status.success() {
-        return Err(format!("DOCX rendering failed: {}", stderr));
+        return Err(explain_error(format!("DOCX rendering failed: {}", stderr)));
     }
 
     // Extract unclear count and missing sections from content
@@ -204,33 +634,278 @@ pub async fn render_gutachten_docx(
     Ok(RenderResult {
         success: true,
         message: "DOCX rendered successfully".to_string(),
-        output_path: Some(output_path),
+        output_path: Some(output_path.to_string()),
         unclear_count,
         missing_sections,
+        warnings,
+        quality_gate_failure: None,
     })
 }
 
-/// Check if template has been extracted
-#[command]
-pub async fn is_template_ready() -> Result<bool, String> {
-    let spec_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\template_output\template_spec.json");
-    Ok(spec_path.exists())
+const DEFAULT_BATCH_FILENAME_PATTERN: &str = "Gutachten_{session_id}";
+
+/// Build an output file name from a pattern with {session_id}, {index} and {date} placeholders
+fn batch_output_filename(pattern: &str, session_id: &str, index: usize, date: &str) -> String {
+    let pattern = if pattern.trim().is_empty() { DEFAULT_BATCH_FILENAME_PATTERN } else { pattern };
+    let name: String = pattern
+        .replace("{session_id}", session_id)
+        .replace("{index}", &format!("{:02}", index))
+        .replace("{date}", date)
+        .chars()
+        .map(|c| if "<>:\"/\\|?*".contains(c) { '_' } else { c })
+        .collect();
+
+    if name.to_lowercase().ends_with(".docx") { name } else { format!("{}.docx", name) }
 }
 
-/// Get list of available section slots from template
+/// Append _2, _3, ... until the name is free, so patterns without {session_id} don't overwrite
+fn unique_output_path(output_dir: &Path, filename: &str) -> PathBuf {
+    let candidate = output_dir.join(filename);
+    if !candidate.exists() {
+        return candidate;
+    }
+    let stem = filename.trim_end_matches(".docx");
+    (2..)
+        .map(|n| output_dir.join(format!("{}_{}.docx", stem, n)))
+        .find(|path| !path.exists())
+        .unwrap_or(candidate)
+}
+
+fn render_session(
+    session_id: &str,
+    spec_path: &str,
+    output_path: &Path,
+    enforce_quality_gate: Option<bool>,
+    force: bool,
+) -> Result<RenderResult, String> {
+    let status = session_status(session_id)?;
+    if status < SessionStatus::Strukturiert {
+        return Err(format!("Sitzung {} ist noch nicht strukturiert (Status: {:?})", session_id, status));
+    }
+    if let Some(failure) = run_quality_gate(session_id, read_spec(spec_path).as_ref(), enforce_quality_gate, force)? {
+        return Ok(blocked_render_result(failure));
+    }
+
+    let mut structured = load_session_structured_content(session_id)?;
+    let content_json = serde_json::to_value(&structured.content)
+        .map_err(|e| format!("Failed to serialize content: {}", e))?;
+    let values = placeholder_values(Some(session_id))?;
+    let mut result = run_docx_renderer(&content_json, spec_path, &output_path.to_string_lossy(), None)?;
+    result.warnings.extend(finish_placeholders(output_path, &values, UnresolvedPlaceholderMode::Abort)?);
+
+    structured.status = SessionStatus::Exportiert;
+    structured.updated_at = chrono::Utc::now().to_rfc3339();
+    write_session_structured_content(&structured)?;
+    Ok(result)
+}
+
+/// Render several structured sessions into one folder without a dialog per document.
+/// Each session is rendered independently; a failure is reported in its result only.
 #[command]
-pub async fn get_template_slots() -> Result<Vec<Value>, String> {
-    let spec_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\template_output\template_spec.json");
+pub async fn render_sessions_batch(
+    window: Window,
+    session_ids: Vec<String>,
+    output_dir: String,
+    filename_pattern: Option<String>,
+    enforce_quality_gate: Option<bool>,
+    force_export: Option<bool>,
+) -> Result<Vec<BatchRenderItem>, String> {
+    let output_dir = PathBuf::from(&output_dir);
+    fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+    let spec_path = TemplateFamilyStore::open()?.resolve_family_spec_path(None)?.to_string_lossy().to_string();
+    let pattern = filename_pattern.unwrap_or_default();
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let total = session_ids.len();
+
+    let mut results = Vec::new();
+    for (i, session_id) in session_ids.iter().enumerate() {
+        let emit = |stage: &str, message: String| {
+            let _ = window.emit("batch_render_progress", BatchRenderProgress {
+                current: i + 1,
+                total,
+                session_id: session_id.clone(),
+                stage: stage.to_string(),
+                message,
+            });
+        };
+        emit("rendering", format!("Gutachten {} von {} wird erstellt...", i + 1, total));
 
-    if !spec_path.exists() {
-        return Err("No template spec found".to_string());
+        let filename = batch_output_filename(&pattern, session_id, i + 1, &date);
+        let output_path = unique_output_path(&output_dir, &filename);
+        println!("[RUST] Batch render {}/{}: {} -> {}", i + 1, total, session_id, output_path.display());
+
+        let item = match render_session(session_id, &spec_path, &output_path, enforce_quality_gate, force_export.unwrap_or(false)) {
+            Ok(result) if result.quality_gate_failure.is_some() => {
+                emit("failed", result.message.clone());
+                BatchRenderItem {
+                    session_id: session_id.clone(),
+                    success: false,
+                    output_path: None,
+                    unclear_count: 0,
+                    missing_sections: Vec::new(),
+                    warnings: Vec::new(),
+                    validation_ok: false,
+                    error: Some(result.message),
+                    quality_gate_failure: result.quality_gate_failure,
+                }
+            }
+            Ok(result) => {
+                emit("done", format!("{} erstellt", filename));
+                BatchRenderItem {
+                    session_id: session_id.clone(),
+                    success: true,
+                    output_path: result.output_path,
+                    unclear_count: result.unclear_count,
+                    validation_ok: result.missing_sections.is_empty() && result.warnings.is_empty(),
+                    missing_sections: result.missing_sections,
+                    warnings: result.warnings,
+                    error: None,
+                    quality_gate_failure: None,
+                }
+            }
+            Err(e) => {
+                println!("[RUST] Batch render failed for {}: {}", session_id, e);
+                emit("failed", e.clone());
+                BatchRenderItem {
+                    session_id: session_id.clone(),
+                    success: false,
+                    output_path: None,
+                    unclear_count: 0,
+                    missing_sections: Vec::new(),
+                    warnings: Vec::new(),
+                    validation_ok: false,
+                    error: Some(e),
+                    quality_gate_failure: None,
+                }
+            }
+        };
+        results.push(item);
     }
 
-    let content = fs::read_to_string(&spec_path)
-        .map_err(|e| format!("Failed to read template spec: {}", e))?;
+    Ok(results)
+}
 
-    let spec: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("Failed to parse template spec: {}", e))?;
+/// A paragraph/character style definition from styles.xml
+struct StyleDefinition {
+    style_id: String,
+    name: String,
+    based_on: Option<String>,
+    font: Option<String>,
+    size: Option<f32>,
+}
+
+/// Run properties every style inherits (docDefaults)
+static RUN_PROPERTIES_DEFAULT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"(?s)<w:rPrDefault>(.*?)</w:rPrDefault>"#).unwrap());
+
+fn parse_style_definitions(styles_xml: &str) -> Vec<StyleDefinition> {
+    STYLE.captures_iter(styles_xml)
+        .filter_map(|c| {
+            let style_id = STYLE_ID.captures(&c[1])?[1].to_string();
+            let body = &c[0];
+            Some(StyleDefinition {
+                name: STYLE_NAME.captures(body).map(|n| n[1].to_string()).unwrap_or_else(|| style_id.clone()),
+                based_on: BASED_ON.captures(body).map(|b| b[1].to_string()),
+                font: run_font(body),
+                size: run_size(body),
+                style_id,
+            })
+        })
+        .collect()
+}
+
+fn run_font(xml: &str) -> Option<String> {
+    RUN_FONT.captures(xml).map(|c| c[1].to_string())
+}
+
+fn run_size(xml: &str) -> Option<f32> {
+    // Word stores sizes in half-points
+    RUN_SIZE.captures(xml).and_then(|c| c[1].parse::<f32>().ok()).map(|v| v / 2.0)
+}
+
+/// Match case- and space-insensitively, since the spec uses UI names ("Heading 1")
+/// while styles.xml stores lowercase built-in names ("heading 1") and IDs ("Heading1")
+fn style_key(name: &str) -> String {
+    name.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase()
+}
+
+/// Style name referenced by a style_roles entry (plain string or {"style_name": ...})
+fn role_style_name(value: &Value) -> Option<String> {
+    match value {
+        Value::String(name) => Some(name.clone()),
+        Value::Object(map) => ["style_name", "style", "name"].iter()
+            .find_map(|key| map.get(*key).and_then(|v| v.as_str()))
+            .map(String::from),
+        _ => None,
+    }
+}
+
+fn resolve_style_roles_in_xml(style_roles: &Value, styles_xml: &str) -> Vec<RoleResolution> {
+    let styles = parse_style_definitions(styles_xml);
+    let defaults = RUN_PROPERTIES_DEFAULT.captures(styles_xml).map(|c| c[1].to_string()).unwrap_or_default();
+
+    let Some(roles) = style_roles.as_object() else { return Vec::new() };
+    roles.iter()
+        .filter_map(|(role, value)| {
+            let style_name = role_style_name(value)?;
+            let key = style_key(&style_name);
+            let style = styles.iter()
+                .find(|s| style_key(&s.name) == key || style_key(&s.style_id) == key);
+
+            let (mut font, mut size) = (None, None);
+            let mut current = style;
+            // Walk the basedOn chain (bounded in case of a cycle)
+            for _ in 0..10 {
+                let Some(definition) = current else { break };
+                font = font.or_else(|| definition.font.clone());
+                size = size.or(definition.size);
+                current = definition.based_on.as_ref()
+                    .and_then(|parent| styles.iter().find(|s| &s.style_id == parent));
+            }
+            if style.is_some() {
+                font = font.or_else(|| run_font(&defaults));
+                size = size.or_else(|| run_size(&defaults));
+            }
+
+            Some(RoleResolution {
+                role: role.clone(),
+                style_name,
+                style_id: style.map(|s| s.style_id.clone()),
+                found: style.is_some(),
+                effective_font: font,
+                effective_size: size,
+            })
+        })
+        .collect()
+}
+
+pub(crate) fn resolve_style_roles_for_docx(spec: &Value, base_docx: &Path) -> Result<Vec<RoleResolution>, String> {
+    let file = fs::File::open(base_docx)
+        .map_err(|e| format!("Failed to open base template: {}", e))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to read base template DOCX: {}", e))?;
+    let styles_xml = extract_styles_xml(&mut archive)?;
+
+    let style_roles = spec.get("style_roles").cloned().unwrap_or(Value::Null);
+    Ok(resolve_style_roles_in_xml(&style_roles, &styles_xml))
+}
+
+/// Check which style roles of a spec exist in the base DOCX and what formatting they produce
+#[command]
+pub async fn resolve_style_roles(spec: Value, base_docx: String) -> Result<Vec<RoleResolution>, String> {
+    resolve_style_roles_for_docx(&spec, Path::new(&base_docx))
+}
+
+/// Check if a template spec exists for the active (or given) family
+#[command]
+pub async fn is_template_ready(family_id: Option<String>) -> Result<bool, String> {
+    Ok(TemplateFamilyStore::open().and_then(|store| store.resolve_family_spec_path(family_id)).is_ok())
+}
+
+/// Get list of available section slots from template
+#[command]
+pub async fn get_template_slots(family_id: Option<String>) -> Result<Vec<Value>, String> {
+    let spec = load_family_spec(family_id)?;
 
     let slots: Vec<Value> = spec.get("skeleton")
         .and_then(|s| s.as_array())
@@ -245,29 +920,356 @@ pub async fn get_template_slots() -> Result<Vec<Value>, String> {
     Ok(slots)
 }
 
-/// Save the edited template spec to disk
+/// Save the edited template spec to disk (into the given family, else the active one)
 #[command]
-pub async fn save_template_spec(spec_json: String) -> Result<Value, String> {
-    let spec_path = PathBuf::from(r"C:\Users\kalin\Desktop\gutachten-assistant\template_output\template_spec.json");
-
+pub async fn save_template_spec(spec_json: String, family_id: Option<String>) -> Result<Value, String> {
     // Validate JSON
-    let _: Value = serde_json::from_str(&spec_json)
+    let spec: Value = serde_json::from_str(&spec_json)
         .map_err(|e| format!("Invalid JSON: {}", e))?;
 
-    // Ensure directory exists
-    if let Some(parent) = spec_path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("Failed to create directory: {}", e))?;
-    }
+    let store = TemplateFamilyStore::open()?;
+    store.migrate_legacy_template_spec()?;
+    let active_family = store.active_family_id()?;
+    let target_family = family_id.or_else(|| active_family.clone());
 
-    // Write to file
-    fs::write(&spec_path, &spec_json)
-        .map_err(|e| format!("Failed to write template spec: {}", e))?;
+    let (saved_family, spec_path) = store.store_family_spec(&spec, target_family.as_deref())?;
+
+    // Activate if nothing is active yet; refresh the legacy copy if this is the active family
+    if active_family.is_none() || active_family.as_deref() == Some(saved_family.as_str()) {
+        store.write_active_family_id(&saved_family)?;
+    }
 
     println!("[RUST] Template spec saved to: {:?}", spec_path);
 
     Ok(serde_json::json!({
         "success": true,
+        "family_id": saved_family,
         "path": spec_path.to_string_lossy()
     }))
 }
+
+/// ID of a skeleton item: "slot_id" for slots, "id" for fixed blocks
+fn skeleton_item_id(item: &Value) -> Option<&str> {
+    match item.get("type").and_then(|t| t.as_str()) {
+        Some("slot") => item.get("slot_id").and_then(|v| v.as_str()),
+        _ => item.get("id").and_then(|v| v.as_str()),
+    }
+}
+
+fn anchor_text(anchor: &Value) -> &str {
+    anchor.get("canonical_text").and_then(|v| v.as_str()).unwrap_or("")
+}
+
+impl TemplateSpec {
+    /// Invariants every edited spec has to keep: unique anchors and item IDs, slots that reference
+    /// an anchor, and required slots in the anchor order
+    pub fn validate(&self) -> Result<(), String> {
+        let mut anchor_ids: Vec<&str> = Vec::new();
+        let mut anchor_texts: Vec<&str> = Vec::new();
+        for anchor in &self.anchors {
+            let id = anchor.get("id").and_then(|v| v.as_str()).unwrap_or("");
+            let text = anchor_text(anchor);
+            if id.is_empty() || text.is_empty() {
+                return Err("Anker ohne ID oder Text".to_string());
+            }
+            if anchor_ids.contains(&id) || anchor_texts.contains(&text) {
+                return Err(format!("Anker doppelt vorhanden: {}", text));
+            }
+            anchor_ids.push(id);
+            anchor_texts.push(text);
+        }
+
+        let mut item_ids: Vec<&str> = Vec::new();
+        let mut last_required: Option<(usize, &str)> = None;  // (anchor position, section)
+        for item in &self.skeleton {
+            let item_type = item.get("type").and_then(|t| t.as_str()).unwrap_or("");
+            if item_type != "slot" && item_type != "fixed" {
+                return Err(format!("Unbekannter Skelett-Typ: '{}'", item_type));
+            }
+            let id = skeleton_item_id(item).filter(|id| !id.is_empty())
+                .ok_or("Skelett-Eintrag ohne ID")?;
+            if item_ids.contains(&id) {
+                return Err(format!("Skelett-Eintrag doppelt vorhanden: {}", id));
+            }
+            item_ids.push(id);
+
+            if item_type == "slot" {
+                let section = item.get("section_name").and_then(|v| v.as_str()).unwrap_or("");
+                let position = anchor_texts.iter().position(|text| *text == section)
+                    .ok_or_else(|| format!("Abschnitt '{}' verweist auf keinen Anker", section))?;
+                let optional = item.get("optional").and_then(|v| v.as_bool()).unwrap_or(false);
+                if !optional {
+                    if let Some((previous_position, previous)) = last_required {
+                        if position < previous_position {
+                            return Err(format!(
+                                "Pflichtabschnitt '{}' steht vor '{}' – Reihenfolge der Vorlage verletzt",
+                                section, previous
+                            ));
+                        }
+                    }
+                    last_required = Some((position, section));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert an item after the given index (`None` inserts at the start)
+    pub fn add_skeleton_item(&mut self, after_index: Option<usize>, item: Value) -> Result<(), String> {
+        let index = match after_index {
+            None => 0,
+            Some(i) if i < self.skeleton.len() => i + 1,
+            Some(i) => return Err(format!("Ungültige Position: {}", i)),
+        };
+        self.skeleton.insert(index, item);
+        Ok(())
+    }
+
+    pub fn remove_skeleton_item(&mut self, index: usize) -> Result<Value, String> {
+        if index >= self.skeleton.len() {
+            return Err(format!("Ungültige Position: {}", index));
+        }
+        Ok(self.skeleton.remove(index))
+    }
+
+    pub fn move_skeleton_item(&mut self, from: usize, to: usize) -> Result<(), String> {
+        if from >= self.skeleton.len() || to >= self.skeleton.len() {
+            return Err(format!("Ungültige Position: {} -> {}", from, to));
+        }
+        let item = self.skeleton.remove(from);
+        self.skeleton.insert(to, item);
+        Ok(())
+    }
+
+    /// Rename an anchor (by ID or heading text) together with its heading paragraphs and slots.
+    /// The old text stays a known variant so existing documents still match.
+    pub fn rename_anchor(&mut self, old: &str, new: &str) -> Result<(), String> {
+        let new = new.trim();
+        if new.is_empty() {
+            return Err("Neuer Ankertext darf nicht leer sein".to_string());
+        }
+        let anchor = self.anchors.iter_mut()
+            .find(|a| anchor_text(a) == old || a.get("id").and_then(|v| v.as_str()) == Some(old))
+            .ok_or_else(|| format!("Anker nicht gefunden: {}", old))?;
+        let old_text = anchor_text(anchor).to_string();
+
+        anchor["canonical_text"] = Value::String(new.to_string());
+        if let Some(variants) = anchor.get_mut("variants_seen").and_then(|v| v.as_array_mut()) {
+            if !variants.iter().any(|v| v.as_str() == Some(old_text.as_str())) {
+                variants.push(Value::String(old_text.clone()));
+            }
+        }
+
+        for item in &mut self.skeleton {
+            if item.get("section_name").and_then(|v| v.as_str()) == Some(old_text.as_str()) {
+                item["section_name"] = Value::String(new.to_string());
+            }
+            if let Some(paragraphs) = item.get_mut("paragraphs").and_then(|p| p.as_array_mut()) {
+                for paragraph in paragraphs {
+                    if paragraph.get("text").and_then(|v| v.as_str()) == Some(old_text.as_str()) {
+                        paragraph["text"] = Value::String(new.to_string());
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Apply an edit to a family's spec (the active one if none is given), validate the result and
+/// replace the file atomically, keeping the previous version as "<family>.json.bak"
+fn edit_family_spec<F>(family_id: Option<String>, edit: F) -> Result<TemplateSpec, String>
+where
+    F: FnOnce(&mut TemplateSpec) -> Result<(), String>,
+{
+    let store = TemplateFamilyStore::open()?;
+    let spec_path = store.resolve_family_spec_path(family_id)?;
+    ensure_local(&spec_path)?;
+    let content = fs::read_to_string(&spec_path)
+        .map_err(|e| format!("Failed to read template spec: {}", e))?;
+    let mut spec: TemplateSpec = serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse template spec: {}", e))?;
+
+    edit(&mut spec)?;
+    spec.validate()?;
+
+    let json = serde_json::to_string_pretty(&spec)
+        .map_err(|e| format!("Failed to serialize template spec: {}", e))?;
+    let temp_path = spec_path.with_extension("json.tmp");
+    write_with_retry(&temp_path, json)
+        .map_err(|e| format!("Failed to write template spec: {}", e))?;
+    fs::copy(&spec_path, spec_path.with_extension("json.bak"))
+        .map_err(|e| format!("Failed to back up template spec: {}", e))?;
+    rename_with_retry(&temp_path, &spec_path)
+        .map_err(|e| format!("Failed to replace template spec: {}", e))?;
+
+    // Refresh the legacy copy if the edited family is the active one
+    let edited_family = spec_path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    if store.active_family_id()?.as_deref() == Some(edited_family.as_str()) {
+        store.write_active_family_id(&edited_family)?;
+    }
+    Ok(spec)
+}
+
+/// Insert a slot or fixed block into the skeleton after `after_index` (at the start if omitted)
+#[command]
+pub async fn add_skeleton_item(after_index: Option<usize>, item: Value, family_id: Option<String>) -> Result<TemplateSpec, String> {
+    edit_family_spec(family_id, |spec| spec.add_skeleton_item(after_index, item))
+}
+
+#[command]
+pub async fn remove_skeleton_item(index: usize, family_id: Option<String>) -> Result<TemplateSpec, String> {
+    edit_family_spec(family_id, |spec| spec.remove_skeleton_item(index).map(|_| ()))
+}
+
+#[command]
+pub async fn move_skeleton_item(from: usize, to: usize, family_id: Option<String>) -> Result<TemplateSpec, String> {
+    edit_family_spec(family_id, |spec| spec.move_skeleton_item(from, to))
+}
+
+/// Rename a section heading everywhere in the spec
+#[command]
+pub async fn rename_anchor(old: String, new: String, family_id: Option<String>) -> Result<TemplateSpec, String> {
+    edit_family_spec(family_id, |spec| spec.rename_anchor(&old, &new))
+}
+
+/// List all stored template families
+#[command]
+pub async fn list_template_families() -> Result<Vec<TemplateFamilyInfo>, String> {
+    TemplateFamilyStore::open()?.list_families()
+}
+
+/// Make a stored template family the active one
+#[command]
+pub async fn set_active_template_family(family_id: String) -> Result<(), String> {
+    let store = TemplateFamilyStore::open()?;
+    store.resolve_family_spec_path(Some(family_id.clone()))?;
+    store.write_active_family_id(&family_id)?;
+    println!("[RUST] Active template family: {}", family_id);
+    Ok(())
+}
+
+/// Delete a stored template family; if it was active, another family becomes active
+#[command]
+pub async fn delete_template_family(family_id: String) -> Result<(), String> {
+    TemplateFamilyStore::open()?.delete_family(&family_id)?;
+    println!("[RUST] Template family deleted: {}", family_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_roles_resolve_through_based_on() {
+        let styles_xml = concat!(
+            r#"<w:styles><w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri"/><w:sz w:val="22"/></w:rPr></w:rPrDefault></w:docDefaults>"#,
+            r#"<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:rPr><w:rFonts w:ascii="Arial"/></w:rPr></w:style>"#,
+            r#"<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:rPr><w:b/><w:sz w:val="28"/></w:rPr></w:style>"#,
+            r#"</w:styles>"#,
+        );
+        let roles = serde_json::json!({"H1": "Heading 1", "BODY": "Normal", "BULLET": "List Bullet"});
+
+        let resolutions = resolve_style_roles_in_xml(&roles, styles_xml);
+        let find = |role: &str| resolutions.iter().find(|r| r.role == role).unwrap();
+
+        let h1 = find("H1");
+        assert_eq!(h1.style_id.as_deref(), Some("Heading1"));
+        assert_eq!(h1.effective_font.as_deref(), Some("Arial"));
+        assert_eq!(h1.effective_size, Some(14.0));
+        assert_eq!(find("BODY").effective_size, Some(11.0));
+        assert!(!find("BULLET").found);
+        assert_eq!(find("BULLET").effective_font, None);
+    }
+
+    #[test]
+    fn test_batch_filename_pattern() {
+        assert_eq!(batch_output_filename("", "s-1", 1, "2026-01-05"), "Gutachten_s-1.docx");
+        assert_eq!(
+            batch_output_filename("{date}_{index}: {session_id}", "abc", 3, "2026-01-05"),
+            "2026-01-05_03_ abc.docx"
+        );
+        assert_eq!(batch_output_filename("Befund.DOCX", "abc", 1, "2026-01-05"), "Befund.DOCX");
+    }
+
+    #[test]
+    fn test_skeleton_edits_keep_spec_valid() {
+        let anchor = |id: &str, text: &str| serde_json::json!({"id": id, "canonical_text": text, "variants_seen": [text]});
+        let slot = |id: &str, section: &str, optional: bool| serde_json::json!({"type": "slot", "slot_id": id, "section_name": section, "optional": optional});
+        let mut spec: TemplateSpec = serde_json::from_value(serde_json::json!({
+            "family_id": "default",
+            "anchors": [anchor("anamnese", "Anamnese"), anchor("befund", "Befund"), anchor("beurteilung", "Beurteilung")],
+            "skeleton": [
+                {"type": "fixed", "id": "anamnese_heading", "paragraphs": [{"text": "Anamnese", "style": "Normal"}]},
+                slot("anamnese_body", "Anamnese", false),
+                slot("befund_body", "Befund", false),
+            ],
+            "render_rules": {"bullets": true},
+        })).unwrap();
+        assert!(spec.validate().is_ok());
+        assert_eq!(serde_json::to_value(&spec).unwrap()["render_rules"]["bullets"], true);
+
+        spec.add_skeleton_item(Some(2), slot("beurteilung_body", "Beurteilung", false)).unwrap();
+        assert!(spec.validate().is_ok());
+
+        let mut duplicate = spec.clone();
+        duplicate.add_skeleton_item(None, slot("befund_body", "Befund", true)).unwrap();
+        assert!(duplicate.validate().unwrap_err().contains("doppelt"));
+
+        let mut dangling = spec.clone();
+        dangling.add_skeleton_item(None, slot("diagnose_body", "Diagnose", true)).unwrap();
+        assert!(dangling.validate().unwrap_err().contains("keinen Anker"));
+
+        let mut reordered = spec.clone();
+        reordered.move_skeleton_item(3, 1).unwrap();
+        assert!(reordered.validate().unwrap_err().contains("Reihenfolge"));
+
+        spec.rename_anchor("anamnese", "1. Anamnese").unwrap();
+        assert!(spec.validate().is_ok());
+        assert_eq!(spec.skeleton[0]["paragraphs"][0]["text"], "1. Anamnese");
+        assert_eq!(spec.skeleton[1]["section_name"], "1. Anamnese");
+        assert_eq!(spec.anchors[0]["variants_seen"], serde_json::json!(["Anamnese"]));
+        assert!(spec.rename_anchor("Befund", "Beurteilung").is_ok());
+        assert!(spec.validate().is_err());
+        assert!(spec.remove_skeleton_item(9).is_err());
+    }
+
+    #[test]
+    fn test_family_store_migrates_switches_and_deletes_active_family() {
+        let root = std::env::temp_dir().join(format!("template_families_{}", uuid::Uuid::new_v4().simple()));
+        let store = TemplateFamilyStore {
+            specs_dir: root.join("templates").join("specs"),
+            legacy_spec_path: root.join("template_output").join("template_spec.json"),
+        };
+        fs::create_dir_all(store.legacy_spec_path.parent().unwrap()).unwrap();
+        fs::write(&store.legacy_spec_path, r#"{"family_id": "orthopaedie", "family_name": "Orthopädie", "anchors": ["Befund"]}"#).unwrap();
+
+        // The single legacy spec becomes the first, active family
+        let families = store.list_families().unwrap();
+        assert_eq!(families.len(), 1);
+        assert_eq!(families[0].family_id, "orthopaedie");
+        assert!(families[0].is_active);
+
+        // Switching the active family refreshes the legacy copy
+        let neuro = serde_json::json!({"family_id": "neurologie", "family_name": "Neurologie", "anchors": []});
+        let (neuro_id, _) = store.store_family_spec(&neuro, None).unwrap();
+        store.write_active_family_id(&neuro_id).unwrap();
+        assert_eq!(store.active_family_id().unwrap().as_deref(), Some("neurologie"));
+        assert!(fs::read_to_string(&store.legacy_spec_path).unwrap().contains("Neurologie"));
+
+        // Deleting the active family activates a remaining one
+        store.delete_family("neurologie").unwrap();
+        assert!(!store.family_spec_path("neurologie").unwrap().exists());
+        assert_eq!(store.active_family_id().unwrap().as_deref(), Some("orthopaedie"));
+
+        // Deleting the last family removes the legacy copy, so it is not migrated back
+        store.delete_family("orthopaedie").unwrap();
+        assert_eq!(store.active_family_id().unwrap(), None);
+        assert!(!store.legacy_spec_path.exists());
+        assert!(store.list_families().unwrap().is_empty());
+        assert!(store.delete_family("orthopaedie").is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
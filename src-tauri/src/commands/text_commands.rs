@@ -0,0 +1,558 @@
+// Text commands - Pure text checks on transcripts and structured output before export
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::commands::audio_commands::text_similarity;
+use crate::text_segmentation::is_abbreviation;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateParagraphOptions {
+    pub similarity_threshold: Option<f32>,  // 1.0 = exact only (default); e.g. 0.9 also groups near-duplicates
+    pub min_words: Option<usize>,           // Shorter paragraphs (headings, "Ja.") are ignored; default 3
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DuplicateParagraph {
+    pub text: String,          // First occurrence, whitespace-normalized
+    pub occurrences: usize,
+    pub indices: Vec<usize>,   // Paragraph indices (0-based, empty paragraphs not counted)
+    pub min_similarity: f32,   // Lowest similarity to the first occurrence; 1.0 for exact repeats
+}
+
+/// Split text into paragraphs at blank lines; single-newline text is split per line
+pub fn split_paragraphs(text: &str) -> Vec<String> {
+    let normalized = text.replace("\r\n", "\n");
+    let blocks: Vec<&str> = normalized.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .collect();
+
+    let paragraphs: Vec<&str> = if blocks.len() <= 1 {
+        normalized.lines().map(str::trim).filter(|p| !p.is_empty()).collect()
+    } else {
+        blocks
+    };
+    paragraphs.into_iter().map(normalize_whitespace).collect()
+}
+
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Group repeated paragraphs; each paragraph joins the first group it matches
+pub fn find_duplicates(text: &str, options: &DuplicateParagraphOptions) -> Vec<DuplicateParagraph> {
+    let threshold = options.similarity_threshold.unwrap_or(1.0).clamp(0.0, 1.0);
+    let min_words = options.min_words.unwrap_or(3);
+
+    let mut groups: Vec<DuplicateParagraph> = Vec::new();
+    for (index, paragraph) in split_paragraphs(text).into_iter().enumerate() {
+        if paragraph.split_whitespace().count() < min_words {
+            continue;
+        }
+
+        let matched = groups.iter_mut().find_map(|group| {
+            if group.text == paragraph {
+                return Some((group, 1.0));
+            }
+            if threshold >= 1.0 {
+                return None;
+            }
+            let similarity = text_similarity(&group.text, &paragraph);
+            (similarity >= threshold).then_some((group, similarity))
+        });
+
+        match matched {
+            Some((group, similarity)) => {
+                group.occurrences += 1;
+                group.indices.push(index);
+                group.min_similarity = group.min_similarity.min(similarity);
+            }
+            None => groups.push(DuplicateParagraph {
+                text: paragraph,
+                occurrences: 1,
+                indices: vec![index],
+                min_similarity: 1.0,
+            }),
+        }
+    }
+
+    groups.retain(|group| group.occurrences > 1);
+    groups
+}
+
+/// Readability of a text by the German Flesch reading ease (Amstad):
+/// 180 - words per sentence - 58.5 * syllables per word. Higher is easier (0-100).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReadabilityScore {
+    pub flesch_de: f32,
+    pub avg_sentence_length: f32,  // Words per sentence
+    pub long_word_ratio: f32,      // Share of words with more than 6 letters
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SectionReadability {
+    pub section: String,
+    pub score: ReadabilityScore,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ReadabilityReport {
+    pub overall: ReadabilityScore,
+    pub sections: Vec<SectionReadability>,  // Hardest (lowest flesch_de) first
+}
+
+/// Sentences as words ending in . ! ? : or ;, skipping abbreviations such as "z.B." or "Dr."
+fn count_sentences(text: &str) -> usize {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let ends = words.iter().enumerate()
+        .filter(|(i, word)| {
+            let is_last = *i + 1 == words.len();
+            word.ends_with(['.', '!', '?', ':', ';'])
+                && (is_last || !is_abbreviation(word))
+        })
+        .count();
+    // Text without final punctuation still forms a sentence
+    let unterminated = words.last().is_some_and(|w| !w.ends_with(['.', '!', '?', ':', ';']));
+    ends + usize::from(unterminated)
+}
+
+/// Syllables as groups of vowels (diphthongs like "ei", "au", "eu" count once); at least one
+fn count_syllables(word: &str) -> usize {
+    let mut syllables = 0;
+    let mut previous_vowel = false;
+    for c in word.to_lowercase().chars() {
+        let vowel = "aeiouyäöü".contains(c);
+        if vowel && !previous_vowel {
+            syllables += 1;
+        }
+        previous_vowel = vowel;
+    }
+    syllables.max(1)
+}
+
+/// Compute the German Flesch reading ease; None for text without words
+pub fn readability_score(text: &str) -> Option<ReadabilityScore> {
+    let words: Vec<String> = text.split_whitespace()
+        .map(|w| w.chars().filter(|c| c.is_alphabetic()).collect::<String>())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let sentences = count_sentences(text).max(1) as f32;
+    let word_count = words.len() as f32;
+    let syllables: usize = words.iter().map(|w| count_syllables(w)).sum();
+    let long_words = words.iter().filter(|w| w.chars().count() > 6).count();
+
+    let avg_sentence_length = word_count / sentences;
+    let avg_syllables = syllables as f32 / word_count;
+    Some(ReadabilityScore {
+        flesch_de: (180.0 - avg_sentence_length - 58.5 * avg_syllables).clamp(0.0, 100.0),
+        avg_sentence_length,
+        long_word_ratio: long_words as f32 / word_count,
+    })
+}
+
+/// Text of a structured slot (a string or a list of paragraphs)
+pub(crate) fn slot_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter()
+            .filter_map(|item| item.as_str())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// Score every non-empty slot of structured content, hardest first
+pub fn section_readability(slots: &Value) -> Vec<SectionReadability> {
+    let mut sections: Vec<SectionReadability> = slots.as_object()
+        .map(|slots| slots.iter()
+            .filter_map(|(section, value)| readability_score(&slot_text(value))
+                .map(|score| SectionReadability { section: section.clone(), score }))
+            .collect())
+        .unwrap_or_default();
+    sections.sort_by(|a, b| a.score.flesch_de.total_cmp(&b.score.flesch_de));
+    sections
+}
+
+/// Length of one structured section
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SectionWordCount {
+    pub section: String,
+    pub words: usize,
+    pub chars: usize,
+    pub too_short: bool,  // Below the minimum: probably under-documented
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SectionWordCountReport {
+    pub sections: Vec<SectionWordCount>,
+    pub total_words: usize,
+    pub total_chars: usize,
+    pub min_words: usize,
+}
+
+const DEFAULT_MIN_SECTION_WORDS: usize = 20;
+
+/// Word and character counts per slot of structured content (the content object or its slots);
+/// characters are counted without line breaks
+pub fn count_section_words(content: &Value, min_words: usize) -> SectionWordCountReport {
+    let slots = content.get("slots").unwrap_or(content);
+    let sections: Vec<SectionWordCount> = slots.as_object()
+        .map(|map| map.iter()
+            .map(|(section, value)| {
+                let text = slot_text(value);
+                let words = text.split_whitespace().count();
+                SectionWordCount {
+                    section: section.clone(),
+                    words,
+                    chars: text.chars().filter(|c| *c != '\n' && *c != '\r').count(),
+                    too_short: words < min_words,
+                }
+            })
+            .collect())
+        .unwrap_or_default();
+
+    SectionWordCountReport {
+        total_words: sections.iter().map(|s| s.words).sum(),
+        total_chars: sections.iter().map(|s| s.chars).sum(),
+        sections,
+        min_words,
+    }
+}
+
+/// Per-section word counts for billing and completeness checks; sections under
+/// `min_words` (default 20) are flagged
+#[command]
+pub async fn section_word_counts(content_json: Value, min_words: Option<usize>) -> Result<SectionWordCountReport, String> {
+    let report = count_section_words(&content_json, min_words.unwrap_or(DEFAULT_MIN_SECTION_WORDS));
+    println!("Section word counts: {} words in {} sections, {} too short",
+        report.total_words, report.sections.len(), report.sections.iter().filter(|s| s.too_short).count());
+    Ok(report)
+}
+
+/// Readability of a Gutachten text, plus per-section scores when structured slots are given
+#[command]
+pub async fn get_readability_score(text: String, slots: Option<Value>) -> Result<ReadabilityReport, String> {
+    let overall = readability_score(&text)
+        .ok_or_else(|| "Text enthält keine Wörter".to_string())?;
+    let sections = slots.as_ref().map(section_readability).unwrap_or_default();
+    println!("Readability: Flesch-DE {:.1}, {} sections", overall.flesch_de, sections.len());
+    Ok(ReadabilityReport { overall, sections })
+}
+
+/// One run of a word-level diff
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DiffOp {
+    pub kind: String,  // "equal", "insert" or "delete"
+    pub text: String,
+}
+
+/// Word-level diff (longest common subsequence) from `old` to `new`; adjacent words of the same kind are merged
+pub fn word_diff(old: &str, new: &str) -> Vec<DiffOp> {
+    let a: Vec<&str> = old.split_whitespace().collect();
+    let b: Vec<&str> = new.split_whitespace().collect();
+
+    // lcs[i][j] = common words of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut ops: Vec<DiffOp> = Vec::new();
+    let mut push = |kind: &str, word: &str| match ops.last_mut() {
+        Some(last) if last.kind == kind => {
+            last.text.push(' ');
+            last.text.push_str(word);
+        }
+        _ => ops.push(DiffOp { kind: kind.to_string(), text: word.to_string() }),
+    };
+
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            push("equal", a[i]);
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            // Deletions first, so a replacement reads "old -> new"
+            push("delete", a[i]);
+            i += 1;
+        } else {
+            push("insert", b[j]);
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Report paragraphs that appear more than once (e.g. after chunk merging or LLM structuring)
+#[command]
+pub async fn find_duplicate_paragraphs(
+    text: String,
+    options: Option<DuplicateParagraphOptions>,
+) -> Result<Vec<DuplicateParagraph>, String> {
+    let options = options.unwrap_or(DuplicateParagraphOptions {
+        similarity_threshold: None,
+        min_words: None,
+    });
+    let duplicates = find_duplicates(&text, &options);
+    println!("Found {} duplicated paragraphs", duplicates.len());
+    Ok(duplicates)
+}
+
+/// A mojibake sequence found in a text and what it most likely was before double encoding
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SuspiciousSequence {
+    pub sequence: String,
+    pub expected: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EncodingHealth {
+    pub has_mojibake: bool,
+    pub suspicious_sequences: Vec<SuspiciousSequence>,
+}
+
+/// UTF-8 read as Windows-1252, longest sequences first so "â€ž" wins over the bare "â€" prefix
+const MOJIBAKE_PATTERNS: &[(&str, &str)] = &[
+    ("â€ž", "„"), ("â€œ", "“"), ("â€˜", "‘"), ("â€™", "’"), ("â€“", "–"), ("â€”", "—"), ("â€¦", "…"),
+    ("â€", "”"),
+    ("Ã¤", "ä"), ("Ã¶", "ö"), ("Ã¼", "ü"), ("Ã„", "Ä"), ("Ã–", "Ö"), ("Ãœ", "Ü"), ("ÃŸ", "ß"),
+    ("Ã©", "é"), ("Ã¨", "è"), ("Ã¡", "á"), ("Ã ", "à"),
+    ("Â§", "§"), ("Â°", "°"), ("Â²", "²"), ("Âµ", "µ"), ("Â½", "½"),
+    ("\u{FFFD}", "?"),  // Replacement character left by a lossy decode
+];
+
+/// Scan a text for typical double-encoding artifacts (e.g. "Ã¤" instead of "ä")
+pub fn encoding_health(text: &str) -> EncodingHealth {
+    let mut found: Vec<SuspiciousSequence> = Vec::new();
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        match MOJIBAKE_PATTERNS.iter().find(|(pattern, _)| rest.starts_with(pattern)) {
+            Some((pattern, expected)) => {
+                match found.iter_mut().find(|f| f.sequence == *pattern) {
+                    Some(existing) => existing.count += 1,
+                    None => found.push(SuspiciousSequence {
+                        sequence: pattern.to_string(),
+                        expected: expected.to_string(),
+                        count: 1,
+                    }),
+                }
+                rest = &rest[pattern.len()..];
+            }
+            None => rest = &rest[c.len_utf8()..],
+        }
+    }
+
+    EncodingHealth {
+        has_mojibake: !found.is_empty(),
+        suspicious_sequences: found,
+    }
+}
+
+/// Log a warning when decoded subprocess output shows double-encoding artifacts
+pub(crate) fn warn_on_mojibake(source: &str, text: &str) -> bool {
+    let health = encoding_health(text);
+    if health.has_mojibake {
+        let sequences: Vec<&str> = health.suspicious_sequences.iter().map(|s| s.sequence.as_str()).collect();
+        println!("WARNING: {} output looks double-encoded (mojibake: {})", source, sequences.join(", "));
+    }
+    health.has_mojibake
+}
+
+/// Check a text for umlaut/encoding corruption so the UI can flag damaged transcripts
+#[command]
+pub async fn check_encoding_health(text: String) -> Result<EncodingHealth, String> {
+    Ok(encoding_health(&text))
+}
+
+/// Quotation marks for `normalize_typography`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotationMarks {
+    Guillemets,  // »Zitat«
+    #[default]
+    German,      // „Zitat“
+    Straight,    // "Zitat"
+}
+
+impl QuotationMarks {
+    fn pair(self) -> (char, char) {
+        match self {
+            QuotationMarks::Guillemets => ('»', '«'),
+            QuotationMarks::German => ('„', '“'),
+            QuotationMarks::Straight => ('"', '"'),
+        }
+    }
+}
+
+const DOUBLE_QUOTES: &[char] = &['"', '„', '“', '”', '»', '«'];
+/// Characters after which a quote opens
+const QUOTE_OPENERS: &[char] = &['(', '[', '/', '–', '—', '-', ':'];
+const CODE_MARKERS: &[char] = &['/', '\\', '_', '=', '<', '>', '{', '}', '@', '#', '`'];
+
+static NUMBER_RANGE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\D*\d+(?:[.,]\d+)?)-(\d+(?:[.,]\d+)?\D*)$").unwrap());
+static WORD: Lazy<Regex> = Lazy::new(|| Regex::new(r"\S+").unwrap());
+
+/// Paths, URLs, identifiers and similar tokens are left as dictated
+fn is_code_like(token: &str) -> bool {
+    let inner = token.trim_matches(DOUBLE_QUOTES);
+    inner.contains(CODE_MARKERS)
+}
+
+fn typeset_token(token: &str, marks: QuotationMarks) -> String {
+    let (open, close) = marks.pair();
+    let mut result = String::with_capacity(token.len());
+    let mut previous: Option<char> = None;
+    for c in token.chars() {
+        if DOUBLE_QUOTES.contains(&c) {
+            let opening = previous.is_none_or(|p| QUOTE_OPENERS.contains(&p));
+            result.push(if opening { open } else { close });
+        } else {
+            result.push(c);
+        }
+        previous = Some(c);
+    }
+
+    let result = result.replace("...", "…");
+    // "10-20" is a range; ISO dates and other multi-part numbers do not match
+    match NUMBER_RANGE.captures(&result) {
+        Some(c) => format!("{}–{}", &c[1], &c[2]),
+        None => result,
+    }
+}
+
+/// Consistent German typography: quotation marks in the chosen style, en dashes in number
+/// ranges and ellipses; code-like tokens stay unchanged. Applying it twice changes nothing.
+pub fn apply_typography(text: &str, marks: QuotationMarks) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut last = 0;
+    for token in WORD.find_iter(text) {
+        result.push_str(&text[last..token.start()]);
+        if is_code_like(token.as_str()) {
+            result.push_str(token.as_str());
+        } else {
+            result.push_str(&typeset_token(token.as_str(), marks));
+        }
+        last = token.end();
+    }
+    result.push_str(&text[last..]);
+    result
+}
+
+/// Normalize quotation marks, range dashes and ellipses (default: German „…“ quotes)
+#[command]
+pub async fn normalize_typography(text: String, quote_style: Option<QuotationMarks>) -> Result<String, String> {
+    Ok(apply_typography(&text, quote_style.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotes_follow_chosen_style_and_code_stays() {
+        let text = r#"Er sagte "Ich habe Schmerzen" und ("nachts") schlimmer; Datei "C:\Befunde\mrt.docx" liegt vor."#;
+        let german = apply_typography(text, QuotationMarks::German);
+        assert_eq!(german, r#"Er sagte „Ich habe Schmerzen“ und („nachts“) schlimmer; Datei "C:\Befunde\mrt.docx" liegt vor."#);
+        assert_eq!(apply_typography(&german, QuotationMarks::German), german);
+
+        let guillemets = apply_typography(&german, QuotationMarks::Guillemets);
+        assert!(guillemets.starts_with("Er sagte »Ich habe Schmerzen« und (»nachts«)"));
+        assert_eq!(apply_typography(&guillemets, QuotationMarks::Straight), apply_typography(text, QuotationMarks::Straight));
+        assert!(apply_typography(&guillemets, QuotationMarks::Straight).starts_with(r#"Er sagte "Ich habe Schmerzen" und ("nachts")"#));
+    }
+
+    #[test]
+    fn test_number_ranges_get_en_dash_and_dots_an_ellipsis() {
+        let text = "Beschwerden seit 3-4 Wochen, S. 10-12, (5,5-7 cm), am 2024-01-15, Schulter-Arm-Syndrom, und dann...";
+        let typeset = apply_typography(text, QuotationMarks::German);
+        assert_eq!(typeset, "Beschwerden seit 3–4 Wochen, S. 10–12, (5,5–7 cm), am 2024-01-15, Schulter-Arm-Syndrom, und dann…");
+        assert_eq!(apply_typography(&typeset, QuotationMarks::German), typeset);
+    }
+
+    #[test]
+    fn test_one_word_section_is_flagged_too_short() {
+        let content = serde_json::json!({"slots": {
+            "befund_body": "Unauffällig.",
+            "anamnese_body": [
+                "Der Patient berichtet über seit 2019 bestehende Rückenschmerzen, die in das linke Bein ausstrahlen.",
+                "Eine Operation erfolgte 2020, danach Physiotherapie ohne anhaltende Besserung der Beschwerden.",
+            ],
+        }});
+        let report = count_section_words(&content, DEFAULT_MIN_SECTION_WORDS);
+
+        let anamnese = report.sections.iter().find(|s| s.section == "anamnese_body").unwrap();
+        let befund = report.sections.iter().find(|s| s.section == "befund_body").unwrap();
+        assert_eq!(anamnese.words, 25);
+        assert!(!anamnese.too_short);
+        assert_eq!((befund.words, befund.chars), (1, 12));
+        assert!(befund.too_short);
+        assert_eq!(report.total_words, 26);
+        assert_eq!(report.total_chars, anamnese.chars + 12);
+    }
+
+    #[test]
+    fn test_mojibake_is_detected_and_clean_umlauts_are_healthy() {
+        let broken = encoding_health("Der Patient klagt Ã¼ber SchmerzÃ¤ und â€žRÃ¼ckenâ€œ.");
+        assert!(broken.has_mojibake);
+        let sequences: Vec<(&str, &str, usize)> = broken.suspicious_sequences.iter()
+            .map(|s| (s.sequence.as_str(), s.expected.as_str(), s.count))
+            .collect();
+        assert_eq!(sequences, vec![("Ã¼", "ü", 2), ("Ã¤", "ä", 1), ("â€ž", "„", 1), ("â€œ", "“", 1)]);
+
+        let clean = encoding_health("Der Patient klagt über Schmerzen, „Rücken“ und Äußerungen.");
+        assert!(!clean.has_mojibake);
+        assert!(clean.suspicious_sequences.is_empty());
+    }
+
+    #[test]
+    fn test_readability_separates_simple_and_dense_text() {
+        let simple = readability_score("Der Mann ist krank. Er hat Schmerzen. Das Bein tut weh. Er kann gut gehen.").unwrap();
+        let dense = readability_score(
+            "Die neurologisch-orthopädische Untersuchung ergab eine ausgeprägte degenerative Veränderung \
+             der Lendenwirbelsäule mit konsekutiver radikulärer Schmerzsymptomatik und Funktionseinschränkung \
+             der unteren Extremitäten, welche die berufliche Leistungsfähigkeit erheblich beeinträchtigt."
+        ).unwrap();
+
+        assert!(simple.flesch_de > 80.0, "{:?}", simple);
+        assert!(dense.flesch_de < 20.0, "{:?}", dense);
+        assert_eq!(simple.avg_sentence_length, 3.75);
+        assert!(dense.long_word_ratio > simple.long_word_ratio);
+        assert_eq!(count_sentences("Vgl. Dr. Muster, z.B. am 09.03.2026. Danach ca. zwei Wochen"), 2);
+
+        let sections = section_readability(&serde_json::json!({
+            "anamnese": ["Er hat Schmerzen.", "Das Bein tut weh."],
+            "beurteilung": "Die Funktionseinschränkung beeinträchtigt die Leistungsfähigkeit erheblich.",
+        }));
+        assert_eq!(sections[0].section, "beurteilung");
+    }
+
+    #[test]
+    fn test_repeated_paragraph_is_reported_with_indices() {
+        let text = "Der Patient klagt über Rückenschmerzen.\n\n\
+                    Die Untersuchung ergab keinen Befund.\n\n\
+                    Der Patient  klagt über\nRückenschmerzen.\n\n\
+                    Der Patient klagt über Rückenschmerz.";
+
+        let exact = find_duplicates(text, &DuplicateParagraphOptions { similarity_threshold: None, min_words: None });
+        assert_eq!(exact.len(), 1);
+        assert_eq!(exact[0].text, "Der Patient klagt über Rückenschmerzen.");
+        assert_eq!(exact[0].indices, vec![0, 2]);
+        assert_eq!(exact[0].occurrences, 2);
+
+        let near = find_duplicates(text, &DuplicateParagraphOptions { similarity_threshold: Some(0.9), min_words: None });
+        assert_eq!(near[0].indices, vec![0, 2, 3]);
+        assert!(near[0].min_similarity < 1.0 && near[0].min_similarity >= 0.9);
+    }
+}
@@ -0,0 +1,454 @@
+// Transcript commands - Converts structured slots back into a flat, editable transcript
+// ("## Abschnitt" lines + paragraphs) and parses such a transcript (or a rendered DOCX) into slots again
+use tauri::command;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::fs;
+use std::io::BufReader;
+use zip::ZipArchive;
+
+use crate::commands::docx_commands::{classify_line, is_break_marker, PAGE_BREAK_MARKER, QUOTE_PREFIX, SECTION_BREAK_MARKER};
+use crate::commands::document_commands::extract_document_xml;
+use crate::commands::export_commands::{blocks_to_text, item_blocks, slot_heading};
+use crate::commands::template_commands::load_family_spec;
+use crate::heading_normalizer::{canonical_heading, heading_similarity, same_heading, KNOWN_SECTIONS};
+
+/// Unclear marker in slot text, as written by the structurer
+static UNCLEAR_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{unclear:([^}]+)\}").unwrap());
+
+/// Unclear marker in the flat transcript
+static FLAT_UNCLEAR_MARKER: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[\?([^\]]+)\]").unwrap());
+
+const SECTION_PREFIX: &str = "## ";
+
+/// (slot_id, section name) of the spec skeleton slots, in template order
+fn skeleton_sections(spec: Option<&Value>) -> Vec<(String, String)> {
+    spec.and_then(|s| s.get("skeleton")).and_then(|s| s.as_array())
+        .map(|skeleton| skeleton.iter()
+            .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("slot"))
+            .filter_map(|item| {
+                let slot_id = item.get("slot_id").and_then(|s| s.as_str())?;
+                let name = item.get("section_name").and_then(|n| n.as_str())
+                    .map(String::from)
+                    .unwrap_or_else(|| slot_heading(slot_id));
+                Some((slot_id.to_string(), name))
+            })
+            .collect())
+        .unwrap_or_default()
+}
+
+fn slot_paragraphs(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => vec![text.clone()],
+        // Typed blocks as they are exported: quotes as "> " paragraphs, list items as "- " paragraphs
+        Value::Array(items) => items.iter()
+            .flat_map(|item| match item.as_str() {
+                Some(paragraph) => vec![paragraph.to_string()],
+                None => item_blocks(item).into_iter().map(|block| blocks_to_text(&[block])).collect(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// "{unclear:...}" markers and the plain text of the slot's unclear spans become "[?...]"
+fn mark_unclear(paragraph: &str, span_texts: &[&str]) -> String {
+    let mut marked = UNCLEAR_MARKER.replace_all(paragraph, "[?$1]").to_string();
+    for text in span_texts {
+        let already_marked = format!("[?{}]", text);
+        if !marked.contains(&already_marked) {
+            if let Some(position) = marked.find(text) {
+                marked.replace_range(position..position + text.len(), &already_marked);
+            }
+        }
+    }
+    marked
+}
+
+/// Flat transcript of structured content: slots in skeleton order (slots the skeleton doesn't
+/// know follow), each as a "## Abschnitt" line with its paragraphs separated by blank lines
+pub fn flatten_content(content: &Value, spec: Option<&Value>) -> String {
+    let empty = Map::new();
+    let slots = content.get("slots").and_then(|s| s.as_object()).unwrap_or(&empty);
+    let spans = content.get("unclear_spans").and_then(|u| u.as_array()).cloned().unwrap_or_default();
+
+    let sections = skeleton_sections(spec);
+    let mut ordered: Vec<(String, String)> = sections.iter()
+        .filter(|(slot_id, _)| slots.contains_key(slot_id))
+        .cloned()
+        .collect();
+    ordered.extend(slots.keys()
+        .filter(|slot_id| !sections.iter().any(|(id, _)| id == *slot_id))
+        .map(|slot_id| (slot_id.clone(), slot_heading(slot_id))));
+
+    let blocks: Vec<String> = ordered.iter()
+        .map(|(slot_id, name)| {
+            let span_texts: Vec<&str> = spans.iter()
+                .filter(|span| span.get("slot_id").and_then(|s| s.as_str()) == Some(slot_id.as_str()))
+                .filter_map(|span| span.get("text").and_then(|t| t.as_str()))
+                .collect();
+            let mut block = vec![format!("{}{}", SECTION_PREFIX, name)];
+            block.extend(slot_paragraphs(&slots[slot_id]).iter().map(|p| mark_unclear(p, &span_texts)));
+            block.join("\n\n")
+        })
+        .collect();
+    format!("{}\n", blocks.join("\n\n"))
+}
+
+/// Slot of a section line: the skeleton slot with the same heading, otherwise derived from the
+/// name ("Anamnese" -> "anamnese_body")
+fn section_slot_id(name: &str, sections: &[(String, String)]) -> String {
+    sections.iter()
+        .find(|(slot_id, section_name)| same_heading(section_name, name) || same_heading(&slot_heading(slot_id), name))
+        .map(|(slot_id, _)| slot_id.clone())
+        .unwrap_or_else(|| format!("{}_body", name.trim().to_lowercase().replace(' ', "_")))
+}
+
+/// Structured content from a flat transcript: "## " lines start a section, blank lines separate
+/// paragraphs, "> " paragraphs become quote blocks, "[?...]" become unclear spans. Text before the first section line goes into the
+/// first section; required skeleton slots without a section are reported missing.
+pub fn parse_flat_transcript(text: &str, spec: Option<&Value>) -> Value {
+    let sections = skeleton_sections(spec);
+    let mut slots: Vec<(String, Vec<String>)> = Vec::new();
+    let mut preamble: Vec<String> = Vec::new();
+
+    for paragraph in text.replace("\r\n", "\n").split("\n\n").map(str::trim).filter(|p| !p.is_empty()) {
+        let mut rest = Vec::new();
+        for line in paragraph.lines() {
+            if is_break_marker(line) {
+                if !rest.is_empty() {
+                    push_paragraph(&mut slots, &mut preamble, rest.join("\n"));
+                    rest.clear();
+                }
+                push_paragraph(&mut slots, &mut preamble, line.trim().to_string());
+                continue;
+            }
+            match line.strip_prefix(SECTION_PREFIX) {
+                Some(name) => {
+                    if !rest.is_empty() {
+                        push_paragraph(&mut slots, &mut preamble, rest.join("\n"));
+                        rest.clear();
+                    }
+                    slots.push((section_slot_id(name, &sections), Vec::new()));
+                }
+                None => rest.push(line),
+            }
+        }
+        if !rest.is_empty() {
+            push_paragraph(&mut slots, &mut preamble, rest.join("\n"));
+        }
+    }
+    if let Some((_, first)) = slots.first_mut() {
+        preamble.append(first);
+        *first = preamble;
+    }
+
+    let mut unclear_spans = Vec::new();
+    let mut slot_map = Map::new();
+    for (slot_id, paragraphs) in slots {
+        let paragraphs: Vec<Value> = paragraphs.iter()
+            .map(|paragraph| {
+                for capture in FLAT_UNCLEAR_MARKER.captures_iter(paragraph) {
+                    unclear_spans.push(json!({ "slot_id": slot_id, "text": &capture[1], "reason": "unclear" }));
+                }
+                let text = FLAT_UNCLEAR_MARKER.replace_all(paragraph, "{unclear:$1}").to_string();
+                match text.as_str() {
+                    PAGE_BREAK_MARKER => return json!({ "type": "page_break" }),
+                    SECTION_BREAK_MARKER => return json!({ "type": "section_break" }),
+                    _ => {}
+                }
+                match text.strip_prefix(QUOTE_PREFIX) {
+                    Some(quote) => json!({ "type": "quote", "text": quote.trim() }),
+                    None => Value::String(text),
+                }
+            })
+            .collect();
+        match slot_map.get_mut(&slot_id).and_then(|v| v.as_array_mut()) {
+            Some(existing) => existing.extend(paragraphs),
+            None => { slot_map.insert(slot_id, Value::Array(paragraphs)); }
+        }
+    }
+
+    let missing_slots: Vec<String> = spec.and_then(|s| s.get("skeleton")).and_then(|s| s.as_array())
+        .map(|skeleton| skeleton.iter()
+            .filter(|item| item.get("type").and_then(|t| t.as_str()) == Some("slot"))
+            .filter(|item| !item.get("optional").and_then(|o| o.as_bool()).unwrap_or(false))
+            .filter_map(|item| item.get("slot_id").and_then(|s| s.as_str()))
+            .filter(|slot_id| !slot_map.contains_key(*slot_id))
+            .map(String::from)
+            .collect())
+        .unwrap_or_default();
+
+    json!({ "slots": slot_map, "unclear_spans": unclear_spans, "missing_slots": missing_slots })
+}
+
+fn push_paragraph(slots: &mut [(String, Vec<String>)], preamble: &mut Vec<String>, paragraph: String) {
+    match slots.last_mut() {
+        Some((_, paragraphs)) => paragraphs.push(paragraph),
+        None => preamble.push(paragraph),
+    }
+}
+
+static DOCX_PARAGRAPH: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:p[ >].*?</w:p>").unwrap());
+static DOCX_RUN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:r(?:\s[^>]*)?>.*?</w:r>").unwrap());
+static DOCX_TEXT: Lazy<Regex> = Lazy::new(|| Regex::new(r"<w:t(?:\s[^>]*)?>([^<]*)</w:t>").unwrap());
+static DOCX_HEADING_STYLE: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:pStyle w:val="(?:Heading\d|berschrift\d|Title)""#).unwrap());
+static DOCX_LEFT_INDENT: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:ind\s[^>]*w:(?:left|start)="(\d+)""#).unwrap());
+static DOCX_PAGE_BREAK: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:br\s[^>]*w:type="page""#).unwrap());
+/// Section properties inside a paragraph end a section; the body's last sectPr is not in a paragraph
+static DOCX_SECTION_BREAK: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?s)<w:pPr>.*<w:sectPr").unwrap());
+static DOCX_ITALIC: Lazy<Regex> = Lazy::new(|| Regex::new(r#"<w:i\s*/>|<w:i\s+w:val="(?:1|true|on)"\s*/>"#).unwrap());
+
+/// Flat transcript of a rendered report's document.xml: headings become "## " lines (only the
+/// skeleton's sections if there is one, other headings are template text), indented paragraphs
+/// whose runs are all italic become "> " quote paragraphs, manual page and section breaks marker lines
+pub fn docx_flat_transcript(document_xml: &str, spec: Option<&Value>) -> String {
+    let sections = skeleton_sections(spec);
+    let mut paragraphs = Vec::new();
+    for paragraph in DOCX_PARAGRAPH.find_iter(document_xml).map(|m| m.as_str()) {
+        let text_runs: Vec<&str> = DOCX_RUN.find_iter(paragraph)
+            .map(|m| m.as_str())
+            .filter(|run| DOCX_TEXT.captures_iter(run).any(|c| !c[1].trim().is_empty()))
+            .collect();
+        let text: String = text_runs.iter()
+            .flat_map(|run| DOCX_TEXT.captures_iter(run).map(|c| c[1].to_string()).collect::<Vec<_>>())
+            .collect();
+        let text = text.trim();
+
+        // A page break before the paragraph's first text belongs before the paragraph
+        let page_break = DOCX_PAGE_BREAK.find(paragraph).map(|m| m.start());
+        let first_text = text_runs.first().and_then(|run| paragraph.find(run));
+        let break_before = matches!((page_break, first_text), (Some(at), Some(text_at)) if at < text_at);
+        if break_before {
+            paragraphs.push(PAGE_BREAK_MARKER.to_string());
+        }
+
+        if !text.is_empty() {
+            let is_heading = DOCX_HEADING_STYLE.is_match(paragraph) || classify_line(text).kind == "heading";
+            let indented = DOCX_LEFT_INDENT.captures(paragraph)
+                .and_then(|c| c[1].parse::<u32>().ok())
+                .is_some_and(|twips| twips > 0);
+            if is_heading {
+                if sections.is_empty() || sections.iter().any(|(_, name)| same_heading(name, text)) {
+                    paragraphs.push(format!("{}{}", SECTION_PREFIX, text));
+                }
+            } else if indented && text_runs.iter().all(|run| DOCX_ITALIC.is_match(run)) {
+                paragraphs.push(format!("{}{}", QUOTE_PREFIX, text));
+            } else {
+                paragraphs.push(text.to_string());
+            }
+        }
+
+        if page_break.is_some() && !break_before {
+            paragraphs.push(PAGE_BREAK_MARKER.to_string());
+        }
+        if DOCX_SECTION_BREAK.is_match(paragraph) {
+            paragraphs.push(SECTION_BREAK_MARKER.to_string());
+        }
+    }
+    paragraphs.join("\n\n")
+}
+
+/// Read a rendered Gutachten DOCX back into structured content (slots of the template family)
+#[command]
+pub async fn import_gutachten_docx(docx_path: String, family_id: Option<String>) -> Result<Value, String> {
+    let file = fs::File::open(&docx_path)
+        .map_err(|e| format!("Datei konnte nicht geöffnet werden: {}", e))?;
+    let mut archive = ZipArchive::new(BufReader::new(file))
+        .map_err(|e| format!("Keine gültige DOCX-Datei: {}", e))?;
+    let document_xml = extract_document_xml(&mut archive)?;
+
+    let spec = load_family_spec(family_id).ok();
+    let text = docx_flat_transcript(&document_xml, spec.as_ref());
+    Ok(parse_flat_transcript(&text, spec.as_ref()))
+}
+
+/// Minimum similarity for a fuzzy match of a heading to a known section ("Anamnesse" -> ANAMNESE)
+const SECTION_SIMILARITY_THRESHOLD: f32 = 0.85;
+
+/// A known section header found in a raw transcript
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DetectedSection {
+    pub section: String,       // Canonical name from the shared section list
+    pub offset: usize,         // Position of the header in the text, in characters
+    pub matched_text: String,
+}
+
+/// The heading part of a line: a "## " section line, the label before a colon ("Befund: o.B."),
+/// or a short line on its own
+fn header_candidate(line: &str) -> Option<&str> {
+    let line = line.strip_prefix(SECTION_PREFIX).unwrap_or(line);
+    let candidate = match line.split_once(':') {
+        Some((label, _)) => label,
+        None => line.trim_end_matches(['.', ' ']),
+    };
+    let words = candidate.split_whitespace().count();
+    (words > 0 && words <= 4 && candidate.chars().count() <= 60).then_some(candidate)
+}
+
+/// Known section of a heading: exact after canonicalization, otherwise the most similar one
+fn known_section(candidate: &str) -> Option<&'static str> {
+    let canonical = canonical_heading(candidate);
+    if let Some(section) = KNOWN_SECTIONS.iter().find(|section| **section == canonical) {
+        return Some(section);
+    }
+    if canonical.chars().count() < 5 {
+        return None;
+    }
+    KNOWN_SECTIONS.iter()
+        .map(|section| (*section, heading_similarity(section, candidate)))
+        .filter(|(_, similarity)| *similarity >= SECTION_SIMILARITY_THRESHOLD)
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(section, _)| section)
+}
+
+/// Known section headers of a transcript in text order; a section may appear more than once
+pub fn detect_sections(text: &str) -> Vec<DetectedSection> {
+    let mut sections = Vec::new();
+    let mut line_start = 0;
+    for line in text.split_inclusive('\n') {
+        let content = line.trim_end_matches(['\r', '\n']);
+        let indent = content.len() - content.trim_start().len();
+        if let Some(candidate) = header_candidate(content.trim_start()) {
+            if let Some(section) = known_section(candidate) {
+                let byte_offset = line_start + indent + content.trim_start().find(candidate).unwrap_or(0);
+                sections.push(DetectedSection {
+                    section: section.to_string(),
+                    offset: text[..byte_offset].chars().count(),
+                    matched_text: candidate.trim().to_string(),
+                });
+            }
+        }
+        line_start += line.len();
+    }
+    sections
+}
+
+/// Which known sections a raw transcript seems to contain, with their positions, as a preview
+/// of the structuring
+#[command]
+pub async fn detect_sections_in_text(text: String) -> Result<Vec<DetectedSection>, String> {
+    Ok(detect_sections(&text))
+}
+
+/// Turn structured content back into a flat transcript for editing as prose; sections follow the
+/// template family's skeleton, unclear spans are marked inline as "[?...]"
+#[command]
+pub async fn flatten_structured_content(content_json: Value, family_id: Option<String>) -> Result<String, String> {
+    let spec = load_family_spec(family_id).ok();
+    Ok(flatten_content(&content_json, spec.as_ref()))
+}
+
+/// Structure a flat transcript by its "## " section lines (the inverse of `flatten_structured_content`)
+#[command]
+pub async fn structure_by_headers(text: String, family_id: Option<String>) -> Result<Value, String> {
+    let spec = load_family_spec(family_id).ok();
+    let content = parse_flat_transcript(&text, spec.as_ref());
+    println!("Structured flat transcript into {} section(s)",
+        content["slots"].as_object().map(|s| s.len()).unwrap_or(0));
+    Ok(content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flatten_then_structure_restores_slots() {
+        let spec = json!({ "skeleton": [
+            { "type": "fixed", "paragraphs": [{ "text": "Gutachten", "style": "Title" }] },
+            { "type": "slot", "slot_id": "fragestellung_body", "section_name": "Fragestellung", "optional": false },
+            { "type": "slot", "slot_id": "anamnese_body", "section_name": "Eigenanamnese", "optional": false },
+        ]});
+        let content = json!({
+            "slots": {
+                "anamnese_body": ["Beschwerden seit {unclear:2019}.", "- Rückenschmerzen\n- Kopfschmerzen"],
+                "fragestellung_body": ["Die Begutachtung erfolgt auf Veranlassung der DRV."],
+                "zusatz_body": [],
+            },
+            "unclear_spans": [{ "slot_id": "anamnese_body", "text": "2019", "reason": "garbled" }],
+            "missing_slots": [],
+        });
+
+        let flat = flatten_content(&content, Some(&spec));
+        assert!(flat.starts_with("## Fragestellung\n\nDie Begutachtung"), "{}", flat);
+        assert!(flat.contains("## Eigenanamnese\n\nBeschwerden seit [?2019]."));
+
+        let structured = parse_flat_transcript(&flat, Some(&spec));
+        assert_eq!(structured["slots"], content["slots"]);
+        assert_eq!(structured["unclear_spans"][0]["text"], "2019");
+        assert_eq!(structured["unclear_spans"][0]["slot_id"], "anamnese_body");
+        assert_eq!(flatten_content(&structured, Some(&spec)), flat);
+    }
+
+    #[test]
+    fn test_indented_italic_docx_paragraph_imports_as_quote_block() {
+        let spec = json!({ "skeleton": [
+            { "type": "fixed", "paragraphs": [{ "text": "GUTACHTEN", "style": "Title" }] },
+            { "type": "slot", "slot_id": "anamnese_body", "section_name": "Anamnese", "optional": false },
+        ]});
+        let document_xml = concat!(
+            r#"<w:body><w:p><w:pPr><w:pStyle w:val="Title"/></w:pPr><w:r><w:t>GUTACHTEN</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:rPr><w:b/></w:rPr><w:t>ANAMNESE</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>Der Patient berichtet über Rückenschmerzen.</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:ind w:left="567"/></w:pPr><w:r><w:rPr><w:i /><w:sz w:val="20"/></w:rPr><w:t>Im Vorgutachten 2021: </w:t></w:r>"#,
+            r#"<w:r><w:rPr><w:i/></w:rPr><w:t>keine Einschränkung.</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:ind w:left="567"/></w:pPr><w:r><w:t>Eingerückt, aber nicht kursiv.</w:t></w:r></w:p></w:body>"#,
+        );
+
+        let flat = docx_flat_transcript(document_xml, Some(&spec));
+        assert_eq!(flat, "## ANAMNESE\n\nDer Patient berichtet über Rückenschmerzen.\n\n> Im Vorgutachten 2021: keine Einschränkung.\n\nEingerückt, aber nicht kursiv.");
+
+        let content = parse_flat_transcript(&flat, Some(&spec));
+        assert_eq!(content["slots"]["anamnese_body"], json!([
+            "Der Patient berichtet über Rückenschmerzen.",
+            { "type": "quote", "text": "Im Vorgutachten 2021: keine Einschränkung." },
+            "Eingerückt, aber nicht kursiv.",
+        ]));
+        // The quote block survives the flat transcript round trip
+        assert!(flatten_content(&content, Some(&spec)).contains("\n\n> Im Vorgutachten 2021"));
+    }
+
+    #[test]
+    fn test_manual_page_and_section_breaks_survive_import_and_export() {
+        let spec = json!({ "skeleton": [
+            { "type": "slot", "slot_id": "befund_body", "section_name": "Befund", "optional": false },
+        ]});
+        let document_xml = concat!(
+            r#"<w:body><w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Befund</w:t></w:r></w:p>"#,
+            r#"<w:p><w:r><w:t>Unauffällig.</w:t></w:r><w:r><w:br w:type="page"/></w:r></w:p>"#,
+            r#"<w:p><w:r><w:br w:type="page"/></w:r><w:r><w:t>Anlage 1</w:t></w:r></w:p>"#,
+            r#"<w:p><w:pPr><w:sectPr><w:type w:val="nextPage"/></w:sectPr></w:pPr></w:p>"#,
+            r#"<w:p><w:r><w:t>Anlage 2</w:t></w:r></w:p><w:sectPr/></w:body>"#,
+        );
+
+        let flat = docx_flat_transcript(document_xml, Some(&spec));
+        assert_eq!(flat, "## Befund\n\nUnauffällig.\n\n[SEITENUMBRUCH]\n\n[SEITENUMBRUCH]\n\nAnlage 1\n\n[ABSCHNITTSUMBRUCH]\n\nAnlage 2");
+
+        let content = parse_flat_transcript(&flat, Some(&spec));
+        assert_eq!(content["slots"]["befund_body"][1], json!({ "type": "page_break" }));
+        assert_eq!(content["slots"]["befund_body"][4], json!({ "type": "section_break" }));
+        assert_eq!(flatten_content(&content, Some(&spec)).trim_end(), flat);
+
+        let blocks = crate::commands::export_commands::content_blocks(&content, Some(&spec));
+        assert_eq!(blocks.iter().filter(|b| **b == crate::commands::export_commands::ExportBlock::PageBreak).count(), 2);
+        assert!(blocks_to_text(&blocks).contains("Anlage 1\n\n[ABSCHNITTSUMBRUCH]\n\nAnlage 2"));
+    }
+
+    #[test]
+    fn test_sections_are_detected_in_text_order() {
+        let text = "Anamnese: Der Patient berichtet über Schmerzen.\nDer Befund wird nachgereicht.\n\nBEFUND\nunauffällig.\n\nAnamnesse\nKeine Allergien.";
+        let sections = detect_sections(text);
+        let found: Vec<(&str, usize, &str)> = sections.iter()
+            .map(|s| (s.section.as_str(), s.offset, s.matched_text.as_str()))
+            .collect();
+        let char_offset = |needle: &str| text[..text.find(needle).unwrap()].chars().count();
+        assert_eq!(found, vec![
+            ("ANAMNESE", 0, "Anamnese"),
+            ("BEFUND", char_offset("BEFUND"), "BEFUND"),
+            ("ANAMNESE", char_offset("Anamnesse"), "Anamnesse"),
+        ]);
+    }
+}
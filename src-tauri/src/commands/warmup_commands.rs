@@ -0,0 +1,235 @@
+// Warm-up commands - Optional background start of the LLM worker at app start, so the first
+// grammar correction or structuring request doesn't wait 60-180s for the model to load
+use tauri::{command, AppHandle, Emitter, State};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use once_cell::sync::Lazy;
+
+use crate::commands::llama_commands::{llm_model_path, warm_up_worker};
+use crate::memory_manager::{get_gpu_memory_info, get_system_memory_info, GpuMemoryInfo};
+
+/// KV cache and runtime buffers on top of the model file
+const WARMUP_OVERHEAD_BYTES: u64 = 1_500_000_000;
+/// RAM left to the rest of the system when the model is loaded into RAM
+const WARMUP_RAM_RESERVE_BYTES: u64 = 2_000_000_000;
+
+/// Startup behaviour; stored in user-data/settings/startup.json
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct StartupSettings {
+    #[serde(default)]
+    pub preload_llm_on_start: bool,  // Off by default: loading blocks several GB of (V)RAM
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WarmupStatus {
+    pub state: String,  // "idle", "warming", "ready", "skipped", "cancelled" or "failed"
+    pub model: Option<String>,
+    pub message: Option<String>,
+}
+
+impl Default for WarmupStatus {
+    fn default() -> Self {
+        WarmupStatus { state: "idle".to_string(), model: None, message: None }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WarmupProgressEvent {
+    pub model: String,
+    pub stage: String,  // "loading", "ready", "skipped", "cancelled" or "failed"
+    pub progress: f32,
+    pub message: String,
+}
+
+/// Warm-up readiness, shared with the frontend as managed state
+#[derive(Debug, Default)]
+pub struct LlmWarmupState {
+    status: Mutex<WarmupStatus>,
+    cancel: AtomicBool,
+}
+
+impl LlmWarmupState {
+    pub fn status(&self) -> WarmupStatus {
+        self.status.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    fn set(&self, state: &str, model: &str, message: Option<String>) {
+        if let Ok(mut status) = self.status.lock() {
+            *status = WarmupStatus { state: state.to_string(), model: Some(model.to_string()), message };
+        }
+    }
+
+    /// Cancel a running warm-up of a different model; returns whether one was cancelled
+    pub fn yield_to(&self, model: &str) -> bool {
+        let status = self.status();
+        let other_model_loading = status.state == "warming" && status.model.as_deref() != Some(model);
+        if other_model_loading {
+            self.cancel.store(true, Ordering::SeqCst);
+        }
+        other_model_loading
+    }
+}
+
+static LLM_WARMUP: Lazy<Arc<LlmWarmupState>> = Lazy::new(|| Arc::new(LlmWarmupState::default()));
+
+/// The warm-up state for `.manage()`
+pub fn llm_warmup_state() -> Arc<LlmWarmupState> {
+    LLM_WARMUP.clone()
+}
+
+/// Called by user LLM requests before they wait for the worker: a warm-up of the other
+/// model is aborted so the request switches models instead of waiting for it
+pub(crate) fn yield_llm_warmup(model: &str) {
+    if LLM_WARMUP.yield_to(model) {
+        println!("[RUST] Warm-up yields to a {} request", model);
+    }
+}
+
+fn get_startup_settings_path() -> Result<PathBuf, String> {
+    let app_dir = std::env::current_dir()
+        .map_err(|e| format!("Failed to get current directory: {}", e))?;
+    Ok(app_dir.join("user-data").join("settings").join("startup.json"))
+}
+
+fn load_startup_settings() -> StartupSettings {
+    get_startup_settings_path().ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Whether the model fits: into free VRAM if there is an NVIDIA GPU, else into RAM with a reserve
+pub fn warmup_memory_check(model_bytes: u64, available_ram: u64, gpu: Option<&GpuMemoryInfo>) -> Result<(), String> {
+    let required = model_bytes + WARMUP_OVERHEAD_BYTES;
+    if let Some(gpu) = gpu {
+        if gpu.total_bytes.saturating_sub(gpu.used_bytes) >= required {
+            return Ok(());
+        }
+    }
+    if available_ram >= required + WARMUP_RAM_RESERVE_BYTES {
+        return Ok(());
+    }
+    const GB: f64 = 1_000_000_000.0;
+    Err(format!(
+        "Zu wenig freier Speicher zum Vorladen ({:.1} GB benötigt, {:.1} GB RAM frei)",
+        required as f64 / GB, available_ram as f64 / GB
+    ))
+}
+
+fn emit_progress(app: &AppHandle, model: &str, stage: &str, progress: f32, message: String) {
+    let _ = app.emit("llm_warmup_progress", WarmupProgressEvent {
+        model: model.to_string(),
+        stage: stage.to_string(),
+        progress,
+        message,
+    });
+}
+
+fn run_warmup(app: &AppHandle, state: &LlmWarmupState) {
+    let use_qwen = llm_model_path(true).exists();
+    let model = if use_qwen { "qwen" } else { "llama" };
+    let model_path = llm_model_path(use_qwen);
+
+    let model_bytes = match fs::metadata(&model_path) {
+        Ok(metadata) => metadata.len(),
+        Err(_) => {
+            let message = "Kein LLM-Modell vorhanden".to_string();
+            state.set("skipped", model, Some(message.clone()));
+            emit_progress(app, model, "skipped", 0.0, message);
+            return;
+        }
+    };
+    let (_, available_ram) = get_system_memory_info();
+    if let Err(message) = warmup_memory_check(model_bytes, available_ram, get_gpu_memory_info().as_ref()) {
+        println!("[RUST] LLM warm-up skipped: {}", message);
+        state.set("skipped", model, Some(message.clone()));
+        emit_progress(app, model, "skipped", 0.0, message);
+        return;
+    }
+
+    println!("[RUST] Warming up {} worker in the background", model);
+    state.cancel.store(false, Ordering::SeqCst);
+    state.set("warming", model, None);
+    emit_progress(app, model, "loading", 0.0, "Sprachmodell wird vorgeladen...".to_string());
+
+    let on_progress = |progress: f32| emit_progress(app, model, "loading", progress, "Sprachmodell wird vorgeladen...".to_string());
+    match warm_up_worker(use_qwen, &state.cancel, &on_progress) {
+        Ok(()) => {
+            state.set("ready", model, None);
+            emit_progress(app, model, "ready", 1.0, "Sprachmodell bereit".to_string());
+        }
+        Err(e) if state.cancel.load(Ordering::SeqCst) => {
+            state.set("cancelled", model, Some(e));
+            emit_progress(app, model, "cancelled", 0.0, "Vorladen zugunsten einer Anfrage abgebrochen".to_string());
+        }
+        Err(e) => {
+            println!("[RUST] LLM warm-up failed: {}", e);
+            state.set("failed", model, Some(e.clone()));
+            emit_progress(app, model, "failed", 0.0, e);
+        }
+    }
+}
+
+/// Start the LLM warm-up on a background thread if enabled in the startup settings
+pub fn spawn_llm_warmup(app: AppHandle) {
+    if !load_startup_settings().preload_llm_on_start {
+        return;
+    }
+    let state = llm_warmup_state();
+    std::thread::spawn(move || run_warmup(&app, &state));
+}
+
+/// Get the startup settings
+#[command]
+pub async fn get_startup_settings() -> Result<StartupSettings, String> {
+    Ok(load_startup_settings())
+}
+
+/// Save the startup settings (takes effect at the next app start)
+#[command]
+pub async fn save_startup_settings(settings: StartupSettings) -> Result<StartupSettings, String> {
+    let path = get_startup_settings_path()?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create settings directory: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("Failed to serialize startup settings: {}", e))?;
+    fs::write(&path, json)
+        .map_err(|e| format!("Failed to write startup settings: {}", e))?;
+    Ok(settings)
+}
+
+/// Current state of the background warm-up
+#[command]
+pub async fn get_llm_warmup_status(state: State<'_, Arc<LlmWarmupState>>) -> Result<WarmupStatus, String> {
+    Ok(state.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warmup_checks_memory_and_yields_to_other_model() {
+        const GB: u64 = 1_000_000_000;
+        let gpu = GpuMemoryInfo { name: "RTX 4060".to_string(), used_bytes: GB, total_bytes: 8 * GB };
+        assert!(warmup_memory_check(5 * GB, 2 * GB, Some(&gpu)).is_ok());
+        assert!(warmup_memory_check(5 * GB, 9 * GB, None).is_ok());
+        assert!(warmup_memory_check(5 * GB, 7 * GB, None).unwrap_err().starts_with("Zu wenig freier Speicher"));
+
+        let state = LlmWarmupState::default();
+        state.set("warming", "qwen", None);
+        assert!(!state.yield_to("qwen"));
+        assert!(!state.cancel.load(Ordering::SeqCst));
+        assert!(state.yield_to("llama"));
+        assert!(state.cancel.load(Ordering::SeqCst));
+
+        state.set("ready", "qwen", None);
+        assert!(!LlmWarmupState::default().yield_to("llama"));
+        assert_eq!(state.status().state, "ready");
+    }
+}
@@ -0,0 +1,79 @@
+// Compiled regexes of the DOCX analyzer. Every pattern is compiled once per process instead of
+// on each call; patterns several extractors need are defined here only once.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+fn compile(pattern: &str) -> Regex {
+    Regex::new(pattern).expect("invalid analyzer pattern")
+}
+
+// Paragraphs and their properties
+pub static PARAGRAPH: Lazy<Regex> = Lazy::new(|| compile(r#"(?s)<w:p[ >].*?</w:p>"#));
+pub static PARAGRAPH_PROPERTIES: Lazy<Regex> = Lazy::new(|| compile(r#"(?s)<w:pPr>.*?</w:pPr>"#));
+/// Inner part of a pPr that may carry attributes (styles.xml)
+pub static PARAGRAPH_PROPERTIES_CONTENT: Lazy<Regex> = Lazy::new(|| compile(r#"(?s)<w:pPr\b[^>]*>(.*?)</w:pPr>"#));
+pub static PARAGRAPH_STYLE: Lazy<Regex> = Lazy::new(|| compile(r#"<w:pStyle[^>]*w:val="([^"]+)""#));
+/// Style IDs of headings; the level is the first capture (none for Title)
+pub static HEADING_STYLE_ID: Lazy<Regex> = Lazy::new(|| compile(r#"^(?:Heading|berschrift)(\d)$|^Title$"#));
+/// Display names of numbered heading styles ("heading 1", "Überschrift 2"), for custom style IDs
+pub static HEADING_STYLE_NAME: Lazy<Regex> = Lazy::new(|| compile(r#"(?i)^(?:heading|überschrift)\s*(\d)$"#));
+pub static TEXT_RUN: Lazy<Regex> = Lazy::new(|| compile(r#"<w:t[^>]*>([^<]*)</w:t>"#));
+pub static JUSTIFICATION: Lazy<Regex> = Lazy::new(|| compile(r#"<w:jc[^>]*w:val="([^"]+)""#));
+/// A whole <w:color> element; value and theme reference are read by color_utils
+pub static COLOR_ELEMENT: Lazy<Regex> = Lazy::new(|| compile(r#"<w:color\b[^>]*/?>"#));
+
+// Indentation and tab stops
+pub static INDENTATION: Lazy<Regex> = Lazy::new(|| compile(r#"<w:ind\s[^>]*/?>"#));
+pub static INDENTATION_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| compile(r#"w:(left|start|firstLine|hanging)="(-?\d+)""#));
+pub static TABS: Lazy<Regex> = Lazy::new(|| compile(r#"(?s)<w:tabs>(.*?)</w:tabs>"#));
+pub static TAB_POSITION: Lazy<Regex> = Lazy::new(|| compile(r#"w:pos="(-?\d+)""#));
+
+// Spacing
+pub static SPACING_ATTRIBUTES: Lazy<Regex> = Lazy::new(|| compile(r#"<w:spacing\b([^>]*)>"#));
+pub static SPACING_BEFORE: Lazy<Regex> = Lazy::new(|| compile(r#"w:before="(\d+)""#));
+pub static SPACING_AFTER: Lazy<Regex> = Lazy::new(|| compile(r#"w:after="(\d+)""#));
+/// Line spacing candidates, most specific first; the line value is the last capture
+pub static LINE_SPACING: Lazy<Vec<Regex>> = Lazy::new(|| vec![
+    compile(r#"<w:spacing[^>]*w:line="(\d+)""#),
+    compile(r#"<w:spacing[^>]*w:lineRule="([^"]+)"[^>]*w:line="(\d+)""#),
+    compile(r#"w:line="(\d+)""#),
+    compile(r#"<w:pPr[^>]*><w:spacing[^>]*w:line="(\d+)""#),
+]);
+
+// Fonts and sizes
+pub static RUN_FONT: Lazy<Regex> = Lazy::new(|| compile(r#"<w:rFonts[^>]*w:ascii="([^"]+)""#));
+pub static RUN_FONT_HANSI: Lazy<Regex> = Lazy::new(|| compile(r#"<w:rFonts[^>]*w:hAnsi="([^"]+)""#));
+pub static RUN_FONT_CS: Lazy<Regex> = Lazy::new(|| compile(r#"<w:rFonts[^>]*w:cs="([^"]+)""#));
+pub static ASCII_FONT: Lazy<Regex> = Lazy::new(|| compile(r#"w:ascii="([^"]+)""#));
+pub static STYLE_NAME: Lazy<Regex> = Lazy::new(|| compile(r#"<w:name[^>]*w:val="([^"]+)""#));
+pub static RUN_SIZE: Lazy<Regex> = Lazy::new(|| compile(r#"<w:sz[^>]*w:val="(\d+)""#));
+pub static SIZE_ATTRIBUTE: Lazy<Regex> = Lazy::new(|| compile(r#"w:sz="(\d+)""#));
+pub static RUN_SIZE_CS: Lazy<Regex> = Lazy::new(|| compile(r#"<w:szCs[^>]*w:val="(\d+)""#));
+pub static SIZE_CONTENT: Lazy<Regex> = Lazy::new(|| compile(r#"w:val="(\d+)"[^>]*>[^<]*</w:sz>"#));
+
+/// Document-wide alignment, first match wins
+pub static ALIGNMENT: Lazy<Vec<(Regex, &'static str)>> = Lazy::new(|| vec![
+    (compile(r#"<w:jc[^>]*w:val="center""#), "center"),
+    (compile(r#"<w:jc[^>]*w:val="right""#), "right"),
+    (compile(r#"<w:jc[^>]*w:val="both""#), "justify"),
+    (compile(r#"<w:jc[^>]*w:val="distribute""#), "justify"),
+    (compile(r#"<w:jc[^>]*w:val="left""#), "left"),
+    (compile(r#"w:val="center""#), "center"),
+    (compile(r#"w:val="right""#), "right"),
+    (compile(r#"w:val="both""#), "justify"),
+]);
+
+// styles.xml
+pub static STYLE: Lazy<Regex> = Lazy::new(|| compile(r#"(?s)<w:style\b([^>]*)>.*?</w:style>"#));
+pub static STYLE_ID: Lazy<Regex> = Lazy::new(|| compile(r#"w:styleId="([^"]+)""#));
+pub static BASED_ON: Lazy<Regex> = Lazy::new(|| compile(r#"<w:basedOn w:val="([^"]+)""#));
+pub static PARAGRAPH_DEFAULTS: Lazy<Regex> = Lazy::new(|| compile(r#"(?s)<w:pPrDefault>(.*?)</w:pPrDefault>"#));
+
+// Breaks
+pub static PAGE_BREAK: Lazy<Regex> = Lazy::new(|| compile(r#"<w:br\b[^>]*w:type="page""#));
+pub static BODY_SECTION: Lazy<Regex> = Lazy::new(|| compile(r#"(?:</w:sectPr>|<w:sectPr[^>]*/>)\s*</w:body>"#));
+
+// Header/footer content that is only a page number ("Seite 3", "- 3 -")
+pub static PAGE_NUMBER_LABEL: Lazy<Regex> = Lazy::new(|| compile(r"^(page|seite|p\.)\s*\d+$"));
+pub static DASHED_PAGE_NUMBER: Lazy<Regex> = Lazy::new(|| compile(r"^-\s*\d+\s*-$"));
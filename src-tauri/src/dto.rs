@@ -0,0 +1,64 @@
+// Data shapes the frontend receives from more than one command; kept in one place so there is
+// exactly one definition of each (see src/services/llamaService.ts for the TypeScript side)
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GrammarCorrectionResponse {
+    pub corrected_text: String,
+    pub changes_made: Vec<String>,
+    pub confidence: f32,
+    pub processing_time_ms: u64,
+    pub guardrail_status: String,
+    pub violations: Vec<String>,
+    pub notes: Vec<String>,
+    pub attempts: u32,
+    pub removed_tokens: Vec<String>,
+    pub tokens_per_sec: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt_debug: Option<Value>,  // Composed prompt, only with prompt debugging enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<Value>,    // Sampling parameters the worker actually used
+    #[serde(default)]
+    pub model_used: String,           // "llama" or "qwen"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grammar_response_keeps_the_field_names_the_frontend_reads() {
+        let response = GrammarCorrectionResponse {
+            corrected_text: "Der Patient berichtet.".to_string(),
+            changes_made: vec!["berichted -> berichtet".to_string()],
+            confidence: 0.9,
+            processing_time_ms: 1200,
+            guardrail_status: "passed".to_string(),
+            violations: Vec::new(),
+            notes: Vec::new(),
+            attempts: 1,
+            removed_tokens: Vec::new(),
+            tokens_per_sec: Some(12.5),
+            prompt_debug: None,
+            generation: Some(serde_json::json!({"temperature": 0.1})),
+            model_used: "qwen".to_string(),
+        };
+
+        let value = serde_json::to_value(&response).unwrap();
+        let mut fields: Vec<&str> = value.as_object().unwrap().keys().map(String::as_str).collect();
+        fields.sort();
+        assert_eq!(fields, vec![
+            "attempts", "changes_made", "confidence", "corrected_text", "generation", "guardrail_status",
+            "model_used", "notes", "processing_time_ms", "removed_tokens", "tokens_per_sec", "violations",
+        ]);
+
+        let old: GrammarCorrectionResponse = serde_json::from_value(serde_json::json!({
+            "corrected_text": "x", "changes_made": [], "confidence": 1.0, "processing_time_ms": 5,
+            "guardrail_status": "passed", "violations": [], "notes": [], "attempts": 1,
+            "removed_tokens": [], "tokens_per_sec": null
+        })).unwrap();
+        assert_eq!(old.model_used, "");
+    }
+}
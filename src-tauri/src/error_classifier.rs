@@ -0,0 +1,147 @@
+// Maps known failure output of Python scripts and FFmpeg (CUDA OOM, missing modules, broken input)
+// to German explanations with a suggested fix. The table is error_signatures.json next to the
+// scripts, so new signatures need no rebuild; the copy compiled in is the fallback.
+
+use once_cell::sync::Lazy;
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::sync::Mutex;
+
+use crate::script_locator::locate_script;
+
+pub const ERROR_SIGNATURES_FILE: &str = "error_signatures.json";
+const BUILTIN_SIGNATURES: &str = include_str!("../../error_signatures.json");
+/// Classified errors kept for support bundles
+const MAX_RECENT_ERRORS: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ErrorSignature {
+    pub code: String,
+    pub patterns: Vec<String>,  // Case-insensitive regexes; the first capture group is named in the message
+    pub message: String,
+    pub remediation: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClassifiedError {
+    pub code: String,
+    pub message: String,
+    pub remediation: String,
+    pub details: String,  // Raw output, for support bundles only
+}
+
+impl ClassifiedError {
+    /// The text shown to the user
+    pub fn user_message(&self) -> String {
+        format!("{} – {}", self.message, self.remediation)
+    }
+}
+
+struct CompiledSignature {
+    signature: ErrorSignature,
+    patterns: Vec<Regex>,
+}
+
+fn compile_signatures(json: &str) -> Result<Vec<CompiledSignature>, String> {
+    let signatures: Vec<ErrorSignature> = serde_json::from_str(json)
+        .map_err(|e| format!("Failed to parse error signatures: {}", e))?;
+    signatures.into_iter()
+        .map(|signature| {
+            let patterns = signature.patterns.iter()
+                .map(|p| RegexBuilder::new(p).case_insensitive(true).build()
+                    .map_err(|e| format!("Invalid pattern in {}: {}", signature.code, e)))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(CompiledSignature { signature, patterns })
+        })
+        .collect()
+}
+
+static SIGNATURES: Lazy<Vec<CompiledSignature>> = Lazy::new(|| {
+    let bundled = locate_script(ERROR_SIGNATURES_FILE).ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .map(|json| compile_signatures(&json));
+    match bundled {
+        Some(Ok(signatures)) => signatures,
+        Some(Err(e)) => {
+            println!("⚠️ {}, using built-in error signatures", e);
+            compile_signatures(BUILTIN_SIGNATURES).unwrap_or_default()
+        }
+        None => compile_signatures(BUILTIN_SIGNATURES).unwrap_or_default(),
+    }
+});
+
+static RECENT_ERRORS: Mutex<VecDeque<ClassifiedError>> = Mutex::new(VecDeque::new());
+
+fn classify_with(signatures: &[CompiledSignature], raw: &str) -> Option<ClassifiedError> {
+    signatures.iter().find_map(|compiled| {
+        let captures = compiled.patterns.iter().find_map(|p| p.captures(raw))?;
+        let message = match captures.get(1) {
+            Some(name) => format!("{} ({})", compiled.signature.message, name.as_str()),
+            None => compiled.signature.message.clone(),
+        };
+        Some(ClassifiedError {
+            code: compiled.signature.code.clone(),
+            message,
+            remediation: compiled.signature.remediation.clone(),
+            details: raw.to_string(),
+        })
+    })
+}
+
+/// Match raw subprocess output against the known failure signatures
+pub fn classify_error(raw: &str) -> Option<ClassifiedError> {
+    classify_with(&SIGNATURES, raw)
+}
+
+/// German explanation for a known failure (remembered for support bundles);
+/// unknown errors are returned unchanged
+pub fn explain_error(raw: String) -> String {
+    match classify_error(&raw) {
+        Some(classified) => {
+            println!("⚠️ {} ({}): {}", classified.message, classified.code, raw.trim());
+            let message = classified.user_message();
+            if let Ok(mut recent) = RECENT_ERRORS.lock() {
+                if recent.len() == MAX_RECENT_ERRORS {
+                    recent.pop_front();
+                }
+                recent.push_back(classified);
+            }
+            message
+        }
+        None => raw,
+    }
+}
+
+/// Classified errors of this run, oldest first
+pub fn recent_errors() -> Vec<ClassifiedError> {
+    RECENT_ERRORS.lock().map(|recent| recent.iter().cloned().collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_failures_are_explained_and_unknown_pass_through() {
+        let signatures = compile_signatures(BUILTIN_SIGNATURES).unwrap();
+
+        let oom = classify_with(&signatures, "RuntimeError: CUDA out of memory. Tried to allocate 20.00 MiB").unwrap();
+        assert_eq!(oom.code, "gpu_out_of_memory");
+        assert_eq!(oom.user_message(), "Grafikspeicher reicht nicht aus – Schließen Sie andere Programme oder wählen Sie das kleinere Modell");
+        assert!(oom.details.contains("Tried to allocate"));
+
+        let module = classify_with(&signatures, "Traceback ...\nModuleNotFoundError: No module named 'faster_whisper'").unwrap();
+        assert_eq!(module.message, "Ein Python-Paket fehlt (faster_whisper)");
+
+        let ffmpeg = classify_with(&signatures, "FFmpeg failed with ffmpeg: diktat.m4a: Invalid data found when processing input").unwrap();
+        assert_eq!(ffmpeg.code, "ffmpeg_invalid_data");
+
+        assert!(classify_with(&signatures, "Template extraction failed: unexpected key").is_none());
+        assert_eq!(explain_error("Template extraction failed: unexpected key".to_string()), "Template extraction failed: unexpected key");
+
+        let custom = compile_signatures(r#"[{"code": "license", "patterns": ["license expired"], "message": "Lizenz abgelaufen", "remediation": "Lizenz erneuern"}]"#).unwrap();
+        assert_eq!(classify_with(&custom, "Error: License expired").unwrap().code, "license");
+    }
+}
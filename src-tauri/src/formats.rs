@@ -0,0 +1,62 @@
+// Supported file formats, shared by all validation sites and the frontend file pickers
+
+use serde::{Deserialize, Serialize};
+
+/// Audio formats accepted for transcription (webm is what the in-app recorder writes)
+pub const AUDIO_FORMATS: &[&str] = &["wav", "mp3", "m4a", "flac", "ogg", "webm"];
+pub const DOCUMENT_FORMATS: &[&str] = &["pdf", "doc", "docx", "txt", "rtf"];
+pub const IMAGE_FORMATS: &[&str] = &["png", "jpg", "jpeg", "tiff", "tif", "bmp"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupportedFormats {
+    pub audio: Vec<String>,
+    pub documents: Vec<String>,
+    pub images: Vec<String>,
+}
+
+impl SupportedFormats {
+    pub fn current() -> Self {
+        SupportedFormats {
+            audio: to_strings(AUDIO_FORMATS),
+            documents: to_strings(DOCUMENT_FORMATS),
+            images: to_strings(IMAGE_FORMATS),
+        }
+    }
+}
+
+pub fn to_strings(formats: &[&str]) -> Vec<String> {
+    formats.iter().map(|f| f.to_string()).collect()
+}
+
+/// Extensions are compared case-insensitively ("MP3" is accepted)
+pub fn is_audio_format(extension: &str) -> bool {
+    AUDIO_FORMATS.contains(&extension.to_lowercase().as_str())
+}
+
+pub fn is_document_format(extension: &str) -> bool {
+    DOCUMENT_FORMATS.contains(&extension.to_lowercase().as_str())
+}
+
+pub fn is_image_format(extension: &str) -> bool {
+    IMAGE_FORMATS.contains(&extension.to_lowercase().as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{AudioService, FileService};
+
+    #[test]
+    fn test_audio_formats_identical_across_paths() {
+        let service_formats = AudioService::get_supported_formats();
+        let file_service_formats = FileService::get_supported_file_types().into_iter()
+            .find(|(category, _)| category == "Audio Processing")
+            .map(|(_, formats)| formats)
+            .unwrap();
+
+        assert_eq!(service_formats, SupportedFormats::current().audio);
+        assert_eq!(file_service_formats, SupportedFormats::current().audio);
+        assert!(is_audio_format("WEBM") && !is_audio_format("mp4"));
+        assert!(is_image_format("tif") && is_document_format("docx"));
+    }
+}
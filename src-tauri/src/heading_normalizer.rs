@@ -0,0 +1,129 @@
+// Canonical forms of section headings, shared by the header extraction, the StyleProfile,
+// the template anchors and the consistency checks so "Eigen-Anamnese", "EIGENANAMNESE" and
+// "2. Eigenanamnese:" count as the same section everywhere
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use crate::commands::audio_commands::text_similarity;
+
+/// Known German medical section headings, in canonical form
+pub const KNOWN_SECTIONS: &[&str] = &[
+    "ANAMNESE", "FAMILIENANAMNESE", "EIGENANAMNESE", "SOZIALANAMNESE",
+    "BEFUND", "DIAGNOSE", "DIAGNOSEN", "BEURTEILUNG",
+    "ZUSAMMENFASSUNG", "EPIKRISE", "PROGNOSE", "THERAPIE",
+    "MEDIKATION", "MEDIKAMENTE", "LABOR", "BILDGEBUNG",
+    "NEUROLOGISCH", "PSYCHIATRISCH", "PSYCHOPATHOLOGISCH",
+    "VORGESCHICHTE", "KRANKENGESCHICHTE", "BESCHWERDEN",
+    "LEISTUNGSBEURTEILUNG", "SOZIALMEDIZINISCH", "EMPFEHLUNG",
+];
+
+/// Numbering prefixes: "2.", "2.1", "2.1.", "II.", "a)", "(3)", "§ 4"
+static NUMBERING_PREFIX: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^\s*(?:\(?(?:\d+(?:\.\d+)+\s|\d+(?:\.\d+)*[.)]|[IVXLC]+[.)]|[A-Za-z][.)])|§\s*\d+)\s*").unwrap()
+});
+
+/// Hyphens and dashes used in headings ("Eigen-Anamnese", "Eigen – Anamnese")
+const HYPHENS: &[char] = &['-', '‐', '‑', '‒', '–', '—', '\u{00AD}'];
+
+fn fold_char(c: char, out: &mut String) {
+    match c {
+        'ä' | 'Ä' => out.push_str("AE"),
+        'ö' | 'Ö' => out.push_str("OE"),
+        'ü' | 'Ü' => out.push_str("UE"),
+        'ß' | 'ẞ' => out.push_str("SS"),
+        _ => out.extend(c.to_uppercase()),
+    }
+}
+
+/// Canonical heading name: numbering, trailing colons and periods, hyphens and extra
+/// whitespace removed, umlauts folded, upper case ("2. Körperliche Untersuchung:" -> "KOERPERLICHE UNTERSUCHUNG")
+pub fn canonical_heading(text: &str) -> String {
+    let text = NUMBERING_PREFIX.replace(text.trim(), "");
+    let text = text.trim().trim_end_matches([':', '.', ' ']);
+
+    let mut folded = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if HYPHENS.contains(&c) {
+            // "Eigen - Anamnese" joins like "Eigen-Anamnese"; drop the space before the hyphen too
+            if folded.ends_with(' ') {
+                folded.pop();
+            }
+            while chars.peek().is_some_and(|c| c.is_whitespace()) {
+                chars.next();
+            }
+            continue;
+        }
+        fold_char(c, &mut folded);
+    }
+
+    folded.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Similarity of two headings in [0, 1] after canonicalization; spaces are ignored so
+/// "Eigen Anamnese" and "Eigenanamnese" are identical
+pub fn heading_similarity(a: &str, b: &str) -> f32 {
+    let compact = |text: &str| canonical_heading(text).replace(' ', "");
+    text_similarity(&compact(a), &compact(b))
+}
+
+/// Whether two headings name the same section
+pub fn same_heading(a: &str, b: &str) -> bool {
+    canonical_heading(a) == canonical_heading(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_real_world_heading_variants() {
+        let cases = [
+            ("Eigenanamnese", "EIGENANAMNESE"),
+            ("EIGENANAMNESE", "EIGENANAMNESE"),
+            ("Eigenanamnese:", "EIGENANAMNESE"),
+            ("Eigen-Anamnese", "EIGENANAMNESE"),
+            ("Eigen – Anamnese", "EIGENANAMNESE"),
+            ("2. Eigenanamnese:", "EIGENANAMNESE"),
+            ("  eigenanamnese  ", "EIGENANAMNESE"),
+            ("Familienanamnese", "FAMILIENANAMNESE"),
+            ("Familien-Anamnese:", "FAMILIENANAMNESE"),
+            ("1.2 Familienanamnese", "FAMILIENANAMNESE"),
+            ("Sozialanamnese", "SOZIALANAMNESE"),
+            ("III. Sozial-Anamnese", "SOZIALANAMNESE"),
+            ("Befund", "BEFUND"),
+            ("BEFUND:", "BEFUND"),
+            ("4) Befund", "BEFUND"),
+            ("(4) Befund", "BEFUND"),
+            ("a) Befund", "BEFUND"),
+            ("Körperliche Untersuchung", "KOERPERLICHE UNTERSUCHUNG"),
+            ("KÖRPERLICHE  UNTERSUCHUNG", "KOERPERLICHE UNTERSUCHUNG"),
+            ("Koerperliche Untersuchung:", "KOERPERLICHE UNTERSUCHUNG"),
+            ("Aktuelle Beschwerden", "AKTUELLE BESCHWERDEN"),
+            ("3.1. Aktuelle Beschwerden:", "AKTUELLE BESCHWERDEN"),
+            ("Psychopathologischer Befund", "PSYCHOPATHOLOGISCHER BEFUND"),
+            ("Psycho-pathologischer Befund", "PSYCHOPATHOLOGISCHER BEFUND"),
+            ("Diagnosen", "DIAGNOSEN"),
+            ("DIAGNOSEN:", "DIAGNOSEN"),
+            ("Beurteilung", "BEURTEILUNG"),
+            ("5. Sozialmedizinische Beurteilung.", "SOZIALMEDIZINISCHE BEURTEILUNG"),
+            ("Zusammenfassung und Beurteilung", "ZUSAMMENFASSUNG UND BEURTEILUNG"),
+            ("Gemäß § 4", "GEMAESS § 4"),
+            ("§ 2 Fragestellung", "FRAGESTELLUNG"),
+            ("Fragestellung des Auftraggebers:", "FRAGESTELLUNG DES AUFTRAGGEBERS"),
+            ("Leistungsbeurteilung", "LEISTUNGSBEURTEILUNG"),
+            ("Größe und Gewicht", "GROESSE UND GEWICHT"),
+            ("Übersicht", "UEBERSICHT"),
+            ("Anlagen:", "ANLAGEN"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(canonical_heading(input), expected, "input: {:?}", input);
+        }
+
+        assert!(same_heading("Eigen-Anamnese", "2. EIGENANAMNESE:"));
+        assert_eq!(heading_similarity("Eigen Anamnese", "Eigenanamnese"), 1.0);
+        assert!(heading_similarity("Psychopatologischer Befund", "Psychopathologischer Befund") > 0.9);
+        assert!(heading_similarity("Befund", "Beurteilung") < 0.5);
+    }
+}
@@ -4,8 +4,19 @@ pub mod commands;
 pub mod services;
 pub mod models;
 pub mod memory_manager;
+pub mod text_segmentation;
+pub mod formats;
+pub mod heading_normalizer;
+pub mod script_locator;
+pub mod template_schema;
+pub mod error_classifier;
+pub mod docx_patterns;
+pub mod dto;
+pub mod color_utils;
+pub mod analysis_trace;
+pub mod cloud_sync;
 
-pub use commands::*;
-pub use services::*;
-pub use models::*;
-pub use memory_manager::*;
\ No newline at end of file
+// Deliberate public API; everything else is reached through its module path
+pub use dto::GrammarCorrectionResponse;
+pub use memory_manager::MemoryManager;
+pub use services::{AudioService, FileService, ModelService};
\ No newline at end of file
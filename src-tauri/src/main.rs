@@ -8,72 +8,236 @@ mod commands;
 mod services;
 mod models;
 mod memory_manager;
+mod text_segmentation;
+mod formats;
+mod heading_normalizer;
+mod script_locator;
+mod template_schema;
+mod error_classifier;
+mod docx_patterns;
+mod dto;
+mod color_utils;
+mod analysis_trace;
+mod cloud_sync;
 
 use commands::{system_info, model_info};
 use memory_manager::MemoryManager;
+use services::ModelService;
 
 #[tokio::main]
 async fn main() {
     // Initialize memory manager for large AI models
     let memory_manager = Arc::new(MemoryManager::new());
+    let model_service = Arc::new(ModelService::new(memory_manager.clone()));
 
     // Initialize Llama service for grammar correction
 
     tauri::Builder::default()
+        // Must come first: a second start (double-clicked file) hands its arguments to this instance
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.set_focus();
+            }
+            // Relative paths belong to the second instance's working directory, not ours
+            commands::queue_external_files(app, &argv, std::path::Path::new(&cwd));
+        }))
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
         .manage(memory_manager)
+        .manage(model_service)
+        .manage(commands::llm_warmup_state())
         .invoke_handler(tauri::generate_handler![
             system_info,
             model_info,
+            commands::get_supported_formats,
             commands::load_whisper_model,
+            commands::get_memory_settings,
+            commands::save_memory_settings,
+            commands::get_model_status,
+            commands::check_system_requirements,
+            commands::get_app_readiness,
+            commands::check_dependencies,
             commands::process_audio_file,
             commands::save_audio_file,
             commands::convert_audio_to_wav,
+            commands::split_audio_by_silence,
             commands::transcribe_audio_simple,
+            commands::transcribe_range,
+            commands::compare_whisper_models,
+            commands::get_chunking_settings,
+            commands::save_chunking_settings,
+            commands::get_transcription_language_settings,
+            commands::save_transcription_language_settings,
+            commands::dedupe_segments,
+            commands::strip_transcription_artifacts,
+            commands::analyze_audio_levels,
             commands::validate_audio_file,
             commands::get_system_memory,
+            commands::subscribe_memory_updates,
             commands::cleanup_models,
+            commands::list_orphaned_temp_files,
+            commands::cleanup_orphaned_temp_files,
             commands::analyze_document_style,
+            commands::analyze_pdf_style,
+            commands::clear_analysis_cache,
+            commands::compute_document_id,
+            commands::extract_document_indentation,
+            commands::aggregate_formatting,
             commands::save_style_template,
             commands::save_uploaded_document,
             commands::get_saved_templates,
+            commands::compose_style,
+            commands::validate_template_file,
             commands::download_llama_model,
             commands::load_llama_model,
             commands::correct_german_grammar,
+            commands::quick_fix_text,
             commands::get_llama_model_info,
             commands::is_llama_model_ready,
             commands::create_styled_docx,
+            commands::preview_paragraph_classification,
             commands::detect_formatting_request,
+            commands::parse_formatting_request,
             commands::format_docx_with_request,
             commands::format_docx_with_spec,
+            commands::list_format_history,
+            commands::undo_format,
+            commands::unify_document_font,
             // Style Profile commands
             commands::analyze_example_documents,
             commands::load_style_profile,
+            commands::diff_profiles,
             commands::get_style_profile_status,
             commands::clear_style_profile,
             commands::get_style_profile_prompt,
+            commands::get_style_profile_prompt_structured,
+            commands::check_spec_profile_consistency,
             // Template management commands
             commands::get_template_info,
             commands::download_template,
             commands::save_template_with_dialog,
             commands::upload_corrected_template,
             commands::approve_template,
+            commands::validate_template_against_profile,
             commands::is_template_approved,
             // Llama worker management
             commands::shutdown_llama_worker,
+            commands::get_startup_settings,
+            commands::save_startup_settings,
+            commands::get_llm_warmup_status,
+            commands::validate_gguf,
+            commands::get_loaded_model_details,
+            commands::benchmark_llm,
+            commands::get_performance_history,
             commands::structure_gutachten_transcript,
+            commands::get_last_prompt,
+            commands::get_prompt_debug_enabled,
+            commands::set_prompt_debug_enabled,
+            commands::get_generation_settings,
+            commands::save_generation_settings,
+            commands::reset_generation_defaults,
+            commands::get_worker_capabilities,
+            commands::get_worker_logs,
             // Template extraction and DOCX rendering
             commands::extract_template,
+            commands::extract_anchors_rust,
             commands::get_template_spec,
             commands::save_template_spec,
+            commands::add_skeleton_item,
+            commands::remove_skeleton_item,
+            commands::move_skeleton_item,
+            commands::rename_anchor,
             commands::render_gutachten_docx,
             commands::is_template_ready,
-            commands::get_template_slots
+            commands::get_template_slots,
+            commands::list_template_families,
+            commands::set_active_template_family,
+            commands::delete_template_family,
+            commands::resolve_style_roles,
+            commands::render_sessions_batch,
+            // Session storage
+            commands::save_session_transcription,
+            commands::get_session_transcription,
+            commands::get_session_audio_info,
+            commands::save_session_structured_content,
+            commands::get_session_status,
+            commands::record_session_spelling_issues,
+            commands::resolve_unclear_span,
+            commands::save_session_metadata,
+            commands::get_session_metadata,
+            commands::retranscribe_range,
+            commands::export_session_bundle,
+            commands::create_support_bundle,
+            // Text language detection
+            commands::detect_text_language,
+            // Transcript text checks
+            commands::find_duplicate_paragraphs,
+            commands::get_readability_score,
+            commands::check_encoding_health,
+            commands::section_word_counts,
+            // Signature blocks
+            commands::get_signature_blocks,
+            commands::save_signature_block,
+            commands::delete_signature_block,
+            // Export placeholders
+            commands::resolve_docx_placeholders,
+            commands::get_placeholder_values,
+            // Export quality gate
+            commands::check_quality_gate,
+            commands::prerender_check,
+            commands::get_quality_gate_settings,
+            commands::save_quality_gate_settings,
+            // Multi-format export
+            commands::export_all_formats,
+            // Editor autosave
+            commands::autosave_editor_content,
+            commands::get_autosaved_content,
+            // Section revision history
+            commands::save_section_revision,
+            commands::undo_section_revision,
+            commands::redo_section_revision,
+            commands::get_section_history,
+            // Interrupted model downloads
+            commands::resume_pending_downloads,
+            // Files opened via the OS
+            commands::get_pending_external_files,
+            commands::take_next_external_file,
+            commands::flatten_structured_content,
+            commands::structure_by_headers,
+            commands::detect_sections_in_text,
+            commands::import_gutachten_docx,
+            commands::list_docx_backups,
+            commands::restore_docx_backup,
+            commands::generate_qc_report,
+            commands::get_temp_dir_settings,
+            commands::save_temp_dir_settings,
+            commands::get_section_prompts,
+            commands::set_section_prompt,
+            commands::reset_section_prompt,
+            commands::normalize_typography,
+            commands::estimate_structuring_time,
+            commands::export_with_selected_header,
+            commands::estimate_pagination,
+            commands::validate_docx,
+            commands::export_sections_separately,
+            commands::get_analysis_trace,
+            commands::normalize_line_spacing,
+            commands::list_document_fonts
         ])
         .setup(|app| {
             let app_handle = app.handle().clone();
 
+            // The installer puts the Python scripts under <resources>/scripts
+            if let Ok(resource_dir) = app.path().resource_dir() {
+                script_locator::set_resource_dir(resource_dir);
+            }
+
+            // Files the app was started with (file association, "Öffnen mit")
+            let argv: Vec<String> = std::env::args().collect();
+            let cwd = std::env::current_dir().unwrap_or_default();
+            commands::queue_external_files(&app_handle, &argv, &cwd);
+
             // Setup application-specific configurations
             tauri::async_runtime::spawn(async move {
                 // Pre-initialize system components
@@ -90,6 +254,10 @@ async fn main() {
 
 /// Initialize application-specific systems
 async fn initialize_application_systems(app_handle: &tauri::AppHandle) -> Result<(), anyhow::Error> {
+    // Register the models whose status and last use get_model_status reports
+    app_handle.state::<Arc<ModelService>>().initialize_models().await
+        .map_err(anyhow::Error::msg)?;
+
     // Check system requirements
     let available_memory = get_available_memory().await?;
     if available_memory < 4_000_000_000 {  // 4GB minimum
@@ -105,6 +273,17 @@ async fn initialize_application_systems(app_handle: &tauri::AppHandle) -> Result
         println!("Created embedded models directory: {:?}", models_dir);
     }
     
+    // Warn early if the configured temp directory cannot be written to
+    if let Err(e) = commands::validate_temp_dir_setting() {
+        eprintln!("Warning: Temp directory setting not usable: {}", e);
+    }
+
+    // Load the LLM in the background if the user opted in; yields to explicit requests
+    commands::spawn_llm_warmup(app_handle.clone());
+
+    // Continue model downloads interrupted by the last shutdown
+    commands::spawn_download_resume(app_handle.clone());
+
     Ok(())
 }
 
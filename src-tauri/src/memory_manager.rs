@@ -175,15 +175,81 @@ impl Default for MemoryManager {
 }
 
 // Helper functions for system memory detection
+const FALLBACK_SYSTEM_MEMORY: (u64, u64) = (8 * 1024 * 1024 * 1024, 6 * 1024 * 1024 * 1024); // (8GB total, 6GB available)
+
+/// Total and available physical memory in bytes (falls back to 8GB/6GB if it can't be read)
 #[cfg(target_os = "windows")]
 pub fn get_system_memory_info() -> (u64, u64) {
-    // Windows-specific memory detection would go here
-    // For now, return reasonable defaults
-    (8 * 1024 * 1024 * 1024, 6 * 1024 * 1024 * 1024) // (8GB total, 6GB available)
+    use windows_sys::Win32::System::SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX};
+
+    // SAFETY: MEMORYSTATUSEX is plain data; dwLength must be set before the call
+    let mut status: MEMORYSTATUSEX = unsafe { std::mem::zeroed() };
+    status.dwLength = std::mem::size_of::<MEMORYSTATUSEX>() as u32;
+    if unsafe { GlobalMemoryStatusEx(&mut status) } == 0 {
+        return FALLBACK_SYSTEM_MEMORY;
+    }
+    (status.ullTotalPhys, status.ullAvailPhys)
 }
 
 #[cfg(not(target_os = "windows"))]
 pub fn get_system_memory_info() -> (u64, u64) {
-    // Cross-platform fallback
-    (8 * 1024 * 1024 * 1024, 6 * 1024 * 1024 * 1024)
-}
\ No newline at end of file
+    std::fs::read_to_string("/proc/meminfo")
+        .ok()
+        .and_then(|content| parse_meminfo(&content))
+        .unwrap_or(FALLBACK_SYSTEM_MEMORY)
+}
+
+fn kib_value(text: &str, key: &str) -> Option<u64> {
+    text.lines()
+        .filter_map(|line| line.trim().split_once(':'))
+        .find(|(name, _)| name.trim() == key)
+        .and_then(|(_, value)| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+        .map(|kib| kib * 1024)
+}
+
+/// Parse /proc/meminfo (values in kB)
+pub fn parse_meminfo(content: &str) -> Option<(u64, u64)> {
+    Some((kib_value(content, "MemTotal")?, kib_value(content, "MemAvailable")?))
+}
+
+/// VRAM of the first NVIDIA GPU
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GpuMemoryInfo {
+    pub name: String,
+    pub used_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Query VRAM via nvidia-smi; None without an NVIDIA GPU/driver. Starts a process, so callers
+/// that poll should not do so every tick.
+pub fn get_gpu_memory_info() -> Option<GpuMemoryInfo> {
+    let mut command = std::process::Command::new("nvidia-smi");
+    command.args(["--query-gpu=name,memory.used,memory.total", "--format=csv,noheader,nounits"]);
+    #[cfg(target_os = "windows")]
+    {
+        // The release build has no console; without this every call flashes a window
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_nvidia_smi_memory(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// Parse "name, used MiB, total MiB" (first line = first GPU)
+pub fn parse_nvidia_smi_memory(content: &str) -> Option<GpuMemoryInfo> {
+    let line = content.lines().next()?;
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 3 {
+        return None;
+    }
+    const MIB: u64 = 1024 * 1024;
+    Some(GpuMemoryInfo {
+        name: fields[0].to_string(),
+        used_bytes: fields[1].parse::<u64>().ok()? * MIB,
+        total_bytes: fields[2].parse::<u64>().ok()? * MIB,
+    })
+}
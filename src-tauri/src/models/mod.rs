@@ -0,0 +1,10 @@
+// Model handles tracked by ModelService. The models themselves run in the Python workers;
+// these only record version and load state for status reporting.
+
+pub mod whisper_model;
+pub mod ocr_model;
+pub mod nlp_model;
+
+pub use whisper_model::WhisperModel;
+pub use ocr_model::OcrModel;
+pub use nlp_model::NlpModel;
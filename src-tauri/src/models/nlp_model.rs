@@ -0,0 +1,23 @@
+// spaCy German medical model handle; not loaded by the app yet
+
+#[derive(Debug, Clone)]
+pub struct NlpModel {
+    pub version: String,
+    loaded: bool,
+}
+
+impl Default for NlpModel {
+    fn default() -> Self {
+        Self {
+            version: "de_core_news_lg".to_string(),
+            loaded: false,
+        }
+    }
+}
+
+impl NlpModel {
+    /// Whether the model is loaded and can annotate text
+    pub fn is_ready(&self) -> bool {
+        self.loaded
+    }
+}
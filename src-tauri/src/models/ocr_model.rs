@@ -0,0 +1,23 @@
+// Tesseract OCR handle; not loaded by the app yet
+
+#[derive(Debug, Clone)]
+pub struct OcrModel {
+    pub version: String,
+    loaded: bool,
+}
+
+impl Default for OcrModel {
+    fn default() -> Self {
+        Self {
+            version: "5".to_string(),
+            loaded: false,
+        }
+    }
+}
+
+impl OcrModel {
+    /// Whether the model is loaded and can recognize text
+    pub fn is_ready(&self) -> bool {
+        self.loaded
+    }
+}
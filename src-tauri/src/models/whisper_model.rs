@@ -0,0 +1,38 @@
+// Whisper Large-v3 handle; transcription itself runs in whisper_transcribe_tauri.py
+
+#[derive(Debug, Clone)]
+pub struct WhisperModel {
+    pub version: String,
+    loaded: bool,
+}
+
+impl Default for WhisperModel {
+    fn default() -> Self {
+        Self {
+            version: "large-v3".to_string(),
+            loaded: false,
+        }
+    }
+}
+
+impl WhisperModel {
+    /// Whether the model is loaded and can transcribe
+    pub fn is_ready(&self) -> bool {
+        self.loaded
+    }
+
+    /// Mark the model as loaded; the caller has checked `available_memory` against its requirement
+    pub async fn load(&mut self, available_memory: u64) -> Result<(), String> {
+        if available_memory == 0 {
+            return Err("No memory available".to_string());
+        }
+        self.loaded = true;
+        Ok(())
+    }
+
+    /// Mark the model as unloaded
+    pub async fn unload(&mut self) -> Result<(), String> {
+        self.loaded = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+// Locates the bundled Python scripts: development override from the settings, then the app's
+// resource directory (where the installer puts them), then the source checkout for `tauri dev`
+
+use once_cell::sync::OnceCell;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::fs;
+
+/// Scripts must declare `SCRIPT_VERSION = <n>`; bump both sides when the JSON contract changes
+pub const EXPECTED_SCRIPT_VERSION: u32 = 1;
+
+pub const WHISPER_SCRIPT: &str = "whisper_transcribe_tauri.py";
+pub const QWEN_SCRIPT: &str = "qwen_structurer.py";
+pub const LLAMA_SCRIPT: &str = "llama_worker.py";
+pub const DOCX_FORMAT_SCRIPT: &str = "docx_format_tauri.py";
+pub const STYLE_PROFILE_SCRIPT: &str = "style_profile_analyzer.py";
+pub const DOCX_RENDERER_SCRIPT: &str = "docx_renderer.py";
+pub const TEMPLATE_EXTRACTOR_SCRIPT: &str = "template_extractor.py";
+
+pub const ALL_SCRIPTS: &[&str] = &[
+    WHISPER_SCRIPT,
+    QWEN_SCRIPT,
+    LLAMA_SCRIPT,
+    DOCX_FORMAT_SCRIPT,
+    STYLE_PROFILE_SCRIPT,
+    DOCX_RENDERER_SCRIPT,
+    TEMPLATE_EXTRACTOR_SCRIPT,
+];
+
+static RESOURCE_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+static SCRIPT_VERSION_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^SCRIPT_VERSION\s*=\s*(\d+)").unwrap());
+
+/// Stored in user-data/settings/scripts.json; lets developers run scripts from a checkout
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScriptSettings {
+    #[serde(default)]
+    pub scripts_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScriptStatus {
+    pub name: String,
+    pub path: Option<String>,
+    pub version: Option<u32>,
+    pub ok: bool,
+    pub message: Option<String>,
+}
+
+/// Called once at startup with `app_handle.path().resource_dir()`
+pub fn set_resource_dir(dir: PathBuf) {
+    let _ = RESOURCE_DIR.set(dir);
+}
+
+fn load_script_settings() -> ScriptSettings {
+    std::env::current_dir().ok()
+        .map(|dir| dir.join("user-data").join("settings").join("scripts.json"))
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Directories searched for scripts, in order
+fn script_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(dir) = load_script_settings().scripts_dir {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(dir) = RESOURCE_DIR.get() {
+        dirs.push(dir.join("scripts"));
+    }
+    if let Ok(dir) = std::env::current_dir() {
+        // `tauri dev` runs in src-tauri; the scripts live in the project root
+        if let Some(parent) = dir.parent() {
+            dirs.push(parent.to_path_buf());
+        }
+        dirs.push(dir);
+    }
+    dirs
+}
+
+fn find_script(name: &str, dirs: &[PathBuf]) -> Option<PathBuf> {
+    dirs.iter().map(|dir| dir.join(name)).find(|path| path.is_file())
+}
+
+/// Full path of a bundled script
+pub fn locate_script(name: &str) -> Result<PathBuf, String> {
+    let dirs = script_dirs();
+    find_script(name, &dirs).ok_or_else(|| format!(
+        "Skript {} nicht gefunden (gesucht in: {})",
+        name,
+        dirs.iter().map(|d| d.display().to_string()).collect::<Vec<_>>().join(", ")
+    ))
+}
+
+/// The `SCRIPT_VERSION` a script declares
+pub fn script_version(content: &str) -> Option<u32> {
+    SCRIPT_VERSION_REGEX.captures(content).and_then(|c| c[1].parse().ok())
+}
+
+fn check_script_at(name: &str, path: Option<&Path>) -> ScriptStatus {
+    let Some(path) = path else {
+        return ScriptStatus { name: name.to_string(), path: None, version: None, ok: false, message: Some("Skript fehlt".to_string()) };
+    };
+    let version = fs::read_to_string(path).ok().as_deref().and_then(script_version);
+    let message = match version {
+        Some(EXPECTED_SCRIPT_VERSION) => None,
+        Some(version) => Some(format!("Skriptversion {} passt nicht zur App (erwartet {})", version, EXPECTED_SCRIPT_VERSION)),
+        None => Some("Keine Versionsangabe (SCRIPT_VERSION) im Skript".to_string()),
+    };
+    ScriptStatus {
+        name: name.to_string(),
+        path: Some(path.to_string_lossy().to_string()),
+        version,
+        ok: message.is_none(),
+        message,
+    }
+}
+
+/// Presence and version of every script
+pub fn check_scripts() -> Vec<ScriptStatus> {
+    let dirs = script_dirs();
+    ALL_SCRIPTS.iter()
+        .map(|name| check_script_at(name, find_script(name, &dirs).as_deref()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts_are_found_in_order_and_version_checked() {
+        let root = std::env::temp_dir().join(format!("script_locator_{}", uuid::Uuid::new_v4()));
+        let (override_dir, resource_dir) = (root.join("dev"), root.join("resources"));
+        fs::create_dir_all(&override_dir).unwrap();
+        fs::create_dir_all(&resource_dir).unwrap();
+        fs::write(resource_dir.join(WHISPER_SCRIPT), "\"\"\"Whisper\"\"\"\nSCRIPT_VERSION = 1\nimport sys\n").unwrap();
+        fs::write(override_dir.join(WHISPER_SCRIPT), "SCRIPT_VERSION = 0\n").unwrap();
+        fs::write(resource_dir.join(QWEN_SCRIPT), "import sys\n").unwrap();
+
+        let dirs = vec![override_dir.clone(), resource_dir.clone()];
+        assert_eq!(find_script(WHISPER_SCRIPT, &dirs), Some(override_dir.join(WHISPER_SCRIPT)));
+        assert_eq!(find_script(QWEN_SCRIPT, &dirs[1..]), Some(resource_dir.join(QWEN_SCRIPT)));
+        assert_eq!(find_script(DOCX_RENDERER_SCRIPT, &dirs), None);
+
+        assert!(check_script_at(WHISPER_SCRIPT, Some(&resource_dir.join(WHISPER_SCRIPT))).ok);
+        let outdated = check_script_at(WHISPER_SCRIPT, Some(&override_dir.join(WHISPER_SCRIPT)));
+        assert_eq!((outdated.ok, outdated.version), (false, Some(0)));
+        assert!(!check_script_at(QWEN_SCRIPT, Some(&resource_dir.join(QWEN_SCRIPT))).ok);
+        assert!(!check_script_at(DOCX_RENDERER_SCRIPT, None).ok);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
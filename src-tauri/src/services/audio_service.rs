@@ -3,6 +3,7 @@
 use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use crate::models::whisper_model::WhisperModel;
+use crate::formats::{self, AUDIO_FORMATS};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AudioMetadata {
@@ -82,11 +83,10 @@ impl AudioService {
             .unwrap_or("")
             .to_lowercase();
         
-        let supported_formats = ["wav", "mp3", "m4a", "flac", "ogg", "webm"];
-        if !supported_formats.contains(&extension.as_str()) {
+        if !formats::is_audio_format(&extension) {
             return Err(format!(
                 "Unsupported format: {}. Supported: {:?}",
-                extension, supported_formats
+                extension, AUDIO_FORMATS
             ));
         }
         
@@ -151,14 +151,7 @@ impl AudioService {
     
     /// Get supported audio formats
     pub fn get_supported_formats() -> Vec<String> {
-        vec![
-            "wav".to_string(),
-            "mp3".to_string(),
-            "m4a".to_string(),
-            "flac".to_string(),
-            "ogg".to_string(),
-            "webm".to_string(),
-        ]
+        formats::to_strings(AUDIO_FORMATS)
     }
 }
 
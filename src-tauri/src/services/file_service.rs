@@ -3,6 +3,7 @@
 use std::path::{Path, PathBuf};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::formats::{self, AUDIO_FORMATS, DOCUMENT_FORMATS, IMAGE_FORMATS};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileInfo {
@@ -71,11 +72,7 @@ impl FileService {
         let file_info = self.get_file_info(file_path).await?;
         
         // Check file type
-        let medical_file_types = [
-            "pdf", "doc", "docx", "txt", "rtf",  // Documents
-            "wav", "mp3", "m4a", "flac", "ogg",  // Audio
-            "png", "jpg", "jpeg", "tiff", "bmp", // Images
-        ];
+        let medical_file_types: Vec<&str> = [DOCUMENT_FORMATS, AUDIO_FORMATS, IMAGE_FORMATS].concat();
         
         if !medical_file_types.contains(&file_info.file_type.as_str()) {
             return Err(format!(
@@ -90,8 +87,8 @@ impl FileService {
         const MAX_IMAGE_SIZE: u64 = 50 * 1024 * 1024;      // 50MB
         
         let max_size = match file_info.file_type.as_str() {
-            "wav" | "mp3" | "m4a" | "flac" | "ogg" => MAX_AUDIO_SIZE,
-            "png" | "jpg" | "jpeg" | "tiff" | "bmp" => MAX_IMAGE_SIZE,
+            t if formats::is_audio_format(t) => MAX_AUDIO_SIZE,
+            t if formats::is_image_format(t) => MAX_IMAGE_SIZE,
             _ => MAX_DOCUMENT_SIZE,
         };
         
@@ -207,6 +204,7 @@ impl FileService {
             "m4a" => "audio/mp4",
             "flac" => "audio/flac",
             "ogg" => "audio/ogg",
+            "webm" => "audio/webm",
             
             // Documents
             "pdf" => "application/pdf",
@@ -228,18 +226,9 @@ impl FileService {
     /// Get supported file types for different operations
     pub fn get_supported_file_types() -> Vec<(String, Vec<String>)> {
         vec![
-            ("Audio Processing".to_string(), vec![
-                "wav".to_string(), "mp3".to_string(), "m4a".to_string(), 
-                "flac".to_string(), "ogg".to_string()
-            ]),
-            ("Document Processing".to_string(), vec![
-                "pdf".to_string(), "doc".to_string(), "docx".to_string(), 
-                "txt".to_string(), "rtf".to_string()
-            ]),
-            ("Image OCR".to_string(), vec![
-                "png".to_string(), "jpg".to_string(), "jpeg".to_string(), 
-                "tiff".to_string(), "bmp".to_string()
-            ]),
+            ("Audio Processing".to_string(), formats::to_strings(AUDIO_FORMATS)),
+            ("Document Processing".to_string(), formats::to_strings(DOCUMENT_FORMATS)),
+            ("Image OCR".to_string(), formats::to_strings(IMAGE_FORMATS)),
         ]
     }
 }
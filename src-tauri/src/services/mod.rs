@@ -5,6 +5,6 @@ pub mod model_service;
 pub mod file_service;
 
 // Re-export services
-pub use audio_service::*;
-pub use model_service::*;
-pub use file_service::*;
\ No newline at end of file
+pub use audio_service::{AudioMetadata, AudioProcessingOptions, AudioService};
+pub use model_service::{ModelService, ModelServiceStats, ModelStatus};
+pub use file_service::{FileInfo, FileOperationResult, FileService};
\ No newline at end of file
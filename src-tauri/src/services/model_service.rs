@@ -247,6 +247,22 @@ impl ModelService {
         }
     }
     
+    /// Record that a model was used now (e.g. loaded for a transcription)
+    pub fn mark_model_used(&self, model_name: &str) {
+        let mut stats = self.model_stats.write();
+        if let Some(status) = stats.get_mut(model_name) {
+            status.last_used = Some(chrono::Utc::now().to_rfc3339());
+        }
+    }
+
+    /// Last-used timestamps of all models that were used since startup
+    pub fn last_used_timestamps(&self) -> HashMap<String, String> {
+        self.model_stats.read()
+            .iter()
+            .filter_map(|(name, status)| status.last_used.clone().map(|time| (name.clone(), time)))
+            .collect()
+    }
+
     /// Get memory usage recommendations
     pub async fn get_memory_recommendations(&self) -> Vec<String> {
         let available = self.memory_manager.get_available_memory().await.unwrap_or(0);
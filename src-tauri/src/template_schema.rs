@@ -0,0 +1,303 @@
+// Schema checks for hand-edited template JSON (DocumentStyleInfo, StyleProfile): all missing and
+// mistyped fields are reported at once with their JSON paths instead of serde's first error
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, Copy)]
+pub enum FieldType {
+    String,
+    Number,
+    Integer,
+    Bool,
+    Object(&'static [Field]),
+    Array(&'static FieldType),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Presence {
+    Required,
+    Optional,              // Option<T>: may be missing or null
+    Default(&'static str), // Added in a later version; filled with the documented default
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Field {
+    pub name: &'static str,
+    pub ty: FieldType,
+    pub presence: Presence,
+}
+
+const fn required(name: &'static str, ty: FieldType) -> Field {
+    Field { name, ty, presence: Presence::Required }
+}
+
+const fn optional(name: &'static str, ty: FieldType) -> Field {
+    Field { name, ty, presence: Presence::Optional }
+}
+
+const fn defaulted(name: &'static str, ty: FieldType, default: &'static str) -> Field {
+    Field { name, ty, presence: Presence::Default(default) }
+}
+
+const HEADING_STYLE: &[Field] = &[
+    required("level", FieldType::Integer),
+    required("font_family", FieldType::String),
+    required("font_size", FieldType::Number),
+    required("font_weight", FieldType::String),
+    required("color", FieldType::String),
+    required("spacing_before", FieldType::Number),
+    required("spacing_after", FieldType::Number),
+];
+
+const PAGE_MARGINS: &[Field] = &[
+    required("top", FieldType::Number),
+    required("bottom", FieldType::Number),
+    required("left", FieldType::Number),
+    required("right", FieldType::Number),
+];
+
+const HEADER_FOOTER_STYLE: &[Field] = &[
+    required("font_family", FieldType::String),
+    required("font_size", FieldType::Number),
+    required("font_weight", FieldType::String),
+    required("color", FieldType::String),
+    required("alignment", FieldType::String),
+];
+
+const HEADER_CANDIDATE: &[Field] = &[
+    required("part_name", FieldType::String),
+    required("content_preview", FieldType::String),
+    required("priority", FieldType::Integer),
+    required("chosen", FieldType::Bool),
+    defaulted("content", FieldType::String, "\"\""),
+    optional("style", FieldType::Object(HEADER_FOOTER_STYLE)),
+];
+
+const HEADER_FOOTER_INFO: &[Field] = &[
+    required("has_header", FieldType::Bool),
+    required("has_footer", FieldType::Bool),
+    required("header_content", FieldType::String),
+    required("footer_content", FieldType::String),
+    optional("header_style", FieldType::Object(HEADER_FOOTER_STYLE)),
+    optional("footer_style", FieldType::Object(HEADER_FOOTER_STYLE)),
+    defaulted("header_candidates", FieldType::Array(&FieldType::Object(HEADER_CANDIDATE)), "keine"),
+];
+
+const INDENTATION_INFO: &[Field] = &[
+    required("left_indent", FieldType::Number),
+    required("first_line_indent", FieldType::Number),
+    required("hanging_indent", FieldType::Number),
+    required("tab_stops", FieldType::Array(&FieldType::Number)),
+    required("indented_paragraphs", FieldType::Integer),
+    required("total_paragraphs", FieldType::Integer),
+];
+
+const ROLE_ALIGNMENTS: &[Field] = &[
+    required("body", FieldType::String),
+    required("heading", FieldType::String),
+    required("header", FieldType::String),
+    required("footer", FieldType::String),
+];
+
+/// `DocumentStyleInfo` as saved by `save_style_template`
+pub const DOCUMENT_STYLE_INFO: &[Field] = &[
+    required("document_id", FieldType::String),
+    required("filename", FieldType::String),
+    required("analysis_date", FieldType::String),
+    required("font_family", FieldType::String),
+    required("font_size", FieldType::Number),
+    required("line_spacing", FieldType::Number),
+    required("paragraph_spacing_before", FieldType::Number),
+    required("paragraph_spacing_after", FieldType::Number),
+    required("heading_styles", FieldType::Array(&FieldType::Object(HEADING_STYLE))),
+    required("text_alignment", FieldType::String),
+    required("page_margins", FieldType::Object(PAGE_MARGINS)),
+    required("header_footer_info", FieldType::Object(HEADER_FOOTER_INFO)),
+    required("style_summary", FieldType::String),
+    required("headers_found", FieldType::Array(&FieldType::String)),
+    defaulted("indentation", FieldType::Object(INDENTATION_INFO), "keine Einzüge"),
+    defaulted("alignments", FieldType::Object(ROLE_ALIGNMENTS), "alles linksbündig"),
+    defaulted("source", FieldType::String, "\"docx\""),
+    defaulted("confidence", FieldType::Number, "1.0"),
+    defaulted("page_break_count", FieldType::Integer, "0"),
+    defaulted("section_break_count", FieldType::Integer, "0"),
+    defaulted("used_heading_levels", FieldType::Array(&FieldType::Integer), "keine"),
+    defaulted("heading_level_gaps", FieldType::Array(&FieldType::String), "keine"),
+];
+
+const SECTION_INFO: &[Field] = &[
+    required("normalized_name", FieldType::String),
+    required("display_name", FieldType::String),
+    required("is_required", FieldType::Bool),
+    required("occurrence_count", FieldType::Integer),
+    required("occurrence_percentage", FieldType::Number),
+    required("order", FieldType::Integer),
+];
+
+const QUOTE_STYLE: &[Field] = &[
+    required("indent_cm", FieldType::Number),
+    required("italic", FieldType::Bool),
+    required("size_delta", FieldType::Number),
+];
+
+const FORMATTING_INFO: &[Field] = &[
+    required("font_family", FieldType::String),
+    required("font_size_pt", FieldType::Number),
+    required("line_spacing", FieldType::Number),
+    defaulted("quote", FieldType::Object(QUOTE_STYLE), "1 cm Einzug, kursiv, 1 pt kleiner"),
+];
+
+/// `StyleProfile` as written by the example analyzer
+pub const STYLE_PROFILE: &[Field] = &[
+    required("version", FieldType::String),
+    required("created_at", FieldType::String),
+    required("analyzed_documents", FieldType::Integer),
+    required("source_files", FieldType::Array(&FieldType::String)),
+    required("sections", FieldType::Array(&FieldType::Object(SECTION_INFO))),
+    required("formatting", FieldType::Object(FORMATTING_INFO)),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SchemaIssue {
+    pub path: String,      // JSON path, e.g. "$.page_margins.top" or "$.heading_styles[1].level"
+    pub expected: String,
+    pub found: String,     // "fehlt" for missing fields
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SchemaReport {
+    pub valid: bool,
+    pub issues: Vec<SchemaIssue>,
+    pub defaults_applied: Vec<String>,  // Migration report: "$.source = \"docx\""
+    pub unknown_fields: Vec<String>,    // Tolerated, ignored on load
+}
+
+fn type_name(ty: &FieldType) -> String {
+    match ty {
+        FieldType::String => "Text".to_string(),
+        FieldType::Number => "Zahl".to_string(),
+        FieldType::Integer => "ganze Zahl".to_string(),
+        FieldType::Bool => "true/false".to_string(),
+        FieldType::Object(_) => "Objekt".to_string(),
+        FieldType::Array(item) => format!("Liste ({})", type_name(item)),
+    }
+}
+
+fn value_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "true/false",
+        Value::Number(_) => "Zahl",
+        Value::String(_) => "Text",
+        Value::Array(_) => "Liste",
+        Value::Object(_) => "Objekt",
+    }
+}
+
+fn check_value(value: &Value, ty: &FieldType, path: &str, report: &mut SchemaReport) {
+    let matches = match ty {
+        FieldType::String => value.is_string(),
+        FieldType::Number => value.is_number(),
+        FieldType::Integer => value.is_i64() || value.is_u64(),
+        FieldType::Bool => value.is_boolean(),
+        FieldType::Object(fields) => match value.as_object() {
+            Some(_) => {
+                check_object(value, fields, path, report);
+                true
+            }
+            None => false,
+        },
+        FieldType::Array(item) => match value.as_array() {
+            Some(items) => {
+                for (i, item_value) in items.iter().enumerate() {
+                    check_value(item_value, item, &format!("{}[{}]", path, i), report);
+                }
+                true
+            }
+            None => false,
+        },
+    };
+    if !matches {
+        report.issues.push(SchemaIssue {
+            path: path.to_string(),
+            expected: type_name(ty),
+            found: value_kind(value).to_string(),
+        });
+    }
+}
+
+fn check_object(value: &Value, fields: &[Field], path: &str, report: &mut SchemaReport) {
+    let Some(object) = value.as_object() else { return };
+    for field in fields {
+        let field_path = format!("{}.{}", path, field.name);
+        match (object.get(field.name), field.presence) {
+            (Some(Value::Null), Presence::Optional) | (None, Presence::Optional) => {}
+            (None, Presence::Default(default)) => report.defaults_applied.push(format!("{} = {}", field_path, default)),
+            (None, Presence::Required) => report.issues.push(SchemaIssue {
+                path: field_path,
+                expected: type_name(&field.ty),
+                found: "fehlt".to_string(),
+            }),
+            (Some(field_value), _) => check_value(field_value, &field.ty, &field_path, report),
+        }
+    }
+    for key in object.keys().filter(|key| !fields.iter().any(|f| f.name == key.as_str())) {
+        report.unknown_fields.push(format!("{}.{}", path, key));
+    }
+}
+
+/// Check a JSON document against a schema
+pub fn validate_json(value: &Value, schema: &'static [Field]) -> SchemaReport {
+    let mut report = SchemaReport::default();
+    check_value(value, &FieldType::Object(schema), "$", &mut report);
+    report.valid = report.issues.is_empty();
+    report
+}
+
+/// All issues in one message, for load errors
+pub fn issues_message(issues: &[SchemaIssue]) -> String {
+    issues.iter()
+        .map(|issue| format!("{}: {} erwartet, {}", issue.path, issue.expected,
+            if issue.found == "fehlt" { "fehlt".to_string() } else { format!("gefunden {}", issue.found) }))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Parse after the schema check; the report lists the defaults filled in for older files
+pub fn parse_checked<T: DeserializeOwned>(content: &str, schema: &'static [Field]) -> Result<(T, SchemaReport), String> {
+    let value: Value = serde_json::from_str(content)
+        .map_err(|e| format!("Kein gültiges JSON: {}", e))?;
+    let report = validate_json(&value, schema);
+    if !report.valid {
+        return Err(format!("{} Fehler: {}", report.issues.len(), issues_message(&report.issues)));
+    }
+    let parsed = serde_json::from_value(value)
+        .map_err(|e| format!("Vorlage nicht lesbar: {}", e))?;
+    Ok((parsed, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_schema_issues_are_reported_with_paths() {
+        let profile = serde_json::json!({
+            "version": "1.0",
+            "created_at": "2025-01-01",
+            "analyzed_documents": "3",
+            "source_files": ["a.docx", 7],
+            "sections": [{"normalized_name": "BEFUND", "display_name": "Befund", "is_required": true,
+                          "occurrence_count": 3, "occurrence_percentage": 100.0, "order": 1.5}],
+            "bemerkung": "von Hand ergänzt",
+        });
+        let report = validate_json(&profile, STYLE_PROFILE);
+        let paths: Vec<&str> = report.issues.iter().map(|i| i.path.as_str()).collect();
+        assert_eq!(paths, vec!["$.analyzed_documents", "$.source_files[1]", "$.sections[0].order", "$.formatting"]);
+        assert_eq!(report.issues[3].found, "fehlt");
+        assert_eq!(report.unknown_fields, vec!["$.bemerkung".to_string()]);
+        assert!(issues_message(&report.issues).contains("$.analyzed_documents: ganze Zahl erwartet, gefunden Text"));
+    }
+}
@@ -0,0 +1,180 @@
+// Paragraph and sentence segmentation of dictated plain text, shared by the DOCX export
+// and the LLM chunking so both see the same paragraphs
+
+/// Abbreviations that end with a period but don't end a sentence
+pub const GERMAN_ABBREVIATIONS: &[&str] = &[
+    "z.b.", "dr.", "bzw.", "ca.", "ggf.", "u.a.", "vgl.", "nr.", "abs.", "s.", "etc.", "d.h.", "usw.", "prof.", "med.",
+];
+
+#[derive(Debug, Clone)]
+pub struct SegmentationOptions {
+    pub collapse_blank_lines: bool,  // Runs of blank lines become a single paragraph break
+    pub join_soft_wraps: bool,       // A line continuing mid-sentence is joined to the previous one
+}
+
+impl Default for SegmentationOptions {
+    fn default() -> Self {
+        SegmentationOptions {
+            collapse_blank_lines: true,
+            join_soft_wraps: true,
+        }
+    }
+}
+
+/// "\r\n" and lone "\r" become "\n"; trailing whitespace is removed from every line
+pub fn normalize_line_endings(text: &str) -> String {
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .split('\n')
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub fn is_abbreviation(word: &str) -> bool {
+    GERMAN_ABBREVIATIONS.contains(&word.to_lowercase().as_str())
+}
+
+pub fn is_list_item(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    ["- ", "• ", "* ", "– "].iter().any(|bullet| trimmed.starts_with(bullet))
+}
+
+/// A soft wrap continues a sentence: the previous line has no closing punctuation, is no
+/// heading (all caps or "Label:") or list item, and the next line starts in lower case
+fn is_soft_wrap(previous: &str, next: &str) -> bool {
+    let previous = previous.trim();
+    let next = next.trim_start();
+    let previous_is_heading = previous.ends_with(':')
+        || previous.chars().filter(|c| c.is_alphabetic()).all(|c| c.is_uppercase());
+
+    !previous.is_empty()
+        && !previous.ends_with(['.', '!', '?', ';'])
+        && !previous_is_heading
+        && !is_list_item(previous)
+        && !is_list_item(next)
+        && next.chars().next().is_some_and(|c| c.is_lowercase())
+}
+
+/// Lines as the DOCX export lays them out: one entry per paragraph, "" for an empty
+/// spacing paragraph. Leading and trailing blank lines are dropped.
+pub fn segment_lines(text: &str, options: &SegmentationOptions) -> Vec<String> {
+    let normalized = normalize_line_endings(text);
+    let mut lines: Vec<String> = Vec::new();
+
+    for line in normalized.split('\n') {
+        if line.trim().is_empty() {
+            let skip = match lines.last() {
+                None => true,  // Leading blank line
+                Some(previous) => options.collapse_blank_lines && previous.is_empty(),
+            };
+            if !skip {
+                lines.push(String::new());
+            }
+            continue;
+        }
+
+        match lines.last_mut() {
+            Some(previous) if options.join_soft_wraps && is_soft_wrap(previous, line) => {
+                previous.push(' ');
+                previous.push_str(line.trim());
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Paragraphs separated by hard breaks, without spacing entries
+pub fn split_paragraphs(text: &str, options: &SegmentationOptions) -> Vec<String> {
+    segment_lines(text, options).into_iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.trim().to_string())
+        .collect()
+}
+
+/// Split a paragraph into sentences at ".", "!" and "?" (not after known abbreviations)
+pub fn split_sentences(text: &str) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let mut sentences = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+
+    for (i, word) in words.iter().enumerate() {
+        current.push(word);
+        let is_last = i + 1 == words.len();
+        if is_last || (word.ends_with(['.', '!', '?']) && !is_abbreviation(word)) {
+            sentences.push(current.join(" "));
+            current.clear();
+        }
+    }
+    sentences
+}
+
+/// Pack paragraphs into chunks of at most `max_chars` (joined by blank lines). A longer
+/// paragraph is split at sentence ends; a single overlong sentence forms its own chunk.
+pub fn chunk_paragraphs(paragraphs: &[String], max_chars: usize) -> Vec<String> {
+    let mut pieces: Vec<(String, &str)> = Vec::new();  // (text, separator to the previous piece)
+    for paragraph in paragraphs {
+        if paragraph.chars().count() <= max_chars {
+            pieces.push((paragraph.clone(), "\n\n"));
+        } else {
+            for (i, sentence) in split_sentences(paragraph).into_iter().enumerate() {
+                pieces.push((sentence, if i == 0 { "\n\n" } else { " " }));
+            }
+        }
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for (piece, separator) in pieces {
+        if !current.is_empty() && current.chars().count() + separator.len() + piece.chars().count() > max_chars {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str(separator);
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segmentation_of_dictated_text() {
+        let text = "ANAMNESE\r\nDer Patient berichtet über Schmerzen im\r\nunteren Rücken seit 2019.   \r\n\r\n\r\n\r\n- Operation 2020\r\n- Physiotherapie\r\nBefund:\r\nunauffällig. \r\n\r\n";
+        let lines = segment_lines(text, &SegmentationOptions::default());
+        assert_eq!(lines, vec![
+            "ANAMNESE",
+            "Der Patient berichtet über Schmerzen im unteren Rücken seit 2019.",
+            "",
+            "- Operation 2020",
+            "- Physiotherapie",
+            "Befund:",
+            "unauffällig.",
+        ]);
+
+        let raw = segment_lines(text, &SegmentationOptions { collapse_blank_lines: false, join_soft_wraps: false });
+        assert_eq!(raw.len(), 10);
+        assert_eq!(raw[2], "unteren Rücken seit 2019.");
+
+        assert_eq!(
+            split_sentences("Vorstellung bei Dr. Meier, z.B. wegen Schmerzen. Keine OP! Weiter?"),
+            vec!["Vorstellung bei Dr. Meier, z.B. wegen Schmerzen.", "Keine OP!", "Weiter?"]
+        );
+
+        let paragraphs = split_paragraphs("Erster Absatz.\n\nZweiter Satz. Dritter Satz.\n\nKurz.", &SegmentationOptions::default());
+        assert_eq!(chunk_paragraphs(&paragraphs, 30), vec!["Erster Absatz.", "Zweiter Satz. Dritter Satz.", "Kurz."]);
+        assert_eq!(chunk_paragraphs(&paragraphs, 15), vec!["Erster Absatz.", "Zweiter Satz.", "Dritter Satz.", "Kurz."]);
+        assert_eq!(chunk_paragraphs(&paragraphs, 100), vec!["Erster Absatz.\n\nZweiter Satz. Dritter Satz.\n\nKurz."]);
+    }
+}
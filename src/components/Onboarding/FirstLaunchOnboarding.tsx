@@ -46,6 +46,12 @@ interface TemplateInfo {
   formatting: FormattingInfo | null;
 }
 
+interface TemplateValidation {
+  ok: boolean;
+  missing_sections: string[];
+  extra_sections: string[];
+}
+
 // New interface for template_spec.json data
 interface TemplateSpecAnchor {
   id: string;
@@ -444,7 +450,20 @@ const FirstLaunchOnboarding: React.FC<FirstLaunchOnboardingProps> = ({ onComplet
     setError(null);
 
     try {
-      await invoke('approve_template');
+      // Warn if the (corrected) template lost required sections of the profile
+      const validation = await invoke('validate_template_against_profile')
+        .catch(() => null) as TemplateValidation | null;
+      if (validation && !validation.ok) {
+        const proceed = window.confirm(
+          `Der Vorlage fehlen Pflichtabschnitte:\n\n${validation.missing_sections.join('\n')}\n\nTrotzdem genehmigen?`
+        );
+        if (!proceed) {
+          setIsApproving(false);
+          return;
+        }
+      }
+
+      await invoke('approve_template', { force: validation ? !validation.ok : false });
       onComplete();
     } catch (err) {
       console.error('Failed to approve template:', err);
@@ -106,10 +106,10 @@ interface FormatSpec {
     add_page_break_after?: boolean;
   };
   defaults?: {
-    font?: { name?: string; size_pt?: number; bold?: boolean };
+    font?: { name?: string; size_pt?: number; bold?: boolean; italic?: boolean; color?: string };
     paragraph?: { line_spacing?: number; space_after_pt?: number };
   };
-  styles?: Record<string, { font?: { name?: string; size_pt?: number; bold?: boolean } }>;
+  styles?: Record<string, { font?: { name?: string; size_pt?: number; bold?: boolean; italic?: boolean; color?: string } }>;
 }
 
 // Build prompt for Llama - SIMPLE, just the text
@@ -512,8 +512,18 @@ JSON:`;
       }) as { is_formatting_request: boolean };
 
       if (detection.is_formatting_request) {
-        // This is a formatting request - generate FormatSpec
-        const newSpec = await generateFormatSpec(userMessage);
+        // This is a formatting request - parse with rules, use the LLM only for what the rules missed
+        const parsed = await invoke('parse_formatting_request', {
+          request: userMessage
+        }) as { spec: FormatSpec; trace: string[]; unparsed_clauses: string[]; needs_llm: boolean };
+
+        let newSpec: FormatSpec | null = Object.keys(parsed.spec).length > 0 ? parsed.spec : null;
+        if (parsed.needs_llm) {
+          const llmSpec = await generateFormatSpec(parsed.unparsed_clauses.join('. '));
+          if (llmSpec) {
+            newSpec = { ...(newSpec || {}), ...llmSpec };
+          }
+        }
 
         if (newSpec) {
           // Merge with existing pending spec
@@ -529,11 +539,13 @@ JSON:`;
           if (newSpec.page?.margins) changes.push('Seitenränder');
           if (newSpec.title_page) changes.push('Titelseite');
           if (newSpec.defaults?.font) changes.push('Schriftart');
+          if (newSpec.defaults?.paragraph) changes.push('Zeilenabstand');
           if (newSpec.styles) changes.push('Überschriften-Stil');
 
           const assistantMessage: ChatMessage = {
             role: 'assistant',
-            content: `Formatierungsänderungen vorgemerkt: ${changes.join(', ')}. Diese werden beim Speichern als Word-Dokument angewendet. Klicken Sie auf "Als Word speichern" um das formatierte Dokument zu erstellen.`,
+            content: `Formatierungsänderungen vorgemerkt: ${changes.join(', ')}. Diese werden beim Speichern als Word-Dokument angewendet. Klicken Sie auf "Als Word speichern" um das formatierte Dokument zu erstellen.`
+              + (parsed.trace.length > 0 ? `\n\nSo wurde Ihre Anfrage verstanden:\n${parsed.trace.join('\n')}` : ''),
             timestamp: new Date()
           };
           setChatMessages(prev => [...prev, assistantMessage]);
@@ -17,6 +17,9 @@ export interface GrammarCorrectionResponse {
   attempts?: number;
   removed_tokens?: string[];
   tokens_per_sec?: number;
+  prompt_debug?: unknown;
+  generation?: Record<string, unknown>;
+  model_used?: string;
 }
 
 export interface LlamaModelInfo {
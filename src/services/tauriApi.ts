@@ -35,6 +35,9 @@ export interface TranscriptionResult {
   processing_time_ms: number;
   language: string;
   segments: TranscriptionSegment[];
+  detected_language?: string | null;
+  language_probabilities?: { language: string; probability: number }[];
+  warnings?: string[];
 }
 
 export interface TranscriptionSegment {
@@ -14,6 +14,9 @@ Usage:
     python style_profile_analyzer.py <documents_json> <output_path>
 """
 
+# Checked by the app (script_locator.rs); bump together with EXPECTED_SCRIPT_VERSION
+SCRIPT_VERSION = 1
+
 import sys
 import os
 import json
@@ -7,6 +7,9 @@ Usage:
     python template_extractor.py analyze <doc_profiles_folder>
 """
 
+# Checked by the app (script_locator.rs); bump together with EXPECTED_SCRIPT_VERSION
+SCRIPT_VERSION = 1
+
 import sys
 import os
 import json
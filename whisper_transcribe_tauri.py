@@ -4,8 +4,14 @@ Whisper Transcription Script for Tauri Backend
 Compatible with audio_commands.rs expectations
 """
 
+# Checked by the app (script_locator.rs); bump together with EXPECTED_SCRIPT_VERSION
+SCRIPT_VERSION = 1
+
 import sys
+import io
+import re
 import json
+import contextlib
 import whisper
 import whisper.audio
 import time
@@ -58,24 +64,77 @@ try:
 except ImportError:
     print("Warning: imageio-ffmpeg not available, ffmpeg must be in PATH", file=sys.stderr)
 
-def transcribe_audio(audio_path, output_format="json"):
+# Model sizes the app may request (third argument)
+MODEL_SIZES = ("tiny", "base", "small", "medium", "large")
+
+# A segment as Whisper prints it with verbose=True: "[00:01.000 --> 00:04.500]  Text"
+SEGMENT_LINE = re.compile(r"^\[((?:\d+:)?\d+:\d+\.\d+) --> ((?:\d+:)?\d+:\d+\.\d+)\]\s*(.*)$")
+
+def _timestamp_seconds(timestamp):
+    seconds = 0.0
+    for part in timestamp.split(":"):
+        seconds = seconds * 60 + float(part)
+    return seconds
+
+class SegmentStream(io.TextIOBase):
+    """
+    Receives Whisper's verbose output and writes one JSON line per decoded segment to the
+    real stdout, so the app keeps the segments decoded before a crash. The full segment
+    data follows in the final result line.
+    """
+
+    def __init__(self, out):
+        self.out = out
+        self.pending = ""
+
+    def write(self, text):
+        self.pending += text
+        while "\n" in self.pending:
+            line, self.pending = self.pending.split("\n", 1)
+            match = SEGMENT_LINE.match(line.strip())
+            if match:
+                segment = {
+                    "type": "segment",
+                    "start_time": _timestamp_seconds(match.group(1)),
+                    "end_time": _timestamp_seconds(match.group(2)),
+                    "text": match.group(3).strip(),
+                    "confidence": 0.0,  # Not known before the result
+                }
+                self.out.write(json.dumps(segment, ensure_ascii=False) + "\n")
+                self.out.flush()
+        return len(text)
+
+def detect_language_probabilities(model, audio_file, top=5):
+    """
+    Whisper's language probabilities for the first 30 seconds, most likely first
+    """
+    audio = whisper.pad_or_trim(whisper.load_audio(str(audio_file)))
+    mel = whisper.log_mel_spectrogram(audio, n_mels=model.dims.n_mels).to(model.device)
+    _, probs = model.detect_language(mel)
+    ranked = sorted(probs.items(), key=lambda item: item[1], reverse=True)[:top]
+    return [{"language": language, "probability": round(float(probability), 4)} for language, probability in ranked]
+
+def transcribe_audio(audio_path, output_format="json", model_size="base", language="de"):
     """
     Transcribe audio file using Whisper model
 
     Args:
         audio_path (str): Path to the audio file
         output_format (str): Output format - "json" or "text"
+        model_size (str): Whisper model size, one of MODEL_SIZES
+        language (str): Language to transcribe in (the expected one, or the detected one on a re-run)
 
     Returns:
         JSON string with transcription results or error
     """
     try:
-        print(f"Loading Whisper model...", file=sys.stderr)
+        if model_size not in MODEL_SIZES:
+            return json.dumps({"error": f"Unknown model size: {model_size}"})
+
+        print(f"Loading Whisper model {model_size}...", file=sys.stderr)
 
-        # Load Whisper model - using 'base' for fast transcription
-        # Options: tiny, base, small, medium, large
-        # Base model: Good accuracy, ~10x faster than large
-        model = whisper.load_model("base")
+        # Default 'base' for fast transcription: good accuracy, ~10x faster than large
+        model = whisper.load_model(model_size)
 
         print(f"Transcribing audio file: {audio_path}", file=sys.stderr)
 
@@ -90,11 +149,20 @@ def transcribe_audio(audio_path, output_format="json"):
         # Start transcription timer
         start_time = time.time()
 
-        # Transcribe audio with German language setting
+        # Transcribe in the requested language; the detected one is only reported so the app can warn
         # Fix dtype compatibility issue
         import torch
         with torch.no_grad():
-            result = model.transcribe(str(audio_file), language="de", fp16=False)
+            language_probabilities = []
+            try:
+                language_probabilities = detect_language_probabilities(model, audio_file)
+            except Exception as e:
+                print(f"Language detection failed: {str(e)}", file=sys.stderr)
+            if output_format.lower() == "json":
+                with contextlib.redirect_stdout(SegmentStream(sys.stdout)):
+                    result = model.transcribe(str(audio_file), language=language, fp16=False, verbose=True)
+            else:
+                result = model.transcribe(str(audio_file), language=language, fp16=False)
 
         # Calculate processing time
         processing_time_ms = int((time.time() - start_time) * 1000)
@@ -107,22 +175,29 @@ def transcribe_audio(audio_path, output_format="json"):
                     "start_time": float(segment.get("start", 0.0)),
                     "end_time": float(segment.get("end", 0.0)),
                     "text": str(segment.get("text", "")).strip(),
-                    "confidence": float(segment.get("prob", 0.0))  # Whisper uses 'prob' for confidence
+                    "confidence": float(segment.get("prob", 0.0)),  # Whisper uses 'prob' for confidence
+                    # Passed through for the Rust-side hallucination filter
+                    "no_speech_prob": float(segment.get("no_speech_prob", 0.0)),
+                    "compression_ratio": float(segment.get("compression_ratio", 0.0))
                 })
 
         # Prepare JSON response format expected by Tauri
         transcription_result = {
+            "type": "result",
             "text": str(result["text"]).strip(),
             "confidence": 0.95,  # Whisper doesn't provide overall confidence, use default
             "processing_time_ms": processing_time_ms,
-            "language": result.get("language", "de"),
+            "language": language,
+            "detected_language": language_probabilities[0]["language"] if language_probabilities else None,
+            "language_probabilities": language_probabilities,
             "segments": segments
         }
 
         print(f"Transcription completed successfully in {processing_time_ms}ms", file=sys.stderr)
 
         if output_format.lower() == "json":
-            return json.dumps(transcription_result, ensure_ascii=False, indent=2)
+            # One line, after the segment lines
+            return json.dumps(transcription_result, ensure_ascii=False)
         else:
             return result["text"]
 
@@ -136,23 +211,26 @@ def transcribe_audio(audio_path, output_format="json"):
 def main():
     """
     Main function for command line execution
-    Expected usage: python whisper_transcribe_tauri.py <audio_file_path> [output_format]
+    Expected usage: python whisper_transcribe_tauri.py <audio_file_path> [output_format] [model_size] [language]
     """
     if len(sys.argv) < 2:
         error_result = {
-            "error": "Usage: python whisper_transcribe_tauri.py <audio_file_path> [output_format]"
+            "error": "Usage: python whisper_transcribe_tauri.py <audio_file_path> [output_format] [model_size] [language]"
         }
         print(json.dumps(error_result))
         sys.exit(1)
 
     audio_path = sys.argv[1]
     output_format = sys.argv[2] if len(sys.argv) > 2 else "json"
+    model_size = sys.argv[3] if len(sys.argv) > 3 else "base"
+    language = sys.argv[4] if len(sys.argv) > 4 else "de"
+
+    # Output to stdout (Tauri reads this) with proper encoding; segment lines are written while transcribing
+    sys.stdout.reconfigure(encoding='utf-8')
 
     # Perform transcription
-    result = transcribe_audio(audio_path, output_format)
+    result = transcribe_audio(audio_path, output_format, model_size, language)
 
-    # Output result to stdout (Tauri reads this) with proper encoding
-    sys.stdout.reconfigure(encoding='utf-8')
     print(result)
 
 if __name__ == "__main__":